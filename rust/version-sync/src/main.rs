@@ -1,10 +1,25 @@
+mod semver;
+mod workspace;
+
 use clap::{Parser, Subcommand};
 use regex::Regex;
+use semver::{BumpKind, Version};
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::process;
 use tracing::{error, info};
-use vm_core::error::Result;
+use vm_core::error::{Result, VmError};
+use workspace::{VersionSite, Workspace};
+
+/// Optional project-root config listing extra files to keep in sync
+const CONFIG_FILE: &str = "version-sync.json";
+
+/// Extra (non-manifest) files synced when no config file is present
+const DEFAULT_EXTRA_FILES: &[&str] = &[
+    "configs/defaults.yaml",
+    "rust/version-sync/fixtures/config.yaml",
+    "rust/version-sync/fixtures/vm.yaml",
+];
 
 #[derive(Parser)]
 #[command(name = "version-sync")]
@@ -20,11 +35,28 @@ enum Command {
     Check,
     /// Update all version references to match package.json
     Sync,
+    /// Bump the version in package.json and all synced files
+    Bump {
+        /// Which part of the version to bump
+        #[arg(value_enum)]
+        kind: BumpKind,
+        /// Build metadata to append (e.g. a commit SHA)
+        #[arg(long)]
+        build: Option<String>,
+        /// Commit the updated files and create an annotated git tag `v<version>` on
+        /// that commit
+        #[arg(long)]
+        tag: bool,
+        /// Print the next version without changing any files
+        #[arg(long)]
+        dry_run: bool,
+    },
 }
 
 struct VersionSync {
     project_root: PathBuf,
     package_version: String,
+    workspace: Option<Workspace>,
     check_regexes: Vec<Regex>,
     update_regex_toml: Regex,
     update_regex_yaml: Regex,
@@ -32,8 +64,12 @@ struct VersionSync {
 
 impl VersionSync {
     fn new() -> Result<Self> {
-        let project_root = Self::find_project_root()?;
+        Self::at(Self::find_project_root()?)
+    }
+
+    fn at(project_root: PathBuf) -> Result<Self> {
         let package_version = Self::read_package_version(&project_root)?;
+        let workspace = Workspace::discover(&project_root)?;
 
         let check_regexes = vec![
             Regex::new(r#"version\s*[:=]\s*"?([^"\s]+)"?"#).expect("Invalid regex"),
@@ -47,6 +83,7 @@ impl VersionSync {
         Ok(Self {
             project_root,
             package_version,
+            workspace,
             check_regexes,
             update_regex_toml,
             update_regex_yaml,
@@ -87,14 +124,65 @@ impl VersionSync {
             })
     }
 
-    fn files_to_sync(&self) -> Vec<PathBuf> {
-        vec![
-            self.project_root.join("rust/Cargo.toml"),
-            self.project_root.join("configs/defaults.yaml"),
-            self.project_root
-                .join("rust/version-sync/fixtures/config.yaml"),
-            self.project_root.join("rust/version-sync/fixtures/vm.yaml"),
-        ]
+    /// Workspace manifests first, then extra files from `version-sync.json`
+    fn files_to_sync(&self) -> Result<Vec<PathBuf>> {
+        let mut files = Vec::new();
+        if let Some(workspace) = &self.workspace {
+            files.push(workspace.root_manifest.clone());
+            files.extend(workspace.members.iter().cloned());
+        }
+
+        for pattern in self.extra_file_patterns()? {
+            let full = self.project_root.join(&pattern);
+            if pattern.contains('*') {
+                let matches = glob::glob(&full.to_string_lossy()).map_err(|e| {
+                    vm_core::error::VmError::Internal(format!("Invalid glob {pattern}: {e}"))
+                })?;
+                files.extend(matches.filter_map(|entry| entry.ok()));
+            } else {
+                files.push(full);
+            }
+        }
+
+        files.dedup();
+        Ok(files)
+    }
+
+    fn extra_file_patterns(&self) -> Result<Vec<String>> {
+        let config_path = self.project_root.join(CONFIG_FILE);
+        if !config_path.exists() {
+            return Ok(DEFAULT_EXTRA_FILES.iter().map(|s| s.to_string()).collect());
+        }
+
+        let content = fs::read_to_string(&config_path).map_err(|e| {
+            vm_core::error::VmError::Internal(format!("Failed to read {CONFIG_FILE}: {e}"))
+        })?;
+        let json: serde_json::Value = serde_json::from_str(&content).map_err(|e| {
+            vm_core::error::VmError::Internal(format!("Failed to parse {CONFIG_FILE}: {e}"))
+        })?;
+
+        Ok(json
+            .get("files")
+            .and_then(|files| files.as_array())
+            .map(|files| {
+                files
+                    .iter()
+                    .filter_map(|f| f.as_str().map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+
+    /// Cargo manifests get section-aware handling; everything else uses the regex fallback
+    fn is_cargo_manifest(path: &Path) -> bool {
+        path.file_name().is_some_and(|name| name == "Cargo.toml")
+    }
+
+    fn internal_crates(&self) -> std::collections::HashSet<String> {
+        self.workspace
+            .as_ref()
+            .map(|w| w.internal_crates.clone())
+            .unwrap_or_default()
     }
 
     fn check_file_version(&self, path: &Path) -> Result<FileVersionStatus> {
@@ -106,6 +194,24 @@ impl VersionSync {
             vm_core::error::VmError::Internal(format!("Failed to read {}: {}", path.display(), e))
         })?;
 
+        if Self::is_cargo_manifest(path) {
+            let sites = workspace::version_sites(&content, &self.internal_crates());
+            if sites.is_empty() {
+                return Ok(FileVersionStatus::NoVersion);
+            }
+            let stale = sites.iter().find_map(|site| match site {
+                VersionSite::Explicit(v) if *v != self.package_version => Some(v.clone()),
+                _ => None,
+            });
+            return Ok(match stale {
+                Some(current) => FileVersionStatus::OutOfSync(current),
+                None if sites.iter().all(|s| *s == VersionSite::Inherited) => {
+                    FileVersionStatus::Inherited
+                }
+                None => FileVersionStatus::Synced,
+            });
+        }
+
         for regex in &self.check_regexes {
             if let Some(captures) = regex.captures(&content) {
                 let current_version = captures.get(1).map(|m| m.as_str()).unwrap_or("unknown");
@@ -121,32 +227,151 @@ impl VersionSync {
     }
 
     fn update_file_version(&self, path: &Path) -> Result<bool> {
-        let content = fs::read_to_string(path).map_err(|e| {
-            vm_core::error::VmError::Internal(format!("Failed to read {}: {}", path.display(), e))
-        })?;
+        match self.render_update(path, &self.package_version)? {
+            Some(updated) => {
+                fs::write(path, updated).map_err(|e| {
+                    VmError::Internal(format!("Failed to write {}: {}", path.display(), e))
+                })?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Compute the file content with all version references set to `version`,
+    /// or `None` if nothing would change
+    fn render_update(&self, path: &Path, version: &str) -> Result<Option<String>> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| VmError::Internal(format!("Failed to read {}: {}", path.display(), e)))?;
 
-        let updated = if self.update_regex_toml.is_match(&content) {
-            self.update_regex_toml.replace_all(
+        let updated = if Self::is_cargo_manifest(path) {
+            std::borrow::Cow::Owned(workspace::rewrite_versions(
                 &content,
-                &format!(r#"version = "{}""#, self.package_version),
-            )
+                &self.internal_crates(),
+                version,
+            ))
+        } else if self.update_regex_toml.is_match(&content) {
+            self.update_regex_toml
+                .replace_all(&content, &format!(r#"version = "{}""#, version))
         } else {
             self.update_regex_yaml
-                .replace_all(&content, &format!(r#"version: "{}""#, self.package_version))
+                .replace_all(&content, &format!(r#"version: "{}""#, version))
         };
 
-        if updated != content {
-            fs::write(path, updated.as_ref()).map_err(|e| {
-                vm_core::error::VmError::Internal(format!(
-                    "Failed to write {}: {}",
-                    path.display(),
-                    e
-                ))
-            })?;
-            Ok(true)
-        } else {
-            Ok(false)
+        Ok((updated != content).then(|| updated.into_owned()))
+    }
+
+    fn bump(&self, kind: BumpKind, build: Option<String>, tag: bool, dry_run: bool) -> Result<()> {
+        let current = Version::parse(&self.package_version)?;
+        // Re-parse so user-supplied build metadata is validated too
+        let next = Version::parse(&current.bump(kind).with_build(build).to_string())?;
+        if next.precedence(&current) != std::cmp::Ordering::Greater {
+            return Err(VmError::Internal(format!(
+                "Next version {next} does not sort after {current}"
+            )));
+        }
+        let next = next.to_string();
+
+        info!("📦 {} → {}", self.package_version, next);
+        if dry_run {
+            return Ok(());
+        }
+
+        let tag_name = format!("v{next}");
+        if tag && git_tag_exists(&self.project_root, &tag_name) {
+            return Err(VmError::Internal(format!(
+                "Git tag {tag_name} already exists"
+            )));
+        }
+
+        // Render every change up front so a bad file aborts before anything is written
+        let package_json = self.project_root.join("package.json");
+        let package_regex = Regex::new(r#"("version"\s*:\s*)"[^"]+""#).expect("Invalid regex");
+        let package_content = fs::read_to_string(&package_json)
+            .map_err(|e| VmError::Internal(format!("Failed to read package.json: {e}")))?;
+        let mut writes = vec![(
+            package_json,
+            package_regex
+                .replace(&package_content, format!(r#"${{1}}"{next}""#))
+                .into_owned(),
+        )];
+        for path in self.files_to_sync()? {
+            if path.exists() {
+                if let Some(updated) = self.render_update(&path, &next)? {
+                    writes.push((path, updated));
+                }
+            }
         }
+
+        write_all_atomically(&writes)?;
+        let mut updated: Vec<&Path> = writes.iter().map(|(path, _)| path.as_path()).collect();
+        let lockfile = self.refresh_lockfile()?;
+        updated.extend(lockfile.as_deref());
+        for path in &updated {
+            let relative_path = path.strip_prefix(&self.project_root).unwrap_or(path);
+            info!("✅ Updated {}", relative_path.display());
+        }
+
+        if tag {
+            // Tag a commit holding the bumped files, not the HEAD they were bumped from.
+            // An untracked (e.g. ignored) lockfile stays out of it.
+            let message = format!("Release {tag_name}");
+            let paths: Vec<&str> = updated
+                .iter()
+                .filter_map(|path| path.strip_prefix(&self.project_root).ok())
+                .filter_map(Path::to_str)
+                .filter(|path| {
+                    Some(self.project_root.join(path)) != lockfile
+                        || git_tracks(&self.project_root, path)
+                })
+                .collect();
+            git(
+                &self.project_root,
+                &[&["add", "--"], paths.as_slice()].concat(),
+            )?;
+            git(
+                &self.project_root,
+                &[&["commit", "-m", &message, "--"], paths.as_slice()].concat(),
+            )?;
+            info!("📝 Committed version {}", next);
+            git(
+                &self.project_root,
+                &["tag", "-a", &tag_name, "-m", &message],
+            )?;
+            info!("🏷️  Created tag {}", tag_name);
+        }
+
+        info!("");
+        info!("✅ Bumped {} files to version {}", updated.len(), next);
+        Ok(())
+    }
+
+    /// Record the bumped workspace versions in `Cargo.lock`, which lists every
+    /// member with its version. Returns the lockfile if there is one.
+    fn refresh_lockfile(&self) -> Result<Option<PathBuf>> {
+        let Some(dir) = self
+            .workspace
+            .as_ref()
+            .and_then(|workspace| workspace.root_manifest.parent())
+        else {
+            return Ok(None);
+        };
+        let lockfile = dir.join("Cargo.lock");
+        if !lockfile.exists() {
+            return Ok(None);
+        }
+        let output = process::Command::new("cargo")
+            .current_dir(dir)
+            .args(["update", "--workspace", "--offline"])
+            .output()
+            .map_err(|e| VmError::Internal(format!("Failed to run cargo: {e}")))?;
+        if !output.status.success() {
+            return Err(VmError::Internal(format!(
+                "Failed to update Cargo.lock: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        Ok(Some(lockfile))
     }
 
     fn check(&self) -> Result<bool> {
@@ -157,7 +382,7 @@ impl VersionSync {
 
         let mut all_synced = true;
 
-        for file_path in self.files_to_sync() {
+        for file_path in self.files_to_sync()? {
             let relative_path = file_path
                 .strip_prefix(&self.project_root)
                 .unwrap_or(&file_path);
@@ -166,6 +391,9 @@ impl VersionSync {
                 FileVersionStatus::Synced => {
                     info!("✅ {} ({})", relative_path.display(), self.package_version);
                 }
+                FileVersionStatus::Inherited => {
+                    info!("✅ {} (workspace)", relative_path.display());
+                }
                 FileVersionStatus::OutOfSync(current) => {
                     info!(
                         "❌ {} ({} → should be {})",
@@ -203,7 +431,7 @@ impl VersionSync {
 
         let mut updated_count = 0;
 
-        for file_path in self.files_to_sync() {
+        for file_path in self.files_to_sync()? {
             let relative_path = file_path
                 .strip_prefix(&self.project_root)
                 .unwrap_or(&file_path);
@@ -227,6 +455,12 @@ impl VersionSync {
                         self.package_version
                     );
                 }
+                FileVersionStatus::Inherited => {
+                    info!(
+                        "✅ {} inherits the workspace version",
+                        relative_path.display()
+                    );
+                }
                 FileVersionStatus::Missing => {
                     info!("⚠️  {} (missing)", relative_path.display());
                 }
@@ -251,9 +485,76 @@ impl VersionSync {
     }
 }
 
+/// Write each file to a sibling temp file, then rename them all into place.
+/// If any temp write fails, no target file is touched.
+fn write_all_atomically(writes: &[(PathBuf, String)]) -> Result<()> {
+    let temp_path = |path: &Path| {
+        let mut name = path.file_name().unwrap_or_default().to_os_string();
+        name.push(".version-sync.tmp");
+        path.with_file_name(name)
+    };
+
+    for (index, (path, content)) in writes.iter().enumerate() {
+        if let Err(e) = fs::write(temp_path(path), content) {
+            for (written, _) in &writes[..=index] {
+                let _ = fs::remove_file(temp_path(written));
+            }
+            return Err(VmError::Internal(format!(
+                "Failed to write {}: {}",
+                path.display(),
+                e
+            )));
+        }
+    }
+
+    for (path, _) in writes {
+        fs::rename(temp_path(path), path).map_err(|e| {
+            VmError::Internal(format!("Failed to replace {}: {}", path.display(), e))
+        })?;
+    }
+    Ok(())
+}
+
+/// Run git in `root`, failing unless it exits successfully
+fn git(root: &Path, args: &[&str]) -> Result<()> {
+    let output = process::Command::new("git")
+        .current_dir(root)
+        .args(args)
+        .output()
+        .map_err(|e| VmError::Internal(format!("Failed to run git: {e}")))?;
+    if !output.status.success() {
+        return Err(VmError::Internal(format!(
+            "git {} failed: {}",
+            args.first().copied().unwrap_or_default(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(())
+}
+
+/// Whether git tracks `path`, so an ignored lockfile stays out of release commits
+fn git_tracks(root: &Path, path: &str) -> bool {
+    git(root, &["ls-files", "--error-unmatch", "--", path]).is_ok()
+}
+
+fn git_tag_exists(root: &Path, tag: &str) -> bool {
+    process::Command::new("git")
+        .current_dir(root)
+        .args([
+            "rev-parse",
+            "--verify",
+            "--quiet",
+            &format!("refs/tags/{tag}"),
+        ])
+        .output()
+        .is_ok_and(|output| output.status.success())
+}
+
 #[derive(Debug)]
 enum FileVersionStatus {
     Synced,
+    /// All versions come from `workspace.package`
+    Inherited,
     OutOfSync(String),
     Missing,
     NoVersion,
@@ -290,5 +591,99 @@ fn main() {
                 process::exit(1);
             }
         }
+        Command::Bump {
+            kind,
+            build,
+            tag,
+            dry_run,
+        } => {
+            if let Err(e) = version_sync.bump(kind, build, tag, dry_run) {
+                error!("Version bump failed: {}", e);
+                process::exit(1);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn git_output(root: &Path, args: &[&str]) -> String {
+        let output = process::Command::new("git")
+            .current_dir(root)
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "git {args:?} failed");
+        String::from_utf8(output.stdout).unwrap()
+    }
+
+    #[test]
+    fn test_bump_tags_the_commit_with_the_new_version() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::write(root.join("package.json"), r#"{ "version": "1.2.3" }"#).unwrap();
+        fs::write(root.join(CONFIG_FILE), r#"{ "files": ["vm.yaml"] }"#).unwrap();
+        fs::write(root.join("vm.yaml"), "version: \"1.2.3\"\n").unwrap();
+        fs::write(root.join("notes.txt"), "draft").unwrap();
+        git_output(root, &["init", "-q"]);
+        git_output(root, &["config", "user.name", "Release Bot"]);
+        git_output(root, &["config", "user.email", "release@example.com"]);
+        git_output(root, &["add", "package.json", CONFIG_FILE, "vm.yaml"]);
+        git_output(root, &["commit", "-q", "-m", "Initial"]);
+
+        VersionSync::at(root.to_path_buf())
+            .unwrap()
+            .bump(BumpKind::Patch, None, true, false)
+            .unwrap();
+
+        assert!(git_output(root, &["show", "v1.2.4:package.json"]).contains("1.2.4"));
+        assert!(git_output(root, &["show", "v1.2.4:vm.yaml"]).contains("1.2.4"));
+        assert_eq!(
+            git_output(root, &["rev-parse", "v1.2.4^{commit}"]),
+            git_output(root, &["rev-parse", "HEAD"])
+        );
+        assert!(git_output(root, &["status", "--porcelain"]).contains("?? notes.txt"));
+    }
+
+    #[test]
+    fn test_bump_commits_the_refreshed_lockfile() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::write(root.join("package.json"), r#"{ "version": "1.2.3" }"#).unwrap();
+        fs::write(root.join(CONFIG_FILE), r#"{ "files": [] }"#).unwrap();
+        fs::write(
+            root.join("Cargo.toml"),
+            "[workspace]\nmembers = [\"app\"]\nresolver = \"2\"\n\n[workspace.package]\nversion = \"1.2.3\"\n",
+        )
+        .unwrap();
+        fs::create_dir_all(root.join("app/src")).unwrap();
+        fs::write(
+            root.join("app/Cargo.toml"),
+            "[package]\nname = \"app\"\nversion.workspace = true\nedition = \"2021\"\n",
+        )
+        .unwrap();
+        fs::write(root.join("app/src/lib.rs"), "").unwrap();
+        let status = process::Command::new("cargo")
+            .current_dir(root)
+            .args(["generate-lockfile", "--offline"])
+            .status()
+            .unwrap();
+        assert!(status.success());
+        git_output(root, &["init", "-q"]);
+        git_output(root, &["config", "user.name", "Release Bot"]);
+        git_output(root, &["config", "user.email", "release@example.com"]);
+        git_output(root, &["add", "."]);
+        git_output(root, &["commit", "-q", "-m", "Initial"]);
+
+        VersionSync::at(root.to_path_buf())
+            .unwrap()
+            .bump(BumpKind::Patch, None, true, false)
+            .unwrap();
+
+        let lockfile = git_output(root, &["show", "v1.2.4:Cargo.lock"]);
+        assert!(lockfile.contains("name = \"app\"\nversion = \"1.2.4\""));
+        assert!(git_output(root, &["status", "--porcelain"]).is_empty());
     }
 }
@@ -0,0 +1,264 @@
+//! Minimal semantic version parsing, precedence, and bumping
+//!
+//! Implements the parts of SemVer 2.0.0 needed for release bumps: pre-release
+//! identifiers take part in ordering, build metadata does not.
+
+use std::cmp::Ordering;
+use std::fmt;
+use vm_core::error::{Result, VmError};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Version {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+    pub pre: Vec<Identifier>,
+    pub build: Option<String>,
+}
+
+/// A dot-separated pre-release identifier
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Identifier {
+    Numeric(u64),
+    AlphaNumeric(String),
+}
+
+/// Which part of the version to bump
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum BumpKind {
+    Major,
+    Minor,
+    Patch,
+    /// Next release candidate (`x.y.z-rc.N`)
+    Rc,
+}
+
+impl Version {
+    pub fn parse(input: &str) -> Result<Self> {
+        let invalid = || VmError::Internal(format!("Invalid semantic version: {input}"));
+
+        let (rest, build) = match input.split_once('+') {
+            Some((rest, build)) if is_valid_dotted(build) => (rest, Some(build.to_string())),
+            Some(_) => return Err(invalid()),
+            None => (input, None),
+        };
+        let (core, pre) = match rest.split_once('-') {
+            Some((core, pre)) if is_valid_dotted(pre) => (core, Some(pre)),
+            Some(_) => return Err(invalid()),
+            None => (rest, None),
+        };
+
+        let parts: Vec<&str> = core.split('.').collect();
+        if parts.len() != 3 {
+            return Err(invalid());
+        }
+        let number = |s: &str| -> Result<u64> {
+            if s.is_empty() || (s.len() > 1 && s.starts_with('0')) {
+                return Err(invalid());
+            }
+            s.parse().map_err(|_| invalid())
+        };
+
+        let pre = match pre {
+            Some(pre) => pre
+                .split('.')
+                .map(|id| match id.parse::<u64>() {
+                    Ok(_) if id.len() > 1 && id.starts_with('0') => Err(invalid()),
+                    Ok(n) => Ok(Identifier::Numeric(n)),
+                    Err(_) => Ok(Identifier::AlphaNumeric(id.to_string())),
+                })
+                .collect::<Result<Vec<_>>>()?,
+            None => Vec::new(),
+        };
+
+        Ok(Self {
+            major: number(parts[0])?,
+            minor: number(parts[1])?,
+            patch: number(parts[2])?,
+            pre,
+            build,
+        })
+    }
+
+    /// Compute the next version. Build metadata is always dropped.
+    ///
+    /// Bumping a pre-release to the release it leads up to finalizes it
+    /// (`2.0.0-rc.2` + major = `2.0.0`), matching npm/cargo-release behavior.
+    pub fn bump(&self, kind: BumpKind) -> Self {
+        let is_pre = !self.pre.is_empty();
+        let (major, minor, patch, pre) = match kind {
+            BumpKind::Major if is_pre && self.minor == 0 && self.patch == 0 => {
+                (self.major, 0, 0, Vec::new())
+            }
+            BumpKind::Major => (self.major + 1, 0, 0, Vec::new()),
+            BumpKind::Minor if is_pre && self.patch == 0 => (self.major, self.minor, 0, Vec::new()),
+            BumpKind::Minor => (self.major, self.minor + 1, 0, Vec::new()),
+            BumpKind::Patch if is_pre => (self.major, self.minor, self.patch, Vec::new()),
+            BumpKind::Patch => (self.major, self.minor, self.patch + 1, Vec::new()),
+            BumpKind::Rc => match self.pre.as_slice() {
+                [Identifier::AlphaNumeric(tag), Identifier::Numeric(n)] if tag == "rc" => {
+                    (self.major, self.minor, self.patch, rc(n + 1))
+                }
+                // Other pre-releases (alpha, beta) move on to the first rc of the same release
+                [_, ..] => (self.major, self.minor, self.patch, rc(1)),
+                [] => (self.major, self.minor, self.patch + 1, rc(1)),
+            },
+        };
+
+        Self {
+            major,
+            minor,
+            patch,
+            pre,
+            build: None,
+        }
+    }
+
+    pub fn with_build(mut self, build: Option<String>) -> Self {
+        self.build = build;
+        self
+    }
+}
+
+fn rc(n: u64) -> Vec<Identifier> {
+    vec![
+        Identifier::AlphaNumeric("rc".to_string()),
+        Identifier::Numeric(n),
+    ]
+}
+
+fn is_valid_dotted(s: &str) -> bool {
+    !s.is_empty()
+        && s.split('.')
+            .all(|id| !id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-'))
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)?;
+        if !self.pre.is_empty() {
+            let pre: Vec<String> = self.pre.iter().map(ToString::to_string).collect();
+            write!(f, "-{}", pre.join("."))?;
+        }
+        if let Some(build) = &self.build {
+            write!(f, "+{build}")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Identifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Identifier::Numeric(n) => write!(f, "{n}"),
+            Identifier::AlphaNumeric(s) => write!(f, "{s}"),
+        }
+    }
+}
+
+impl Version {
+    /// SemVer precedence: build metadata is ignored, and a pre-release sorts
+    /// before the release it precedes
+    pub fn precedence(&self, other: &Self) -> Ordering {
+        (self.major, self.minor, self.patch)
+            .cmp(&(other.major, other.minor, other.patch))
+            .then_with(|| match (self.pre.is_empty(), other.pre.is_empty()) {
+                (true, true) => Ordering::Equal,
+                (true, false) => Ordering::Greater,
+                (false, true) => Ordering::Less,
+                (false, false) => compare_pre(&self.pre, &other.pre),
+            })
+    }
+}
+
+fn compare_pre(a: &[Identifier], b: &[Identifier]) -> Ordering {
+    for (x, y) in a.iter().zip(b) {
+        let ord = match (x, y) {
+            (Identifier::Numeric(x), Identifier::Numeric(y)) => x.cmp(y),
+            (Identifier::Numeric(_), Identifier::AlphaNumeric(_)) => Ordering::Less,
+            (Identifier::AlphaNumeric(_), Identifier::Numeric(_)) => Ordering::Greater,
+            (Identifier::AlphaNumeric(x), Identifier::AlphaNumeric(y)) => x.cmp(y),
+        };
+        if ord != Ordering::Equal {
+            return ord;
+        }
+    }
+    a.len().cmp(&b.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v(s: &str) -> Version {
+        Version::parse(s).unwrap()
+    }
+
+    #[test]
+    fn test_parse_roundtrip() {
+        for s in [
+            "4.8.3",
+            "1.0.0-rc.1",
+            "1.0.0-alpha.beta.1+build.5",
+            "2.0.0+sha.abc",
+        ] {
+            assert_eq!(v(s).to_string(), s);
+        }
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid() {
+        for s in [
+            "4.8",
+            "01.2.3",
+            "1.2.3-",
+            "1.2.3-rc..1",
+            "1.2.3+",
+            "1.2.3-01",
+        ] {
+            assert!(Version::parse(s).is_err(), "{s} should be invalid");
+        }
+    }
+
+    #[test]
+    fn test_bump_release() {
+        assert_eq!(v("4.8.3").bump(BumpKind::Patch).to_string(), "4.8.4");
+        assert_eq!(v("4.8.3").bump(BumpKind::Minor).to_string(), "4.9.0");
+        assert_eq!(
+            v("4.8.3+build.7").bump(BumpKind::Major).to_string(),
+            "5.0.0"
+        );
+        assert_eq!(v("4.8.3").bump(BumpKind::Rc).to_string(), "4.8.4-rc.1");
+    }
+
+    #[test]
+    fn test_bump_prerelease() {
+        assert_eq!(v("4.9.0-rc.1").bump(BumpKind::Rc).to_string(), "4.9.0-rc.2");
+        assert_eq!(
+            v("4.9.0-beta.3").bump(BumpKind::Rc).to_string(),
+            "4.9.0-rc.1"
+        );
+        assert_eq!(v("4.9.0-rc.2").bump(BumpKind::Minor).to_string(), "4.9.0");
+        assert_eq!(v("5.0.0-rc.2").bump(BumpKind::Major).to_string(), "5.0.0");
+        assert_eq!(v("4.9.1-rc.1").bump(BumpKind::Patch).to_string(), "4.9.1");
+        assert_eq!(v("4.9.1-rc.1").bump(BumpKind::Minor).to_string(), "4.10.0");
+    }
+
+    #[test]
+    fn test_precedence() {
+        let ordered = [
+            "1.0.0-alpha",
+            "1.0.0-alpha.1",
+            "1.0.0-alpha.beta",
+            "1.0.0-beta",
+            "1.0.0-beta.2",
+            "1.0.0-beta.11",
+            "1.0.0-rc.1",
+            "1.0.0",
+        ];
+        for pair in ordered.windows(2) {
+            assert_eq!(v(pair[0]).precedence(&v(pair[1])), Ordering::Less);
+        }
+        assert_eq!(v("1.0.0+a").precedence(&v("1.0.0+b")), Ordering::Equal);
+    }
+}
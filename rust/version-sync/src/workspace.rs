@@ -0,0 +1,357 @@
+//! Cargo workspace discovery and section-aware manifest version editing
+//!
+//! Manifests are edited line by line rather than re-serialized so comments and
+//! formatting survive a sync.
+
+use regex::Regex;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use vm_core::error::{Result, VmError};
+
+/// A Cargo workspace rooted at `root_manifest`
+pub struct Workspace {
+    pub root_manifest: PathBuf,
+    /// Member `Cargo.toml` paths, in declaration order
+    pub members: Vec<PathBuf>,
+    /// Package names of all members, used to recognize internal path dependencies
+    pub internal_crates: HashSet<String>,
+}
+
+/// A version declaration found in a manifest
+#[derive(Debug, Clone, PartialEq)]
+pub enum VersionSite {
+    /// An explicit `version = "x.y.z"` (package version or internal dependency requirement)
+    Explicit(String),
+    /// `version.workspace = true`
+    Inherited,
+}
+
+impl Workspace {
+    /// Find the workspace manifest at the project root or under `rust/`
+    pub fn discover(project_root: &Path) -> Result<Option<Self>> {
+        for candidate in ["Cargo.toml", "rust/Cargo.toml"] {
+            let manifest = project_root.join(candidate);
+            if !manifest.exists() {
+                continue;
+            }
+            let content = read(&manifest)?;
+            if section_body(&content, "workspace").is_some() {
+                return Self::load(manifest, &content).map(Some);
+            }
+        }
+        Ok(None)
+    }
+
+    fn load(root_manifest: PathBuf, content: &str) -> Result<Self> {
+        let workspace_dir = root_manifest
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_default();
+        let body = section_body(content, "workspace").unwrap_or_default();
+        let excluded: Vec<PathBuf> = string_array(&body, "exclude")
+            .iter()
+            .map(|e| workspace_dir.join(e))
+            .collect();
+
+        let mut members = Vec::new();
+        for pattern in string_array(&body, "members") {
+            let full = workspace_dir.join(&pattern);
+            let dirs: Vec<PathBuf> = if pattern.contains('*') {
+                glob::glob(&full.to_string_lossy())
+                    .map_err(|e| VmError::Internal(format!("Invalid member glob {pattern}: {e}")))?
+                    .filter_map(|entry| entry.ok())
+                    .collect()
+            } else {
+                vec![full]
+            };
+
+            for dir in dirs {
+                let manifest = dir.join("Cargo.toml");
+                if manifest.exists()
+                    && !excluded.iter().any(|e| dir.starts_with(e))
+                    && !members.contains(&manifest)
+                {
+                    members.push(manifest);
+                }
+            }
+        }
+
+        let mut internal_crates = HashSet::new();
+        for manifest in &members {
+            if let Some(name) = package_name(&read(manifest)?) {
+                internal_crates.insert(name);
+            }
+        }
+
+        Ok(Self {
+            root_manifest,
+            members,
+            internal_crates,
+        })
+    }
+}
+
+/// Collect every version declaration the sync is responsible for
+pub fn version_sites(content: &str, internal_crates: &HashSet<String>) -> Vec<VersionSite> {
+    let mut sites = Vec::new();
+    edit_versions(content, internal_crates, |site| {
+        sites.push(site.clone());
+        None
+    });
+    sites
+}
+
+/// Rewrite all explicit version declarations to `new_version`
+pub fn rewrite_versions(
+    content: &str,
+    internal_crates: &HashSet<String>,
+    new_version: &str,
+) -> String {
+    edit_versions(content, internal_crates, |_| Some(new_version.to_string()))
+}
+
+/// Internal view of a version site while scanning, carrying its byte range in the line
+struct SiteMatch {
+    site: VersionSite,
+    range: Option<std::ops::Range<usize>>,
+}
+
+/// Walk the manifest, calling `f` for each version site. When `f` returns a
+/// replacement, explicit versions are rewritten in place.
+fn edit_versions(
+    content: &str,
+    internal_crates: &HashSet<String>,
+    mut f: impl FnMut(&VersionSite) -> Option<String>,
+) -> String {
+    let re = regexes();
+    let mut section = String::new();
+    let mut output = String::with_capacity(content.len());
+
+    for line in content.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        if let Some(caps) = re.header.captures(trimmed) {
+            section = caps[1].trim().to_string();
+            output.push_str(line);
+            continue;
+        }
+
+        let site = if section == "package" || section == "workspace.package" {
+            if re.inherited.is_match(line) {
+                Some(SiteMatch {
+                    site: VersionSite::Inherited,
+                    range: None,
+                })
+            } else {
+                re.package_version.captures(line).and_then(|caps| {
+                    let m = caps.get(1)?;
+                    Some(SiteMatch {
+                        site: VersionSite::Explicit(m.as_str().to_string()),
+                        range: Some(m.range()),
+                    })
+                })
+            }
+        } else if is_dependency_section(&section) {
+            dependency_site(line, &section, internal_crates)
+        } else {
+            None
+        };
+
+        match site {
+            Some(site) => match (f(&site.site), site.range) {
+                (Some(new_version), Some(range)) => {
+                    output.push_str(&line[..range.start]);
+                    output.push_str(&new_version);
+                    output.push_str(&line[range.end..]);
+                }
+                _ => output.push_str(line),
+            },
+            None => output.push_str(line),
+        }
+    }
+
+    output
+}
+
+fn dependency_site(
+    line: &str,
+    section: &str,
+    internal_crates: &HashSet<String>,
+) -> Option<SiteMatch> {
+    let re = regexes();
+
+    // `[dependencies.vm-core]` table form
+    if let Some(name) = section.rsplit_once("dependencies.").map(|(_, name)| name) {
+        if !internal_crates.contains(name.trim_matches('"')) {
+            return None;
+        }
+        let caps = re.package_version.captures(line)?;
+        let m = caps.get(1)?;
+        return Some(SiteMatch {
+            site: VersionSite::Explicit(m.as_str().to_string()),
+            range: Some(m.range()),
+        });
+    }
+
+    // `vm-core = { path = "../vm-core", version = "1.2.3" }` inline form
+    let caps = re.inline_dep.captures(line)?;
+    let body = caps.get(2)?.as_str();
+    let name = re
+        .renamed_package
+        .captures(body)
+        .map(|c| c[1].to_string())
+        .unwrap_or_else(|| caps[1].to_string());
+    if !internal_crates.contains(&name) || !body.contains("path") {
+        return None;
+    }
+    let version = re.inline_version.captures(body)?.get(1)?;
+    let offset = caps.get(2)?.start();
+    Some(SiteMatch {
+        site: VersionSite::Explicit(version.as_str().to_string()),
+        range: Some(offset + version.start()..offset + version.end()),
+    })
+}
+
+fn is_dependency_section(section: &str) -> bool {
+    section.ends_with("dependencies") || section.contains("dependencies.")
+}
+
+struct ManifestRegexes {
+    header: Regex,
+    package_version: Regex,
+    inherited: Regex,
+    inline_dep: Regex,
+    inline_version: Regex,
+    renamed_package: Regex,
+}
+
+fn regexes() -> &'static ManifestRegexes {
+    static REGEXES: OnceLock<ManifestRegexes> = OnceLock::new();
+    REGEXES.get_or_init(|| ManifestRegexes {
+        header: Regex::new(r"^\[\[?([^\]]+)\]\]?\s*(#.*)?$").expect("Invalid regex"),
+        package_version: Regex::new(r#"^\s*version\s*=\s*"([^"]+)""#).expect("Invalid regex"),
+        inherited: Regex::new(
+            r"^\s*version\s*(\.\s*workspace\s*=\s*true|=\s*\{\s*workspace\s*=\s*true\s*\})",
+        )
+        .expect("Invalid regex"),
+        inline_dep: Regex::new(r#"^\s*"?([A-Za-z0-9_-]+)"?\s*=\s*\{(.*)\}"#)
+            .expect("Invalid regex"),
+        inline_version: Regex::new(r#"\bversion\s*=\s*"([^"]+)""#).expect("Invalid regex"),
+        renamed_package: Regex::new(r#"\bpackage\s*=\s*"([^"]+)""#).expect("Invalid regex"),
+    })
+}
+
+/// Body of a `[name]` section, up to the next header
+fn section_body(content: &str, name: &str) -> Option<String> {
+    let re = regexes();
+    let mut body = None::<String>;
+    for line in content.lines() {
+        if let Some(caps) = re.header.captures(line.trim_start()) {
+            if body.is_some() {
+                break;
+            }
+            if caps[1].trim() == name {
+                body = Some(String::new());
+            }
+            continue;
+        }
+        if let Some(body) = body.as_mut() {
+            body.push_str(line);
+            body.push('\n');
+        }
+    }
+    body
+}
+
+/// Parse a (possibly multi-line) array of strings such as `members = [...]`
+fn string_array(body: &str, key: &str) -> Vec<String> {
+    let without_comments: String = body
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or_default())
+        .collect::<Vec<_>>()
+        .join("\n");
+    let pattern = format!(r#"(?s)\b{key}\s*=\s*\[(.*?)\]"#);
+    let Some(caps) = Regex::new(&pattern)
+        .expect("Invalid regex")
+        .captures(&without_comments)
+    else {
+        return Vec::new();
+    };
+    let item = Regex::new(r#""([^"]+)""#).expect("Invalid regex");
+    item.captures_iter(&caps[1])
+        .map(|c| c[1].to_string())
+        .collect()
+}
+
+fn package_name(content: &str) -> Option<String> {
+    let body = section_body(content, "package")?;
+    let re = Regex::new(r#"(?m)^\s*name\s*=\s*"([^"]+)""#).expect("Invalid regex");
+    re.captures(&body).map(|c| c[1].to_string())
+}
+
+fn read(path: &Path) -> Result<String> {
+    fs::read_to_string(path)
+        .map_err(|e| VmError::Internal(format!("Failed to read {}: {}", path.display(), e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn internal() -> HashSet<String> {
+        ["vm-core".to_string(), "vm-config".to_string()].into()
+    }
+
+    const MEMBER: &str = r#"[package]
+name = "vm"
+version.workspace = true
+
+[dependencies]
+serde = { version = "1.0.228", features = ["derive"] }
+vm-core = { path = "../vm-core", version = "4.8.2" }
+config = { package = "vm-config", path = "../vm-config", version = "4.8.2" }
+
+[dependencies.vm-config]
+path = "../vm-config"
+version = "4.8.2"
+"#;
+
+    #[test]
+    fn test_version_sites_skip_external_dependencies() {
+        assert_eq!(
+            version_sites(MEMBER, &internal()),
+            vec![
+                VersionSite::Inherited,
+                VersionSite::Explicit("4.8.2".to_string()),
+                VersionSite::Explicit("4.8.2".to_string()),
+                VersionSite::Explicit("4.8.2".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rewrite_versions_preserves_external_dependencies() {
+        let updated = rewrite_versions(MEMBER, &internal(), "4.9.0");
+        assert!(updated.contains(r#"serde = { version = "1.0.228", features = ["derive"] }"#));
+        assert!(updated.contains(r#"vm-core = { path = "../vm-core", version = "4.9.0" }"#));
+        assert!(
+            updated.contains(r#"package = "vm-config", path = "../vm-config", version = "4.9.0""#)
+        );
+        assert!(updated.contains("path = \"../vm-config\"\nversion = \"4.9.0\"\n"));
+        assert!(updated.contains("version.workspace = true"));
+    }
+
+    #[test]
+    fn test_workspace_package_version() {
+        let root = "[workspace]\nmembers = [\n    # core\n    \"vm-core\",\n]\n\n[workspace.package]\nversion = \"4.8.3\"\n\n[workspace.dependencies]\nanyhow = \"1.0\"\n";
+        assert_eq!(
+            version_sites(root, &internal()),
+            vec![VersionSite::Explicit("4.8.3".to_string())]
+        );
+        assert_eq!(
+            string_array(&section_body(root, "workspace").unwrap(), "members"),
+            vec!["vm-core".to_string()]
+        );
+    }
+}
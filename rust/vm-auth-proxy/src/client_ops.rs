@@ -5,7 +5,6 @@ use crate::types::{SecretListResponse, SecretRequest, SecretScope, SecretSummary
 use anyhow::{anyhow, Context, Result};
 use colored::Colorize;
 use dialoguer::{theme::ColorfulTheme, Confirm, Input, Select};
-use reqwest::Client;
 use std::collections::HashMap;
 use tracing::{debug, info, warn};
 use vm_core::msg;
@@ -51,7 +50,7 @@ pub async fn add_secret(
     };
 
     let auth_token = get_auth_token().await?;
-    let client = Client::new();
+    let client = vm_core::network::client();
     let url = format!("{server_url}/secrets/{name}");
 
     let response = client
@@ -76,7 +75,7 @@ pub async fn add_secret(
 /// List all secrets
 pub async fn list_secrets(server_url: &str, show_values: bool) -> Result<()> {
     let auth_token = get_auth_token().await?;
-    let client = Client::new();
+    let client = vm_core::network::client();
     let url = format!("{server_url}/secrets");
 
     let response = client
@@ -134,7 +133,7 @@ pub async fn remove_secret(server_url: &str, name: &str, force: bool) -> Result<
     }
 
     let auth_token = get_auth_token().await?;
-    let client = Client::new();
+    let client = vm_core::network::client();
     let url = format!("{server_url}/secrets/{name}");
 
     let response = client
@@ -165,7 +164,7 @@ pub async fn get_secret_for_vm(
     project_name: Option<&str>,
 ) -> Result<HashMap<String, String>> {
     let auth_token = get_auth_token().await?;
-    let client = Client::new();
+    let client = vm_core::network::client();
     let mut url = format!("{server_url}/env/{vm_name}");
 
     if let Some(project) = project_name {
@@ -196,7 +195,7 @@ pub async fn get_secret_for_vm(
 /// Check if the auth proxy server is running
 pub async fn check_server_running(port: u16) -> bool {
     let url = format!("http://127.0.0.1:{port}/health");
-    match reqwest::get(&url).await {
+    match vm_core::network::client().get(&url).send().await {
         Ok(response) => response.status().is_success(),
         Err(_) => false,
     }
@@ -311,7 +310,7 @@ async fn print_secret_summary(
 
 /// Get a specific secret value
 async fn get_secret_value(server_url: &str, name: &str, auth_token: &str) -> Result<String> {
-    let client = Client::new();
+    let client = vm_core::network::client();
     let url = format!("{server_url}/secrets/{name}");
 
     let response = client
@@ -465,7 +464,7 @@ mod tests {
             description: description.map(|s| s.to_string()),
         };
 
-        let client = Client::new();
+        let client = vm_core::network::client();
         let url = format!("{}/secrets/{}", server_url, name);
 
         let response = client
@@ -487,7 +486,7 @@ mod tests {
 
     /// Helper function for tests that lists secrets with provided auth token
     async fn list_secrets_with_token(server_url: &str, auth_token: &str) -> Result<()> {
-        let client = Client::new();
+        let client = vm_core::network::client();
         let url = format!("{}/secrets", server_url);
 
         let response = client
@@ -106,7 +106,7 @@ pub async fn run_server_background(host: String, port: u16, data_dir: PathBuf) -
 /// Check if the auth proxy server is running
 pub async fn check_server_running(port: u16) -> bool {
     let url = format!("http://127.0.0.1:{port}/health");
-    match reqwest::get(&url).await {
+    match vm_core::network::client().get(&url).send().await {
         Ok(response) => response.status().is_success(),
         Err(_) => false,
     }
@@ -563,10 +563,10 @@ fn get_recommended_services(detected_types: &std::collections::HashSet<String>)
 
     for tech in detected_types {
         match tech.as_str() {
-            "nodejs" | "react" | "vue" | "next" | "angular" => {
-                if !services.contains(&"postgresql".to_string()) {
-                    services.push("postgresql".to_string());
-                }
+            "nodejs" | "react" | "vue" | "next" | "angular"
+                if !services.contains(&"postgresql".to_string()) =>
+            {
+                services.push("postgresql".to_string());
             }
             "python" | "django" | "flask" => {
                 if !services.contains(&"postgresql".to_string()) {
@@ -584,10 +584,8 @@ fn get_recommended_services(detected_types: &std::collections::HashSet<String>)
                     services.push("redis".to_string());
                 }
             }
-            "docker" => {
-                if !services.contains(&"docker".to_string()) {
-                    services.push("docker".to_string());
-                }
+            "docker" if !services.contains(&"docker".to_string()) => {
+                services.push("docker".to_string());
             }
             _ => {}
         }
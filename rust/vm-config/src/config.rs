@@ -120,6 +120,9 @@ pub struct VmConfig {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tart: Option<TartConfig>,
 
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub libvirt: Option<LibvirtConfig>,
+
     // 3. Project Identity
     #[serde(skip_serializing_if = "Option::is_none")]
     pub project: Option<ProjectConfig>,
@@ -143,6 +146,12 @@ pub struct VmConfig {
     #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
     pub services: IndexMap<String, ServiceConfig>,
 
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub db: Option<DbConfig>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub devices: Option<DevicesConfig>,
+
     // 8. Package Management
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub apt_packages: Vec<String>,
@@ -156,6 +165,10 @@ pub struct VmConfig {
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub cargo_packages: Vec<String>,
 
+    /// Devcontainer features installed after provisioning
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub features: Vec<FeatureSpec>,
+
     // 9. Development Environment
     #[serde(skip_serializing_if = "Option::is_none")]
     pub terminal: Option<TerminalConfig>,
@@ -287,6 +300,47 @@ pub struct ProjectConfig {
     pub env_template_path: Option<String>,
 }
 
+/// A devcontainer feature reference, optionally with options
+///
+/// Examples: `ghcr.io/devcontainers/features/node:1`, or
+/// `{ id: ghcr.io/devcontainers/features/python:1, options: { version: "3.12" } }`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(untagged)]
+pub enum FeatureSpec {
+    Id(String),
+    WithOptions {
+        id: String,
+        #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+        options: IndexMap<String, serde_json::Value>,
+    },
+}
+
+impl FeatureSpec {
+    /// OCI reference of the feature
+    pub fn id(&self) -> &str {
+        match self {
+            Self::Id(id) | Self::WithOptions { id, .. } => id,
+        }
+    }
+
+    /// Feature options as the strings passed to its install script
+    pub fn options(&self) -> IndexMap<String, String> {
+        match self {
+            Self::Id(_) => IndexMap::new(),
+            Self::WithOptions { options, .. } => options
+                .iter()
+                .map(|(key, value)| {
+                    let value = match value {
+                        serde_json::Value::String(s) => s.clone(),
+                        other => other.to_string(),
+                    };
+                    (key.clone(), value)
+                })
+                .collect(),
+        }
+    }
+}
+
 /// Box specification - unified way to specify VM base images, Dockerfiles, or snapshots
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(untagged)]
@@ -331,6 +385,144 @@ pub struct VmSettings {
     pub port_binding: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub gui: Option<bool>,
+    /// Provisioning engine: `ansible` (default) or `native`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub provisioner: Option<String>,
+    /// Resource profiles for `vm mode` (`focus`, `background`)
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub modes: IndexMap<String, ResourceMode>,
+    /// What happens when the VM's container exits on its own
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub restart_policy: Option<RestartPolicy>,
+    /// Kernel parameters for the container (`net.core.somaxconn: 1024`).
+    /// Host-wide keys like `vm.max_map_count` are checked on the host instead.
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub sysctls: IndexMap<String, SysctlValue>,
+    /// Process resource limits (`nofile: 65536` or `nofile: { soft: 1024, hard: 65536 }`)
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub ulimits: IndexMap<String, Ulimit>,
+}
+
+/// Container restart policy, passed through to the container engine and
+/// enforced between commands by the crash watchdog.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RestartPolicy {
+    /// Never restart (default)
+    No,
+    /// Restart only after a non-zero exit
+    OnFailure,
+    /// Restart whenever the container exits, unless stopped with `vm stop`
+    Always,
+}
+
+impl RestartPolicy {
+    /// Name used by Docker, Podman and Compose
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RestartPolicy::No => "no",
+            RestartPolicy::OnFailure => "on-failure",
+            RestartPolicy::Always => "always",
+        }
+    }
+}
+
+/// Prefixes of sysctls that are namespaced per container and can be set with
+/// `--sysctl`. Everything else would change the whole host and is refused by Docker.
+pub const CONTAINER_SYSCTL_PREFIXES: &[&str] = &[
+    "net.",
+    "kernel.shm",
+    "kernel.msg",
+    "kernel.sem",
+    "fs.mqueue.",
+    "fs.inotify.",
+];
+
+/// Host-wide sysctls `vm.sysctls` accepts as a minimum the host must provide
+pub const HOST_SYSCTLS: &[&str] = &["vm.max_map_count"];
+
+/// Engines accepted by `vm.provisioner`
+pub const PROVISIONERS: &[&str] = &["ansible", "native"];
+
+/// Resource names accepted by `vm.ulimits` (see `setrlimit(2)`)
+pub const ULIMIT_NAMES: &[&str] = &[
+    "core",
+    "cpu",
+    "data",
+    "fsize",
+    "locks",
+    "memlock",
+    "msgqueue",
+    "nice",
+    "nofile",
+    "nproc",
+    "rss",
+    "rtprio",
+    "rttime",
+    "sigpending",
+    "stack",
+];
+
+/// Whether `key` can be applied inside the container
+pub fn is_container_sysctl(key: &str) -> bool {
+    CONTAINER_SYSCTL_PREFIXES
+        .iter()
+        .any(|prefix| key.starts_with(prefix))
+}
+
+/// Whether `key` has to be provided by the host kernel
+pub fn is_host_sysctl(key: &str) -> bool {
+    HOST_SYSCTLS.contains(&key)
+}
+
+/// Sysctl value; YAML numbers and strings are both accepted
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum SysctlValue {
+    Number(i64),
+    Text(String),
+}
+
+impl std::fmt::Display for SysctlValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SysctlValue::Number(n) => write!(f, "{n}"),
+            SysctlValue::Text(s) => f.write_str(s),
+        }
+    }
+}
+
+/// A ulimit, either one value for both limits or separate soft and hard limits.
+/// `-1` means unlimited.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Ulimit {
+    Single(i64),
+    Range { soft: i64, hard: i64 },
+}
+
+impl Ulimit {
+    /// `(soft, hard)` limits
+    pub fn limits(&self) -> (i64, i64) {
+        match *self {
+            Ulimit::Single(value) => (value, value),
+            Ulimit::Range { soft, hard } => (soft, hard),
+        }
+    }
+}
+
+/// Resource profile applied live by `vm mode`.
+///
+/// Unset fields fall back to the mode's defaults: `focus` uses the top-level
+/// `vm` limits, `background` parks the VM on 1 CPU and 1GB of memory.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ResourceMode {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memory: Option<MemoryLimit>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cpus: Option<CpuLimit>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub swap: Option<SwapLimit>,
 }
 
 impl VmSettings {
@@ -338,6 +530,33 @@ impl VmSettings {
     pub fn get_box_spec(&self) -> Option<BoxSpec> {
         self.r#box.clone()
     }
+
+    /// Whether boxes are provisioned by the built-in Rust engine instead of Ansible.
+    pub fn uses_native_provisioner(&self) -> bool {
+        self.provisioner.as_deref() == Some("native")
+    }
+
+    /// Resolve a resource profile by name, filling unset fields with its defaults.
+    pub fn resource_mode(&self, name: &str) -> ResourceMode {
+        let configured = self.modes.get(name).cloned().unwrap_or_default();
+        let defaults = match name {
+            "background" => ResourceMode {
+                memory: Some(MemoryLimit::Limited(1024)),
+                cpus: Some(CpuLimit::Limited(1)),
+                swap: None,
+            },
+            _ => ResourceMode {
+                memory: self.memory.clone(),
+                cpus: self.cpus.clone(),
+                swap: self.swap.clone(),
+            },
+        };
+        ResourceMode {
+            memory: configured.memory.or(defaults.memory),
+            cpus: configured.cpus.or(defaults.cpus),
+            swap: configured.swap.or(defaults.swap),
+        }
+    }
 }
 
 /// Memory limit configuration supporting both specific limits and unlimited access.
@@ -743,6 +962,116 @@ pub struct ServiceConfig {
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub seed_file: Option<PathBuf>,
+
+    /// OpenAPI document the `mock` service generates responses from
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub spec: Option<PathBuf>,
+    /// WireMock fixtures directory (`mappings/`, `__files/`) the `mock` service serves
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fixtures: Option<PathBuf>,
+
+    /// Services that must be ready before this one starts, keyed by service name
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub depends_on: IndexMap<String, ServiceDependency>,
+}
+
+/// How to decide that a dependency is ready.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ReadinessCondition {
+    /// The service's built-in health check passes
+    #[default]
+    Healthcheck,
+    /// The service port accepts TCP connections
+    Tcp,
+    /// A SQL query succeeds (database services only)
+    Sql,
+}
+
+/// A readiness gate on another service, declared under `services.<name>.depends_on`.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ServiceDependency {
+    #[serde(default)]
+    pub condition: ReadinessCondition,
+    /// Query for the `sql` condition (defaults to `SELECT 1`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub query: Option<String>,
+    /// Port for the `tcp` condition (defaults to the dependency's port)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub port: Option<u16>,
+    /// How long to wait for the dependency, in seconds (defaults to 60)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout_secs: Option<u64>,
+}
+
+impl ServiceDependency {
+    /// Query run by the `sql` condition
+    pub fn sql_query(&self) -> &str {
+        self.query.as_deref().unwrap_or("SELECT 1")
+    }
+
+    /// Maximum time to wait for the dependency
+    pub fn timeout(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.timeout_secs.unwrap_or(60))
+    }
+}
+
+/// Project database settings used by `vm db`.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct DbConfig {
+    /// Scheduled backups run by the service manager
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub backup: Option<DbBackupConfig>,
+
+    /// Named fixture sets: each is an ordered list of SQL files, dumps, or URLs
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub fixtures: IndexMap<String, Vec<String>>,
+
+    /// Fixture set loaded into a fresh database after `vm create` and `vm db reset`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_fixture: Option<String>,
+}
+
+/// Scheduled database backup settings.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct DbBackupConfig {
+    /// How often to back up: `hourly`, `daily`, `weekly`, or an interval like `6h` / `2d`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub schedule: Option<String>,
+
+    /// Number of scheduled backups to keep per database (defaults to global `backups.keep_count`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub keep: Option<u32>,
+
+    /// Databases to back up as `<service>:<database>` (defaults to each database service's configured database)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub databases: Vec<String>,
+}
+
+impl DbBackupConfig {
+    /// Parse `schedule` into an interval. Returns `None` when unset or invalid.
+    pub fn interval(&self) -> Option<std::time::Duration> {
+        let schedule = self.schedule.as_deref()?.trim().to_lowercase();
+        let hours = match schedule.as_str() {
+            "hourly" => 1,
+            "daily" => 24,
+            "weekly" => 24 * 7,
+            other => {
+                let unit = other.chars().last()?;
+                let value: u64 = other[..other.len() - unit.len_utf8()]
+                    .parse()
+                    .ok()
+                    .filter(|v| *v > 0)?;
+                match unit {
+                    'h' => value,
+                    'd' => value * 24,
+                    'w' => value * 24 * 7,
+                    _ => return None,
+                }
+            }
+        };
+        Some(std::time::Duration::from_secs(hours * 3600))
+    }
 }
 
 /// Terminal and shell customization settings.
@@ -793,6 +1122,94 @@ pub struct HostSyncConfig {
     /// Git worktrees support
     #[serde(skip_serializing_if = "Option::is_none")]
     pub worktrees: Option<WorktreesConfig>,
+
+    /// Propagate the host locale as LANG/LC_ALL (default: false)
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub locale: bool,
+
+    /// Propagate the host keyboard layout as XKB_DEFAULT_* variables (default: false)
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub keyboard: bool,
+
+    /// Copy custom entries from the host's /etc/hosts (default: false)
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub etc_hosts: bool,
+
+    /// Extra /etc/hosts entries as `hostname:ip`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extra_hosts: Vec<String>,
+
+    /// Forward URLs opened in the VM (`xdg-open`, `open`, `$BROWSER`) to the host browser (default: true)
+    #[serde(default = "default_true")]
+    pub browser: bool,
+
+    /// Share the clipboard with `vm ssh`/`vm exec` sessions through `vm-clip` and OSC 52 (default: false)
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub clipboard: bool,
+
+    /// Line-ending guidance for Windows hosts (default: lf)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line_endings: Option<LineEndings>,
+}
+
+/// Host devices passed through to the VM.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub struct DevicesConfig {
+    /// Sound card (/dev/snd) and the host PulseAudio socket (default: false)
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub audio: bool,
+
+    /// Every webcam (/dev/video*) on the host (default: false)
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub webcam: bool,
+
+    /// USB devices as `vendor:product` IDs (e.g. `046d:0825`) or /dev/bus/usb paths
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub usb: Vec<String>,
+}
+
+impl DevicesConfig {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// A USB device from `devices.usb`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UsbDevice {
+    /// Whichever device has this vendor and product ID
+    Id { vendor: u16, product: u16 },
+    /// A device node under /dev/bus/usb
+    Path(String),
+}
+
+impl UsbDevice {
+    pub fn parse(spec: &str) -> Option<Self> {
+        if spec.starts_with("/dev/bus/usb/") {
+            return Some(Self::Path(spec.to_string()));
+        }
+        let (vendor, product) = spec.split_once(':')?;
+        let parse_id = |id: &str| {
+            (id.len() == 4)
+                .then(|| u16::from_str_radix(id, 16).ok())
+                .flatten()
+        };
+        Some(Self::Id {
+            vendor: parse_id(vendor)?,
+            product: parse_id(product)?,
+        })
+    }
+}
+
+/// Line endings a project expects inside the Linux VM.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum LineEndings {
+    /// Files must be checked out with LF; warn on Windows hosts when Git would write CRLF
+    #[default]
+    Lf,
+    /// The project handles line endings itself; skip the check
+    Ignore,
 }
 
 /// Tart virtualization provider configuration.
@@ -814,6 +1231,23 @@ pub struct TartConfig {
     pub storage_path: Option<String>,
 }
 
+/// QEMU/libvirt virtualization provider configuration (Linux hosts).
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct LibvirtConfig {
+    /// libvirt connection URI (default: `qemu:///system`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub uri: Option<String>,
+    /// libvirt network the VM joins (default: `default`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub network: Option<String>,
+    /// Size of the VM's disk overlay (default: 40 GB)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub disk_size: Option<DiskLimit>,
+    /// Directory holding disks and cloud-init seeds (default: `~/.vm/libvirt`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub storage_path: Option<String>,
+}
+
 /// Package linking and development workflow configuration.
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct PackageLinkingConfig {
@@ -945,6 +1379,14 @@ pub struct NetworkingConfig {
     /// Networks will be created automatically if they don't exist.
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub networks: Vec<String>,
+
+    /// DNS servers used instead of the ones inherited from the host.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub dns: Vec<String>,
+
+    /// Extra /etc/hosts entries as `hostname:ip`; the IP may be `host-gateway`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extra_hosts: Vec<String>,
 }
 
 impl VmConfig {
@@ -970,6 +1412,18 @@ impl VmConfig {
         Ok(serde_json::to_string_pretty(self)?)
     }
 
+    /// Short, stable hash of the configuration, recorded on every resource
+    /// created from it as the `com.vm.config-hash` label
+    pub fn config_hash(&self) -> String {
+        use sha2::{Digest, Sha256};
+        let serialized = serde_json::to_vec(self).unwrap_or_default();
+        Sha256::digest(&serialized)
+            .iter()
+            .take(6)
+            .map(|byte| format!("{byte:02x}"))
+            .collect()
+    }
+
     pub fn apply_default_backup_settings(&mut self) {
         for (_, service) in self.services.iter_mut() {
             let should_backup = service.backup_on_destroy.is_none()
@@ -981,6 +1435,86 @@ impl VmConfig {
         }
     }
 
+    /// This configuration as an instance template (see `vm config template save`).
+    ///
+    /// The project identity and port allocation are left out, because they belong
+    /// to each instance rather than to the environment it runs.
+    pub fn to_template(&self) -> VmConfig {
+        let mut template = self.clone();
+        template.schema = None;
+        template.project = None;
+        template.ports = PortsConfig::default();
+        template.profiles = None;
+        template.default_profile = None;
+        template.source_path = None;
+        template.git_config = None;
+        template
+    }
+
+    /// Replace this configuration with an instance template, keeping the project
+    /// identity, port range and the ports of services this project also defines.
+    pub fn apply_template(&mut self, template: &VmConfig) {
+        let mut config = template.clone();
+        config.project = self.project.take();
+        config.ports = std::mem::take(&mut self.ports);
+        for (name, service) in config.services.iter_mut() {
+            if let Some(own) = self.services.get(name) {
+                service.port = own.port;
+            }
+        }
+        config.source_path = self.source_path.take();
+        config.git_config = self.git_config.take();
+        *self = config;
+    }
+
+    /// Devices to pass through: the `devices` section plus the older
+    /// `services.audio` and `services.video` switches
+    pub fn requested_devices(&self) -> DevicesConfig {
+        let mut devices = self.devices.clone().unwrap_or_default();
+        let legacy = |name: &str| self.services.get(name).is_some_and(|s| s.enabled);
+        devices.audio |= legacy("audio");
+        devices.webcam |= legacy("video");
+        devices
+    }
+
+    /// Labels describing what each published port is for (see `vm ports`):
+    /// explicit mappings, then enabled services with a port
+    pub fn port_labels(&self) -> Vec<vm_core::labels::PortLabel> {
+        let mut labels: Vec<_> = self
+            .ports
+            .mappings
+            .iter()
+            .map(|mapping| vm_core::labels::PortLabel {
+                host: mapping.host,
+                guest: mapping.guest,
+                udp: mapping.protocol == crate::ports::Protocol::Udp,
+                name: mapping.purpose().to_string(),
+            })
+            .collect();
+        for (name, service) in &self.services {
+            let Some(port) = service.port.filter(|_| service.enabled) else {
+                continue;
+            };
+            if labels.iter().any(|label| label.host == port) {
+                continue;
+            }
+            let guest = match name.as_str() {
+                "postgresql" => 5432,
+                "mysql" => 3306,
+                "mongodb" => 27017,
+                "redis" => 6379,
+                _ => port,
+            };
+            labels.push(vm_core::labels::PortLabel {
+                host: port,
+                guest,
+                udp: false,
+                name: name.clone(),
+            });
+        }
+        labels
+    }
+
     pub fn is_partial(&self) -> bool {
         self.provider.is_none() || self.project.as_ref().map_or(true, |p| p.name.is_none())
     }
@@ -1002,9 +1536,9 @@ impl VmConfig {
 
         if let Some(provider) = &self.provider {
             #[cfg(feature = "test-helpers")]
-            let valid_providers = ["docker", "podman", "tart", "mock"];
+            let valid_providers = ["docker", "podman", "tart", "libvirt", "mock"];
             #[cfg(not(feature = "test-helpers"))]
-            let valid_providers = ["docker", "podman", "tart"];
+            let valid_providers = ["docker", "podman", "tart", "libvirt"];
 
             if !valid_providers.contains(&provider.as_str()) {
                 errors.push(format!(
@@ -1042,9 +1576,79 @@ impl VmConfig {
                 ));
             }
         }
+
+        if let Some(mock) = self.services.get("mock").filter(|mock| mock.enabled) {
+            match (&mock.spec, &mock.fixtures) {
+                (Some(_), Some(_)) => errors
+                    .push("Service 'mock' sets both 'spec' and 'fixtures'; choose one".to_string()),
+                (None, None) => errors.push(
+                    "Service 'mock' needs an OpenAPI 'spec' file or a 'fixtures' directory"
+                        .to_string(),
+                ),
+                _ => {}
+            }
+        }
+
+        const SQL_SERVICES: &[&str] = &["postgresql", "mysql", "mongodb"];
+        for (service_name, service) in &self.services {
+            for (dependency, gate) in &service.depends_on {
+                if gate.condition == ReadinessCondition::Sql
+                    && !SQL_SERVICES.contains(&dependency.as_str())
+                {
+                    errors.push(format!(
+                        "Service '{service_name}' uses a sql readiness check on '{dependency}', which is not a database service"
+                    ));
+                }
+            }
+        }
+        let names: Vec<&str> = self.services.keys().map(String::as_str).collect();
+        if let Err(cycle) = self.service_start_order(&names) {
+            errors.push(cycle);
+        }
         errors
     }
 
+    /// Order services so that every service comes after the services it depends on.
+    ///
+    /// Dependencies outside `names` are ignored. Returns an error naming a service
+    /// on the cycle if the `depends_on` graph is cyclic.
+    pub fn service_start_order(&self, names: &[&str]) -> std::result::Result<Vec<String>, String> {
+        fn visit(
+            config: &VmConfig,
+            name: &str,
+            names: &[&str],
+            visiting: &mut Vec<String>,
+            ordered: &mut Vec<String>,
+        ) -> std::result::Result<(), String> {
+            if ordered.iter().any(|n| n == name) {
+                return Ok(());
+            }
+            if visiting.iter().any(|n| n == name) {
+                visiting.push(name.to_string());
+                return Err(format!(
+                    "Service dependency cycle: {}",
+                    visiting.join(" → ")
+                ));
+            }
+            visiting.push(name.to_string());
+            if let Some(service) = config.services.get(name) {
+                let dependencies = service.depends_on.keys();
+                for dependency in dependencies.filter(|d| names.contains(&d.as_str())) {
+                    visit(config, dependency, names, visiting, ordered)?;
+                }
+            }
+            visiting.pop();
+            ordered.push(name.to_string());
+            Ok(())
+        }
+
+        let mut ordered = Vec::new();
+        for name in names {
+            visit(self, name, names, &mut Vec::new(), &mut ordered)?;
+        }
+        Ok(ordered)
+    }
+
     pub fn ensure_service_ports(&mut self) {
         const PRIORITY_SERVICES: &[&str] = &["postgresql", "redis", "mysql", "mongodb"];
         const SERVICES_WITHOUT_PORTS: &[&str] = &["docker"];
@@ -73,6 +73,10 @@ fn apply_preset_to_config(
     let mut missing_presets = Vec::new();
 
     for preset_name in &preset_list {
+        if preset_name.starts_with(crate::preset_registry::REGISTRY_PREFIX) {
+            // Resolved from the local registry cache when loaded
+            continue;
+        }
         if !available_presets.contains(&preset_name.to_string()) {
             missing_presets.push(*preset_name);
         }
@@ -230,16 +234,19 @@ fn create_minimal_preset_config(
         provider: merged.provider.clone(),
         default_profile: merged.default_profile.clone(),
         tart: merged.tart.clone(),
+        libvirt: merged.libvirt.clone(),
         project: merged.project.clone(),
         vm,
         ports: merged.ports.clone(),
         services: merged.services.clone(),
+        db: merged.db.clone(),
         terminal: merged.terminal.clone(),
         profiles: merged.profiles.clone(),
         apt_packages: merged.apt_packages.clone(),
         npm_packages: merged.npm_packages.clone(),
         pip_packages: merged.pip_packages.clone(),
         cargo_packages: merged.cargo_packages.clone(),
+        features: merged.features.clone(),
         ..Default::default()
     };
 
@@ -390,6 +397,11 @@ fn preserve_user_customizations(
         has_customizations = true;
     }
 
+    if original.libvirt.is_some() {
+        minimal.libvirt = merged.libvirt.clone();
+        has_customizations = true;
+    }
+
     has_customizations
 }
 
@@ -428,6 +440,9 @@ fn print_customization_warning(original: &VmConfig) {
     if original.tart.is_some() {
         vm_println!("   - tart provider settings");
     }
+    if original.libvirt.is_some() {
+        vm_println!("   - libvirt provider settings");
+    }
 
     vm_println!("");
     vm_println!("These have been preserved, but consider:");
@@ -309,3 +309,223 @@ memory: 4096
         assert!(debug.contains("ubuntu:24.04"));
     }
 }
+
+#[cfg(test)]
+mod db_backup_tests {
+    use crate::config::DbBackupConfig;
+    use std::time::Duration;
+
+    fn schedule(value: &str) -> Option<Duration> {
+        DbBackupConfig {
+            schedule: Some(value.to_string()),
+            ..Default::default()
+        }
+        .interval()
+    }
+
+    #[test]
+    fn test_named_schedules() {
+        assert_eq!(schedule("hourly"), Some(Duration::from_secs(3600)));
+        assert_eq!(schedule("Daily"), Some(Duration::from_secs(24 * 3600)));
+        assert_eq!(schedule("weekly"), Some(Duration::from_secs(7 * 24 * 3600)));
+    }
+
+    #[test]
+    fn test_interval_schedules() {
+        assert_eq!(schedule("6h"), Some(Duration::from_secs(6 * 3600)));
+        assert_eq!(schedule("2d"), Some(Duration::from_secs(48 * 3600)));
+        assert_eq!(schedule("1w"), Some(Duration::from_secs(7 * 24 * 3600)));
+    }
+
+    #[test]
+    fn test_invalid_schedules() {
+        assert_eq!(schedule(""), None);
+        assert_eq!(schedule("0h"), None);
+        assert_eq!(schedule("soon"), None);
+        assert_eq!(DbBackupConfig::default().interval(), None);
+    }
+}
+
+#[cfg(test)]
+mod service_dependency_tests {
+    use crate::config::{ReadinessCondition, VmConfig};
+
+    fn config(yaml: &str) -> VmConfig {
+        serde_yaml_ng::from_str(yaml).unwrap()
+    }
+
+    #[test]
+    fn test_start_order_follows_depends_on() {
+        let config = config(
+            r#"
+services:
+  redis:
+    enabled: true
+    depends_on:
+      postgresql:
+        condition: sql
+        query: SELECT 1 FROM users
+  postgresql:
+    enabled: true
+"#,
+        );
+        let redis = &config.services["redis"];
+        assert_eq!(
+            redis.depends_on["postgresql"].condition,
+            ReadinessCondition::Sql
+        );
+        assert_eq!(
+            config.service_start_order(&["redis", "postgresql"]),
+            Ok(vec!["postgresql".to_string(), "redis".to_string()])
+        );
+        // Dependencies that aren't being started don't affect the order
+        assert_eq!(
+            config.service_start_order(&["redis"]),
+            Ok(vec!["redis".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_dependency_cycles_are_rejected() {
+        let config = config(
+            r#"
+services:
+  redis:
+    depends_on:
+      postgresql: {}
+  postgresql:
+    depends_on:
+      redis:
+        condition: tcp
+"#,
+        );
+        let err = config
+            .service_start_order(&["redis", "postgresql"])
+            .unwrap_err();
+        assert!(err.contains("cycle"), "{err}");
+        assert!(config.validate(true).iter().any(|e| e.contains("cycle")));
+    }
+
+    #[test]
+    fn test_mock_needs_exactly_one_source() {
+        let mock_errors = |source: &str| {
+            config(&format!(
+                "services:\n  mock:\n    enabled: true\n    port: 4010\n{source}"
+            ))
+            .validate(true)
+            .into_iter()
+            .filter(|e| e.contains("'mock'"))
+            .count()
+        };
+        assert_eq!(mock_errors("    spec: api/openapi.yaml\n"), 0);
+        assert_eq!(mock_errors("    fixtures: api/fixtures\n"), 0);
+        assert_eq!(mock_errors(""), 1);
+        assert_eq!(
+            mock_errors("    spec: api/openapi.yaml\n    fixtures: api/fixtures\n"),
+            1
+        );
+    }
+}
+
+#[cfg(test)]
+mod template_tests {
+    use crate::config::VmConfig;
+
+    fn config(yaml: &str) -> VmConfig {
+        serde_yaml_ng::from_str(yaml).unwrap()
+    }
+
+    #[test]
+    fn test_template_keeps_project_identity_and_ports() {
+        let source = config(
+            r#"
+provider: docker
+project:
+  name: backend
+ports:
+  _range: [3100, 3109]
+vm:
+  memory: 8192
+services:
+  postgresql:
+    enabled: true
+    port: 3100
+apt_packages: [jq]
+"#,
+        );
+        let template = source.to_template();
+        assert!(template.project.is_none());
+        assert!(template.ports.range.is_none());
+
+        let mut target = config(
+            r#"
+project:
+  name: backend-2
+ports:
+  _range: [3200, 3209]
+services:
+  postgresql:
+    port: 3200
+"#,
+        );
+        target.apply_template(&template);
+        assert_eq!(
+            target.project.and_then(|project| project.name).as_deref(),
+            Some("backend-2")
+        );
+        assert_eq!(target.ports.range, Some(vec![3200, 3209]));
+        assert_eq!(target.provider.as_deref(), Some("docker"));
+        assert_eq!(target.apt_packages, vec!["jq"]);
+        assert!(target.services["postgresql"].enabled);
+        assert_eq!(target.services["postgresql"].port, Some(3200));
+    }
+}
+
+#[cfg(test)]
+mod port_label_tests {
+    use crate::config::VmConfig;
+
+    #[test]
+    fn test_port_labels_name_mappings_and_services() {
+        let config: VmConfig = serde_yaml_ng::from_str(
+            r#"
+ports:
+  _range: [3100, 3109]
+  mappings:
+    - host: 3100
+      guest: 3000
+    - host: 3101
+      guest: 8000
+      name: docs
+    - host: 3102
+      guest: 12345
+      protocol: udp
+services:
+  postgresql:
+    enabled: true
+    port: 3105
+  redis:
+    enabled: false
+    port: 3106
+"#,
+        )
+        .unwrap();
+        let labels: Vec<_> = config
+            .port_labels()
+            .iter()
+            .map(|label| (label.key(), label.value()))
+            .collect();
+        assert_eq!(
+            labels,
+            vec![
+                ("com.vm.port.3100".to_string(), "web:3000".to_string()),
+                ("com.vm.port.3101".to_string(), "docs:8000".to_string()),
+                ("com.vm.port.3102".to_string(), "app:12345/udp".to_string()),
+                (
+                    "com.vm.port.3105".to_string(),
+                    "postgresql:5432".to_string()
+                ),
+            ]
+        );
+    }
+}
@@ -5,6 +5,7 @@
 //! - Adapt VM configurations for different host platforms
 //! - Provide platform-specific recommendations
 //! - Enable host-aware virtualization optimizations
+//! - Propagate host timezone, locale and keyboard layout into VMs
 
 use std::env;
 use std::fs;
@@ -100,6 +101,158 @@ pub fn detect_timezone() -> String {
     "UTC".to_string()
 }
 
+/// Host keyboard layout in XKB terms
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct KeyboardLayout {
+    pub layout: String,
+    pub variant: Option<String>,
+    pub model: Option<String>,
+    pub options: Option<String>,
+}
+
+/// Detect the host locale (e.g. `de_DE.UTF-8`), ignoring the `C`/`POSIX` defaults.
+pub fn detect_locale() -> Option<String> {
+    // 1. Environment, in POSIX precedence order
+    for var in ["LC_ALL", "LC_CTYPE", "LANG"] {
+        if let Ok(value) = env::var(var) {
+            if let Some(locale) = normalize_locale(&value) {
+                return Some(locale);
+            }
+        }
+    }
+
+    // 2. macOS stores the locale as a user default (e.g. `en_GB`)
+    if cfg!(target_os = "macos") {
+        if let Ok(output) = Command::new("defaults")
+            .args(["read", "-g", "AppleLocale"])
+            .output()
+        {
+            if output.status.success() {
+                return normalize_locale(String::from_utf8_lossy(&output.stdout).trim());
+            }
+        }
+    }
+
+    // 3. System locale on systemd hosts
+    fs::read_to_string("/etc/locale.conf")
+        .or_else(|_| fs::read_to_string("/etc/default/locale"))
+        .ok()
+        .and_then(|contents| {
+            parse_shell_assignments(&contents)
+                .into_iter()
+                .find(|(key, _)| key == "LANG")
+                .and_then(|(_, value)| normalize_locale(&value))
+        })
+}
+
+/// Normalize a locale name to `lang_COUNTRY.UTF-8`, or `None` for `C`/`POSIX`.
+fn normalize_locale(value: &str) -> Option<String> {
+    // macOS locales can carry a calendar or currency suffix (`en_US@rg=gbzzzz`)
+    let value = value.trim().split('@').next().unwrap_or_default();
+    if value.is_empty() || value == "C" || value == "POSIX" || value.starts_with("C.") {
+        return None;
+    }
+    if value.contains('.') {
+        Some(value.to_string())
+    } else {
+        Some(format!("{value}.UTF-8"))
+    }
+}
+
+/// Detect the host keyboard layout (Linux only).
+pub fn detect_keyboard_layout() -> Option<KeyboardLayout> {
+    if let Ok(layout) = env::var("XKB_DEFAULT_LAYOUT") {
+        if !layout.is_empty() {
+            return Some(KeyboardLayout {
+                layout,
+                variant: env::var("XKB_DEFAULT_VARIANT")
+                    .ok()
+                    .filter(|v| !v.is_empty()),
+                model: env::var("XKB_DEFAULT_MODEL").ok().filter(|v| !v.is_empty()),
+                options: env::var("XKB_DEFAULT_OPTIONS")
+                    .ok()
+                    .filter(|v| !v.is_empty()),
+            });
+        }
+    }
+
+    fs::read_to_string("/etc/default/keyboard")
+        .ok()
+        .and_then(|contents| parse_keyboard_config(&contents))
+}
+
+/// Parse a Debian-style `/etc/default/keyboard` file.
+pub fn parse_keyboard_config(contents: &str) -> Option<KeyboardLayout> {
+    let values = parse_shell_assignments(contents);
+    let get = |key: &str| {
+        values
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.clone())
+            .filter(|v| !v.is_empty())
+    };
+
+    Some(KeyboardLayout {
+        layout: get("XKBLAYOUT")?,
+        variant: get("XKBVARIANT"),
+        model: get("XKBMODEL"),
+        options: get("XKBOPTIONS"),
+    })
+}
+
+/// Custom host entries from `/etc/hosts`, as `(hostname, ip)` pairs.
+pub fn detect_host_entries() -> Vec<(String, String)> {
+    fs::read_to_string("/etc/hosts")
+        .map(|contents| parse_hosts_file(&contents))
+        .unwrap_or_default()
+}
+
+/// Parse an `/etc/hosts` file, skipping loopback and IPv6 boilerplate entries.
+pub fn parse_hosts_file(contents: &str) -> Vec<(String, String)> {
+    let mut entries = Vec::new();
+    for line in contents.lines() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        let mut fields = line.split_whitespace();
+        let Some(ip) = fields.next() else {
+            continue;
+        };
+        if ip.starts_with("127.")
+            || ip == "::1"
+            || ip.starts_with("fe00:")
+            || ip.starts_with("ff0")
+            || ip == "255.255.255.255"
+        {
+            continue;
+        }
+        for hostname in fields {
+            if hostname != "localhost" && hostname != "broadcasthost" {
+                entries.push((hostname.to_string(), ip.to_string()));
+            }
+        }
+    }
+    entries
+}
+
+/// Parse `KEY=value` / `KEY="value"` lines from a shell-style config file.
+fn parse_shell_assignments(contents: &str) -> Vec<(String, String)> {
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| {
+            (
+                key.trim().to_string(),
+                value
+                    .trim()
+                    .trim_matches('"')
+                    .trim_matches('\'')
+                    .to_string(),
+            )
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -138,4 +291,58 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_normalize_locale() {
+        assert_eq!(
+            normalize_locale("de_DE.UTF-8").as_deref(),
+            Some("de_DE.UTF-8")
+        );
+        assert_eq!(normalize_locale("en_GB").as_deref(), Some("en_GB.UTF-8"));
+        assert_eq!(
+            normalize_locale("en_US@rg=gbzzzz").as_deref(),
+            Some("en_US.UTF-8")
+        );
+        assert_eq!(normalize_locale("C"), None);
+        assert_eq!(normalize_locale("C.UTF-8"), None);
+        assert_eq!(normalize_locale("POSIX"), None);
+    }
+
+    #[test]
+    fn test_parse_keyboard_config() {
+        let contents = r#"
+# KEYBOARD CONFIGURATION FILE
+XKBMODEL="pc105"
+XKBLAYOUT="de"
+XKBVARIANT="nodeadkeys"
+XKBOPTIONS=""
+BACKSPACE="guess"
+"#;
+        let layout = parse_keyboard_config(contents).unwrap();
+        assert_eq!(layout.layout, "de");
+        assert_eq!(layout.variant.as_deref(), Some("nodeadkeys"));
+        assert_eq!(layout.model.as_deref(), Some("pc105"));
+        assert_eq!(layout.options, None);
+
+        assert_eq!(parse_keyboard_config("XKBMODEL=pc105"), None);
+    }
+
+    #[test]
+    fn test_parse_hosts_file() {
+        let contents = "\
+127.0.0.1 localhost
+127.0.1.1 myhost
+::1 localhost ip6-localhost ip6-loopback
+ff02::1 ip6-allnodes
+10.0.0.5 db.internal cache.internal # staging
+# 10.0.0.6 disabled.internal
+";
+        assert_eq!(
+            parse_hosts_file(contents),
+            vec![
+                ("db.internal".to_string(), "10.0.0.5".to_string()),
+                ("cache.internal".to_string(), "10.0.0.5".to_string()),
+            ]
+        );
+    }
 }
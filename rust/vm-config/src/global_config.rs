@@ -18,6 +18,14 @@ pub struct GlobalConfig {
     #[serde(rename = "$schema", skip_serializing_if = "Option::is_none")]
     pub schema: Option<String>,
 
+    /// Air-gapped mode: never reach the network and rely on caches only
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub offline: bool,
+
+    /// HTTP proxy and extra CA settings for outbound connections
+    #[serde(default, skip_serializing_if = "ProxySettings::is_default")]
+    pub proxy: ProxySettings,
+
     /// Global services configuration
     #[serde(default, skip_serializing_if = "GlobalServices::is_default")]
     pub services: GlobalServices,
@@ -42,6 +50,26 @@ pub struct GlobalConfig {
     #[serde(default, skip_serializing_if = "SnapshotSettings::is_default")]
     pub snapshots: SnapshotSettings,
 
+    /// Image build settings
+    #[serde(default, skip_serializing_if = "BuildSettings::is_default")]
+    pub build: BuildSettings,
+
+    /// Vulnerability scan settings
+    #[serde(default, skip_serializing_if = "ScanSettings::is_default")]
+    pub scan: ScanSettings,
+
+    /// Desktop notification settings
+    #[serde(default, skip_serializing_if = "NotificationSettings::is_default")]
+    pub notifications: NotificationSettings,
+
+    /// Service log archive settings
+    #[serde(default, skip_serializing_if = "LogArchiveSettings::is_default")]
+    pub logs: LogArchiveSettings,
+
+    /// Instance templates saved with `vm config template save`, for `vm create --like`
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub templates: IndexMap<String, crate::config::VmConfig>,
+
     /// Extra configuration for extensions
     #[serde(flatten)]
     pub extra: IndexMap<String, serde_json::Value>,
@@ -122,6 +150,195 @@ impl SnapshotSettings {
     }
 }
 
+/// Image build settings
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct BuildSettings {
+    /// Offload `vm create` image builds to a remote BuildKit builder
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remote_builder: Option<RemoteBuilderSettings>,
+}
+
+impl BuildSettings {
+    pub fn is_default(&self) -> bool {
+        self.remote_builder.is_none()
+    }
+}
+
+/// `vm scan` settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScanSettings {
+    /// Lowest severity that makes `vm scan` exit non-zero
+    /// (`low`, `medium`, `high`, `critical`, or `none`)
+    #[serde(default = "default_scan_fail_on")]
+    pub fail_on: String,
+
+    /// Vulnerability IDs to ignore (e.g. `CVE-2023-1234`, `GHSA-xxxx-xxxx-xxxx`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ignore: Vec<String>,
+
+    /// OSV API base URL, for mirrors or air-gapped proxies
+    #[serde(default = "default_osv_url")]
+    pub osv_url: String,
+}
+
+fn default_scan_fail_on() -> String {
+    "high".to_string()
+}
+
+fn default_osv_url() -> String {
+    "https://api.osv.dev".to_string()
+}
+
+impl Default for ScanSettings {
+    fn default() -> Self {
+        Self {
+            fail_on: default_scan_fail_on(),
+            ignore: Vec::new(),
+            osv_url: default_osv_url(),
+        }
+    }
+}
+
+impl ScanSettings {
+    pub fn is_default(&self) -> bool {
+        self.fail_on == default_scan_fail_on()
+            && self.ignore.is_empty()
+            && self.osv_url == default_osv_url()
+    }
+}
+
+/// Desktop notifications when long operations finish
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationSettings {
+    /// Whether to show desktop notifications
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Only notify for operations that took at least this many seconds
+    /// (failures of background services are always reported)
+    #[serde(default = "default_notification_min_duration")]
+    pub min_duration_secs: u64,
+}
+
+fn default_notification_min_duration() -> u64 {
+    30
+}
+
+impl Default for NotificationSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            min_duration_secs: default_notification_min_duration(),
+        }
+    }
+}
+
+impl NotificationSettings {
+    pub fn is_default(&self) -> bool {
+        !self.enabled && self.min_duration_secs == default_notification_min_duration()
+    }
+}
+
+/// Archiving of service container logs to the host
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogArchiveSettings {
+    /// Copy postgres, redis, mongodb and mysql logs to `path` when a VM stops
+    /// or is destroyed
+    #[serde(default)]
+    pub archive: bool,
+
+    /// Directory holding one subdirectory of logs per project
+    #[serde(default = "default_logs_path")]
+    pub path: String,
+
+    /// Size at which a service's log is rotated, in MB
+    #[serde(default = "default_log_max_size_mb")]
+    pub max_size_mb: u64,
+
+    /// Number of rotated logs to keep per service
+    #[serde(default = "default_keep_count")]
+    pub keep_count: u32,
+}
+
+fn default_logs_path() -> String {
+    "~/.vm/logs".to_string()
+}
+
+fn default_log_max_size_mb() -> u64 {
+    10
+}
+
+impl Default for LogArchiveSettings {
+    fn default() -> Self {
+        Self {
+            archive: false,
+            path: default_logs_path(),
+            max_size_mb: default_log_max_size_mb(),
+            keep_count: default_keep_count(),
+        }
+    }
+}
+
+impl LogArchiveSettings {
+    pub fn is_default(&self) -> bool {
+        !self.archive
+            && self.path == default_logs_path()
+            && self.max_size_mb == default_log_max_size_mb()
+            && self.keep_count == default_keep_count()
+    }
+}
+
+/// Proxy settings for hosts behind a corporate proxy
+///
+/// Applied to vm's own HTTP clients and passed on to created VMs. Standard
+/// `HTTP_PROXY`/`HTTPS_PROXY`/`NO_PROXY` environment variables take precedence.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub struct ProxySettings {
+    /// Proxy URL for plain HTTP requests
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub http: Option<String>,
+
+    /// Proxy URL for HTTPS requests
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub https: Option<String>,
+
+    /// Hosts, domains or CIDR ranges that bypass the proxy
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub no_proxy: Vec<String>,
+
+    /// PEM bundle of extra CA certificates to trust (e.g. a TLS-intercepting proxy's CA)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ca_bundle: Option<String>,
+}
+
+impl ProxySettings {
+    pub fn is_default(&self) -> bool {
+        self == &Self::default()
+    }
+}
+
+/// A `docker buildx` builder backed by a remote BuildKit daemon
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RemoteBuilderSettings {
+    /// Name of the buildx builder to use
+    #[serde(default = "default_remote_builder_name")]
+    pub name: String,
+
+    /// BuildKit endpoint (e.g. `tcp://buildkitd.internal:1234`). When set, the
+    /// builder is created with the `remote` driver if it doesn't exist yet;
+    /// otherwise the named builder must already be configured.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub endpoint: Option<String>,
+
+    /// Extra `--driver-opt` values for `docker buildx create` (e.g. `cacert`, `cert`, `key`)
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub driver_opts: IndexMap<String, String>,
+}
+
+fn default_remote_builder_name() -> String {
+    "vm-remote".to_string()
+}
+
 /// Global services that serve all VMs on the system
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct GlobalServices {
@@ -353,6 +570,11 @@ pub struct DockerRegistrySettings {
     /// Health check interval in minutes
     #[serde(default = "default_health_check_interval")]
     pub health_check_interval_minutes: u32,
+
+    /// Limit each image download from Docker Hub to this many KiB/s (0 = unlimited).
+    /// The limit applies per connection; cached images are served at full speed.
+    #[serde(default)]
+    pub max_download_kbps: u64,
 }
 
 impl Default for DockerRegistrySettings {
@@ -366,6 +588,7 @@ impl Default for DockerRegistrySettings {
             enable_lru_eviction: true,
             enable_auto_restart: true,
             health_check_interval_minutes: default_health_check_interval(),
+            max_download_kbps: 0,
         }
     }
 }
@@ -424,6 +647,10 @@ pub struct PackageRegistrySettings {
     /// Maximum storage size in GB
     #[serde(default = "default_package_storage")]
     pub max_storage_gb: u64,
+
+    /// Limit all upstream package downloads to this many KiB/s (0 = unlimited)
+    #[serde(default)]
+    pub max_download_kbps: u64,
 }
 
 impl Default for PackageRegistrySettings {
@@ -432,6 +659,7 @@ impl Default for PackageRegistrySettings {
             enabled: false,
             port: default_package_registry_port(),
             max_storage_gb: default_package_storage(),
+            max_download_kbps: 0,
         }
     }
 }
@@ -640,6 +868,10 @@ fn default_true() -> bool {
     true
 }
 
+fn is_false(b: &bool) -> bool {
+    !b
+}
+
 impl GlobalConfig {
     /// Load global configuration from the standard location
     ///
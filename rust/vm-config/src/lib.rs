@@ -38,6 +38,7 @@ pub mod paths;
 pub mod ports;
 pub mod preset; // Made public for integration tests - used internally by config_ops and cli
 pub mod preset_cache; // Preset caching layer
+pub mod preset_registry; // Registry-backed presets (registry:<org>/<name>@<version>)
 pub mod resources; // VM resource suggestions
 pub mod schema; // Schema-aware type detection
 pub mod validate;
@@ -10,6 +10,30 @@ pub struct PortMapping {
     /// The network protocol (TCP or UDP). Defaults to TCP.
     #[serde(default)]
     pub protocol: Protocol,
+    /// What the port is for (e.g. `web`, `api`); guessed from the guest port if unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+impl PortMapping {
+    /// The mapping's name, or the usual purpose of its guest port
+    pub fn purpose(&self) -> &str {
+        self.name
+            .as_deref()
+            .unwrap_or_else(|| port_purpose(self.guest))
+    }
+}
+
+/// Usual purpose of a well-known development port, `app` for any other port
+pub fn port_purpose(port: u16) -> &'static str {
+    match port {
+        80 | 443 | 3000 | 3001 | 4200 | 5173 | 8080 | 8081 => "web",
+        4000 | 5000 | 8000 | 8001 => "api",
+        1433 | 3306 | 5432 | 27017 => "db",
+        6379 | 11211 => "cache",
+        9229 | 5678 => "debug",
+        _ => "app",
+    }
 }
 
 /// Represents the network protocol for port mapping.
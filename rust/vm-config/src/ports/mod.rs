@@ -7,6 +7,6 @@ pub mod mapping;
 pub mod range;
 pub mod registry;
 
-pub use mapping::{PortMapping, Protocol};
+pub use mapping::{port_purpose, PortMapping, Protocol};
 pub use range::PortRange;
 pub use registry::{PortRegistry, ProjectEntry};
@@ -6,18 +6,15 @@
 
 // Standard library
 use std::collections::HashMap;
-use std::fs;
-use std::fs::OpenOptions;
 use std::path::PathBuf;
-use std::time::{Duration, Instant};
 
 // External crates
-use fs2::FileExt;
 use serde::{Deserialize, Serialize};
 use vm_core::error::VmError;
 use vm_core::msg;
 use vm_core::{error::Result, user_paths, vm_println};
 use vm_messages::messages::MESSAGES;
+use vm_state::StateStore;
 
 // Internal imports
 use super::range::PortRange;
@@ -36,44 +33,32 @@ pub struct ProjectEntry {
 #[derive(Debug, Default)]
 pub struct PortRegistry {
     entries: HashMap<String, ProjectEntry>,
-    registry_path: PathBuf,
+    db_path: PathBuf,
 }
 
 impl PortRegistry {
-    /// Loads the port registry from the default location (`~/.vm/port-registry.json`).
+    /// Loads the port registry from the shared state database (`~/.vm/state.db`).
+    ///
+    /// A legacy `~/.vm/ports.json` registry is imported the first time the
+    /// database is opened.
     ///
     /// # Returns
     /// A `Result` containing the loaded registry or an error if loading fails.
     pub fn load() -> Result<Self> {
-        let registry_path = user_paths::port_registry_path()?;
-        let registry_dir = registry_path
-            .parent()
-            .ok_or_else(|| vm_core::error::VmError::Config("Invalid registry path".to_string()))?;
-
-        // Create registry directory if it doesn't exist
-        if !registry_dir.exists() {
-            fs::create_dir_all(registry_dir)?;
-        }
-
-        // Initialize empty registry file if it doesn't exist
-        if !registry_path.exists() {
-            fs::write(&registry_path, "{}")?;
-        }
-
-        // Load registry from file
-        // Note: File locking APIs (lock_shared/unlock) require Rust 1.89.0+
-        // For compatibility with MSRV 1.70.0, we use a simpler approach
-        let content = fs::read_to_string(&registry_path)?;
-        let entries: HashMap<String, ProjectEntry> =
-            if content.trim().is_empty() || content.trim() == "{}" {
-                HashMap::new()
-            } else {
-                serde_json::from_str(&content)?
-            };
+        let mut store = StateStore::open_default()?;
+        store.import_legacy_file(
+            vm_state::PORTS,
+            &user_paths::port_registry_path()?,
+            |content| {
+                let entries: HashMap<String, serde_json::Value> = serde_json::from_str(content)?;
+                Ok(entries.into_iter().collect())
+            },
+        )?;
+        let entries = store.list(vm_state::PORTS)?.into_iter().collect();
 
         Ok(PortRegistry {
             entries,
-            registry_path,
+            db_path: store.path().to_path_buf(),
         })
     }
 
@@ -214,115 +199,26 @@ impl PortRegistry {
         None
     }
 
-    /// Performs an atomic update operation with proper file locking.
-    /// This prevents race conditions during concurrent access to the registry file.
+    /// Applies `update_fn` to the stored entries inside a single state transaction.
+    /// This prevents race conditions during concurrent access to the registry.
     fn atomic_update<F>(&mut self, update_fn: F) -> Result<()>
     where
         F: FnOnce(&mut HashMap<String, ProjectEntry>) -> Result<()>,
     {
-        // Ensure parent directory exists
-        if let Some(parent) = self.registry_path.parent() {
-            if !parent.exists() {
-                fs::create_dir_all(parent).map_err(|e| {
-                    VmError::Filesystem(format!(
-                        "Failed to create registry directory: {parent:?}: {e}"
-                    ))
-                })?;
-            }
-        }
-
-        // Open or create the registry file
-        let file = OpenOptions::new()
-            .create(true)
-            .read(true)
-            .write(true)
-            .truncate(false)
-            .open(&self.registry_path)
-            .map_err(|e| {
-                VmError::Filesystem(format!(
-                    "Failed to open registry file: {:?}: {}",
-                    self.registry_path, e
-                ))
-            })?;
-
-        // Acquire exclusive lock with timeout and retry logic
-        const MAX_RETRIES: u32 = 100; // More retries for lock acquisition
-        const RETRY_DELAY: Duration = Duration::from_millis(10);
-        const LOCK_TIMEOUT: Duration = Duration::from_secs(30);
-
-        let lock_start = Instant::now();
-        let mut attempts = 0;
-
-        loop {
-            match file.try_lock_exclusive() {
-                Ok(()) => break,
-                Err(e) => {
-                    attempts += 1;
-                    if lock_start.elapsed() > LOCK_TIMEOUT {
-                        return Err(vm_core::error::VmError::Internal(format!(
-                            "Timeout waiting for exclusive lock on registry file after {attempts} attempts: {e}"
-                        )));
-                    }
-                    if attempts >= MAX_RETRIES {
-                        return Err(vm_core::error::VmError::Internal(format!(
-                            "Maximum retry attempts ({MAX_RETRIES}) exceeded for lock acquisition: {e}"
-                        )));
-                    }
-                    std::thread::sleep(RETRY_DELAY);
-                }
-            }
-        }
+        let mut store = StateStore::open(&self.db_path)?;
+        let entries = store.transaction(|tx| {
+            let mut entries: HashMap<String, ProjectEntry> =
+                tx.list(vm_state::PORTS)?.into_iter().collect();
 
-        // Note: File will be automatically unlocked when it goes out of scope
-        // Manual unlock is not available in MSRV 1.70.0
+            update_fn(&mut entries)
+                .map_err(|e| VmError::Internal(format!("Update function failed: {e}")))?;
 
-        // Read current state
-        let content =
-            fs::read_to_string(&self.registry_path).unwrap_or_else(|_| String::from("{}"));
-
-        let mut entries: HashMap<String, ProjectEntry> =
-            if content.trim().is_empty() || content.trim() == "{}" {
-                HashMap::new()
-            } else {
-                serde_json::from_str(&content).map_err(|e| {
-                    VmError::Serialization(format!("Failed to parse registry JSON: {e}"))
-                })?
-            };
-
-        // Apply the update
-        update_fn(&mut entries)
-            .map_err(|e| VmError::Internal(format!("Update function failed: {e}")))?;
-
-        // Write back to file
-        let json_content = if entries.is_empty() {
-            String::from("{}")
-        } else {
-            serde_json::to_string_pretty(&entries).map_err(|e| {
-                VmError::Serialization(format!("Failed to serialize registry to JSON: {e}"))
-            })?
-        };
-
-        // Write to a temporary file first, then atomically rename
-        // This provides protection against corruption during write operations
-        // Use thread ID to ensure unique temporary file names for concurrent access
-        let thread_id = std::thread::current().id();
-        let temp_path = self
-            .registry_path
-            .with_extension(format!("json.tmp.{thread_id:?}"));
-        fs::write(&temp_path, &json_content).map_err(|e| {
-            VmError::Filesystem(format!(
-                "Failed to write temporary file: {temp_path:?}: {e}"
-            ))
+            tx.clear(vm_state::PORTS)?;
+            for (project, entry) in &entries {
+                tx.put(vm_state::PORTS, project, entry)?;
+            }
+            Ok(entries)
         })?;
-        // If the rename step fails (e.g. cross-device, perms), drop the
-        // leftover temp file so we don't leak `port-registry.json.tmp.*`
-        // siblings into `~/.vm/` over time.
-        if let Err(e) = fs::rename(&temp_path, &self.registry_path) {
-            let _ = fs::remove_file(&temp_path);
-            return Err(VmError::Filesystem(format!(
-                "Failed to atomically rename temporary file: {e}"
-            )));
-        }
 
         // Update our local state
         self.entries = entries;
@@ -339,11 +235,11 @@ mod tests {
 
     #[test]
     fn test_conflict_detection() {
-        let temp_file = tempfile::NamedTempFile::new()
-            .expect("Failed to create temporary file for conflict detection test");
+        let temp_dir =
+            tempdir().expect("Failed to create temporary directory for conflict detection test");
         let mut registry = PortRegistry {
             entries: HashMap::new(),
-            registry_path: temp_file.path().to_path_buf(),
+            db_path: temp_dir.path().join("state.db"),
         };
 
         // Add a project
@@ -373,11 +269,10 @@ mod tests {
 
     #[test]
     fn test_suggest_next_range() {
-        let temp_file = tempfile::NamedTempFile::new()
-            .expect("Failed to create temporary file for suggestion test");
+        let temp_dir = tempdir().expect("Failed to create temporary directory for suggestion test");
         let mut registry = PortRegistry {
             entries: HashMap::new(),
-            registry_path: temp_file.path().to_path_buf(),
+            db_path: temp_dir.path().join("state.db"),
         };
 
         // Register a range
@@ -400,13 +295,13 @@ mod tests {
 
         let temp_dir =
             tempdir().expect("Failed to create temporary directory for file locking test");
-        let registry_path = temp_dir.path().join("port-registry.json");
+        let db_path = temp_dir.path().join("state.db");
 
-        // Initialize an empty registry file
-        std::fs::write(&registry_path, "{}").expect("Failed to initialize empty registry file");
+        // Initialize the state database before the threads race to open it
+        StateStore::open(&db_path).expect("Failed to initialize state database");
 
-        // Create multiple registries that point to the same file (simulating different processes)
-        let shared_path = Arc::new(registry_path);
+        // Create multiple registries that point to the same database (simulating different processes)
+        let shared_path = Arc::new(db_path);
         let mut handles = vec![];
         let num_threads = 10_usize;
 
@@ -416,7 +311,7 @@ mod tests {
                 // Each thread creates its own registry instance pointing to the same file
                 let mut registry = PortRegistry {
                     entries: HashMap::new(),
-                    registry_path: (*path).clone(),
+                    db_path: (*path).clone(),
                 };
 
                 // Add our entry using the register method (now with proper file locking)
@@ -460,10 +355,11 @@ mod tests {
         }
 
         // Load final registry and check if all entries are present
-        let content =
-            std::fs::read_to_string(&*shared_path).expect("Failed to read final registry state");
-        let final_entries: HashMap<String, ProjectEntry> =
-            serde_json::from_str(&content).expect("Failed to parse final registry JSON");
+        let final_entries: HashMap<String, ProjectEntry> = StateStore::open(&shared_path)
+            .and_then(|store| store.list(vm_state::PORTS))
+            .expect("Failed to read final registry state")
+            .into_iter()
+            .collect();
 
         let actual_count = final_entries.len();
 
@@ -83,6 +83,15 @@ impl PresetDetector {
     /// Returns error if preset is not found or cannot be parsed
     #[instrument(skip(self), fields(name = %name))]
     pub fn load_preset(&self, name: &str) -> Result<VmConfig> {
+        // Registry presets are served from the local cache populated by `--from`
+        if let Some(registry_ref) = crate::preset_registry::RegistryPresetRef::parse(name)? {
+            let content = crate::preset_registry::load_cached(&self.presets_dir, &registry_ref)?;
+            let source_desc = format!("registry preset '{}'", registry_ref);
+            let preset_file: PresetFile =
+                crate::yaml::CoreOperations::parse_yaml_with_diagnostics(&content, &source_desc)?;
+            return Ok(preset_file.config);
+        }
+
         // Try plugin presets first (user-facing presets)
         if let Some(config) = self.load_plugin_preset(name)? {
             return Ok(config);
@@ -0,0 +1,216 @@
+//! Registry-backed presets.
+//!
+//! Presets published to the package server are referenced as
+//! `registry:<org>/<name>[@<version>]`. Downloaded documents are cached under
+//! `<presets_dir>/registry/<org>/<name>/<version>.yaml` next to a `.sha256`
+//! sidecar, and every load re-verifies the checksum so a tampered cache entry
+//! is rejected instead of silently applied.
+
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use vm_core::error::{Result, VmError};
+
+/// Prefix marking a preset reference as registry-backed
+pub const REGISTRY_PREFIX: &str = "registry:";
+
+/// A parsed `registry:<org>/<name>[@<version>]` reference
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegistryPresetRef {
+    pub org: String,
+    pub name: String,
+    pub version: Option<String>,
+}
+
+impl RegistryPresetRef {
+    /// Parse a preset reference, returning `Ok(None)` for non-registry presets.
+    pub fn parse(value: &str) -> Result<Option<Self>> {
+        let Some(rest) = value.trim().strip_prefix(REGISTRY_PREFIX) else {
+            return Ok(None);
+        };
+
+        let (path, version) = match rest.split_once('@') {
+            Some((path, version)) => (path, Some(version.to_string())),
+            None => (rest, None),
+        };
+        let (org, name) = path.split_once('/').ok_or_else(|| {
+            VmError::Config(format!(
+                "Invalid registry preset '{value}': expected registry:<org>/<name>[@<version>]"
+            ))
+        })?;
+
+        let valid = |part: &str| {
+            !part.is_empty()
+                && part
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+                && !part.starts_with('.')
+        };
+        if !valid(org) || !valid(name) || version.as_deref().is_some_and(|v| !valid(v)) {
+            return Err(VmError::Config(format!(
+                "Invalid registry preset '{value}': org, name and version may only contain letters, digits, '-', '_' and '.'"
+            )));
+        }
+
+        Ok(Some(Self {
+            org: org.to_string(),
+            name: name.to_string(),
+            version,
+        }))
+    }
+
+    /// `org/name` path used by the package server API
+    pub fn path(&self) -> String {
+        format!("{}/{}", self.org, self.name)
+    }
+
+    /// The reference pinned to a specific version
+    pub fn pinned(&self, version: &str) -> Self {
+        Self {
+            version: Some(version.to_string()),
+            ..self.clone()
+        }
+    }
+
+    fn cache_dir(&self, presets_dir: &Path) -> PathBuf {
+        presets_dir
+            .join("registry")
+            .join(&self.org)
+            .join(&self.name)
+    }
+}
+
+impl std::fmt::Display for RegistryPresetRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{REGISTRY_PREFIX}{}", self.path())?;
+        if let Some(version) = &self.version {
+            write!(f, "@{version}")?;
+        }
+        Ok(())
+    }
+}
+
+fn sha256_hex(content: &[u8]) -> String {
+    let digest = Sha256::digest(content);
+    digest.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// Store a downloaded preset in the local cache after verifying its checksum.
+pub fn store(
+    presets_dir: &Path,
+    preset: &RegistryPresetRef,
+    version: &str,
+    content: &str,
+    sha256: &str,
+) -> Result<PathBuf> {
+    let actual = sha256_hex(content.as_bytes());
+    if actual != sha256 {
+        return Err(VmError::Config(format!(
+            "Integrity check failed for {}: expected sha256 {sha256}, got {actual}",
+            preset.pinned(version)
+        )));
+    }
+
+    let dir = preset.cache_dir(presets_dir);
+    std::fs::create_dir_all(&dir)?;
+    let path = dir.join(format!("{version}.yaml"));
+    std::fs::write(&path, content)?;
+    std::fs::write(dir.join(format!("{version}.sha256")), &actual)?;
+    Ok(path)
+}
+
+/// Load a cached registry preset, verifying it against its recorded checksum.
+///
+/// Unpinned references resolve to the highest cached version.
+pub fn load_cached(presets_dir: &Path, preset: &RegistryPresetRef) -> Result<String> {
+    let dir = preset.cache_dir(presets_dir);
+    let version = match &preset.version {
+        Some(version) => version.clone(),
+        None => latest_cached_version(&dir).ok_or_else(|| not_cached(preset))?,
+    };
+
+    let path = dir.join(format!("{version}.yaml"));
+    let content = std::fs::read_to_string(&path).map_err(|_| not_cached(preset))?;
+    let expected = std::fs::read_to_string(dir.join(format!("{version}.sha256")))
+        .map_err(|_| not_cached(preset))?;
+
+    let actual = sha256_hex(content.as_bytes());
+    if actual != expected.trim() {
+        return Err(VmError::Config(format!(
+            "Cached preset {} failed its integrity check; re-fetch it with 'vm config preset --from {}'",
+            preset.pinned(&version),
+            preset.pinned(&version)
+        )));
+    }
+    Ok(content)
+}
+
+fn not_cached(preset: &RegistryPresetRef) -> VmError {
+    VmError::Config(format!(
+        "Preset {preset} is not cached locally; fetch it with 'vm config preset --from {preset}'"
+    ))
+}
+
+fn latest_cached_version(dir: &Path) -> Option<String> {
+    let entries = std::fs::read_dir(dir).ok()?;
+    entries
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            if path.extension()? != "yaml" {
+                return None;
+            }
+            Some(path.file_stem()?.to_string_lossy().into_owned())
+        })
+        .max_by(|a, b| version_key(a).cmp(&version_key(b)))
+}
+
+fn version_key(version: &str) -> Vec<u64> {
+    version
+        .split(['.', '-', '+'])
+        .map(|part| part.parse().unwrap_or(0))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn parses_registry_references() {
+        assert_eq!(RegistryPresetRef::parse("rails").unwrap(), None);
+
+        let preset = RegistryPresetRef::parse("registry:acme/rails@1.2.0")
+            .unwrap()
+            .unwrap();
+        assert_eq!(preset.path(), "acme/rails");
+        assert_eq!(preset.version.as_deref(), Some("1.2.0"));
+        assert_eq!(preset.to_string(), "registry:acme/rails@1.2.0");
+
+        assert!(RegistryPresetRef::parse("registry:rails").is_err());
+        assert!(RegistryPresetRef::parse("registry:acme/../etc").is_err());
+    }
+
+    #[test]
+    fn cache_round_trip_verifies_checksum() {
+        let temp = TempDir::new().unwrap();
+        let preset = RegistryPresetRef::parse("registry:acme/rails")
+            .unwrap()
+            .unwrap();
+        let content = "preset:\n  name: rails\n  description: Rails\n";
+        let sha = sha256_hex(content.as_bytes());
+
+        assert!(store(temp.path(), &preset, "1.0.0", content, "bad").is_err());
+        store(temp.path(), &preset, "1.0.0", content, &sha).unwrap();
+        store(temp.path(), &preset, "1.10.0", content, &sha).unwrap();
+        assert_eq!(
+            latest_cached_version(&preset.cache_dir(temp.path())).as_deref(),
+            Some("1.10.0")
+        );
+        assert_eq!(load_cached(temp.path(), &preset).unwrap(), content);
+
+        let pinned = preset.pinned("1.0.0");
+        let path = pinned.cache_dir(temp.path()).join("1.0.0.yaml");
+        std::fs::write(path, "tampered").unwrap();
+        assert!(load_cached(temp.path(), &pinned).is_err());
+    }
+}
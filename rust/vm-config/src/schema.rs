@@ -118,7 +118,9 @@ fn add_vm_schema_fields(cache: &mut HashMap<String, SchemaType>) {
         "vm.user",
         "vm.port_binding",
         "vm.timezone",
-        "vm.swap"
+        "vm.swap",
+        "vm.provisioner",
+        "vm.restart_policy"
     );
     add_booleans!(cache, "vm.gui");
     add_integers!(cache, "vm.swappiness");
@@ -174,6 +176,9 @@ fn add_service_schema_fields(cache: &mut HashMap<String, SchemaType>) {
     // Simple boolean services (gpu/audio/video passthrough)
     add_booleans!(cache, "services.gpu", "services.audio", "services.video");
 
+    // Device passthrough
+    add_booleans!(cache, "devices.audio", "devices.webcam");
+
     // Service-specific fields
     add_booleans!(cache, "services.docker.buildx");
     add_strings!(
@@ -181,6 +186,11 @@ fn add_service_schema_fields(cache: &mut HashMap<String, SchemaType>) {
         "services.headless_browser.display",
         "services.headless_browser.executable_path"
     );
+
+    // Database settings
+    add_strings!(cache, "db.backup.schedule", "db.default_fixture");
+    add_integers!(cache, "db.backup.keep");
+    add_string_arrays!(cache, "db.backup.databases");
 }
 
 /// Add terminal, package, and host sync schema fields
@@ -200,7 +210,8 @@ fn add_terminal_and_package_fields(cache: &mut HashMap<String, SchemaType>) {
         "apt_packages",
         "npm_packages",
         "cargo_packages",
-        "pip_packages"
+        "pip_packages",
+        "features"
     );
 
     // Object types
@@ -217,16 +228,35 @@ fn add_terminal_and_package_fields(cache: &mut HashMap<String, SchemaType>) {
         "host_sync.package_links.npm",
         "host_sync.package_links.pip",
         "host_sync.package_links.cargo",
-        "host_sync.worktrees.enabled"
+        "host_sync.worktrees.enabled",
+        "host_sync.locale",
+        "host_sync.keyboard",
+        "host_sync.etc_hosts"
+    );
+    add_strings!(
+        cache,
+        "host_sync.worktrees.base_path",
+        "host_sync.line_endings"
+    );
+    add_string_arrays!(
+        cache,
+        "host_sync.dotfiles",
+        "host_sync.extra_hosts",
+        "networking.networks",
+        "networking.dns",
+        "networking.extra_hosts",
+        "devices.usb"
     );
-    add_strings!(cache, "host_sync.worktrees.base_path");
-    add_string_arrays!(cache, "host_sync.dotfiles", "networking.networks");
 }
 
 /// Build the global schema cache
 fn build_global_schema_cache() -> HashMap<String, SchemaType> {
     let mut cache = HashMap::new();
 
+    add_booleans!(cache, "offline");
+    add_strings!(cache, "proxy.http", "proxy.https", "proxy.ca_bundle");
+    add_string_arrays!(cache, "proxy.no_proxy");
+
     // Docker registry service
     add_booleans!(
         cache,
@@ -240,7 +270,8 @@ fn build_global_schema_cache() -> HashMap<String, SchemaType> {
         "services.docker_registry.max_cache_size_gb",
         "services.docker_registry.max_image_age_days",
         "services.docker_registry.cleanup_interval_hours",
-        "services.docker_registry.health_check_interval_minutes"
+        "services.docker_registry.health_check_interval_minutes",
+        "services.docker_registry.max_download_kbps"
     );
 
     // Auth proxy service
@@ -256,7 +287,8 @@ fn build_global_schema_cache() -> HashMap<String, SchemaType> {
     add_integers!(
         cache,
         "services.package_registry.port",
-        "services.package_registry.max_storage_gb"
+        "services.package_registry.max_storage_gb",
+        "services.package_registry.max_download_kbps"
     );
 
     // Defaults
@@ -282,6 +314,11 @@ fn build_global_schema_cache() -> HashMap<String, SchemaType> {
     add_booleans!(cache, "worktrees.enabled");
     add_strings!(cache, "worktrees.base_path");
 
+    // Service log archive
+    add_booleans!(cache, "logs.archive");
+    add_strings!(cache, "logs.path");
+    add_integers!(cache, "logs.max_size_mb", "logs.keep_count");
+
     cache
 }
 
@@ -536,4 +536,30 @@ tart:
         // Limited just returns the value
         assert_eq!(limited.resolve_percentage(200), Some(40));
     }
+
+    // ===== Resource Mode Tests =====
+
+    #[test]
+    fn test_resource_modes_fall_back_to_defaults() {
+        let yaml = r#"
+vm:
+  memory: 8192
+  cpus: 4
+  modes:
+    background:
+      memory: "25%"
+"#;
+        let config: VmConfig = serde_yaml::from_str(yaml).unwrap();
+        let vm = config.vm.unwrap();
+
+        // focus inherits the top-level vm limits
+        let focus = vm.resource_mode("focus");
+        assert_eq!(focus.memory, Some(MemoryLimit::Limited(8192)));
+        assert_eq!(focus.cpus, Some(CpuLimit::Limited(4)));
+
+        // background keeps configured fields and defaults the rest
+        let background = vm.resource_mode("background");
+        assert_eq!(background.memory, Some(MemoryLimit::Percentage(25)));
+        assert_eq!(background.cpus, Some(CpuLimit::Limited(1)));
+    }
 }
@@ -1,6 +1,9 @@
-use crate::config::{BoxSpec, VmConfig};
+use crate::config::{
+    is_container_sysctl, is_host_sysctl, BoxSpec, UsbDevice, VmConfig, CONTAINER_SYSCTL_PREFIXES,
+    HOST_SYSCTLS, PROVISIONERS, ULIMIT_NAMES,
+};
 use std::collections::HashSet;
-use std::net::TcpListener;
+use std::net::{IpAddr, TcpListener};
 use std::path::PathBuf;
 use tracing::warn;
 use vm_core::error::{Result, VmError};
@@ -20,6 +23,7 @@ pub fn validate_box_spec(config: &VmConfig, provider: &str) -> Vec<String> {
     match provider {
         "docker" | "podman" => validate_docker_box_spec(&box_spec, &mut errors),
         "tart" => validate_tart_box_spec(&box_spec, &mut errors),
+        "libvirt" => validate_libvirt_box_spec(&box_spec, &mut errors),
         _ => {}
     }
 
@@ -41,6 +45,18 @@ fn validate_tart_box_spec(box_spec: &BoxSpec, errors: &mut Vec<String>) {
     }
 }
 
+fn validate_libvirt_box_spec(box_spec: &BoxSpec, errors: &mut Vec<String>) {
+    match box_spec {
+        BoxSpec::Build { .. } => {
+            errors.push("libvirt does not support Dockerfile builds".to_string());
+        }
+        BoxSpec::String(s) if s.starts_with('@') => {
+            errors.push("libvirt boxes are cloud images; snapshots are not supported".to_string());
+        }
+        BoxSpec::String(_) => {}
+    }
+}
+
 /// Checks if a given host port is available to bind to.
 fn check_port_available(port: u16, binding: &str) -> Result<()> {
     let addr = format!("{binding}:{port}");
@@ -88,6 +104,9 @@ impl ConfigValidator {
         self.validate_services()?;
         self.validate_versions()?;
         self.validate_networking()?;
+        self.validate_kernel_settings()?;
+        self.validate_devices()?;
+        self.validate_provisioner()?;
         Ok(())
     }
 
@@ -116,7 +135,7 @@ impl ConfigValidator {
     fn validate_provider(&self) -> Result<()> {
         if let Some(provider) = &self.config.provider {
             match provider.as_str() {
-                "docker" | "podman" | "tart" => Ok(()),
+                "docker" | "podman" | "tart" | "libvirt" => Ok(()),
                 _ => Err(vm_core::error::VmError::Config(format!(
                     "Invalid provider: {provider}"
                 ))),
@@ -361,7 +380,120 @@ impl ConfigValidator {
                     )));
                 }
             }
+
+            for server in &networking.dns {
+                if server.parse::<IpAddr>().is_err() {
+                    return Err(VmError::Config(format!(
+                        "Invalid DNS server '{server}': expected an IPv4 or IPv6 address"
+                    )));
+                }
+            }
+
+            for entry in &networking.extra_hosts {
+                if !Self::is_valid_host_entry(entry) {
+                    return Err(VmError::Config(format!(
+                        "Invalid extra_hosts entry '{entry}': expected hostname:ip, e.g. api.corp.internal:10.0.0.5"
+                    )));
+                }
+            }
+        }
+        if let Some(host_sync) = &self.config.host_sync {
+            for entry in &host_sync.extra_hosts {
+                if !Self::is_valid_host_entry(entry) {
+                    return Err(VmError::Config(format!(
+                        "Invalid host_sync.extra_hosts entry '{entry}': expected hostname:ip, e.g. api.corp.internal:10.0.0.5"
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether `entry` is a `hostname:ip` pair as accepted by compose `extra_hosts`
+    fn is_valid_host_entry(entry: &str) -> bool {
+        let Some((hostname, ip)) = entry.split_once(':') else {
+            return false;
+        };
+        let ip = ip.trim_start_matches('[').trim_end_matches(']');
+        let hostname_valid = !hostname.is_empty()
+            && hostname.len() <= 253
+            && hostname.split('.').all(|label| {
+                !label.is_empty()
+                    && !label.starts_with('-')
+                    && !label.ends_with('-')
+                    && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+            });
+        hostname_valid && (ip == "host-gateway" || ip.parse::<IpAddr>().is_ok())
+    }
+
+    fn validate_devices(&self) -> Result<()> {
+        let Some(devices) = &self.config.devices else {
+            return Ok(());
+        };
+        for spec in &devices.usb {
+            if UsbDevice::parse(spec).is_none() {
+                return Err(VmError::Config(format!(
+                    "Invalid USB device '{spec}': expected a vendor:product ID like 046d:0825 or a /dev/bus/usb path"
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    fn validate_provisioner(&self) -> Result<()> {
+        let Some(provisioner) = self
+            .config
+            .vm
+            .as_ref()
+            .and_then(|vm| vm.provisioner.as_deref())
+        else {
+            return Ok(());
+        };
+        if !PROVISIONERS.contains(&provisioner) {
+            return Err(VmError::Config(format!(
+                "Unknown vm.provisioner '{provisioner}': expected one of {}",
+                PROVISIONERS.join(", ")
+            )));
+        }
+        Ok(())
+    }
+
+    fn validate_kernel_settings(&self) -> Result<()> {
+        let Some(vm) = &self.config.vm else {
+            return Ok(());
+        };
+
+        for key in vm.sysctls.keys() {
+            if !is_container_sysctl(key) && !is_host_sysctl(key) {
+                return Err(VmError::Config(format!(
+                    "Unsupported sysctl '{key}': containers can only set namespaced parameters ({}*) or the host minimum {}",
+                    CONTAINER_SYSCTL_PREFIXES.join("*, "),
+                    HOST_SYSCTLS.join(", ")
+                )));
+            }
+        }
+
+        for (name, limit) in &vm.ulimits {
+            if !ULIMIT_NAMES.contains(&name.as_str()) {
+                return Err(VmError::Config(format!(
+                    "Unknown ulimit '{name}': expected one of {}",
+                    ULIMIT_NAMES.join(", ")
+                )));
+            }
+            let (soft, hard) = limit.limits();
+            if soft < -1 || hard < -1 {
+                return Err(VmError::Config(format!(
+                    "Invalid ulimit '{name}': limits must be -1 (unlimited) or positive"
+                )));
+            }
+            // -1 is unlimited, so it can only be the soft limit if the hard one is too
+            if hard != -1 && (soft == -1 || soft > hard) {
+                return Err(VmError::Config(format!(
+                    "Invalid ulimit '{name}': soft limit {soft} exceeds hard limit {hard}"
+                )));
+            }
         }
+
         Ok(())
     }
 }
@@ -403,6 +535,31 @@ mod tests {
         assert!(validator.validate().is_err());
     }
 
+    #[test]
+    fn test_provisioner_values() {
+        let config_with = |provisioner: &str| VmConfig {
+            provider: Some("docker".to_string()),
+            project: Some(crate::config::ProjectConfig {
+                name: Some("test".to_string()),
+                ..Default::default()
+            }),
+            vm: Some(crate::config::VmSettings {
+                provisioner: Some(provisioner.to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let validate = |provisioner: &str| {
+            ConfigValidator::new(config_with(provisioner), PathBuf::from("test.yaml"), true)
+                .validate()
+        };
+
+        assert!(validate("ansible").is_ok());
+        assert!(validate("native").is_ok());
+        let error = validate("chef").unwrap_err().to_string();
+        assert!(error.contains("vm.provisioner 'chef'"));
+    }
+
     #[test]
     fn test_invalid_port_range() {
         let mut config = VmConfig::default();
@@ -416,4 +573,131 @@ mod tests {
         let validator = ConfigValidator::new(config, std::path::PathBuf::from("test.yaml"), false);
         assert!(validator.validate().is_err());
     }
+
+    #[test]
+    fn test_kernel_settings() {
+        let mut config = VmConfig::default();
+        config.provider = Some("docker".to_string());
+        config.project = Some(crate::config::ProjectConfig {
+            name: Some("test".to_string()),
+            ..Default::default()
+        });
+        let vm_yaml = r#"
+sysctls:
+  net.core.somaxconn: 1024
+  vm.max_map_count: 262144
+ulimits:
+  nofile: { soft: 1024, hard: 65536 }
+  nproc: 4096
+"#;
+        config.vm = Some(serde_yaml_ng::from_str(vm_yaml).unwrap());
+        let validator =
+            ConfigValidator::new(config.clone(), std::path::PathBuf::from("test.yaml"), false);
+        assert!(validator.validate().is_ok());
+
+        let mut bad_sysctl = config.clone();
+        if let Some(vm) = bad_sysctl.vm.as_mut() {
+            vm.sysctls.insert(
+                "kernel.panic".to_string(),
+                crate::config::SysctlValue::Number(1),
+            );
+        }
+        let validator =
+            ConfigValidator::new(bad_sysctl, std::path::PathBuf::from("test.yaml"), false);
+        assert!(validator.validate().is_err());
+
+        let mut bad_ulimit = config;
+        if let Some(vm) = bad_ulimit.vm.as_mut() {
+            vm.ulimits.insert(
+                "nofile".to_string(),
+                crate::config::Ulimit::Range {
+                    soft: 70000,
+                    hard: 65536,
+                },
+            );
+        }
+        let validator =
+            ConfigValidator::new(bad_ulimit, std::path::PathBuf::from("test.yaml"), false);
+        assert!(validator.validate().is_err());
+    }
+
+    #[test]
+    fn test_networking_dns_and_extra_hosts() {
+        let mut config = VmConfig::default();
+        config.provider = Some("docker".to_string());
+        config.project = Some(crate::config::ProjectConfig {
+            name: Some("test".to_string()),
+            ..Default::default()
+        });
+        config.networking = Some(crate::config::NetworkingConfig {
+            dns: vec!["10.0.0.2".to_string(), "fd00::53".to_string()],
+            extra_hosts: vec![
+                "git.corp.internal:10.1.2.3".to_string(),
+                "proxy.local:host-gateway".to_string(),
+                "v6.local:[fd00::1]".to_string(),
+            ],
+            ..Default::default()
+        });
+        let validator =
+            ConfigValidator::new(config.clone(), std::path::PathBuf::from("test.yaml"), false);
+        assert!(validator.validate().is_ok());
+
+        for (dns, host) in [
+            ("dns.corp.internal", "ok.local:10.0.0.1"),
+            ("10.0.0.2", "no-ip.local"),
+            ("10.0.0.2", "-bad.local:10.0.0.1"),
+            ("10.0.0.2", "ok.local:not-an-ip"),
+        ] {
+            let mut bad = config.clone();
+            bad.networking = Some(crate::config::NetworkingConfig {
+                dns: vec![dns.to_string()],
+                extra_hosts: vec![host.to_string()],
+                ..Default::default()
+            });
+            let validator = ConfigValidator::new(bad, std::path::PathBuf::from("test.yaml"), false);
+            assert!(validator.validate().is_err(), "{dns} / {host}");
+        }
+
+        let mut bad = config.clone();
+        bad.host_sync = Some(crate::config::HostSyncConfig {
+            extra_hosts: vec!["no-ip.local".to_string()],
+            ..Default::default()
+        });
+        let validator = ConfigValidator::new(bad, std::path::PathBuf::from("test.yaml"), false);
+        assert!(validator.validate().is_err());
+    }
+
+    #[test]
+    fn test_usb_device_specs() {
+        assert_eq!(
+            UsbDevice::parse("046d:0825"),
+            Some(UsbDevice::Id {
+                vendor: 0x046d,
+                product: 0x0825
+            })
+        );
+        assert!(UsbDevice::parse("/dev/bus/usb/001/004").is_some());
+        assert!(UsbDevice::parse("46d:825").is_none());
+
+        let mut config = VmConfig::default();
+        config.provider = Some("docker".to_string());
+        config.project = Some(crate::config::ProjectConfig {
+            name: Some("test".to_string()),
+            ..Default::default()
+        });
+        config.services.insert(
+            "video".to_string(),
+            crate::config::ServiceConfig {
+                enabled: true,
+                ..Default::default()
+            },
+        );
+        config.devices = Some(crate::config::DevicesConfig {
+            usb: vec!["logitech".to_string()],
+            ..Default::default()
+        });
+        assert!(config.requested_devices().webcam);
+        let validator = ConfigValidator::new(config, std::path::PathBuf::from("test.yaml"), false);
+        assert!(validator.validate().is_err());
+    }
 }
@@ -17,6 +17,7 @@ const FIELD_ORDER: &[&str] = &[
     "provider",
     "os",
     "tart",
+    "libvirt",
     // 3. Project Identity
     "project",
     // 4. VM Resources
@@ -27,11 +28,13 @@ const FIELD_ORDER: &[&str] = &[
     "ports",
     // 7. Services & Infrastructure
     "services",
+    "devices",
     // 8. Package Management
     "apt_packages",
     "npm_packages",
     "pip_packages",
     "cargo_packages",
+    "features",
     // 9. Development Environment
     "terminal",
     "aliases",
@@ -98,7 +101,7 @@ fn format_field_value(field_name: &str, value: &Value) -> Result<Value> {
 fn should_format_nested(field_name: &str) -> bool {
     matches!(
         field_name,
-        "project" | "vm" | "versions" | "services" | "terminal" | "security" | "tart"
+        "project" | "vm" | "versions" | "services" | "terminal" | "security" | "tart" | "libvirt"
     )
 }
 
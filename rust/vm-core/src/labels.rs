@@ -0,0 +1,116 @@
+//! Labels applied to every container, volume and network the VM tool creates.
+//!
+//! Providers, snapshot restore and the usage monitor all identify vm-managed
+//! resources through these keys, so they live here rather than in any one crate.
+
+/// Marks a resource as created (or adopted) by the VM tool
+pub const MANAGED: &str = "com.vm.managed";
+/// Project the resource belongs to
+pub const PROJECT: &str = "com.vm.project";
+/// Instance name within the project (`default` for the primary instance)
+pub const INSTANCE: &str = "com.vm.instance";
+/// Hash of the configuration the resource was created from
+pub const CONFIG_HASH: &str = "com.vm.config-hash";
+
+/// Set on containers created with `vm create --ephemeral`
+pub const EPHEMERAL: &str = "com.vm.ephemeral";
+
+/// Prefix of the labels describing published ports: `com.vm.port.<host port>`
+pub const PORT_PREFIX: &str = "com.vm.port.";
+
+/// Instance label value used when no instance name was given
+pub const DEFAULT_INSTANCE: &str = "default";
+
+/// Full set of labels for a resource, in a stable order
+pub fn resource_labels(project: &str, instance: &str, config_hash: &str) -> Vec<(String, String)> {
+    vec![
+        (MANAGED.to_string(), "true".to_string()),
+        (PROJECT.to_string(), project.to_string()),
+        (INSTANCE.to_string(), instance.to_string()),
+        (CONFIG_HASH.to_string(), config_hash.to_string()),
+    ]
+}
+
+/// `--label key=value` arguments for `docker`/`podman` create commands
+pub fn label_args(project: &str, instance: &str, config_hash: &str) -> Vec<String> {
+    resource_labels(project, instance, config_hash)
+        .into_iter()
+        .flat_map(|(key, value)| ["--label".to_string(), format!("{key}={value}")])
+        .collect()
+}
+
+/// What a published port is for, as recorded in a `com.vm.port.<host port>` label
+/// with the value `<name>:<guest port>[/udp]`, e.g. `com.vm.port.3100=web:3000`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PortLabel {
+    pub host: u16,
+    pub guest: u16,
+    pub udp: bool,
+    /// Purpose (`web`, `api`, `db`, ...) or service name (`postgresql`)
+    pub name: String,
+}
+
+impl PortLabel {
+    pub fn key(&self) -> String {
+        format!("{PORT_PREFIX}{}", self.host)
+    }
+
+    pub fn value(&self) -> String {
+        let protocol = if self.udp { "/udp" } else { "" };
+        format!("{}:{}{protocol}", self.name, self.guest)
+    }
+
+    /// Parse a label; returns `None` for labels that aren't port labels
+    pub fn parse(key: &str, value: &str) -> Option<Self> {
+        let host = key.strip_prefix(PORT_PREFIX)?.parse().ok()?;
+        let (name, guest) = value.rsplit_once(':')?;
+        let (guest, udp) = match guest.strip_suffix("/udp") {
+            Some(guest) => (guest, true),
+            None => (guest, false),
+        };
+        Some(Self {
+            host,
+            guest: guest.parse().ok()?,
+            udp,
+            name: name.to_string(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn label_args_cover_every_key() {
+        let args = label_args("myapp", "default", "abc123");
+        assert_eq!(
+            args,
+            vec![
+                "--label",
+                "com.vm.managed=true",
+                "--label",
+                "com.vm.project=myapp",
+                "--label",
+                "com.vm.instance=default",
+                "--label",
+                "com.vm.config-hash=abc123",
+            ]
+        );
+    }
+
+    #[test]
+    fn port_labels_round_trip() {
+        let label = PortLabel {
+            host: 3100,
+            guest: 53,
+            udp: true,
+            name: "dns".to_string(),
+        };
+        assert_eq!(label.key(), "com.vm.port.3100");
+        assert_eq!(label.value(), "dns:53/udp");
+        assert_eq!(PortLabel::parse(&label.key(), &label.value()), Some(label));
+        assert_eq!(PortLabel::parse(CONFIG_HASH, "abc123"), None);
+        assert_eq!(PortLabel::parse("com.vm.port.3100", "web"), None);
+    }
+}
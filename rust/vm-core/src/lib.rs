@@ -1,7 +1,10 @@
 pub mod command_stream;
 pub mod error;
 pub mod file_system;
+pub mod labels;
 pub mod message;
+pub mod network;
+pub mod offline;
 pub mod output_macros;
 pub mod project;
 pub mod secrets;
@@ -0,0 +1,207 @@
+//! Proxy and corporate CA settings for outbound HTTP
+//!
+//! Proxies use the standard `HTTP_PROXY`, `HTTPS_PROXY` and `NO_PROXY`
+//! variables (either case). An extra CA bundle for TLS-intercepting proxies is
+//! read from `VM_CA_BUNDLE`, a PEM file whose certificates are trusted in
+//! addition to the built-in roots.
+//!
+//! The `vm` CLI exports these from the `proxy` section of the global config, so
+//! in-process services, child processes and the clients built here all agree.
+//! Every HTTP client should come from [`client_builder`] or
+//! [`blocking_client_builder`] rather than `reqwest::Client::new()`.
+
+use crate::error::{Result, VmError};
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+/// Environment variable pointing to an extra PEM CA bundle
+pub const CA_BUNDLE_ENV: &str = "VM_CA_BUNDLE";
+
+/// Where distributions keep the system CA bundle
+const SYSTEM_CA_BUNDLES: [&str; 4] = [
+    "/etc/ssl/certs/ca-certificates.crt",
+    "/etc/pki/tls/certs/ca-bundle.crt",
+    "/etc/ssl/cert.pem",
+    "/usr/local/etc/openssl/cert.pem",
+];
+
+/// Hosts that always bypass the proxy, since they are vm's own services
+const LOCAL_HOSTS: [&str; 3] = ["localhost", "127.0.0.1", "::1"];
+
+/// Proxy settings to export to this process and its children
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProxySettings {
+    pub http: Option<String>,
+    pub https: Option<String>,
+    pub no_proxy: Vec<String>,
+    /// Path to the extra CA bundle; a leading `~/` is expanded
+    pub ca_bundle: Option<String>,
+}
+
+/// Export proxy settings as environment variables.
+///
+/// Values already present in the environment win, so a one-off
+/// `HTTPS_PROXY=... vm ...` still overrides the global config.
+pub fn apply(settings: &ProxySettings) {
+    let set_both = |upper: &str, value: &str| {
+        if env_var(upper).is_none() {
+            std::env::set_var(upper, value);
+            std::env::set_var(upper.to_ascii_lowercase(), value);
+        }
+    };
+    if let Some(http) = &settings.http {
+        set_both("HTTP_PROXY", http);
+    }
+    if let Some(https) = &settings.https {
+        set_both("HTTPS_PROXY", https);
+    }
+    if env_var("HTTP_PROXY").is_some() || env_var("HTTPS_PROXY").is_some() {
+        let no_proxy = merge_no_proxy(env_var("NO_PROXY").as_deref(), &settings.no_proxy);
+        std::env::set_var("NO_PROXY", &no_proxy);
+        std::env::set_var("no_proxy", &no_proxy);
+    }
+    if let Some(bundle) = &settings.ca_bundle {
+        if std::env::var_os(CA_BUNDLE_ENV).is_none() {
+            let path = match (bundle.strip_prefix("~/"), crate::user_paths::home_dir()) {
+                (Some(rest), Ok(home)) => home.join(rest),
+                _ => PathBuf::from(bundle),
+            };
+            std::env::set_var(CA_BUNDLE_ENV, path);
+        }
+    }
+}
+
+/// Read a variable in upper or lower case, ignoring empty values
+fn env_var(upper: &str) -> Option<String> {
+    std::env::var(upper)
+        .or_else(|_| std::env::var(upper.to_ascii_lowercase()))
+        .ok()
+        .filter(|value| !value.trim().is_empty())
+}
+
+fn merge_no_proxy(existing: Option<&str>, extra: &[String]) -> String {
+    let mut hosts: Vec<String> = Vec::new();
+    let candidates = existing
+        .unwrap_or_default()
+        .split(',')
+        .map(str::to_string)
+        .chain(extra.iter().cloned())
+        .chain(LOCAL_HOSTS.iter().map(|host| host.to_string()));
+    for host in candidates {
+        let host = host.trim();
+        if !host.is_empty() && !hosts.iter().any(|h| h == host) {
+            hosts.push(host.to_string());
+        }
+    }
+    hosts.join(",")
+}
+
+/// Proxy environment to pass on to VMs and other child environments
+pub fn proxy_env() -> Vec<(String, String)> {
+    let mut vars = Vec::new();
+    for name in ["HTTP_PROXY", "HTTPS_PROXY", "NO_PROXY"] {
+        if let Some(value) = env_var(name) {
+            vars.push((name.to_string(), value.clone()));
+            vars.push((name.to_ascii_lowercase(), value));
+        }
+    }
+    vars
+}
+
+/// The configured extra CA bundle, if any
+pub fn ca_bundle_path() -> Option<PathBuf> {
+    std::env::var_os(CA_BUNDLE_ENV)
+        .filter(|value| !value.is_empty())
+        .map(PathBuf::from)
+}
+
+/// Write the system roots together with the extra CA bundle to `dir/ca-bundle.pem`.
+///
+/// curl reads the proxy variables itself, but `--cacert` replaces its default
+/// store, so it needs both sets of roots in one file. Returns `None` when no
+/// extra bundle is configured.
+pub fn write_combined_ca_bundle(dir: &Path) -> Result<Option<PathBuf>> {
+    let Some(extra) = ca_bundle_path() else {
+        return Ok(None);
+    };
+    let mut bundle = SYSTEM_CA_BUNDLES
+        .iter()
+        .find_map(|path| std::fs::read(path).ok())
+        .unwrap_or_default();
+    bundle.push(b'\n');
+    bundle.extend(
+        std::fs::read(&extra)
+            .map_err(|e| VmError::filesystem(e, extra.display(), "read CA bundle"))?,
+    );
+    let path = dir.join("ca-bundle.pem");
+    std::fs::write(&path, bundle)?;
+    Ok(Some(path))
+}
+
+/// Load the certificates in the extra CA bundle.
+pub fn extra_root_certificates() -> Result<Vec<reqwest::Certificate>> {
+    let Some(path) = ca_bundle_path() else {
+        return Ok(Vec::new());
+    };
+    let pem = std::fs::read(&path).map_err(|e| {
+        VmError::Network(format!(
+            "Failed to read CA bundle {} (from {CA_BUNDLE_ENV}): {e}",
+            path.display()
+        ))
+    })?;
+    reqwest::Certificate::from_pem_bundle(&pem).map_err(|e| {
+        VmError::Network(format!(
+            "CA bundle {} is not a valid PEM file: {e}",
+            path.display()
+        ))
+    })
+}
+
+fn root_certificates_or_warn() -> Vec<reqwest::Certificate> {
+    extra_root_certificates().unwrap_or_else(|e| {
+        warn!("{e}; continuing with the built-in CA roots only");
+        Vec::new()
+    })
+}
+
+/// An async HTTP client builder that trusts the extra CA bundle.
+///
+/// reqwest picks up the proxy variables itself.
+pub fn client_builder() -> reqwest::ClientBuilder {
+    root_certificates_or_warn()
+        .into_iter()
+        .fold(reqwest::Client::builder(), |builder, cert| {
+            builder.add_root_certificate(cert)
+        })
+}
+
+/// A blocking HTTP client builder that trusts the extra CA bundle.
+pub fn blocking_client_builder() -> reqwest::blocking::ClientBuilder {
+    root_certificates_or_warn()
+        .into_iter()
+        .fold(reqwest::blocking::Client::builder(), |builder, cert| {
+            builder.add_root_certificate(cert)
+        })
+}
+
+/// An async HTTP client with default settings plus the extra CA bundle.
+pub fn client() -> reqwest::Client {
+    client_builder().build().unwrap_or_else(|e| {
+        warn!("Failed to build HTTP client: {e}; using defaults");
+        reqwest::Client::new()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_no_proxy_always_includes_local_hosts() {
+        assert_eq!(
+            merge_no_proxy(Some("corp.internal, localhost"), &["10.0.0.0/8".into()]),
+            "corp.internal,localhost,10.0.0.0/8,127.0.0.1,::1"
+        );
+        assert_eq!(merge_no_proxy(None, &[]), "localhost,127.0.0.1,::1");
+    }
+}
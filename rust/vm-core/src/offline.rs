@@ -0,0 +1,65 @@
+//! Air-gapped operation
+//!
+//! When offline mode is on, code that would reach the network (package server
+//! upstreams, the Docker Hub fallback, update checks, registry preset fetches)
+//! fails immediately with an "offline mode" error instead of waiting for a
+//! connection to time out, and relies on what is already cached.
+//!
+//! The switch is the `VM_OFFLINE` environment variable. The `vm` CLI sets it
+//! from `offline: true` in the global config, so in-process services and child
+//! processes see the same setting.
+
+use crate::error::{Result, VmError};
+
+/// Environment variable that turns offline mode on
+pub const OFFLINE_ENV: &str = "VM_OFFLINE";
+
+/// Whether offline mode is enabled for this process.
+pub fn is_offline() -> bool {
+    std::env::var(OFFLINE_ENV).is_ok_and(|value| is_truthy(&value))
+}
+
+fn is_truthy(value: &str) -> bool {
+    matches!(
+        value.trim().to_ascii_lowercase().as_str(),
+        "1" | "true" | "yes" | "on"
+    )
+}
+
+/// Turn offline mode on for this process and any process it starts.
+pub fn enable() {
+    std::env::set_var(OFFLINE_ENV, "1");
+}
+
+/// Message explaining that `action` needs the network.
+pub fn offline_message(action: &str) -> String {
+    format!(
+        "Offline mode is enabled, so vm cannot {action}. Only cached data is available; set `offline: false` in the global config or unset {OFFLINE_ENV} to go online"
+    )
+}
+
+/// Fail with an offline mode error if offline mode is enabled.
+pub fn ensure_online(action: &str) -> Result<()> {
+    if is_offline() {
+        return Err(VmError::Network(offline_message(action)));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_truthy_values() {
+        for value in ["1", "true", "TRUE", " yes ", "on"] {
+            assert!(is_truthy(value), "{value:?} should enable offline mode");
+        }
+        for value in ["", "0", "false", "off", "no"] {
+            assert!(
+                !is_truthy(value),
+                "{value:?} should not enable offline mode"
+            );
+        }
+    }
+}
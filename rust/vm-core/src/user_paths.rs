@@ -178,6 +178,70 @@ pub fn temp_vms_state_path() -> Result<PathBuf> {
     Ok(new_path)
 }
 
+/// Get the shared state database path for the VM tool.
+///
+/// Returns: `~/.vm/state.db`
+#[must_use = "state database path should be used"]
+pub fn state_db_path() -> Result<PathBuf> {
+    Ok(vm_state_dir()?.join("state.db"))
+}
+
+/// Get the provisioning step timings path for the VM tool.
+///
+/// Returns: `~/.vm/provision-timings.json`
+#[must_use = "provision timings path should be used"]
+pub fn provision_timings_path() -> Result<PathBuf> {
+    Ok(vm_state_dir()?.join("provision-timings.json"))
+}
+
+/// Get the per-project resource usage history path for the VM tool.
+///
+/// Returns: `~/.vm/usage.json`
+#[must_use = "usage history path should be used"]
+pub fn usage_history_path() -> Result<PathBuf> {
+    Ok(vm_state_dir()?.join("usage.json"))
+}
+
+/// Get the environment override file for one instance of a project.
+///
+/// Returns: `~/.vm/instance-env/<project>/<instance>.env`
+#[must_use = "instance env path should be used"]
+pub fn instance_env_path(project: &str, instance: &str) -> Result<PathBuf> {
+    Ok(vm_state_dir()?
+        .join("instance-env")
+        .join(project)
+        .join(format!("{instance}.env")))
+}
+
+/// Get the persistent directory for a project's generated provider files.
+///
+/// `root_hash` identifies the project's directory, so two checkouts that share a
+/// project name don't overwrite each other's files.
+///
+/// Returns: `~/.vm/projects/<project>-<root_hash>`
+#[must_use = "project generated directory should be used"]
+pub fn project_generated_dir(project: &str, root_hash: &str) -> Result<PathBuf> {
+    Ok(vm_state_dir()?
+        .join("projects")
+        .join(format!("{project}-{root_hash}")))
+}
+
+/// Get the path of the registry of containers adopted with `vm adopt`.
+///
+/// Returns: `~/.vm/adopted.json`
+#[must_use = "adopted containers path should be used"]
+pub fn adopted_containers_path() -> Result<PathBuf> {
+    Ok(vm_state_dir()?.join("adopted.json"))
+}
+
+/// Get the crash history path used by the restart watchdog.
+///
+/// Returns: `~/.vm/crashes.json`
+#[must_use = "crash history path should be used"]
+pub fn crash_history_path() -> Result<PathBuf> {
+    Ok(vm_state_dir()?.join("crashes.json"))
+}
+
 /// Get the user's home directory.
 ///
 /// This is a convenience wrapper that returns a Result with a proper error message.
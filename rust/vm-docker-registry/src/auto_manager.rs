@@ -1,8 +1,10 @@
 //! Auto-management for Docker registry cache
 //!
 //! This module provides intelligent, background management of the Docker registry cache
-//! including automatic cleanup, LRU eviction, and self-healing capabilities.
+//! including automatic cleanup, LRU eviction, and self-healing capabilities. Images
+//! protected by [`Pins`] are never evicted.
 
+use crate::pins::Pins;
 use crate::server::{check_registry_running, get_registry_status, start_registry};
 use crate::types::AutoConfig;
 use anyhow::{anyhow, Result};
@@ -163,11 +165,15 @@ impl AutoManager {
 
         // Get repository list and check image ages
         let repositories = self.get_repository_list().await?;
+        let pins = load_pins();
         let mut images_to_delete = Vec::new();
 
         for repo in repositories {
             let tags = self.get_repository_tags(&repo).await?;
-            for tag in tags {
+            for tag in tags
+                .into_iter()
+                .filter(|tag| !pins.is_protected(&repo, tag))
+            {
                 let manifest_info = match self.get_image_manifest_info(&repo, &tag).await {
                     Ok(info) => info,
                     Err(_) => continue,
@@ -205,11 +211,15 @@ impl AutoManager {
 
         // Get repository list and check image ages
         let repositories = self.get_repository_list().await?;
+        let pins = load_pins();
         let mut deleted_count = 0;
 
         for repo in repositories {
             let tags = self.get_repository_tags(&repo).await?;
-            for tag in tags {
+            for tag in tags
+                .into_iter()
+                .filter(|tag| !pins.is_protected(&repo, tag))
+            {
                 let manifest_info = match self.get_image_manifest_info(&repo, &tag).await {
                     Ok(info) => info,
                     Err(_) => continue,
@@ -338,7 +348,9 @@ impl AutoManager {
             crate::DEFAULT_REGISTRY_PORT
         );
 
-        let response = reqwest::get(&url)
+        let response = vm_core::network::client()
+            .get(&url)
+            .send()
             .await
             .map_err(|e| anyhow!("Failed to query registry catalog: {e}"))?;
 
@@ -370,7 +382,9 @@ impl AutoManager {
             repository
         );
 
-        let response = reqwest::get(&url)
+        let response = vm_core::network::client()
+            .get(&url)
+            .send()
             .await
             .map_err(|e| anyhow!("Failed to query repository tags: {e}"))?;
 
@@ -407,7 +421,7 @@ impl AutoManager {
             tag
         );
 
-        let client = reqwest::Client::new();
+        let client = vm_core::network::client();
         let response = client
             .get(&url)
             .header(
@@ -476,7 +490,7 @@ impl AutoManager {
             tag
         );
 
-        let client = reqwest::Client::new();
+        let client = vm_core::network::client();
         let response = client
             .head(&url)
             .header(
@@ -525,6 +539,14 @@ impl Default for AutoManager {
     }
 }
 
+/// Load the pins, protecting nothing extra if they can't be read
+fn load_pins() -> Pins {
+    Pins::load().unwrap_or_else(|e| {
+        warn!("Failed to load registry pins: {}", e);
+        Pins::default()
+    })
+}
+
 /// Status of the auto-manager
 #[derive(Debug, Clone)]
 pub struct AutoManagerStatus {
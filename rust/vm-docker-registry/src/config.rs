@@ -48,6 +48,18 @@ http {
         proxy_buffers 8 4k;
         proxy_max_temp_file_size 2048m;
 
+        # BuildKit layer caches pushed by vm create
+        location ^~ /v2/{{ cache_namespace }}/ {
+            proxy_cache off;
+            client_max_body_size 0;
+            proxy_request_buffering off;
+            proxy_pass http://buildcache:5000;
+            proxy_set_header Host $http_host;
+            proxy_set_header X-Real-IP $remote_addr;
+            proxy_set_header X-Forwarded-For $proxy_add_x_forwarded_for;
+            proxy_set_header X-Forwarded-Proto $scheme;
+        }
+
         # Registry v2 API
         location /v2/ {
             # Try local registry first
@@ -63,6 +75,18 @@ http {
 
         # Fallback to Docker Hub
         location @dockerhub {
+{%- if offline %}
+            # Offline mode: answer misses immediately instead of reaching Docker Hub
+            default_type application/json;
+            return 503 '{"errors":[{"code":"UNAVAILABLE","message":"offline mode is enabled; image is not in the local registry cache"}]}';
+        }
+{%- else %}
+{%- if max_download_kbps > 0 %}
+            # Throttle each Docker Hub download so warming the cache doesn't saturate
+            # the uplink; cache hits are served at full speed
+            limit_rate {{ max_download_kbps }}k;
+            proxy_limit_rate {{ max_download_kbps }}k;
+{%- endif %}
             proxy_pass https://registry-1.docker.io;
             proxy_set_header Host registry-1.docker.io;
             proxy_set_header X-Real-IP $remote_addr;
@@ -75,6 +99,7 @@ http {
             # Store successful responses in local registry
             # This is handled by the registry backend when images are pulled
         }
+{%- endif %}
 
         # Health check endpoint
         location /health {
@@ -98,6 +123,9 @@ http {
     let mut context = TeraContext::new();
     context.insert("backend_host", &config.host);
     context.insert("backend_port", &config.backend_port);
+    context.insert("cache_namespace", crate::BUILD_CACHE_NAMESPACE);
+    context.insert("offline", &config.offline);
+    context.insert("max_download_kbps", &config.max_download_kbps);
 
     tera.render_str(template, &context)
         .context("Failed to render nginx configuration")
@@ -137,6 +165,32 @@ proxy:
     Ok(config_yaml)
 }
 
+/// Generate configuration for the build cache registry.
+///
+/// Unlike the pull-through backend this registry accepts pushes, so BuildKit
+/// can export layer caches to it.
+pub fn generate_cache_registry_config(config: &RegistryConfig) -> Result<String> {
+    let config_yaml = format!(
+        r#"version: 0.1
+log:
+  level: {}
+storage:
+  filesystem:
+    rootdirectory: /var/lib/registry
+  cache:
+    blobdescriptor: inmemory
+  delete:
+    enabled: true
+http:
+  addr: :5000
+  relativeurls: true
+"#,
+        if config.debug { "debug" } else { "info" },
+    );
+
+    Ok(config_yaml)
+}
+
 /// Generate Docker Compose configuration for the registry
 pub fn generate_docker_compose_config(config: &RegistryConfig, data_dir: &str) -> Result<String> {
     let compose_yaml = format!(
@@ -156,6 +210,16 @@ services:
     networks:
       - registry-network
 
+  buildcache:
+    image: registry:2
+    container_name: vm-registry-cache
+    restart: unless-stopped
+    volumes:
+      - "{}/build-cache:/var/lib/registry"
+      - "./registry-cache-config.yml:/etc/docker/registry/config.yml"
+    networks:
+      - registry-network
+
   proxy:
     image: nginx:alpine
     container_name: vm-registry-proxy
@@ -167,6 +231,7 @@ services:
       - "nginx-cache:/var/cache/nginx"
     depends_on:
       - registry
+      - buildcache
     networks:
       - registry-network
 
@@ -177,7 +242,7 @@ networks:
   registry-network:
     driver: bridge
 "#,
-        config.host, config.backend_port, data_dir, config.host, config.registry_port
+        config.host, config.backend_port, data_dir, data_dir, config.host, config.registry_port
     );
 
     Ok(compose_yaml)
@@ -191,6 +256,7 @@ pub fn write_config_files(config: &RegistryConfig, data_dir: &Path) -> Result<()
     // Generate configurations
     let nginx_config = generate_nginx_config(config)?;
     let registry_config = generate_registry_config(config)?;
+    let cache_registry_config = generate_cache_registry_config(config)?;
     let compose_config = generate_docker_compose_config(config, &data_dir.to_string_lossy())?;
 
     // Write configuration files
@@ -200,6 +266,12 @@ pub fn write_config_files(config: &RegistryConfig, data_dir: &Path) -> Result<()
     fs::write(data_dir.join("registry-config.yml"), registry_config)
         .context("Failed to write registry configuration")?;
 
+    fs::write(
+        data_dir.join("registry-cache-config.yml"),
+        cache_registry_config,
+    )
+    .context("Failed to write build cache registry configuration")?;
+
     fs::write(data_dir.join("docker-compose.yml"), compose_config)
         .context("Failed to write docker-compose configuration")?;
 
@@ -226,6 +298,42 @@ mod tests {
         assert!(nginx_config.contains("upstream dockerhub"));
         assert!(nginx_config.contains("127.0.0.1:5001"));
         assert!(nginx_config.contains("location /v2/"));
+        assert!(nginx_config.contains("location ^~ /v2/vm-cache/"));
+    }
+
+    #[test]
+    fn test_offline_nginx_config_skips_docker_hub() {
+        let config = RegistryConfig {
+            offline: true,
+            ..Default::default()
+        };
+        let nginx_config = generate_nginx_config(&config).expect("should generate nginx config");
+
+        assert!(nginx_config.contains("location @dockerhub"));
+        assert!(nginx_config.contains("return 503"));
+        assert!(!nginx_config.contains("proxy_pass https://registry-1.docker.io"));
+        assert!(generate_nginx_config(&RegistryConfig::default())
+            .expect("should generate nginx config")
+            .contains("proxy_pass https://registry-1.docker.io"));
+    }
+
+    #[test]
+    fn test_bandwidth_limit_throttles_pulls() {
+        let config = RegistryConfig {
+            max_download_kbps: 2048,
+            ..Default::default()
+        };
+        let nginx_config = generate_nginx_config(&config).expect("should generate nginx config");
+
+        // Only Docker Hub fallbacks are throttled, not cache hits
+        let dockerhub = &nginx_config[nginx_config.find("location @dockerhub").unwrap()..];
+        let dockerhub = &dockerhub[..dockerhub.find("proxy_pass").unwrap()];
+        assert!(dockerhub.contains("    limit_rate 2048k;"));
+        assert!(dockerhub.contains("proxy_limit_rate 2048k;"));
+        assert_eq!(nginx_config.matches("limit_rate 2048k;").count(), 2);
+        assert!(!generate_nginx_config(&RegistryConfig::default())
+            .expect("should generate nginx config")
+            .contains("limit_rate"));
     }
 
     #[test]
@@ -240,6 +348,16 @@ mod tests {
         assert!(registry_config.contains("remoteurl: https://registry-1.docker.io"));
     }
 
+    #[test]
+    fn test_generate_cache_registry_config_accepts_pushes() {
+        let config = RegistryConfig::default();
+        let cache_config =
+            generate_cache_registry_config(&config).expect("should generate cache config");
+
+        assert!(cache_config.contains("rootdirectory: /var/lib/registry"));
+        assert!(!cache_config.contains("proxy:"));
+    }
+
     #[test]
     fn test_generate_docker_compose_config() {
         let config = RegistryConfig::default();
@@ -252,6 +370,8 @@ mod tests {
         assert!(compose_config.contains("vm-registry-proxy"));
         assert!(compose_config.contains("5000:80"));
         assert!(compose_config.contains("/test/data:/var/lib/registry"));
+        assert!(compose_config.contains("vm-registry-cache"));
+        assert!(compose_config.contains("/test/data/build-cache:/var/lib/registry"));
     }
 
     #[test]
@@ -263,6 +383,7 @@ mod tests {
 
         assert!(temp_dir.path().join("nginx.conf").exists());
         assert!(temp_dir.path().join("registry-config.yml").exists());
+        assert!(temp_dir.path().join("registry-cache-config.yml").exists());
         assert!(temp_dir.path().join("docker-compose.yml").exists());
 
         // Verify nginx config content
@@ -9,6 +9,7 @@
 //! - **Bandwidth savings**: 80-95% reduction for teams reusing base images
 //! - **Offline capability**: Previously pulled images available without internet
 //! - **Automatic cleanup**: Configurable garbage collection and size limits
+//! - **Pinning**: Images a project depends on are never evicted while it exists
 //! - **VM integration**: Automatic Docker daemon configuration
 //!
 //! ## Architecture
@@ -34,12 +35,15 @@
 pub mod auto_manager;
 pub mod config;
 pub mod docker_config;
+pub mod pins;
 pub mod server;
 pub mod types;
 
 // Re-export main types
 pub use types::{AutoConfig, ContainerInfo, RegistryConfig, RegistryStatus};
 
+pub use pins::Pins;
+
 // Re-export server functions
 pub use server::{check_registry_running, start_registry, stop_registry};
 
@@ -64,3 +68,8 @@ pub const SERVICE_NAME: &str = "vm-docker-registry";
 /// Container names
 pub const PROXY_CONTAINER_NAME: &str = "vm-registry-proxy";
 pub const BACKEND_CONTAINER_NAME: &str = "vm-registry-backend";
+pub const CACHE_CONTAINER_NAME: &str = "vm-registry-cache";
+
+/// Repository namespace for BuildKit layer caches, served by the push-capable
+/// cache backend (the pull-through backend rejects pushes)
+pub const BUILD_CACHE_NAMESPACE: &str = "vm-cache";
@@ -0,0 +1,201 @@
+//! Images protected from cache eviction
+//!
+//! Every VM records the images it was created from (its base image and service
+//! images), and the auto-manager skips them while the VM exists, so the cache
+//! doesn't drop a project's base image right before you go offline.
+//! `vm registry pin` protects extra images and `vm registry unpin` releases
+//! one even while a project still uses it.
+//!
+//! Pins are stored in `~/.vm/registry/pins.json`.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+/// Images protected from cache eviction
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Pins {
+    /// Images each VM depends on, keyed by VM name
+    #[serde(default)]
+    pub projects: BTreeMap<String, BTreeSet<String>>,
+    /// Images pinned with `vm registry pin`
+    #[serde(default)]
+    pub pinned: BTreeSet<String>,
+    /// Project images released with `vm registry unpin`
+    #[serde(default)]
+    pub unpinned: BTreeSet<String>,
+}
+
+/// Normalize an image reference to the `repository:tag` form the registry
+/// catalog uses, e.g. `node:20` becomes `library/node:20`.
+pub fn normalize_image(image: &str) -> String {
+    let image = image.trim();
+    let image = image
+        .strip_prefix("docker.io/")
+        .or_else(|| image.strip_prefix("registry-1.docker.io/"))
+        .unwrap_or(image);
+    let (name, reference) = match image.split_once('@') {
+        Some((name, digest)) => (name, format!("@{digest}")),
+        None => match image.rsplit_once(':') {
+            Some((name, tag)) if !tag.contains('/') => (name, format!(":{tag}")),
+            _ => (image, ":latest".to_string()),
+        },
+    };
+    if name.contains('/') {
+        format!("{name}{reference}")
+    } else {
+        format!("library/{name}{reference}")
+    }
+}
+
+fn pins_path() -> Result<PathBuf> {
+    Ok(crate::config::get_registry_data_dir()?.join("pins.json"))
+}
+
+impl Pins {
+    /// Load the pins, or none if nothing was pinned yet
+    pub fn load() -> Result<Self> {
+        Self::load_from(&pins_path()?)
+    }
+
+    pub fn load_from(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse {}", path.display()))
+    }
+
+    pub fn save(&self) -> Result<()> {
+        self.save_to(&pins_path()?)
+    }
+
+    pub fn save_to(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// Record the images `vm_name` depends on, replacing earlier ones
+    pub fn track_project<I, S>(&mut self, vm_name: &str, images: I)
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let images: BTreeSet<String> = images
+            .into_iter()
+            .map(|image| normalize_image(image.as_ref()))
+            .collect();
+        if images.is_empty() {
+            self.projects.remove(vm_name);
+        } else {
+            self.projects.insert(vm_name.to_string(), images);
+        }
+    }
+
+    /// Stop protecting the images of a destroyed VM
+    pub fn forget_project(&mut self, vm_name: &str) -> bool {
+        self.projects.remove(vm_name).is_some()
+    }
+
+    /// Protect `image` regardless of which projects use it
+    pub fn pin(&mut self, image: &str) -> String {
+        let image = normalize_image(image);
+        self.unpinned.remove(&image);
+        self.pinned.insert(image.clone());
+        image
+    }
+
+    /// Let the auto-manager evict `image` again, even if a project uses it.
+    ///
+    /// Returns false if the image wasn't protected.
+    pub fn unpin(&mut self, image: &str) -> bool {
+        let image = normalize_image(image);
+        let was_pinned = self.pinned.remove(&image);
+        let used = self.projects.values().any(|images| images.contains(&image));
+        if used {
+            self.unpinned.insert(image.clone());
+        }
+        was_pinned || used
+    }
+
+    /// Whether the auto-manager must keep `repository:tag`
+    pub fn is_protected(&self, repository: &str, tag: &str) -> bool {
+        let image = format!("{repository}:{tag}");
+        self.protected().contains_key(&image)
+    }
+
+    /// Protected images with what protects them (`pinned` or VM names)
+    pub fn protected(&self) -> BTreeMap<String, Vec<String>> {
+        let mut protected: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for image in &self.pinned {
+            protected
+                .entry(image.clone())
+                .or_default()
+                .push("pinned".to_string());
+        }
+        for (vm_name, images) in &self.projects {
+            for image in images
+                .iter()
+                .filter(|image| !self.unpinned.contains(*image))
+            {
+                protected
+                    .entry(image.clone())
+                    .or_default()
+                    .push(vm_name.clone());
+            }
+        }
+        protected
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_normalize_image() {
+        assert_eq!(normalize_image("node:20"), "library/node:20");
+        assert_eq!(normalize_image("ubuntu"), "library/ubuntu:latest");
+        assert_eq!(
+            normalize_image("docker.io/bitnami/redis:7"),
+            "bitnami/redis:7"
+        );
+        assert_eq!(
+            normalize_image("postgres@sha256:abc"),
+            "library/postgres@sha256:abc"
+        );
+    }
+
+    #[test]
+    fn test_project_images_are_protected_until_destroyed_or_unpinned() {
+        let mut pins = Pins::default();
+        pins.track_project("web-dev", ["ubuntu:24.04", "postgres:16"]);
+        pins.track_project("api-dev", ["ubuntu:24.04"]);
+        pins.pin("alpine:3.20");
+        assert!(pins.is_protected("library/ubuntu", "24.04"));
+        assert!(pins.is_protected("library/alpine", "3.20"));
+        assert!(!pins.is_protected("library/redis", "7"));
+
+        assert!(pins.unpin("postgres:16"));
+        assert!(!pins.is_protected("library/postgres", "16"));
+        assert!(!pins.unpin("redis:7"));
+
+        assert!(pins.forget_project("web-dev"));
+        assert_eq!(
+            pins.protected().get("library/ubuntu:24.04"),
+            Some(&vec!["api-dev".to_string()])
+        );
+
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("pins.json");
+        pins.save_to(&path).unwrap();
+        assert_eq!(Pins::load_from(&path).unwrap(), pins);
+    }
+}
@@ -63,7 +63,7 @@ pub async fn stop_registry() -> Result<()> {
 /// Check if the Docker registry is running
 pub async fn check_registry_running(port: u16) -> bool {
     let url = format!("http://127.0.0.1:{port}/health");
-    match reqwest::get(&url).await {
+    match vm_core::network::client().get(&url).send().await {
         Ok(response) => response.status().is_success(),
         Err(_) => false,
     }
@@ -190,7 +190,11 @@ fn stop_containers() -> Result<()> {
     debug!("Stopping registry containers...");
 
     // Stop and remove containers directly
-    let containers = [crate::PROXY_CONTAINER_NAME, crate::BACKEND_CONTAINER_NAME];
+    let containers = [
+        crate::PROXY_CONTAINER_NAME,
+        crate::BACKEND_CONTAINER_NAME,
+        crate::CACHE_CONTAINER_NAME,
+    ];
 
     for container in &containers {
         // Stop container
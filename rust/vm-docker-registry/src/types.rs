@@ -20,6 +20,10 @@ pub struct RegistryConfig {
     pub max_size_bytes: Option<u64>,
     /// Whether to enable debug logging
     pub debug: bool,
+    /// Serve cached images only; misses fail instead of falling back to Docker Hub
+    pub offline: bool,
+    /// Per-connection limit for image data fetched from Docker Hub in KiB/s (0 = unlimited)
+    pub max_download_kbps: u64,
 }
 
 impl Default for RegistryConfig {
@@ -32,6 +36,8 @@ impl Default for RegistryConfig {
             gc_policy: GcPolicy::default(),
             max_size_bytes: Some(50 * 1024 * 1024 * 1024), // 50GB
             debug: false,
+            offline: false,
+            max_download_kbps: 0,
         }
     }
 }
@@ -1,4 +1,7 @@
-use clap::Parser;
+use clap::{Parser, Subcommand};
+use std::path::PathBuf;
+
+use crate::package::PackageFormat;
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
@@ -6,4 +9,26 @@ pub struct Args {
     /// Clean all build artifacts before building
     #[arg(long)]
     pub clean: bool,
+
+    #[command(subcommand)]
+    pub command: Option<InstallerCommand>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum InstallerCommand {
+    /// Build distributable packages instead of installing for the current user
+    Package {
+        /// Package formats to build (defaults to the formats native to this platform)
+        #[arg(long = "format", value_enum, value_delimiter = ',')]
+        formats: Vec<PackageFormat>,
+        /// Directory the packages are written to
+        #[arg(long, default_value = "dist")]
+        output: PathBuf,
+        /// Base URL the release tarball is downloaded from, used in the Homebrew formula
+        #[arg(
+            long,
+            default_value = "https://github.com/goobits/vm/releases/download"
+        )]
+        url_base: String,
+    },
 }
@@ -26,7 +26,9 @@ pub fn install(clean: bool) -> Result<()> {
         run_cargo_clean(&project_root)?;
     }
 
-    let source_binary = build_workspace(&project_root)?;
+    let install_profile =
+        env::var("VM_INSTALL_PROFILE").unwrap_or_else(|_| "source-install".to_string());
+    let source_binary = build_workspace(&project_root, &install_profile)?;
     create_symlink(&source_binary, &bin_dir)?;
     install_plugins(&project_root)?;
     platform::ensure_path(&bin_dir)?;
@@ -182,7 +184,7 @@ fn append_line_if_missing(profile_path: &Path, line: &str) -> Result<()> {
     Ok(())
 }
 
-fn get_project_root() -> Result<PathBuf> {
+pub(crate) fn get_project_root() -> Result<PathBuf> {
     // Use the executable's path to reliably find the project root, as `cargo run`
     // can change the current working directory.
     let exe_path = env::current_exe()?;
@@ -236,7 +238,7 @@ fn run_cargo_clean(project_root: &Path) -> Result<()> {
     Ok(())
 }
 
-fn build_workspace(project_root: &Path) -> Result<PathBuf> {
+pub(crate) fn build_workspace(project_root: &Path, profile: &str) -> Result<PathBuf> {
     let platform = platform::detect_platform_string();
     let span = info_span!("cargo_build",
         operation = "cargo_build",
@@ -266,10 +268,8 @@ fn build_workspace(project_root: &Path) -> Result<PathBuf> {
     // Use platform-specific target directory to avoid conflicts in shared filesystems
     let target_dir = project_root.join(format!("target-{platform}"));
 
-    let install_profile =
-        env::var("VM_INSTALL_PROFILE").unwrap_or_else(|_| "source-install".to_string());
     let mut cmd = Command::new("cargo");
-    cmd.args(["build", "--profile", &install_profile, "--bin", "vm"]);
+    cmd.args(["build", "--profile", profile, "--bin", "vm"]);
 
     // Enable Tart provider on macOS (Apple Silicon VMs)
     #[cfg(target_os = "macos")]
@@ -301,7 +301,7 @@ fn build_workspace(project_root: &Path) -> Result<PathBuf> {
     vm_success!("Rust binaries built successfully.");
 
     let binary_name = vm_platform::platform::executable_name("vm");
-    let binary_path = target_dir.join(&install_profile).join(&binary_name);
+    let binary_path = target_dir.join(profile).join(&binary_name);
     if !binary_path.exists() {
         return Err(vm_core::error::VmError::Internal(format!(
             "Binary not found at: {}",
@@ -417,7 +417,7 @@ fn install_plugins(project_root: &Path) -> Result<()> {
     Ok(())
 }
 
-fn copy_dir_recursive(src: &Path, dst: &Path) -> Result<()> {
+pub(crate) fn copy_dir_recursive(src: &Path, dst: &Path) -> Result<()> {
     if !dst.exists() {
         fs::create_dir_all(dst).map_err(|e| {
             vm_core::error::VmError::Internal(format!(
@@ -1,10 +1,12 @@
 //! VM installer library.
 //!
 //! This library provides installation functionality for the VM tool,
-//! including platform detection, binary building, and PATH management.
+//! including platform detection, binary building, PATH management and
+//! building distributable packages.
 
 pub mod dependencies;
 pub mod installer;
+pub mod package;
 pub mod platform;
 pub mod prompt;
 
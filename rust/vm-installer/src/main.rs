@@ -7,10 +7,11 @@ use vm_messages::messages::MESSAGES;
 mod cli;
 mod dependencies;
 mod installer;
+mod package;
 mod platform;
 mod prompt;
 
-use cli::Args;
+use cli::{Args, InstallerCommand};
 use installer::install;
 
 fn main() {
@@ -26,6 +27,16 @@ fn run() -> Result<()> {
     let _guard = init_subscriber();
     let args = Args::parse();
 
+    if let Some(InstallerCommand::Package {
+        formats,
+        output,
+        url_base,
+    }) = args.command
+    {
+        dependencies::check()?;
+        return package::package(&formats, &output, &url_base);
+    }
+
     vm_println!("{}", MESSAGES.service.installer_installing);
 
     // 1. Check dependencies (like cargo)
@@ -0,0 +1,613 @@
+//! Distributable packages for the VM tool.
+//!
+//! `vm-installer package` builds the `vm` binary in release mode and lays it out
+//! as a self-contained tool directory, then wraps that directory in Homebrew,
+//! Debian, RPM or MSI packages:
+//!
+//! ```text
+//! <tool dir>/bin/vm                      the binary
+//! <tool dir>/configs/                    presets, schema and defaults
+//! <tool dir>/plugins/presets/<name>/     preset plugins
+//! <tool dir>/completions/                bash, zsh, fish and PowerShell completions
+//! ```
+//!
+//! On Unix the packages install a small `vm` launcher that points `VM_TOOL_DIR`
+//! at the tool directory and copies bundled preset plugins into `~/.vm/plugins`
+//! on first use. Building `.deb`, `.rpm` and `.msi` files needs `dpkg-deb`,
+//! `rpmbuild` and `wix` respectively; the manifests are written to the output
+//! directory either way so they can be built elsewhere.
+
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use clap::ValueEnum;
+use sha2::{Digest, Sha256};
+use tracing::info_span;
+use vm_core::error::{Result, VmError};
+use vm_core::{vm_println, vm_progress, vm_success, vm_warning};
+
+use crate::installer::{build_workspace, copy_dir_recursive, get_project_root};
+
+/// Version recorded in every package
+const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Install prefix of the tool directory on Linux
+const LINUX_TOOL_DIR: &str = "/usr/lib/vm";
+
+const DESCRIPTION: &str = "Development environments in Docker, Podman or Tart VMs";
+const HOMEPAGE: &str = "https://github.com/goobits/vm";
+
+/// Distributable artifact formats
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PackageFormat {
+    /// Homebrew formula plus the release tarball it installs
+    Brew,
+    /// Debian/Ubuntu package
+    Deb,
+    /// Fedora/RHEL package
+    Rpm,
+    /// Windows installer (WiX)
+    Msi,
+}
+
+impl PackageFormat {
+    /// Formats that can be built for binaries of the current platform
+    pub fn native() -> Vec<Self> {
+        match env::consts::OS {
+            "macos" => vec![Self::Brew],
+            "linux" => vec![Self::Deb, Self::Rpm, Self::Brew],
+            "windows" => vec![Self::Msi],
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// Shells completions are generated for, with the file name used in the package
+const COMPLETIONS: &[(&str, &str)] = &[
+    ("bash", "vm.bash"),
+    ("zsh", "_vm"),
+    ("fish", "vm.fish"),
+    ("powershell", "vm.ps1"),
+];
+
+pub fn package(formats: &[PackageFormat], output: &Path, url_base: &str) -> Result<()> {
+    let span = info_span!("package", operation = "package");
+    let _enter = span.enter();
+
+    let formats = if formats.is_empty() {
+        PackageFormat::native()
+    } else {
+        formats.to_vec()
+    };
+    if formats.is_empty() {
+        return Err(VmError::Internal(format!(
+            "No package formats are supported on {}; pass --format explicitly",
+            env::consts::OS
+        )));
+    }
+
+    let project_root = get_project_root()?;
+    let binary = build_workspace(&project_root, "release")?;
+
+    fs::create_dir_all(output).map_err(|e| io_error("create", output, e))?;
+    let output = output
+        .canonicalize()
+        .map_err(|e| io_error("resolve", output, e))?;
+    let tool_dir = output.join("staging").join("vm");
+    if tool_dir.exists() {
+        fs::remove_dir_all(&tool_dir).map_err(|e| io_error("clean", &tool_dir, e))?;
+    }
+    stage_tool_dir(&project_root, &binary, &tool_dir)?;
+
+    let mut failed = Vec::new();
+    for format in formats {
+        let result = match format {
+            PackageFormat::Brew => build_brew(&tool_dir, &output, url_base),
+            PackageFormat::Deb => build_deb(&tool_dir, &output),
+            PackageFormat::Rpm => build_rpm(&tool_dir, &output),
+            PackageFormat::Msi => build_msi(&tool_dir, &output),
+        };
+        match result {
+            Ok(artifact) => vm_success!("Built {}", artifact.display()),
+            Err(e) => {
+                vm_warning!("{:?} package not built: {}", format, e);
+                failed.push(format!("{format:?}").to_lowercase());
+            }
+        }
+    }
+
+    if !failed.is_empty() {
+        return Err(VmError::Internal(format!(
+            "Failed to build packages: {}",
+            failed.join(", ")
+        )));
+    }
+    vm_println!("Packages written to {}", output.display());
+    Ok(())
+}
+
+fn io_error(action: &str, path: &Path, e: std::io::Error) -> VmError {
+    VmError::Internal(format!("Failed to {action} {}: {e}", path.display()))
+}
+
+fn write_file(path: &Path, content: &str) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| io_error("create", parent, e))?;
+    }
+    fs::write(path, content).map_err(|e| io_error("write", path, e))
+}
+
+#[cfg(unix)]
+fn set_executable(path: &Path) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(path, fs::Permissions::from_mode(0o755))
+        .map_err(|e| io_error("set permissions on", path, e))
+}
+
+#[cfg(not(unix))]
+fn set_executable(_path: &Path) -> Result<()> {
+    Ok(())
+}
+
+/// Lay out the binary, configs, preset plugins and completions in `tool_dir`
+fn stage_tool_dir(project_root: &Path, binary: &Path, tool_dir: &Path) -> Result<()> {
+    vm_progress!("Staging package contents...");
+    let repo_root = project_root
+        .parent()
+        .ok_or_else(|| VmError::Internal("Could not find project root".to_string()))?;
+
+    let bin_dir = tool_dir.join("bin");
+    fs::create_dir_all(&bin_dir).map_err(|e| io_error("create", &bin_dir, e))?;
+    let staged_binary = bin_dir.join(vm_platform::platform::executable_name("vm"));
+    fs::copy(binary, &staged_binary).map_err(|e| io_error("copy", binary, e))?;
+    set_executable(&staged_binary)?;
+
+    copy_dir_recursive(&repo_root.join("configs"), &tool_dir.join("configs"))?;
+
+    // Repo plugins are named `<name>-dev`, matching what `install_plugins` does
+    let plugins_dir = repo_root.join("plugins");
+    if let Ok(entries) = fs::read_dir(&plugins_dir) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let dir_name = entry.file_name().to_string_lossy().into_owned();
+            if let (true, Some(name)) = (path.is_dir(), dir_name.strip_suffix("-dev")) {
+                copy_dir_recursive(&path, &tool_dir.join("plugins/presets").join(name))?;
+            }
+        }
+    }
+
+    let completions_dir = tool_dir.join("completions");
+    fs::create_dir_all(&completions_dir).map_err(|e| io_error("create", &completions_dir, e))?;
+    for (shell, file_name) in COMPLETIONS {
+        let path = completions_dir.join(file_name);
+        let file = fs::File::create(&path).map_err(|e| io_error("create", &path, e))?;
+        let status = Command::new(&staged_binary)
+            .args(["internal-completion", shell])
+            .stdout(Stdio::from(file))
+            .stderr(Stdio::inherit())
+            .status()
+            .map_err(|e| {
+                VmError::Internal(format!("Failed to generate {shell} completion: {e}"))
+            })?;
+        if !status.success() {
+            return Err(VmError::Internal(format!(
+                "Generating {shell} completion exited with code {}",
+                status.code().unwrap_or(-1)
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+/// Launcher installed as `vm` on Unix systems
+fn launcher_script(tool_dir: &str) -> String {
+    format!(
+        r#"#!/bin/sh
+# Launcher installed by the vm package
+VM_TOOL_DIR="{tool_dir}"
+export VM_TOOL_DIR
+
+# Seed bundled preset plugins into the user's plugin directory
+plugins="${{HOME}}/.vm/plugins/presets"
+for preset in "$VM_TOOL_DIR"/plugins/presets/*/; do
+    [ -d "$preset" ] || continue
+    name=$(basename "$preset")
+    if [ ! -e "$plugins/$name" ]; then
+        mkdir -p "$plugins" && cp -R "$preset" "$plugins/$name" 2>/dev/null
+    fi
+done
+
+exec "$VM_TOOL_DIR/bin/vm" "$@"
+"#
+    )
+}
+
+fn run_tool(program: &str, args: &[&str], cwd: &Path) -> Result<()> {
+    let status = Command::new(program)
+        .args(args)
+        .current_dir(cwd)
+        .stdout(Stdio::inherit())
+        .stderr(Stdio::inherit())
+        .status()
+        .map_err(|e| {
+            VmError::Internal(format!(
+                "'{program}' is required to build this package but could not be run: {e}"
+            ))
+        })?;
+    if !status.success() {
+        return Err(VmError::Internal(format!(
+            "'{program}' failed with exit code {}",
+            status.code().unwrap_or(-1)
+        )));
+    }
+    Ok(())
+}
+
+fn sha256_file(path: &Path) -> Result<String> {
+    let content = fs::read(path).map_err(|e| io_error("read", path, e))?;
+    let digest = Sha256::digest(&content);
+    Ok(digest.iter().map(|b| format!("{b:02x}")).collect())
+}
+
+/// Debian architecture name for the current target
+fn deb_arch() -> &'static str {
+    match env::consts::ARCH {
+        "x86_64" => "amd64",
+        "aarch64" => "arm64",
+        "x86" => "i386",
+        other => other,
+    }
+}
+
+/// Copy the tool directory into a packaging root under the Linux prefix
+fn stage_linux_root(tool_dir: &Path, root: &Path) -> Result<()> {
+    if root.exists() {
+        fs::remove_dir_all(root).map_err(|e| io_error("clean", root, e))?;
+    }
+    let prefix = root.join(LINUX_TOOL_DIR.trim_start_matches('/'));
+    copy_dir_recursive(tool_dir, &prefix)?;
+    set_executable(&prefix.join("bin/vm"))?;
+
+    let launcher = root.join("usr/bin/vm");
+    write_file(&launcher, &launcher_script(LINUX_TOOL_DIR))?;
+    set_executable(&launcher)?;
+
+    for (source, dest) in [
+        ("vm.bash", "usr/share/bash-completion/completions/vm"),
+        ("_vm", "usr/share/zsh/vendor-completions/_vm"),
+        ("vm.fish", "usr/share/fish/vendor_completions.d/vm.fish"),
+    ] {
+        let dest = root.join(dest);
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent).map_err(|e| io_error("create", parent, e))?;
+        }
+        let source = tool_dir.join("completions").join(source);
+        fs::copy(&source, &dest).map_err(|e| io_error("copy", &source, e))?;
+    }
+    Ok(())
+}
+
+fn deb_control(arch: &str) -> String {
+    format!(
+        "Package: vm\n\
+         Version: {VERSION}\n\
+         Section: devel\n\
+         Priority: optional\n\
+         Architecture: {arch}\n\
+         Maintainer: goobits <noreply@goobits.dev>\n\
+         Homepage: {HOMEPAGE}\n\
+         Recommends: docker.io | docker-ce | podman\n\
+         Description: {DESCRIPTION}\n \
+         Creates and manages reproducible development environments from a vm.yaml file.\n"
+    )
+}
+
+fn build_deb(tool_dir: &Path, output: &Path) -> Result<PathBuf> {
+    vm_progress!("Building Debian package...");
+    let root = output.join("staging/deb");
+    stage_linux_root(tool_dir, &root)?;
+    write_file(&root.join("DEBIAN/control"), &deb_control(deb_arch()))?;
+
+    let artifact = output.join(format!("vm_{VERSION}_{}.deb", deb_arch()));
+    run_tool(
+        "dpkg-deb",
+        &[
+            "--root-owner-group",
+            "--build",
+            &root.to_string_lossy(),
+            &artifact.to_string_lossy(),
+        ],
+        output,
+    )?;
+    Ok(artifact)
+}
+
+fn rpm_spec(root: &Path) -> String {
+    // RPM versions can't contain '-', so pre-release suffixes use '~'
+    let version = VERSION.replace('-', "~");
+    format!(
+        "Name: vm\n\
+         Version: {version}\n\
+         Release: 1\n\
+         Summary: {DESCRIPTION}\n\
+         License: MIT\n\
+         URL: {HOMEPAGE}\n\
+         AutoReqProv: no\n\
+         \n\
+         %description\n\
+         Creates and manages reproducible development environments from a vm.yaml file.\n\
+         \n\
+         %install\n\
+         cp -a {root}/. %{{buildroot}}/\n\
+         \n\
+         %files\n\
+         {LINUX_TOOL_DIR}\n\
+         /usr/bin/vm\n\
+         /usr/share/bash-completion/completions/vm\n\
+         /usr/share/zsh/vendor-completions/_vm\n\
+         /usr/share/fish/vendor_completions.d/vm.fish\n",
+        root = root.display()
+    )
+}
+
+fn build_rpm(tool_dir: &Path, output: &Path) -> Result<PathBuf> {
+    vm_progress!("Building RPM package...");
+    let root = output.join("staging/rpm-root");
+    stage_linux_root(tool_dir, &root)?;
+
+    let spec = output.join("vm.spec");
+    write_file(&spec, &rpm_spec(&root))?;
+
+    let topdir = output.join("staging/rpmbuild");
+    run_tool(
+        "rpmbuild",
+        &[
+            "-bb",
+            "--define",
+            &format!("_topdir {}", topdir.display()),
+            "--define",
+            &format!("_rpmdir {}", output.display()),
+            "--define",
+            "_build_name_fmt %%{NAME}-%%{VERSION}-%%{RELEASE}.%%{ARCH}.rpm",
+            &spec.to_string_lossy(),
+        ],
+        output,
+    )?;
+    Ok(output.join(format!(
+        "vm-{}-1.{}.rpm",
+        VERSION.replace('-', "~"),
+        env::consts::ARCH
+    )))
+}
+
+/// Pack the tool directory as `vm-<version>-<os>-<arch>.tar.gz`
+fn build_tarball(tool_dir: &Path, output: &Path) -> Result<PathBuf> {
+    let name = format!(
+        "vm-{VERSION}-{}.tar.gz",
+        crate::platform::detect_platform_string()
+    );
+    let artifact = output.join(&name);
+    let file = fs::File::create(&artifact).map_err(|e| io_error("create", &artifact, e))?;
+    let encoder = flate2::write::GzEncoder::new(file, flate2::Compression::default());
+    let mut tar = tar::Builder::new(encoder);
+    tar.append_dir_all("vm", tool_dir)
+        .map_err(|e| io_error("archive", tool_dir, e))?;
+    tar.into_inner()
+        .and_then(|encoder| encoder.finish())
+        .and_then(|mut file| file.flush())
+        .map_err(|e| io_error("write", &artifact, e))?;
+    Ok(artifact)
+}
+
+fn brew_formula(url: &str, sha256: &str) -> String {
+    format!(
+        r##"class Vm < Formula
+  desc "{DESCRIPTION}"
+  homepage "{HOMEPAGE}"
+  url "{url}"
+  sha256 "{sha256}"
+  version "{VERSION}"
+  license "MIT"
+
+  def install
+    libexec.install Dir["*"]
+    (bin/"vm").write <<~EOS
+{launcher}    EOS
+    bash_completion.install libexec/"completions/vm.bash" => "vm"
+    zsh_completion.install libexec/"completions/_vm"
+    fish_completion.install libexec/"completions/vm.fish"
+  end
+
+  test do
+    assert_match version.to_s, shell_output("#{{bin}}/vm --version")
+  end
+end
+"##,
+        launcher = launcher_script("#{libexec}")
+            .lines()
+            .map(|line| format!("      {line}\n"))
+            .collect::<String>()
+    )
+}
+
+fn build_brew(tool_dir: &Path, output: &Path, url_base: &str) -> Result<PathBuf> {
+    vm_progress!("Building Homebrew formula...");
+    let tarball = build_tarball(tool_dir, output)?;
+    let file_name = tarball
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let url = format!("{}/v{VERSION}/{file_name}", url_base.trim_end_matches('/'));
+
+    let formula = output.join("vm.rb");
+    write_file(&formula, &brew_formula(&url, &sha256_file(&tarball)?))?;
+    vm_println!("Upload {} to {}", tarball.display(), url);
+    Ok(formula)
+}
+
+/// Stable GUID-shaped ID derived from `seed`, so upgrades keep component identity
+fn stable_guid(seed: &str) -> String {
+    let hex: String = Sha256::digest(seed.as_bytes())
+        .iter()
+        .take(16)
+        .map(|b| format!("{b:02X}"))
+        .collect();
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[0..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..32]
+    )
+}
+
+/// Files under `dir`, relative to it, in a stable order
+fn relative_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        for entry in fs::read_dir(&current).map_err(|e| io_error("read", &current, e))? {
+            let path = entry.map_err(|e| io_error("read", &current, e))?.path();
+            if path.is_dir() {
+                pending.push(path);
+            } else if let Ok(relative) = path.strip_prefix(dir) {
+                files.push(relative.to_path_buf());
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// WiX v4 source installing the tool directory under Program Files
+fn wix_source(tool_dir: &Path, files: &[PathBuf]) -> String {
+    let version = VERSION.split('-').next().unwrap_or(VERSION);
+    let mut components = String::new();
+    for file in files {
+        let relative = file.to_string_lossy().replace('/', "\\");
+        let subdir = file
+            .parent()
+            .map(|p| p.to_string_lossy().replace('/', "\\"))
+            .unwrap_or_default();
+        components.push_str(&format!(
+            "      <Component Guid=\"{}\" Subdirectory=\"{subdir}\">\n        <File Source=\"{}\" />\n      </Component>\n",
+            stable_guid(&relative),
+            tool_dir.join(file).display()
+        ));
+    }
+
+    format!(
+        r#"<Wix xmlns="http://wixtoolset.org/schemas/v4/wxs">
+  <Package Name="vm" Manufacturer="goobits" Version="{version}" UpgradeCode="{upgrade_code}">
+    <MajorUpgrade DowngradeErrorMessage="A newer version of vm is already installed." />
+    <MediaTemplate EmbedCab="yes" />
+    <StandardDirectory Id="ProgramFiles64Folder">
+      <Directory Id="INSTALLFOLDER" Name="vm" />
+    </StandardDirectory>
+    <ComponentGroup Id="ToolFiles" Directory="INSTALLFOLDER">
+{components}      <Component Guid="{env_guid}">
+        <Environment Id="VmToolDir" Name="VM_TOOL_DIR" Value="[INSTALLFOLDER]" Action="set" System="yes" />
+        <Environment Id="VmPath" Name="PATH" Value="[INSTALLFOLDER]bin" Action="set" Part="last" System="yes" />
+      </Component>
+    </ComponentGroup>
+    <Feature Id="Main">
+      <ComponentGroupRef Id="ToolFiles" />
+    </Feature>
+  </Package>
+</Wix>
+"#,
+        upgrade_code = stable_guid("vm-upgrade-code"),
+        env_guid = stable_guid("vm-environment"),
+    )
+}
+
+fn build_msi(tool_dir: &Path, output: &Path) -> Result<PathBuf> {
+    vm_progress!("Building Windows installer...");
+    let source = output.join("vm.wxs");
+    write_file(&source, &wix_source(tool_dir, &relative_files(tool_dir)?))?;
+
+    if !tool_dir.join("bin/vm.exe").exists() {
+        return Err(VmError::Internal(format!(
+            "the MSI needs a Windows build of vm; run this on Windows (WiX source written to {})",
+            source.display()
+        )));
+    }
+
+    let artifact = output.join(format!("vm-{VERSION}-x64.msi"));
+    run_tool(
+        "wix",
+        &[
+            "build",
+            &source.to_string_lossy(),
+            "-arch",
+            "x64",
+            "-o",
+            &artifact.to_string_lossy(),
+        ],
+        output,
+    )?;
+    Ok(artifact)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_deb_control_fields() {
+        let control = deb_control("amd64");
+        assert!(control.starts_with("Package: vm\n"));
+        assert!(control.contains(&format!("Version: {VERSION}\n")));
+        assert!(control.contains("Architecture: amd64\n"));
+        // Continuation lines of the description must be indented
+        assert!(control.ends_with(
+            "\n Creates and manages reproducible development environments from a vm.yaml file.\n"
+        ));
+    }
+
+    #[test]
+    fn test_launcher_sets_tool_dir() {
+        let script = launcher_script(LINUX_TOOL_DIR);
+        assert!(script.starts_with("#!/bin/sh\n"));
+        assert!(script.contains("VM_TOOL_DIR=\"/usr/lib/vm\""));
+        assert!(script.contains("${HOME}/.vm/plugins/presets"));
+        assert!(script.ends_with("exec \"$VM_TOOL_DIR/bin/vm\" \"$@\"\n"));
+    }
+
+    #[test]
+    fn test_brew_formula_embeds_url_and_checksum() {
+        let formula = brew_formula("https://example.com/vm.tar.gz", "abc123");
+        assert!(formula.contains("url \"https://example.com/vm.tar.gz\""));
+        assert!(formula.contains("sha256 \"abc123\""));
+        assert!(formula.contains("VM_TOOL_DIR=\"#{libexec}\""));
+        assert!(formula.contains("#{bin}/vm --version"));
+    }
+
+    #[test]
+    fn test_wix_components_are_stable() {
+        let dir = tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("bin")).unwrap();
+        fs::write(dir.path().join("bin/vm.exe"), "").unwrap();
+        fs::write(dir.path().join("README.md"), "").unwrap();
+
+        let files = relative_files(dir.path()).unwrap();
+        assert_eq!(
+            files,
+            [PathBuf::from("README.md"), PathBuf::from("bin/vm.exe")]
+        );
+
+        let source = wix_source(dir.path(), &files);
+        assert_eq!(source, wix_source(dir.path(), &files));
+        assert!(source.contains(&format!(
+            "<Component Guid=\"{}\" Subdirectory=\"bin\">",
+            stable_guid("bin\\vm.exe")
+        )));
+        assert_eq!(stable_guid("x").len(), 36);
+    }
+}
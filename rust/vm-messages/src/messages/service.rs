@@ -168,6 +168,8 @@ pub struct ServiceMessages {
     pub progress_creating_vm: &'static str,
     pub progress_provisioning_complete: &'static str,
     pub progress_ansible_error: &'static str,
+    pub progress_step_timing: &'static str,
+    pub progress_step_eta: &'static str,
 
     // Ports
     pub ports_no_ranges: &'static str,
@@ -346,6 +348,8 @@ pub const SERVICE_MESSAGES: ServiceMessages = ServiceMessages {
     progress_creating_vm: "Creating VM...",
     progress_provisioning_complete: "\n✅ Configuration application complete",
     progress_ansible_error: "\n❌ Error: {error}",
+    progress_step_timing: "⏱  Step {step} · {elapsed} elapsed",
+    progress_step_eta: " · ~{remaining} remaining",
 
     // Ports
     ports_no_ranges: "📡 No port ranges registered yet",
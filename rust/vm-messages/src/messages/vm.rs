@@ -229,6 +229,7 @@ pub struct VmMessages {
     pub dry_run_header: &'static str,
     pub dry_run_command: &'static str,
     pub dry_run_config: &'static str,
+    pub dry_run_no_plan: &'static str,
     pub dry_run_complete: &'static str,
 
     // VM Package Registry Messages
@@ -473,6 +474,7 @@ pub const VM_MESSAGES: VmMessages = VmMessages {
     dry_run_header: "🔍 DRY RUN MODE - showing what would be executed:",
     dry_run_command: "   Command: {command}",
     dry_run_config: "   Config: {config}",
+    dry_run_no_plan: "   ⚠️  Could not render the provider's files and commands: {error}",
     dry_run_complete: "🚫 Dry run complete - no commands were executed",
 
     // VM Package Registry
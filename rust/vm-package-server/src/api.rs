@@ -7,6 +7,14 @@ use reqwest::blocking::Client;
 use serde_json::Value;
 use tracing::{debug, error, info};
 
+/// A preset downloaded from the registry and verified against its checksum
+#[derive(Debug, Clone)]
+pub struct FetchedPreset {
+    pub version: String,
+    pub content: String,
+    pub sha256: String,
+}
+
 /// API client for interacting with the package server
 #[allow(dead_code)]
 pub struct PackageServerClient {
@@ -93,12 +101,179 @@ impl PackageServerClient {
         }
 
         Self {
-            client: Client::new(),
+            client: vm_core::network::blocking_client_builder()
+                .build()
+                .unwrap_or_default(),
             base_url: base_url.to_string(),
             auth_token,
         }
     }
 
+    /// Use `token` instead of `PKG_SERVER_AUTH_TOKEN`, if one is given
+    pub fn with_auth_token(mut self, token: Option<String>) -> Self {
+        if token.is_some() {
+            self.auth_token = token;
+        }
+        self
+    }
+
+    /// Base URL of the package server
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    fn authorized(
+        &self,
+        request: reqwest::blocking::RequestBuilder,
+    ) -> reqwest::blocking::RequestBuilder {
+        match &self.auth_token {
+            Some(token) => request.header("Authorization", format!("Bearer {token}")),
+            None => request,
+        }
+    }
+
+    /// Get the files a server holds locally, for replication
+    pub fn get_replication_manifest(&self) -> Result<Vec<crate::replication::ReplicatedFile>> {
+        let response = self
+            .authorized(self.client.get(format!(
+                "{}{}",
+                self.base_url,
+                crate::replication::MANIFEST_ROUTE
+            )))
+            .send()
+            .with_context(|| format!("Failed to reach package server at {}", self.base_url))?;
+
+        if !response.status().is_success() {
+            anyhow::bail!(
+                "{} did not return a replication manifest ({})",
+                self.base_url,
+                response.status()
+            );
+        }
+        response
+            .json()
+            .context("Failed to parse replication manifest")
+    }
+
+    /// Sync the server's mirror manifests and wait for the result
+    pub fn sync_mirrors(&self) -> Result<crate::mirror::MirrorReport> {
+        let response = self
+            .authorized(
+                self.client
+                    .post(format!("{}{}", self.base_url, crate::mirror::SYNC_ROUTE)),
+            )
+            // Pulling a manifest's packages and images can take a while
+            .timeout(std::time::Duration::from_secs(60 * 60))
+            .send()
+            .with_context(|| format!("Failed to reach package server at {}", self.base_url))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().unwrap_or_default();
+            anyhow::bail!("Mirror sync failed ({status}): {body}");
+        }
+        response
+            .json()
+            .context("Failed to parse mirror sync report")
+    }
+
+    /// Have the server fetch every package in a lockfile into its cache
+    pub fn warm_cache(
+        &self,
+        format: crate::warm::LockfileFormat,
+        lockfile: String,
+    ) -> Result<crate::warm::WarmReport> {
+        let response = self
+            .authorized(
+                self.client
+                    .post(format!("{}{}", self.base_url, crate::warm::WARM_ROUTE))
+                    .query(&[("format", format.as_str())])
+                    .body(lockfile),
+            )
+            // A large lockfile means thousands of upstream downloads
+            .timeout(std::time::Duration::from_secs(60 * 60))
+            .send()
+            .with_context(|| format!("Failed to reach package server at {}", self.base_url))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().unwrap_or_default();
+            anyhow::bail!("Cache warm failed ({status}): {body}");
+        }
+        response.json().context("Failed to parse cache warm report")
+    }
+
+    /// Stream a backup archive of the server's data into `out`
+    pub fn backup(
+        &self,
+        options: &crate::backup::BackupOptions,
+        out: &mut impl std::io::Write,
+    ) -> Result<u64> {
+        let mut request = self
+            .client
+            .get(format!("{}{}", self.base_url, crate::backup::BACKUP_ROUTE))
+            .query(&[
+                ("include_cache", options.include_cache),
+                ("include_secrets", options.include_secrets),
+            ]);
+        if let Some(since) = options.since {
+            request = request.query(&[("since", since)]);
+        }
+        let mut response = self
+            .authorized(request)
+            .timeout(std::time::Duration::from_secs(60 * 60))
+            .send()
+            .with_context(|| format!("Failed to reach package server at {}", self.base_url))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().unwrap_or_default();
+            anyhow::bail!("Backup failed ({status}): {body}");
+        }
+        response
+            .copy_to(out)
+            .context("Failed to download backup archive")
+    }
+
+    /// Upload the backup archive at `archive` and restore the server's data from it
+    pub fn restore(&self, archive: &std::path::Path) -> Result<crate::backup::RestoreReport> {
+        let file = std::fs::File::open(archive)
+            .with_context(|| format!("Failed to open {}", archive.display()))?;
+        let response = self
+            .authorized(
+                self.client
+                    .post(format!("{}{}", self.base_url, crate::backup::RESTORE_ROUTE))
+                    .header("Content-Type", "application/zstd")
+                    .body(file),
+            )
+            .timeout(std::time::Duration::from_secs(60 * 60))
+            .send()
+            .with_context(|| format!("Failed to reach package server at {}", self.base_url))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().unwrap_or_default();
+            anyhow::bail!("Restore failed ({status}): {body}");
+        }
+        response.json().context("Failed to parse restore report")
+    }
+
+    /// Download a file by its server path (e.g. `/pypi/packages/<file>`)
+    pub fn download(&self, path: &str) -> Result<Vec<u8>> {
+        let response = self
+            .authorized(self.client.get(format!("{}{}", self.base_url, path)))
+            .send()
+            .with_context(|| format!("Failed to download {path}"))?;
+
+        if !response.status().is_success() {
+            anyhow::bail!("Failed to download {path}: {}", response.status());
+        }
+        Ok(response
+            .bytes()
+            .with_context(|| format!("Failed to read {path}"))?
+            .to_vec())
+    }
+
     /// Check if the server is running
     pub fn is_server_running(&self) -> bool {
         self.client
@@ -108,6 +283,24 @@ impl PackageServerClient {
             .unwrap_or(false)
     }
 
+    /// Get the readiness report from `/readyz`.
+    ///
+    /// The report is returned for both ready (200) and not ready (503) responses.
+    pub fn get_readiness(&self) -> Result<serde_json::Value> {
+        let response = self
+            .client
+            .get(format!("{}/readyz", self.base_url))
+            .send()
+            .context("Failed to get readiness")?;
+
+        let status = response.status();
+        if !status.is_success() && status != reqwest::StatusCode::SERVICE_UNAVAILABLE {
+            anyhow::bail!("Server does not report readiness (HTTP {status})");
+        }
+
+        response.json().context("Failed to parse readiness report")
+    }
+
     /// Get all packages from the server
     pub fn get_all_packages(&self) -> Result<serde_json::Value> {
         let response = self
@@ -136,9 +329,17 @@ impl PackageServerClient {
         let _file = File::open(file_path)
             .with_context(|| format!("Failed to open file: {}", file_path.display()))?;
 
-        let form = reqwest::blocking::multipart::Form::new()
+        let mut form = reqwest::blocking::multipart::Form::new()
             .file("content", file_path)
             .with_context(|| "Failed to create multipart form")?;
+        // Detached PGP signature from `gpg --detach-sign --armor`, as twine sends it
+        if let Some(signature) = crate::signatures::sidecar(file_path, ".asc") {
+            form = form.part(
+                "gpg_signature",
+                reqwest::blocking::multipart::Part::bytes(signature)
+                    .file_name(format!("{file_name}.asc")),
+            );
+        }
 
         let mut request = self
             .client
@@ -174,7 +375,11 @@ impl PackageServerClient {
         info!(package_name = %package_name, "Uploading NPM package");
         let mut request = self
             .client
-            .put(format!("{}/npm/{}", self.base_url, package_name))
+            .put(format!(
+                "{}/npm/{}",
+                self.base_url,
+                package_name.replace('/', "%2f")
+            ))
             .json(&metadata);
 
         // Add auth header if token is available
@@ -232,7 +437,23 @@ impl PackageServerClient {
             "links": null
         });
 
-        let metadata_str = serde_json::to_string(&metadata)?;
+        // Signature from `cosign sign-blob --output-signature <crate>.sig`
+        let signature = crate::signatures::sidecar(crate_file, ".sig")
+            .map(|signature| String::from_utf8_lossy(&signature).trim().to_string());
+
+        self.publish_cargo_crate(&metadata, &crate_data, signature)?;
+        info!(file_name = %file_name, "✅ Successfully published Cargo crate: {}", file_name);
+        Ok(())
+    }
+
+    /// Publish a `.crate` file with the given publish metadata
+    pub fn publish_cargo_crate(
+        &self,
+        metadata: &Value,
+        crate_data: &[u8],
+        signature: Option<String>,
+    ) -> Result<()> {
+        let metadata_str = serde_json::to_string(metadata)?;
         let metadata_bytes = metadata_str.as_bytes();
 
         // Create cargo publish format: 4-byte metadata length + JSON metadata + 4-byte crate length + .crate file
@@ -248,31 +469,30 @@ impl PackageServerClient {
         payload.extend_from_slice(&(crate_data.len() as u32).to_le_bytes());
 
         // .crate file data
-        payload.extend_from_slice(&crate_data);
+        payload.extend_from_slice(crate_data);
 
         let mut request = self
             .client
             .put(format!("{}/cargo/api/v1/crates/new", self.base_url))
             .header("Content-Type", "application/octet-stream");
 
-        // Add auth header if token is available
-        if let Some(token) = &self.auth_token {
-            request = request.header("Authorization", format!("Bearer {token}"));
+        if let Some(signature) = signature {
+            request = request.header(crate::signatures::SIGNATURE_HEADER, signature);
         }
 
-        let response = request
+        let response = self
+            .authorized(request)
             .body(payload)
             .send()
             .context("Failed to upload crate")?;
 
         if response.status().is_success() {
-            info!(file_name = %file_name, "✅ Successfully published Cargo crate: {}", file_name);
             Ok(())
         } else {
             let error_text = response
                 .text()
                 .unwrap_or_else(|_| "Unknown error".to_string());
-            error!(file_name = %file_name, error = %error_text, "Cargo crate publish failed");
+            error!(crate_name = %metadata["name"], error = %error_text, "Cargo crate publish failed");
             anyhow::bail!("Cargo publish failed: {error_text}");
         }
     }
@@ -490,4 +710,81 @@ impl PackageServerClient {
             anyhow::bail!("Failed to fetch server status");
         }
     }
+
+    /// Publish a preset version (raw YAML) as `org/name@version`
+    pub fn publish_preset(&self, preset: &str, version: &str, content: &str) -> Result<()> {
+        info!(preset = %preset, version = %version, "Publishing preset");
+        let mut request = self
+            .client
+            .put(format!("{}/presets/{}/{}", self.base_url, preset, version))
+            .body(content.to_string());
+
+        if let Some(token) = &self.auth_token {
+            request = request.header("Authorization", format!("Bearer {token}"));
+        }
+
+        let response = request.send().context("Failed to publish preset")?;
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            let error_text = response
+                .text()
+                .unwrap_or_else(|_| "Unknown error".to_string());
+            error!(preset = %preset, error = %error_text, "Preset publish failed");
+            anyhow::bail!("Preset publish failed: {error_text}");
+        }
+    }
+
+    /// Download a preset version, resolving the latest one if `version` is `None`.
+    ///
+    /// The document is checked against the SHA-256 recorded in the preset index.
+    pub fn fetch_preset(&self, preset: &str, version: Option<&str>) -> Result<FetchedPreset> {
+        debug!(preset = %preset, version = ?version, "Fetching preset");
+        let response = self
+            .client
+            .get(format!("{}/presets/{}", self.base_url, preset))
+            .send()
+            .context("Failed to fetch preset index")?;
+        if !response.status().is_success() {
+            anyhow::bail!("Preset '{preset}' not found in registry");
+        }
+        let index: crate::presets::PresetIndex =
+            response.json().context("Failed to parse preset index")?;
+
+        let version = match version {
+            Some(version) => version.to_string(),
+            None => crate::presets::latest_version(&index)
+                .with_context(|| format!("Preset '{preset}' has no published versions"))?
+                .to_string(),
+        };
+        let expected = index
+            .versions
+            .get(&version)
+            .with_context(|| format!("Preset '{preset}' has no version {version}"))?
+            .sha256
+            .clone();
+
+        let response = self
+            .client
+            .get(format!("{}/presets/{}/{}", self.base_url, preset, version))
+            .send()
+            .context("Failed to download preset")?;
+        if !response.status().is_success() {
+            anyhow::bail!("Failed to download preset {preset}@{version}");
+        }
+        let content = response.text().context("Failed to read preset")?;
+
+        let actual = crate::sha256_hash(content.as_bytes());
+        if actual != expected {
+            anyhow::bail!(
+                "Integrity check failed for preset {preset}@{version}: expected sha256 {expected}, got {actual}"
+            );
+        }
+
+        Ok(FetchedPreset {
+            version,
+            content,
+            sha256: actual,
+        })
+    }
 }
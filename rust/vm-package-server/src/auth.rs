@@ -2,16 +2,36 @@
 //!
 //! This module provides optional authentication for upload/publish endpoints.
 //! When enabled via config, it validates Bearer tokens from the Authorization header.
+//!
+//! The Cargo endpoints use [`cargo_auth_middleware`], which also accepts the raw
+//! token Cargo sends for registries that advertise `auth-required` (the token
+//! saved by `cargo login --registry <name>`).
+//!
+//! Besides the admin API keys, requests may carry a scoped token issued through
+//! `/api/tokens` (see [`crate::tokens`]) or credentials verified by the OIDC or
+//! LDAP backend (see [`crate::external_auth`]); [`is_allowed`] checks all three.
+//!
+//! Packages in the npm scopes listed in `security.private_npm_scopes` need a
+//! token to be read as well as published; [`check_npm_scope`] enforces that.
 
 use axum::{
     extract::{Request, State},
-    http::header,
+    http::{header, HeaderMap, HeaderValue, StatusCode},
     middleware::Next,
-    response::Response,
+    response::{IntoResponse, Response},
+    Json,
 };
+use base64::Engine;
+use serde_json::json;
 use std::sync::Arc;
 
-use crate::{config::Config, error::AppError};
+use crate::{
+    config::{Config, NpmScopeAcl},
+    error::AppError,
+    external_auth, normalize_pypi_name,
+    state::AppState,
+    tokens::{TokenScope, TokenStore},
+};
 
 /// Extract bearer token from Authorization header
 fn extract_bearer_token(req: &Request) -> Option<String> {
@@ -21,6 +41,291 @@ fn extract_bearer_token(req: &Request) -> Option<String> {
         .and_then(|value| value.strip_prefix("Bearer ").map(|token| token.to_string()))
 }
 
+/// Extract a token from the Authorization header, accepting both `Bearer <token>`
+/// and the bare token Cargo's credential providers send.
+fn extract_token(headers: &HeaderMap) -> Option<&str> {
+    let value = headers.get(header::AUTHORIZATION)?.to_str().ok()?.trim();
+    let token = value.strip_prefix("Bearer ").unwrap_or(value).trim();
+    (!token.is_empty()).then_some(token)
+}
+
+/// Compare tokens without short-circuiting on the first differing byte
+pub(crate) fn tokens_match(expected: &str, provided: &str) -> bool {
+    expected.len() == provided.len()
+        && expected
+            .bytes()
+            .zip(provided.bytes())
+            .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+            == 0
+}
+
+/// Check whether the request headers carry one of the configured API keys
+pub fn is_authorized(config: &Config, headers: &HeaderMap) -> bool {
+    extract_token(headers).is_some_and(|token| {
+        config
+            .security
+            .api_keys
+            .iter()
+            .any(|key| tokens_match(key, token))
+    })
+}
+
+/// Check whether the request may perform `scope` on `registry`.
+///
+/// Admin API keys may do anything; issued tokens and OIDC/LDAP identities only
+/// what their scopes and registry restrictions allow.
+pub fn is_allowed(
+    state: &AppState,
+    headers: &HeaderMap,
+    registry: &str,
+    scope: TokenScope,
+) -> bool {
+    is_authorized(&state.config.load(), headers)
+        || extract_token(headers)
+            .and_then(|token| state.tokens.find(token))
+            .is_some_and(|token| token.allows(scope, registry))
+        || external_auth::find(&state.data_dir, headers)
+            .is_some_and(|identity| identity.allows(scope, registry))
+}
+
+/// Projects an upload credential is allowed to publish
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UploadScope {
+    /// Unrestricted (global API key, or authentication disabled)
+    All,
+    /// Limited to these PEP 503-normalized project names
+    Projects(Vec<String>),
+}
+
+impl UploadScope {
+    /// Whether this scope may upload `project` (a normalized name)
+    pub fn allows(&self, project: &str) -> bool {
+        match self {
+            UploadScope::All => true,
+            UploadScope::Projects(projects) => projects.iter().any(|p| p == project),
+        }
+    }
+}
+
+/// Extract a PyPI API token, accepting twine-style Basic auth with the
+/// `__token__` username as well as Bearer tokens.
+fn extract_pypi_token(headers: &HeaderMap) -> Result<Option<String>, AppError> {
+    let Some(value) = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+    else {
+        return Ok(None);
+    };
+
+    if let Some(encoded) = value.strip_prefix("Basic ") {
+        let decoded = base64::engine::general_purpose::STANDARD.decode(encoded.trim())?;
+        let credentials = String::from_utf8_lossy(&decoded);
+        let (username, password) = credentials.split_once(':').unwrap_or((&credentials, ""));
+        if username != "__token__" {
+            return Err(AppError::Unauthorized(
+                "Use '__token__' as the username with an API token".to_string(),
+            ));
+        }
+        return Ok(Some(password.to_string()));
+    }
+
+    Ok(extract_token(headers).map(str::to_string))
+}
+
+/// The token a request presents, as a Bearer token, a bare Cargo token or a
+/// twine-style `__token__` password
+pub(crate) fn request_token(headers: &HeaderMap) -> Option<String> {
+    extract_pypi_token(headers).ok().flatten()
+}
+
+/// Resolve which PyPI projects the request's credentials may upload.
+///
+/// Global API keys, issued tokens with the `publish` scope and OIDC/LDAP
+/// identities granted it may upload anything; scoped tokens and tokens minted
+/// through trusted publishing are limited to their projects.
+pub fn pypi_upload_scope(
+    config: &Config,
+    tokens: &TokenStore,
+    headers: &HeaderMap,
+) -> Result<UploadScope, AppError> {
+    if !config.security.require_authentication {
+        return Ok(UploadScope::All);
+    }
+    if let Some(identity) = external_auth::find(tokens.registry_dir(), headers) {
+        if identity.allows(TokenScope::Publish, "pypi") {
+            return Ok(UploadScope::All);
+        }
+        return Err(AppError::Unauthorized(format!(
+            "'{}' may not publish to the PyPI registry",
+            identity.name
+        )));
+    }
+
+    let token = extract_pypi_token(headers)?.ok_or_else(|| {
+        AppError::Unauthorized("Missing API token (use username '__token__')".to_string())
+    })?;
+
+    if config
+        .security
+        .api_keys
+        .iter()
+        .any(|key| tokens_match(key, &token))
+    {
+        return Ok(UploadScope::All);
+    }
+    if let Some(issued) = tokens.find(&token) {
+        if issued.allows(TokenScope::Publish, "pypi") {
+            return Ok(UploadScope::All);
+        }
+        return Err(AppError::Unauthorized(
+            "This token may not publish to the PyPI registry".to_string(),
+        ));
+    }
+    if let Some(scoped) = config
+        .security
+        .scoped_tokens
+        .iter()
+        .find(|scoped| tokens_match(&scoped.token, &token))
+    {
+        return Ok(UploadScope::Projects(
+            scoped
+                .projects
+                .iter()
+                .map(|p| normalize_pypi_name(p))
+                .collect(),
+        ));
+    }
+    if let Some(projects) = crate::trusted_publishing::minted_token_projects(&token) {
+        return Ok(UploadScope::Projects(projects));
+    }
+
+    Err(AppError::Unauthorized("Invalid API token".to_string()))
+}
+
+/// The private npm scope `package` belongs to, if any
+pub fn private_npm_scope<'a>(config: &'a Config, package: &str) -> Option<&'a NpmScopeAcl> {
+    config
+        .security
+        .private_npm_scopes
+        .iter()
+        .find(|acl| acl.contains(package))
+}
+
+/// Check that the request may `read` or `publish` an npm package.
+///
+/// Packages outside the private scopes are not restricted here. Inside one, the
+/// request needs an admin API key, or an issued token or OIDC/LDAP identity
+/// granting `scope` on npm whose name the scope lists (any name when the list is
+/// empty). This applies whether or not `require_authentication` is set.
+pub fn check_npm_scope(
+    config: &Config,
+    tokens: &TokenStore,
+    headers: &HeaderMap,
+    package: &str,
+    scope: TokenScope,
+) -> Result<(), AppError> {
+    let Some(acl) = private_npm_scope(config, package) else {
+        return Ok(());
+    };
+    if is_authorized(config, headers) {
+        return Ok(());
+    }
+    let listed = |name: &str| acl.tokens.is_empty() || acl.tokens.iter().any(|t| t == name);
+    if let Some(identity) = external_auth::find(tokens.registry_dir(), headers) {
+        if identity.allows(scope, "npm") && listed(&identity.name) {
+            return Ok(());
+        }
+        return Err(AppError::Unauthorized(format!(
+            "'{}' may not {} packages in the '{}' scope",
+            identity.name,
+            scope_verb(scope),
+            acl.scope
+        )));
+    }
+    let Some(token) = extract_token(headers) else {
+        return Err(AppError::Unauthorized(format!(
+            "Package '{package}' is in a private scope; authenticate with `npm login` or an auth token"
+        )));
+    };
+    let permitted = tokens
+        .find(token)
+        .is_some_and(|token| token.allows(scope, "npm") && listed(&token.name));
+    if permitted {
+        Ok(())
+    } else {
+        Err(AppError::Unauthorized(format!(
+            "This token may not {} packages in the '{}' scope",
+            scope_verb(scope),
+            acl.scope
+        )))
+    }
+}
+
+/// Whether the request may see an npm package, which private scopes restrict
+pub fn may_read_npm(state: &AppState, headers: &HeaderMap, package: &str) -> bool {
+    check_npm_scope(
+        &state.config.load(),
+        &state.tokens,
+        headers,
+        package,
+        TokenScope::Read,
+    )
+    .is_ok()
+}
+
+fn scope_verb(scope: TokenScope) -> &'static str {
+    match scope {
+        TokenScope::Read => "read",
+        TokenScope::Publish => "publish",
+        TokenScope::Delete => "delete",
+    }
+}
+
+/// Middleware guarding the Cargo registry (index, downloads, publish and API).
+///
+/// When authentication is required, every Cargo request must carry a valid token:
+/// issued tokens need the `publish` scope for `cargo publish` and `cargo owner`,
+/// `delete` for `cargo yank` and `read` for everything else. Failures use the crates.io error format plus a `WWW-Authenticate: Cargo`
+/// challenge so Cargo prompts the user to run `cargo login`.
+pub async fn cargo_auth_middleware(
+    State(state): State<Arc<AppState>>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let scope = if req.method() != axum::http::Method::GET && req.uri().path().ends_with("/owners")
+    {
+        TokenScope::Publish
+    } else if req.method() == axum::http::Method::DELETE || req.uri().path().ends_with("/unyank") {
+        TokenScope::Delete
+    } else if req.method() == axum::http::Method::PUT {
+        TokenScope::Publish
+    } else {
+        TokenScope::Read
+    };
+    if !is_auth_required(&state.config.load()) || is_allowed(&state, req.headers(), "cargo", scope)
+    {
+        return next.run(req).await;
+    }
+
+    let login_url = format!("{}/cargo/me", state.server_addr);
+    let detail = if extract_token(req.headers()).is_some() {
+        "invalid registry token"
+    } else {
+        "this registry requires a token; run `cargo login --registry <name>`"
+    };
+    let mut response = (
+        StatusCode::UNAUTHORIZED,
+        Json(json!({ "errors": [{ "detail": detail }] })),
+    )
+        .into_response();
+    if let Ok(challenge) = HeaderValue::from_str(&format!("Cargo login_url=\"{login_url}\"")) {
+        response
+            .headers_mut()
+            .insert(header::WWW_AUTHENTICATE, challenge);
+    }
+    response
+}
+
 /// Middleware to validate authentication for upload endpoints
 pub async fn auth_middleware(
     State(config): State<Arc<Config>>,
@@ -48,5 +353,176 @@ pub async fn auth_middleware(
 
 /// Check if authentication is required based on config
 pub fn is_auth_required(config: &Config) -> bool {
-    config.security.require_authentication && !config.security.api_keys.is_empty()
+    config.security.require_authentication
+        && (!config.security.api_keys.is_empty() || config.security.has_identity_provider())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config_with_key(key: &str) -> Config {
+        let mut config = Config::default();
+        config.security.require_authentication = true;
+        config.security.api_keys = vec![key.to_string()];
+        config
+    }
+
+    fn headers(authorization: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::AUTHORIZATION,
+            HeaderValue::from_str(authorization).unwrap(),
+        );
+        headers
+    }
+
+    #[test]
+    fn accepts_bearer_and_raw_cargo_tokens() {
+        let config = config_with_key("secret-token");
+        assert!(is_authorized(&config, &headers("Bearer secret-token")));
+        assert!(is_authorized(&config, &headers("secret-token")));
+        assert!(!is_authorized(&config, &headers("Bearer other-token")));
+        assert!(!is_authorized(&config, &HeaderMap::new()));
+    }
+
+    #[test]
+    fn pypi_scoped_tokens_limit_projects() {
+        let mut config = config_with_key("global-token");
+        config.security.scoped_tokens = vec![crate::config::ScopedToken {
+            token: "pypi-scoped".to_string(),
+            projects: vec!["My_Package".to_string()],
+        }];
+        let basic = |token: &str| {
+            let encoded =
+                base64::engine::general_purpose::STANDARD.encode(format!("__token__:{token}"));
+            headers(&format!("Basic {encoded}"))
+        };
+        let dir = tempfile::TempDir::new().unwrap();
+        let tokens = TokenStore::open(dir.path()).unwrap();
+
+        assert_eq!(
+            pypi_upload_scope(&config, &tokens, &basic("global-token")).unwrap(),
+            UploadScope::All
+        );
+        let scope = pypi_upload_scope(&config, &tokens, &basic("pypi-scoped")).unwrap();
+        assert!(scope.allows("my-package"));
+        assert!(!scope.allows("other-package"));
+        assert!(pypi_upload_scope(&config, &tokens, &basic("wrong")).is_err());
+
+        let encoded = base64::engine::general_purpose::STANDARD.encode("alice:global-token");
+        assert!(
+            pypi_upload_scope(&config, &tokens, &headers(&format!("Basic {encoded}"))).is_err()
+        );
+    }
+
+    #[test]
+    fn private_npm_scopes_need_a_permitted_token() {
+        let mut config = config_with_key("admin-key");
+        config.security.private_npm_scopes = vec![NpmScopeAcl {
+            scope: "@acme".to_string(),
+            tokens: vec!["ci".to_string()],
+        }];
+        let dir = tempfile::TempDir::new().unwrap();
+        let tokens = TokenStore::open(dir.path()).unwrap();
+        let (_, ci) = tokens
+            .create("ci", vec![TokenScope::Read], vec!["npm".to_string()])
+            .unwrap();
+        let (_, other) = tokens
+            .create("other", vec![TokenScope::Read], vec![])
+            .unwrap();
+        let check = |headers: &HeaderMap, package: &str, scope: TokenScope| {
+            check_npm_scope(&config, &tokens, headers, package, scope).is_ok()
+        };
+        let ci = headers(&format!("Bearer {ci}"));
+
+        assert!(check(&HeaderMap::new(), "left-pad", TokenScope::Read));
+        assert!(check(&HeaderMap::new(), "@other/widgets", TokenScope::Read));
+        assert!(!check(&HeaderMap::new(), "@acme/widgets", TokenScope::Read));
+        assert!(check(
+            &headers("Bearer admin-key"),
+            "@ACME/widgets",
+            TokenScope::Publish
+        ));
+        assert!(check(&ci, "@acme/widgets", TokenScope::Read));
+        assert!(!check(&ci, "@acme/widgets", TokenScope::Publish));
+        assert!(!check(
+            &headers(&format!("Bearer {other}")),
+            "@acme/widgets",
+            TokenScope::Read
+        ));
+    }
+
+    #[test]
+    fn identity_provider_users_get_their_grants() {
+        let mut config = Config::default();
+        config.security.require_authentication = true;
+        config.security.ldap = Some(
+            serde_json::from_value(json!({
+                "url": "ldaps://ldap.example.com",
+                "bind_dn": "uid={username},ou=people,dc=example,dc=com",
+            }))
+            .unwrap(),
+        );
+        config.security.private_npm_scopes = vec![NpmScopeAcl {
+            scope: "@acme".to_string(),
+            tokens: vec!["alice".to_string()],
+        }];
+        assert!(is_auth_required(&config));
+        let dir = tempfile::TempDir::new().unwrap();
+        let tokens = TokenStore::open(dir.path()).unwrap();
+
+        let alice = headers("Basic YWxpY2U6czNjcmV0");
+        let bob = headers("Basic Ym9iOmh1bnRlcjI=");
+        let grants = |scopes: Vec<TokenScope>, registries: Vec<&str>| {
+            vec![external_auth::Grant {
+                scopes,
+                registries: registries.into_iter().map(str::to_string).collect(),
+            }]
+        };
+        external_auth::remember_for_tests(
+            dir.path(),
+            &alice,
+            "alice",
+            grants(vec![TokenScope::Read, TokenScope::Publish], vec![]),
+        );
+        external_auth::remember_for_tests(
+            dir.path(),
+            &bob,
+            "bob",
+            grants(vec![TokenScope::Read], vec!["npm"]),
+        );
+
+        assert_eq!(
+            pypi_upload_scope(&config, &tokens, &alice).unwrap(),
+            UploadScope::All
+        );
+        assert!(pypi_upload_scope(&config, &tokens, &bob).is_err());
+        let check = |headers: &HeaderMap| {
+            check_npm_scope(&config, &tokens, headers, "@acme/widgets", TokenScope::Read).is_ok()
+        };
+        assert!(check(&alice));
+        assert!(!check(&bob));
+    }
+
+    #[test]
+    fn issued_tokens_need_publish_scope_for_pypi() {
+        let config = config_with_key("global-token");
+        let dir = tempfile::TempDir::new().unwrap();
+        let tokens = TokenStore::open(dir.path()).unwrap();
+        let (_, publisher) = tokens
+            .create("ci", vec![TokenScope::Publish], vec!["pypi".to_string()])
+            .unwrap();
+        let (_, reader) = tokens
+            .create("reader", vec![TokenScope::Read], vec![])
+            .unwrap();
+
+        assert_eq!(
+            pypi_upload_scope(&config, &tokens, &headers(&format!("Bearer {publisher}"))).unwrap(),
+            UploadScope::All
+        );
+        assert!(
+            pypi_upload_scope(&config, &tokens, &headers(&format!("Bearer {reader}"))).is_err()
+        );
+    }
 }
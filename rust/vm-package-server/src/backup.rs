@@ -0,0 +1,702 @@
+//! # Backup and restore
+//!
+//! `pkg-server backup <file.tar.zst>` downloads a snapshot of the data directory
+//! from the running server (`GET /api/backup`) and `pkg-server restore` uploads one
+//! (`POST /api/restore`). The server doesn't need to be stopped.
+//!
+//! While a snapshot is taken or restored, [`write_gate`] holds back uploads,
+//! deletions and other writing requests, so the archive never contains half of a
+//! publish. They continue once the snapshot is done. The package index is copied
+//! with SQLite's `VACUUM INTO`, so it is consistent as well.
+//!
+//! An archive is a zstd-compressed tarball holding:
+//!
+//! - `pkg-server-backup.json`: a [`BackupManifest`] listing every file in the
+//!   data directory at the time of the backup
+//! - `index.db`: a copy of the package index, for the download audit trail
+//! - the files themselves, relative to the data directory
+//!
+//! An incremental backup (`?since=<unix time>`, or `--incremental <previous>` on
+//! the CLI) holds only the files modified since then, but still lists every file.
+//! Restoring it on top of the backup it is based on brings back the exact data
+//! directory, including removing files deleted in between.
+//!
+//! The upstream cache is left out unless `include_cache` is set, because it can be
+//! fetched again. `config.json` and `tokens.json` (the admin API keys and token
+//! hashes, for the main registry and each virtual registry), the self-signed
+//! certificate in `tls/` and a configured `tls.key_path` inside the data directory
+//! are left out unless `include_secrets` is set; a restore then leaves the server's
+//! own copies alone.
+//!
+//! Both routes need an admin API key.
+
+use crate::config::TlsConfig;
+use crate::lifecycle::PID_FILE;
+use crate::package_index::{PackageIndex, INDEX_FILE};
+use crate::state::AppState;
+use crate::{tokens, AppError, AppResult};
+use axum::body::Body;
+use axum::extract::{Query, Request, State};
+use axum::http::{header, HeaderMap, Method};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use futures_util::StreamExt;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::io::{Read, Seek, Write};
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::sync::RwLock;
+use tracing::{info, warn};
+
+/// Route that streams a backup archive
+pub const BACKUP_ROUTE: &str = "/api/backup";
+
+/// Route that restores an uploaded backup archive
+pub const RESTORE_ROUTE: &str = "/api/restore";
+
+/// Name of the manifest inside an archive
+pub const MANIFEST_NAME: &str = "pkg-server-backup.json";
+
+/// Archive layout version written into the manifest
+const FORMAT_VERSION: u32 = 1;
+
+/// zstd compression level
+const COMPRESSION_LEVEL: i32 = 3;
+
+/// Held for reading by writing requests and for writing by backup and restore
+static WRITES: Lazy<RwLock<()>> = Lazy::new(|| RwLock::new(()));
+
+/// Describes the contents of a backup archive
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupManifest {
+    pub format: u32,
+    /// Version of the server that wrote the backup
+    pub server_version: String,
+    /// When the backup was taken, in seconds since the Unix epoch
+    pub created: i64,
+    /// For incremental backups, the time files had to be modified after
+    pub since: Option<i64>,
+    /// Whether the upstream cache is included
+    pub include_cache: bool,
+    /// Whether `config.json`, `tokens.json` and TLS keys are included
+    #[serde(default)]
+    pub include_secrets: bool,
+    /// Every file in the data directory, relative to it, including those an
+    /// incremental backup doesn't contain
+    pub files: Vec<String>,
+}
+
+/// Query parameters of `GET /api/backup`
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct BackupOptions {
+    /// Only include files modified at or after this time (seconds since the Unix epoch)
+    pub since: Option<i64>,
+    /// Include the upstream cache
+    #[serde(default)]
+    pub include_cache: bool,
+    /// Include `config.json` and `tokens.json`, which hold the admin API keys, and
+    /// the TLS private key
+    #[serde(default)]
+    pub include_secrets: bool,
+    /// The server's `tls.key_path` relative to the data directory, if it lies inside
+    /// it. Set by the server, not by the request.
+    #[serde(skip)]
+    pub tls_key: Option<PathBuf>,
+}
+
+/// Outcome of a restore
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct RestoreReport {
+    /// When the restored backup was taken
+    pub created: i64,
+    pub incremental: bool,
+    /// Files written from the archive
+    pub restored: usize,
+    /// Files removed because the backup doesn't list them
+    pub removed: usize,
+}
+
+/// Middleware that makes writing requests wait while a backup or restore runs
+pub async fn write_gate(req: Request, next: Next) -> Response {
+    let path = req.uri().path();
+    let reads_only = matches!(*req.method(), Method::GET | Method::HEAD | Method::OPTIONS);
+    if reads_only || path == BACKUP_ROUTE || path == RESTORE_ROUTE {
+        return next.run(req).await;
+    }
+    let _writes = WRITES.read().await;
+    next.run(req).await
+}
+
+/// Whether `relative` holds API keys, token hashes or a TLS private key:
+/// `config.json` or `tokens.json` of the main registry or of a virtual registry,
+/// the self-signed certificate directory, or the configured `tls_key`
+fn is_secret(relative: &Path, tls_key: Option<&Path>) -> bool {
+    if tls_key == Some(relative)
+        || relative.components().next()
+            == Some(Component::Normal(crate::tls::SELF_SIGNED_DIR.as_ref()))
+    {
+        return true;
+    }
+    let parts: Vec<_> = relative.components().collect();
+    let top_level = match parts.as_slice() {
+        [_] => true,
+        [Component::Normal(tenants), _, _] => *tenants == "tenants",
+        _ => false,
+    };
+    top_level
+        && relative
+            .file_name()
+            .is_some_and(|name| name == "config.json" || name == "tokens.json")
+}
+
+/// `tls.key_path` relative to `data_dir`, when the key is stored inside it
+pub fn tls_key_in(data_dir: &Path, tls: &TlsConfig) -> Option<PathBuf> {
+    let key = data_dir.join(tls.key_path.as_ref()?);
+    if let Ok(relative) = key.strip_prefix(data_dir) {
+        return Some(relative.to_path_buf());
+    }
+    let key = key.canonicalize().ok()?;
+    let data_dir = data_dir.canonicalize().ok()?;
+    key.strip_prefix(data_dir).ok().map(Path::to_path_buf)
+}
+
+/// Whether `relative` is left out of backups and untouched by restores
+fn excluded(relative: &Path, options: &BackupOptions) -> bool {
+    let Some(Component::Normal(first)) = relative.components().next() else {
+        return true;
+    };
+    let first = first.to_string_lossy();
+    let name = relative
+        .file_name()
+        .map(|name| name.to_string_lossy())
+        .unwrap_or_default();
+    // Dot entries hold staging directories of in-progress restores
+    first.starts_with('.')
+        || (first == crate::cache::CACHE_DIR && !options.include_cache)
+        || (!options.include_secrets && is_secret(relative, options.tls_key.as_deref()))
+        || (relative.components().count() == 1
+            && (name == PID_FILE || name.starts_with(INDEX_FILE)))
+        || name.ends_with(".tmp")
+}
+
+/// Files in the data directory as `(relative path, modification time)`, sorted
+fn data_files(data_dir: &Path, options: &BackupOptions) -> AppResult<Vec<(String, i64)>> {
+    fn walk(
+        dir: &Path,
+        data_dir: &Path,
+        options: &BackupOptions,
+        files: &mut Vec<(String, i64)>,
+    ) -> AppResult<()> {
+        for entry in std::fs::read_dir(dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            let Ok(relative) = path.strip_prefix(data_dir) else {
+                continue;
+            };
+            if excluded(relative, options) {
+                continue;
+            }
+            let file_type = entry.file_type()?;
+            if file_type.is_dir() {
+                walk(&path, data_dir, options, files)?;
+            } else if file_type.is_file() {
+                let modified = entry
+                    .metadata()?
+                    .modified()
+                    .ok()
+                    .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                    .map(|elapsed| elapsed.as_secs() as i64)
+                    .unwrap_or(0);
+                let name: Vec<_> = relative
+                    .components()
+                    .map(|part| part.as_os_str().to_string_lossy())
+                    .collect();
+                files.push((name.join("/"), modified));
+            }
+        }
+        Ok(())
+    }
+
+    let mut files = Vec::new();
+    if data_dir.is_dir() {
+        walk(data_dir, data_dir, options, &mut files)?;
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Write a backup of `data_dir` to `out`
+pub fn write_backup<W: Write>(
+    data_dir: &Path,
+    index: &PackageIndex,
+    out: W,
+    options: &BackupOptions,
+) -> AppResult<BackupManifest> {
+    let created = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs() as i64)
+        .unwrap_or(0);
+    let files = data_files(data_dir, options)?;
+    let manifest = BackupManifest {
+        format: FORMAT_VERSION,
+        server_version: env!("CARGO_PKG_VERSION").to_string(),
+        created,
+        since: options.since,
+        include_cache: options.include_cache,
+        include_secrets: options.include_secrets,
+        files: files.iter().map(|(name, _)| name.clone()).collect(),
+    };
+
+    let staging = tempfile::Builder::new()
+        .prefix(".backup-")
+        .tempdir_in(data_dir)?;
+    let index_copy = staging.path().join(INDEX_FILE);
+    index.snapshot(&index_copy)?;
+
+    let encoder = zstd::stream::write::Encoder::new(out, COMPRESSION_LEVEL)?;
+    let mut archive = tar::Builder::new(encoder);
+    let manifest_json = serde_json::to_vec_pretty(&manifest)?;
+    let mut header = tar::Header::new_gnu();
+    header.set_size(manifest_json.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(created as u64);
+    archive.append_data(&mut header, MANIFEST_NAME, manifest_json.as_slice())?;
+    archive.append_path_with_name(&index_copy, INDEX_FILE)?;
+    for (name, modified) in &files {
+        if options.since.is_some_and(|since| *modified < since) {
+            continue;
+        }
+        archive.append_path_with_name(data_dir.join(name), name)?;
+    }
+    archive.into_inner()?.finish()?;
+    Ok(manifest)
+}
+
+/// Read the manifest of a backup archive
+pub fn read_manifest<R: Read>(input: R) -> AppResult<BackupManifest> {
+    let mut archive = tar::Archive::new(zstd::stream::read::Decoder::new(input)?);
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        if entry.path()?.as_ref() == Path::new(MANIFEST_NAME) {
+            let mut content = Vec::new();
+            entry.read_to_end(&mut content)?;
+            return Ok(serde_json::from_slice(&content)?);
+        }
+    }
+    Err(AppError::BadRequest(format!(
+        "Not a package server backup: {MANIFEST_NAME} is missing"
+    )))
+}
+
+/// Restore the backup in `input` into `data_dir`.
+///
+/// `tls_key` is the server's own private key (see [`tls_key_in`]), which is left
+/// alone unless the backup includes secrets.
+pub fn restore_backup<R: Read>(
+    data_dir: &Path,
+    index: &PackageIndex,
+    input: R,
+    tls_key: Option<&Path>,
+) -> AppResult<RestoreReport> {
+    std::fs::create_dir_all(data_dir)?;
+    let staging = tempfile::Builder::new()
+        .prefix(".restore-")
+        .tempdir_in(data_dir)?;
+    let mut archive = tar::Archive::new(zstd::stream::read::Decoder::new(input)?);
+    let mut staged = Vec::new();
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let path = entry.path()?.into_owned();
+        if path
+            .components()
+            .any(|part| !matches!(part, Component::Normal(_)))
+        {
+            return Err(AppError::BadRequest(format!(
+                "Backup contains an unsafe path: {}",
+                path.display()
+            )));
+        }
+        let target = staging.path().join(&path);
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        entry.unpack(&target)?;
+        staged.push(path);
+    }
+
+    let manifest: BackupManifest = std::fs::read(staging.path().join(MANIFEST_NAME))
+        .map_err(|_| {
+            AppError::BadRequest(format!(
+                "Not a package server backup: {MANIFEST_NAME} is missing"
+            ))
+        })
+        .and_then(|content| Ok(serde_json::from_slice(&content)?))?;
+    if manifest.format != FORMAT_VERSION {
+        return Err(AppError::BadRequest(format!(
+            "Unsupported backup format {}",
+            manifest.format
+        )));
+    }
+
+    let listed: HashSet<&str> = manifest.files.iter().map(String::as_str).collect();
+    let missing = manifest
+        .files
+        .iter()
+        .filter(|name| !staging.path().join(name).is_file() && !data_dir.join(name).is_file())
+        .count();
+    if missing > 0 {
+        return Err(AppError::BadRequest(format!(
+            "{missing} files listed by this incremental backup are neither in it nor on disk; \
+             restore the backup it is based on first"
+        )));
+    }
+
+    let contents = BackupOptions {
+        since: manifest.since,
+        include_cache: manifest.include_cache,
+        include_secrets: manifest.include_secrets,
+        tls_key: tls_key.map(Path::to_path_buf),
+    };
+    let mut restored = 0;
+    for path in &staged {
+        let name = path.to_string_lossy();
+        if name == MANIFEST_NAME || name == INDEX_FILE {
+            continue;
+        }
+        if excluded(path, &contents) {
+            continue;
+        }
+        let target = data_dir.join(path);
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::rename(staging.path().join(path), &target)?;
+        restored += 1;
+    }
+
+    let mut removed = 0;
+    for (name, _) in data_files(data_dir, &contents)? {
+        if !listed.contains(name.as_str()) {
+            std::fs::remove_file(data_dir.join(&name))?;
+            removed += 1;
+        }
+    }
+
+    let index_copy = staging.path().join(INDEX_FILE);
+    if index_copy.is_file() {
+        index.restore(&index_copy)?;
+    } else {
+        index.rebuild_all()?;
+    }
+
+    Ok(RestoreReport {
+        created: manifest.created,
+        incremental: manifest.since.is_some(),
+        restored,
+        removed,
+    })
+}
+
+/// Stream a backup archive of the data directory
+///
+/// # Route
+/// `GET /api/backup?since=<unix time>&include_cache=true`
+pub async fn backup_handler(
+    State(state): State<Arc<AppState>>,
+    Query(mut options): Query<BackupOptions>,
+    headers: HeaderMap,
+) -> AppResult<Response> {
+    tokens::require_admin(&state, &headers)?;
+    options.tls_key = tls_key_in(&state.data_dir, &state.config.load().tls);
+
+    let writes = WRITES.write().await;
+    let snapshot_state = state.clone();
+    let (file, manifest) = tokio::task::spawn_blocking(move || -> AppResult<_> {
+        let mut file = tempfile::tempfile_in(&snapshot_state.data_dir)?;
+        let manifest = write_backup(
+            &snapshot_state.data_dir,
+            &snapshot_state.package_index,
+            &mut file,
+            &options,
+        )?;
+        file.rewind()?;
+        Ok((file, manifest))
+    })
+    .await
+    .map_err(|e| AppError::InternalError(format!("Backup task failed: {e}")))??;
+    drop(writes);
+    info!(
+        files = manifest.files.len(),
+        incremental = manifest.since.is_some(),
+        "Backup snapshot taken"
+    );
+
+    let file = tokio::fs::File::from_std(file);
+    let stream = futures_util::stream::unfold(file, |mut file| async move {
+        let mut chunk = vec![0; 64 * 1024];
+        match file.read(&mut chunk).await {
+            Ok(0) => None,
+            Ok(read) => {
+                chunk.truncate(read);
+                Some((Ok::<_, std::io::Error>(chunk), file))
+            }
+            Err(e) => Some((Err(e), file)),
+        }
+    });
+    let filename = chrono::DateTime::from_timestamp(manifest.created, 0)
+        .map(|time| {
+            time.format("pkg-server-backup-%Y%m%d-%H%M%S.tar.zst")
+                .to_string()
+        })
+        .unwrap_or_else(|| "pkg-server-backup.tar.zst".to_string());
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/zstd".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{filename}\""),
+            ),
+        ],
+        Body::from_stream(stream),
+    )
+        .into_response())
+}
+
+/// Restore an uploaded backup archive
+///
+/// # Route
+/// `POST /api/restore`
+pub async fn restore_handler(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    body: Body,
+) -> AppResult<Json<RestoreReport>> {
+    tokens::require_admin(&state, &headers)?;
+
+    let upload = tempfile::tempfile_in(&state.data_dir)?;
+    let mut upload = tokio::fs::File::from_std(upload);
+    let mut stream = body.into_data_stream();
+    while let Some(chunk) = stream.next().await {
+        let chunk =
+            chunk.map_err(|e| AppError::BadRequest(format!("Failed to read upload: {e}")))?;
+        upload.write_all(&chunk).await?;
+    }
+    let mut upload = upload.into_std().await;
+    upload.rewind()?;
+
+    let _writes = WRITES.write().await;
+    let restore_state = state.clone();
+    let tls_key = tls_key_in(&state.data_dir, &state.config.load().tls);
+    let report = tokio::task::spawn_blocking(move || {
+        restore_backup(
+            &restore_state.data_dir,
+            &restore_state.package_index,
+            upload,
+            tls_key.as_deref(),
+        )
+    })
+    .await
+    .map_err(|e| AppError::InternalError(format!("Restore task failed: {e}")))??;
+    if let Err(e) = state.tokens.reload() {
+        warn!(error = %e, "Failed to reload API tokens after restore");
+    }
+    info!(
+        restored = report.restored,
+        removed = report.removed,
+        "Backup restored"
+    );
+    Ok(Json(report))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write(dir: &Path, name: &str, content: &str) {
+        let path = dir.join(name);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+
+    fn backup(dir: &Path, options: &BackupOptions) -> (Vec<u8>, BackupManifest) {
+        let index = PackageIndex::open(dir).unwrap();
+        let mut archive = Vec::new();
+        let manifest = write_backup(dir, &index, &mut archive, options).unwrap();
+        (archive, manifest)
+    }
+
+    #[test]
+    fn test_backup_and_restore_round_trip() {
+        let source = TempDir::new().unwrap();
+        write(source.path(), "pypi/packages/demo-1.0.0.tar.gz", "sdist");
+        write(
+            source.path(),
+            "tenants/team/pypi/packages/team-1.0.tar.gz",
+            "sdist",
+        );
+        write(
+            source.path(),
+            "cache/npm/left-pad/1.0.0/left-pad-1.0.0.tgz",
+            "cached",
+        );
+        write(source.path(), PID_FILE, "123");
+        let (archive, manifest) = backup(source.path(), &BackupOptions::default());
+        assert_eq!(
+            manifest.files,
+            vec![
+                "pypi/packages/demo-1.0.0.tar.gz",
+                "tenants/team/pypi/packages/team-1.0.tar.gz"
+            ]
+        );
+        assert_eq!(
+            read_manifest(archive.as_slice()).unwrap().files,
+            manifest.files
+        );
+
+        let target = TempDir::new().unwrap();
+        write(target.path(), "npm/metadata/stale.json", "{}");
+        write(target.path(), "cache/pypi/kept/1.0/kept-1.0.whl", "cached");
+        let index = PackageIndex::open(target.path()).unwrap();
+        let report = restore_backup(target.path(), &index, archive.as_slice(), None).unwrap();
+        assert_eq!((report.restored, report.removed), (2, 1));
+        assert!(!report.incremental);
+        assert_eq!(
+            std::fs::read_to_string(target.path().join("pypi/packages/demo-1.0.0.tar.gz")).unwrap(),
+            "sdist"
+        );
+        assert!(!target.path().join("npm/metadata/stale.json").exists());
+        assert!(target
+            .path()
+            .join("cache/pypi/kept/1.0/kept-1.0.whl")
+            .exists());
+        assert_eq!(index.list("pypi").unwrap(), vec!["demo"]);
+    }
+
+    #[test]
+    fn test_incremental_backup_needs_its_base() {
+        let source = TempDir::new().unwrap();
+        write(source.path(), "npm/metadata/old.json", "{}");
+        let future = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64
+            + 3600;
+        let (archive, manifest) = backup(
+            source.path(),
+            &BackupOptions {
+                since: Some(future),
+                ..Default::default()
+            },
+        );
+        assert_eq!(manifest.files, vec!["npm/metadata/old.json"]);
+
+        let empty = TempDir::new().unwrap();
+        let index = PackageIndex::open(empty.path()).unwrap();
+        let result = restore_backup(empty.path(), &index, archive.as_slice(), None);
+        assert!(matches!(result, Err(AppError::BadRequest(_))));
+
+        let base = TempDir::new().unwrap();
+        write(base.path(), "npm/metadata/old.json", "{}");
+        write(base.path(), "npm/metadata/deleted.json", "{}");
+        let index = PackageIndex::open(base.path()).unwrap();
+        let report = restore_backup(base.path(), &index, archive.as_slice(), None).unwrap();
+        assert!(report.incremental);
+        assert_eq!((report.restored, report.removed), (0, 1));
+        assert!(base.path().join("npm/metadata/old.json").exists());
+    }
+
+    #[test]
+    fn test_secrets_are_only_backed_up_on_request() {
+        let source = TempDir::new().unwrap();
+        write(
+            source.path(),
+            "config.json",
+            r#"{"security":{"api_keys":["old"]}}"#,
+        );
+        write(source.path(), "tokens.json", "[]");
+        write(source.path(), "tenants/team/config.json", "{}");
+        write(source.path(), "npm/metadata/widget.json", "{}");
+        let (archive, manifest) = backup(source.path(), &BackupOptions::default());
+        assert_eq!(manifest.files, vec!["npm/metadata/widget.json"]);
+
+        let target = TempDir::new().unwrap();
+        write(
+            target.path(),
+            "config.json",
+            r#"{"security":{"api_keys":["new"]}}"#,
+        );
+        write(target.path(), "tokens.json", "[{}]");
+        let index = PackageIndex::open(target.path()).unwrap();
+        let report = restore_backup(target.path(), &index, archive.as_slice(), None).unwrap();
+        assert_eq!((report.restored, report.removed), (1, 0));
+        assert!(std::fs::read_to_string(target.path().join("config.json"))
+            .unwrap()
+            .contains("new"));
+
+        let (archive, manifest) = backup(
+            source.path(),
+            &BackupOptions {
+                include_secrets: true,
+                ..Default::default()
+            },
+        );
+        assert_eq!(
+            manifest.files,
+            vec![
+                "config.json",
+                "npm/metadata/widget.json",
+                "tenants/team/config.json",
+                "tokens.json"
+            ]
+        );
+        restore_backup(target.path(), &index, archive.as_slice(), None).unwrap();
+        assert!(std::fs::read_to_string(target.path().join("config.json"))
+            .unwrap()
+            .contains("old"));
+    }
+
+    #[test]
+    fn test_tls_keys_are_only_backed_up_on_request() {
+        let source = TempDir::new().unwrap();
+        write(source.path(), "tls/cert.pem", "cert");
+        write(source.path(), "tls/key.pem", "self-signed key");
+        write(source.path(), "certs/privkey.pem", "configured key");
+        write(source.path(), "certs/fullchain.pem", "chain");
+        let tls = TlsConfig {
+            cert_path: Some("certs/fullchain.pem".into()),
+            key_path: Some("certs/privkey.pem".into()),
+            ..Default::default()
+        };
+        let tls_key = tls_key_in(source.path(), &tls);
+        assert_eq!(tls_key, Some(PathBuf::from("certs/privkey.pem")));
+
+        let (_, manifest) = backup(
+            source.path(),
+            &BackupOptions {
+                tls_key: tls_key.clone(),
+                ..Default::default()
+            },
+        );
+        assert_eq!(manifest.files, vec!["certs/fullchain.pem"]);
+
+        let (_, manifest) = backup(
+            source.path(),
+            &BackupOptions {
+                include_secrets: true,
+                tls_key,
+                ..Default::default()
+            },
+        );
+        assert!(manifest.files.contains(&"tls/key.pem".to_string()));
+        assert!(manifest.files.contains(&"certs/privkey.pem".to_string()));
+    }
+}
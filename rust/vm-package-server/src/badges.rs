@@ -0,0 +1,297 @@
+//! # Latest versions and badges
+//!
+//! Internal READMEs and dashboards can show the current version of a package
+//! published to this server:
+//!
+//! ```text
+//! GET /api/npm/packages/left-pad/latest
+//! {"registry": "npm", "package": "left-pad", "version": "1.3.0", "prerelease": false}
+//!
+//! ![left-pad](http://packages.internal:3080/badge/npm/left-pad.svg)
+//! ```
+//!
+//! Both work for `pypi`, `npm` and `cargo` packages. The latest version is the
+//! highest one that is not a pre-release (one with letters or a `-` in it, like
+//! `2.0.0-beta.1` or `2.0rc1`); only when a package has nothing else is its
+//! highest pre-release reported.
+//!
+//! Scoped npm names are URL-encoded in the API path (`@acme%2Fui`) while the
+//! badge path takes them as they are (`/badge/npm/@acme/ui`). The `.svg` suffix
+//! of a badge is optional and `?label=` replaces the package name on its left.
+//! Unknown packages get a grey "not found" badge rather than an error so a
+//! broken image never shows up in a README.
+
+use crate::tokens::TokenScope;
+use crate::{auth, AppError, AppResult, AppState};
+use axum::extract::{Path, Query, State};
+use axum::http::{header, HeaderMap};
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// Registries with versioned packages. Go modules and Helm charts are left out
+/// because [`crate::registry::package_versions`] only reads PyPI, npm and Cargo
+/// metadata.
+const REGISTRIES: &[&str] = &["pypi", "npm", "cargo"];
+
+/// Badges are cheap to render but READMEs are viewed often
+const BADGE_CACHE_CONTROL: &str = "public, max-age=300";
+
+/// Response of `GET /api/{registry}/packages/{name}/latest`
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct LatestVersion {
+    pub registry: String,
+    pub package: String,
+    pub version: String,
+    /// Whether `version` is a pre-release, because no stable version exists
+    pub prerelease: bool,
+}
+
+/// Query parameters of a badge
+#[derive(Debug, Default, Deserialize)]
+pub struct BadgeQuery {
+    /// Text on the left of the badge, the package name by default
+    label: Option<String>,
+}
+
+fn is_prerelease(version: &str) -> bool {
+    version.contains(|c: char| c == '-' || c.is_ascii_alphabetic())
+}
+
+/// The name a package is stored under
+fn stored_name(registry: &str, package: &str) -> String {
+    match registry {
+        "pypi" => crate::normalize_pypi_name(package),
+        "cargo" => package.to_lowercase(),
+        _ => package.to_string(),
+    }
+}
+
+/// Newest published version of `package`, or `None` if it has none
+async fn find_latest(
+    state: &AppState,
+    headers: &HeaderMap,
+    registry: &str,
+    package: &str,
+) -> AppResult<Option<LatestVersion>> {
+    if !REGISTRIES.contains(&registry) {
+        return Err(AppError::BadRequest(format!(
+            "Unknown registry '{registry}'; expected one of {}",
+            REGISTRIES.join(", ")
+        )));
+    }
+    if package
+        .split('/')
+        .any(|part| part.is_empty() || part == "..")
+        || package.contains('\\')
+    {
+        return Err(AppError::BadRequest(format!(
+            "Invalid package name '{package}'"
+        )));
+    }
+    if registry == "npm" {
+        auth::check_npm_scope(
+            &state.config.load(),
+            &state.tokens,
+            headers,
+            package,
+            TokenScope::Read,
+        )?;
+    }
+
+    let name = stored_name(registry, package);
+    let versions: Vec<String> = crate::registry::package_versions(state, registry, &name)
+        .await?
+        .into_iter()
+        .collect();
+    let stable: Vec<String> = versions
+        .iter()
+        .filter(|version| !is_prerelease(version))
+        .cloned()
+        .collect();
+    let latest = crate::registry::search::latest_version(&stable)
+        .or_else(|| crate::registry::search::latest_version(&versions));
+
+    Ok(latest.map(|version| LatestVersion {
+        registry: registry.to_string(),
+        package: name,
+        prerelease: is_prerelease(&version),
+        version,
+    }))
+}
+
+/// Newest published version of a package
+///
+/// # Route
+/// `GET /api/{registry}/packages/{name}/latest`
+pub async fn latest_handler(
+    Path((registry, package)): Path<(String, String)>,
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> AppResult<Json<LatestVersion>> {
+    find_latest(&state, &headers, &registry, &package)
+        .await?
+        .map(Json)
+        .ok_or_else(|| AppError::NotFound(format!("No published versions of {package}")))
+}
+
+/// SVG badge showing the newest published version of a package
+///
+/// # Route
+/// `GET /badge/{registry}/{*name}`
+pub async fn badge_handler(
+    Path((registry, package)): Path<(String, String)>,
+    Query(query): Query<BadgeQuery>,
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> AppResult<Response> {
+    let package = package.strip_suffix(".svg").unwrap_or(&package);
+    let (value, color) = match find_latest(&state, &headers, &registry, package).await? {
+        Some(latest) if latest.prerelease => (format!("v{}", latest.version), "#fe7d37"),
+        Some(latest) => (format!("v{}", latest.version), "#007ec6"),
+        None => ("not found".to_string(), "#9f9f9f"),
+    };
+    let label = query.label.as_deref().unwrap_or(package);
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "image/svg+xml"),
+            (header::CACHE_CONTROL, BADGE_CACHE_CONTROL),
+        ],
+        render_badge(label, &value, color),
+    )
+        .into_response())
+}
+
+/// Approximate rendered width of `text` in 11px Verdana
+fn text_width(text: &str) -> usize {
+    text.chars()
+        .map(|c| match c {
+            'i' | 'j' | 'l' | '.' | ',' | ':' | ';' | '!' | '|' | '\'' => 4,
+            'm' | 'w' | 'M' | 'W' | '@' => 10,
+            c if c.is_ascii_uppercase() => 8,
+            _ => 7,
+        })
+        .sum()
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// A flat two-part badge: `label` on grey, `value` on `color`
+fn render_badge(label: &str, value: &str, color: &str) -> String {
+    let label_width = text_width(label) + 10;
+    let value_width = text_width(value) + 10;
+    let width = label_width + value_width;
+    let label_x = label_width / 2;
+    let value_x = label_width + value_width / 2;
+    let (label, value) = (escape_xml(label), escape_xml(value));
+
+    format!(
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="20" role="img" aria-label="{label}: {value}"><title>{label}: {value}</title><linearGradient id="s" x2="0" y2="100%"><stop offset="0" stop-color="#bbb" stop-opacity=".1"/><stop offset="1" stop-opacity=".1"/></linearGradient><clipPath id="r"><rect width="{width}" height="20" rx="3" fill="#fff"/></clipPath><g clip-path="url(#r)"><rect width="{label_width}" height="20" fill="#555"/><rect x="{label_width}" width="{value_width}" height="20" fill="{color}"/><rect width="{width}" height="20" fill="url(#s)"/></g><g fill="#fff" text-anchor="middle" font-family="Verdana,Geneva,DejaVu Sans,sans-serif" font-size="11"><text x="{label_x}" y="15" fill="#010101" fill-opacity=".3">{label}</text><text x="{label_x}" y="14">{label}</text><text x="{value_x}" y="15" fill="#010101" fill-opacity=".3">{value}</text><text x="{value_x}" y="14">{value}</text></g></svg>"##
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn publish_npm(state: &AppState, package: &str, versions: &[&str]) {
+        let versions: serde_json::Map<String, serde_json::Value> = versions
+            .iter()
+            .map(|version| {
+                (
+                    version.to_string(),
+                    serde_json::json!({ "dist": { "tarball": format!("x/{package}-{version}.tgz") } }),
+                )
+            })
+            .collect();
+        let path = state
+            .data_dir
+            .join("npm/metadata")
+            .join(format!("{package}.json"));
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(
+            path,
+            serde_json::json!({ "versions": versions }).to_string(),
+        )
+        .unwrap();
+    }
+
+    async fn badge(state: &Arc<AppState>, package: &str, label: Option<&str>) -> String {
+        let response = badge_handler(
+            Path(("npm".to_string(), package.to_string())),
+            Query(BadgeQuery {
+                label: label.map(str::to_string),
+            }),
+            State(state.clone()),
+            HeaderMap::new(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "image/svg+xml");
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        String::from_utf8(body.to_vec()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_latest_prefers_stable_versions() {
+        let (state, _dir) = crate::test_utils::create_npm_test_state();
+        publish_npm(&state, "widget", &["1.2.0", "1.10.0", "2.0.0-beta.1"]);
+        publish_npm(&state, "@acme/ui", &["0.1.0-rc.1"]);
+
+        let Json(latest) = latest_handler(
+            Path(("npm".to_string(), "widget".to_string())),
+            State(state.clone()),
+            HeaderMap::new(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(latest.version, "1.10.0");
+        assert!(!latest.prerelease);
+
+        let Json(latest) = latest_handler(
+            Path(("npm".to_string(), "@acme/ui".to_string())),
+            State(state.clone()),
+            HeaderMap::new(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(latest.version, "0.1.0-rc.1");
+        assert!(latest.prerelease);
+
+        for (registry, package) in [("npm", "missing"), ("npm", "../secret"), ("helm", "x")] {
+            let result = latest_handler(
+                Path((registry.to_string(), package.to_string())),
+                State(state.clone()),
+                HeaderMap::new(),
+            )
+            .await;
+            assert!(result.is_err(), "{registry}/{package} should fail");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_badge_shows_version_or_not_found() {
+        let (state, _dir) = crate::test_utils::create_npm_test_state();
+        publish_npm(&state, "widget", &["1.0.0"]);
+
+        let svg = badge(&state, "widget.svg", None).await;
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.contains("<title>widget: v1.0.0</title>"));
+
+        let svg = badge(&state, "widget", Some("<b>&")).await;
+        assert!(svg.contains("<title>&lt;b&gt;&amp;: v1.0.0</title>"));
+
+        let svg = badge(&state, "missing", None).await;
+        assert!(svg.contains("missing: not found"));
+    }
+}
@@ -0,0 +1,441 @@
+//! # Upstream Package Cache
+//!
+//! PyPI files, npm tarballs and crates fetched from upstream registries are kept
+//! under `cache/<registry>/<package>/<version>/<file>` in the data directory, so
+//! repeat installs don't hit the network. Uploaded packages live elsewhere and are
+//! never touched by garbage collection.
+//!
+//! Serving a cached file refreshes its modification time, which garbage collection
+//! uses as the last-used time. A run applies the [`CacheConfig`] policies in order:
+//!
+//! 1. Files unused for longer than `max_age_days` are removed
+//! 2. Versions beyond the newest `keep_latest` of each package are removed
+//! 3. The least recently used files are evicted until the cache fits in `max_size_mb`
+//!
+//! Versions pinned by a mirror manifest sync are never collected. Collection runs
+//! in the background every `gc_interval_minutes` and on demand with `pkg-server gc`.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use serde::Serialize;
+use tracing::{debug, info, warn};
+
+use crate::config::CacheConfig;
+use crate::{storage, AppResult, AppState, Config};
+
+/// Cache directory inside the data directory
+pub const CACHE_DIR: &str = "cache";
+
+/// Where a cached upstream file lives, or `None` if a path component is unsafe
+pub fn cached_path(
+    data_dir: &Path,
+    registry: &str,
+    package: &str,
+    version: &str,
+    filename: &str,
+) -> Option<PathBuf> {
+    // Scoped npm packages (`@types/node`) keep one directory per package
+    let package = package.replace('/', "%2F");
+    let safe =
+        |part: &str| !part.is_empty() && !part.starts_with('.') && !part.contains(['/', '\\']);
+    if !(safe(&package) && safe(version) && safe(filename)) {
+        return None;
+    }
+    Some(
+        data_dir
+            .join(CACHE_DIR)
+            .join(registry)
+            .join(package)
+            .join(version)
+            .join(filename),
+    )
+}
+
+/// Read a cached file, marking it as recently used
+pub async fn read(
+    state: &AppState,
+    registry: &str,
+    package: &str,
+    version: &str,
+    filename: &str,
+) -> Option<Vec<u8>> {
+    if !state.config.load().cache.enabled
+        || !state
+            .upstream_client
+            .load()
+            .policy()
+            .allows(registry, package)
+    {
+        return None;
+    }
+    let path = cached_path(&state.data_dir, registry, package, version, filename)?;
+    if !path.exists() {
+        return None;
+    }
+    let data = storage::read_file(&path).await.ok()?;
+    if let Err(e) = fs::File::options()
+        .write(true)
+        .open(&path)
+        .and_then(|file| file.set_modified(SystemTime::now()))
+    {
+        debug!(path = %path.display(), error = %e, "Could not refresh cache timestamp");
+    }
+    Some(data)
+}
+
+/// Keep a file fetched from upstream; failures are logged and otherwise ignored
+pub async fn store(
+    state: &AppState,
+    registry: &str,
+    package: &str,
+    version: &str,
+    filename: &str,
+    data: &[u8],
+) {
+    if !state.config.load().cache.enabled {
+        return;
+    }
+    let Some(path) = cached_path(&state.data_dir, registry, package, version, filename) else {
+        return;
+    };
+    match storage::save_file(&path, data).await {
+        Ok(()) => {
+            debug!(registry = %registry, package = %package, version = %version, "Cached upstream file")
+        }
+        Err(e) => warn!(path = %path.display(), error = %e, "Failed to cache upstream file"),
+    }
+}
+
+/// A file in the cache
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CachedFile {
+    pub registry: String,
+    pub package: String,
+    pub version: String,
+    pub path: PathBuf,
+    pub size: u64,
+    /// Last time the file was stored or served
+    pub modified: SystemTime,
+}
+
+/// Outcome of a garbage collection run
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct GcReport {
+    pub removed_files: usize,
+    pub freed_bytes: u64,
+    pub remaining_files: usize,
+    pub remaining_bytes: u64,
+}
+
+/// Every file in the cache
+pub fn scan(data_dir: &Path) -> Vec<CachedFile> {
+    fn dirs(path: &Path) -> Vec<(String, PathBuf)> {
+        fs::read_dir(path)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter_map(|entry| Some((entry.file_name().into_string().ok()?, entry.path())))
+            .collect()
+    }
+
+    let mut files = Vec::new();
+    for (registry, registry_dir) in dirs(&data_dir.join(CACHE_DIR)) {
+        for (package, package_dir) in dirs(&registry_dir) {
+            for (version, version_dir) in dirs(&package_dir) {
+                for (_, path) in dirs(&version_dir) {
+                    let Ok(metadata) = fs::metadata(&path) else {
+                        continue;
+                    };
+                    if !metadata.is_file() {
+                        continue;
+                    }
+                    files.push(CachedFile {
+                        registry: registry.clone(),
+                        package: package.replace("%2F", "/"),
+                        version: version.clone(),
+                        path,
+                        size: metadata.len(),
+                        modified: metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+                    });
+                }
+            }
+        }
+    }
+    files
+}
+
+/// Indices of the files the policy removes
+pub fn plan(files: &[CachedFile], policy: &CacheConfig, now: SystemTime) -> BTreeSet<usize> {
+    let mut doomed = BTreeSet::new();
+
+    if policy.max_age_days > 0 {
+        let max_age = Duration::from_secs(policy.max_age_days * 24 * 60 * 60);
+        for (i, file) in files.iter().enumerate() {
+            if now.duration_since(file.modified).unwrap_or_default() > max_age {
+                doomed.insert(i);
+            }
+        }
+    }
+
+    if policy.keep_latest > 0 {
+        let mut versions: BTreeMap<(&str, &str), BTreeSet<&str>> = BTreeMap::new();
+        for file in files {
+            versions
+                .entry((&file.registry, &file.package))
+                .or_default()
+                .insert(&file.version);
+        }
+        for (i, file) in files.iter().enumerate() {
+            let mut newest: Vec<&str> = versions[&(file.registry.as_str(), file.package.as_str())]
+                .iter()
+                .copied()
+                .collect();
+            newest.sort_by_key(|version| std::cmp::Reverse(version_key(version)));
+            if !newest[..policy.keep_latest.min(newest.len())].contains(&file.version.as_str()) {
+                doomed.insert(i);
+            }
+        }
+    }
+
+    if policy.max_size_mb > 0 {
+        let max_bytes = policy.max_size_mb * 1024 * 1024;
+        let mut remaining: Vec<usize> = (0..files.len()).filter(|i| !doomed.contains(i)).collect();
+        let mut total: u64 = remaining.iter().map(|&i| files[i].size).sum();
+        remaining.sort_by_key(|&i| files[i].modified);
+        for i in remaining {
+            if total <= max_bytes {
+                break;
+            }
+            total -= files[i].size;
+            doomed.insert(i);
+        }
+    }
+
+    doomed
+}
+
+/// Apply the retention policy to the cache; with `dry_run` nothing is deleted
+pub fn collect_garbage(data_dir: &Path, policy: &CacheConfig, dry_run: bool) -> GcReport {
+    // Versions listed in mirror manifests are kept regardless of the policy
+    let pins = crate::mirror::load_pins(data_dir);
+    let (pinned, files): (Vec<CachedFile>, Vec<CachedFile>) =
+        scan(data_dir).into_iter().partition(|file| {
+            pins.contains(&crate::mirror::Pin {
+                registry: file.registry.clone(),
+                package: file.package.clone(),
+                version: file.version.clone(),
+            })
+        });
+    let doomed = plan(&files, policy, SystemTime::now());
+    let mut report = GcReport {
+        remaining_files: pinned.len(),
+        remaining_bytes: pinned.iter().map(|file| file.size).sum(),
+        ..Default::default()
+    };
+
+    for (i, file) in files.iter().enumerate() {
+        let removed = if !doomed.contains(&i) {
+            false
+        } else if dry_run {
+            true
+        } else if let Err(e) = fs::remove_file(&file.path) {
+            warn!(path = %file.path.display(), error = %e, "Failed to evict cached file");
+            false
+        } else {
+            remove_empty_parents(&file.path, &data_dir.join(CACHE_DIR));
+            true
+        };
+        if removed {
+            report.removed_files += 1;
+            report.freed_bytes += file.size;
+        } else {
+            report.remaining_files += 1;
+            report.remaining_bytes += file.size;
+        }
+    }
+    report
+}
+
+/// Collect garbage using the cache settings in `<data_dir>/config.json`
+pub fn run_gc(data_dir: &Path, dry_run: bool) -> AppResult<GcReport> {
+    let mut config = Config::default();
+    config.apply_security_file(data_dir.join("config.json"))?;
+    Ok(collect_garbage(data_dir, &config.cache, dry_run))
+}
+
+/// Run garbage collection in the background every `gc_interval_minutes`
+pub fn spawn_gc_task(state: Arc<AppState>) {
+    let minutes = state.config.load().cache.gc_interval_minutes;
+    if !state.config.load().cache.enabled || minutes == 0 {
+        return;
+    }
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(minutes * 60));
+        loop {
+            interval.tick().await;
+            let data_dir = state.data_dir.clone();
+            let policy = state.config.load().cache.clone();
+            match tokio::task::spawn_blocking(move || collect_garbage(&data_dir, &policy, false))
+                .await
+            {
+                Ok(report) if report.removed_files > 0 => info!(
+                    removed = report.removed_files,
+                    freed_bytes = report.freed_bytes,
+                    remaining_bytes = report.remaining_bytes,
+                    "Cache garbage collection finished"
+                ),
+                Ok(_) => debug!("Cache garbage collection found nothing to remove"),
+                Err(e) => warn!(error = %e, "Cache garbage collection failed"),
+            }
+        }
+    });
+}
+
+fn remove_empty_parents(path: &Path, cache_root: &Path) {
+    let mut dir = path.parent();
+    while let Some(current) = dir.filter(|d| d.starts_with(cache_root) && *d != cache_root) {
+        if fs::remove_dir(current).is_err() {
+            break;
+        }
+        dir = current.parent();
+    }
+}
+
+/// Compare versions by their numeric components, so 1.10.0 sorts after 1.9.0
+pub(crate) fn version_key(version: &str) -> Vec<u64> {
+    version
+        .split(['.', '-', '+'])
+        .map(|part| part.parse().unwrap_or(0))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(package: &str, version: &str, size: u64, days_ago: u64) -> CachedFile {
+        CachedFile {
+            registry: "pypi".to_string(),
+            package: package.to_string(),
+            version: version.to_string(),
+            path: PathBuf::from(format!("{package}-{version}")),
+            size,
+            modified: SystemTime::UNIX_EPOCH + Duration::from_secs((100 - days_ago) * 86_400),
+        }
+    }
+
+    fn policy(max_size_mb: u64, max_age_days: u64, keep_latest: usize) -> CacheConfig {
+        CacheConfig {
+            max_size_mb,
+            max_age_days,
+            keep_latest,
+            ..CacheConfig::default()
+        }
+    }
+
+    fn now() -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_secs(100 * 86_400)
+    }
+
+    #[test]
+    fn removes_files_unused_for_too_long() {
+        let files = [
+            file("requests", "2.31.0", 1, 40),
+            file("requests", "2.32.0", 1, 2),
+        ];
+        assert_eq!(plan(&files, &policy(0, 30, 0), now()), BTreeSet::from([0]));
+        assert!(plan(&files, &policy(0, 0, 0), now()).is_empty());
+    }
+
+    #[test]
+    fn keeps_the_newest_versions_of_each_package() {
+        let files = [
+            file("django", "4.2.0", 1, 1),
+            file("django", "4.10.0", 1, 1),
+            file("django", "5.0.0", 1, 1),
+            file("flask", "3.0.0", 1, 1),
+        ];
+        assert_eq!(plan(&files, &policy(0, 0, 2), now()), BTreeSet::from([0]));
+    }
+
+    #[test]
+    fn evicts_least_recently_used_to_fit_the_size_limit() {
+        let mb = 1024 * 1024;
+        let files = [
+            file("a", "1.0", mb, 5),
+            file("b", "1.0", mb, 1),
+            file("c", "1.0", mb, 9),
+        ];
+        assert_eq!(plan(&files, &policy(2, 0, 0), now()), BTreeSet::from([2]));
+        assert_eq!(
+            plan(&files, &policy(1, 0, 0), now()),
+            BTreeSet::from([0, 2])
+        );
+    }
+
+    #[test]
+    fn rejects_unsafe_cache_paths() {
+        let data_dir = Path::new("/data");
+        assert_eq!(
+            cached_path(data_dir, "npm", "@types/node", "20.1.0", "node-20.1.0.tgz"),
+            Some(PathBuf::from(
+                "/data/cache/npm/@types%2Fnode/20.1.0/node-20.1.0.tgz"
+            ))
+        );
+        assert!(cached_path(data_dir, "pypi", "..", "1.0", "x.whl").is_none());
+        assert!(cached_path(data_dir, "pypi", "pkg", "1.0", "../x.whl").is_none());
+    }
+
+    #[test]
+    fn collect_garbage_deletes_and_cleans_up_directories() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = cached_path(
+            temp_dir.path(),
+            "cargo",
+            "serde",
+            "1.0.0",
+            "serde-1.0.0.crate",
+        )
+        .unwrap();
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, vec![0u8; 2 * 1024 * 1024]).unwrap();
+
+        let dry_run = collect_garbage(temp_dir.path(), &policy(1, 0, 0), true);
+        assert_eq!(dry_run.removed_files, 1);
+        assert!(path.exists());
+
+        let report = collect_garbage(temp_dir.path(), &policy(1, 0, 0), false);
+        assert_eq!(report.removed_files, 1);
+        assert_eq!(report.remaining_bytes, 0);
+        assert!(!temp_dir.path().join("cache/cargo/serde").exists());
+    }
+
+    #[test]
+    fn collect_garbage_keeps_mirror_pins() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = cached_path(
+            temp_dir.path(),
+            "npm",
+            "react",
+            "18.2.0",
+            "react-18.2.0.tgz",
+        )
+        .unwrap();
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, vec![0u8; 2 * 1024 * 1024]).unwrap();
+        fs::write(
+            temp_dir.path().join("mirror-pins.json"),
+            r#"[{"registry": "npm", "package": "react", "version": "18.2.0"}]"#,
+        )
+        .unwrap();
+
+        let report = collect_garbage(temp_dir.path(), &policy(1, 0, 0), false);
+        assert_eq!(report.removed_files, 0);
+        assert_eq!(report.remaining_files, 1);
+        assert!(path.exists());
+    }
+}
@@ -4,14 +4,16 @@
 //! including package uploads, downloads, version management, and configuration.
 
 use super::{index::*, parsing::*, storage::*};
-use crate::deletion::{remove_version_from_index, update_index_yank_version};
+use crate::cache;
+use crate::deletion::{remove_version_from_index, update_index_yanked};
+use crate::provenance::{self, Source};
 use crate::{
     package_utils, sha256_hash, storage, validation, AppError, AppResult, AppState, SuccessResponse,
 };
 use axum::{
     extract::{Path as AxumPath, Query, State},
-    http::StatusCode,
-    response::{IntoResponse, Json, Response},
+    http::{HeaderMap, StatusCode},
+    response::{Html, IntoResponse, Json, Response},
 };
 use serde::Deserialize;
 use serde_json::{json, Value};
@@ -21,35 +23,13 @@ use tracing::{debug, info, warn};
 /// Count total number of Cargo crates
 #[allow(dead_code)]
 pub async fn count_crates(state: &AppState) -> AppResult<usize> {
-    let index_dir = state.data_dir.join("cargo/index");
-
-    package_utils::count_recursive_unique(index_dir, |path| {
-        if path.is_file() {
-            path.file_name()
-                .and_then(|n| n.to_str())
-                .map(|name| name.to_string())
-        } else {
-            None
-        }
-    })
-    .await
+    state.package_index.run(|index| index.count("cargo")).await
 }
 
 /// List all crate names
 #[allow(dead_code)]
 pub async fn list_all_crates(state: &AppState) -> AppResult<Vec<String>> {
-    let index_dir = state.data_dir.join("cargo/index");
-
-    package_utils::list_recursive_unique(index_dir, |path| {
-        if path.is_file() {
-            path.file_name()
-                .and_then(|n| n.to_str())
-                .map(|name| name.to_string())
-        } else {
-            None
-        }
-    })
-    .await
+    state.package_index.run(|index| index.list("cargo")).await
 }
 
 /// Get crate versions with checksums and file sizes
@@ -88,6 +68,26 @@ pub async fn get_crate_versions(
     Ok(versions)
 }
 
+/// Search crates by name and version; the sparse index carries no descriptions
+pub async fn search_crates(
+    state: &AppState,
+    query: &str,
+) -> AppResult<Vec<crate::registry::SearchHit>> {
+    let mut hits = Vec::new();
+    for name in list_all_crates(state).await? {
+        let versions: Vec<String> = get_crate_versions(state, &name)
+            .await?
+            .into_iter()
+            .map(|(version, _, _)| version)
+            .collect();
+        if let Some(hit) = crate::registry::SearchHit::rank("cargo", &name, &versions, None, query)
+        {
+            hits.push(hit);
+        }
+    }
+    Ok(hits)
+}
+
 /// API endpoint to get versions for a specific crate
 pub async fn get_crate_versions_api(
     AxumPath(crate_name): AxumPath<String>,
@@ -107,29 +107,10 @@ pub async fn get_crate_versions_api(
 
 /// Get recent crates
 pub async fn get_recent_crates(state: &AppState, limit: usize) -> AppResult<Vec<(String, String)>> {
-    let crates_dir = state.data_dir.join("cargo/crates");
-
-    package_utils::get_recent_packages_by_name_extraction(
-        crates_dir,
-        &[".crate"],
-        limit,
-        |filename| {
-            // Extract crate name and version from filename (cratename-version.crate)
-            if let Some(base) = filename.strip_suffix(".crate") {
-                // Find the last hyphen to split name and version
-                if let Some(last_dash) = base.rfind('-') {
-                    let crate_name = &base[..last_dash];
-                    let version = &base[last_dash + 1..];
-                    Some((crate_name.to_string(), version.to_string()))
-                } else {
-                    None
-                }
-            } else {
-                None
-            }
-        },
-    )
-    .await
+    state
+        .package_index
+        .run(move |index| index.recent("cargo", limit))
+        .await
 }
 
 /// Returns Cargo registry configuration required for client setup.
@@ -137,10 +118,30 @@ pub async fn config(State(state): State<Arc<AppState>>) -> AppResult<Json<Value>
     debug!("Incoming Cargo config request");
     let host = &state.server_addr;
 
-    Ok(Json(json!({
+    let mut config = json!({
         "dl": format!("{}/cargo/api/v1/crates/{{crate}}/{{version}}/download", host),
         "api": format!("{}/cargo", host)
-    })))
+    });
+    if crate::auth::is_auth_required(&state.config.load()) {
+        // Tells Cargo to send the registry token on index and download requests too
+        config["auth-required"] = json!(true);
+    }
+
+    Ok(Json(config))
+}
+
+/// Token page shown by `cargo login`, which points users at `{api}/me`.
+pub async fn login_page(State(state): State<Arc<AppState>>) -> Html<String> {
+    let body = if crate::auth::is_auth_required(&state.config.load()) {
+        "This registry requires a token. Ask the registry operator for a token with \
+         the read and publish scopes (issued through /api/tokens) or one of the keys \
+         configured in PKG_SERVER_API_KEYS, and paste it into `cargo login`."
+    } else {
+        "This registry does not require authentication; any token is accepted by `cargo login`."
+    };
+    Html(format!(
+        "<html><body><h1>Cargo registry token</h1><p>{body}</p></body></html>"
+    ))
 }
 
 /// Downloads Cargo crate files from local storage or upstream registry.
@@ -170,18 +171,52 @@ pub async fn download_crate(
     match storage::read_file(&file_path).await {
         Ok(data) => {
             debug!(crate_name = %crate_name, version = %version, size = data.len(), "Serving crate from local storage");
+            provenance::record(
+                &state,
+                "cargo",
+                &crate_name,
+                &version,
+                &filename,
+                Source::Local,
+            )
+            .await;
             Ok(data)
         }
         Err(_) => {
-            // File not found locally, try upstream crates.io
+            // File not found locally, try the upstream cache and then crates.io
+            if let Some(data) = cache::read(&state, "cargo", &crate_name, &version, &filename).await
+            {
+                debug!(crate_name = %crate_name, version = %version, size = data.len(), "Serving crate from upstream cache");
+                provenance::record(
+                    &state,
+                    "cargo",
+                    &crate_name,
+                    &version,
+                    &filename,
+                    Source::Upstream,
+                )
+                .await;
+                return Ok(data);
+            }
             debug!(crate_name = %crate_name, version = %version, "Crate not found locally, checking upstream crates.io");
             match state
                 .upstream_client
+                .load()
                 .stream_cargo_crate(&crate_name, &version)
                 .await
             {
                 Ok(bytes) => {
                     info!(crate_name = %crate_name, version = %version, size = bytes.len(), "Streaming crate from upstream crates.io");
+                    cache::store(&state, "cargo", &crate_name, &version, &filename, &bytes).await;
+                    provenance::record(
+                        &state,
+                        "cargo",
+                        &crate_name,
+                        &version,
+                        &filename,
+                        Source::Upstream,
+                    )
+                    .await;
                     Ok(bytes.to_vec())
                 }
                 Err(e) => {
@@ -196,6 +231,7 @@ pub async fn download_crate(
 /// Publishes a new Cargo crate version to the local registry.
 pub async fn publish_crate(
     State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
     body: axum::body::Bytes,
 ) -> AppResult<Json<SuccessResponse>> {
     debug!(payload_size = body.len(), "Incoming Cargo publish request");
@@ -204,6 +240,27 @@ pub async fn publish_crate(
     // Parse and validate the upload payload
     let (metadata, crate_data) = parse_crate_upload(body)?;
 
+    crate::signatures::verify_blob_upload(
+        &state.config.load().security.signatures.cargo,
+        "Cargo",
+        &metadata.name,
+        &crate_data,
+        &headers,
+    )?;
+    super::owners::check_publish(&state, &headers, &metadata.name).await?;
+
+    let reservation = crate::quotas::check_upload(
+        &state,
+        &headers,
+        &crate::quotas::Upload {
+            registry: "cargo",
+            package: &metadata.name,
+            version: Some(&metadata.version),
+            size: crate_data.len(),
+        },
+    )
+    .await?;
+
     info!(crate_name = %metadata.name, version = %metadata.version, "Publishing Cargo crate");
 
     // Save the crate file
@@ -219,7 +276,20 @@ pub async fn publish_crate(
     let cksum = sha256_hash(&crate_data);
 
     // Update the index
+    let first_publish = !super::owners::is_published(&state.data_dir, &metadata.name);
     update_crate_index(&metadata, &cksum, &state.data_dir).await?;
+    reservation.commit();
+    super::owners::record_publisher(&state, &headers, &metadata.name, first_publish).await;
+
+    let (name, version) = (metadata.name.clone(), metadata.version.clone());
+    if let Err(e) = state
+        .package_index
+        .run(move |index| index.record("cargo", &name, &version))
+        .await
+    {
+        warn!(crate_name = %metadata.name, error = %e, "Failed to update package index");
+    }
+    crate::webhooks::package_published(&state, "cargo", &metadata.name, &metadata.version);
 
     info!(
         crate_name = %metadata.name,
@@ -232,6 +302,18 @@ pub async fn publish_crate(
     }))
 }
 
+/// Deleting and yanking need an admin key or a token with the `delete` scope
+fn require_delete_access(state: &AppState, headers: &HeaderMap) -> AppResult<()> {
+    if crate::auth::is_auth_required(&state.config.load())
+        && !crate::auth::is_allowed(state, headers, "cargo", crate::tokens::TokenScope::Delete)
+    {
+        return Err(AppError::Unauthorized(
+            "A token with the 'delete' scope is required".to_string(),
+        ));
+    }
+    Ok(())
+}
+
 #[derive(Deserialize)]
 pub struct DeleteParams {
     #[serde(default)]
@@ -239,11 +321,16 @@ pub struct DeleteParams {
 }
 
 /// Yanks or deletes a specific version of a Cargo crate.
+///
+/// # Route
+/// `DELETE /api/cargo/{crate}/{version}?force=<bool>`
 pub async fn delete_crate_version(
     AxumPath((crate_name, version)): AxumPath<(String, String)>,
     State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
     Query(params): Query<DeleteParams>,
 ) -> AppResult<Json<SuccessResponse>> {
+    require_delete_access(&state, &headers)?;
     // Validate crate name and version for security
     validation::validate_package_name(&crate_name, "cargo")
         .map_err(|e| AppError::BadRequest(format!("Invalid crate name '{crate_name}': {e}")))?;
@@ -272,12 +359,23 @@ pub async fn delete_crate_version(
             info!(crate_file = %crate_file.display(), "Deleted crate file");
         }
 
+        let (name, removed) = (crate_name.clone(), version.clone());
+        if let Err(e) = state
+            .package_index
+            .run(move |index| index.remove("cargo", &name, Some(&removed)))
+            .await
+        {
+            warn!(crate_name = %crate_name, error = %e, "Failed to update package index");
+        }
+        crate::webhooks::version_deleted(&state, "cargo", &crate_name, Some(&version));
+
         Ok(Json(SuccessResponse {
             message: format!("Force deleted version {version} of crate '{crate_name}'"),
         }))
     } else {
         // Yank: just mark as yanked in index
-        update_index_yank_version(&index_file_path, &version).await?;
+        update_index_yanked(&index_file_path, &version, true).await?;
+        crate::webhooks::version_yanked(&state, "cargo", &crate_name, &version, true);
 
         Ok(Json(SuccessResponse {
             message: format!("Yanked version {version} of crate '{crate_name}'"),
@@ -285,6 +383,58 @@ pub async fn delete_crate_version(
     }
 }
 
+/// Sets the `yanked` flag of a published version in the sparse index
+async fn set_yanked(
+    state: &AppState,
+    crate_name: &str,
+    version: &str,
+    yanked: bool,
+) -> AppResult<Json<Value>> {
+    validation::validate_package_name(crate_name, "cargo")
+        .map_err(|e| AppError::BadRequest(format!("Invalid crate name '{crate_name}': {e}")))?;
+    validation::validate_version(version)
+        .map_err(|e| AppError::BadRequest(format!("Invalid version '{version}': {e}")))?;
+
+    let index_file_path = state
+        .data_dir
+        .join("cargo/index")
+        .join(index_path(crate_name)?);
+    if !index_file_path.exists() {
+        return Err(AppError::NotFound(format!(
+            "Crate '{crate_name}' is not published to this registry"
+        )));
+    }
+    update_index_yanked(&index_file_path, version, yanked)
+        .await
+        .map_err(|e| AppError::NotFound(format!("Crate '{crate_name}': {e}")))?;
+
+    info!(crate_name = %crate_name, version = %version, yanked = yanked, "Updated Cargo crate yank status");
+    crate::webhooks::version_yanked(state, "cargo", crate_name, version, yanked);
+    Ok(Json(json!({ "ok": true })))
+}
+
+/// Yanks a crate version so new lockfiles stop selecting it (`cargo yank`).
+///
+/// # Route
+/// `DELETE /cargo/api/v1/crates/{crate}/{version}/yank`
+pub async fn yank_crate(
+    AxumPath((crate_name, version)): AxumPath<(String, String)>,
+    State(state): State<Arc<AppState>>,
+) -> AppResult<Json<Value>> {
+    set_yanked(&state, &crate_name, &version, true).await
+}
+
+/// Restores a yanked crate version (`cargo yank --undo`).
+///
+/// # Route
+/// `PUT /cargo/api/v1/crates/{crate}/{version}/unyank`
+pub async fn unyank_crate(
+    AxumPath((crate_name, version)): AxumPath<(String, String)>,
+    State(state): State<Arc<AppState>>,
+) -> AppResult<Json<Value>> {
+    set_yanked(&state, &crate_name, &version, false).await
+}
+
 /// Placeholder API endpoint for crates.io compatibility
 pub async fn crates_io_api_placeholder() -> AppResult<Response> {
     // Return minimal valid response for Cargo's API version check
@@ -292,10 +442,15 @@ pub async fn crates_io_api_placeholder() -> AppResult<Response> {
 }
 
 /// Deletes all versions of a Cargo crate from the registry.
+///
+/// # Route
+/// `DELETE /api/cargo/crate/{crate}`
 pub async fn delete_all_versions(
     AxumPath(crate_name): AxumPath<String>,
     State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
 ) -> AppResult<Json<SuccessResponse>> {
+    require_delete_access(&state, &headers)?;
     // Validate crate name for security
     validation::validate_package_name(&crate_name, "cargo")
         .map_err(|e| AppError::BadRequest(format!("Invalid crate name '{crate_name}': {e}")))?;
@@ -342,6 +497,17 @@ pub async fn delete_all_versions(
         )));
     }
 
+    let name = crate_name.clone();
+    if let Err(e) = state
+        .package_index
+        .run(move |index| index.remove("cargo", &name, None))
+        .await
+    {
+        warn!(crate_name = %crate_name, error = %e, "Failed to update package index");
+    }
+    super::owners::remove_owners_file(&state.data_dir, &crate_name).await;
+    crate::webhooks::version_deleted(&state, "cargo", &crate_name, None);
+
     info!(crate_name = %crate_name, files = ?deleted_files, "All Cargo crate versions deleted successfully");
     Ok(Json(SuccessResponse {
         message: format!(
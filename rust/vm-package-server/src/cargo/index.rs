@@ -2,15 +2,32 @@
 //!
 //! This module handles Cargo registry index operations including path calculation,
 //! index file management, and sparse index serving.
+//!
+//! Crates with hundreds of versions have large index files, so sparse index
+//! responses carry an `ETag` that Cargo sends back as `If-None-Match` on its next
+//! `cargo update`; an unchanged file is answered with an empty `304`. Files of
+//! at least [`MIN_COMPRESSED_SIZE`] bytes are gzip-compressed for clients that
+//! accept it. Publishing appends the new version's line to the index file
+//! instead of rewriting it.
 
 use crate::{storage, validation, AppError, AppResult, AppState};
 use axum::{
     extract::{Path as AxumPath, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
     response::{IntoResponse, Response},
 };
+use flate2::write::GzEncoder;
 use serde_json::json;
+use std::io::Write;
 use std::sync::Arc;
-use tracing::{debug, info};
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+/// Index files mirrored from crates.io, inside the data directory
+const UPSTREAM_INDEX_DIR: &str = "cargo/upstream-index";
+
+/// Smallest sparse index response worth compressing
+pub const MIN_COMPRESSED_SIZE: usize = 1024;
 
 /// Calculate Cargo index path for a crate name according to Cargo's index structure
 /// Names are organized in directories: 1/a, 2/ab, 3/a/abc, ab/cd/abcd...
@@ -91,8 +108,10 @@ pub async fn update_crate_index(
 /// Serves Cargo index files containing crate version metadata.
 ///
 /// This endpoint serves index files according to Cargo's index structure, returning
-/// newline-delimited JSON containing metadata for all versions of a crate. Falls back
-/// to upstream crates.io if the crate is not found locally.
+/// newline-delimited JSON containing metadata for all versions of a crate. A crate
+/// published here is served from the local index only; other crates are served
+/// from the index mirrored from crates.io. Never mixing the two keeps a crates.io
+/// crate with the same name as a private one from adding versions to it.
 pub async fn index_file(
     axum::extract::Path(path): axum::extract::Path<String>,
     State(state): State<Arc<AppState>>,
@@ -115,29 +134,68 @@ pub async fn index_file(
     let index_path_str = index_path(crate_name)?;
     let index_file_path = state.data_dir.join("cargo/index").join(&index_path_str);
 
-    // Try local index first
-    match storage::read_file_string(&index_file_path).await {
-        Ok(content) => {
-            debug!(crate_name = %crate_name, "Serving index from local storage");
-            Ok(content)
+    let has_entries = |content: &String| content.lines().any(|line| !line.trim().is_empty());
+    if let Some(local) = storage::read_file_string(&index_file_path)
+        .await
+        .ok()
+        .filter(has_entries)
+    {
+        return Ok(local);
+    }
+
+    mirrored_index(&state, crate_name, &index_path_str)
+        .await
+        .filter(has_entries)
+        .ok_or_else(|| {
+            debug!(crate_name = %crate_name, "Crate not found locally or on crates.io");
+            AppError::NotFound(format!("Crate not found: {crate_name}"))
+        })
+}
+
+/// Index file for `crate_name` mirrored from crates.io.
+///
+/// Mirrored files are kept under `cargo/upstream-index/` and refetched once they
+/// are older than `cache.cargo_index_ttl_minutes`. Crates missing upstream are
+/// cached as empty files so private crates don't hit crates.io on every request.
+/// If crates.io can't be reached, the stale copy is served.
+async fn mirrored_index(state: &AppState, crate_name: &str, index_path: &str) -> Option<String> {
+    if !state
+        .upstream_client
+        .load()
+        .policy()
+        .allows("cargo", crate_name)
+    {
+        return None;
+    }
+    let cache_path = state.data_dir.join(UPSTREAM_INDEX_DIR).join(index_path);
+    let ttl = Duration::from_secs(state.config.load().cache.cargo_index_ttl_minutes * 60);
+    let fresh = tokio::fs::metadata(&cache_path)
+        .await
+        .and_then(|meta| meta.modified())
+        .is_ok_and(|modified| modified.elapsed().is_ok_and(|age| age < ttl));
+    if fresh {
+        if let Ok(content) = storage::read_file_string(&cache_path).await {
+            debug!(crate_name = %crate_name, "Serving mirrored crates.io index");
+            return Some(content);
         }
-        Err(_) => {
-            // Index not found locally, try upstream crates.io
-            debug!(crate_name = %crate_name, "Index not found locally, checking upstream crates.io");
-            match state
-                .upstream_client
-                .fetch_cargo_index(crate_name, &path)
-                .await
-            {
-                Ok(upstream_content) => {
-                    info!(crate_name = %crate_name, "Found crate on upstream crates.io, returning index");
-                    Ok(upstream_content)
-                }
-                Err(e) => {
-                    debug!(crate_name = %crate_name, error = %e, "Crate not found on upstream crates.io either");
-                    Err(e)
-                }
+    }
+
+    match state
+        .upstream_client
+        .load()
+        .fetch_cargo_index(crate_name, index_path)
+        .await
+    {
+        Ok(content) => {
+            let content = content.unwrap_or_default();
+            if let Err(e) = storage::save_file(&cache_path, &content).await {
+                warn!(path = %cache_path.display(), error = %e, "Failed to mirror crates.io index");
             }
+            Some(content)
+        }
+        Err(e) => {
+            debug!(crate_name = %crate_name, error = %e, "crates.io index unavailable, using mirrored copy");
+            storage::read_file_string(&cache_path).await.ok()
         }
     }
 }
@@ -150,6 +208,7 @@ pub async fn index_file(
 pub async fn sparse_index(
     AxumPath(path): AxumPath<String>,
     State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
 ) -> AppResult<Response> {
     // Handle config.json separately
     if path == "config.json" {
@@ -172,9 +231,78 @@ pub async fn sparse_index(
     debug!(crate_name = %crate_name, path = %path, "Sparse index request");
 
     // Use the existing index_file logic
-    index_file(AxumPath(path), State(state))
-        .await
-        .map(|content| content.into_response())
+    let content = index_file(AxumPath(path), State(state)).await?;
+    Ok(index_response(&headers, content))
+}
+
+/// Weak entity tag of an index file; weak because the gzip and plain
+/// responses share it
+fn index_etag(content: &str) -> String {
+    format!("W/\"{}\"", &crate::sha256_hash(content.as_bytes())[..32])
+}
+
+/// Whether an `If-None-Match` header lists `etag`, using weak comparison
+fn etag_matches(headers: &HeaderMap, etag: &str) -> bool {
+    let strip = |tag: &str| tag.trim().trim_start_matches("W/").to_string();
+    headers
+        .get_all(header::IF_NONE_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|tag| tag.trim() == "*" || strip(tag) == strip(etag))
+}
+
+/// Whether `Accept-Encoding` allows gzip
+fn accepts_gzip(headers: &HeaderMap) -> bool {
+    headers
+        .get_all(header::ACCEPT_ENCODING)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|coding| {
+            let mut params = coding.split(';').map(str::trim);
+            let name = params.next().unwrap_or_default();
+            let refused = params.any(|param| {
+                param
+                    .strip_prefix("q=")
+                    .and_then(|q| q.parse::<f32>().ok())
+                    .is_some_and(|q| q == 0.0)
+            });
+            (name.eq_ignore_ascii_case("gzip") || name == "*") && !refused
+        })
+}
+
+/// Sparse index response for `content`: `304` when the client's copy is
+/// current, otherwise the file, gzip-compressed when it is large enough
+pub fn index_response(headers: &HeaderMap, content: String) -> Response {
+    let etag = index_etag(&content);
+    let etag_header = HeaderValue::from_str(&etag).expect("hex digest is a valid header value");
+    if etag_matches(headers, &etag) {
+        return (StatusCode::NOT_MODIFIED, [(header::ETAG, etag_header)]).into_response();
+    }
+
+    let vary = (header::VARY, HeaderValue::from_static("accept-encoding"));
+    if content.len() >= MIN_COMPRESSED_SIZE && accepts_gzip(headers) {
+        let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+        let compressed = encoder
+            .write_all(content.as_bytes())
+            .and_then(|()| encoder.finish());
+        match compressed {
+            Ok(body) => {
+                return (
+                    [
+                        (header::ETAG, etag_header),
+                        (header::CONTENT_ENCODING, HeaderValue::from_static("gzip")),
+                        vary,
+                    ],
+                    body,
+                )
+                    .into_response();
+            }
+            Err(e) => warn!(error = %e, "Failed to compress index file; sending it uncompressed"),
+        }
+    }
+    ([(header::ETAG, etag_header), vary], content).into_response()
 }
 
 #[cfg(test)]
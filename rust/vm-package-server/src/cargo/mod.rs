@@ -7,6 +7,7 @@ use serde_json::Value;
 
 mod handlers;
 mod index;
+mod owners;
 mod parsing;
 mod storage;
 
@@ -25,5 +26,6 @@ pub struct CrateMetadata {
 // Re-export all public functions and types to maintain API compatibility
 pub use handlers::*;
 pub use index::*;
+pub use owners::*;
 pub use parsing::*;
 pub use storage::*;
@@ -0,0 +1,241 @@
+//! Crate ownership
+//!
+//! Owners are the names of issued API tokens. The token that first publishes a
+//! crate becomes its owner, and `cargo owner --add/--remove/--list` manage the
+//! list afterwards. When authentication is required, only owners and admin API
+//! keys may publish new versions of a crate that has owners or change who owns
+//! it. Crates published before they had an owner stay open until an admin adds
+//! one.
+//!
+//! Owner lists are kept in `cargo/owners/{crate}.json` in the data directory.
+
+use super::index::index_path;
+use crate::{auth, storage, validation, AppError, AppResult, AppState};
+use axum::extract::{Path as AxumPath, State};
+use axum::http::HeaderMap;
+use axum::Json;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tracing::{info, warn};
+
+/// Who a request is acting as
+enum Caller {
+    /// An admin API key
+    Admin,
+    /// An issued token, identified by its name
+    Token(String),
+    Anonymous,
+}
+
+fn caller(state: &AppState, headers: &HeaderMap) -> Caller {
+    if auth::is_authorized(&state.config.load(), headers) {
+        return Caller::Admin;
+    }
+    auth::request_token(headers)
+        .and_then(|secret| state.tokens.find(&secret))
+        .map_or(Caller::Anonymous, |token| Caller::Token(token.name))
+}
+
+/// Body of `cargo owner --add` and `cargo owner --remove`
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
+pub struct OwnersRequest {
+    pub users: Vec<String>,
+}
+
+fn owners_path(data_dir: &Path, crate_name: &str) -> PathBuf {
+    data_dir
+        .join("cargo/owners")
+        .join(format!("{}.json", crate_name.to_lowercase()))
+}
+
+/// Owners recorded for `crate_name`, empty when it has none
+pub async fn load_owners(data_dir: &Path, crate_name: &str) -> Vec<String> {
+    let Ok(content) = storage::read_file_string(&owners_path(data_dir, crate_name)).await else {
+        return Vec::new();
+    };
+    serde_json::from_str(&content).unwrap_or_default()
+}
+
+async fn save_owners(data_dir: &Path, crate_name: &str, owners: &[String]) -> AppResult<()> {
+    storage::save_file(
+        owners_path(data_dir, crate_name),
+        serde_json::to_string_pretty(owners)?.as_bytes(),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Forget the owners of a crate that was deleted
+pub async fn remove_owners_file(data_dir: &Path, crate_name: &str) {
+    let path = owners_path(data_dir, crate_name);
+    if path.exists() {
+        if let Err(e) = tokio::fs::remove_file(&path).await {
+            warn!(file = %path.display(), error = %e, "Failed to delete crate owners");
+        }
+    }
+}
+
+/// Only admins and owners may act on a crate that has owners
+fn require_owner(
+    state: &AppState,
+    headers: &HeaderMap,
+    crate_name: &str,
+    owners: &[String],
+    action: &str,
+) -> AppResult<()> {
+    if !auth::is_auth_required(&state.config.load()) {
+        return Ok(());
+    }
+    match caller(state, headers) {
+        Caller::Admin => Ok(()),
+        Caller::Token(name) if owners.contains(&name) => Ok(()),
+        _ => Err(AppError::Unauthorized(format!(
+            "Only owners of crate '{crate_name}' may {action}"
+        ))),
+    }
+}
+
+/// Check that the request may publish a new version of `crate_name`
+pub async fn check_publish(
+    state: &AppState,
+    headers: &HeaderMap,
+    crate_name: &str,
+) -> AppResult<()> {
+    let owners = load_owners(&state.data_dir, crate_name).await;
+    if owners.is_empty() {
+        return Ok(());
+    }
+    require_owner(state, headers, crate_name, &owners, "publish new versions")
+}
+
+/// Whether `crate_name` has an index file, i.e. some version was published here
+pub fn is_published(data_dir: &Path, crate_name: &str) -> bool {
+    index_path(crate_name).is_ok_and(|path| data_dir.join("cargo/index").join(path).exists())
+}
+
+/// Make the publishing token the owner of a crate it just published for the
+/// first time. Crates that were published before stay open until an admin adds
+/// an owner, rather than going to whichever token publishes next.
+pub async fn record_publisher(
+    state: &AppState,
+    headers: &HeaderMap,
+    crate_name: &str,
+    first_publish: bool,
+) {
+    if !first_publish {
+        return;
+    }
+    let Caller::Token(name) = caller(state, headers) else {
+        return;
+    };
+    if !load_owners(&state.data_dir, crate_name).await.is_empty() {
+        return;
+    }
+    match save_owners(&state.data_dir, crate_name, std::slice::from_ref(&name)).await {
+        Ok(()) => info!(crate_name = %crate_name, owner = %name, "Recorded crate owner"),
+        Err(e) => warn!(crate_name = %crate_name, error = %e, "Failed to record crate owner"),
+    }
+}
+
+/// Validates the crate name and that it was published here
+fn check_crate(state: &AppState, crate_name: &str) -> AppResult<()> {
+    validation::validate_package_name(crate_name, "cargo")
+        .map_err(|e| AppError::BadRequest(format!("Invalid crate name '{crate_name}': {e}")))?;
+    index_path(crate_name)?;
+    if is_published(&state.data_dir, crate_name) {
+        Ok(())
+    } else {
+        Err(AppError::NotFound(format!(
+            "Crate '{crate_name}' is not published to this registry"
+        )))
+    }
+}
+
+/// Lists the owners of a crate (`cargo owner --list`).
+///
+/// # Route
+/// `GET /cargo/api/v1/crates/{crate}/owners`
+pub async fn list_owners(
+    AxumPath(crate_name): AxumPath<String>,
+    State(state): State<Arc<AppState>>,
+) -> AppResult<Json<Value>> {
+    check_crate(&state, &crate_name)?;
+    let owners = load_owners(&state.data_dir, &crate_name).await;
+    let users: Vec<Value> = owners
+        .iter()
+        .enumerate()
+        .map(|(i, login)| json!({ "id": i + 1, "login": login, "name": null }))
+        .collect();
+    Ok(Json(json!({ "users": users })))
+}
+
+/// Adds owners to a crate (`cargo owner --add`).
+///
+/// Every user must be the name of an issued token.
+///
+/// # Route
+/// `PUT /cargo/api/v1/crates/{crate}/owners` with body `{"users": ["name"]}`
+pub async fn add_owners(
+    AxumPath(crate_name): AxumPath<String>,
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(request): Json<OwnersRequest>,
+) -> AppResult<Json<Value>> {
+    check_crate(&state, &crate_name)?;
+    let mut owners = load_owners(&state.data_dir, &crate_name).await;
+    require_owner(&state, &headers, &crate_name, &owners, "add owners")?;
+
+    let tokens = state.tokens.list();
+    for user in &request.users {
+        if !tokens.iter().any(|token| &token.name == user) {
+            return Err(AppError::BadRequest(format!(
+                "Unknown user '{user}'; owners must be names of issued tokens"
+            )));
+        }
+    }
+    for user in &request.users {
+        if !owners.contains(user) {
+            owners.push(user.clone());
+        }
+    }
+    save_owners(&state.data_dir, &crate_name, &owners).await?;
+
+    info!(crate_name = %crate_name, added = ?request.users, "Added crate owners");
+    Ok(Json(json!({
+        "ok": true,
+        "msg": format!("added {} as owners of crate {crate_name}", request.users.join(", ")),
+    })))
+}
+
+/// Removes owners from a crate (`cargo owner --remove`).
+///
+/// A crate always keeps at least one owner.
+///
+/// # Route
+/// `DELETE /cargo/api/v1/crates/{crate}/owners` with body `{"users": ["name"]}`
+pub async fn remove_owners(
+    AxumPath(crate_name): AxumPath<String>,
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(request): Json<OwnersRequest>,
+) -> AppResult<Json<Value>> {
+    check_crate(&state, &crate_name)?;
+    let mut owners = load_owners(&state.data_dir, &crate_name).await;
+    require_owner(&state, &headers, &crate_name, &owners, "remove owners")?;
+
+    owners.retain(|owner| !request.users.contains(owner));
+    if owners.is_empty() {
+        return Err(AppError::BadRequest(format!(
+            "Cannot remove all owners of crate '{crate_name}'"
+        )));
+    }
+    save_owners(&state.data_dir, &crate_name, &owners).await?;
+
+    info!(crate_name = %crate_name, removed = ?request.users, "Removed crate owners");
+    Ok(Json(json!({
+        "ok": true,
+        "msg": format!("removed {} as owners of crate {crate_name}", request.users.join(", ")),
+    })))
+}
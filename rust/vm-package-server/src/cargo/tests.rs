@@ -2,7 +2,7 @@
 
 #[cfg(test)]
 mod cargo_tests {
-    use crate::cargo::{handlers::*, index::*};
+    use crate::cargo::{handlers::*, index::*, CrateMetadata};
     use crate::{AppState, UpstreamClient};
     use axum::http::StatusCode;
     use axum_test::TestServer;
@@ -22,13 +22,22 @@ mod cargo_tests {
         std::fs::create_dir_all(data_dir.join("cargo/index")).expect("Failed to create index dir");
 
         let config = Arc::new(crate::config::Config::default());
+        let package_index = Arc::new(
+            crate::package_index::PackageIndex::open(&data_dir)
+                .expect("Failed to open package index"),
+        );
+        let tokens = Arc::new(
+            crate::tokens::TokenStore::open(&data_dir).expect("Failed to open token store"),
+        );
         let state = Arc::new(AppState {
             data_dir,
             server_addr: "http://localhost:8080".to_string(),
-            upstream_client: Arc::new(UpstreamClient::disabled()),
-            config,
+            upstream_client: Arc::new(UpstreamClient::disabled()).into(),
+            config: config.into(),
             npm_registry: crate::registry::NpmRegistry::new(),
             pypi_registry: crate::registry::PypiRegistry::new(),
+            package_index,
+            tokens,
         });
 
         (state, temp_dir)
@@ -221,6 +230,25 @@ mod cargo_tests {
         assert!(dl_url.contains("{version}"));
     }
 
+    #[tokio::test]
+    async fn test_login_page_is_reachable_without_a_token() {
+        let (state, _temp_dir) = create_cargo_test_state();
+        let mut config = crate::config::Config::default();
+        config.security.require_authentication = true;
+        config.security.api_keys = vec!["admin-key".to_string()];
+        let mut state = (*state).clone();
+        state.config = Arc::new(config).into();
+        let app = crate::server::registry_routes(Arc::new(state));
+        let server = TestServer::new(app).expect("Failed to create test server");
+
+        let response = server.get("/cargo/me").await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+        assert!(response.text().contains("requires a token"));
+
+        let response = server.get("/cargo/config.json").await;
+        assert_eq!(response.status_code(), StatusCode::UNAUTHORIZED);
+    }
+
     #[tokio::test]
     async fn test_index_file_after_publish() {
         let (state, _temp_dir) = create_cargo_test_state();
@@ -266,6 +294,36 @@ mod cargo_tests {
         assert!(body.contains("abc123def456"));
     }
 
+    #[tokio::test]
+    async fn test_local_index_shadows_mirrored_crates_io_index() {
+        let (state, _temp_dir) = create_cargo_test_state();
+        let write_index = |dir: &str, crate_name: &str, version: &str| {
+            let path = state
+                .data_dir
+                .join(dir)
+                .join(index_path(crate_name).expect("Failed to get index path"));
+            std::fs::create_dir_all(path.parent().expect("index path should have parent"))
+                .expect("Failed to create parent dir");
+            let entry = json!({"name": crate_name, "vers": version, "deps": [], "features": {}});
+            std::fs::write(path, format!("{entry}\n")).expect("Failed to write index file");
+        };
+        write_index("cargo/index", "internal-tools", "0.1.0");
+        write_index("cargo/upstream-index", "internal-tools", "99.0.0");
+        write_index("cargo/upstream-index", "public-crate", "1.2.0");
+
+        let app = axum::Router::new()
+            .route("/cargo/index/{crate}", axum::routing::get(index_file))
+            .with_state(state);
+        let server = TestServer::new(app).expect("Failed to create test server");
+
+        let body = server.get("/cargo/index/internal-tools").await.text();
+        assert!(body.contains("0.1.0"));
+        assert!(!body.contains("99.0.0"));
+
+        let body = server.get("/cargo/index/public-crate").await.text();
+        assert!(body.contains("1.2.0"));
+    }
+
     #[tokio::test]
     async fn test_download_crate() {
         let (state, _temp_dir) = create_cargo_test_state();
@@ -296,4 +354,283 @@ mod cargo_tests {
         assert_eq!(response.status_code(), StatusCode::OK);
         assert_eq!(response.as_bytes().to_vec(), content.to_vec());
     }
+
+    #[tokio::test]
+    async fn test_sparse_index_revalidation_and_compression() {
+        let (state, _temp_dir) = create_cargo_test_state();
+
+        let crate_name = "big-crate";
+        let lines: Vec<String> = (0..50)
+            .map(|minor| {
+                json!({
+                    "name": crate_name,
+                    "vers": format!("1.{minor}.0"),
+                    "deps": [],
+                    "cksum": format!("{minor:064}"),
+                    "features": {},
+                    "yanked": false
+                })
+                .to_string()
+            })
+            .collect();
+        let index_file_path = state
+            .data_dir
+            .join("cargo/index")
+            .join(index_path(crate_name).expect("Failed to get index path"));
+        std::fs::create_dir_all(index_file_path.parent().unwrap())
+            .expect("Failed to create parent dir");
+        std::fs::write(&index_file_path, lines.join("\n") + "\n")
+            .expect("Failed to write index file");
+
+        let app = axum::Router::new()
+            .route("/cargo/{*path}", axum::routing::get(sparse_index))
+            .with_state(state.clone());
+        let server = TestServer::new(app).expect("Failed to create test server");
+        let path = "/cargo/bi/g-/big-crate";
+
+        let response = server
+            .get(path)
+            .add_header("accept-encoding", "gzip, br")
+            .await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+        assert_eq!(response.header("content-encoding"), "gzip");
+        let mut body = String::new();
+        std::io::Read::read_to_string(
+            &mut flate2::read::GzDecoder::new(response.as_bytes().as_ref()),
+            &mut body,
+        )
+        .expect("Failed to decompress index");
+        assert_eq!(body.lines().count(), 50);
+        let etag = response.header("etag").to_str().unwrap().to_string();
+
+        let response = server.get(path).add_header("if-none-match", &etag).await;
+        assert_eq!(response.status_code(), StatusCode::NOT_MODIFIED);
+        assert!(response.as_bytes().is_empty());
+
+        // Publishing a version changes the tag; clients without gzip get plain text
+        let metadata = CrateMetadata {
+            name: crate_name.to_string(),
+            version: "2.0.0".to_string(),
+            deps: json!([]),
+            features: json!({}),
+        };
+        update_crate_index(&metadata, "new", &state.data_dir)
+            .await
+            .expect("Failed to append to index");
+        let response = server.get(path).add_header("if-none-match", &etag).await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+        assert!(response.maybe_header("content-encoding").is_none());
+        assert_ne!(response.header("etag").to_str().unwrap(), etag);
+        assert_eq!(response.text().lines().count(), 51);
+    }
+
+    #[tokio::test]
+    async fn test_yank_and_unyank_update_index() {
+        let (state, _temp_dir) = create_cargo_test_state();
+
+        let crate_name = "test-crate";
+        let index_path_str = index_path(crate_name).expect("Failed to get index path");
+        let index_file_path = state.data_dir.join("cargo/index").join(&index_path_str);
+        std::fs::create_dir_all(
+            index_file_path
+                .parent()
+                .expect("index path should have parent"),
+        )
+        .expect("Failed to create parent dir");
+        let lines: Vec<String> = ["0.9.0", "1.0.0"]
+            .iter()
+            .map(|vers| {
+                json!({"name": crate_name, "vers": vers, "deps": [], "cksum": "abc", "features": {}, "yanked": false})
+                    .to_string()
+            })
+            .collect();
+        std::fs::write(&index_file_path, lines.join("\n") + "\n")
+            .expect("Failed to write index file");
+
+        let app = axum::Router::new()
+            .route(
+                "/cargo/api/v1/crates/{crate}/{version}/yank",
+                axum::routing::delete(yank_crate),
+            )
+            .route(
+                "/cargo/api/v1/crates/{crate}/{version}/unyank",
+                axum::routing::put(unyank_crate),
+            )
+            .with_state(state);
+        let server = TestServer::new(app).expect("Failed to create test server");
+        let yanked = |version: &str| {
+            std::fs::read_to_string(&index_file_path)
+                .expect("Failed to read index file")
+                .lines()
+                .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+                .find(|entry| entry["vers"] == version)
+                .map(|entry| entry["yanked"] == true)
+                .expect("version should stay in the index")
+        };
+
+        let response = server
+            .delete("/cargo/api/v1/crates/test-crate/1.0.0/yank")
+            .await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+        assert_eq!(response.json::<serde_json::Value>(), json!({"ok": true}));
+        assert!(yanked("1.0.0"));
+        assert!(!yanked("0.9.0"));
+
+        let response = server
+            .put("/cargo/api/v1/crates/test-crate/1.0.0/unyank")
+            .await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+        assert!(!yanked("1.0.0"));
+
+        let response = server
+            .delete("/cargo/api/v1/crates/test-crate/2.0.0/yank")
+            .await;
+        assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+        let response = server
+            .delete("/cargo/api/v1/crates/missing-crate/1.0.0/yank")
+            .await;
+        assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_owners_restrict_publishing() {
+        use crate::cargo::owners::*;
+        use crate::tokens::TokenScope;
+
+        let (state, _temp_dir) = create_cargo_test_state();
+        let mut config = crate::config::Config::default();
+        config.security.require_authentication = true;
+        config.security.api_keys = vec!["admin-key".to_string()];
+        let mut state = (*state).clone();
+        state.config = Arc::new(config).into();
+        let state = Arc::new(state);
+        let secret = |name: &str| {
+            state
+                .tokens
+                .create(name, vec![TokenScope::Publish], vec![])
+                .expect("Failed to create token")
+                .1
+        };
+        let (alice, bob) = (secret("alice"), secret("bob"));
+
+        let app = axum::Router::new()
+            .route(
+                "/cargo/api/v1/crates/new",
+                axum::routing::put(publish_crate),
+            )
+            .route(
+                "/cargo/api/v1/crates/{crate}/owners",
+                axum::routing::get(list_owners)
+                    .put(add_owners)
+                    .delete(remove_owners),
+            )
+            .with_state(state.clone());
+        let server = TestServer::new(app).expect("Failed to create test server");
+        let publish = |token: &str, version: &str| {
+            server
+                .put("/cargo/api/v1/crates/new")
+                .authorization(token)
+                .bytes(create_cargo_publish_payload("internal", version, b"crate").into())
+        };
+        let owners_url = "/cargo/api/v1/crates/internal/owners";
+
+        assert_eq!(publish(&alice, "1.0.0").await.status_code(), StatusCode::OK);
+        let owners = server.get(owners_url).await.json::<serde_json::Value>();
+        assert_eq!(owners["users"][0]["login"], "alice");
+
+        // Non-owners can neither publish nor add themselves
+        assert_eq!(
+            publish(&bob, "1.1.0").await.status_code(),
+            StatusCode::UNAUTHORIZED
+        );
+        let response = server
+            .put(owners_url)
+            .authorization(bob.clone())
+            .json(&json!({ "users": ["bob"] }))
+            .await;
+        assert_eq!(response.status_code(), StatusCode::UNAUTHORIZED);
+
+        let response = server
+            .put(owners_url)
+            .authorization(alice.clone())
+            .json(&json!({ "users": ["carol"] }))
+            .await;
+        assert_eq!(response.status_code(), StatusCode::BAD_REQUEST);
+        let response = server
+            .put(owners_url)
+            .authorization(alice.clone())
+            .json(&json!({ "users": ["bob"] }))
+            .await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+        assert_eq!(publish(&bob, "1.1.0").await.status_code(), StatusCode::OK);
+        assert_eq!(
+            publish("admin-key", "1.2.0").await.status_code(),
+            StatusCode::OK
+        );
+
+        let response = server
+            .delete(owners_url)
+            .authorization(bob.clone())
+            .json(&json!({ "users": ["alice", "bob"] }))
+            .await;
+        assert_eq!(response.status_code(), StatusCode::BAD_REQUEST);
+        let response = server
+            .delete(owners_url)
+            .authorization(bob.clone())
+            .json(&json!({ "users": ["alice"] }))
+            .await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+        assert_eq!(
+            load_owners(&state.data_dir, "internal").await,
+            vec!["bob".to_string()]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_publishing_an_ownerless_crate_does_not_claim_it() {
+        use crate::cargo::owners::*;
+        use crate::tokens::TokenScope;
+
+        let (state, _temp_dir) = create_cargo_test_state();
+        let mut config = crate::config::Config::default();
+        config.security.require_authentication = true;
+        config.security.api_keys = vec!["admin-key".to_string()];
+        let mut state = (*state).clone();
+        state.config = Arc::new(config).into();
+        let state = Arc::new(state);
+        let secret = |name: &str| {
+            state
+                .tokens
+                .create(name, vec![TokenScope::Publish], vec![])
+                .expect("Failed to create token")
+                .1
+        };
+        let (alice, bob) = (secret("alice"), secret("bob"));
+
+        let app = axum::Router::new()
+            .route(
+                "/cargo/api/v1/crates/new",
+                axum::routing::put(publish_crate),
+            )
+            .with_state(state.clone());
+        let server = TestServer::new(app).expect("Failed to create test server");
+        let publish = |token: &str, version: &str| {
+            server
+                .put("/cargo/api/v1/crates/new")
+                .authorization(token)
+                .bytes(create_cargo_publish_payload("legacy", version, b"crate").into())
+        };
+
+        // Published before crates had owners
+        assert_eq!(
+            publish("admin-key", "1.0.0").await.status_code(),
+            StatusCode::OK
+        );
+        assert!(load_owners(&state.data_dir, "legacy").await.is_empty());
+
+        assert_eq!(publish(&alice, "1.1.0").await.status_code(), StatusCode::OK);
+        assert_eq!(publish(&bob, "1.2.0").await.status_code(), StatusCode::OK);
+        assert_eq!(publish(&alice, "1.3.0").await.status_code(), StatusCode::OK);
+        assert!(load_owners(&state.data_dir, "legacy").await.is_empty());
+    }
 }
@@ -3,8 +3,8 @@
 //! This module provides builders that can create packages from source code
 //! for Python, Node.js, and Rust projects.
 
+use super::progress::ProgressBarManager;
 use anyhow::{Context, Result};
-use indicatif::{ProgressBar, ProgressStyle};
 use serde_json::Value;
 use std::fs;
 use std::path::Path;
@@ -12,30 +12,6 @@ use std::process::Command;
 use tracing::error;
 use which::which;
 
-/// Utility for creating consistent progress bars across build operations
-pub struct ProgressBarManager {
-    pb: ProgressBar,
-}
-
-impl ProgressBarManager {
-    /// Create a new progress bar with consistent styling
-    pub fn new(message: &str) -> Self {
-        let pb = ProgressBar::new_spinner();
-        pb.set_style(
-            ProgressStyle::default_spinner()
-                .template("{spinner:.green} {msg}")
-                .unwrap_or_else(|_| ProgressStyle::default_spinner()),
-        );
-        pb.set_message(message.to_string());
-        Self { pb }
-    }
-
-    /// Finish the progress bar and clear it
-    pub fn finish(self) {
-        self.pb.finish_and_clear();
-    }
-}
-
 /// Helper function to validate that a required tool is available
 pub fn ensure_tool_available(tool_name: &str) -> Result<()> {
     if which(tool_name).is_err() {
@@ -52,23 +28,29 @@ pub trait PackageBuilder {
     /// The name of the tool required for building
     fn tool_name(&self) -> &str;
 
-    /// Create the build command with appropriate arguments
-    fn build_command(&self) -> Command;
+    /// Create the build command for the project in `dir`
+    fn build_command(&self, dir: &Path) -> Command;
 
-    /// Process the output directory to find build artifacts
-    fn process_build_output(&self) -> Result<Self::Output>;
+    /// Find the build artifacts of the project in `dir`
+    fn process_build_output(&self, dir: &Path) -> Result<Self::Output>;
 
     /// The progress message to show during building
     fn progress_message(&self) -> &str;
 
-    /// Execute the full build process
+    /// Build the project in the current directory
     fn build(&self) -> Result<Self::Output> {
+        self.build_in(&std::env::current_dir()?)
+    }
+
+    /// Build the project in `dir`
+    fn build_in(&self, dir: &Path) -> Result<Self::Output> {
         ensure_tool_available(self.tool_name())?;
 
         let pb = ProgressBarManager::new(self.progress_message());
 
         let output = self
-            .build_command()
+            .build_command(dir)
+            .current_dir(dir)
             .output()
             .with_context(|| format!("Failed to run {} build command", self.tool_name()))?;
 
@@ -80,7 +62,7 @@ pub trait PackageBuilder {
             anyhow::bail!("Build failed: {stderr}");
         }
 
-        self.process_build_output()
+        self.process_build_output(dir)
     }
 }
 
@@ -94,8 +76,8 @@ impl PackageBuilder for PythonBuilder {
         "python"
     }
 
-    fn build_command(&self) -> Command {
-        if Path::new("pyproject.toml").exists() {
+    fn build_command(&self, dir: &Path) -> Command {
+        if dir.join("pyproject.toml").exists() {
             // Try to install build if not available
             let _ = Command::new("python")
                 .args(["-m", "pip", "install", "build"])
@@ -111,14 +93,14 @@ impl PackageBuilder for PythonBuilder {
         }
     }
 
-    fn process_build_output(&self) -> Result<Self::Output> {
-        let dist_dir = Path::new("dist");
+    fn process_build_output(&self, dir: &Path) -> Result<Self::Output> {
+        let dist_dir = dir.join("dist");
         if !dist_dir.exists() {
             anyhow::bail!("dist/ directory not found after build");
         }
 
         let mut package_files = Vec::new();
-        for entry in fs::read_dir(dist_dir)? {
+        for entry in fs::read_dir(&dist_dir)? {
             let entry = entry?;
             let path = entry.path();
             if path.is_file() {
@@ -147,20 +129,19 @@ impl PackageBuilder for NpmBuilder {
         "npm"
     }
 
-    fn build_command(&self) -> Command {
+    fn build_command(&self, _dir: &Path) -> Command {
         let mut cmd = Command::new("npm");
         cmd.args(["pack"]);
         cmd
     }
 
-    fn process_build_output(&self) -> Result<Self::Output> {
+    fn process_build_output(&self, dir: &Path) -> Result<Self::Output> {
         // Read package.json to create metadata
-        let package_json = fs::read_to_string("package.json")?;
+        let package_json = fs::read_to_string(dir.join("package.json"))?;
         let metadata: Value = serde_json::from_str(&package_json)?;
 
-        // Find .tgz files in current directory (created by npm pack)
-        let current_dir = std::env::current_dir()?;
-        for entry in fs::read_dir(&current_dir)? {
+        // Find .tgz files in the package directory (created by npm pack)
+        for entry in fs::read_dir(dir)? {
             let entry = entry?;
             let path = entry.path();
             if path.is_file() && path.extension() == Some(std::ffi::OsStr::new("tgz")) {
@@ -177,46 +158,49 @@ impl PackageBuilder for NpmBuilder {
 }
 
 /// Cargo package builder
+///
+/// In a workspace root every publishable member is packaged.
 pub struct CargoBuilder;
 
+impl CargoBuilder {
+    fn is_workspace_root(dir: &Path) -> bool {
+        fs::read_to_string(dir.join("Cargo.toml"))
+            .map(|content| content.lines().any(|line| line.trim() == "[workspace]"))
+            .unwrap_or(false)
+    }
+}
+
 impl PackageBuilder for CargoBuilder {
-    type Output = std::path::PathBuf;
+    type Output = Vec<std::path::PathBuf>;
 
     fn tool_name(&self) -> &str {
         "cargo"
     }
 
-    fn build_command(&self) -> Command {
+    fn build_command(&self, dir: &Path) -> Command {
         let mut cmd = Command::new("cargo");
         cmd.args(["package", "--allow-dirty"]);
+        if Self::is_workspace_root(dir) {
+            cmd.arg("--workspace");
+        }
         cmd
     }
 
-    fn process_build_output(&self) -> Result<Self::Output> {
-        // Try to get the target directory from CARGO_TARGET_DIR or cargo metadata
-        let target_dir = if let Ok(output) = Command::new("cargo")
+    fn process_build_output(&self, dir: &Path) -> Result<Self::Output> {
+        let metadata = Command::new("cargo")
             .args(["metadata", "--format-version", "1", "--no-deps"])
+            .current_dir(dir)
             .output()
-        {
-            if let Ok(metadata_str) = String::from_utf8(output.stdout) {
-                if let Ok(metadata) = serde_json::from_str::<Value>(&metadata_str) {
-                    if let Some(target_dir) =
-                        metadata.get("target_directory").and_then(|v| v.as_str())
-                    {
-                        std::path::PathBuf::from(target_dir)
-                    } else {
-                        std::path::PathBuf::from("target")
-                    }
-                } else {
-                    std::path::PathBuf::from("target")
-                }
-            } else {
-                std::path::PathBuf::from("target")
-            }
-        } else {
-            std::path::PathBuf::from("target")
-        };
-
+            .ok()
+            .and_then(|output| serde_json::from_slice::<Value>(&output.stdout).ok())
+            .unwrap_or(Value::Null);
+
+        // Honour CARGO_TARGET_DIR and workspace target directories
+        let target_dir = metadata
+            .get("target_directory")
+            .and_then(|v| v.as_str())
+            .map(std::path::PathBuf::from)
+            .unwrap_or_else(|| dir.join("target"));
         let target_package_dir = target_dir.join("package");
 
         if !target_package_dir.exists() {
@@ -226,17 +210,59 @@ impl PackageBuilder for CargoBuilder {
             );
         }
 
+        // Only pick up the crates this build produced, not stale ones from earlier runs
+        let manifest = dir.join("Cargo.toml");
+        let workspace = Self::is_workspace_root(dir);
+        let expected: Vec<String> = metadata
+            .get("packages")
+            .and_then(|v| v.as_array())
+            .into_iter()
+            .flatten()
+            .filter(|package| {
+                if workspace {
+                    // `publish = false` is reported as an empty registry list
+                    !package
+                        .get("publish")
+                        .and_then(|p| p.as_array())
+                        .is_some_and(|registries| registries.is_empty())
+                } else {
+                    package
+                        .get("manifest_path")
+                        .and_then(|p| p.as_str())
+                        .is_some_and(|p| Path::new(p) == manifest)
+                }
+            })
+            .filter_map(|package| {
+                Some(format!(
+                    "{}-{}.crate",
+                    package.get("name")?.as_str()?,
+                    package.get("version")?.as_str()?
+                ))
+            })
+            .collect();
+
+        let mut crate_files = Vec::new();
         for entry in fs::read_dir(&target_package_dir)? {
-            let entry = entry?;
-            let path = entry.path();
+            let path = entry?.path();
             if path.is_file() && path.extension() == Some(std::ffi::OsStr::new("crate")) {
-                return Ok(path);
+                let file_name = path
+                    .file_name()
+                    .map(|n| n.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+                if expected.is_empty() || expected.contains(&file_name) {
+                    crate_files.push(path);
+                }
             }
         }
-        anyhow::bail!(
-            "Could not find .crate file in {}",
-            target_package_dir.display()
-        );
+
+        if crate_files.is_empty() {
+            anyhow::bail!(
+                "Could not find .crate file in {}",
+                target_package_dir.display()
+            );
+        }
+        crate_files.sort();
+        Ok(crate_files)
     }
 
     fn progress_message(&self) -> &str {
@@ -254,7 +280,12 @@ pub fn build_npm_package() -> Result<(std::path::PathBuf, Value)> {
     NpmBuilder.build()
 }
 
-/// Build Cargo package using the builder
-pub fn build_cargo_package() -> Result<std::path::PathBuf> {
+/// Build Cargo package(s) using the builder
+pub fn build_cargo_package() -> Result<Vec<std::path::PathBuf>> {
     CargoBuilder.build()
 }
+
+/// Build Cargo package(s) in `dir` using the builder
+pub fn build_cargo_package_in(dir: &Path) -> Result<Vec<std::path::PathBuf>> {
+    CargoBuilder.build_in(dir)
+}
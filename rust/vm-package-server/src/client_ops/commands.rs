@@ -5,6 +5,7 @@
 
 use super::builders::*;
 use super::detection::*;
+use super::uploads::*;
 use crate::api::PackageServerClient;
 use anyhow::Result;
 use colored::Colorize;
@@ -66,7 +67,11 @@ pub fn add_package(server_url: &str, type_filter: Option<&str>) -> Result<()> {
         info!("");
     }
 
-    // Build and upload each package
+    if !use_local {
+        return publish_packages(&client, &packages_to_build);
+    }
+
+    // Build and add each package to local storage
     let mut results = Vec::new();
     for (i, package_info) in packages_to_build.iter().enumerate() {
         if packages_to_build.len() > 1 {
@@ -79,17 +84,10 @@ pub fn add_package(server_url: &str, type_filter: Option<&str>) -> Result<()> {
             );
         }
 
-        let result = if use_local {
-            package_info
-                .package_type
-                .add_package_local(&package_info.name)
-        } else {
-            package_info
-                .package_type
-                .add_package(&client, &package_info.name)
-        };
-
-        match result {
+        match package_info
+            .package_type
+            .add_package_local(&package_info.name)
+        {
             Ok(_) => {
                 results.push((package_info.clone(), true));
                 if packages_to_build.len() > 1 {
@@ -138,8 +136,103 @@ pub fn add_package(server_url: &str, type_filter: Option<&str>) -> Result<()> {
     Ok(())
 }
 
+/// Build every selected package, then upload all artifacts in parallel
+fn publish_packages(client: &PackageServerClient, packages: &[PackageInfo]) -> Result<()> {
+    let current_dir = std::env::current_dir()?;
+    let mut artifacts = Vec::new();
+    let mut build_failures = 0;
+    for (i, package_info) in packages.iter().enumerate() {
+        if packages.len() > 1 {
+            info!(
+                "[{}/{}] Building {} package: {}",
+                i + 1,
+                packages.len(),
+                package_info.package_type,
+                package_info.name
+            );
+        } else {
+            info!(package_name = %package_info.name, "🔨 Building {} package...", package_info.package_type);
+        }
+
+        match build_artifacts(&package_info.package_type, &package_info.name, &current_dir) {
+            Ok(built) => artifacts.extend(built),
+            Err(e) if packages.len() == 1 => return Err(e),
+            Err(e) => {
+                build_failures += 1;
+                error!(package_name = %package_info.name, error = %e, "Failed to build package");
+                error!("  ❌ {} failed to build: {}", package_info.name, e);
+            }
+        }
+    }
+
+    if artifacts.is_empty() {
+        anyhow::bail!("No packages were built");
+    }
+
+    info!(
+        "📤 Uploading {} artifact(s) to package server...",
+        artifacts.len()
+    );
+    let results = upload_artifacts(client, &artifacts);
+
+    // npm tarballs are only needed for the upload
+    for artifact in &artifacts {
+        if matches!(artifact, Artifact::Npm { .. }) {
+            let _ = fs::remove_file(artifact.path());
+        }
+    }
+
+    let mut published = Vec::new();
+    let mut upload_failures = Vec::new();
+    for (artifact, result) in artifacts.into_iter().zip(results) {
+        match result {
+            Ok(()) => published.push(artifact),
+            Err(e) => {
+                error!(file = %artifact.path().display(), error = %e, "Failed to upload artifact");
+                upload_failures.push((artifact, e));
+            }
+        }
+    }
+
+    let instructions = install_instructions(client.base_url(), &published);
+    if !instructions.is_empty() {
+        info!("");
+        info!("📋 Install with:");
+        for instruction in &instructions {
+            info!("  {}", instruction);
+        }
+    }
+
+    if upload_failures.is_empty() && build_failures == 0 {
+        if published.len() > 1 {
+            info!(
+                "✨ All {} artifacts published successfully!",
+                published.len()
+            );
+        }
+        return Ok(());
+    }
+
+    info!("");
+    for (artifact, e) in &upload_failures {
+        error!(
+            "  ❌ {} ({}) failed to upload: {}",
+            artifact.label(),
+            artifact.package_type(),
+            e
+        );
+    }
+    error!(
+        "❌ {} artifacts published, {} uploads failed, {} builds failed",
+        published.len(),
+        upload_failures.len(),
+        build_failures
+    );
+    anyhow::bail!("Some packages failed to publish");
+}
+
 /// Filter package types based on CLI string input
-fn filter_types_from_string(
+pub(super) fn filter_types_from_string(
     detected_types: &[PackageType],
     type_filter: &str,
 ) -> Result<Vec<PackageType>> {
@@ -247,70 +340,49 @@ pub fn add_npm_package_local(package_name: &str) -> Result<()> {
 pub fn add_cargo_package_local(package_name: &str) -> Result<()> {
     info!(package_name = %package_name, "🔨 Building Cargo package for local storage");
 
-    // Build the package (reuse existing build logic)
-    let crate_file = build_cargo_package()?;
+    // Build the package(s) (reuse existing build logic)
+    let crate_files = build_cargo_package()?;
 
     // Get data directory (search upward for project root)
     let data_dir = vm_core::project::get_package_data_dir()?;
 
-    // Add to local storage
-    crate::local_storage::add_cargo_package_local(&crate_file, &data_dir)?;
+    // Add each crate to local storage
+    for crate_file in crate_files {
+        crate::local_storage::add_cargo_package_local(&crate_file, &data_dir)?;
+    }
 
     info!("✅ {} successfully added to local storage", package_name);
     Ok(())
 }
 
 pub fn add_python_package(client: &PackageServerClient, package_name: &str) -> Result<()> {
-    info!(package_name = %package_name, "🔨 Building Python package...");
-
-    // Build the package using the common builder
-    let package_files = PythonBuilder.build()?;
-
-    info!("📤 Uploading to package server...");
-
-    // Upload all built files
-    for path in package_files {
-        debug!(file = %path.display(), "Uploading Python package file");
-        client.upload_pypi_package(&path)?;
-    }
-
-    info!(package_name = %package_name, "Python package uploaded successfully");
-    info!("📋 Install with: pip install {}", package_name);
-    Ok(())
+    publish_packages(
+        client,
+        &[PackageInfo {
+            package_type: PackageType::Python,
+            name: package_name.to_string(),
+        }],
+    )
 }
 
 pub fn add_npm_package(client: &PackageServerClient, package_name: &str) -> Result<()> {
-    info!(package_name = %package_name, "🔨 Building NPM package...");
-
-    // Build the package using the common builder
-    let (tarball_path, metadata) = NpmBuilder.build()?;
-
-    info!("📤 Publishing to package server...");
-
-    // Read tarball data for upload
-    let tarball_data = fs::read(&tarball_path)?;
-    client.upload_npm_package(package_name, &tarball_data, metadata)?;
-
-    // Clean up tarball
-    let _ = fs::remove_file(&tarball_path);
-
-    info!(package_name = %package_name, "NPM package published successfully");
-    info!("📋 Install with: npm install {}", package_name);
-    Ok(())
+    publish_packages(
+        client,
+        &[PackageInfo {
+            package_type: PackageType::Npm,
+            name: package_name.to_string(),
+        }],
+    )
 }
 
 pub fn add_cargo_package(client: &PackageServerClient, package_name: &str) -> Result<()> {
-    info!(package_name = %package_name, "🔨 Building Cargo package...");
-
-    // Build the package using the common builder
-    let crate_file = CargoBuilder.build()?;
-
-    info!("📤 Publishing to package server...");
-    client.upload_cargo_crate(&crate_file)?;
-
-    info!(package_name = %package_name, "Cargo crate published successfully");
-    info!("📋 Install with: cargo add {}", package_name);
-    Ok(())
+    publish_packages(
+        client,
+        &[PackageInfo {
+            package_type: PackageType::Cargo,
+            name: package_name.to_string(),
+        }],
+    )
 }
 
 fn remove_package_local(force: bool) -> Result<()> {
@@ -649,6 +721,24 @@ pub fn show_status(server_url: &str) -> Result<()> {
         info!("📊 Server status: {}", status);
     }
 
+    if let Ok(readiness) = client.get_readiness() {
+        let overall = readiness["status"].as_str().unwrap_or("unknown");
+        info!("🩺 Readiness: {}", overall.replace('_', " "));
+        for check in readiness["checks"].as_array().into_iter().flatten() {
+            let name = check["name"].as_str().unwrap_or("?");
+            let icon = match check["status"].as_str() {
+                Some("ok") => "✅",
+                Some("skipped") => "⏭️",
+                _ if check["required"].as_bool().unwrap_or(false) => "❌",
+                _ => "⚠️",
+            };
+            match check["detail"].as_str() {
+                Some(detail) => info!("  {} {}: {}", icon, name, detail),
+                None => info!("  {} {}", icon, name),
+            }
+        }
+    }
+
     // Also show package counts
     if let Ok(packages) = client.list_all_packages() {
         let pypi_count = packages.get("pypi").map(|p| p.len()).unwrap_or(0);
@@ -249,5 +249,12 @@ fn get_cargo_package_name() -> Result<String> {
         }
     }
 
+    // Virtual workspace: every member is published, so name it after the directory
+    if cargo_toml.lines().any(|line| line.trim() == "[workspace]") {
+        if let Some(name) = env::current_dir()?.file_name() {
+            return Ok(name.to_string_lossy().into_owned());
+        }
+    }
+
     anyhow::bail!("Could not find package name in Cargo.toml");
 }
@@ -6,8 +6,14 @@
 mod builders;
 mod commands;
 mod detection;
+mod progress;
+mod uploads;
+mod workspace;
 
 // Re-export all public functions and types to maintain API compatibility
 pub use builders::*;
 pub use commands::*;
 pub use detection::*;
+pub use progress::*;
+pub use uploads::*;
+pub use workspace::*;
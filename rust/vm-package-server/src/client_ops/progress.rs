@@ -0,0 +1,86 @@
+//! Progress reporting for package builds and uploads
+//!
+//! Builds show a single spinner; uploads show one line per artifact under a
+//! shared [`MultiProgress`] so parallel uploads don't overwrite each other.
+
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use std::time::Duration;
+
+/// Utility for creating consistent progress bars across build operations
+pub struct ProgressBarManager {
+    pb: ProgressBar,
+}
+
+impl ProgressBarManager {
+    /// Create a new progress bar with consistent styling
+    pub fn new(message: &str) -> Self {
+        let pb = ProgressBar::new_spinner();
+        pb.set_style(
+            ProgressStyle::default_spinner()
+                .template("{spinner:.green} {msg}")
+                .unwrap_or_else(|_| ProgressStyle::default_spinner()),
+        );
+        pb.set_message(message.to_string());
+        Self { pb }
+    }
+
+    /// Finish the progress bar and clear it
+    pub fn finish(self) {
+        self.pb.finish_and_clear();
+    }
+}
+
+/// A group of per-artifact upload bars
+pub struct UploadProgress {
+    multi: MultiProgress,
+}
+
+impl UploadProgress {
+    pub fn new() -> Self {
+        Self {
+            multi: MultiProgress::new(),
+        }
+    }
+
+    /// Add a line for an artifact waiting to be uploaded
+    pub fn add(&self, label: &str) -> UploadBar {
+        let pb = self.multi.add(ProgressBar::new_spinner());
+        pb.set_style(
+            ProgressStyle::default_spinner()
+                .template("{spinner:.green} {prefix:.bold} {msg}")
+                .unwrap_or_else(|_| ProgressStyle::default_spinner()),
+        );
+        pb.set_prefix(label.to_string());
+        pb.set_message("waiting");
+        UploadBar { pb }
+    }
+}
+
+impl Default for UploadProgress {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Progress line for a single artifact upload
+pub struct UploadBar {
+    pb: ProgressBar,
+}
+
+impl UploadBar {
+    /// Mark the upload as in flight
+    pub fn start(&self) {
+        self.pb.set_message("uploading...");
+        self.pb.enable_steady_tick(Duration::from_millis(100));
+    }
+
+    /// Finish the line with the upload outcome
+    pub fn finish(self, success: bool) {
+        let message = if success {
+            "✅ uploaded"
+        } else {
+            "❌ failed"
+        };
+        self.pb.finish_with_message(message);
+    }
+}
@@ -0,0 +1,215 @@
+//! Parallel artifact uploads
+//!
+//! A single `pkg add` can produce several artifacts (an sdist and a wheel, or one
+//! crate per workspace member). They are uploaded concurrently, each with its own
+//! progress line, and the install instructions are derived from what was actually
+//! published.
+
+use super::builders::*;
+use super::detection::PackageType;
+use super::progress::UploadProgress;
+use crate::api::PackageServerClient;
+use crate::utils::{extract_cargo_name_and_version, extract_pypi_package_name_and_version};
+use anyhow::Result;
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+/// Maximum number of artifacts uploaded at the same time
+const MAX_PARALLEL_UPLOADS: usize = 4;
+
+/// A built package file ready to be uploaded
+#[derive(Debug, Clone)]
+pub enum Artifact {
+    /// Python wheel or source distribution
+    Python(PathBuf),
+    /// npm tarball with its package.json metadata
+    Npm {
+        path: PathBuf,
+        name: String,
+        metadata: Value,
+    },
+    /// Cargo `.crate` file
+    Cargo(PathBuf),
+}
+
+impl Artifact {
+    pub fn path(&self) -> &Path {
+        match self {
+            Artifact::Python(path) | Artifact::Cargo(path) => path,
+            Artifact::Npm { path, .. } => path,
+        }
+    }
+
+    pub fn package_type(&self) -> PackageType {
+        match self {
+            Artifact::Python(_) => PackageType::Python,
+            Artifact::Npm { .. } => PackageType::Npm,
+            Artifact::Cargo(_) => PackageType::Cargo,
+        }
+    }
+
+    /// File name shown on the progress line
+    pub fn label(&self) -> String {
+        self.path()
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| self.path().display().to_string())
+    }
+
+    /// Upload this artifact to the package server
+    pub fn upload(&self, client: &PackageServerClient) -> Result<()> {
+        match self {
+            Artifact::Python(path) => client.upload_pypi_package(path),
+            Artifact::Npm {
+                path,
+                name,
+                metadata,
+            } => {
+                let tarball_data = std::fs::read(path)?;
+                client.upload_npm_package(name, &tarball_data, metadata.clone())
+            }
+            Artifact::Cargo(path) => client.upload_cargo_crate(path),
+        }
+    }
+
+    /// Command that installs this artifact from the package server
+    pub fn install_instruction(&self, server_url: &str) -> Option<String> {
+        let server_url = server_url.trim_end_matches('/');
+        match self {
+            Artifact::Python(_) => {
+                let (name, version) = extract_pypi_package_name_and_version(&self.label())?;
+                Some(format!(
+                    "pip install --index-url {server_url}/pypi/simple/ {name}=={version}"
+                ))
+            }
+            Artifact::Npm { name, metadata, .. } => {
+                let version = metadata.get("version").and_then(|v| v.as_str());
+                let spec = match version {
+                    Some(version) => format!("{name}@{version}"),
+                    None => name.clone(),
+                };
+                Some(format!("npm install --registry {server_url}/npm/ {spec}"))
+            }
+            Artifact::Cargo(_) => {
+                let (name, version) = extract_cargo_name_and_version(&self.label())?;
+                Some(format!("cargo add {name}@{version} --registry local"))
+            }
+        }
+    }
+}
+
+/// Build the package in `dir` and collect its artifacts
+pub fn build_artifacts(
+    package_type: &PackageType,
+    package_name: &str,
+    dir: &Path,
+) -> Result<Vec<Artifact>> {
+    Ok(match package_type {
+        PackageType::Python => PythonBuilder
+            .build_in(dir)?
+            .into_iter()
+            .map(Artifact::Python)
+            .collect(),
+        PackageType::Npm => {
+            let (path, metadata) = NpmBuilder.build_in(dir)?;
+            vec![Artifact::Npm {
+                path,
+                name: package_name.to_string(),
+                metadata,
+            }]
+        }
+        PackageType::Cargo => CargoBuilder
+            .build_in(dir)?
+            .into_iter()
+            .map(Artifact::Cargo)
+            .collect(),
+    })
+}
+
+/// Upload artifacts concurrently, returning each artifact's result in input order
+pub fn upload_artifacts(client: &PackageServerClient, artifacts: &[Artifact]) -> Vec<Result<()>> {
+    let progress = UploadProgress::new();
+    let mut bars: Vec<_> = artifacts
+        .iter()
+        .map(|artifact| Some(progress.add(&artifact.label())))
+        .collect();
+
+    let mut results = Vec::with_capacity(artifacts.len());
+    for (chunk, chunk_bars) in artifacts
+        .chunks(MAX_PARALLEL_UPLOADS)
+        .zip(bars.chunks_mut(MAX_PARALLEL_UPLOADS))
+    {
+        let chunk_results: Vec<Result<()>> = std::thread::scope(|scope| {
+            let handles: Vec<_> = chunk
+                .iter()
+                .zip(chunk_bars.iter_mut())
+                .map(|(artifact, bar)| {
+                    let bar = bar.take();
+                    scope.spawn(move || {
+                        if let Some(bar) = &bar {
+                            bar.start();
+                        }
+                        let result = artifact.upload(client);
+                        if let Some(bar) = bar {
+                            bar.finish(result.is_ok());
+                        }
+                        result
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| {
+                    handle
+                        .join()
+                        .unwrap_or_else(|_| Err(anyhow::anyhow!("Upload thread panicked")))
+                })
+                .collect()
+        });
+        results.extend(chunk_results);
+    }
+    results
+}
+
+/// Install commands for the published artifacts, one per package version
+pub fn install_instructions(server_url: &str, artifacts: &[Artifact]) -> Vec<String> {
+    let mut instructions: Vec<String> = Vec::new();
+    for instruction in artifacts
+        .iter()
+        .filter_map(|artifact| artifact.install_instruction(server_url))
+    {
+        // An sdist and a wheel of the same release share one instruction
+        if !instructions.contains(&instruction) {
+            instructions.push(instruction);
+        }
+    }
+    instructions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn install_instructions_are_deduplicated_per_release() {
+        let artifacts = vec![
+            Artifact::Python(PathBuf::from("dist/my_pkg-1.2.0-py3-none-any.whl")),
+            Artifact::Python(PathBuf::from("dist/my_pkg-1.2.0.tar.gz")),
+            Artifact::Cargo(PathBuf::from("target/package/core-utils-0.3.1.crate")),
+            Artifact::Npm {
+                path: PathBuf::from("widget-2.0.0.tgz"),
+                name: "widget".to_string(),
+                metadata: serde_json::json!({ "version": "2.0.0" }),
+            },
+        ];
+
+        assert_eq!(
+            install_instructions("http://localhost:3080/", &artifacts),
+            vec![
+                "pip install --index-url http://localhost:3080/pypi/simple/ my-pkg==1.2.0",
+                "cargo add core-utils@0.3.1 --registry local",
+                "npm install --registry http://localhost:3080/npm/ widget@2.0.0",
+            ]
+        );
+    }
+}
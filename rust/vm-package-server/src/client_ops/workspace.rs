@@ -0,0 +1,517 @@
+//! Monorepo publishing
+//!
+//! `add --workspace` discovers every publishable package below the current
+//! directory (Cargo workspace members, npm workspaces and Python projects with a
+//! `pyproject.toml`) and publishes them in dependency order, so a package's
+//! workspace dependencies are already on the server when it is uploaded.
+
+use super::builders::build_cargo_package_in;
+use super::commands::filter_types_from_string;
+use super::detection::PackageType;
+use super::uploads::*;
+use crate::api::PackageServerClient;
+use crate::normalize_pypi_name;
+use anyhow::{Context, Result};
+use serde_json::Value;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tracing::{error, info};
+
+/// Directories never searched for Python projects
+const IGNORED_DIRS: &[&str] = &["node_modules", "target", "venv", "dist", "build"];
+
+/// A publishable package found in the workspace
+#[derive(Debug, Clone)]
+pub struct WorkspacePackage {
+    pub package_type: PackageType,
+    pub name: String,
+    pub version: Option<String>,
+    pub dir: PathBuf,
+    /// Workspace packages of the same type this package depends on
+    pub dependencies: Vec<String>,
+}
+
+enum PublishOutcome {
+    Published(usize),
+    Failed(String),
+    Skipped(String),
+}
+
+/// Find all publishable packages below `root`
+pub fn discover_workspace_packages(root: &Path) -> Result<Vec<WorkspacePackage>> {
+    let mut packages = discover_cargo_packages(root)?;
+    packages.extend(discover_npm_packages(root)?);
+    packages.extend(discover_python_packages(root)?);
+
+    // Only dependencies on other workspace packages affect the publish order
+    let known: Vec<(PackageType, String)> = packages
+        .iter()
+        .map(|p| (p.package_type.clone(), p.name.clone()))
+        .collect();
+    for package in &mut packages {
+        let package_type = package.package_type.clone();
+        package
+            .dependencies
+            .retain(|dep| known.iter().any(|(t, n)| *t == package_type && n == dep));
+    }
+    Ok(packages)
+}
+
+/// Order packages so every package comes after the workspace packages it depends on
+pub fn dependency_order(packages: Vec<WorkspacePackage>) -> Result<Vec<WorkspacePackage>> {
+    let mut remaining = packages;
+    let mut ordered: Vec<WorkspacePackage> = Vec::with_capacity(remaining.len());
+    while !remaining.is_empty() {
+        let ready = remaining.iter().position(|package| {
+            package.dependencies.iter().all(|dep| {
+                ordered
+                    .iter()
+                    .any(|o| o.package_type == package.package_type && &o.name == dep)
+            })
+        });
+        match ready {
+            Some(index) => ordered.push(remaining.remove(index)),
+            None => {
+                let names: Vec<&str> = remaining.iter().map(|p| p.name.as_str()).collect();
+                anyhow::bail!(
+                    "Dependency cycle between workspace packages: {}",
+                    names.join(", ")
+                );
+            }
+        }
+    }
+    Ok(ordered)
+}
+
+/// Publish every package in the workspace rooted at the current directory
+pub fn add_workspace_packages(server_url: &str, type_filter: Option<&str>) -> Result<()> {
+    let client = PackageServerClient::new(server_url);
+    if !client.is_server_running() {
+        anyhow::bail!("Package server is not running at {server_url}");
+    }
+
+    let root = env::current_dir().context("Failed to get current directory")?;
+    let mut packages = discover_workspace_packages(&root)?;
+    if let Some(type_filter) = type_filter {
+        let mut present: Vec<PackageType> = Vec::new();
+        for package in &packages {
+            if !present.contains(&package.package_type) {
+                present.push(package.package_type.clone());
+            }
+        }
+        let selected = filter_types_from_string(&present, type_filter)?;
+        packages.retain(|p| selected.contains(&p.package_type));
+    }
+    let packages = dependency_order(packages)?;
+    if packages.is_empty() {
+        anyhow::bail!("No publishable packages found in workspace");
+    }
+
+    info!(
+        "📦 Publishing {} workspace packages in dependency order:",
+        packages.len()
+    );
+    for (i, package) in packages.iter().enumerate() {
+        info!("  {}. {} ({})", i + 1, package.name, package.package_type);
+    }
+    info!("");
+
+    // Cargo members are packaged together so path dependencies between them resolve
+    let crate_files = if packages
+        .iter()
+        .any(|p| p.package_type == PackageType::Cargo)
+    {
+        info!("🔨 Packaging Cargo workspace...");
+        build_cargo_package_in(&root).map_err(|e| e.to_string())
+    } else {
+        Ok(Vec::new())
+    };
+
+    let mut outcomes: Vec<(&WorkspacePackage, PublishOutcome)> = Vec::new();
+    for (i, package) in packages.iter().enumerate() {
+        let blocked = package.dependencies.iter().find(|dep| {
+            outcomes.iter().any(|(p, outcome)| {
+                p.package_type == package.package_type
+                    && &p.name == *dep
+                    && !matches!(outcome, PublishOutcome::Published(_))
+            })
+        });
+
+        let outcome = if let Some(dep) = blocked {
+            PublishOutcome::Skipped(format!("{dep} was not published"))
+        } else {
+            info!(
+                "[{}/{}] Publishing {} package: {}",
+                i + 1,
+                packages.len(),
+                package.package_type,
+                package.name
+            );
+            match publish_workspace_package(&client, package, &crate_files) {
+                Ok(count) => PublishOutcome::Published(count),
+                Err(e) => {
+                    error!(package_name = %package.name, error = %e, "Failed to publish workspace package");
+                    PublishOutcome::Failed(e.to_string())
+                }
+            }
+        };
+        outcomes.push((package, outcome));
+    }
+
+    print_summary(&outcomes);
+
+    let unpublished = outcomes
+        .iter()
+        .filter(|(_, outcome)| !matches!(outcome, PublishOutcome::Published(_)))
+        .count();
+    if unpublished > 0 {
+        anyhow::bail!(
+            "{unpublished} of {} workspace packages were not published",
+            outcomes.len()
+        );
+    }
+    info!(
+        "✨ All {} workspace packages published successfully!",
+        outcomes.len()
+    );
+    Ok(())
+}
+
+fn publish_workspace_package(
+    client: &PackageServerClient,
+    package: &WorkspacePackage,
+    crate_files: &std::result::Result<Vec<PathBuf>, String>,
+) -> Result<usize> {
+    let artifacts = match package.package_type {
+        PackageType::Cargo => {
+            let crate_files = crate_files.as_ref().map_err(|e| anyhow::anyhow!("{e}"))?;
+            let file_name = format!(
+                "{}-{}.crate",
+                package.name,
+                package.version.as_deref().unwrap_or_default()
+            );
+            let artifacts: Vec<Artifact> = crate_files
+                .iter()
+                .filter(|path| {
+                    path.file_name()
+                        .is_some_and(|name| name.to_string_lossy() == file_name)
+                })
+                .cloned()
+                .map(Artifact::Cargo)
+                .collect();
+            if artifacts.is_empty() {
+                anyhow::bail!("{file_name} was not produced by cargo package");
+            }
+            artifacts
+        }
+        _ => build_artifacts(&package.package_type, &package.name, &package.dir)?,
+    };
+
+    let results = upload_artifacts(client, &artifacts);
+    for artifact in &artifacts {
+        if matches!(artifact, Artifact::Npm { .. }) {
+            let _ = fs::remove_file(artifact.path());
+        }
+    }
+    results.into_iter().collect::<Result<Vec<_>>>()?;
+    Ok(artifacts.len())
+}
+
+fn print_summary(outcomes: &[(&WorkspacePackage, PublishOutcome)]) {
+    info!("");
+    info!("📋 Workspace Publishing Summary:");
+    info!("  {:<32} {:<8} {:<12} STATUS", "PACKAGE", "TYPE", "VERSION");
+    for (package, outcome) in outcomes {
+        let status = match outcome {
+            PublishOutcome::Published(1) => "✅ published".to_string(),
+            PublishOutcome::Published(count) => format!("✅ published ({count} files)"),
+            PublishOutcome::Failed(e) => format!("❌ failed: {e}"),
+            PublishOutcome::Skipped(reason) => format!("⏭️  skipped: {reason}"),
+        };
+        info!(
+            "  {:<32} {:<8} {:<12} {}",
+            package.name,
+            package.package_type.to_string(),
+            package.version.as_deref().unwrap_or("-"),
+            status
+        );
+    }
+    info!("");
+}
+
+fn discover_cargo_packages(root: &Path) -> Result<Vec<WorkspacePackage>> {
+    if !root.join("Cargo.toml").exists() {
+        return Ok(Vec::new());
+    }
+
+    let output = Command::new("cargo")
+        .args(["metadata", "--format-version", "1", "--no-deps"])
+        .current_dir(root)
+        .output()
+        .context("Failed to run cargo metadata")?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "cargo metadata failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+    let metadata: Value =
+        serde_json::from_slice(&output.stdout).context("Failed to parse cargo metadata")?;
+
+    Ok(metadata["packages"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        // `publish = false` is reported as an empty registry list
+        .filter(|p| !p["publish"].as_array().is_some_and(|r| r.is_empty()))
+        .filter_map(|p| {
+            Some(WorkspacePackage {
+                package_type: PackageType::Cargo,
+                name: p["name"].as_str()?.to_string(),
+                version: p["version"].as_str().map(str::to_string),
+                dir: Path::new(p["manifest_path"].as_str()?)
+                    .parent()?
+                    .to_path_buf(),
+                // Dev-dependencies are stripped by `cargo package` and may form cycles
+                dependencies: p["dependencies"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter(|d| d["kind"].as_str() != Some("dev"))
+                    .filter_map(|d| d["name"].as_str().map(str::to_string))
+                    .collect(),
+            })
+        })
+        .collect())
+}
+
+fn discover_npm_packages(root: &Path) -> Result<Vec<WorkspacePackage>> {
+    let Ok(content) = fs::read_to_string(root.join("package.json")) else {
+        return Ok(Vec::new());
+    };
+    let manifest: Value = serde_json::from_str(&content).context("Failed to parse package.json")?;
+
+    // "workspaces": ["packages/*"] or {"packages": ["packages/*"]}
+    let patterns: Vec<&str> = match &manifest["workspaces"] {
+        Value::Array(patterns) => patterns.iter().filter_map(Value::as_str).collect(),
+        Value::Object(config) => config
+            .get("packages")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_str)
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    let mut dirs = Vec::new();
+    if patterns.is_empty() {
+        dirs.push(root.to_path_buf());
+    }
+    for pattern in patterns.iter().filter(|p| !p.starts_with('!')) {
+        let pattern = root.join(pattern).join("package.json");
+        for manifest_path in glob::glob(&pattern.to_string_lossy())?.flatten() {
+            if let Some(dir) = manifest_path.parent() {
+                dirs.push(dir.to_path_buf());
+            }
+        }
+    }
+
+    let mut packages = Vec::new();
+    for dir in dirs {
+        let manifest: Value = serde_json::from_str(&fs::read_to_string(dir.join("package.json"))?)
+            .with_context(|| format!("Failed to parse {}/package.json", dir.display()))?;
+        if manifest["private"].as_bool() == Some(true) {
+            continue;
+        }
+        let Some(name) = manifest["name"].as_str() else {
+            continue;
+        };
+        let dependencies = ["dependencies", "peerDependencies", "optionalDependencies"]
+            .iter()
+            .filter_map(|key| manifest[*key].as_object())
+            .flat_map(|deps| deps.keys().cloned())
+            .collect();
+        packages.push(WorkspacePackage {
+            package_type: PackageType::Npm,
+            name: name.to_string(),
+            version: manifest["version"].as_str().map(str::to_string),
+            dir,
+            dependencies,
+        });
+    }
+    Ok(packages)
+}
+
+fn discover_python_packages(root: &Path) -> Result<Vec<WorkspacePackage>> {
+    let mut packages = Vec::new();
+    for pattern in ["pyproject.toml", "*/pyproject.toml", "*/*/pyproject.toml"] {
+        for manifest_path in glob::glob(&root.join(pattern).to_string_lossy())?.flatten() {
+            let ignored = manifest_path
+                .strip_prefix(root)
+                .unwrap_or(&manifest_path)
+                .components()
+                .any(|c| {
+                    let part = c.as_os_str().to_string_lossy();
+                    part.starts_with('.') || IGNORED_DIRS.contains(&part.as_ref())
+                });
+            if ignored {
+                continue;
+            }
+            let content = fs::read_to_string(&manifest_path)?;
+            let Some((name, version, dependencies)) = parse_pyproject(&content) else {
+                continue;
+            };
+            let Some(dir) = manifest_path.parent() else {
+                continue;
+            };
+            packages.push(WorkspacePackage {
+                package_type: PackageType::Python,
+                name,
+                version,
+                dir: dir.to_path_buf(),
+                dependencies,
+            });
+        }
+    }
+    Ok(packages)
+}
+
+/// Read the normalized name, version and dependency names from a `[project]` table
+fn parse_pyproject(content: &str) -> Option<(String, Option<String>, Vec<String>)> {
+    let mut in_project = false;
+    let mut in_dependencies = false;
+    let mut name = None;
+    let mut version = None;
+    let mut dependencies = Vec::new();
+
+    for line in content.lines() {
+        let line = line.trim();
+        if in_dependencies {
+            dependencies.extend(requirement_names(line));
+            if line.contains(']') {
+                in_dependencies = false;
+            }
+            continue;
+        }
+        if line.starts_with('[') {
+            in_project = line == "[project]";
+            continue;
+        }
+        if !in_project {
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let unquote = |v: &str| v.trim().trim_matches('"').trim_matches('\'').to_string();
+        match key.trim() {
+            "name" => name = Some(normalize_pypi_name(&unquote(value))),
+            "version" => version = Some(unquote(value)),
+            "dependencies" => {
+                dependencies.extend(requirement_names(value));
+                in_dependencies = !value.contains(']');
+            }
+            _ => {}
+        }
+    }
+
+    Some((name?, version, dependencies))
+}
+
+/// Normalized project names of the quoted requirement strings on a line
+fn requirement_names(line: &str) -> Vec<String> {
+    line.split(['"', '\''])
+        .skip(1)
+        .step_by(2)
+        .filter_map(|requirement| {
+            let name: String = requirement
+                .trim()
+                .chars()
+                .take_while(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+                .collect();
+            (!name.is_empty()).then(|| normalize_pypi_name(&name))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn discovers_npm_and_python_packages_in_dependency_order() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        let write = |path: &str, content: &str| {
+            let path = root.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        };
+        write(
+            "package.json",
+            r#"{"name": "monorepo", "private": true, "workspaces": ["packages/*"]}"#,
+        );
+        write(
+            "packages/app/package.json",
+            r#"{"name": "app", "version": "1.0.0", "dependencies": {"core": "^1.0.0", "react": "^18"}}"#,
+        );
+        write(
+            "packages/core/package.json",
+            r#"{"name": "core", "version": "1.0.0"}"#,
+        );
+        write(
+            "python/api/pyproject.toml",
+            "[project]\nname = \"Acme_API\"\nversion = \"0.2.0\"\ndependencies = [\n  \"acme-models>=1.0\",\n  \"requests\",\n]\n",
+        );
+        write(
+            "python/models/pyproject.toml",
+            "[project]\nname = \"acme-models\"\nversion = \"1.0.0\"\n",
+        );
+        write(
+            "packages/app/node_modules/dep/pyproject.toml",
+            "[project]\nname = \"ignored\"\n",
+        );
+
+        let packages = dependency_order(discover_workspace_packages(root).unwrap()).unwrap();
+        let names: Vec<&str> = packages.iter().map(|p| p.name.as_str()).collect();
+
+        assert_eq!(names.len(), 4);
+        let position = |name: &str| names.iter().position(|n| *n == name).unwrap();
+        assert!(position("core") < position("app"));
+        assert!(position("acme-models") < position("acme-api"));
+        assert_eq!(packages[position("app")].dependencies, vec!["core"]);
+    }
+
+    #[test]
+    fn rejects_dependency_cycles() {
+        let package = |name: &str, dep: &str| WorkspacePackage {
+            package_type: PackageType::Npm,
+            name: name.to_string(),
+            version: None,
+            dir: PathBuf::new(),
+            dependencies: vec![dep.to_string()],
+        };
+        assert!(dependency_order(vec![package("a", "b"), package("b", "a")]).is_err());
+    }
+
+    #[test]
+    fn finds_build_output_in_the_package_directory() {
+        use crate::client_ops::builders::{NpmBuilder, PackageBuilder};
+
+        let temp_dir = TempDir::new().unwrap();
+        let package_dir = temp_dir.path().join("packages/app");
+        fs::create_dir_all(&package_dir).unwrap();
+        fs::write(package_dir.join("package.json"), r#"{"name": "app"}"#).unwrap();
+        fs::write(package_dir.join("app-1.0.0.tgz"), b"x").unwrap();
+
+        let cwd = env::current_dir().unwrap();
+        let (tarball, metadata) = NpmBuilder.process_build_output(&package_dir).unwrap();
+        assert_eq!(tarball, package_dir.join("app-1.0.0.tgz"));
+        assert_eq!(metadata["name"], "app");
+        assert_eq!(env::current_dir().unwrap(), cwd);
+    }
+}
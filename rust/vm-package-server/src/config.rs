@@ -20,6 +20,8 @@
 //! - [`ClientConfig`]: Configuration file templates for package managers
 //! - [`LimitsConfig`]: Upload limits and rate limiting
 //! - [`SecurityConfig`]: Authentication and authorization settings
+//! - [`CacheConfig`]: Retention of packages cached from upstream registries
+//! - [`WebhooksConfig`]: Endpoints notified when packages are published or deleted
 //!
 //! ## Loading Configuration
 //!
@@ -39,9 +41,12 @@
 //! ```
 
 use crate::error::AppResult;
+use crate::tokens::TokenScope;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 /// Main configuration structure for the package registry server.
 ///
@@ -68,6 +73,39 @@ pub struct Config {
     /// Security and authentication settings (defaults applied if not specified)
     #[serde(default)]
     pub security: SecurityConfig,
+    /// Upstream package cache retention (defaults applied if not specified)
+    #[serde(default)]
+    pub cache: CacheConfig,
+    /// Webhook endpoints for publish and delete events (none by default)
+    #[serde(default)]
+    pub webhooks: WebhooksConfig,
+    /// Bandwidth limits for upstream downloads (unlimited by default)
+    #[serde(default)]
+    pub bandwidth: BandwidthConfig,
+    /// Other package servers to keep in sync with (none by default)
+    #[serde(default)]
+    pub replication: ReplicationConfig,
+    /// Mirror manifests kept synced into the upstream cache
+    #[serde(default)]
+    pub mirror: MirrorConfig,
+    /// Upload quotas per token, package and registry (unlimited by default)
+    #[serde(default)]
+    pub quotas: QuotaConfig,
+    /// Download audit trail (off by default)
+    #[serde(default)]
+    pub audit: AuditConfig,
+    /// Upstream packages that may or may not be proxied (everything allowed by default)
+    #[serde(default)]
+    pub upstream_policy: UpstreamPolicyConfig,
+    /// Upstream registry URLs per ecosystem (the public registries by default)
+    #[serde(default)]
+    pub upstreams: UpstreamsConfig,
+    /// HTTPS certificate settings (plain HTTP by default)
+    #[serde(default)]
+    pub tls: TlsConfig,
+    /// Connection draining on shutdown
+    #[serde(default)]
+    pub shutdown: ShutdownConfig,
 }
 
 /// Server configuration settings.
@@ -281,12 +319,465 @@ impl Default for RateLimitConfig {
     }
 }
 
+/// Retention policy for packages cached from upstream registries.
+///
+/// A value of 0 disables the corresponding limit.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CacheConfig {
+    /// Keep upstream downloads for reuse
+    pub enabled: bool,
+    /// Evict least recently used files once the cache grows past this size
+    pub max_size_mb: u64,
+    /// Remove files that haven't been served for this many days
+    pub max_age_days: u64,
+    /// Keep only the newest N cached versions of each package
+    pub keep_latest: usize,
+    /// How often garbage collection runs in the background
+    pub gc_interval_minutes: u64,
+    /// Answer `npm audit` from cached results without contacting upstream
+    pub offline_audit: bool,
+    /// How long crates.io index files are served from the mirror before refetching
+    pub cargo_index_ttl_minutes: u64,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        CacheConfig {
+            enabled: true,
+            max_size_mb: 10240,
+            max_age_days: 90,
+            keep_latest: 0,
+            gc_interval_minutes: 60,
+            offline_audit: false,
+            cargo_index_ttl_minutes: 10,
+        }
+    }
+}
+
+/// HTTPS for the package server.
+///
+/// TLS is on when `cert_path` and `key_path` are set, or when `self_signed` is true.
+/// Relative paths are resolved against the data directory.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TlsConfig {
+    /// PEM certificate chain
+    pub cert_path: Option<PathBuf>,
+    /// PEM private key
+    pub key_path: Option<PathBuf>,
+    /// Generate a self-signed certificate in `data/tls/` when no paths are set
+    pub self_signed: bool,
+    /// Extra host names and IP addresses the self-signed certificate is valid for
+    pub hostnames: Vec<String>,
+}
+
+impl TlsConfig {
+    /// Whether the server should serve HTTPS
+    pub fn is_enabled(&self) -> bool {
+        self.self_signed || (self.cert_path.is_some() && self.key_path.is_some())
+    }
+}
+
+/// Bandwidth limits for package downloads from upstream registries.
+///
+/// Limits are in KiB per second; 0 means unlimited.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BandwidthConfig {
+    /// Combined limit across all upstream registries
+    pub max_kbps: u64,
+    /// Per-registry limits, keyed by `pypi`, `npm`, `cargo` or `go`
+    pub registries: HashMap<String, u64>,
+}
+
+/// Upload quotas.
+///
+/// A value of 0 disables the corresponding quota.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QuotaConfig {
+    /// Total size of all uploads made with one API token, in MB
+    pub max_upload_mb_per_token: u64,
+    /// Versions a PyPI, npm or Cargo package may have
+    pub max_versions_per_package: usize,
+    /// Largest artifact accepted per registry (`pypi`, `npm`, `cargo`, `go`, `helm`), in MB
+    pub max_artifact_mb: HashMap<String, u64>,
+}
+
+/// Download auditing.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AuditConfig {
+    /// Record whether each served artifact was published locally or cached from upstream
+    pub provenance: bool,
+}
+
+/// How the server stops.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ShutdownConfig {
+    /// Seconds to let in-flight requests finish after SIGTERM or SIGINT before
+    /// closing the remaining connections
+    pub drain_timeout_secs: u64,
+}
+
+impl Default for ShutdownConfig {
+    fn default() -> Self {
+        Self {
+            drain_timeout_secs: 120,
+        }
+    }
+}
+
+impl ShutdownConfig {
+    pub fn drain_timeout(&self) -> Duration {
+        Duration::from_secs(self.drain_timeout_secs)
+    }
+}
+
+/// Upstream registries each ecosystem proxies, such as an internal Artifactory for
+/// npm or a crates.io mirror for Cargo.
+///
+/// Each list is tried in order until one has the package, so later entries act as
+/// fallbacks. An empty list uses the public registry.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UpstreamsConfig {
+    /// PyPI-compatible registries serving `/simple/` and `/packages/`
+    pub pypi: Vec<String>,
+    /// npm registries
+    pub npm: Vec<String>,
+    /// Cargo sparse index URLs; crates are downloaded from the `dl` URL in each
+    /// index's `config.json`
+    pub cargo: Vec<String>,
+    /// Go module proxies
+    pub go: Vec<String>,
+}
+
+/// Allow and deny lists for packages proxied from upstream registries.
+///
+/// A package is refused when it matches a `deny` rule, or when its registry has
+/// `allow` rules and it matches none of them.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UpstreamPolicyConfig {
+    /// Packages that may be proxied; a registry without rules allows everything
+    pub allow: Vec<PolicyRule>,
+    /// Packages that are never proxied
+    pub deny: Vec<PolicyRule>,
+}
+
+/// One entry of an upstream allow or deny list
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PolicyRule {
+    /// `pypi`, `npm`, `cargo` or `go`
+    pub registry: String,
+    /// Package name or glob pattern (`@types/*`, `golang.org/x/*`)
+    pub package: String,
+    /// Why the rule exists, included in the error clients see
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+/// Replication with other package servers.
+///
+/// Every `interval_minutes` the server copies packages it is missing from each
+/// `pull` peer and sends each `push` peer the packages it is missing.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReplicationConfig {
+    /// Servers to replicate with
+    pub peers: Vec<ReplicationPeer>,
+    /// How often the background task runs (0 = only `pkg-server replicate`)
+    pub interval_minutes: u64,
+}
+
+impl Default for ReplicationConfig {
+    fn default() -> Self {
+        ReplicationConfig {
+            peers: Vec::new(),
+            interval_minutes: 30,
+        }
+    }
+}
+
+/// A package server to replicate with
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplicationPeer {
+    /// Base URL of the peer, e.g. `http://pkg.berlin.example:3080`
+    pub url: String,
+    /// Whether packages are copied from the peer or to it
+    #[serde(default)]
+    pub mode: ReplicationMode,
+    /// Registries to replicate (`pypi`, `npm`, `cargo`); empty means all
+    #[serde(default)]
+    pub registries: Vec<String>,
+    /// Package names to replicate; empty means all
+    #[serde(default)]
+    pub packages: Vec<String>,
+    /// API token for the peer, if it requires authentication
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+}
+
+/// Direction of replication relative to this server
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReplicationMode {
+    /// Copy packages from the peer to this server
+    #[default]
+    Pull,
+    /// Copy packages from this server to the peer
+    Push,
+}
+
+/// Mirror manifests: packages and images kept in the local caches.
+///
+/// Manifest paths are glob patterns relative to the data directory.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MirrorConfig {
+    /// Manifest files to sync
+    pub manifests: Vec<String>,
+    /// How often manifests are synced in the background (0 = only `pkg-server mirror`)
+    pub interval_minutes: u64,
+    /// Docker registry mirror that `images` are pulled through
+    pub docker_registry: String,
+}
+
+impl Default for MirrorConfig {
+    fn default() -> Self {
+        MirrorConfig {
+            manifests: vec!["mirrors/*.yaml".to_string(), "mirrors/*.yml".to_string()],
+            interval_minutes: 360,
+            docker_registry: "http://127.0.0.1:5000".to_string(),
+        }
+    }
+}
+
+/// Webhook delivery settings.
+///
+/// Each event is POSTed as JSON to every endpoint whose filters match it.
+/// Failed deliveries are retried with exponential backoff, starting at
+/// `initial_backoff_ms` and doubling up to `max_backoff_ms`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WebhooksConfig {
+    /// Endpoints to notify
+    pub endpoints: Vec<WebhookEndpoint>,
+    /// Delivery attempts per endpoint before the event is dropped
+    pub max_attempts: u32,
+    /// Delay before the first retry
+    pub initial_backoff_ms: u64,
+    /// Upper bound for the delay between retries
+    pub max_backoff_ms: u64,
+    /// Timeout for a single delivery request
+    pub timeout_seconds: u64,
+}
+
+impl Default for WebhooksConfig {
+    fn default() -> Self {
+        WebhooksConfig {
+            endpoints: Vec::new(),
+            max_attempts: 5,
+            initial_backoff_ms: 1000,
+            max_backoff_ms: 60_000,
+            timeout_seconds: 10,
+        }
+    }
+}
+
+/// A URL that receives webhook events
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookEndpoint {
+    pub url: String,
+    /// Event names to send (e.g. `package.published`); empty sends all events
+    #[serde(default)]
+    pub events: Vec<String>,
+    /// Registries to send events for (e.g. `cargo`, `npm`); empty sends all
+    #[serde(default)]
+    pub registries: Vec<String>,
+    /// Shared secret used to sign the payload (`X-Pkg-Server-Signature` header)
+    #[serde(default)]
+    pub secret: Option<String>,
+}
+
 /// Security configuration
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
 pub struct SecurityConfig {
     pub require_authentication: bool,
     pub api_keys: Vec<String>,
     pub allowed_publishers: Vec<String>,
+    /// PyPI API tokens restricted to specific projects
+    pub scoped_tokens: Vec<ScopedToken>,
+    /// CI identities allowed to mint short-lived PyPI upload tokens via OIDC
+    pub trusted_publishers: Vec<TrustedPublisher>,
+    /// Signature requirements for uploaded packages, per registry
+    pub signatures: UploadSignatureConfig,
+    /// npm scopes whose packages only authenticated clients may read and publish
+    pub private_npm_scopes: Vec<NpmScopeAcl>,
+    /// Accept bearer tokens issued by an OpenID Connect provider
+    pub oidc: Option<OidcAuthConfig>,
+    /// Accept user names and passwords checked with an LDAP bind
+    pub ldap: Option<LdapAuthConfig>,
+}
+
+impl SecurityConfig {
+    /// Whether clients can authenticate through OIDC or LDAP
+    pub fn has_identity_provider(&self) -> bool {
+        self.oidc.is_some() || self.ldap.is_some()
+    }
+}
+
+/// An OpenID Connect provider whose tokens are accepted in place of API keys.
+///
+/// Tokens are verified against the issuer's published JWKS and must carry
+/// `audience` in their `aud` claim. What a verified identity may do is decided
+/// by `claim_scopes`; an identity no mapping matches may do nothing.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OidcAuthConfig {
+    /// Issuer URL, e.g. `https://login.example.com/realms/dev`
+    pub issuer: String,
+    /// Required `aud` claim
+    #[serde(default = "default_oidc_auth_audience")]
+    pub audience: String,
+    /// Claim holding the user name, matched against `private_npm_scopes` token
+    /// lists (falls back to `sub`)
+    #[serde(default = "default_username_claim")]
+    pub username_claim: String,
+    /// Scopes granted by claim values
+    #[serde(default)]
+    pub claim_scopes: Vec<ClaimScopes>,
+}
+
+fn default_oidc_auth_audience() -> String {
+    crate::trusted_publishing::OIDC_AUDIENCE.to_string()
+}
+
+fn default_username_claim() -> String {
+    "preferred_username".to_string()
+}
+
+/// Scopes granted to OIDC identities whose `claim` matches `value`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClaimScopes {
+    /// Claim name; dots reach into nested claims, e.g. `realm_access.roles`
+    pub claim: String,
+    /// Value the claim must equal or, for list claims, contain; when omitted
+    /// the claim only needs to be present
+    #[serde(default)]
+    pub value: Option<String>,
+    pub scopes: Vec<TokenScope>,
+    /// Registries the scopes apply to; empty means all
+    #[serde(default)]
+    pub registries: Vec<String>,
+}
+
+/// An LDAP directory that checks HTTP Basic credentials with a simple bind
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LdapAuthConfig {
+    /// Server URL, `ldap://` or `ldaps://`
+    pub url: String,
+    /// DN to bind as, with `{username}` standing for the escaped user name,
+    /// e.g. `uid={username},ou=people,dc=example,dc=com`
+    pub bind_dn: String,
+    /// Upgrade `ldap://` connections with StartTLS
+    #[serde(default)]
+    pub starttls: bool,
+    /// Scopes granted to every user that binds successfully
+    #[serde(default = "default_ldap_scopes")]
+    pub scopes: Vec<TokenScope>,
+    /// Registries the scopes apply to; empty means all
+    #[serde(default)]
+    pub registries: Vec<String>,
+}
+
+fn default_ldap_scopes() -> Vec<TokenScope> {
+    vec![TokenScope::Read, TokenScope::Publish]
+}
+
+/// An npm scope restricted to authenticated clients.
+///
+/// Packages in the scope are served only from this server, never from upstream,
+/// and reading or publishing them needs an admin API key or an issued token with
+/// the `read` or `publish` scope for npm.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NpmScopeAcl {
+    /// Scope name, e.g. `@myorg` (the leading `@` is optional)
+    pub scope: String,
+    /// Names of the issued tokens (or OIDC/LDAP users) allowed to use the scope;
+    /// empty allows any npm token
+    #[serde(default)]
+    pub tokens: Vec<String>,
+}
+
+impl NpmScopeAcl {
+    /// Whether `package` (e.g. `@myorg/widgets`) belongs to this scope
+    pub fn contains(&self, package: &str) -> bool {
+        let scope = self.scope.trim_start_matches('@');
+        package
+            .strip_prefix('@')
+            .and_then(|name| name.split_once('/'))
+            .is_some_and(|(package_scope, _)| package_scope.eq_ignore_ascii_case(scope))
+    }
+}
+
+/// Per-registry upload signature requirements.
+///
+/// PyPI uploads carry a detached PGP signature (`twine upload --sign`); npm and
+/// Cargo uploads carry a cosign `sign-blob` signature of the tarball or `.crate`
+/// file in the `X-Package-Signature` header. npm signatures cannot be `required`.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct UploadSignatureConfig {
+    pub pypi: SignaturePolicy,
+    pub npm: SignaturePolicy,
+    pub cargo: SignaturePolicy,
+}
+
+/// Signature policy for one registry
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct SignaturePolicy {
+    /// Reject uploads that aren't signed by one of `trusted_keys`
+    pub required: bool,
+    /// Public keys, inline or as file paths: PGP keys for PyPI, PEM keys
+    /// (`cosign.pub`) for npm and Cargo
+    pub trusted_keys: Vec<String>,
+}
+
+/// An API token that may only upload the listed PyPI projects
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScopedToken {
+    pub token: String,
+    pub projects: Vec<String>,
+}
+
+/// A CI workflow trusted to publish PyPI projects without a long-lived token.
+///
+/// Mirrors pypi.org trusted publishing: the workflow exchanges its OIDC identity
+/// token for an upload token scoped to `projects`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrustedPublisher {
+    /// OIDC issuer (defaults to GitHub Actions)
+    #[serde(default = "default_oidc_issuer")]
+    pub issuer: String,
+    /// Repository in `owner/name` form (the token's `repository` claim)
+    pub repository: String,
+    /// Workflow file name, e.g. `release.yml` (matched against `job_workflow_ref`)
+    #[serde(default)]
+    pub workflow: Option<String>,
+    /// Deployment environment the job must run in
+    #[serde(default)]
+    pub environment: Option<String>,
+    /// PyPI projects the minted token may upload
+    pub projects: Vec<String>,
+}
+
+fn default_oidc_issuer() -> String {
+    "https://token.actions.githubusercontent.com".to_string()
 }
 
 impl Config {
@@ -365,6 +856,128 @@ impl Config {
             Ok(Self::default())
         }
     }
+
+    /// Apply the `security`, `cache` and `webhooks` sections of a JSON config file, if the file exists.
+    ///
+    /// Only those sections are read, so the file can be as small as the
+    /// examples in the configuration guide.
+    pub fn apply_security_file<P: AsRef<Path>>(&mut self, path: P) -> AppResult<()> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Ok(());
+        }
+        let value: serde_json::Value = serde_json::from_str(&fs::read_to_string(path)?)?;
+        if let Some(security) = value.get("security") {
+            self.security = serde_json::from_value(security.clone())?;
+            crate::signatures::validate_config(&self.security.signatures)?;
+        }
+        if let Some(cache) = value.get("cache") {
+            self.cache = serde_json::from_value(cache.clone())?;
+        }
+        if let Some(webhooks) = value.get("webhooks") {
+            self.webhooks = serde_json::from_value(webhooks.clone())?;
+        }
+        if let Some(bandwidth) = value.get("bandwidth") {
+            self.bandwidth = serde_json::from_value(bandwidth.clone())?;
+        }
+        if let Some(replication) = value.get("replication") {
+            self.replication = serde_json::from_value(replication.clone())?;
+        }
+        if let Some(mirror) = value.get("mirror") {
+            self.mirror = serde_json::from_value(mirror.clone())?;
+        }
+        if let Some(quotas) = value.get("quotas") {
+            self.quotas = serde_json::from_value(quotas.clone())?;
+        }
+        if let Some(audit) = value.get("audit") {
+            self.audit = serde_json::from_value(audit.clone())?;
+        }
+        if let Some(policy) = value.get("upstream_policy") {
+            self.upstream_policy = serde_json::from_value(policy.clone())?;
+        }
+        if let Some(tls) = value.get("tls") {
+            self.tls = serde_json::from_value(tls.clone())?;
+        }
+        if let Some(upstreams) = value.get("upstreams") {
+            self.upstreams = serde_json::from_value(upstreams.clone())?;
+        }
+        if let Some(shutdown) = value.get("shutdown") {
+            self.shutdown = serde_json::from_value(shutdown.clone())?;
+        }
+        Ok(())
+    }
+
+    /// Apply security overrides from the environment.
+    ///
+    /// `PKG_SERVER_API_KEYS` holds a comma-separated list of accepted tokens. Setting
+    /// it enables authentication, so the registry can be exposed beyond localhost
+    /// without editing the embedded configuration. Clients send one of these tokens
+    /// via `PKG_SERVER_AUTH_TOKEN` or, for Cargo, `cargo login --registry <name>`.
+    ///
+    /// `PKG_SERVER_MAX_KBPS` sets the combined upstream bandwidth limit, which is
+    /// how `vm` passes `services.package_registry.max_download_kbps` through.
+    ///
+    /// `PKG_SERVER_TLS_CERT` and `PKG_SERVER_TLS_KEY` set the HTTPS certificate and
+    /// key, and `PKG_SERVER_TLS_SELF_SIGNED=1` enables a generated certificate.
+    ///
+    /// `PKG_SERVER_UPSTREAM_PYPI`, `_NPM`, `_CARGO` and `_GO` replace the upstream
+    /// registries of an ecosystem with a comma-separated list of URLs.
+    ///
+    /// `PKG_SERVER_DRAIN_TIMEOUT_SECS` sets how long shutdown waits for in-flight
+    /// requests.
+    pub fn apply_env_overrides(&mut self) {
+        if let Ok(keys) = std::env::var("PKG_SERVER_API_KEYS") {
+            let keys: Vec<String> = keys
+                .split(',')
+                .map(str::trim)
+                .filter(|key| !key.is_empty())
+                .map(str::to_string)
+                .collect();
+            if !keys.is_empty() {
+                self.security.require_authentication = true;
+                self.security.api_keys = keys;
+            }
+        }
+        if let Some(kbps) = std::env::var("PKG_SERVER_MAX_KBPS")
+            .ok()
+            .and_then(|kbps| kbps.trim().parse::<u64>().ok())
+        {
+            self.bandwidth.max_kbps = kbps;
+        }
+        if let Ok(cert) = std::env::var("PKG_SERVER_TLS_CERT") {
+            self.tls.cert_path = Some(PathBuf::from(cert));
+        }
+        if let Ok(key) = std::env::var("PKG_SERVER_TLS_KEY") {
+            self.tls.key_path = Some(PathBuf::from(key));
+        }
+        if std::env::var("PKG_SERVER_TLS_SELF_SIGNED")
+            .is_ok_and(|value| value == "1" || value == "true")
+        {
+            self.tls.self_signed = true;
+        }
+        if let Some(secs) = std::env::var("PKG_SERVER_DRAIN_TIMEOUT_SECS")
+            .ok()
+            .and_then(|secs| secs.trim().parse::<u64>().ok())
+        {
+            self.shutdown.drain_timeout_secs = secs;
+        }
+        let upstreams = [
+            ("PKG_SERVER_UPSTREAM_PYPI", &mut self.upstreams.pypi),
+            ("PKG_SERVER_UPSTREAM_NPM", &mut self.upstreams.npm),
+            ("PKG_SERVER_UPSTREAM_CARGO", &mut self.upstreams.cargo),
+            ("PKG_SERVER_UPSTREAM_GO", &mut self.upstreams.go),
+        ];
+        for (var, urls) in upstreams {
+            if let Ok(value) = std::env::var(var) {
+                *urls = value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|url| !url.is_empty())
+                    .map(str::to_string)
+                    .collect();
+            }
+        }
+    }
 }
 
 /// Default routes - these are not configurable as they follow package manager standards
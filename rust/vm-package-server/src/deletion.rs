@@ -20,7 +20,8 @@
 //!
 //! ## Registry-Specific Behavior
 //!
-//! - **PyPI**: Removes `.whl` and `.tar.gz` files plus associated `.meta` files
+//! - **PyPI**: Removes `.whl` and `.tar.gz` files plus associated `.meta` and
+//!   `.whl.metadata` files
 //! - **NPM**: Updates `package.json` metadata, manages `dist-tags`, preserves history
 //! - **Cargo**: Supports both yanking (marking as unavailable) and full deletion
 //!
@@ -112,6 +113,14 @@ pub async fn remove_files_matching_pattern(
                                     }
                                 }
                             }
+                            // And the PEP 658 core metadata served next to wheels
+                            let core_metadata = path.with_extension(format!(
+                                "{}.metadata",
+                                path.extension().and_then(|ext| ext.to_str()).unwrap_or("")
+                            ));
+                            if filename.ends_with(".whl") && core_metadata.exists() {
+                                let _ = tokio::fs::remove_file(&core_metadata).await;
+                            }
                         }
                     }
                 }
@@ -185,8 +194,8 @@ pub async fn update_json_remove_version(metadata_path: &Path, version: &str) ->
     Ok(())
 }
 
-/// Update Cargo index to yank a specific version
-pub async fn update_index_yank_version(index_path: &Path, version: &str) -> Result<()> {
+/// Set the `yanked` flag of a specific version in a Cargo index file
+pub async fn update_index_yanked(index_path: &Path, version: &str, yanked: bool) -> Result<()> {
     if !index_path.exists() {
         anyhow::bail!("Crate index not found");
     }
@@ -202,11 +211,10 @@ pub async fn update_index_yank_version(index_path: &Path, version: &str) -> Resu
 
         if let Ok(mut entry) = serde_json::from_str::<Value>(line) {
             if entry.get("vers").and_then(|v| v.as_str()) == Some(version) {
-                // Mark as yanked
-                entry["yanked"] = Value::Bool(true);
+                entry["yanked"] = Value::Bool(yanked);
                 updated_lines.push(serde_json::to_string(&entry)?);
                 found = true;
-                info!(version = %version, "Marked version as yanked in index");
+                info!(version = %version, yanked = yanked, "Updated yanked flag in index");
             } else {
                 updated_lines.push(line.to_string());
             }
@@ -13,17 +13,22 @@
 //!
 //! ## Error Response Format
 //!
-//! All API errors are returned in a consistent JSON format:
+//! `/api/*` and other non-registry routes return errors in a consistent JSON format:
 //!
 //! ```json
 //! {
-//!   "error": "Human-readable error message",
 //!   "code": "machine_readable_error_code",
-//!   "details": {...},  // Optional additional details
+//!   "message": "Human-readable error message",
+//!   "detail": {...},  // Optional additional details
+//!   "request_id": "3f2c9a0e-...",
 //!   "timestamp": "2024-01-01T12:00:00Z"
 //! }
 //! ```
 //!
+//! Registry routes use the body each package manager understands instead (see
+//! [`ErrorProtocol`]), so pip, npm, cargo and go print the message to the user. Every
+//! error response carries an `x-request-id` header matching the server logs.
+//!
 //! ## Error Classifications
 //!
 //! Errors are classified into categories that map to appropriate HTTP status codes:
@@ -32,6 +37,7 @@
 //! - **Not Found** (404 Not Found): Missing resources
 //! - **Upload Errors** (413 Payload Too Large): File upload issues
 //! - **Auth Errors** (401 Unauthorized): Authentication failures
+//! - **Upstream Unavailable** (503 Service Unavailable): Upstream registry unreachable
 //! - **Internal Errors** (500 Internal Server Error): Server-side errors
 //!
 //! ## Usage
@@ -56,22 +62,92 @@ use serde::Serialize;
 use serde_json::{json, Value};
 
 /// Standardized error response structure for consistent API error handling
-#[derive(Serialize, Debug)]
+#[derive(Serialize, Debug, Clone, utoipa::ToSchema)]
 pub struct ApiErrorResponse {
-    pub error: String,          // Human-readable error message
-    pub code: String,           // Machine-readable error code
-    pub details: Option<Value>, // Additional error details
-    pub timestamp: String,      // ISO 8601 timestamp
+    pub code: String,    // Machine-readable error code
+    pub message: String, // Human-readable error message
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<Value>, // Additional error details
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>, // Correlates with server log lines
+    pub timestamp: String, // ISO 8601 timestamp
+}
+
+/// Wire format expected by the client that made a failing request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorProtocol {
+    /// `{code, message, detail, request_id, timestamp}`
+    Api,
+    /// Cargo registry web API: `{"errors": [{"detail": "..."}]}`
+    Cargo,
+    /// npm registry and the ChartMuseum API used by `helm cm-push`: `{"error": "..."}`
+    Npm,
+    /// pip/twine show the plain-text body
+    Pypi,
+    /// `go` prints the plain-text body of proxy errors
+    GoProxy,
+}
+
+impl ErrorProtocol {
+    /// Pick the protocol from the request path
+    pub fn for_path(path: &str) -> Self {
+        if path.starts_with("/cargo/") {
+            ErrorProtocol::Cargo
+        } else if path.starts_with("/npm/") || path.starts_with("/helm/") {
+            ErrorProtocol::Npm
+        } else if path.starts_with("/pypi/") && !path.starts_with("/pypi/_/oidc/") {
+            ErrorProtocol::Pypi
+        } else if path.starts_with("/go/") {
+            ErrorProtocol::GoProxy
+        } else {
+            ErrorProtocol::Api
+        }
+    }
+}
+
+impl ApiErrorResponse {
+    /// Render this error in the format `protocol` clients understand
+    pub fn render(&self, protocol: ErrorProtocol, status: StatusCode) -> Response {
+        let suffix = self
+            .request_id
+            .as_deref()
+            .map(|id| format!(" (request id {id})"))
+            .unwrap_or_default();
+        match protocol {
+            ErrorProtocol::Api => (status, axum::Json(self)).into_response(),
+            ErrorProtocol::Cargo => (
+                status,
+                axum::Json(json!({
+                    "errors": [{ "detail": format!("{}{suffix}", self.message) }]
+                })),
+            )
+                .into_response(),
+            ErrorProtocol::Npm => (
+                status,
+                axum::Json(json!({
+                    "error": format!("{}{suffix}", self.message),
+                    "code": self.code,
+                })),
+            )
+                .into_response(),
+            ErrorProtocol::Pypi | ErrorProtocol::GoProxy => {
+                (status, format!("{}{suffix}\n", self.message)).into_response()
+            }
+        }
+    }
 }
 
 /// Error code classification for machine-readable error types
 #[derive(Debug, Clone, PartialEq)]
 pub enum ErrorCode {
-    ValidationError, // For input validation failures
-    NotFound,        // For missing resources
-    UploadError,     // For file upload issues
-    InternalError,   // For server-side errors
-    AuthError,       // For authentication issues
+    ValidationError,     // For input validation failures
+    NotFound,            // For missing resources
+    UploadError,         // For file upload issues
+    InternalError,       // For server-side errors
+    AuthError,           // For authentication issues
+    QuotaExceeded,       // For uploads beyond a configured quota
+    UpstreamBlocked,     // For upstream packages refused by policy
+    UpstreamUnavailable, // For upstream registries that can't be reached
 }
 
 impl ErrorCode {
@@ -82,6 +158,9 @@ impl ErrorCode {
             ErrorCode::UploadError => "upload_error",
             ErrorCode::InternalError => "internal_error",
             ErrorCode::AuthError => "auth_error",
+            ErrorCode::QuotaExceeded => "quota_exceeded",
+            ErrorCode::UpstreamBlocked => "upstream_blocked",
+            ErrorCode::UpstreamUnavailable => "upstream_unavailable",
         }
     }
 
@@ -92,6 +171,9 @@ impl ErrorCode {
             ErrorCode::UploadError => StatusCode::PAYLOAD_TOO_LARGE,
             ErrorCode::InternalError => StatusCode::INTERNAL_SERVER_ERROR,
             ErrorCode::AuthError => StatusCode::UNAUTHORIZED,
+            ErrorCode::QuotaExceeded => StatusCode::FORBIDDEN,
+            ErrorCode::UpstreamBlocked => StatusCode::FORBIDDEN,
+            ErrorCode::UpstreamUnavailable => StatusCode::SERVICE_UNAVAILABLE,
         }
     }
 }
@@ -132,6 +214,15 @@ pub enum AppError {
     #[error("Not implemented: {0}")]
     NotImplemented(String),
 
+    #[error("Quota exceeded: {0}")]
+    QuotaExceeded(String),
+
+    #[error("Blocked by upstream policy: {0}")]
+    UpstreamBlocked(String),
+
+    #[error("Upstream unavailable: {0}")]
+    UpstreamUnavailable(String),
+
     #[error(transparent)]
     Anyhow(#[from] anyhow::Error),
 }
@@ -149,6 +240,9 @@ impl AppError {
             AppError::UploadError(_) => ErrorCode::UploadError,
             AppError::InternalError(_) => ErrorCode::InternalError,
             AppError::Unauthorized(_) => ErrorCode::AuthError,
+            AppError::QuotaExceeded(_) => ErrorCode::QuotaExceeded,
+            AppError::UpstreamBlocked(_) => ErrorCode::UpstreamBlocked,
+            AppError::UpstreamUnavailable(_) => ErrorCode::UpstreamUnavailable,
             AppError::NotImplemented(_) => ErrorCode::InternalError,
             AppError::Io(_) | AppError::Anyhow(_) => ErrorCode::InternalError,
         }
@@ -168,9 +262,10 @@ impl AppError {
     pub fn to_error_response(&self) -> ApiErrorResponse {
         let code = self.error_code();
         ApiErrorResponse {
-            error: self.to_string(),
             code: code.as_str().to_string(),
-            details: self.details(),
+            message: self.to_string(),
+            detail: self.details(),
+            request_id: None,
             timestamp: Utc::now().to_rfc3339(),
         }
     }
@@ -193,7 +288,10 @@ impl IntoResponse for AppError {
 
         tracing::debug!(status = %status, code = %error_response.code, "Returning standardized error response");
 
-        (status, axum::Json(error_response)).into_response()
+        // The request ID middleware re-renders this per protocol and stamps the request ID
+        let mut response = error_response.render(ErrorProtocol::Api, status);
+        response.extensions_mut().insert(error_response);
+        response
     }
 }
 
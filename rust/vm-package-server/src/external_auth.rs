@@ -0,0 +1,452 @@
+//! # OIDC and LDAP Authentication
+//!
+//! Lets clients authenticate with the organisation's identity provider instead
+//! of a static API key. With `security.oidc` configured, bearer tokens issued by
+//! the provider are verified against its JWKS and their claims are mapped to
+//! [`TokenScope`]s through `claim_scopes`. With `security.ldap` configured, HTTP
+//! Basic credentials (as sent by twine, pip and npm) are checked with an LDAP
+//! simple bind.
+//!
+//! Verification needs the network, so [`identity_middleware`] runs it once per
+//! credential and caches the result until the token expires (for LDAP, for
+//! [`LDAP_CACHE_TTL`]). Rejected credentials are remembered for
+//! [`REJECTED_CACHE_TTL`], so a client retrying a bad password doesn't cause a
+//! bind per request. The checks in [`crate::auth`] then look the request's
+//! credential up with [`find`].
+//!
+//! The main registry and every virtual registry run the middleware with their
+//! own `security` settings, and identities are cached per registry (keyed by its
+//! data directory), so a user of one registry has no grants in another.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderMap},
+    middleware::Next,
+    response::Response,
+};
+use base64::Engine;
+use ldap3::{LdapConnAsync, LdapConnSettings};
+use once_cell::sync::Lazy;
+use serde_json::Value;
+use tracing::{debug, warn};
+
+use crate::config::{ClaimScopes, LdapAuthConfig, OidcAuthConfig, SecurityConfig};
+use crate::hash_utils::sha256_hash;
+use crate::tokens::TokenScope;
+use crate::{AppError, AppResult, AppState};
+
+/// How long a successful LDAP bind is trusted before binding again
+pub const LDAP_CACHE_TTL: Duration = Duration::from_secs(5 * 60);
+
+/// Longest a verified OIDC token is cached, whatever its `exp`
+const MAX_OIDC_CACHE_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// How long a credential the identity provider rejected isn't verified again
+pub const REJECTED_CACHE_TTL: Duration = Duration::from_secs(30);
+
+/// Scopes granted on a set of registries
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Grant {
+    pub scopes: Vec<TokenScope>,
+    /// Registries the scopes apply to; empty means all
+    pub registries: Vec<String>,
+}
+
+/// A user verified by the identity provider
+#[derive(Debug, Clone)]
+pub struct Identity {
+    /// User name, matched against `private_npm_scopes` token lists
+    pub name: String,
+    pub grants: Vec<Grant>,
+    expires_at: Instant,
+}
+
+impl Identity {
+    /// Whether this identity may perform `scope` on `registry`
+    pub fn allows(&self, scope: TokenScope, registry: &str) -> bool {
+        self.grants.iter().any(|grant| {
+            grant.scopes.contains(&scope)
+                && (grant.registries.is_empty() || grant.registries.iter().any(|r| r == registry))
+        })
+    }
+}
+
+/// Verified identities keyed by registry and the hash of the credential that proved them
+static VERIFIED: Lazy<Mutex<HashMap<String, Identity>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Credentials the identity provider rejected, keyed like [`VERIFIED`], with when
+/// they may be tried again
+static REJECTED: Lazy<Mutex<HashMap<String, Instant>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Cache key of the request's credential for the registry stored in `realm`;
+/// the secret itself isn't kept
+fn credential_key(realm: &Path, headers: &HeaderMap) -> Option<String> {
+    let value = headers.get(header::AUTHORIZATION)?;
+    let mut keyed = realm.to_string_lossy().into_owned().into_bytes();
+    keyed.push(0);
+    keyed.extend_from_slice(value.as_bytes());
+    Some(sha256_hash(&keyed))
+}
+
+/// The identity the request's credential was verified as by the registry whose
+/// data directory is `realm`, if any
+pub fn find(realm: &Path, headers: &HeaderMap) -> Option<Identity> {
+    let key = credential_key(realm, headers)?;
+    let mut verified = VERIFIED.lock().ok()?;
+    let now = Instant::now();
+    verified.retain(|_, identity| identity.expires_at > now);
+    verified.get(&key).cloned()
+}
+
+/// Whether the request's credential was rejected recently enough not to retry it
+fn recently_rejected(realm: &Path, headers: &HeaderMap) -> bool {
+    let (Some(key), Ok(mut rejected)) = (credential_key(realm, headers), REJECTED.lock()) else {
+        return false;
+    };
+    let now = Instant::now();
+    rejected.retain(|_, until| *until > now);
+    rejected.contains_key(&key)
+}
+
+fn remember(realm: &Path, headers: &HeaderMap, identity: Identity) {
+    if let (Some(key), Ok(mut verified)) = (credential_key(realm, headers), VERIFIED.lock()) {
+        verified.insert(key, identity);
+    }
+}
+
+fn remember_rejection(realm: &Path, headers: &HeaderMap) {
+    if let (Some(key), Ok(mut rejected)) = (credential_key(realm, headers), REJECTED.lock()) {
+        rejected.insert(key, Instant::now() + REJECTED_CACHE_TTL);
+    }
+}
+
+/// A credential an identity provider can verify
+#[derive(Debug, PartialEq, Eq)]
+enum Credential {
+    /// A JWT, as a Bearer token, a bare Cargo token or a `__token__` password
+    Jwt(String),
+    /// HTTP Basic user name and password
+    Password { username: String, password: String },
+}
+
+fn is_jwt(token: &str) -> bool {
+    token.starts_with("eyJ") && token.split('.').count() == 3
+}
+
+fn credential(headers: &HeaderMap) -> Option<Credential> {
+    let value = headers.get(header::AUTHORIZATION)?.to_str().ok()?.trim();
+    if let Some(encoded) = value.strip_prefix("Basic ") {
+        let decoded = base64::engine::general_purpose::STANDARD
+            .decode(encoded.trim())
+            .ok()?;
+        let decoded = String::from_utf8(decoded).ok()?;
+        let (username, password) = decoded.split_once(':')?;
+        if username == "__token__" {
+            return is_jwt(password).then(|| Credential::Jwt(password.to_string()));
+        }
+        return Some(Credential::Password {
+            username: username.to_string(),
+            password: password.to_string(),
+        });
+    }
+    let token = value.strip_prefix("Bearer ").unwrap_or(value).trim();
+    is_jwt(token).then(|| Credential::Jwt(token.to_string()))
+}
+
+/// Middleware verifying OIDC tokens and LDAP credentials before the handlers run.
+///
+/// Credentials are checked against the `security` settings of the registry
+/// serving the request. Requests whose credential fails verification continue
+/// unauthenticated, so the handlers reject them, or serve them where no
+/// authentication is needed.
+pub async fn identity_middleware(
+    State(state): State<Arc<AppState>>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let security = &state.config.load().security;
+    let realm = state.data_dir.as_path();
+    if security.has_identity_provider()
+        && find(realm, req.headers()).is_none()
+        && !recently_rejected(realm, req.headers())
+    {
+        if let Some(credential) = credential(req.headers()) {
+            match authenticate(security, credential).await {
+                Ok(Some(identity)) => {
+                    debug!(user = %identity.name, "Authenticated through identity provider");
+                    remember(realm, req.headers(), identity);
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    warn!(error = %e, "Identity provider rejected credentials");
+                    remember_rejection(realm, req.headers());
+                }
+            }
+        }
+    }
+    next.run(req).await
+}
+
+async fn authenticate(
+    security: &SecurityConfig,
+    credential: Credential,
+) -> AppResult<Option<Identity>> {
+    match (credential, &security.oidc, &security.ldap) {
+        (Credential::Jwt(token), Some(oidc), _) => {
+            let claims: Value = crate::trusted_publishing::verify_identity_token(
+                &token,
+                &oidc.issuer,
+                &oidc.audience,
+            )
+            .await?;
+            Ok(Some(identity_from_claims(oidc, &claims)))
+        }
+        (Credential::Password { username, password }, _, Some(ldap)) => {
+            ldap_bind(ldap, &username, &password).await?;
+            Ok(Some(Identity {
+                grants: vec![Grant {
+                    scopes: ldap.scopes.clone(),
+                    registries: ldap.registries.clone(),
+                }],
+                name: username,
+                expires_at: Instant::now() + LDAP_CACHE_TTL,
+            }))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Look up a claim, following dots into nested objects
+fn claim<'a>(claims: &'a Value, path: &str) -> Option<&'a Value> {
+    claims.get(path).or_else(|| {
+        path.split('.')
+            .try_fold(claims, |value, key| value.get(key))
+    })
+}
+
+fn claim_matches(claims: &Value, mapping: &ClaimScopes) -> bool {
+    let Some(actual) = claim(claims, &mapping.claim) else {
+        return false;
+    };
+    match (&mapping.value, actual) {
+        (None, Value::Null | Value::Bool(false)) => false,
+        (None, _) => true,
+        // Space-separated strings are lists too, like the OAuth `scope` claim
+        (Some(expected), Value::String(actual)) => {
+            actual == expected || actual.split_whitespace().any(|word| word == expected)
+        }
+        (Some(expected), Value::Array(items)) => {
+            items.iter().any(|item| item.as_str() == Some(expected))
+        }
+        (Some(expected), other) => {
+            serde_json::from_str::<Value>(expected).is_ok_and(|v| v == *other)
+        }
+    }
+}
+
+/// Build the identity described by verified claims
+fn identity_from_claims(oidc: &OidcAuthConfig, claims: &Value) -> Identity {
+    let name = claim(claims, &oidc.username_claim)
+        .or_else(|| claims.get("sub"))
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string();
+    let grants = oidc
+        .claim_scopes
+        .iter()
+        .filter(|mapping| claim_matches(claims, mapping))
+        .map(|mapping| Grant {
+            scopes: mapping.scopes.clone(),
+            registries: mapping.registries.clone(),
+        })
+        .collect();
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let ttl = claims
+        .get("exp")
+        .and_then(Value::as_u64)
+        .map_or(MAX_OIDC_CACHE_TTL, |exp| {
+            Duration::from_secs(exp.saturating_sub(now)).min(MAX_OIDC_CACHE_TTL)
+        });
+    Identity {
+        name,
+        grants,
+        expires_at: Instant::now() + ttl,
+    }
+}
+
+async fn ldap_bind(ldap: &LdapAuthConfig, username: &str, password: &str) -> AppResult<()> {
+    // A bind without a password is an anonymous bind, which servers accept for any DN
+    if username.is_empty() || password.is_empty() {
+        return Err(AppError::Unauthorized(
+            "LDAP authentication needs a user name and password".to_string(),
+        ));
+    }
+
+    let settings = LdapConnSettings::new()
+        .set_conn_timeout(Duration::from_secs(10))
+        .set_starttls(ldap.starttls);
+    let (conn, mut connection) = LdapConnAsync::with_settings(settings, &ldap.url)
+        .await
+        .map_err(|e| {
+            AppError::Unauthorized(format!("Failed to reach LDAP server {}: {e}", ldap.url))
+        })?;
+    tokio::spawn(async move {
+        if let Err(e) = conn.drive().await {
+            debug!(error = %e, "LDAP connection closed");
+        }
+    });
+
+    let dn = ldap
+        .bind_dn
+        .replace("{username}", &ldap3::dn_escape(username));
+    let result = connection
+        .simple_bind(&dn, password)
+        .await
+        .and_then(|result| result.success());
+    let _ = connection.unbind().await;
+    result
+        .map(|_| ())
+        .map_err(|e| AppError::Unauthorized(format!("LDAP bind as '{dn}' failed: {e}")))
+}
+
+#[cfg(test)]
+pub(crate) fn remember_for_tests(
+    realm: &Path,
+    headers: &HeaderMap,
+    name: &str,
+    grants: Vec<Grant>,
+) {
+    remember(
+        realm,
+        headers,
+        Identity {
+            name: name.to_string(),
+            grants,
+            expires_at: Instant::now() + LDAP_CACHE_TTL,
+        },
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+    use serde_json::json;
+
+    fn oidc() -> OidcAuthConfig {
+        serde_json::from_value(json!({
+            "issuer": "https://login.example.com/realms/dev",
+            "claim_scopes": [
+                { "claim": "realm_access.roles", "value": "release", "scopes": ["read", "publish", "delete"] },
+                { "claim": "scope", "value": "packages:read", "scopes": ["read"], "registries": ["cargo"] },
+                { "claim": "email_verified", "scopes": ["read"], "registries": ["npm"] },
+            ],
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn claims_map_to_grants() {
+        let config = oidc();
+        assert_eq!(config.audience, "vm-package-server");
+
+        let release = identity_from_claims(
+            &config,
+            &json!({
+                "sub": "f81d4fae",
+                "preferred_username": "alice",
+                "realm_access": { "roles": ["release", "offline_access"] },
+                "exp": 4_102_444_800u64,
+            }),
+        );
+        assert_eq!(release.name, "alice");
+        assert!(release.allows(TokenScope::Delete, "pypi"));
+
+        let reader = identity_from_claims(
+            &config,
+            &json!({ "sub": "f81d4fae", "scope": "openid packages:read", "email_verified": false }),
+        );
+        assert_eq!(reader.name, "f81d4fae");
+        assert!(reader.allows(TokenScope::Read, "cargo"));
+        assert!(!reader.allows(TokenScope::Read, "npm"));
+        assert!(!reader.allows(TokenScope::Publish, "cargo"));
+
+        let nobody = identity_from_claims(&config, &json!({ "sub": "x", "exp": 1 }));
+        assert!(nobody.grants.is_empty());
+        assert!(nobody.expires_at <= Instant::now());
+    }
+
+    #[test]
+    fn credentials_from_authorization_header() {
+        let header = |value: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(header::AUTHORIZATION, HeaderValue::from_str(value).unwrap());
+            headers
+        };
+        let basic = |credentials: &str| {
+            header(&format!(
+                "Basic {}",
+                base64::engine::general_purpose::STANDARD.encode(credentials)
+            ))
+        };
+        let jwt = "eyJhbGciOiJSUzI1NiJ9.eyJzdWIiOiJhIn0.c2ln";
+
+        assert_eq!(
+            credential(&header(&format!("Bearer {jwt}"))),
+            Some(Credential::Jwt(jwt.to_string()))
+        );
+        assert_eq!(
+            credential(&header(jwt)),
+            Some(Credential::Jwt(jwt.to_string()))
+        );
+        assert_eq!(
+            credential(&basic(&format!("__token__:{jwt}"))),
+            Some(Credential::Jwt(jwt.to_string()))
+        );
+        assert_eq!(
+            credential(&basic("alice:s3cret:with-colon")),
+            Some(Credential::Password {
+                username: "alice".to_string(),
+                password: "s3cret:with-colon".to_string(),
+            })
+        );
+        assert_eq!(credential(&header("Bearer vmpkg_0123")), None);
+        assert_eq!(credential(&basic("__token__:pypi-0123")), None);
+    }
+
+    #[test]
+    fn identities_and_rejections_are_cached_per_registry() {
+        let root = tempfile::TempDir::new().unwrap();
+        let tenant = root.path().join("tenants/team-a");
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::AUTHORIZATION,
+            HeaderValue::from_static("Basic Y2Fyb2w6czNjcmV0"),
+        );
+        remember_for_tests(
+            root.path(),
+            &headers,
+            "carol",
+            vec![Grant {
+                scopes: vec![TokenScope::Publish],
+                registries: vec![],
+            }],
+        );
+
+        assert!(find(root.path(), &headers).is_some());
+        assert!(find(&tenant, &headers).is_none());
+
+        assert!(!recently_rejected(&tenant, &headers));
+        remember_rejection(&tenant, &headers);
+        assert!(recently_rejected(&tenant, &headers));
+        assert!(!recently_rejected(root.path(), &headers));
+    }
+}
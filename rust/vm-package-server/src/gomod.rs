@@ -0,0 +1,420 @@
+//! # Go Module Proxy
+//!
+//! Implements the GOPROXY protocol under `/go/`, so `go` can fetch modules with
+//! `GOPROXY=http://<server>/go,direct`:
+//!
+//! - `GET /go/{module}/@v/list` - known versions, local and upstream
+//! - `GET /go/{module}/@v/{version}.info` - version metadata
+//! - `GET /go/{module}/@v/{version}.mod` - the module's `go.mod`
+//! - `GET /go/{module}/@v/{version}.zip` - module source archive
+//! - `GET /go/{module}/@latest` - metadata of the newest version
+//!
+//! Module paths and versions arrive case-encoded (`!` followed by a lowercase
+//! letter stands for the uppercase letter), which is also the layout on disk under
+//! `gomod/<module>/@v/`. Files missing locally are fetched from the upstream proxy
+//! and cached, since a published module version never changes.
+//!
+//! Private modules are published with `PUT` on the `.mod` path and then the `.zip`
+//! path; the `.info` file is written when the archive arrives.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use axum::{
+    body::Bytes,
+    extract::{Path as AxumPath, State},
+    http::{header, HeaderMap},
+    response::{IntoResponse, Json, Response},
+};
+use serde_json::json;
+use tracing::{debug, info, warn};
+
+use crate::provenance::{self, Source};
+use crate::validation_utils::FileStreamValidator;
+use crate::{storage, AppError, AppResult, AppState, SuccessResponse};
+
+const MAX_MODULE_PATH_LENGTH: usize = 512;
+const MAX_VERSION_LENGTH: usize = 128;
+
+/// A parsed GOPROXY request for one module
+#[derive(Debug, PartialEq, Eq)]
+enum GoRequest {
+    List,
+    Latest,
+    Info(String),
+    Mod(String),
+    Zip(String),
+}
+
+impl GoRequest {
+    /// Path of the requested file relative to the module directory
+    fn file(&self) -> String {
+        match self {
+            GoRequest::List => "@v/list".to_string(),
+            GoRequest::Latest => "@latest".to_string(),
+            GoRequest::Info(version) => format!("@v/{version}.info"),
+            GoRequest::Mod(version) => format!("@v/{version}.mod"),
+            GoRequest::Zip(version) => format!("@v/{version}.zip"),
+        }
+    }
+
+    fn content_type(&self) -> &'static str {
+        match self {
+            GoRequest::List | GoRequest::Mod(_) => "text/plain; charset=utf-8",
+            GoRequest::Latest | GoRequest::Info(_) => "application/json",
+            GoRequest::Zip(_) => "application/zip",
+        }
+    }
+}
+
+/// Split `<module>/@v/<file>` or `<module>/@latest` into a validated module and request
+fn parse_request(path: &str) -> AppResult<(String, GoRequest)> {
+    if let Some(module) = path.strip_suffix("/@latest") {
+        return Ok((validate_module_path(module)?, GoRequest::Latest));
+    }
+
+    let (module, file) = path
+        .rsplit_once("/@v/")
+        .ok_or_else(|| AppError::NotFound(format!("Not a Go module proxy path: {path}")))?;
+    let module = validate_module_path(module)?;
+    let request = if file == "list" {
+        GoRequest::List
+    } else if let Some(version) = file.strip_suffix(".info") {
+        GoRequest::Info(validate_version(version)?)
+    } else if let Some(version) = file.strip_suffix(".mod") {
+        GoRequest::Mod(validate_version(version)?)
+    } else if let Some(version) = file.strip_suffix(".zip") {
+        GoRequest::Zip(validate_version(version)?)
+    } else {
+        return Err(AppError::NotFound(format!("Unknown module file: {file}")));
+    };
+    Ok((module, request))
+}
+
+/// Validate a case-encoded module path so it can be used as a relative directory
+fn validate_module_path(module: &str) -> AppResult<String> {
+    let valid_chars = module
+        .chars()
+        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || "-._~/!".contains(c));
+    let valid_segments = module
+        .split('/')
+        .all(|segment| !segment.is_empty() && !segment.starts_with('.'));
+    if module.is_empty() || module.len() > MAX_MODULE_PATH_LENGTH || !valid_chars || !valid_segments
+    {
+        return Err(AppError::BadRequest(format!(
+            "Invalid Go module path: {module}"
+        )));
+    }
+    Ok(module.to_string())
+}
+
+/// Validate a case-encoded module version (e.g. `v1.2.3`, `v0.0.0-20240101-abcdef`)
+fn validate_version(version: &str) -> AppResult<String> {
+    let valid = version.starts_with('v')
+        && version.len() <= MAX_VERSION_LENGTH
+        && !version.contains("..")
+        && version
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || "-.+!".contains(c));
+    if !valid {
+        return Err(AppError::BadRequest(format!(
+            "Invalid Go module version: {version}"
+        )));
+    }
+    Ok(version.to_string())
+}
+
+/// Undo the proxy case encoding (`!a` -> `A`)
+pub(crate) fn decode_case(encoded: &str) -> String {
+    let mut decoded = String::with_capacity(encoded.len());
+    let mut bang = false;
+    for c in encoded.chars() {
+        if c == '!' {
+            bang = true;
+        } else if bang {
+            decoded.push(c.to_ascii_uppercase());
+            bang = false;
+        } else {
+            decoded.push(c);
+        }
+    }
+    decoded
+}
+
+/// Sort key for semantic versions; releases sort after their pre-releases
+fn version_key(version: &str) -> (Vec<u64>, bool, String) {
+    let version = version.trim_start_matches('v');
+    let version = version.split('+').next().unwrap_or(version);
+    let (release, prerelease) = match version.split_once('-') {
+        Some((release, prerelease)) => (release, prerelease.to_string()),
+        None => (version, String::new()),
+    };
+    let numbers = release
+        .split('.')
+        .map(|part| part.parse().unwrap_or(0))
+        .collect();
+    (numbers, prerelease.is_empty(), prerelease)
+}
+
+fn module_dir(data_dir: &Path, module: &str) -> PathBuf {
+    data_dir.join("gomod").join(module)
+}
+
+/// Versions with metadata stored locally (uploaded or cached)
+async fn local_versions(data_dir: &Path, module: &str) -> Vec<String> {
+    let dir = module_dir(data_dir, module).join("@v");
+    let Ok(mut entries) = tokio::fs::read_dir(&dir).await else {
+        return Vec::new();
+    };
+    let mut versions = Vec::new();
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        if let Some(version) = entry
+            .file_name()
+            .to_str()
+            .and_then(|name| name.strip_suffix(".info"))
+        {
+            versions.push(version.to_string());
+        }
+    }
+    versions
+}
+
+fn respond(request: &GoRequest, body: impl Into<Bytes>) -> Response {
+    (
+        [(header::CONTENT_TYPE, request.content_type())],
+        body.into(),
+    )
+        .into_response()
+}
+
+/// Serves GOPROXY protocol requests from local storage, falling back to upstream.
+///
+/// # Route
+/// `GET /go/{*path}`
+pub async fn get_module_file(
+    AxumPath(path): AxumPath<String>,
+    State(state): State<Arc<AppState>>,
+) -> AppResult<Response> {
+    debug!(path = %path, "Incoming Go module proxy request");
+    let (module, request) = parse_request(&path)?;
+
+    match &request {
+        GoRequest::List => {
+            let mut versions: Vec<String> = local_versions(&state.data_dir, &module)
+                .await
+                .iter()
+                .map(|v| decode_case(v))
+                .collect();
+            let upstream = state
+                .upstream_client
+                .load()
+                .fetch_go_module_file(&module, &request.file())
+                .await;
+            match upstream {
+                Ok(list) => versions.extend(
+                    String::from_utf8_lossy(&list)
+                        .lines()
+                        .map(str::trim)
+                        .filter(|v| !v.is_empty())
+                        .map(str::to_string),
+                ),
+                Err(e) if versions.is_empty() => return Err(e),
+                Err(e) => debug!(module = %module, error = %e, "Upstream version list unavailable"),
+            }
+            versions.sort_by_key(|v| version_key(v));
+            versions.dedup();
+            let mut body = versions.join("\n");
+            if !body.is_empty() {
+                body.push('\n');
+            }
+            Ok(respond(&request, body))
+        }
+        GoRequest::Latest => {
+            // Local versions win so private modules resolve without upstream
+            let local = local_versions(&state.data_dir, &module).await;
+            if let Some(latest) = local.iter().max_by_key(|v| version_key(v)) {
+                let info_path = module_dir(&state.data_dir, &module)
+                    .join("@v")
+                    .join(format!("{latest}.info"));
+                return Ok(respond(&request, storage::read_file(&info_path).await?));
+            }
+            let body = state
+                .upstream_client
+                .load()
+                .fetch_go_module_file(&module, &request.file())
+                .await?;
+            Ok(respond(&request, body))
+        }
+        GoRequest::Info(_) | GoRequest::Mod(_) | GoRequest::Zip(_) => {
+            let file_path = module_dir(&state.data_dir, &module).join(request.file());
+            if let Ok(data) = storage::read_file(&file_path).await {
+                debug!(module = %module, file = %request.file(), "Serving module file from local storage");
+                if let GoRequest::Zip(version) = &request {
+                    let filename = format!("{version}.zip");
+                    let source =
+                        if provenance::fetched_upstream(&state, "go", &module, &filename).await {
+                            Source::Upstream
+                        } else {
+                            Source::Local
+                        };
+                    provenance::record(&state, "go", &module, version, &filename, source).await;
+                }
+                return Ok(respond(&request, data));
+            }
+
+            let data = state
+                .upstream_client
+                .load()
+                .fetch_go_module_file(&module, &request.file())
+                .await?;
+            // Module versions are immutable, so upstream files are cached for good
+            if let Err(e) = storage::save_file(&file_path, &data).await {
+                warn!(path = %file_path.display(), error = %e, "Failed to cache module file");
+            }
+            info!(module = %module, file = %request.file(), size = data.len(), "Cached module file from upstream");
+            if let GoRequest::Zip(version) = &request {
+                let filename = format!("{version}.zip");
+                provenance::record(&state, "go", &module, version, &filename, Source::Upstream)
+                    .await;
+            }
+            Ok(respond(&request, data))
+        }
+    }
+}
+
+/// Publishes a private module version: upload `{version}.mod`, then `{version}.zip`.
+///
+/// # Route
+/// `PUT /go/{*path}`
+pub async fn publish_module_file(
+    AxumPath(path): AxumPath<String>,
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> AppResult<Json<SuccessResponse>> {
+    if crate::auth::is_auth_required(&state.config.load())
+        && !crate::auth::is_allowed(&state, &headers, "go", crate::tokens::TokenScope::Publish)
+    {
+        return Err(AppError::Unauthorized(
+            "Missing or invalid API key".to_string(),
+        ));
+    }
+
+    let (module, request) = parse_request(&path)?;
+    let version_dir = module_dir(&state.data_dir, &module).join("@v");
+    let module_name = decode_case(&module);
+
+    match &request {
+        GoRequest::Mod(version) => {
+            let content = std::str::from_utf8(&body)?;
+            let declared = content
+                .lines()
+                .find_map(|line| line.trim().strip_prefix("module "))
+                .map(|name| name.trim().trim_matches('"'));
+            if declared != Some(module_name.as_str()) {
+                return Err(AppError::BadRequest(format!(
+                    "go.mod must declare 'module {module_name}'"
+                )));
+            }
+            if version_dir.join(format!("{version}.zip")).exists() {
+                return Err(AppError::BadRequest(format!(
+                    "{module_name}@{} is already published",
+                    decode_case(version)
+                )));
+            }
+            storage::save_file(version_dir.join(format!("{version}.mod")), &body).await?;
+        }
+        GoRequest::Zip(version) => {
+            let filename = format!("{version}.zip");
+            FileStreamValidator::validate_package_upload(&body, &filename, "Go")?;
+            if !body.starts_with(b"PK\x03\x04") {
+                return Err(AppError::BadRequest(
+                    "Module archive is not a zip file".to_string(),
+                ));
+            }
+            if !version_dir.join(format!("{version}.mod")).exists() {
+                return Err(AppError::BadRequest(format!(
+                    "Upload {version}.mod before {filename}"
+                )));
+            }
+            if version_dir.join(&filename).exists() {
+                return Err(AppError::BadRequest(format!(
+                    "{module_name}@{} is already published",
+                    decode_case(version)
+                )));
+            }
+            let reservation = crate::quotas::check_upload(
+                &state,
+                &headers,
+                &crate::quotas::Upload {
+                    registry: "go",
+                    package: &module_name,
+                    version: None,
+                    size: body.len(),
+                },
+            )
+            .await?;
+            storage::save_file(version_dir.join(&filename), &body).await?;
+            reservation.commit();
+            let info = json!({
+                "Version": decode_case(version),
+                "Time": chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            });
+            storage::save_file(
+                version_dir.join(format!("{version}.info")),
+                serde_json::to_vec(&info)?,
+            )
+            .await?;
+            crate::webhooks::package_published(&state, "go", &module_name, &decode_case(version));
+        }
+        _ => {
+            return Err(AppError::BadRequest(
+                "Only .mod and .zip files can be uploaded".to_string(),
+            ))
+        }
+    }
+
+    info!(module = %module_name, file = %request.file(), "Go module file published");
+    Ok(Json(SuccessResponse {
+        message: format!("Published {module_name} {}", request.file()),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_proxy_paths() {
+        assert_eq!(
+            parse_request("github.com/!azure/sdk/@v/list").unwrap(),
+            ("github.com/!azure/sdk".to_string(), GoRequest::List)
+        );
+        assert_eq!(
+            parse_request("example.com/lib/@v/v1.2.3.zip").unwrap(),
+            (
+                "example.com/lib".to_string(),
+                GoRequest::Zip("v1.2.3".to_string())
+            )
+        );
+        assert_eq!(
+            parse_request("example.com/lib/@latest").unwrap().1,
+            GoRequest::Latest
+        );
+        assert!(parse_request("example.com/../etc/@v/list").is_err());
+        assert!(parse_request("example.com/lib/@v/v1.0.0.exe").is_err());
+        assert!(parse_request("example.com/lib/@v/1.0.0.mod").is_err());
+    }
+
+    #[test]
+    fn decodes_case_and_orders_versions() {
+        assert_eq!(
+            decode_case("github.com/!azure/!s!d!k"),
+            "github.com/Azure/SDK"
+        );
+
+        let mut versions = vec!["v1.10.0", "v1.2.0", "v1.2.0-rc.1", "v0.9.0"];
+        versions.sort_by_key(|v| version_key(v));
+        assert_eq!(versions, vec!["v0.9.0", "v1.2.0-rc.1", "v1.2.0", "v1.10.0"]);
+    }
+}
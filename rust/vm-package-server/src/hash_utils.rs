@@ -42,6 +42,47 @@ pub fn sha1_hash(data: &[u8]) -> String {
     format!("{:x}", hasher.finalize())
 }
 
+/// Calculate the Subresource Integrity string npm records for tarballs.
+///
+/// Returns `sha512-` followed by the base64-encoded SHA512 digest, the value of
+/// the `dist.integrity` field in npm metadata.
+pub fn sha512_integrity(data: &[u8]) -> String {
+    use base64::{engine::general_purpose, Engine as _};
+    use sha2::{Digest, Sha512};
+    format!(
+        "sha512-{}",
+        general_purpose::STANDARD.encode(Sha512::digest(data))
+    )
+}
+
+/// Check data against a Subresource Integrity string such as `sha512-...`.
+///
+/// Every space-separated hash using sha1, sha256 or sha512 must match. Returns
+/// `None` when the string has no hash in a supported algorithm.
+pub fn verify_integrity(integrity: &str, data: &[u8]) -> Option<bool> {
+    use base64::{engine::general_purpose, Engine as _};
+    use sha2::Digest;
+    let mut checked = false;
+    for hash in integrity.split_whitespace() {
+        let Some((algorithm, expected)) = hash.split_once('-') else {
+            continue;
+        };
+        // Options such as `?foo` may follow the digest
+        let expected = expected.split('?').next().unwrap_or_default();
+        let actual = match algorithm {
+            "sha1" => sha1::Sha1::digest(data).to_vec(),
+            "sha256" => sha2::Sha256::digest(data).to_vec(),
+            "sha512" => sha2::Sha512::digest(data).to_vec(),
+            _ => continue,
+        };
+        if general_purpose::STANDARD.encode(actual) != expected {
+            return Some(false);
+        }
+        checked = true;
+    }
+    checked.then_some(true)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -66,4 +107,22 @@ mod tests {
         // Known SHA1 hash for "hello world"
         assert_eq!(hash, "2aae6c35c94fcfb415dbe95f408b9ce91ee846ed");
     }
+
+    #[test]
+    fn test_integrity() {
+        let data = b"hello world";
+        let integrity = sha512_integrity(data);
+        assert!(integrity.starts_with("sha512-"));
+        assert_eq!(verify_integrity(&integrity, data), Some(true));
+        assert_eq!(verify_integrity(&integrity, b"hello world!"), Some(false));
+        assert_eq!(
+            verify_integrity("sha1-Kq5sNclPz7QV2+lfQIuc6R7oRu0=", data),
+            Some(true)
+        );
+        assert_eq!(
+            verify_integrity(&format!("{integrity} sha1-AAAA"), data),
+            Some(false)
+        );
+        assert_eq!(verify_integrity("md5-XrY7u+Ae7tCTyyK7j1rNww==", data), None);
+    }
 }
@@ -0,0 +1,275 @@
+//! # Health and Readiness
+//!
+//! Liveness (`/healthz`) only says the process is serving requests. Readiness
+//! (`/readyz`) checks whether the registry can do useful work:
+//!
+//! - **storage**: the data directory is writable (required)
+//! - **upstream**: pypi.org, npmjs, crates.io and the Go module proxy answer HTTP requests
+//! - **index**: Cargo index entries, npm metadata and PyPI checksums are intact
+//!
+//! Only required checks make the server "not ready" (HTTP 503). Failures in the
+//! other checks report "degraded" so local packages keep being served while an
+//! upstream is down or a single index file is damaged.
+
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::AppState;
+
+/// Maximum number of problems listed per check
+const MAX_REPORTED_PROBLEMS: usize = 5;
+
+/// Outcome of a single readiness check
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Ok,
+    Failed,
+    Skipped,
+}
+
+/// Result of one readiness check
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthCheck {
+    pub name: &'static str,
+    pub status: CheckStatus,
+    /// Whether a failure makes the server not ready
+    pub required: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+/// Overall readiness of the server
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReadinessStatus {
+    Ready,
+    Degraded,
+    NotReady,
+}
+
+/// Readiness report returned by `/readyz`
+#[derive(Debug, Clone, Serialize)]
+pub struct ReadinessReport {
+    pub status: ReadinessStatus,
+    pub checks: Vec<HealthCheck>,
+}
+
+impl ReadinessReport {
+    fn from_checks(checks: Vec<HealthCheck>) -> Self {
+        let failed = |required: bool| {
+            checks
+                .iter()
+                .any(|c| c.required == required && c.status == CheckStatus::Failed)
+        };
+        let status = if failed(true) {
+            ReadinessStatus::NotReady
+        } else if failed(false) {
+            ReadinessStatus::Degraded
+        } else {
+            ReadinessStatus::Ready
+        };
+        Self { status, checks }
+    }
+
+    /// Whether the server can accept traffic (ready or degraded)
+    pub fn is_ready(&self) -> bool {
+        self.status != ReadinessStatus::NotReady
+    }
+}
+
+/// Run all readiness checks
+pub async fn readiness(state: &AppState) -> ReadinessReport {
+    let data_dir = state.data_dir.clone();
+    let local_checks = tokio::task::spawn_blocking(move || {
+        vec![check_storage(&data_dir), check_index_integrity(&data_dir)]
+    })
+    .await
+    .unwrap_or_else(|e| {
+        vec![HealthCheck {
+            name: "storage",
+            status: CheckStatus::Failed,
+            required: true,
+            detail: Some(format!("Readiness check panicked: {e}")),
+        }]
+    });
+
+    let mut checks = local_checks;
+    checks.insert(1, check_upstream(state).await);
+    ReadinessReport::from_checks(checks)
+}
+
+fn check_storage(data_dir: &Path) -> HealthCheck {
+    let probe = data_dir.join(".readyz-probe");
+    let result = std::fs::write(&probe, b"ok").and_then(|_| std::fs::remove_file(&probe));
+    HealthCheck {
+        name: "storage",
+        status: if result.is_ok() {
+            CheckStatus::Ok
+        } else {
+            CheckStatus::Failed
+        },
+        required: true,
+        detail: result
+            .err()
+            .map(|e| format!("{} is not writable: {e}", data_dir.display())),
+    }
+}
+
+async fn check_upstream(state: &AppState) -> HealthCheck {
+    if !state.upstream_client.load().is_enabled() {
+        return HealthCheck {
+            name: "upstream",
+            status: CheckStatus::Skipped,
+            required: false,
+            detail: Some("Upstream lookups are disabled".to_string()),
+        };
+    }
+    if vm_core::offline::is_offline() {
+        return HealthCheck {
+            name: "upstream",
+            status: CheckStatus::Skipped,
+            required: false,
+            detail: Some("Offline mode is enabled; serving cached packages only".to_string()),
+        };
+    }
+
+    let unreachable = state.upstream_client.load().unreachable_registries().await;
+    HealthCheck {
+        name: "upstream",
+        status: if unreachable.is_empty() {
+            CheckStatus::Ok
+        } else {
+            CheckStatus::Failed
+        },
+        required: false,
+        detail: (!unreachable.is_empty()).then(|| {
+            unreachable
+                .iter()
+                .map(|(name, error)| format!("{name}: {error}"))
+                .collect::<Vec<_>>()
+                .join("; ")
+        }),
+    }
+}
+
+fn check_index_integrity(data_dir: &Path) -> HealthCheck {
+    let mut problems = Vec::new();
+
+    // Cargo index: newline-delimited JSON entries with name, version and checksum
+    for path in files_under(&data_dir.join("cargo/index")) {
+        let Ok(content) = std::fs::read_to_string(&path) else {
+            problems.push(format!("{}: unreadable", path.display()));
+            continue;
+        };
+        let valid = content
+            .lines()
+            .filter(|l| !l.trim().is_empty())
+            .all(|line| {
+                serde_json::from_str::<Value>(line).is_ok_and(|entry| {
+                    ["name", "vers", "cksum"]
+                        .iter()
+                        .all(|key| entry.get(key).is_some())
+                })
+            });
+        if !valid {
+            problems.push(format!("{}: invalid index entry", path.display()));
+        }
+    }
+
+    // npm metadata documents must be valid JSON
+    for path in files_under(&data_dir.join("npm/metadata")) {
+        let valid = std::fs::read_to_string(&path)
+            .is_ok_and(|content| serde_json::from_str::<Value>(&content).is_ok());
+        if !valid {
+            problems.push(format!("{}: invalid metadata", path.display()));
+        }
+    }
+
+    // PyPI packages need their checksum sidecar for the simple index
+    for path in files_under(&data_dir.join("pypi/packages")) {
+        let name = path.to_string_lossy();
+        if !(name.ends_with(".whl") || name.ends_with(".tar.gz")) {
+            continue;
+        }
+        let meta = PathBuf::from(format!("{name}.meta"));
+        if !meta.exists() {
+            problems.push(format!("{}: missing checksum", path.display()));
+        }
+    }
+
+    let detail = (!problems.is_empty()).then(|| {
+        let mut listed: Vec<String> = problems
+            .iter()
+            .take(MAX_REPORTED_PROBLEMS)
+            .cloned()
+            .collect();
+        if problems.len() > MAX_REPORTED_PROBLEMS {
+            listed.push(format!(
+                "and {} more",
+                problems.len() - MAX_REPORTED_PROBLEMS
+            ));
+        }
+        listed.join("; ")
+    });
+    HealthCheck {
+        name: "index",
+        status: if problems.is_empty() {
+            CheckStatus::Ok
+        } else {
+            CheckStatus::Failed
+        },
+        required: false,
+        detail,
+    }
+}
+
+/// All regular files below `dir` (empty if it doesn't exist)
+fn files_under(dir: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        let Ok(entries) = std::fs::read_dir(&current) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                pending.push(path);
+            } else {
+                files.push(path);
+            }
+        }
+    }
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn detects_damaged_index_entries() {
+        let temp = TempDir::new().unwrap();
+        let index_dir = temp.path().join("cargo/index/se/rd");
+        std::fs::create_dir_all(&index_dir).unwrap();
+        std::fs::write(
+            index_dir.join("serde"),
+            r#"{"name":"serde","vers":"1.0.0","cksum":"abc"}"#,
+        )
+        .unwrap();
+        assert_eq!(check_index_integrity(temp.path()).status, CheckStatus::Ok);
+
+        std::fs::write(index_dir.join("serde"), "not json").unwrap();
+        let check = check_index_integrity(temp.path());
+        assert_eq!(check.status, CheckStatus::Failed);
+        assert!(!check.required);
+
+        let report = ReadinessReport::from_checks(vec![check_storage(temp.path()), check]);
+        assert_eq!(report.status, ReadinessStatus::Degraded);
+        assert!(report.is_ready());
+    }
+}
@@ -0,0 +1,478 @@
+//! # Helm Chart Repository
+//!
+//! Serves a classic Helm chart repository under `/helm/`, so charts can be added with
+//! `helm repo add local http://<server>/helm`:
+//!
+//! - `GET /helm/index.yaml` - repository index, generated from the stored charts
+//! - `GET /helm/charts/{name}-{version}.tgz` - chart archive
+//! - `POST /helm/api/charts` - publish a chart (ChartMuseum API, used by `helm cm-push`)
+//!
+//! Charts are stored as `helm/charts/<name>-<version>.tgz` next to a JSON index entry
+//! holding the archive's `Chart.yaml` plus digest and creation time. The index is
+//! rebuilt from those entries on every request, so there is no shared file to keep
+//! consistent between concurrent uploads. Published versions are immutable.
+
+use std::collections::{BTreeMap, HashMap};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use axum::{
+    body::Bytes,
+    extract::{FromRequest, Multipart, Path as AxumPath, Request, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Json, Response},
+};
+use serde_json::{json, Value};
+use tracing::{debug, info, warn};
+
+use crate::package_index::PackageSummary;
+use crate::provenance::Source;
+use crate::validation::{MAX_METADATA_SIZE, MAX_MULTIPART_FIELDS, MAX_VERSION_LENGTH};
+use crate::validation_utils::FileStreamValidator;
+use crate::{sha256_hash, storage, AppError, AppResult, AppState};
+
+const MAX_CHART_NAME_LENGTH: usize = 128;
+
+fn charts_dir(data_dir: &Path) -> PathBuf {
+    data_dir.join("helm").join("charts")
+}
+
+fn chart_filename(name: &str, version: &str) -> String {
+    format!("{name}-{version}.tgz")
+}
+
+/// Chart names are lowercase letters, digits and dashes, starting with a letter
+fn validate_chart_name(name: &str) -> AppResult<()> {
+    let valid = name.len() <= MAX_CHART_NAME_LENGTH
+        && name.starts_with(|c: char| c.is_ascii_lowercase())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_');
+    if !valid {
+        return Err(AppError::BadRequest(format!("Invalid chart name: {name}")));
+    }
+    Ok(())
+}
+
+/// Chart versions are SemVer 2 strings (`1.2.3`, `0.1.0-rc.1+build.5`)
+fn validate_chart_version(version: &str) -> AppResult<()> {
+    let valid = !version.is_empty()
+        && version.len() <= MAX_VERSION_LENGTH
+        && version.starts_with(|c: char| c.is_ascii_digit())
+        && !version.contains("..")
+        && version
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-.+".contains(c));
+    if !valid {
+        return Err(AppError::BadRequest(format!(
+            "Invalid chart version: {version}"
+        )));
+    }
+    Ok(())
+}
+
+/// Validate a requested archive name so it can only address files in the charts directory
+fn validate_archive_filename(filename: &str) -> AppResult<()> {
+    let valid = filename.ends_with(".tgz")
+        && !filename.starts_with('.')
+        && !filename.contains("..")
+        && filename
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-._+".contains(c));
+    if !valid {
+        return Err(AppError::BadRequest(format!(
+            "Invalid chart archive name: {filename}"
+        )));
+    }
+    Ok(())
+}
+
+/// Chart name and version of an archive name: the version starts at the first
+/// `-` followed by a digit (`my-chart-1.2.3-rc.1.tgz`)
+fn split_archive_filename(filename: &str) -> Option<(&str, &str)> {
+    let stem = filename.strip_suffix(".tgz")?;
+    let split = stem
+        .match_indices('-')
+        .map(|(i, _)| i)
+        .find(|&i| stem[i + 1..].starts_with(|c: char| c.is_ascii_digit()))?;
+    Some((&stem[..split], &stem[split + 1..]))
+}
+
+/// Read `<chart>/Chart.yaml` from a packaged chart
+fn read_chart_metadata(archive: &[u8]) -> AppResult<serde_json::Map<String, Value>> {
+    let decoder = flate2::read::GzDecoder::new(archive);
+    let mut tar = tar::Archive::new(decoder);
+    let entries = tar
+        .entries()
+        .map_err(|e| AppError::BadRequest(format!("Chart is not a gzipped tarball: {e}")))?;
+
+    for entry in entries {
+        let entry =
+            entry.map_err(|e| AppError::BadRequest(format!("Corrupt chart archive: {e}")))?;
+        let path = entry.path()?.into_owned();
+        // Only the top-level chart; subcharts under charts/ carry their own Chart.yaml
+        if path.components().count() != 2 || !path.ends_with("Chart.yaml") {
+            continue;
+        }
+
+        let mut content = String::new();
+        entry
+            .take(MAX_METADATA_SIZE as u64)
+            .read_to_string(&mut content)
+            .map_err(|e| AppError::BadRequest(format!("Unreadable Chart.yaml: {e}")))?;
+        let metadata: Value = serde_yaml_ng::from_str(&content)
+            .map_err(|e| AppError::BadRequest(format!("Invalid Chart.yaml: {e}")))?;
+        return match metadata {
+            Value::Object(map) => Ok(map),
+            _ => Err(AppError::BadRequest(
+                "Chart.yaml must be a mapping".to_string(),
+            )),
+        };
+    }
+
+    Err(AppError::BadRequest(
+        "Chart archive does not contain Chart.yaml".to_string(),
+    ))
+}
+
+fn metadata_str<'a>(metadata: &'a serde_json::Map<String, Value>, key: &str) -> AppResult<&'a str> {
+    metadata
+        .get(key)
+        .and_then(Value::as_str)
+        .ok_or_else(|| AppError::BadRequest(format!("Chart.yaml is missing '{key}'")))
+}
+
+/// Sort key for chart versions; releases sort after their pre-releases
+fn version_key(version: &str) -> (Vec<u64>, bool, String) {
+    let version = version.split('+').next().unwrap_or(version);
+    let (release, prerelease) = match version.split_once('-') {
+        Some((release, prerelease)) => (release, prerelease.to_string()),
+        None => (version, String::new()),
+    };
+    let numbers = release
+        .split('.')
+        .map(|part| part.parse().unwrap_or(0))
+        .collect();
+    (numbers, prerelease.is_empty(), prerelease)
+}
+
+/// Index entries of every stored chart version
+pub async fn chart_entries(data_dir: &Path) -> Vec<Value> {
+    let Ok(mut dir) = tokio::fs::read_dir(charts_dir(data_dir)).await else {
+        return Vec::new();
+    };
+    let mut entries = Vec::new();
+    while let Ok(Some(file)) = dir.next_entry().await {
+        let path = file.path();
+        if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+            continue;
+        }
+        match storage::read_file(&path)
+            .await
+            .and_then(|data| Ok(serde_json::from_slice::<Value>(&data)?))
+        {
+            Ok(entry) => entries.push(entry),
+            Err(e) => warn!(path = %path.display(), error = %e, "Skipping unreadable chart entry"),
+        }
+    }
+    entries
+}
+
+/// Sorted names of the stored charts
+pub async fn chart_names(data_dir: &Path) -> Vec<String> {
+    let mut names: Vec<String> = chart_entries(data_dir)
+        .await
+        .iter()
+        .filter_map(|entry| entry.get("name")?.as_str().map(str::to_string))
+        .collect();
+    names.sort();
+    names.dedup();
+    names
+}
+
+/// Every chart with its newest `created` time and the given download counts, by name
+pub async fn chart_summaries(
+    data_dir: &Path,
+    downloads: &HashMap<String, u64>,
+) -> Vec<PackageSummary> {
+    let mut charts: BTreeMap<String, i64> = BTreeMap::new();
+    for entry in chart_entries(data_dir).await {
+        let Some(name) = entry.get("name").and_then(Value::as_str) else {
+            continue;
+        };
+        let created = entry
+            .get("created")
+            .and_then(Value::as_str)
+            .and_then(|created| chrono::DateTime::parse_from_rfc3339(created).ok())
+            .and_then(|created| created.timestamp_nanos_opt())
+            .unwrap_or(0);
+        let modified = charts.entry(name.to_string()).or_insert(created);
+        *modified = (*modified).max(created);
+    }
+    charts
+        .into_iter()
+        .map(|(name, modified)| PackageSummary {
+            downloads: downloads.get(&name).copied().unwrap_or(0),
+            name,
+            modified,
+        })
+        .collect()
+}
+
+/// Build `index.yaml` with charts grouped by name, newest version first
+fn render_index(entries: Vec<Value>, generated: &str) -> AppResult<String> {
+    let mut charts: BTreeMap<String, Vec<Value>> = BTreeMap::new();
+    for entry in entries {
+        if let Some(name) = entry.get("name").and_then(Value::as_str) {
+            charts.entry(name.to_string()).or_default().push(entry);
+        }
+    }
+    for versions in charts.values_mut() {
+        versions.sort_by_key(|entry| {
+            std::cmp::Reverse(version_key(
+                entry.get("version").and_then(Value::as_str).unwrap_or(""),
+            ))
+        });
+    }
+
+    let index = json!({
+        "apiVersion": "v1",
+        "entries": charts,
+        "generated": generated,
+    });
+    serde_yaml_ng::to_string(&index)
+        .map_err(|e| AppError::InternalError(format!("Failed to render index.yaml: {e}")))
+}
+
+/// Names of the stored charts with their newest version, most recently published first
+pub async fn recent_charts(data_dir: &Path, limit: usize) -> Vec<(String, String)> {
+    let mut entries = chart_entries(data_dir).await;
+    entries.sort_by(|a, b| {
+        let created = |entry: &Value| {
+            entry
+                .get("created")
+                .and_then(Value::as_str)
+                .map(str::to_string)
+        };
+        created(b).cmp(&created(a))
+    });
+    entries
+        .iter()
+        .filter_map(|entry| {
+            Some((
+                entry.get("name")?.as_str()?.to_string(),
+                entry.get("version")?.as_str()?.to_string(),
+            ))
+        })
+        .take(limit)
+        .collect()
+}
+
+/// Serves the repository index.
+///
+/// # Route
+/// `GET /helm/index.yaml`
+pub async fn repository_index(State(state): State<Arc<AppState>>) -> AppResult<Response> {
+    let entries = chart_entries(&state.data_dir).await;
+    debug!(versions = entries.len(), "Rendering Helm repository index");
+    let generated = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+    let body = render_index(entries, &generated)?;
+    Ok(([(header::CONTENT_TYPE, "application/x-yaml")], body).into_response())
+}
+
+/// Serves a chart archive.
+///
+/// # Route
+/// `GET /helm/charts/{filename}`
+pub async fn download_chart(
+    AxumPath(filename): AxumPath<String>,
+    State(state): State<Arc<AppState>>,
+) -> AppResult<Response> {
+    validate_archive_filename(&filename)?;
+    let path = charts_dir(&state.data_dir).join(&filename);
+    if !path.exists() {
+        return Err(AppError::NotFound(format!("Chart not found: {filename}")));
+    }
+    let data = storage::read_file(&path).await?;
+    debug!(filename = %filename, size = data.len(), "Serving chart archive");
+    if let Some((name, version)) = split_archive_filename(&filename) {
+        crate::provenance::record(&state, "helm", name, version, &filename, Source::Local).await;
+    }
+    Ok(([(header::CONTENT_TYPE, "application/gzip")], data).into_response())
+}
+
+/// Publishes a packaged chart.
+///
+/// Accepts the archive either as the raw request body
+/// (`curl --data-binary @chart.tgz`) or as the `chart` field of a multipart form,
+/// which is what the `helm cm-push` plugin sends.
+///
+/// # Route
+/// `POST /helm/api/charts`
+pub async fn upload_chart(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    request: Request,
+) -> AppResult<Response> {
+    if crate::auth::is_auth_required(&state.config.load())
+        && !crate::auth::is_allowed(&state, &headers, "helm", crate::tokens::TokenScope::Publish)
+    {
+        return Err(AppError::Unauthorized(
+            "Missing or invalid API key".to_string(),
+        ));
+    }
+
+    let is_multipart = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("multipart/form-data"));
+    let archive = if is_multipart {
+        let mut multipart = Multipart::from_request(request, &state)
+            .await
+            .map_err(|e| AppError::BadRequest(format!("Invalid multipart upload: {e}")))?;
+        let mut chart = None;
+        let mut field_count = 0;
+        while let Some(field) = multipart.next_field().await? {
+            field_count += 1;
+            if field_count > MAX_MULTIPART_FIELDS {
+                return Err(AppError::UploadError(format!(
+                    "Too many multipart fields: {field_count} (max: {MAX_MULTIPART_FIELDS})"
+                )));
+            }
+            if field.name() == Some("chart") {
+                chart = Some(field.bytes().await?);
+            }
+        }
+        chart.ok_or_else(|| {
+            AppError::BadRequest("Multipart upload has no 'chart' field".to_string())
+        })?
+    } else {
+        Bytes::from_request(request, &state)
+            .await
+            .map_err(|e| AppError::BadRequest(format!("Invalid upload body: {e}")))?
+    };
+
+    FileStreamValidator::validate_package_upload(&archive, "chart.tgz", "Helm")?;
+    let mut metadata = read_chart_metadata(&archive)?;
+    let name = metadata_str(&metadata, "name")?.to_string();
+    let version = metadata_str(&metadata, "version")?.to_string();
+    validate_chart_name(&name)?;
+    validate_chart_version(&version)?;
+
+    let filename = chart_filename(&name, &version);
+    let dir = charts_dir(&state.data_dir);
+    if dir.join(&filename).exists() {
+        return Err(AppError::BadRequest(format!(
+            "{name} {version} is already published"
+        )));
+    }
+    let reservation = crate::quotas::check_upload(
+        &state,
+        &headers,
+        &crate::quotas::Upload {
+            registry: "helm",
+            package: &name,
+            version: None,
+            size: archive.len(),
+        },
+    )
+    .await?;
+
+    metadata.insert("urls".to_string(), json!([format!("charts/{filename}")]));
+    metadata.insert("digest".to_string(), json!(sha256_hash(&archive)));
+    metadata.insert(
+        "created".to_string(),
+        json!(chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true)),
+    );
+
+    storage::save_file(dir.join(&filename), &archive).await?;
+    storage::save_file(
+        dir.join(format!("{name}-{version}.json")),
+        serde_json::to_vec(&Value::Object(metadata))?,
+    )
+    .await?;
+    reservation.commit();
+
+    info!(chart = %name, version = %version, size = archive.len(), "Helm chart published");
+    crate::webhooks::package_published(&state, "helm", &name, &version);
+    Ok((StatusCode::CREATED, Json(json!({ "saved": true }))).into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn package_chart(chart_yaml: &str) -> Vec<u8> {
+        let mut builder = tar::Builder::new(Vec::new());
+        for (path, content) in [
+            ("demo/Chart.yaml", chart_yaml),
+            ("demo/charts/dep/Chart.yaml", "name: dep\nversion: 9.9.9\n"),
+            ("demo/values.yaml", "replicas: 1\n"),
+        ] {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder
+                .append_data(&mut header, path, content.as_bytes())
+                .unwrap();
+        }
+        let tarball = builder.into_inner().unwrap();
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        std::io::Write::write_all(&mut encoder, &tarball).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[test]
+    fn reads_top_level_chart_metadata() {
+        let archive = package_chart("apiVersion: v2\nname: demo\nversion: 1.2.0\n");
+        let metadata = read_chart_metadata(&archive).unwrap();
+        assert_eq!(metadata_str(&metadata, "name").unwrap(), "demo");
+        assert_eq!(metadata_str(&metadata, "version").unwrap(), "1.2.0");
+
+        assert!(read_chart_metadata(b"not a tarball").is_err());
+        let missing_version = package_chart("apiVersion: v2\nname: demo\n");
+        let metadata = read_chart_metadata(&missing_version).unwrap();
+        assert!(metadata_str(&metadata, "version").is_err());
+    }
+
+    #[test]
+    fn validates_names_and_archive_paths() {
+        assert!(validate_chart_name("my-chart").is_ok());
+        assert!(validate_chart_name("MyChart").is_err());
+        assert!(validate_chart_name("../etc").is_err());
+        assert!(validate_chart_version("0.1.0-rc.1+build.5").is_ok());
+        assert!(validate_chart_version("v1.0.0").is_err());
+        assert!(validate_archive_filename("my-chart-1.0.0.tgz").is_ok());
+        assert!(validate_archive_filename("../secret.tgz").is_err());
+        assert!(validate_archive_filename("my-chart-1.0.0.json").is_err());
+        assert_eq!(
+            split_archive_filename("my-chart-1.0.0-rc.1.tgz"),
+            Some(("my-chart", "1.0.0-rc.1"))
+        );
+        assert_eq!(split_archive_filename("my-chart.tgz"), None);
+    }
+
+    #[test]
+    fn index_groups_charts_newest_first() {
+        let entries = vec![
+            json!({"name": "web", "version": "1.2.0-rc.1"}),
+            json!({"name": "web", "version": "1.10.0"}),
+            json!({"name": "db", "version": "0.1.0"}),
+            json!({"name": "web", "version": "1.2.0"}),
+        ];
+        let index = render_index(entries, "2026-01-01T00:00:00Z").unwrap();
+        let parsed: Value = serde_yaml_ng::from_str(&index).unwrap();
+
+        assert_eq!(parsed["apiVersion"], "v1");
+        let versions: Vec<&str> = parsed["entries"]["web"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|entry| entry["version"].as_str().unwrap())
+            .collect();
+        assert_eq!(versions, vec!["1.10.0", "1.2.0", "1.2.0-rc.1"]);
+        assert_eq!(parsed["entries"]["db"][0]["version"], "0.1.0");
+    }
+}
@@ -1,12 +1,12 @@
 //! # Package Registry Server
 //!
-//! A multi-package registry server that supports PyPI, npm, and Cargo registries.
+//! A multi-package registry server that supports PyPI, npm, Cargo, Go module and Helm chart registries.
 //! This library provides a unified interface for managing package repositories with
 //! caching, upload, and download capabilities.
 //!
 //! ## Features
 //!
-//! - **Multi-format support**: Handles PyPI, npm, and Cargo package formats
+//! - **Multi-format support**: Handles PyPI, npm, Cargo, Go module and Helm chart formats
 //! - **Upstream caching**: Automatically caches packages from upstream registries
 //! - **Package uploads**: Supports direct package uploads to the registry
 //! - **Web UI**: Provides a web interface for package management
@@ -17,10 +17,21 @@
 //! - [`config`]: Configuration management and settings
 //! - [`state`]: Application state and shared resources
 //! - [`error`]: Error handling and standardized responses
+//! - [`request_id`]: Request IDs and per-protocol error rendering
 //! - [`upstream`]: Communication with upstream registries
 //! - [`api`]: HTTP API endpoints and routing
 //! - [`storage`]: Package storage and file management
+//! - [`package_index`]: SQLite index of stored packages for fast listing
+//! - [`cache`]: Upstream package cache and its garbage collection
+//! - [`health`]: Liveness and readiness checks
+//! - [`gomod`]: Go module proxy (GOPROXY protocol)
+//! - [`helm`]: Helm chart repository
+//! - [`presets`]: Versioned vm preset distribution
+//! - [`signatures`]: Signature enforcement for package uploads
+//! - [`trusted_publishing`]: OIDC token exchange for PyPI CI uploads
+//! - [`tokens`]: Scoped API tokens and their management endpoints
 //! - [`validation`]: Security-focused input validation utilities
+//! - [`webhooks`]: Publish, yank and delete notifications to external endpoints
 //!
 //! ## Usage
 //!
@@ -31,27 +42,54 @@
 // Module declarations
 pub mod api;
 pub mod auth;
+pub mod backup;
+pub mod badges;
+pub mod cache;
 pub mod cargo;
 #[cfg(not(test))]
 pub mod client_ops;
 pub mod config;
 pub mod deletion;
 pub mod error;
+pub mod external_auth;
+pub mod gomod;
+pub mod health;
+pub mod helm;
+pub mod lifecycle;
 pub mod live_reload;
 pub mod local_storage;
+pub mod mirror;
 pub mod npm;
+pub mod npm_audit;
+pub mod openapi;
+pub mod package_edits;
+pub mod package_index;
 pub mod package_utils;
+pub mod presets;
+pub mod provenance;
 pub mod pypi;
+pub mod quotas;
 pub mod registry;
+pub mod replication;
+pub mod request_id;
 pub mod server;
+pub mod signatures;
 pub mod state;
 pub mod storage;
+pub mod tenants;
+pub mod throttle;
+pub mod tls;
+pub mod tokens;
+pub mod trusted_publishing;
 pub mod types;
 pub mod ui;
 pub mod upstream;
+pub mod upstream_policy;
 pub mod utils;
 pub mod validation;
 pub mod validation_utils;
+pub mod warm;
+pub mod webhooks;
 
 // Simplified configuration for VM tool integration
 pub mod simple_config;
@@ -62,10 +100,14 @@ pub mod pypi_utils;
 
 // Re-export key types for convenience
 #[cfg(not(test))]
-pub use client_ops::{add_package, list_packages, remove_package, show_status};
+pub use client_ops::{
+    add_package, add_workspace_packages, list_packages, remove_package, show_status,
+};
 pub use config::Config;
 pub use error::{ApiErrorResponse, AppError, AppResult, ErrorCode};
-pub use server::{run_server, run_server_background, run_server_with_shutdown};
+pub use server::{
+    reload_server, restart_server, run_server, run_server_background, run_server_with_shutdown,
+};
 pub use state::{AppState, SuccessResponse};
 pub use upstream::{UpstreamClient, UpstreamConfig};
 pub use validation::{
@@ -76,7 +118,7 @@ pub use validation::{
 };
 
 // NEW: Re-export utility functions from dedicated modules
-pub use hash_utils::{sha1_hash, sha256_hash};
+pub use hash_utils::{sha1_hash, sha256_hash, sha512_integrity};
 pub use pypi_utils::normalize_pypi_name;
 pub use validation_utils::validate_filename; // Moved from lib.rs
 
@@ -102,13 +144,23 @@ pub mod test_utils {
         let upstream_client = Arc::new(UpstreamClient::disabled());
         let config = Arc::new(crate::config::Config::default());
 
+        let package_index = Arc::new(
+            crate::package_index::PackageIndex::open(&data_dir)
+                .expect("Failed to open package index"),
+        );
+
+        let tokens = Arc::new(
+            crate::tokens::TokenStore::open(&data_dir).expect("Failed to open token store"),
+        );
         let state = Arc::new(AppState {
             data_dir,
             server_addr: "http://localhost:3080".to_string(),
-            upstream_client,
-            config,
+            upstream_client: upstream_client.into(),
+            config: config.into(),
             npm_registry: crate::registry::NpmRegistry::new(),
             pypi_registry: crate::registry::PypiRegistry::new(),
+            package_index,
+            tokens,
         });
         (state, temp_dir)
     }
@@ -126,13 +178,23 @@ pub mod test_utils {
         let upstream_client = Arc::new(UpstreamClient::disabled());
         let config = Arc::new(crate::config::Config::default());
 
+        let package_index = Arc::new(
+            crate::package_index::PackageIndex::open(&data_dir)
+                .expect("Failed to open package index"),
+        );
+
+        let tokens = Arc::new(
+            crate::tokens::TokenStore::open(&data_dir).expect("Failed to open token store"),
+        );
         let state = Arc::new(AppState {
             data_dir,
             server_addr: "http://localhost:3080".to_string(),
-            upstream_client,
-            config,
+            upstream_client: upstream_client.into(),
+            config: config.into(),
             npm_registry: crate::registry::NpmRegistry::new(),
             pypi_registry: crate::registry::PypiRegistry::new(),
+            package_index,
+            tokens,
         });
         (state, temp_dir)
     }
@@ -152,13 +214,23 @@ pub mod test_utils {
         let upstream_client = Arc::new(UpstreamClient::disabled());
         let config = Arc::new(crate::config::Config::default());
 
+        let package_index = Arc::new(
+            crate::package_index::PackageIndex::open(&data_dir)
+                .expect("Failed to open package index"),
+        );
+
+        let tokens = Arc::new(
+            crate::tokens::TokenStore::open(&data_dir).expect("Failed to open token store"),
+        );
         let state = Arc::new(AppState {
             data_dir,
             server_addr: "http://localhost:3080".to_string(),
-            upstream_client,
-            config,
+            upstream_client: upstream_client.into(),
+            config: config.into(),
             npm_registry: crate::registry::NpmRegistry::new(),
             pypi_registry: crate::registry::PypiRegistry::new(),
+            package_index,
+            tokens,
         });
         (state, temp_dir)
     }
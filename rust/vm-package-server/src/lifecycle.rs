@@ -0,0 +1,281 @@
+//! # Server Lifecycle
+//!
+//! Signal handling, connection draining and listening-socket handoff for the
+//! standalone server.
+//!
+//! On SIGTERM or SIGINT the server stops accepting connections and lets in-flight
+//! requests (for example a large `cargo publish` upload) finish, for up to
+//! `shutdown.drain_timeout_secs` seconds (default 120).
+//!
+//! On SIGHUP the server re-reads `config.json` and `upstream-policy.json`.
+//! `pkg-server reload` sends it through the pid file.
+//!
+//! Standalone servers record their pid in `<data_dir>/pkg-server.pid`.
+//! `pkg-server restart --graceful` sends the old process SIGUSR2, which makes it
+//! set `SO_REUSEPORT` on its listening socket. The new process then binds the
+//! same port with `SO_REUSEPORT` and asks the old one to drain, so there is no
+//! window in which connections are refused. Servers started normally bind
+//! without `SO_REUSEPORT`, so a second server can't silently share their port.
+
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use tokio::net::{TcpListener, TcpSocket};
+use tracing::{debug, info, warn};
+
+/// Pid file written into the data directory by standalone servers
+pub const PID_FILE: &str = "pkg-server.pid";
+
+const LISTEN_BACKLOG: u32 = 1024;
+
+/// How long a graceful restart waits for the old server to open its port
+const HANDOFF_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Bind the listening socket.
+///
+/// With `reuse_port`, the socket is bound with `SO_REUSEPORT` (Unix only) so a
+/// replacement server can bind the same address before this one exits.
+pub fn bind_listener(addr: SocketAddr, reuse_port: bool) -> Result<TcpListener> {
+    let socket = if addr.is_ipv4() {
+        TcpSocket::new_v4()
+    } else {
+        TcpSocket::new_v6()
+    }?;
+    socket.set_reuseaddr(true)?;
+    #[cfg(unix)]
+    if reuse_port {
+        socket.set_reuseport(true)?;
+    }
+    #[cfg(not(unix))]
+    let _ = reuse_port;
+    socket.bind(addr)?;
+    Ok(socket.listen(LISTEN_BACKLOG)?)
+}
+
+/// Let a replacement server bind this listener's address when asked.
+///
+/// Each SIGUSR2 sets `SO_REUSEPORT` on the listening socket, which a
+/// replacement needs before it can bind alongside it.
+#[cfg(unix)]
+pub fn spawn_handoff_task(listener: &TcpListener) -> Result<()> {
+    use std::os::fd::AsFd;
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let socket = listener
+        .as_fd()
+        .try_clone_to_owned()
+        .context("Failed to duplicate the listening socket")?;
+    let mut handoff =
+        signal(SignalKind::user_defined2()).context("Failed to install SIGUSR2 handler")?;
+    tokio::spawn(async move {
+        while handoff.recv().await.is_some() {
+            use nix::sys::socket::{setsockopt, sockopt::ReusePort};
+            match setsockopt(&socket, ReusePort, &true) {
+                Ok(()) => info!("Received SIGUSR2; a replacement server may now share the port"),
+                Err(e) => warn!(error = %e, "Failed to open the listening socket for handoff"),
+            }
+        }
+    });
+    Ok(())
+}
+
+/// Bind `addr` alongside the running server `previous` for a graceful restart
+#[cfg(unix)]
+pub async fn bind_handoff_listener(addr: SocketAddr, previous: u32) -> Result<TcpListener> {
+    use nix::sys::signal::{kill, Signal};
+    use nix::unistd::Pid;
+    if previous == std::process::id() {
+        anyhow::bail!("Refusing to signal the current process");
+    }
+    kill(Pid::from_raw(previous as i32), Signal::SIGUSR2)
+        .with_context(|| format!("Failed to signal server process {previous}"))?;
+
+    // The old server sets SO_REUSEPORT asynchronously, so retry until it has
+    let deadline = tokio::time::Instant::now() + HANDOFF_TIMEOUT;
+    loop {
+        match bind_listener(addr, true) {
+            Ok(listener) => return Ok(listener),
+            Err(e) if tokio::time::Instant::now() >= deadline => {
+                return Err(e.context(format!(
+                    "Server process {previous} did not share its port in time"
+                )))
+            }
+            Err(_) => tokio::time::sleep(Duration::from_millis(50)).await,
+        }
+    }
+}
+
+/// Resolve when the process receives SIGTERM or SIGINT (Ctrl+C elsewhere)
+pub async fn termination_signal() -> &'static str {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        match (
+            signal(SignalKind::terminate()),
+            signal(SignalKind::interrupt()),
+        ) {
+            (Ok(mut term), Ok(mut int)) => tokio::select! {
+                _ = term.recv() => "SIGTERM",
+                _ = int.recv() => "SIGINT",
+            },
+            _ => {
+                warn!("Failed to install signal handlers; falling back to Ctrl+C");
+                tokio::signal::ctrl_c().await.ok();
+                "SIGINT"
+            }
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        tokio::signal::ctrl_c().await.ok();
+        "Ctrl+C"
+    }
+}
+
+/// Listen for SIGHUP, the request to reload the configuration
+#[cfg(unix)]
+pub fn reload_signal() -> Option<tokio::signal::unix::Signal> {
+    use tokio::signal::unix::{signal, SignalKind};
+    match signal(SignalKind::hangup()) {
+        Ok(hangup) => Some(hangup),
+        Err(e) => {
+            warn!(error = %e, "Failed to install SIGHUP handler; configuration reload is disabled");
+            None
+        }
+    }
+}
+
+fn pid_file(data_dir: &Path) -> PathBuf {
+    data_dir.join(PID_FILE)
+}
+
+/// Record this process as the server for `data_dir`
+pub fn write_pid_file(data_dir: &Path) -> Result<()> {
+    std::fs::write(pid_file(data_dir), std::process::id().to_string())
+        .context("Failed to write pid file")
+}
+
+/// Remove the pid file if it still belongs to this process
+pub fn remove_pid_file(data_dir: &Path) {
+    if read_pid_file(data_dir) == Some(std::process::id()) {
+        let _ = std::fs::remove_file(pid_file(data_dir));
+    }
+}
+
+/// Pid of the server recorded for `data_dir`, if any
+pub fn read_pid_file(data_dir: &Path) -> Option<u32> {
+    std::fs::read_to_string(pid_file(data_dir))
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+}
+
+/// Whether a process with this pid is still running
+#[cfg(unix)]
+pub fn is_running(pid: u32) -> bool {
+    use nix::sys::signal::kill;
+    use nix::unistd::Pid;
+    kill(Pid::from_raw(pid as i32), None).is_ok()
+}
+
+/// Ask a previous server to stop accepting connections and drain
+#[cfg(unix)]
+pub fn request_drain(pid: u32) -> Result<()> {
+    use nix::sys::signal::{kill, Signal};
+    use nix::unistd::Pid;
+    if pid == std::process::id() {
+        anyhow::bail!("Refusing to signal the current process");
+    }
+    kill(Pid::from_raw(pid as i32), Signal::SIGTERM)
+        .with_context(|| format!("Failed to signal server process {pid}"))
+}
+
+/// Ask a running server to reload its configuration
+#[cfg(unix)]
+pub fn request_reload(pid: u32) -> Result<()> {
+    use nix::sys::signal::{kill, Signal};
+    use nix::unistd::Pid;
+    kill(Pid::from_raw(pid as i32), Signal::SIGHUP)
+        .with_context(|| format!("Failed to signal server process {pid}"))
+}
+
+/// Wait for a process to exit, up to `timeout`
+#[cfg(unix)]
+pub async fn wait_for_exit(pid: u32, timeout: Duration) -> bool {
+    let deadline = tokio::time::Instant::now() + timeout;
+    while is_running(pid) {
+        if tokio::time::Instant::now() >= deadline {
+            return false;
+        }
+        tokio::time::sleep(Duration::from_millis(200)).await;
+    }
+    debug!(pid, "Previous server exited");
+    true
+}
+
+/// Stop the server recorded in `data_dir` and wait for it to finish draining.
+///
+/// Used by non-graceful restarts, where the new server binds only after the old
+/// one has released the port. `drain_timeout` is the previous server's.
+#[cfg(unix)]
+pub async fn stop_previous(data_dir: &Path, drain_timeout: Duration) -> Result<()> {
+    let Some(pid) = read_pid_file(data_dir).filter(|pid| is_running(*pid)) else {
+        return Ok(());
+    };
+    info!(pid, "Stopping previous server");
+    request_drain(pid)?;
+    if !wait_for_exit(pid, drain_timeout + Duration::from_secs(5)).await {
+        anyhow::bail!("Previous server (pid {pid}) did not exit in time");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn pid_file_round_trip_only_removes_own_pid() {
+        let temp = TempDir::new().unwrap();
+        assert_eq!(read_pid_file(temp.path()), None);
+
+        write_pid_file(temp.path()).unwrap();
+        assert_eq!(read_pid_file(temp.path()), Some(std::process::id()));
+        remove_pid_file(temp.path());
+        assert_eq!(read_pid_file(temp.path()), None);
+
+        std::fs::write(temp.path().join(PID_FILE), "1").unwrap();
+        remove_pid_file(temp.path());
+        assert_eq!(read_pid_file(temp.path()), Some(1));
+    }
+
+    #[tokio::test]
+    #[cfg(unix)]
+    async fn reuse_port_allows_a_second_listener() {
+        let first = bind_listener("127.0.0.1:0".parse().unwrap(), true).unwrap();
+        let addr = first.local_addr().unwrap();
+        let second = bind_listener(addr, true);
+        assert!(second.is_ok());
+    }
+
+    #[tokio::test]
+    #[cfg(unix)]
+    async fn port_is_only_shared_after_handoff() {
+        use nix::sys::socket::{setsockopt, sockopt::ReusePort};
+
+        let first = bind_listener("127.0.0.1:0".parse().unwrap(), false).unwrap();
+        let addr = first.local_addr().unwrap();
+        assert!(bind_listener(addr, true).is_err());
+
+        // What the handoff task does on SIGUSR2
+        setsockopt(&first, ReusePort, &true).unwrap();
+        let second = bind_listener(addr, true).unwrap();
+        drop(first);
+        let _client = tokio::net::TcpStream::connect(addr).await.unwrap();
+        assert!(second.accept().await.is_ok());
+    }
+}
@@ -18,6 +18,9 @@ pub fn list_local_packages(data_dir: &Path) -> Result<HashMap<String, Vec<String
     // List Cargo crates
     packages.insert("cargo".to_string(), list_cargo_crates(data_dir)?);
 
+    // List Helm charts
+    packages.insert("helm".to_string(), list_helm_charts(data_dir)?);
+
     Ok(packages)
 }
 
@@ -59,12 +62,19 @@ fn list_npm_packages(data_dir: &Path) -> Result<Vec<String>> {
         return Ok(Vec::new());
     }
 
-    // Each subdirectory in metadata is a package
+    // Each package has a `<name>.json` metadata file; scoped ones sit in `@scope/`
     for entry in fs::read_dir(npm_metadata_dir)? {
         let entry = entry?;
-        if entry.file_type()?.is_dir() {
-            let package_name = entry.file_name().to_string_lossy().to_string();
-            packages.push(package_name);
+        let name = entry.file_name().to_string_lossy().to_string();
+        if entry.file_type()?.is_dir() && name.starts_with('@') {
+            for scoped in fs::read_dir(entry.path())? {
+                let scoped = scoped?.file_name().to_string_lossy().to_string();
+                if let Some(package) = scoped.strip_suffix(".json") {
+                    packages.push(format!("{name}/{package}"));
+                }
+            }
+        } else if let Some(package) = name.strip_suffix(".json") {
+            packages.push(package.to_string());
         }
     }
 
@@ -90,6 +100,34 @@ fn list_cargo_crates(data_dir: &Path) -> Result<Vec<String>> {
     Ok(sorted)
 }
 
+/// List Helm charts from their index entries in local storage
+fn list_helm_charts(data_dir: &Path) -> Result<Vec<String>> {
+    let charts_dir = data_dir.join("helm").join("charts");
+    let mut charts = HashSet::new();
+
+    if !charts_dir.exists() {
+        debug!("Helm charts directory does not exist");
+        return Ok(Vec::new());
+    }
+
+    // Chart names may contain dashes, so read the name from each entry
+    // instead of splitting `<name>-<version>.json`
+    for entry in fs::read_dir(charts_dir)? {
+        let path = entry?.path();
+        if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+            continue;
+        }
+        let metadata: Value = serde_json::from_slice(&fs::read(&path)?)?;
+        if let Some(name) = metadata.get("name").and_then(Value::as_str) {
+            charts.insert(name.to_string());
+        }
+    }
+
+    let mut sorted: Vec<String> = charts.into_iter().collect();
+    sorted.sort();
+    Ok(sorted)
+}
+
 /// Recursively walk Cargo index to find crate names
 fn list_cargo_crates_recursive(dir: &Path, crates: &mut HashSet<String>) -> Result<()> {
     for entry in fs::read_dir(dir)? {
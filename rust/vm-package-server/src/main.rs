@@ -3,11 +3,19 @@
 //! This binary provides the same commands as `vm pkg` but as a standalone `pkg-server` tool.
 //! Both CLIs expose identical functionality for package server operations.
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use clap::{Parser, Subcommand};
 use std::path::PathBuf;
 use vm_package_server::{
-    add_package, list_packages, remove_package, run_server, run_server_background, show_status,
+    add_package, add_workspace_packages, list_packages, reload_server, remove_package,
+    restart_server, run_server, run_server_background, show_status,
+};
+use vm_package_server::{
+    api::PackageServerClient,
+    backup::{self, BackupOptions},
+    config::{ReplicationMode, ReplicationPeer},
+    replication,
+    warm::LockfileFormat,
 };
 
 #[derive(Parser)]
@@ -55,11 +63,41 @@ enum Commands {
         data: PathBuf,
     },
 
+    /// Reload config.json and upstream-policy.json without dropping connections
+    Reload {
+        /// Data directory of the running server
+        #[arg(long, default_value = "./data")]
+        data: PathBuf,
+    },
+
+    /// Restart the server that uses the given data directory
+    Restart {
+        /// Host to bind to
+        #[arg(long, default_value = "0.0.0.0")]
+        host: String,
+
+        /// Port to bind to
+        #[arg(long, default_value = "3080")]
+        port: u16,
+
+        /// Data directory for package storage
+        #[arg(long, default_value = "./data")]
+        data: PathBuf,
+
+        /// Take over the listening socket before the old server drains (zero downtime)
+        #[arg(long)]
+        graceful: bool,
+    },
+
     /// Add/publish package from current directory
     Add {
         /// Filter package types (e.g., "python,npm")
         #[arg(long)]
         r#type: Option<String>,
+
+        /// Publish every package in the monorepo in dependency order
+        #[arg(long)]
+        workspace: bool,
     },
 
     /// Remove/delete packages interactively
@@ -74,6 +112,73 @@ enum Commands {
 
     /// Show server status and package counts
     Status,
+
+    /// Remove cached upstream packages according to the cache retention policy
+    Gc {
+        /// Data directory for package storage
+        #[arg(long, default_value = "./data")]
+        data: PathBuf,
+
+        /// Report what would be removed without deleting anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Copy packages between this server and another package server
+    Replicate {
+        /// URL of the other package server
+        peer: String,
+
+        /// Copy packages from this server to the peer instead of from the peer
+        #[arg(long)]
+        push: bool,
+
+        /// Only replicate this registry (pypi, npm or cargo); can be repeated
+        #[arg(long = "registry")]
+        registries: Vec<String>,
+
+        /// Only replicate this package; can be repeated
+        #[arg(long = "package")]
+        packages: Vec<String>,
+
+        /// API key for the peer, if it requires authentication
+        #[arg(long)]
+        peer_token: Option<String>,
+    },
+
+    /// Fetch everything listed in the server's mirror manifests into its caches
+    Mirror,
+
+    /// Fetch every package a lockfile references into the server's cache, for offline use
+    Warm {
+        /// requirements.txt, package-lock.json or Cargo.lock; can be repeated
+        #[arg(long = "from", required = true)]
+        lockfiles: Vec<PathBuf>,
+    },
+
+    /// Save all packages and metadata from the running server to a .tar.zst archive
+    Backup {
+        /// Archive to write
+        file: PathBuf,
+
+        /// Only save files changed since this earlier backup, which restore needs first
+        #[arg(long, value_name = "PREVIOUS")]
+        incremental: Option<PathBuf>,
+
+        /// Also save the upstream cache
+        #[arg(long)]
+        include_cache: bool,
+
+        /// Also save config.json and tokens.json, which hold the admin API keys
+        #[arg(long)]
+        include_secrets: bool,
+    },
+
+    /// Replace the running server's data with a backup archive
+    Restore {
+        /// Archive written by `pkg-server backup`
+        file: PathBuf,
+    },
 }
 
 #[tokio::main]
@@ -88,12 +193,201 @@ async fn main() -> Result<()> {
 
         Commands::Background { host, port, data } => run_server_background(host, port, data).await,
 
-        Commands::Add { r#type } => add_package(&cli.server, r#type.as_deref()),
+        Commands::Reload { data } => reload_server(data),
+
+        Commands::Restart {
+            host,
+            port,
+            data,
+            graceful,
+        } => restart_server(host, port, data, graceful).await,
+
+        Commands::Add {
+            r#type,
+            workspace: false,
+        } => add_package(&cli.server, r#type.as_deref()),
+
+        Commands::Add {
+            r#type,
+            workspace: true,
+        } => add_workspace_packages(&cli.server, r#type.as_deref()),
 
         Commands::Remove { force } => remove_package(&cli.server, force),
 
         Commands::List => list_packages(&cli.server),
 
         Commands::Status => show_status(&cli.server),
+
+        Commands::Gc { data, dry_run } => {
+            let report = vm_package_server::cache::run_gc(&data, dry_run)?;
+            let verb = if dry_run { "Would remove" } else { "Removed" };
+            println!(
+                "{verb} {} cached files ({:.1} MB); {} files ({:.1} MB) remain",
+                report.removed_files,
+                report.freed_bytes as f64 / 1_048_576.0,
+                report.remaining_files,
+                report.remaining_bytes as f64 / 1_048_576.0
+            );
+            Ok(())
+        }
+
+        Commands::Replicate {
+            peer,
+            push,
+            registries,
+            packages,
+            peer_token,
+        } => {
+            let server = cli.server.clone();
+            let report = tokio::task::spawn_blocking(move || {
+                let peer = ReplicationPeer {
+                    url: peer,
+                    mode: if push {
+                        ReplicationMode::Push
+                    } else {
+                        ReplicationMode::Pull
+                    },
+                    registries,
+                    packages,
+                    token: peer_token,
+                };
+                let local = PackageServerClient::new(&server);
+                replication::replicate_with_peer(&local, &peer)
+            })
+            .await??;
+            println!(
+                "Copied {} files; {} already up to date",
+                report.copied, report.up_to_date
+            );
+            for (file, error) in &report.failed {
+                eprintln!("Failed to copy {file}: {error}");
+            }
+            if !report.failed.is_empty() {
+                anyhow::bail!("{} files could not be replicated", report.failed.len());
+            }
+            Ok(())
+        }
+
+        Commands::Mirror => {
+            let server = cli.server.clone();
+            let report = tokio::task::spawn_blocking(move || {
+                PackageServerClient::new(&server).sync_mirrors()
+            })
+            .await??;
+            println!(
+                "Fetched {} files; {} entries already cached",
+                report.fetched, report.up_to_date
+            );
+            for (entry, error) in &report.failed {
+                eprintln!("Failed to mirror {entry}: {error}");
+            }
+            if !report.failed.is_empty() {
+                anyhow::bail!("{} entries could not be mirrored", report.failed.len());
+            }
+            Ok(())
+        }
+
+        Commands::Warm { lockfiles } => {
+            let mut failed = 0;
+            for path in lockfiles {
+                let format = LockfileFormat::detect(&path).ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "Unsupported lockfile {}; expected requirements.txt, package-lock.json or Cargo.lock",
+                        path.display()
+                    )
+                })?;
+                let lockfile = std::fs::read_to_string(&path)
+                    .with_context(|| format!("Failed to read {}", path.display()))?;
+                let server = cli.server.clone();
+                let report = tokio::task::spawn_blocking(move || {
+                    PackageServerClient::new(&server).warm_cache(format, lockfile)
+                })
+                .await??;
+                println!(
+                    "{}: {} packages, fetched {} files, {} already available",
+                    path.display(),
+                    report.packages,
+                    report.fetched,
+                    report.up_to_date
+                );
+                for (package, error) in &report.failed {
+                    eprintln!("Failed to warm {package}: {error}");
+                }
+                failed += report.failed.len();
+            }
+            if failed > 0 {
+                anyhow::bail!("{failed} packages could not be fetched");
+            }
+            Ok(())
+        }
+
+        Commands::Backup {
+            file,
+            incremental,
+            include_cache,
+            include_secrets,
+        } => {
+            let since = match incremental {
+                Some(previous) => {
+                    let archive = std::fs::File::open(&previous)
+                        .with_context(|| format!("Failed to open {}", previous.display()))?;
+                    Some(backup::read_manifest(archive)?.created)
+                }
+                None => None,
+            };
+            let options = BackupOptions {
+                since,
+                include_cache,
+                include_secrets,
+                ..Default::default()
+            };
+            let server = cli.server.clone();
+            let partial = file.with_extension("partial");
+            let written = {
+                let partial = partial.clone();
+                tokio::task::spawn_blocking(move || -> Result<u64> {
+                    let mut out = std::fs::File::create(&partial)
+                        .with_context(|| format!("Failed to create {}", partial.display()))?;
+                    PackageServerClient::new(&server).backup(&options, &mut out)
+                })
+                .await?
+            };
+            let written = match written {
+                Ok(written) => written,
+                Err(e) => {
+                    let _ = std::fs::remove_file(&partial);
+                    return Err(e);
+                }
+            };
+            std::fs::rename(&partial, &file)
+                .with_context(|| format!("Failed to write {}", file.display()))?;
+            println!(
+                "Saved {} backup to {} ({:.1} MB)",
+                if since.is_some() {
+                    "incremental"
+                } else {
+                    "full"
+                },
+                file.display(),
+                written as f64 / 1_000_000.0
+            );
+            Ok(())
+        }
+
+        Commands::Restore { file } => {
+            let server = cli.server.clone();
+            let report = tokio::task::spawn_blocking(move || {
+                PackageServerClient::new(&server).restore(&file)
+            })
+            .await??;
+            println!(
+                "Restored {} files, removed {} files not in the backup",
+                report.restored, report.removed
+            );
+            println!(
+                "Run `pkg-server reload` to apply a restored config.json or upstream-policy.json"
+            );
+            Ok(())
+        }
     }
 }
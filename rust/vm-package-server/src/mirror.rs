@@ -0,0 +1,652 @@
+//! # Mirror manifests
+//!
+//! Declarative lists of packages and images that are kept in the local caches,
+//! so a team's exact dependency set stays available even when nobody has
+//! installed it recently. Manifests are YAML files, by default any
+//! `data/mirrors/*.yaml`:
+//!
+//! ```yaml
+//! npm:
+//!   - react@18.2.0
+//!   - "@types/node@20.11.5"
+//!   - lodash            # latest version
+//! pypi:
+//!   - requests==2.31.0
+//! cargo:
+//!   - serde@1.0.197
+//! images:
+//!   - postgres:16
+//! ```
+//!
+//! Packages are fetched from upstream into the upstream cache and pinned, so
+//! garbage collection never evicts them. Images are pulled through the Docker
+//! registry mirror. Syncing runs every `interval_minutes` (see the `mirror`
+//! section of `data/config.json`) and on demand with `pkg-server mirror`, which
+//! calls `POST /api/mirror/sync`.
+
+use crate::config::MirrorConfig;
+use crate::state::AppState;
+use crate::{auth, cache, AppError, AppResult};
+use axum::{extract::State, http::HeaderMap, Json};
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+/// Route that triggers a sync
+pub const SYNC_ROUTE: &str = "/api/mirror/sync";
+
+/// File in the data directory listing the cached versions a sync pinned
+const PINS_FILE: &str = "mirror-pins.json";
+
+/// Delay before the first background sync, so startup stays quick
+const STARTUP_DELAY: Duration = Duration::from_secs(30);
+
+/// Manifest media types accepted from the Docker registry
+const MANIFEST_TYPES: &str = "application/vnd.docker.distribution.manifest.list.v2+json, \
+    application/vnd.oci.image.index.v1+json, \
+    application/vnd.docker.distribution.manifest.v2+json, \
+    application/vnd.oci.image.manifest.v1+json";
+
+/// The packages and images listed in one or more manifest files
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MirrorManifest {
+    /// `name` or `name@version`
+    pub npm: Vec<String>,
+    /// `name` or `name==version`
+    pub pypi: Vec<String>,
+    /// `name` or `name@version`
+    pub cargo: Vec<String>,
+    /// Docker Hub image references such as `postgres:16`
+    pub images: Vec<String>,
+}
+
+impl MirrorManifest {
+    fn extend(&mut self, other: MirrorManifest) {
+        self.npm.extend(other.npm);
+        self.pypi.extend(other.pypi);
+        self.cargo.extend(other.cargo);
+        self.images.extend(other.images);
+    }
+
+    fn is_empty(&self) -> bool {
+        self.npm.is_empty()
+            && self.pypi.is_empty()
+            && self.cargo.is_empty()
+            && self.images.is_empty()
+    }
+}
+
+/// Read and merge every manifest matched by the configured patterns
+pub fn load_manifests(data_dir: &Path, config: &MirrorConfig) -> AppResult<MirrorManifest> {
+    let mut merged = MirrorManifest::default();
+    for path in manifest_paths(data_dir, config)? {
+        let content = std::fs::read_to_string(&path)?;
+        let manifest: MirrorManifest = serde_yaml_ng::from_str(&content).map_err(|e| {
+            AppError::BadRequest(format!("Invalid mirror manifest {}: {e}", path.display()))
+        })?;
+        merged.extend(manifest);
+    }
+    Ok(merged)
+}
+
+fn manifest_paths(data_dir: &Path, config: &MirrorConfig) -> AppResult<Vec<PathBuf>> {
+    let mut paths = Vec::new();
+    for pattern in &config.manifests {
+        let pattern = data_dir.join(pattern);
+        let matches = glob::glob(&pattern.to_string_lossy()).map_err(|e| {
+            AppError::BadRequest(format!("Invalid mirror manifest pattern {pattern:?}: {e}"))
+        })?;
+        paths.extend(matches.flatten().filter(|path| path.is_file()));
+    }
+    paths.sort();
+    paths.dedup();
+    Ok(paths)
+}
+
+/// Split `name@version` (npm, Cargo) or `name==version` (PyPI)
+fn parse_spec<'a>(registry: &str, spec: &'a str) -> (&'a str, Option<&'a str>) {
+    let spec = spec.trim();
+    let split = if registry == "pypi" {
+        spec.split_once("==")
+    } else {
+        // Scoped npm packages start with `@`
+        spec.rfind('@')
+            .filter(|&at| at > 0)
+            .map(|at| (&spec[..at], &spec[at + 1..]))
+    };
+    match split {
+        Some((name, version)) if !version.trim().is_empty() => (name.trim(), Some(version.trim())),
+        Some((name, _)) => (name.trim(), None),
+        None => (spec, None),
+    }
+}
+
+/// A cached package version that garbage collection must keep
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Pin {
+    pub registry: String,
+    pub package: String,
+    pub version: String,
+}
+
+/// Versions pinned by the last sync
+pub fn load_pins(data_dir: &Path) -> HashSet<Pin> {
+    std::fs::read_to_string(data_dir.join(PINS_FILE))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save_pins(data_dir: &Path, pins: &HashSet<Pin>) {
+    let mut pins: Vec<&Pin> = pins.iter().collect();
+    pins.sort_by(|a, b| {
+        (&a.registry, &a.package, &a.version).cmp(&(&b.registry, &b.package, &b.version))
+    });
+    let result = serde_json::to_string_pretty(&pins)
+        .map_err(std::io::Error::from)
+        .and_then(|json| std::fs::write(data_dir.join(PINS_FILE), json));
+    if let Err(e) = result {
+        warn!(error = %e, "Failed to save mirror pins");
+    }
+}
+
+/// Outcome of a mirror sync
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MirrorReport {
+    /// Files or images fetched into the caches
+    pub fetched: usize,
+    /// Entries that were already cached
+    pub up_to_date: usize,
+    /// Entries that could not be synced, with the reason
+    pub failed: Vec<(String, String)>,
+}
+
+/// Bring the caches in line with the configured manifests
+pub async fn sync(state: &AppState) -> AppResult<MirrorReport> {
+    let manifest = load_manifests(&state.data_dir, &state.config.load().mirror)?;
+    let mut report = MirrorReport::default();
+    if manifest.is_empty() {
+        return Ok(report);
+    }
+    let has_packages =
+        !(manifest.npm.is_empty() && manifest.pypi.is_empty() && manifest.cargo.is_empty());
+    if has_packages && !state.config.load().cache.enabled {
+        return Err(AppError::BadRequest(
+            "Mirroring packages requires the upstream cache; enable `cache.enabled`".to_string(),
+        ));
+    }
+
+    let previous = load_pins(&state.data_dir);
+    let mut pins = HashSet::new();
+    let entries = manifest
+        .npm
+        .iter()
+        .map(|spec| ("npm", spec))
+        .chain(manifest.pypi.iter().map(|spec| ("pypi", spec)))
+        .chain(manifest.cargo.iter().map(|spec| ("cargo", spec)));
+    for (registry, spec) in entries {
+        let (name, version) = parse_spec(registry, spec);
+        let result = match registry {
+            "npm" => sync_npm(state, name, version).await,
+            "pypi" => sync_pypi(state, name, version).await,
+            _ => sync_cargo(state, name, version).await,
+        };
+        match result {
+            Ok((pin, fetched)) => {
+                if fetched > 0 {
+                    report.fetched += fetched;
+                } else {
+                    report.up_to_date += 1;
+                }
+                pins.insert(pin);
+            }
+            Err(e) => {
+                warn!(registry, spec = %spec, error = %e, "Failed to mirror package");
+                report
+                    .failed
+                    .push((format!("{registry}:{spec}"), e.to_string()));
+                // Keep whatever an earlier sync cached until this one succeeds
+                let package = if registry == "pypi" {
+                    crate::pypi_utils::normalize_pypi_name(name)
+                } else {
+                    name.to_string()
+                };
+                pins.extend(
+                    previous
+                        .iter()
+                        .filter(|pin| pin.registry == registry && pin.package == package)
+                        .cloned(),
+                );
+            }
+        }
+    }
+    save_pins(&state.data_dir, &pins);
+
+    for image in &manifest.images {
+        match sync_image(&state.config.load().mirror.docker_registry, image).await {
+            Ok(()) => report.fetched += 1,
+            Err(e) => {
+                warn!(image = %image, error = %e, "Failed to mirror image");
+                report
+                    .failed
+                    .push((format!("image:{image}"), e.to_string()));
+            }
+        }
+    }
+    Ok(report)
+}
+
+/// Cache one upstream file unless it is already cached; returns whether it was fetched
+async fn ensure_cached<F, Fut>(
+    state: &AppState,
+    pin: &Pin,
+    filename: &str,
+    fetch: F,
+) -> AppResult<bool>
+where
+    F: FnOnce() -> Fut,
+    Fut: std::future::Future<Output = AppResult<bytes::Bytes>>,
+{
+    let path = cache::cached_path(
+        &state.data_dir,
+        &pin.registry,
+        &pin.package,
+        &pin.version,
+        filename,
+    )
+    .ok_or_else(|| AppError::BadRequest(format!("Invalid package file name: {filename}")))?;
+    if path.is_file() {
+        return Ok(false);
+    }
+    let data = fetch().await?;
+    cache::store(
+        state,
+        &pin.registry,
+        &pin.package,
+        &pin.version,
+        filename,
+        &data,
+    )
+    .await;
+    Ok(true)
+}
+
+pub(crate) async fn sync_npm(
+    state: &AppState,
+    name: &str,
+    version: Option<&str>,
+) -> AppResult<(Pin, usize)> {
+    let metadata = state
+        .upstream_client
+        .load()
+        .fetch_npm_metadata(name)
+        .await?;
+    let version = match version {
+        Some(version) => version.to_string(),
+        None => metadata["dist-tags"]["latest"]
+            .as_str()
+            .ok_or_else(|| AppError::NotFound(format!("{name} has no latest version")))?
+            .to_string(),
+    };
+    let tarball = metadata["versions"][&version]["dist"]["tarball"]
+        .as_str()
+        .ok_or_else(|| AppError::NotFound(format!("{name}@{version} not found on npm")))?
+        .to_string();
+    let filename = tarball.rsplit('/').next().unwrap_or_default().to_string();
+    let pin = Pin {
+        registry: "npm".to_string(),
+        package: name.to_string(),
+        version,
+    };
+    let upstream = state.upstream_client.load();
+    let fetched = ensure_cached(state, &pin, &filename, || {
+        upstream.stream_npm_tarball(&tarball)
+    })
+    .await?;
+    Ok((pin, fetched as usize))
+}
+
+/// `(filename, url)` for each file linked from a PyPI simple index page
+fn pypi_links(html: &str, base: &str) -> Vec<(String, String)> {
+    let base = url::Url::parse(base).ok();
+    let href = regex::Regex::new(r#"href="([^"]+)""#).expect("valid regex");
+    href.captures_iter(html)
+        .filter_map(|captures| {
+            let link = captures[1].replace("&amp;", "&");
+            let link = link.split('#').next().unwrap_or_default();
+            let url = match &base {
+                Some(base) => base.join(link).ok()?.to_string(),
+                None => link.to_string(),
+            };
+            let filename = url.rsplit('/').next()?.to_string();
+            Some((filename, url))
+        })
+        .collect()
+}
+
+pub(crate) async fn sync_pypi(
+    state: &AppState,
+    name: &str,
+    version: Option<&str>,
+) -> AppResult<(Pin, usize)> {
+    let package = crate::pypi_utils::normalize_pypi_name(name);
+    let (html, base) = state
+        .upstream_client
+        .load()
+        .fetch_pypi_simple_page(&package)
+        .await?;
+    let mut releases: BTreeMap<String, Vec<(String, String)>> = BTreeMap::new();
+    for (filename, url) in pypi_links(&html, &base) {
+        if let Some((_, file_version)) =
+            crate::utils::extract_pypi_package_name_and_version(&filename)
+        {
+            releases
+                .entry(file_version)
+                .or_default()
+                .push((filename, url));
+        }
+    }
+    let version = match version {
+        Some(version) => version.to_string(),
+        None => releases
+            .keys()
+            .max_by_key(|version| cache::version_key(version))
+            .cloned()
+            .ok_or_else(|| AppError::NotFound(format!("{name} has no files on PyPI")))?,
+    };
+    let files = releases
+        .remove(&version)
+        .ok_or_else(|| AppError::NotFound(format!("{name}=={version} not found on PyPI")))?;
+    let pin = Pin {
+        registry: "pypi".to_string(),
+        package,
+        version,
+    };
+    let upstream = state.upstream_client.load();
+    let mut fetched = 0;
+    for (filename, url) in files {
+        if ensure_cached(state, &pin, &filename, || {
+            upstream.stream_pypi_url(&url, &filename)
+        })
+        .await?
+        {
+            fetched += 1;
+        }
+    }
+    Ok((pin, fetched))
+}
+
+pub(crate) async fn sync_cargo(
+    state: &AppState,
+    name: &str,
+    version: Option<&str>,
+) -> AppResult<(Pin, usize)> {
+    let index_path = crate::cargo::index_path(name)?;
+    let index = state
+        .upstream_client
+        .load()
+        .fetch_cargo_index(name, &index_path)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("{name} not found on crates.io")))?;
+    let versions: Vec<Value> = index
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect();
+    let version = match version {
+        Some(version) => versions
+            .iter()
+            .any(|entry| entry["vers"] == version)
+            .then(|| version.to_string()),
+        None => versions
+            .iter()
+            .filter(|entry| entry["yanked"] != true)
+            .filter_map(|entry| entry["vers"].as_str())
+            .max_by_key(|version| cache::version_key(version))
+            .map(str::to_string),
+    }
+    .ok_or_else(|| AppError::NotFound(format!("No matching version of {name} on crates.io")))?;
+    let filename = format!("{name}-{version}.crate");
+    let pin = Pin {
+        registry: "cargo".to_string(),
+        package: name.to_string(),
+        version,
+    };
+    let upstream = state.upstream_client.load();
+    let fetched = ensure_cached(state, &pin, &filename, || {
+        upstream.stream_cargo_crate(name, &pin.version)
+    })
+    .await?;
+    Ok((pin, fetched as usize))
+}
+
+/// Repository and tag or digest of a Docker Hub image reference
+fn parse_image(image: &str) -> Option<(String, String)> {
+    let (name, reference) = match image.split_once('@') {
+        Some((name, digest)) => (name, digest.to_string()),
+        None => match image.rsplit_once(':') {
+            Some((name, tag)) if !tag.contains('/') => (name, tag.to_string()),
+            _ => (image, "latest".to_string()),
+        },
+    };
+    let name = name
+        .strip_prefix("docker.io/")
+        .or_else(|| name.strip_prefix("index.docker.io/"))
+        .unwrap_or(name);
+    let first = name.split('/').next().unwrap_or_default();
+    if name.contains('/') && (first.contains(['.', ':']) || first == "localhost") {
+        // The registry mirror only proxies Docker Hub
+        return None;
+    }
+    let name = if name.contains('/') {
+        name.to_string()
+    } else {
+        format!("library/{name}")
+    };
+    Some((name, reference))
+}
+
+/// Pull an image through the registry mirror so its layers are cached
+async fn sync_image(registry: &str, image: &str) -> AppResult<()> {
+    let (name, reference) = parse_image(image).ok_or_else(|| {
+        AppError::BadRequest(format!(
+            "{image} is not a Docker Hub image; only Docker Hub images can be mirrored"
+        ))
+    })?;
+    let client = vm_core::network::client();
+    let base = format!("{}/v2/{name}", registry.trim_end_matches('/'));
+    let get_json = |url: String| {
+        let client = client.clone();
+        async move {
+            let response = client
+                .get(&url)
+                .header("Accept", MANIFEST_TYPES)
+                .send()
+                .await
+                .map_err(|e| AppError::InternalError(format!("Docker registry mirror: {e}")))?;
+            if !response.status().is_success() {
+                return Err(AppError::NotFound(format!(
+                    "Docker registry mirror returned {} for {url}",
+                    response.status()
+                )));
+            }
+            response
+                .json::<Value>()
+                .await
+                .map_err(|e| AppError::InternalError(format!("Invalid image manifest: {e}")))
+        }
+    };
+
+    let mut manifest = get_json(format!("{base}/manifests/{reference}")).await?;
+    if let Some(platforms) = manifest["manifests"].as_array() {
+        let arch = match std::env::consts::ARCH {
+            "x86_64" => "amd64",
+            "aarch64" => "arm64",
+            other => other,
+        };
+        let digest = platforms
+            .iter()
+            .find(|entry| {
+                entry["platform"]["os"] == "linux" && entry["platform"]["architecture"] == arch
+            })
+            .and_then(|entry| entry["digest"].as_str())
+            .ok_or_else(|| AppError::NotFound(format!("{image} has no linux/{arch} image")))?
+            .to_string();
+        manifest = get_json(format!("{base}/manifests/{digest}")).await?;
+    }
+
+    let blobs = std::iter::once(&manifest["config"])
+        .chain(manifest["layers"].as_array().into_iter().flatten())
+        .filter_map(|blob| blob["digest"].as_str());
+    for digest in blobs {
+        let response = client
+            .get(format!("{base}/blobs/{digest}"))
+            .send()
+            .await
+            .map_err(|e| AppError::InternalError(format!("Docker registry mirror: {e}")))?;
+        if !response.status().is_success() {
+            return Err(AppError::NotFound(format!(
+                "Docker registry mirror returned {} for {name}@{digest}",
+                response.status()
+            )));
+        }
+        // The mirror caches the blob as it streams; the bytes themselves aren't needed
+        let mut stream = response.bytes_stream();
+        while let Some(chunk) = stream.next().await {
+            chunk.map_err(|e| AppError::InternalError(format!("Failed to pull {digest}: {e}")))?;
+        }
+    }
+    debug!(image = %image, "Image cached in registry mirror");
+    Ok(())
+}
+
+/// Sync the mirror manifests now
+pub async fn sync_handler(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> AppResult<Json<MirrorReport>> {
+    if auth::is_auth_required(&state.config.load())
+        && !auth::is_authorized(&state.config.load(), &headers)
+    {
+        return Err(AppError::Unauthorized(
+            "A valid API token is required to sync mirror manifests".to_string(),
+        ));
+    }
+    Ok(Json(sync(&state).await?))
+}
+
+/// Sync the mirror manifests in the background every `interval_minutes`
+pub fn spawn_mirror_task(state: Arc<AppState>) {
+    let minutes = state.config.load().mirror.interval_minutes;
+    if minutes == 0 {
+        return;
+    }
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval_at(
+            tokio::time::Instant::now() + STARTUP_DELAY,
+            Duration::from_secs(minutes * 60),
+        );
+        loop {
+            interval.tick().await;
+            if vm_core::offline::is_offline() {
+                debug!("Skipping mirror sync in offline mode");
+                continue;
+            }
+            match sync(&state).await {
+                Ok(report) if report.fetched > 0 || !report.failed.is_empty() => info!(
+                    fetched = report.fetched,
+                    failed = report.failed.len(),
+                    "Mirror sync finished"
+                ),
+                Ok(_) => debug!("Mirror sync found everything cached"),
+                Err(e) => warn!(error = %e, "Mirror sync failed"),
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_specs() {
+        assert_eq!(parse_spec("npm", "react@18.2.0"), ("react", Some("18.2.0")));
+        assert_eq!(
+            parse_spec("npm", "@types/node@20.11.5"),
+            ("@types/node", Some("20.11.5"))
+        );
+        assert_eq!(parse_spec("npm", "@types/node"), ("@types/node", None));
+        assert_eq!(
+            parse_spec("pypi", "requests == 2.31.0"),
+            ("requests", Some("2.31.0"))
+        );
+        assert_eq!(parse_spec("cargo", "serde"), ("serde", None));
+    }
+
+    #[test]
+    fn test_parse_images() {
+        assert_eq!(
+            parse_image("postgres:16"),
+            Some(("library/postgres".into(), "16".into()))
+        );
+        assert_eq!(
+            parse_image("docker.io/grafana/grafana"),
+            Some(("grafana/grafana".into(), "latest".into()))
+        );
+        assert_eq!(
+            parse_image("redis@sha256:abc"),
+            Some(("library/redis".into(), "sha256:abc".into()))
+        );
+        assert_eq!(parse_image("ghcr.io/acme/app:1"), None);
+    }
+
+    #[test]
+    fn test_load_manifests_merges_files() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("mirrors")).unwrap();
+        std::fs::write(
+            dir.path().join("mirrors/web.yaml"),
+            "npm:\n  - react@18.2.0\nimages:\n  - postgres:16\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("mirrors/ml.yml"),
+            "pypi:\n  - numpy==1.26.4\n",
+        )
+        .unwrap();
+
+        let manifest = load_manifests(dir.path(), &MirrorConfig::default()).unwrap();
+        assert_eq!(manifest.npm, ["react@18.2.0"]);
+        assert_eq!(manifest.pypi, ["numpy==1.26.4"]);
+        assert_eq!(manifest.images, ["postgres:16"]);
+
+        std::fs::write(dir.path().join("mirrors/bad.yaml"), "gems:\n  - rails\n").unwrap();
+        assert!(load_manifests(dir.path(), &MirrorConfig::default()).is_err());
+    }
+
+    #[test]
+    fn test_pypi_links_resolve_relative_urls() {
+        let html = r#"<a href="https://files.example/packages/ab/requests-2.31.0.tar.gz#sha256=1">x</a>
+<a href="../../packages/requests-2.31.0-py3-none-any.whl">y</a>"#;
+        let links = pypi_links(html, "https://pypi.org/simple/requests/");
+        assert_eq!(
+            links,
+            [
+                (
+                    "requests-2.31.0.tar.gz".to_string(),
+                    "https://files.example/packages/ab/requests-2.31.0.tar.gz".to_string()
+                ),
+                (
+                    "requests-2.31.0-py3-none-any.whl".to_string(),
+                    "https://pypi.org/packages/requests-2.31.0-py3-none-any.whl".to_string()
+                ),
+            ]
+        );
+    }
+}
@@ -2,16 +2,23 @@ use std::sync::Arc;
 
 use axum::{
     extract::{Path as AxumPath, State},
+    http::HeaderMap,
     response::Json,
 };
 use base64::{engine::general_purpose, Engine as _};
 use serde_json::{json, Value};
 use tracing::{debug, info, warn};
 
+use crate::auth;
+use crate::cache;
+use crate::hash_utils::verify_integrity;
+use crate::provenance::Source;
+use crate::tokens::TokenScope;
 use crate::validation;
 use crate::validation_utils::FileStreamValidator;
 use crate::{
-    sha1_hash, storage, validate_filename, AppError, AppResult, AppState, SuccessResponse,
+    sha1_hash, sha512_integrity, storage, validate_filename, AppError, AppResult, AppState,
+    SuccessResponse,
 };
 
 // Deprecated functions have been removed.
@@ -20,7 +27,8 @@ use crate::{
 ///
 /// Serves package metadata compatible with NPM registry API, including version information,
 /// dependencies, and download URLs. Falls back to upstream NPM registry if package
-/// is not found locally.
+/// is not found locally, except for packages in a private scope, which also need a token
+/// that can read them.
 ///
 /// # Route
 /// `GET /npm/{package}`
@@ -29,7 +37,8 @@ use crate::{
 /// * `package` - The NPM package name (supports scoped packages like @scope/package)
 ///
 /// # Returns
-/// JSON object containing complete package metadata
+/// JSON object containing complete package metadata, with any edits made through
+/// `/api/metadata/npm/{package}` (such as a `deprecated` message on every version)
 ///
 /// # Example Response
 /// ```json
@@ -41,7 +50,8 @@ use crate::{
 ///       "version": "1.0.0",
 ///       "dist": {
 ///         "tarball": "http://localhost:8080/npm/package-name/-/package-name-1.0.0.tgz",
-///         "shasum": "abcd1234..."
+///         "shasum": "abcd1234...",
+///         "integrity": "sha512-..."
 ///       }
 ///     }
 ///   }
@@ -50,8 +60,16 @@ use crate::{
 pub async fn package_metadata(
     AxumPath(package): AxumPath<String>,
     State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
 ) -> AppResult<Json<Value>> {
     debug!(package = %package, "Incoming npm metadata request");
+    auth::check_npm_scope(
+        &state.config.load(),
+        &state.tokens,
+        &headers,
+        &package,
+        TokenScope::Read,
+    )?;
     let host = &state.server_addr;
     let metadata_path = state
         .data_dir
@@ -64,6 +82,19 @@ pub async fn package_metadata(
     let result = storage::read_file_string(&metadata_path).await;
     if let Ok(content) = result {
         if let Ok(mut metadata) = serde_json::from_str::<Value>(&content) {
+            if backfill_integrity(&state, &mut metadata).await {
+                match serde_json::to_string_pretty(&metadata) {
+                    Ok(updated) => {
+                        if let Err(e) = storage::save_file(&metadata_path, updated.as_bytes()).await
+                        {
+                            warn!(package = %package, error = %e, "Failed to save npm integrity hashes");
+                        }
+                    }
+                    Err(e) => {
+                        warn!(package = %package, error = %e, "Failed to serialize npm metadata")
+                    }
+                }
+            }
             // Update tarball URLs with current host
             if let Some(versions) = metadata["versions"].as_object_mut() {
                 for version_data in versions.values_mut() {
@@ -77,21 +108,37 @@ pub async fn package_metadata(
                     }
                 }
             }
+            // Description, homepage and deprecation edited after publishing
+            if let Some(edits) = crate::package_edits::load(&state.data_dir, "npm", &package).await
+            {
+                crate::package_edits::apply_npm(&mut metadata, &edits);
+            }
             return Ok(Json(metadata));
         }
     }
 
+    if auth::private_npm_scope(&state.config.load(), &package).is_some() {
+        return Err(AppError::NotFound(format!("Package not found: {package}")));
+    }
+
     // No local metadata found, try upstream NPM
     debug!(package = %package, "No local metadata found, checking upstream NPM");
-    match state.upstream_client.fetch_npm_metadata(&package).await {
+    match state
+        .upstream_client
+        .load()
+        .fetch_npm_metadata(&package)
+        .await
+    {
         Ok(upstream_metadata) => {
             info!(package = %package, "Found package on upstream NPM, updating URLs and returning");
             // Update tarball URLs to point to our server for transparent proxying
             let updated_metadata = state
                 .upstream_client
+                .load()
                 .update_npm_tarball_urls(upstream_metadata, host);
             Ok(Json(updated_metadata))
         }
+        Err(e @ AppError::UpstreamBlocked(_)) => Err(e),
         Err(_) => {
             debug!(package = %package, "Package not found on upstream NPM either");
             // Return 404 as per npm registry behavior
@@ -100,6 +147,39 @@ pub async fn package_metadata(
     }
 }
 
+/// Add `dist.integrity` to versions published before it was recorded, hashing
+/// their local tarballs. Returns whether any version was updated.
+async fn backfill_integrity(state: &AppState, metadata: &mut Value) -> bool {
+    let Some(versions) = metadata["versions"].as_object_mut() else {
+        return false;
+    };
+    let mut updated = false;
+    for version_data in versions.values_mut() {
+        let Some(dist) = version_data.get_mut("dist").and_then(|d| d.as_object_mut()) else {
+            continue;
+        };
+        if dist.contains_key("integrity") {
+            continue;
+        }
+        let Some(filename) = dist
+            .get("tarball")
+            .and_then(|url| url.as_str())
+            .and_then(|url| url.rsplit("/-/").next())
+        else {
+            continue;
+        };
+        if validate_filename(filename).is_err() {
+            continue;
+        }
+        let tarball_path = state.data_dir.join("npm/tarballs").join(filename);
+        if let Ok(data) = storage::read_file(&tarball_path).await {
+            dist.insert("integrity".to_string(), json!(sha512_integrity(&data)));
+            updated = true;
+        }
+    }
+    updated
+}
+
 /// Downloads NPM package tarballs from local storage or upstream registry.
 ///
 /// This endpoint serves NPM package tarballs (.tgz files) with fallback to the upstream
@@ -127,34 +207,71 @@ pub async fn package_metadata(
 ///
 /// # Behavior
 /// 1. First attempts to serve from local storage (`npm/tarballs/`)
-/// 2. If not found locally, streams from upstream NPM registry
+/// 2. If not found locally, streams from upstream NPM registry (never for private scopes)
 /// 3. Returns appropriate error if file not found anywhere
 pub async fn download_tarball(
     AxumPath((package, filename)): AxumPath<(String, String)>,
     State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
 ) -> AppResult<Vec<u8>> {
     // Validate filename to prevent path traversal
     validate_filename(&filename)?;
+    auth::check_npm_scope(
+        &state.config.load(),
+        &state.tokens,
+        &headers,
+        &package,
+        TokenScope::Read,
+    )?;
 
     debug!(package = %package, filename = %filename, "Incoming npm tarball download request");
     info!(package = %package, filename = %filename, "Downloading npm tarball");
     let file_path = state.data_dir.join("npm/tarballs").join(&filename);
 
+    let version = tarball_version(&package, &filename);
+    let (state_ref, package_ref, filename_ref) = (&state, &package, &filename);
+    let provenance = move |source| async move {
+        if let Some(version) = version {
+            crate::provenance::record(state_ref, "npm", package_ref, version, filename_ref, source)
+                .await;
+        }
+    };
+
     // Try local file first
     match storage::read_file(&file_path).await {
         Ok(data) => {
             debug!(package = %package, filename = %filename, size = data.len(), "Serving tarball from local storage");
+            provenance(Source::Local).await;
             Ok(data)
         }
+        Err(_) if auth::private_npm_scope(&state.config.load(), &package).is_some() => Err(
+            AppError::NotFound(format!("Tarball not found: {package}/{filename}")),
+        ),
         Err(_) => {
-            // File not found locally, try upstream NPM
+            // File not found locally, try the upstream cache and then upstream NPM
+            if let Some(version) = version {
+                if let Some(data) = cache::read(&state, "npm", &package, version, &filename).await {
+                    debug!(package = %package, filename = %filename, size = data.len(), "Serving tarball from upstream cache");
+                    provenance(Source::Upstream).await;
+                    return Ok(data);
+                }
+            }
             debug!(package = %package, filename = %filename, "Tarball not found locally, checking upstream NPM");
 
             // Construct the tarball URL for upstream
             let tarball_url = format!("/{package}/-/{filename}");
-            match state.upstream_client.stream_npm_tarball(&tarball_url).await {
+            match state
+                .upstream_client
+                .load()
+                .stream_npm_tarball(&tarball_url)
+                .await
+            {
                 Ok(bytes) => {
                     info!(package = %package, filename = %filename, size = bytes.len(), "Streaming tarball from upstream NPM");
+                    if let Some(version) = version {
+                        cache::store(&state, "npm", &package, version, &filename, &bytes).await;
+                    }
+                    provenance(Source::Upstream).await;
                     Ok(bytes.to_vec())
                 }
                 Err(e) => {
@@ -166,6 +283,101 @@ pub async fn download_tarball(
     }
 }
 
+/// Versions of a publish document that a tarball belongs to
+struct TarballTargets {
+    versions: Vec<String>,
+    /// Whether the versions were matched by tarball URL rather than assumed
+    matched: bool,
+}
+
+/// The versions whose `dist.tarball` URL names `filename`. A document carrying
+/// several versions (e.g. from replication) keeps the others' hashes; when no
+/// tarball URL matches, every version is a target.
+fn tarball_targets(versions: &Value, filename: &str) -> TarballTargets {
+    let Some(versions) = versions.as_object() else {
+        return TarballTargets {
+            versions: Vec::new(),
+            matched: false,
+        };
+    };
+    let tarball_suffix = format!("/{filename}");
+    let owning: Vec<String> = versions
+        .iter()
+        .filter(|(_, version_data)| {
+            version_data["dist"]["tarball"]
+                .as_str()
+                .is_some_and(|url| url.ends_with(&tarball_suffix))
+        })
+        .map(|(version, _)| version.clone())
+        .collect();
+    if owning.is_empty() {
+        TarballTargets {
+            versions: versions.keys().cloned().collect(),
+            matched: false,
+        }
+    } else {
+        TarballTargets {
+            versions: owning,
+            matched: true,
+        }
+    }
+}
+
+/// Reject a tarball that doesn't match the length, `shasum` or `integrity`
+/// the client computed before uploading, so a publish corrupted in transit
+/// fails now rather than at install time.
+///
+/// Hashes are only checked on versions known to own the tarball: by URL, or
+/// because the document has a single version.
+fn check_tarball_claims(
+    package: &str,
+    filename: &str,
+    attachment: &Value,
+    versions: &Value,
+    targets: &TarballTargets,
+    tarball_data: &[u8],
+    shasum: &str,
+) -> AppResult<()> {
+    let mismatch = |field: &str| {
+        warn!(package = %package, filename = %filename, field = %field, "npm tarball does not match publish payload");
+        AppError::BadRequest(format!(
+            "Tarball '{filename}' does not match its {field}; it may have been corrupted during upload"
+        ))
+    };
+
+    if let Some(length) = attachment["length"].as_u64() {
+        if length != tarball_data.len() as u64 {
+            return Err(mismatch("length"));
+        }
+    }
+    if !targets.matched && targets.versions.len() != 1 {
+        return Ok(());
+    }
+    for version in &targets.versions {
+        let dist = &versions[version]["dist"];
+        if let Some(claimed) = dist["shasum"].as_str() {
+            if !claimed.eq_ignore_ascii_case(shasum) {
+                return Err(mismatch("shasum"));
+            }
+        }
+        if let Some(claimed) = dist["integrity"].as_str() {
+            if verify_integrity(claimed, tarball_data) == Some(false) {
+                return Err(mismatch("integrity"));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Version from a tarball name: `@scope/pkg` publishes `pkg-1.2.3.tgz`
+fn tarball_version<'a>(package: &str, filename: &'a str) -> Option<&'a str> {
+    let name = package.rsplit('/').next()?;
+    filename
+        .strip_prefix(name)?
+        .strip_prefix('-')?
+        .strip_suffix(".tgz")
+}
+
 /// Publishes a new NPM package version to the local registry.
 ///
 /// This endpoint handles NPM package publishing according to the NPM registry API.
@@ -208,22 +420,39 @@ pub async fn download_tarball(
 /// # Processing Steps
 /// 1. Extracts and validates `_attachments` field
 /// 2. Decodes base64 tarball data
-/// 3. Calculates SHA1 hash for integrity
+/// 3. Calculates SHA1 and SHA512 hashes and checks them against the attachment
+///    `length` and the version's `dist.shasum` and `dist.integrity`
 /// 4. Saves tarball to `npm/tarballs/` directory
-/// 5. Updates metadata with calculated hash
+/// 5. Updates metadata with the calculated `shasum` and `integrity`
 /// 6. Saves metadata to `npm/metadata/` directory
 ///
 /// # Error Conditions
 /// - Missing or invalid `_attachments` field
 /// - No valid .tgz attachment found
 /// - Base64 decoding failures
+/// - Tarball not matching the length or hashes in the payload
 /// - File system write errors
 pub async fn publish_package(
     AxumPath(package): AxumPath<String>,
     State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
     Json(mut payload): Json<Value>,
 ) -> AppResult<Json<SuccessResponse>> {
     debug!(package = %package, "Incoming npm publish request");
+    if auth::is_auth_required(&state.config.load())
+        && !auth::is_allowed(&state, &headers, "npm", TokenScope::Publish)
+    {
+        return Err(AppError::Unauthorized(
+            "Missing or invalid API key".to_string(),
+        ));
+    }
+    auth::check_npm_scope(
+        &state.config.load(),
+        &state.tokens,
+        &headers,
+        &package,
+        TokenScope::Publish,
+    )?;
     info!(package = %package, "Publishing npm package");
 
     // Extract attachments containing the tarball
@@ -271,19 +500,53 @@ pub async fn publish_package(
             // Use centralized validation for decoded tarball size
             FileStreamValidator::validate_package_upload(&tarball_data, filename, "NPM")?;
 
+            let shasum = sha1_hash(&tarball_data);
+            let integrity = sha512_integrity(&tarball_data);
+            let targets = tarball_targets(&payload["versions"], filename);
+            check_tarball_claims(
+                &package,
+                filename,
+                attachment,
+                &payload["versions"],
+                &targets,
+                &tarball_data,
+                &shasum,
+            )?;
+
+            crate::signatures::verify_blob_upload(
+                &state.config.load().security.signatures.npm,
+                "npm",
+                &package,
+                &tarball_data,
+                &headers,
+            )?;
+
+            let reservation = crate::quotas::check_upload(
+                &state,
+                &headers,
+                &crate::quotas::Upload {
+                    registry: "npm",
+                    package: &package,
+                    version: tarball_version(&package, filename),
+                    size: tarball_data.len(),
+                },
+            )
+            .await?;
+
             // Save tarball
             let tarball_path = state.data_dir.join("npm/tarballs").join(filename);
             storage::save_file(tarball_path, &tarball_data).await?;
 
-            // Calculate SHA1 hash for metadata
-            let shasum = sha1_hash(&tarball_data);
-
-            // Update metadata with correct tarball URL and hash
+            // Record the hashes of the versions this tarball belongs to
             if let Some(versions) = payload["versions"].as_object_mut() {
-                for version_data in versions.values_mut() {
+                for (version, version_data) in versions.iter_mut() {
+                    if !targets.versions.contains(version) {
+                        continue;
+                    }
                     if let Some(dist) = version_data.get_mut("dist").and_then(|d| d.as_object_mut())
                     {
                         dist.insert("shasum".to_string(), json!(shasum));
+                        dist.insert("integrity".to_string(), json!(integrity));
                     }
                 }
             }
@@ -300,6 +563,23 @@ pub async fn publish_package(
                 .join(format!("{package}.json"));
             let metadata_str = serde_json::to_string_pretty(&payload)?;
             storage::save_file(metadata_path, metadata_str.as_bytes()).await?;
+            reservation.commit();
+
+            if let Some(latest) = payload["dist-tags"]["latest"].as_str() {
+                let (name, latest) = (package.clone(), latest.to_string());
+                if let Err(e) = state
+                    .package_index
+                    .run(move |index| index.record_latest("npm", &name, &latest))
+                    .await
+                {
+                    warn!(package = %package, error = %e, "Failed to update package index");
+                }
+            }
+            // Report the versions in this tarball, not `latest`, which may not have
+            // changed (e.g. `npm publish --tag next`)
+            for version in &targets.versions {
+                crate::webhooks::package_published(&state, "npm", &package, version);
+            }
 
             info!(package = %package, filename = %filename, size = tarball_data.len(), "npm package published successfully");
             return Ok(Json(SuccessResponse {
@@ -371,13 +651,22 @@ mod tests {
             .expect("Failed to create npm metadata dir");
 
         let config = Arc::new(crate::config::Config::default());
+        let package_index = Arc::new(
+            crate::package_index::PackageIndex::open(&data_dir)
+                .expect("Failed to open package index"),
+        );
+        let tokens = Arc::new(
+            crate::tokens::TokenStore::open(&data_dir).expect("Failed to open token store"),
+        );
         let state = Arc::new(AppState {
             data_dir,
             server_addr: "http://localhost:8080".to_string(),
-            upstream_client: Arc::new(UpstreamClient::disabled()),
-            config,
+            upstream_client: Arc::new(UpstreamClient::disabled()).into(),
+            config: config.into(),
             npm_registry: crate::registry::NpmRegistry::new(),
             pypi_registry: crate::registry::PypiRegistry::new(),
+            package_index,
+            tokens,
         });
 
         (state, temp_dir)
@@ -464,6 +753,100 @@ mod tests {
 
         // Verify shasum was calculated and added
         assert!(metadata["versions"][version]["dist"]["shasum"].is_string());
+        assert_eq!(
+            metadata["versions"][version]["dist"]["integrity"],
+            sha512_integrity(tarball_content)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_publish_webhook_reports_uploaded_version() {
+        // Collects the bodies posted to the webhook receiver
+        let received: Arc<std::sync::Mutex<Vec<Value>>> = Arc::default();
+        let receiver = axum::Router::new()
+            .route(
+                "/hook",
+                axum::routing::post(
+                    |State(received): State<Arc<std::sync::Mutex<Vec<Value>>>>,
+                     Json(event): Json<Value>| async move {
+                        received.lock().unwrap().push(event);
+                        StatusCode::OK
+                    },
+                ),
+            )
+            .with_state(received.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, receiver).await });
+
+        let (state, _temp_dir) = create_npm_test_state();
+        let mut config = crate::config::Config::default();
+        config.webhooks.endpoints = vec![crate::config::WebhookEndpoint {
+            url: format!("http://{addr}/hook"),
+            events: Vec::new(),
+            registries: Vec::new(),
+            secret: None,
+        }];
+        let mut state = (*state).clone();
+        state.config = Arc::new(config).into();
+        let app = axum::Router::new()
+            .route("/npm/{package}", axum::routing::put(publish_package))
+            .with_state(Arc::new(state));
+        let server = TestServer::new(app).expect("should create test server");
+
+        // `npm publish --tag next` leaves `latest` on an older version
+        let mut payload = create_npm_publish_payload("test-package", "2.0.0-beta.1", b"beta");
+        payload["dist-tags"] = json!({ "latest": "1.0.0", "next": "2.0.0-beta.1" });
+        let response = server.put("/npm/test-package").json(&payload).await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+
+        for _ in 0..100 {
+            if !received.lock().unwrap().is_empty() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 1);
+        assert_eq!(received[0]["event"], "package.published");
+        assert_eq!(received[0]["version"], "2.0.0-beta.1");
+    }
+
+    #[tokio::test]
+    async fn test_publish_rejects_tarball_not_matching_its_hashes() {
+        let (state, _temp_dir) = create_npm_test_state();
+        let app = axum::Router::new()
+            .route("/npm/{package}", axum::routing::put(publish_package))
+            .with_state(state.clone());
+        let server = TestServer::new(app).expect("should create test server");
+
+        let tarball_content = b"fake tarball content";
+        let dist = "/versions/1.0.0/dist";
+        let corruptions = [
+            ("shasum", json!(sha1_hash(b"other content"))),
+            ("integrity", json!(sha512_integrity(b"other content"))),
+        ];
+        for (field, value) in corruptions {
+            let mut payload = create_npm_publish_payload("test-package", "1.0.0", tarball_content);
+            payload.pointer_mut(dist).unwrap()[field] = value;
+            let response = server.put("/npm/test-package").json(&payload).await;
+            assert_eq!(response.status_code(), StatusCode::BAD_REQUEST);
+            assert!(response.text().contains(field));
+        }
+        let mut payload = create_npm_publish_payload("test-package", "1.0.0", tarball_content);
+        payload["_attachments"]["test-package-1.0.0.tgz"]["length"] = json!(3);
+        let response = server.put("/npm/test-package").json(&payload).await;
+        assert_eq!(response.status_code(), StatusCode::BAD_REQUEST);
+        assert!(!state
+            .data_dir
+            .join("npm/tarballs/test-package-1.0.0.tgz")
+            .exists());
+
+        let mut payload = create_npm_publish_payload("test-package", "1.0.0", tarball_content);
+        payload.pointer_mut(dist).unwrap()["shasum"] = json!(sha1_hash(tarball_content));
+        payload.pointer_mut(dist).unwrap()["integrity"] = json!(sha512_integrity(tarball_content));
+        let response = server.put("/npm/test-package").json(&payload).await;
+        assert_eq!(response.status_code(), StatusCode::OK);
     }
 
     #[tokio::test]
@@ -533,6 +916,41 @@ mod tests {
             .contains("test-package-1.0.0.tgz"));
     }
 
+    #[tokio::test]
+    async fn test_package_metadata_backfills_integrity() {
+        let (state, _temp_dir) = create_npm_test_state();
+        let metadata = json!({
+            "name": "old-package",
+            "versions": {
+                "1.0.0": {
+                    "dist": {
+                        "tarball": "http://localhost:8080/npm/old-package/-/old-package-1.0.0.tgz",
+                        "shasum": sha1_hash(b"old tarball")
+                    }
+                }
+            }
+        });
+        let metadata_path = state.data_dir.join("npm/metadata/old-package.json");
+        std::fs::write(&metadata_path, metadata.to_string()).unwrap();
+        std::fs::write(
+            state.data_dir.join("npm/tarballs/old-package-1.0.0.tgz"),
+            b"old tarball",
+        )
+        .unwrap();
+
+        let app = axum::Router::new()
+            .route("/npm/{package}", axum::routing::get(package_metadata))
+            .with_state(state);
+        let server = TestServer::new(app).expect("should create test server");
+        let body: Value = server.get("/npm/old-package").await.json();
+
+        let integrity = sha512_integrity(b"old tarball");
+        assert_eq!(body["versions"]["1.0.0"]["dist"]["integrity"], integrity);
+        let saved: Value =
+            serde_json::from_str(&std::fs::read_to_string(metadata_path).unwrap()).unwrap();
+        assert_eq!(saved["versions"]["1.0.0"]["dist"]["integrity"], integrity);
+    }
+
     #[tokio::test]
     async fn test_package_metadata_updates_host_header() {
         let (state, _temp_dir) = create_npm_test_state();
@@ -580,6 +998,72 @@ mod tests {
         assert!(tarball_url.contains("localhost:8080"));
     }
 
+    #[tokio::test]
+    async fn test_private_scope_requires_token_and_stays_local() {
+        let (state, _temp_dir) = create_npm_test_state();
+        let mut state = (*state).clone();
+        let mut config = crate::config::Config::default();
+        config.security.api_keys = vec!["admin-key".to_string()];
+        config.security.private_npm_scopes = vec![crate::config::NpmScopeAcl {
+            scope: "@acme".to_string(),
+            tokens: vec![],
+        }];
+        state.config = Arc::new(config).into();
+        let scope_dir = state.data_dir.join("npm/metadata/@acme");
+        std::fs::create_dir_all(&scope_dir).expect("should create scope dir");
+        std::fs::write(
+            scope_dir.join("widgets.json"),
+            json!({ "name": "@acme/widgets", "versions": {} }).to_string(),
+        )
+        .expect("should write metadata file");
+
+        let app = axum::Router::new()
+            .route("/npm/{package}", axum::routing::get(package_metadata))
+            .with_state(Arc::new(state));
+        let server = TestServer::new(app).expect("should create test server");
+
+        let response = server.get("/npm/@acme%2fwidgets").await;
+        assert_eq!(response.status_code(), StatusCode::UNAUTHORIZED);
+
+        let response = server
+            .get("/npm/@acme%2fwidgets")
+            .add_header("authorization", "Bearer admin-key")
+            .await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+        assert_eq!(response.json::<Value>()["name"], "@acme/widgets");
+
+        let response = server
+            .get("/npm/@acme%2fmissing")
+            .add_header("authorization", "Bearer admin-key")
+            .await;
+        assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_publish_requires_token_when_authentication_is_required() {
+        let (state, _temp_dir) = create_npm_test_state();
+        let mut state = (*state).clone();
+        let mut config = crate::config::Config::default();
+        config.security.require_authentication = true;
+        config.security.api_keys = vec!["admin-key".to_string()];
+        state.config = Arc::new(config).into();
+        let app = axum::Router::new()
+            .route("/npm/{package}", axum::routing::put(publish_package))
+            .with_state(Arc::new(state));
+        let server = TestServer::new(app).expect("should create test server");
+        let payload = create_npm_publish_payload("open-package", "1.0.0", b"tarball");
+
+        let response = server.put("/npm/open-package").json(&payload).await;
+        assert_eq!(response.status_code(), StatusCode::UNAUTHORIZED);
+
+        let response = server
+            .put("/npm/open-package")
+            .add_header("authorization", "Bearer admin-key")
+            .json(&payload)
+            .await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+    }
+
     #[tokio::test]
     async fn test_download_tarball() {
         let (state, _temp_dir) = create_npm_test_state();
@@ -605,4 +1089,17 @@ mod tests {
         assert_eq!(response.status_code(), StatusCode::OK);
         assert_eq!(response.as_bytes().to_vec(), content.to_vec());
     }
+
+    #[test]
+    fn test_tarball_version() {
+        assert_eq!(
+            tarball_version("lodash", "lodash-4.17.21.tgz"),
+            Some("4.17.21")
+        );
+        assert_eq!(
+            tarball_version("@types/node", "node-20.1.0-beta.1.tgz"),
+            Some("20.1.0-beta.1")
+        );
+        assert_eq!(tarball_version("lodash", "other-1.0.0.tgz"), None);
+    }
 }
@@ -0,0 +1,195 @@
+//! # npm Audit Proxy
+//!
+//! `npm audit` posts the dependency tree to the registry's security API. These
+//! handlers forward the request to the upstream npm registry and keep each answer
+//! in the upstream cache, keyed by a hash of the request body.
+//!
+//! When upstream can't be reached, or `cache.offline_audit` is set, the cached
+//! answer for the same request is served instead. Without one, the request fails
+//! with `503`, so `npm audit` reports an error rather than a clean result.
+
+use std::sync::Arc;
+
+use axum::{
+    body::Bytes,
+    extract::State,
+    http::{header, HeaderMap},
+    response::{IntoResponse, Response},
+};
+use tracing::{debug, info, warn};
+
+use crate::{cache, sha256_hash, AppError, AppResult, AppState};
+
+/// Cache registry name for stored audit responses
+const AUDIT_CACHE: &str = "npm-audit";
+
+/// `POST /npm/-/npm/v1/security/advisories/bulk`
+pub async fn advisories_bulk(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> AppResult<Response> {
+    audit(&state, AuditEndpoint::AdvisoriesBulk, &headers, body).await
+}
+
+/// `POST /npm/-/npm/v1/security/audits`
+pub async fn audits(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> AppResult<Response> {
+    audit(&state, AuditEndpoint::Audits, &headers, body).await
+}
+
+/// `POST /npm/-/npm/v1/security/audits/quick`
+pub async fn audits_quick(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> AppResult<Response> {
+    audit(&state, AuditEndpoint::AuditsQuick, &headers, body).await
+}
+
+#[derive(Debug, Clone, Copy)]
+enum AuditEndpoint {
+    AdvisoriesBulk,
+    Audits,
+    AuditsQuick,
+}
+
+impl AuditEndpoint {
+    /// Path below `/-/npm/v1/security/`
+    fn path(self) -> &'static str {
+        match self {
+            Self::AdvisoriesBulk => "advisories/bulk",
+            Self::Audits => "audits",
+            Self::AuditsQuick => "audits/quick",
+        }
+    }
+
+    /// Cache directory name for this endpoint's responses
+    fn cache_key(self) -> &'static str {
+        match self {
+            Self::AdvisoriesBulk => "advisories-bulk",
+            Self::Audits => "audits",
+            Self::AuditsQuick => "audits-quick",
+        }
+    }
+}
+
+async fn audit(
+    state: &AppState,
+    endpoint: AuditEndpoint,
+    headers: &HeaderMap,
+    body: Bytes,
+) -> AppResult<Response> {
+    let filename = format!("{}.json", sha256_hash(&body));
+    let offline = state.config.load().cache.offline_audit
+        || !state.upstream_client.load().is_enabled()
+        || vm_core::offline::is_offline();
+
+    if !offline {
+        let content_encoding = headers
+            .get(header::CONTENT_ENCODING)
+            .and_then(|value| value.to_str().ok());
+        match state
+            .upstream_client
+            .load()
+            .post_npm_audit(endpoint.path(), body, content_encoding)
+            .await
+        {
+            Ok(report) => {
+                info!(
+                    endpoint = endpoint.path(),
+                    "Fetched npm audit report from upstream"
+                );
+                cache::store(
+                    state,
+                    AUDIT_CACHE,
+                    endpoint.cache_key(),
+                    "-",
+                    &filename,
+                    &report,
+                )
+                .await;
+                return Ok(json_response(report.to_vec()));
+            }
+            Err(e) => {
+                warn!(endpoint = endpoint.path(), error = %e, "npm audit upstream failed, using cache")
+            }
+        }
+    }
+
+    match cache::read(state, AUDIT_CACHE, endpoint.cache_key(), "-", &filename).await {
+        Some(report) => {
+            debug!(
+                endpoint = endpoint.path(),
+                "Serving cached npm audit report"
+            );
+            Ok(json_response(report))
+        }
+        None if offline => Err(AppError::UpstreamUnavailable(
+            "npm audit is offline and no report is cached for this request".to_string(),
+        )),
+        None => Err(AppError::UpstreamUnavailable(
+            "registry.npmjs.org could not be reached and no audit report is cached for this request"
+                .to_string(),
+        )),
+    }
+}
+
+fn json_response(body: Vec<u8>) -> Response {
+    ([(header::CONTENT_TYPE, "application/json")], body).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::create_npm_test_state;
+    use serde_json::{json, Value};
+
+    async fn response_json(response: Response) -> Value {
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .expect("Failed to read response body");
+        serde_json::from_slice(&body).expect("Response should be JSON")
+    }
+
+    #[tokio::test]
+    async fn test_offline_audit_serves_cached_report() {
+        let (state, _temp_dir) = create_npm_test_state();
+        let request = Bytes::from_static(br#"{"lodash":["4.17.20"]}"#);
+        let report = json!({"lodash": [{"id": 1106913, "severity": "high"}]});
+        cache::store(
+            &state,
+            AUDIT_CACHE,
+            "advisories-bulk",
+            "-",
+            &format!("{}.json", sha256_hash(&request)),
+            report.to_string().as_bytes(),
+        )
+        .await;
+
+        let response = advisories_bulk(State(state), HeaderMap::new(), request)
+            .await
+            .expect("Audit should succeed");
+        assert_eq!(response_json(response).await, report);
+    }
+
+    #[tokio::test]
+    async fn test_offline_audit_without_cache_is_unavailable() {
+        let (state, _temp_dir) = create_npm_test_state();
+        let request = Bytes::from_static(br#"{"name":"app","dependencies":{}}"#);
+
+        let bulk = advisories_bulk(State(state.clone()), HeaderMap::new(), request.clone()).await;
+        assert!(matches!(bulk, Err(AppError::UpstreamUnavailable(_))));
+
+        let quick = audits_quick(State(state), HeaderMap::new(), request)
+            .await
+            .expect_err("Quick audit without a cached report should fail");
+        assert_eq!(
+            quick.error_code().http_status(),
+            axum::http::StatusCode::SERVICE_UNAVAILABLE
+        );
+    }
+}
@@ -0,0 +1,810 @@
+//! # OpenAPI specification
+//!
+//! The server describes its HTTP API as an OpenAPI 3.1 document at
+//! `GET /api/openapi.json` and serves Swagger UI for it at `/api/docs`, so other
+//! tools can integrate without reading this crate.
+//!
+//! Routes are listed in [`ENDPOINTS`] rather than annotated on each handler,
+//! since several handlers serve more than one path and most registry routes
+//! speak their client's protocol (pip, npm, Cargo) rather than JSON. JSON bodies
+//! of the management API reference the schemas derived with
+//! [`utoipa::ToSchema`]. A test checks that every route registered in
+//! `server.rs` is listed.
+
+use crate::backup::{BACKUP_ROUTE, RESTORE_ROUTE};
+use crate::mirror::SYNC_ROUTE;
+use crate::replication::MANIFEST_ROUTE;
+use crate::warm::WARM_ROUTE;
+use utoipa::openapi::path::{HttpMethod, OperationBuilder, ParameterBuilder, ParameterIn};
+use utoipa::openapi::request_body::RequestBodyBuilder;
+use utoipa::openapi::security::{HttpAuthScheme, HttpBuilder, SecurityRequirement, SecurityScheme};
+use utoipa::openapi::{
+    ContentBuilder, ObjectBuilder, OpenApi as OpenApiDoc, Ref, Required, ResponseBuilder, Type,
+};
+use utoipa::{Modify, OpenApi};
+
+/// Where the OpenAPI document is served
+pub const SPEC_ROUTE: &str = "/api/openapi.json";
+
+/// Where Swagger UI is served
+pub const DOCS_ROUTE: &str = "/api/docs";
+
+/// Credentials an endpoint needs when `security.require_authentication` is set
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Access {
+    /// Open to anyone who can reach the server
+    Public,
+    /// An admin API key or an issued token with the right scope
+    Token,
+    /// An admin API key from `security.api_keys`
+    Admin,
+}
+
+/// One route of the server
+struct Endpoint {
+    method: HttpMethod,
+    /// Axum route syntax; `{*rest}` wildcards become plain parameters
+    path: &'static str,
+    tag: &'static str,
+    summary: &'static str,
+    access: Access,
+    /// Optional query parameters
+    query: &'static [&'static str],
+    /// Content type of the request body, with its schema when it has one
+    request: Option<(&'static str, Option<&'static str>)>,
+    /// Content type of a successful response, with its schema when it has one
+    response: (&'static str, Option<&'static str>),
+}
+
+const JSON: &str = "application/json";
+const HTML: &str = "text/html";
+const TEXT: &str = "text/plain";
+const BINARY: &str = "application/octet-stream";
+
+const fn endpoint(
+    method: HttpMethod,
+    path: &'static str,
+    tag: &'static str,
+    summary: &'static str,
+    access: Access,
+) -> Endpoint {
+    Endpoint {
+        method,
+        path,
+        tag,
+        summary,
+        access,
+        query: &[],
+        request: None,
+        response: (JSON, None),
+    }
+}
+
+impl Endpoint {
+    const fn query(mut self, query: &'static [&'static str]) -> Self {
+        self.query = query;
+        self
+    }
+
+    const fn request(mut self, content_type: &'static str, schema: Option<&'static str>) -> Self {
+        self.request = Some((content_type, schema));
+        self
+    }
+
+    const fn response(mut self, content_type: &'static str, schema: Option<&'static str>) -> Self {
+        self.response = (content_type, schema);
+        self
+    }
+}
+
+use Access::{Admin, Public, Token};
+use HttpMethod::{Delete, Get, Patch, Post, Put};
+
+/// Every route the server registers
+const ENDPOINTS: &[Endpoint] = &[
+    // Server
+    endpoint(Get, "/", "server", "Web interface home page", Public).response(HTML, None),
+    endpoint(Get, "/ui", "server", "Web interface home page", Public).response(HTML, None),
+    endpoint(
+        Get,
+        "/ui/{pkg_type}",
+        "server",
+        "Web interface package list for a registry",
+        Public,
+    )
+    .response(HTML, None),
+    endpoint(
+        Get,
+        "/ui/pypi/{pkg_name}",
+        "server",
+        "Web interface page for a PyPI package",
+        Public,
+    )
+    .response(HTML, None),
+    endpoint(
+        Get,
+        "/ui/npm/{*pkg_name}",
+        "server",
+        "Web interface page for an npm package",
+        Public,
+    )
+    .response(HTML, None),
+    endpoint(
+        Get,
+        "/ui/cargo/{pkg_name}",
+        "server",
+        "Web interface page for a crate",
+        Public,
+    )
+    .response(HTML, None),
+    endpoint(
+        Get,
+        "/status",
+        "server",
+        "Server status and package counts",
+        Public,
+    ),
+    endpoint(
+        Get,
+        "/api/status",
+        "server",
+        "Server status and package counts",
+        Public,
+    ),
+    endpoint(
+        Get,
+        "/setup.sh",
+        "server",
+        "Script that points pip, npm and Cargo at this server",
+        Public,
+    )
+    .query(&["registry", "port"])
+    .response(TEXT, None),
+    endpoint(
+        Get,
+        "/tls/cert.pem",
+        "server",
+        "Certificate clients should trust when TLS is enabled",
+        Public,
+    )
+    .response("application/x-pem-file", None),
+    endpoint(Get, "/health", "server", "Liveness check", Public),
+    endpoint(Get, "/healthz", "server", "Liveness check", Public),
+    endpoint(
+        Get,
+        "/readyz",
+        "server",
+        "Readiness check of storage, index and upstreams",
+        Public,
+    ),
+    endpoint(Post, "/shutdown", "server", "Stop the server", Public),
+    endpoint(Get, SPEC_ROUTE, "server", "This OpenAPI document", Public),
+    // Packages
+    endpoint(
+        Get,
+        "/api/packages",
+        "packages",
+        "Packages per registry",
+        Public,
+    ),
+    endpoint(
+        Get,
+        "/api/search",
+        "packages",
+        "Ranked search across registries",
+        Public,
+    )
+    .query(&["q", "page", "per_page"]),
+    endpoint(
+        Get,
+        "/api/provenance/{registry}/{*package}",
+        "packages",
+        "Where each served file of a package came from",
+        Public,
+    )
+    .response(JSON, Some("ProvenanceResponse")),
+    endpoint(
+        Get,
+        "/api/{registry}/packages/{name}/latest",
+        "packages",
+        "Newest published version of a package",
+        Public,
+    )
+    .response(JSON, Some("LatestVersion")),
+    endpoint(
+        Get,
+        "/badge/{registry}/{*name}",
+        "packages",
+        "SVG badge showing the newest published version of a package",
+        Public,
+    )
+    .query(&["label"])
+    .response("image/svg+xml", None),
+    endpoint(
+        Get,
+        "/api/metadata/{registry}/{*package}",
+        "packages",
+        "Metadata edits of a published package",
+        Public,
+    ),
+    endpoint(
+        Patch,
+        "/api/metadata/{registry}/{*package}",
+        "packages",
+        "Edit the description, homepage or deprecation notice of a published package",
+        Token,
+    )
+    .request(JSON, Some("EditRequest")),
+    endpoint(
+        Post,
+        "/api/index/rebuild",
+        "packages",
+        "Rebuild the package index from disk",
+        Admin,
+    ),
+    // Administration
+    endpoint(
+        Get,
+        MANIFEST_ROUTE,
+        "admin",
+        "Files a replica needs to mirror this server",
+        Public,
+    ),
+    endpoint(
+        Post,
+        SYNC_ROUTE,
+        "admin",
+        "Sync from the configured mirror manifests now",
+        Admin,
+    ),
+    endpoint(
+        Post,
+        WARM_ROUTE,
+        "admin",
+        "Cache every upstream package a lockfile references",
+        Admin,
+    )
+    .query(&["format"])
+    .request(TEXT, None),
+    endpoint(
+        Get,
+        BACKUP_ROUTE,
+        "admin",
+        "Download a backup archive of the data directory",
+        Admin,
+    )
+    .query(&["since", "include_cache", "include_secrets"])
+    .response("application/zstd", None),
+    endpoint(
+        Post,
+        RESTORE_ROUTE,
+        "admin",
+        "Restore the data directory from a backup archive",
+        Admin,
+    )
+    .request("application/zstd", None)
+    .response(JSON, Some("RestoreReport")),
+    endpoint(Get, "/api/tokens", "admin", "List issued tokens", Admin),
+    endpoint(Post, "/api/tokens", "admin", "Issue a scoped token", Admin)
+        .request(JSON, Some("CreateTokenRequest")),
+    endpoint(Delete, "/api/tokens/{id}", "admin", "Revoke a token", Admin),
+    endpoint(
+        Get,
+        "/api/tenants",
+        "admin",
+        "List virtual registries",
+        Admin,
+    ),
+    endpoint(
+        Put,
+        "/api/tenants/{name}",
+        "admin",
+        "Create a virtual registry or replace its security settings",
+        Admin,
+    )
+    .request(JSON, None)
+    .response(JSON, Some("TenantSummary")),
+    endpoint(
+        Delete,
+        "/api/tenants/{name}",
+        "admin",
+        "Delete a virtual registry and its packages",
+        Admin,
+    ),
+    endpoint(
+        Get,
+        "/r/{tenant}/{*path}",
+        "server",
+        "Any route above, served by the virtual registry `tenant`",
+        Public,
+    )
+    .response(BINARY, None),
+    endpoint(
+        Get,
+        "/api/upstream/policy",
+        "admin",
+        "Upstream allow and deny rules",
+        Admin,
+    ),
+    endpoint(
+        Put,
+        "/api/upstream/policy",
+        "admin",
+        "Replace the upstream rules",
+        Admin,
+    )
+    .request(JSON, None),
+    endpoint(
+        Post,
+        "/api/upstream/policy/{list}",
+        "admin",
+        "Add an allow or deny rule",
+        Admin,
+    )
+    .request(JSON, None),
+    endpoint(
+        Delete,
+        "/api/upstream/policy/{list}/{registry}/{*package}",
+        "admin",
+        "Remove an allow or deny rule",
+        Admin,
+    ),
+    // PyPI
+    endpoint(
+        Get,
+        "/pypi/simple/{package}/",
+        "pypi",
+        "PEP 503 links for a project",
+        Public,
+    )
+    .response(HTML, None),
+    endpoint(
+        Get,
+        "/pypi/packages/{filename}",
+        "pypi",
+        "Download a distribution file",
+        Public,
+    )
+    .response(BINARY, None),
+    endpoint(
+        Get,
+        "/pypi/legacy/api/pypi",
+        "pypi",
+        "PEP 503 project list",
+        Public,
+    )
+    .response(HTML, None),
+    endpoint(
+        Get,
+        "/pypi/legacy/api/pypi/{package}/",
+        "pypi",
+        "PEP 503 links for a project",
+        Public,
+    )
+    .response(HTML, None),
+    endpoint(
+        Get,
+        "/pypi/legacy/api/pypi/{package}/{version}",
+        "pypi",
+        "PEP 503 links for a project",
+        Public,
+    )
+    .response(HTML, None),
+    endpoint(
+        Post,
+        "/pypi/",
+        "pypi",
+        "Upload a distribution (twine)",
+        Token,
+    )
+    .request("multipart/form-data", None)
+    .response(JSON, Some("SuccessResponse")),
+    endpoint(Put, "/pypi/upload", "pypi", "Upload a distribution", Token)
+        .request("multipart/form-data", None)
+        .response(JSON, Some("SuccessResponse")),
+    endpoint(
+        Get,
+        "/pypi/_/oidc/audience",
+        "pypi",
+        "Audience for trusted publishing tokens",
+        Public,
+    ),
+    endpoint(
+        Post,
+        "/pypi/_/oidc/mint-token",
+        "pypi",
+        "Exchange a CI identity token for an upload token",
+        Public,
+    )
+    .request(JSON, None),
+    // npm
+    endpoint(
+        Get,
+        "/npm/{package}",
+        "npm",
+        "Package document (packument)",
+        Public,
+    ),
+    endpoint(
+        Put,
+        "/npm/{package}",
+        "npm",
+        "Publish a package (npm publish)",
+        Token,
+    )
+    .request(JSON, None)
+    .response(JSON, Some("SuccessResponse")),
+    endpoint(
+        Get,
+        "/npm/{package}/-/{filename}",
+        "npm",
+        "Download a tarball",
+        Public,
+    )
+    .response(BINARY, None),
+    endpoint(
+        Post,
+        "/npm/-/npm/v1/security/advisories/bulk",
+        "npm",
+        "Bulk advisories (npm audit)",
+        Public,
+    )
+    .request(JSON, None),
+    endpoint(
+        Post,
+        "/npm/-/npm/v1/security/audits",
+        "npm",
+        "Audit a dependency tree (legacy npm audit)",
+        Public,
+    )
+    .request(JSON, None),
+    endpoint(
+        Post,
+        "/npm/-/npm/v1/security/audits/quick",
+        "npm",
+        "Quick audit of a dependency tree",
+        Public,
+    )
+    .request(JSON, None),
+    // Cargo
+    endpoint(
+        Get,
+        "/cargo/config.json",
+        "cargo",
+        "Sparse registry configuration",
+        Token,
+    ),
+    endpoint(
+        Get,
+        "/cargo/me",
+        "cargo",
+        "Login page shown by cargo login",
+        Public,
+    )
+    .response(HTML, None),
+    endpoint(
+        Put,
+        "/cargo/api/v1/crates/new",
+        "cargo",
+        "Publish a crate (cargo publish)",
+        Token,
+    )
+    .request(BINARY, None),
+    endpoint(
+        Get,
+        "/cargo/api/v1/crates/{crate}",
+        "cargo",
+        "Versions of a crate",
+        Token,
+    ),
+    endpoint(
+        Get,
+        "/cargo/api/v1/crates/{crate}/{version}",
+        "cargo",
+        "Download a crate",
+        Token,
+    )
+    .response(BINARY, None),
+    endpoint(
+        Get,
+        "/cargo/api/v1/crates/{crate}/{version}/download",
+        "cargo",
+        "Download a crate",
+        Token,
+    )
+    .response(BINARY, None),
+    endpoint(
+        Delete,
+        "/cargo/api/v1/crates/{crate}/{version}/yank",
+        "cargo",
+        "Yank a version",
+        Token,
+    ),
+    endpoint(
+        Put,
+        "/cargo/api/v1/crates/{crate}/{version}/unyank",
+        "cargo",
+        "Unyank a version",
+        Token,
+    ),
+    endpoint(
+        Get,
+        "/cargo/api/v1/crates/{crate}/owners",
+        "cargo",
+        "Owners of a crate (cargo owner --list)",
+        Token,
+    ),
+    endpoint(
+        Put,
+        "/cargo/api/v1/crates/{crate}/owners",
+        "cargo",
+        "Add crate owners (cargo owner --add)",
+        Token,
+    )
+    .request(JSON, Some("OwnersRequest")),
+    endpoint(
+        Delete,
+        "/cargo/api/v1/crates/{crate}/owners",
+        "cargo",
+        "Remove crate owners (cargo owner --remove)",
+        Token,
+    )
+    .request(JSON, Some("OwnersRequest")),
+    endpoint(Get, "/cargo/{*path}", "cargo", "Sparse index file", Token).response(TEXT, None),
+    endpoint(
+        Delete,
+        "/api/cargo/crate/{crate}",
+        "cargo",
+        "Delete every version of a crate",
+        Token,
+    )
+    .query(&["force"]),
+    endpoint(
+        Delete,
+        "/api/cargo/{crate}/{version}",
+        "cargo",
+        "Delete a crate version",
+        Token,
+    )
+    .query(&["force"]),
+    // Go
+    endpoint(
+        Get,
+        "/go/{*path}",
+        "go",
+        "GOPROXY protocol file (list, info, mod, zip)",
+        Public,
+    )
+    .response(BINARY, None),
+    endpoint(
+        Put,
+        "/go/{*path}",
+        "go",
+        "Publish a private module file",
+        Token,
+    )
+    .request(BINARY, None),
+    // Helm
+    endpoint(
+        Get,
+        "/helm/index.yaml",
+        "helm",
+        "Chart repository index",
+        Public,
+    )
+    .response("application/x-yaml", None),
+    endpoint(
+        Get,
+        "/helm/charts/{filename}",
+        "helm",
+        "Download a chart archive",
+        Public,
+    )
+    .response("application/gzip", None),
+    endpoint(
+        Post,
+        "/helm/api/charts",
+        "helm",
+        "Upload a chart (helm cm-push)",
+        Token,
+    )
+    .request("application/gzip", None),
+    // Presets
+    endpoint(
+        Get,
+        "/presets/{org}/{name}",
+        "presets",
+        "Published versions of a preset",
+        Public,
+    ),
+    endpoint(
+        Get,
+        "/presets/{org}/{name}/{version}",
+        "presets",
+        "Download a preset",
+        Public,
+    )
+    .response("application/x-yaml", None),
+    endpoint(
+        Put,
+        "/presets/{org}/{name}/{version}",
+        "presets",
+        "Publish a preset",
+        Token,
+    )
+    .request("application/x-yaml", None),
+];
+
+/// `{*rest}` as OpenAPI writes it
+fn openapi_path(path: &str) -> String {
+    path.replace("{*", "{")
+}
+
+fn path_parameters(path: &str) -> impl Iterator<Item = &str> {
+    path.split('{')
+        .skip(1)
+        .filter_map(|part| part.split_once('}'))
+        .map(|(name, _)| name.trim_start_matches('*'))
+}
+
+/// Adds [`ENDPOINTS`] and the bearer scheme to the derived document
+struct Endpoints;
+
+impl Modify for Endpoints {
+    fn modify(&self, openapi: &mut OpenApiDoc) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "api_key",
+            SecurityScheme::Http(
+                HttpBuilder::new()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .description(Some(
+                        "An admin key from security.api_keys, or a token issued by /api/tokens",
+                    ))
+                    .build(),
+            ),
+        );
+
+        for endpoint in ENDPOINTS {
+            let mut operation = OperationBuilder::new()
+                .tag(endpoint.tag)
+                .summary(Some(endpoint.summary));
+            for name in path_parameters(endpoint.path) {
+                operation = operation.parameter(
+                    ParameterBuilder::new()
+                        .name(name)
+                        .parameter_in(ParameterIn::Path)
+                        .required(Required::True)
+                        .schema(Some(ObjectBuilder::new().schema_type(Type::String))),
+                );
+            }
+            for name in endpoint.query {
+                operation = operation.parameter(
+                    ParameterBuilder::new()
+                        .name(*name)
+                        .parameter_in(ParameterIn::Query)
+                        .required(Required::False)
+                        .schema(Some(ObjectBuilder::new().schema_type(Type::String))),
+                );
+            }
+            if let Some((content_type, schema)) = endpoint.request {
+                operation = operation.request_body(Some(
+                    RequestBodyBuilder::new()
+                        .content(content_type, content(schema))
+                        .required(Some(Required::True))
+                        .build(),
+                ));
+            }
+
+            let (content_type, schema) = endpoint.response;
+            operation = operation
+                .response(
+                    "200",
+                    ResponseBuilder::new()
+                        .description("Success")
+                        .content(content_type, content(schema)),
+                )
+                .response(
+                    "default",
+                    ResponseBuilder::new()
+                        .description("Error")
+                        .content(JSON, content(Some("ApiErrorResponse"))),
+                );
+            if endpoint.access != Access::Public {
+                operation = operation
+                    .description(Some(match endpoint.access {
+                        Access::Admin => "Needs an admin API key when authentication is required.",
+                        _ => "Needs an admin API key or a token with the matching scope when authentication is required.",
+                    }))
+                    .security(SecurityRequirement::new("api_key", Vec::<String>::new()));
+            }
+
+            openapi.paths.add_path_operation(
+                openapi_path(endpoint.path),
+                vec![endpoint.method.clone()],
+                operation,
+            );
+        }
+    }
+}
+
+fn content(schema: Option<&str>) -> utoipa::openapi::Content {
+    ContentBuilder::new()
+        .schema(schema.map(Ref::from_schema_name))
+        .build()
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "vm package server",
+        description = "Private PyPI, npm, Cargo, Go, Helm and preset registry with upstream caching"
+    ),
+    components(schemas(
+        crate::SuccessResponse,
+        crate::error::ApiErrorResponse,
+        crate::provenance::ProvenanceResponse,
+        crate::provenance::ServedArtifact,
+        crate::badges::LatestVersion,
+        crate::package_edits::EditRequest,
+        crate::package_edits::PackageEdits,
+        crate::tokens::CreateTokenRequest,
+        crate::tokens::TokenScope,
+        crate::cargo::OwnersRequest,
+        crate::backup::RestoreReport,
+        crate::tenants::TenantSummary,
+    )),
+    modifiers(&Endpoints)
+)]
+pub struct ApiDoc;
+
+/// Swagger UI at [`DOCS_ROUTE`] and the document at [`SPEC_ROUTE`]
+pub fn docs_router() -> axum::Router {
+    utoipa_swagger_ui::SwaggerUi::new(DOCS_ROUTE)
+        .url(SPEC_ROUTE, ApiDoc::openapi())
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use regex::Regex;
+
+    #[test]
+    fn test_every_server_route_is_documented() {
+        let documented: Vec<&str> = ENDPOINTS.iter().map(|endpoint| endpoint.path).collect();
+        let route = Regex::new(r#"\.route\(\s*"([^"]+)""#).unwrap();
+        let source = include_str!("server.rs");
+        let registered: Vec<&str> = route
+            .captures_iter(source)
+            .map(|captures| captures.get(1).unwrap().as_str())
+            .collect();
+        assert!(registered.len() > 40, "routes should be found in server.rs");
+        for path in registered {
+            assert!(
+                documented.contains(&path),
+                "{path} is missing from ENDPOINTS"
+            );
+        }
+    }
+
+    #[test]
+    fn test_spec_references_known_schemas() {
+        let spec = serde_json::to_value(ApiDoc::openapi()).unwrap();
+        let schemas = spec["components"]["schemas"].as_object().unwrap();
+        for endpoint in ENDPOINTS {
+            for name in [endpoint.request.and_then(|(_, s)| s), endpoint.response.1]
+                .into_iter()
+                .flatten()
+            {
+                assert!(schemas.contains_key(name), "unknown schema {name}");
+            }
+        }
+
+        let patch = &spec["paths"]["/api/metadata/{registry}/{package}"]["patch"];
+        assert_eq!(patch["parameters"][1]["name"], "package");
+        assert_eq!(patch["security"][0]["api_key"], serde_json::json!([]));
+        assert!(spec["paths"]["/npm/{package}"]["put"].is_object());
+        assert!(spec["paths"]["/npm/{package}"]["get"].is_object());
+    }
+}
@@ -0,0 +1,389 @@
+//! # Package metadata edits
+//!
+//! Publishers can change the description, homepage and deprecation notice of a
+//! package after it was published, without uploading a new version:
+//!
+//! ```text
+//! PATCH /api/metadata/npm/left-pad
+//! {"deprecated": "Use String.prototype.padStart instead"}
+//! ```
+//!
+//! Fields left out of the request are unchanged and an empty string clears one.
+//! Edits need an admin API key or a token with the `publish` scope for the
+//! registry when authentication is required, and only apply to packages
+//! published to this server (npm and PyPI).
+//!
+//! Edits are kept in `metadata-edits/{registry}/{package}.json` in the data
+//! directory, so they survive later publishes and also cover new versions:
+//!
+//! - npm metadata (`GET /npm/{package}`) carries the description and homepage,
+//!   and every version gets a `deprecated` message, which `npm install` prints
+//!   as a warning.
+//! - The PyPI simple index page of a deprecated project carries the
+//!   [PEP 792](https://peps.python.org/pep-0792/) `pypi:project-status` and
+//!   `pypi:project-status-reason` meta tags.
+
+use crate::tokens::TokenScope;
+use crate::{auth, storage, AppError, AppResult, AppState};
+use axum::extract::{Path as AxumPath, State};
+use axum::http::HeaderMap;
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tracing::info;
+
+/// Registries whose packages can be edited. Cargo index entries, Go module files
+/// and the Helm index are derived from the published archives, which an edit
+/// cannot change, so those registries are left out.
+const REGISTRIES: &[&str] = &["npm", "pypi"];
+
+/// Directory in the data directory holding the edits
+const EDITS_DIR: &str = "metadata-edits";
+
+/// Longest accepted description or deprecation message
+const MAX_TEXT_LENGTH: usize = 4096;
+
+/// Metadata changed after publishing
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct PackageEdits {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub homepage: Option<String>,
+    /// Why the package should no longer be used
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deprecated: Option<String>,
+}
+
+impl PackageEdits {
+    fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Body of a metadata edit; missing fields are unchanged, empty ones cleared
+#[derive(Debug, Default, Deserialize, utoipa::ToSchema)]
+pub struct EditRequest {
+    description: Option<String>,
+    homepage: Option<String>,
+    deprecated: Option<String>,
+}
+
+fn edits_path(data_dir: &Path, registry: &str, package: &str) -> PathBuf {
+    data_dir
+        .join(EDITS_DIR)
+        .join(registry)
+        .join(format!("{package}.json"))
+}
+
+/// The name edits are stored under
+fn stored_name(registry: &str, package: &str) -> String {
+    match registry {
+        "pypi" => crate::normalize_pypi_name(package),
+        _ => package.to_string(),
+    }
+}
+
+/// Edits recorded for `package`, if any
+pub async fn load(data_dir: &Path, registry: &str, package: &str) -> Option<PackageEdits> {
+    let path = edits_path(data_dir, registry, &stored_name(registry, package));
+    let content = storage::read_file_string(&path).await.ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+/// Overlay `edits` on an npm packument
+pub fn apply_npm(metadata: &mut Value, edits: &PackageEdits) {
+    if let Some(description) = &edits.description {
+        metadata["description"] = json!(description);
+    }
+    if let Some(homepage) = &edits.homepage {
+        metadata["homepage"] = json!(homepage);
+    }
+    if let Some(deprecated) = &edits.deprecated {
+        if let Some(versions) = metadata["versions"].as_object_mut() {
+            for version_data in versions.values_mut().filter(|v| v.is_object()) {
+                version_data["deprecated"] = json!(deprecated);
+            }
+        }
+    }
+}
+
+/// PEP 792 status meta tags for a PyPI simple index page
+pub fn pypi_status_tags(edits: &PackageEdits) -> String {
+    match &edits.deprecated {
+        Some(reason) => format!(
+            "\n    <meta name=\"pypi:project-status\" content=\"deprecated\">\n    <meta name=\"pypi:project-status-reason\" content=\"{}\">\n  ",
+            escape_attribute(reason)
+        ),
+        None => String::new(),
+    }
+}
+
+fn escape_attribute(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('"', "&quot;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+fn check_registry(registry: &str) -> AppResult<()> {
+    if REGISTRIES.contains(&registry) {
+        Ok(())
+    } else {
+        Err(AppError::BadRequest(format!(
+            "Unknown registry '{registry}'; expected one of {}",
+            REGISTRIES.join(", ")
+        )))
+    }
+}
+
+/// Only packages published here can be edited
+async fn check_published(state: &AppState, registry: &str, package: &str) -> AppResult<()> {
+    let published = match registry {
+        "npm" => {
+            !package
+                .split('/')
+                .any(|part| part.is_empty() || part == "..")
+                && !package.contains('\\')
+                && state
+                    .data_dir
+                    .join("npm/metadata")
+                    .join(format!("{package}.json"))
+                    .is_file()
+        }
+        _ => {
+            let registry = registry.to_string();
+            state
+                .package_index
+                .run(move |index| index.list(&registry))
+                .await?
+                .iter()
+                .any(|name| name == package)
+        }
+    };
+    if published {
+        Ok(())
+    } else {
+        Err(AppError::NotFound(format!(
+            "No {registry} package '{package}' was published to this server"
+        )))
+    }
+}
+
+/// Apply `request` to `edits`, validating the new values
+fn merge(mut edits: PackageEdits, request: EditRequest) -> AppResult<PackageEdits> {
+    fn update(field: &mut Option<String>, value: Option<String>, name: &str) -> AppResult<()> {
+        let Some(value) = value else {
+            return Ok(());
+        };
+        let value = value.trim();
+        if value.len() > MAX_TEXT_LENGTH {
+            return Err(AppError::BadRequest(format!(
+                "'{name}' must be at most {MAX_TEXT_LENGTH} bytes"
+            )));
+        }
+        *field = (!value.is_empty()).then(|| value.to_string());
+        Ok(())
+    }
+
+    update(&mut edits.description, request.description, "description")?;
+    update(&mut edits.homepage, request.homepage, "homepage")?;
+    update(&mut edits.deprecated, request.deprecated, "deprecated")?;
+    if let Some(homepage) = &edits.homepage {
+        let is_web_url = homepage.starts_with("https://") || homepage.starts_with("http://");
+        if !is_web_url || homepage.chars().any(char::is_whitespace) {
+            return Err(AppError::BadRequest(
+                "'homepage' must be an http(s) URL".to_string(),
+            ));
+        }
+    }
+    Ok(edits)
+}
+
+/// Metadata edits recorded for a package.
+///
+/// # Route
+/// `GET /api/metadata/{registry}/{package}`
+pub async fn get_edits(
+    AxumPath((registry, package)): AxumPath<(String, String)>,
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> AppResult<Json<Value>> {
+    check_registry(&registry)?;
+    if registry == "npm" {
+        auth::check_npm_scope(
+            &state.config.load(),
+            &state.tokens,
+            &headers,
+            &package,
+            TokenScope::Read,
+        )?;
+    }
+    let name = stored_name(&registry, &package);
+    check_published(&state, &registry, &name).await?;
+    let edits = load(&state.data_dir, &registry, &name)
+        .await
+        .unwrap_or_default();
+    Ok(Json(
+        json!({ "registry": registry, "package": name, "metadata": edits }),
+    ))
+}
+
+/// Edits the description, homepage or deprecation notice of a published package.
+///
+/// # Route
+/// `PATCH /api/metadata/{registry}/{package}` with body
+/// `{"description": "...", "homepage": "https://...", "deprecated": "Use bar instead"}`
+pub async fn update_edits(
+    AxumPath((registry, package)): AxumPath<(String, String)>,
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(request): Json<EditRequest>,
+) -> AppResult<Json<Value>> {
+    check_registry(&registry)?;
+    if auth::is_auth_required(&state.config.load())
+        && !auth::is_allowed(&state, &headers, &registry, TokenScope::Publish)
+    {
+        return Err(AppError::Unauthorized(
+            "A token with the 'publish' scope is required".to_string(),
+        ));
+    }
+    if registry == "npm" {
+        auth::check_npm_scope(
+            &state.config.load(),
+            &state.tokens,
+            &headers,
+            &package,
+            TokenScope::Publish,
+        )?;
+    }
+    let name = stored_name(&registry, &package);
+    check_published(&state, &registry, &name).await?;
+
+    let current = load(&state.data_dir, &registry, &name)
+        .await
+        .unwrap_or_default();
+    let edits = merge(current, request)?;
+    let path = edits_path(&state.data_dir, &registry, &name);
+    if edits.is_empty() {
+        if path.exists() {
+            tokio::fs::remove_file(&path).await?;
+        }
+    } else {
+        storage::save_file(path, serde_json::to_string_pretty(&edits)?.as_bytes()).await?;
+    }
+
+    info!(
+        registry = %registry,
+        package = %name,
+        deprecated = edits.deprecated.is_some(),
+        "Updated package metadata"
+    );
+    Ok(Json(
+        json!({ "registry": registry, "package": name, "metadata": edits }),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(
+        description: Option<&str>,
+        homepage: Option<&str>,
+        deprecated: Option<&str>,
+    ) -> EditRequest {
+        EditRequest {
+            description: description.map(str::to_string),
+            homepage: homepage.map(str::to_string),
+            deprecated: deprecated.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_merge_keeps_missing_fields_and_clears_empty_ones() {
+        let edits = merge(
+            PackageEdits::default(),
+            request(
+                Some("Pads strings"),
+                Some("https://example.com"),
+                Some("Use padStart"),
+            ),
+        )
+        .unwrap();
+        let edits = merge(edits, request(None, Some(""), None)).unwrap();
+        assert_eq!(edits.description.as_deref(), Some("Pads strings"));
+        assert_eq!(edits.homepage, None);
+        assert_eq!(edits.deprecated.as_deref(), Some("Use padStart"));
+
+        assert!(merge(
+            PackageEdits::default(),
+            request(None, Some("javascript:alert(1)"), None)
+        )
+        .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_edits_surface_in_npm_metadata_and_pypi_index() {
+        let (state, _dir) = crate::test_utils::create_npm_test_state();
+        let packument = json!({
+            "name": "left-pad",
+            "dist-tags": { "latest": "1.1.0" },
+            "versions": { "1.0.0": { "version": "1.0.0" }, "1.1.0": { "version": "1.1.0" } }
+        });
+        std::fs::write(
+            state.data_dir.join("npm/metadata/left-pad.json"),
+            packument.to_string(),
+        )
+        .unwrap();
+
+        let Json(body) = update_edits(
+            AxumPath(("npm".to_string(), "left-pad".to_string())),
+            State(state.clone()),
+            HeaderMap::new(),
+            Json(request(
+                Some("Pads strings"),
+                None,
+                Some("Use \"padStart\""),
+            )),
+        )
+        .await
+        .unwrap();
+        assert_eq!(body["metadata"]["deprecated"], "Use \"padStart\"");
+
+        let Json(served) = crate::npm::package_metadata(
+            AxumPath("left-pad".to_string()),
+            State(state.clone()),
+            HeaderMap::new(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(served["description"], "Pads strings");
+        assert_eq!(
+            served["versions"]["1.0.0"]["deprecated"],
+            "Use \"padStart\""
+        );
+        assert_eq!(
+            served["versions"]["1.1.0"]["deprecated"],
+            "Use \"padStart\""
+        );
+
+        let missing = update_edits(
+            AxumPath(("npm".to_string(), "right-pad".to_string())),
+            State(state.clone()),
+            HeaderMap::new(),
+            Json(request(None, None, Some("gone"))),
+        )
+        .await;
+        assert!(matches!(missing, Err(AppError::NotFound(_))));
+
+        let edits = load(&state.data_dir, "npm", "left-pad").await.unwrap();
+        let tags = pypi_status_tags(&edits);
+        assert!(tags.contains(r#"content="deprecated""#));
+        assert!(tags.contains("Use &quot;padStart&quot;"));
+        assert!(pypi_status_tags(&PackageEdits::default()).is_empty());
+    }
+}
@@ -0,0 +1,786 @@
+//! # Package Metadata Index
+//!
+//! An embedded SQLite database (`index.db` in the data directory) recording every
+//! package version the server holds, so dashboard counts, package listings and
+//! recent packages are single queries instead of directory walks.
+//!
+//! Uploads and deletions update the index as they happen. Each registry also stores
+//! a fingerprint of the directory trees it is built from (the newest modification
+//! time and the number of entries, nested directories included): when files are
+//! added, changed or removed outside the server (the `vm pkg` CLI writing straight
+//! to the data directory, a restored backup), the fingerprint stops matching and that
+//! registry is rebuilt from disk on its next query. `POST /api/index/rebuild` rebuilds
+//! everything on demand.
+//!
+//! | Registry | Rows come from                                                     | Version recorded        |
+//! |----------|--------------------------------------------------------------------|-------------------------|
+//! | `pypi`   | `.whl`/`.tar.gz` files in `pypi/packages/`                         | Version in the filename |
+//! | `npm`    | `.json` documents in `npm/metadata/` and its `@scope/` directories | The `latest` dist-tag   |
+//! | `cargo`  | Sparse index files under `cargo/index/`                            | Every `vers` line       |
+//!
+//! The `served` table holds the download audit trail kept by
+//! [`crate::provenance`]. It is never rebuilt from disk.
+//!
+//! Queries take a lock on the connection and may rebuild a registry, so async
+//! handlers call the index through [`PackageIndex::run`], which runs them on the
+//! blocking thread pool.
+
+use crate::error::{AppError, AppResult};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::{debug, info};
+
+/// Database file name inside the data directory
+pub const INDEX_FILE: &str = "index.db";
+
+/// Registries tracked by the index and the directories each is rebuilt from
+const SOURCES: &[(&str, &[&str])] = &[
+    ("pypi", &["pypi/packages"]),
+    ("npm", &["npm/metadata"]),
+    ("cargo", &["cargo/index", "cargo/crates"]),
+];
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS packages (
+        registry TEXT NOT NULL,
+        name     TEXT NOT NULL,
+        version  TEXT NOT NULL,
+        modified INTEGER NOT NULL,
+        PRIMARY KEY (registry, name, version)
+    );
+    CREATE INDEX IF NOT EXISTS packages_by_modified ON packages (registry, modified);
+    CREATE TABLE IF NOT EXISTS fingerprints (
+        registry    TEXT PRIMARY KEY,
+        fingerprint TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS served (
+        registry     TEXT NOT NULL,
+        name         TEXT NOT NULL,
+        version      TEXT NOT NULL,
+        filename     TEXT NOT NULL,
+        source       TEXT NOT NULL,
+        downloads    INTEGER NOT NULL,
+        first_served INTEGER NOT NULL,
+        last_served  INTEGER NOT NULL,
+        PRIMARY KEY (registry, name, filename, source)
+    );
+";
+
+/// A package version found on disk
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexedVersion {
+    pub name: String,
+    /// Empty when the version is unknown (e.g. npm metadata without a `latest` tag)
+    pub version: String,
+    /// Modification time in nanoseconds since the Unix epoch
+    pub modified: i64,
+}
+
+/// An artifact the server has served, recorded by audit mode
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServedFile {
+    pub version: String,
+    pub filename: String,
+    /// `local` or `upstream`
+    pub source: String,
+    pub downloads: u64,
+    /// First and last download in seconds since the Unix epoch
+    pub first_served: i64,
+    pub last_served: i64,
+}
+
+/// A package as shown in the web UI listing
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackageSummary {
+    pub name: String,
+    /// Newest modification time of any version, in nanoseconds since the Unix epoch
+    pub modified: i64,
+    /// Downloads recorded by audit mode (zero when it is off)
+    pub downloads: u64,
+}
+
+/// SQLite-backed index of package names and versions per registry
+pub struct PackageIndex {
+    data_dir: PathBuf,
+    conn: Mutex<Connection>,
+}
+
+impl PackageIndex {
+    /// Open (or create) the index in `data_dir`
+    pub fn open(data_dir: &Path) -> AppResult<Self> {
+        std::fs::create_dir_all(data_dir)?;
+        let conn = Connection::open(data_dir.join(INDEX_FILE)).map_err(index_error)?;
+        conn.execute_batch(SCHEMA).map_err(index_error)?;
+        Ok(Self {
+            data_dir: data_dir.to_path_buf(),
+            conn: Mutex::new(conn),
+        })
+    }
+
+    /// Run `query` against the index on the blocking thread pool
+    pub async fn run<T, F>(self: &Arc<Self>, query: F) -> AppResult<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&PackageIndex) -> AppResult<T> + Send + 'static,
+    {
+        let index = Arc::clone(self);
+        tokio::task::spawn_blocking(move || query(&index))
+            .await
+            .map_err(|e| AppError::InternalError(format!("Package index task failed: {e}")))?
+    }
+
+    /// Number of unique packages in `registry`
+    pub fn count(&self, registry: &str) -> AppResult<usize> {
+        let conn = self.fresh(registry)?;
+        let count: i64 = conn
+            .query_row(
+                "SELECT COUNT(DISTINCT name) FROM packages WHERE registry = ?1",
+                params![registry],
+                |row| row.get(0),
+            )
+            .map_err(index_error)?;
+        Ok(count as usize)
+    }
+
+    /// Package names in `registry`, sorted alphabetically
+    pub fn list(&self, registry: &str) -> AppResult<Vec<String>> {
+        let conn = self.fresh(registry)?;
+        let mut stmt = conn
+            .prepare("SELECT DISTINCT name FROM packages WHERE registry = ?1 ORDER BY name")
+            .map_err(index_error)?;
+        let names = stmt
+            .query_map(params![registry], |row| row.get(0))
+            .map_err(index_error)?
+            .collect::<Result<Vec<String>, _>>()
+            .map_err(index_error)?;
+        Ok(names)
+    }
+
+    /// Every package in `registry` with its last modification and download count, by name
+    pub fn summaries(&self, registry: &str) -> AppResult<Vec<PackageSummary>> {
+        let conn = self.fresh(registry)?;
+        let mut stmt = conn
+            .prepare(
+                "SELECT p.name, MAX(p.modified), COALESCE(s.downloads, 0) FROM packages p
+                 LEFT JOIN (SELECT name, SUM(downloads) AS downloads FROM served
+                            WHERE registry = ?1 GROUP BY name) s ON s.name = p.name
+                 WHERE p.registry = ?1 GROUP BY p.name ORDER BY p.name",
+            )
+            .map_err(index_error)?;
+        let summaries = stmt
+            .query_map(params![registry], |row| {
+                Ok(PackageSummary {
+                    name: row.get(0)?,
+                    modified: row.get(1)?,
+                    downloads: row.get::<_, i64>(2)? as u64,
+                })
+            })
+            .map_err(index_error)?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(index_error)?;
+        Ok(summaries)
+    }
+
+    /// Downloads recorded by audit mode per package name in `registry`
+    pub fn download_counts(&self, registry: &str) -> AppResult<HashMap<String, u64>> {
+        let conn = self.lock();
+        let mut stmt = conn
+            .prepare("SELECT name, SUM(downloads) FROM served WHERE registry = ?1 GROUP BY name")
+            .map_err(index_error)?;
+        let counts = stmt
+            .query_map(params![registry], |row| {
+                Ok((row.get(0)?, row.get::<_, i64>(1)? as u64))
+            })
+            .map_err(index_error)?
+            .collect::<Result<HashMap<String, u64>, _>>()
+            .map_err(index_error)?;
+        Ok(counts)
+    }
+
+    /// The `limit` most recently modified packages as `(name, version)`, newest first
+    pub fn recent(&self, registry: &str, limit: usize) -> AppResult<Vec<(String, String)>> {
+        let conn = self.fresh(registry)?;
+        // SQLite takes the bare `version` column from the row holding MAX(modified)
+        let mut stmt = conn
+            .prepare(
+                "SELECT name, version, MAX(modified) AS latest FROM packages
+                 WHERE registry = ?1 AND version != ''
+                 GROUP BY name ORDER BY latest DESC, name LIMIT ?2",
+            )
+            .map_err(index_error)?;
+        let recent = stmt
+            .query_map(params![registry, limit as i64], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .map_err(index_error)?
+            .collect::<Result<Vec<(String, String)>, _>>()
+            .map_err(index_error)?;
+        Ok(recent)
+    }
+
+    /// Record an uploaded version
+    pub fn record(&self, registry: &str, name: &str, version: &str) -> AppResult<()> {
+        let conn = self.lock();
+        conn.execute(
+            "INSERT OR REPLACE INTO packages (registry, name, version, modified)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![registry, name, version, now_nanos()],
+        )
+        .map_err(index_error)?;
+        self.store_fingerprint(&conn, registry)
+    }
+
+    /// Replace every row of a package with a single version (npm tracks only `latest`)
+    pub fn record_latest(&self, registry: &str, name: &str, version: &str) -> AppResult<()> {
+        self.lock()
+            .execute(
+                "DELETE FROM packages WHERE registry = ?1 AND name = ?2",
+                params![registry, name],
+            )
+            .map_err(index_error)?;
+        self.record(registry, name, version)
+    }
+
+    /// Drop one version of a package, or the whole package when `version` is `None`
+    pub fn remove(&self, registry: &str, name: &str, version: Option<&str>) -> AppResult<()> {
+        let conn = self.lock();
+        match version {
+            Some(version) => conn.execute(
+                "DELETE FROM packages WHERE registry = ?1 AND name = ?2 AND version = ?3",
+                params![registry, name, version],
+            ),
+            None => conn.execute(
+                "DELETE FROM packages WHERE registry = ?1 AND name = ?2",
+                params![registry, name],
+            ),
+        }
+        .map_err(index_error)?;
+        self.store_fingerprint(&conn, registry)
+    }
+
+    /// Count a download of `filename` from `source` (see [`crate::provenance`])
+    pub fn record_served(
+        &self,
+        registry: &str,
+        name: &str,
+        version: &str,
+        filename: &str,
+        source: &str,
+    ) -> AppResult<()> {
+        let now = now_nanos() / 1_000_000_000;
+        self.lock()
+            .execute(
+                "INSERT INTO served
+                 (registry, name, version, filename, source, downloads, first_served, last_served)
+                 VALUES (?1, ?2, ?3, ?4, ?5, 1, ?6, ?6)
+                 ON CONFLICT (registry, name, filename, source)
+                 DO UPDATE SET downloads = downloads + 1, last_served = excluded.last_served",
+                params![registry, name, version, filename, source, now],
+            )
+            .map_err(index_error)?;
+        Ok(())
+    }
+
+    /// Files of a package served so far, by version then filename
+    pub fn served(&self, registry: &str, name: &str) -> AppResult<Vec<ServedFile>> {
+        let conn = self.lock();
+        let mut stmt = conn
+            .prepare(
+                "SELECT version, filename, source, downloads, first_served, last_served
+                 FROM served WHERE registry = ?1 AND name = ?2
+                 ORDER BY version, filename, source",
+            )
+            .map_err(index_error)?;
+        let files = stmt
+            .query_map(params![registry, name], |row| {
+                Ok(ServedFile {
+                    version: row.get(0)?,
+                    filename: row.get(1)?,
+                    source: row.get(2)?,
+                    downloads: row.get::<_, i64>(3)? as u64,
+                    first_served: row.get(4)?,
+                    last_served: row.get(5)?,
+                })
+            })
+            .map_err(index_error)?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(index_error)?;
+        Ok(files)
+    }
+
+    /// Whether `filename` has ever been served from `source`
+    pub fn was_served_from(
+        &self,
+        registry: &str,
+        name: &str,
+        filename: &str,
+        source: &str,
+    ) -> AppResult<bool> {
+        let found = self
+            .lock()
+            .query_row(
+                "SELECT 1 FROM served
+                 WHERE registry = ?1 AND name = ?2 AND filename = ?3 AND source = ?4",
+                params![registry, name, filename, source],
+                |_| Ok(()),
+            )
+            .optional()
+            .map_err(index_error)?;
+        Ok(found.is_some())
+    }
+
+    /// Rebuild every registry from the files on disk, returning package counts
+    pub fn rebuild_all(&self) -> AppResult<Vec<(&'static str, usize)>> {
+        let mut conn = self.lock();
+        let mut counts = Vec::new();
+        for (registry, _) in SOURCES {
+            self.rebuild(&mut conn, registry)?;
+            let count: i64 = conn
+                .query_row(
+                    "SELECT COUNT(DISTINCT name) FROM packages WHERE registry = ?1",
+                    params![registry],
+                    |row| row.get(0),
+                )
+                .map_err(index_error)?;
+            counts.push((*registry, count as usize));
+        }
+        Ok(counts)
+    }
+
+    /// Write a consistent copy of the index to `path` (see [`crate::backup`])
+    pub fn snapshot(&self, path: &Path) -> AppResult<()> {
+        self.lock()
+            .execute("VACUUM INTO ?1", params![path.to_string_lossy()])
+            .map_err(index_error)?;
+        Ok(())
+    }
+
+    /// Take the download audit trail from a snapshot at `path`, then rebuild the
+    /// package rows from the files on disk
+    pub fn restore(&self, path: &Path) -> AppResult<()> {
+        {
+            let conn = self.lock();
+            conn.execute(
+                "ATTACH DATABASE ?1 AS restored",
+                params![path.to_string_lossy()],
+            )
+            .map_err(index_error)?;
+            let copied = conn.execute_batch(
+                "BEGIN;
+                 DELETE FROM served;
+                 INSERT INTO served SELECT * FROM restored.served;
+                 DELETE FROM fingerprints;
+                 COMMIT;",
+            );
+            if copied.is_err() {
+                let _ = conn.execute_batch("ROLLBACK");
+            }
+            conn.execute_batch("DETACH DATABASE restored")
+                .map_err(index_error)?;
+            copied.map_err(index_error)?;
+        }
+        self.rebuild_all()?;
+        Ok(())
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Connection> {
+        self.conn
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Lock the connection, rebuilding `registry` first if its files changed on disk
+    fn fresh(&self, registry: &str) -> AppResult<MutexGuard<'_, Connection>> {
+        let mut conn = self.lock();
+        let stored: Option<String> = conn
+            .query_row(
+                "SELECT fingerprint FROM fingerprints WHERE registry = ?1",
+                params![registry],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(index_error)?;
+        if stored.as_deref() != Some(self.fingerprint(registry).as_str()) {
+            self.rebuild(&mut conn, registry)?;
+        }
+        Ok(conn)
+    }
+
+    fn rebuild(&self, conn: &mut Connection, registry: &str) -> AppResult<()> {
+        let rows = match registry {
+            "pypi" => scan_pypi(&self.data_dir),
+            "npm" => scan_npm(&self.data_dir),
+            "cargo" => scan_cargo(&self.data_dir),
+            other => {
+                return Err(AppError::InternalError(format!(
+                    "Unknown registry in package index: {other}"
+                )))
+            }
+        };
+
+        let tx = conn.transaction().map_err(index_error)?;
+        tx.execute(
+            "DELETE FROM packages WHERE registry = ?1",
+            params![registry],
+        )
+        .map_err(index_error)?;
+        {
+            let mut insert = tx
+                .prepare(
+                    "INSERT INTO packages (registry, name, version, modified)
+                     VALUES (?1, ?2, ?3, ?4)
+                     ON CONFLICT (registry, name, version)
+                     DO UPDATE SET modified = MAX(modified, excluded.modified)",
+                )
+                .map_err(index_error)?;
+            for row in &rows {
+                insert
+                    .execute(params![registry, row.name, row.version, row.modified])
+                    .map_err(index_error)?;
+            }
+        }
+        self.store_fingerprint(&tx, registry)?;
+        tx.commit().map_err(index_error)?;
+
+        info!(registry = %registry, versions = rows.len(), "Rebuilt package index");
+        Ok(())
+    }
+
+    fn store_fingerprint(&self, conn: &Connection, registry: &str) -> AppResult<()> {
+        conn.execute(
+            "INSERT OR REPLACE INTO fingerprints (registry, fingerprint) VALUES (?1, ?2)",
+            params![registry, self.fingerprint(registry)],
+        )
+        .map_err(index_error)?;
+        Ok(())
+    }
+
+    /// Newest modification time and entry count of each directory tree a
+    /// registry is built from
+    fn fingerprint(&self, registry: &str) -> String {
+        let dirs = SOURCES
+            .iter()
+            .find(|(name, _)| *name == registry)
+            .map(|(_, dirs)| *dirs)
+            .unwrap_or_default();
+        dirs.iter()
+            .map(|dir| {
+                tree_fingerprint(&self.data_dir.join(dir)).map_or_else(
+                    || "-".to_string(),
+                    |(newest, entries)| format!("{newest}:{entries}"),
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+}
+
+fn index_error(e: rusqlite::Error) -> AppError {
+    AppError::InternalError(format!("Package index error: {e}"))
+}
+
+fn now_nanos() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_nanos() as i64)
+}
+
+fn modified_nanos(path: &Path) -> Option<i64> {
+    let modified = std::fs::metadata(path).ok()?.modified().ok()?;
+    Some(modified.duration_since(UNIX_EPOCH).ok()?.as_nanos() as i64)
+}
+
+/// Newest modification time of `dir` or anything below it, and the number of
+/// entries below it.
+///
+/// Nested directories matter: cargo's sparse index is sharded into
+/// subdirectories and scoped npm packages live in `@scope/`, so changes there
+/// don't touch the top-level directory.
+fn tree_fingerprint(dir: &Path) -> Option<(i64, usize)> {
+    let mut newest = modified_nanos(dir)?;
+    let mut entries = 0;
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let Ok(read) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for path in read.flatten().map(|entry| entry.path()) {
+            entries += 1;
+            if let Some(modified) = modified_nanos(&path) {
+                newest = newest.max(modified);
+            }
+            if path.is_dir() {
+                pending.push(path);
+            }
+        }
+    }
+    Some((newest, entries))
+}
+
+/// Regular files directly inside `dir` with their names
+fn files_in(dir: &Path) -> Vec<(PathBuf, String)> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        debug!(dir = %dir.display(), "Directory does not exist");
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file())
+        .filter_map(|path| {
+            let name = path.file_name()?.to_str()?.to_string();
+            Some((path, name))
+        })
+        .collect()
+}
+
+fn scan_pypi(data_dir: &Path) -> Vec<IndexedVersion> {
+    files_in(&data_dir.join("pypi/packages"))
+        .into_iter()
+        .filter_map(|(path, filename)| {
+            let name = crate::utils::extract_pypi_package_name(&filename)?;
+            let version = crate::utils::extract_pypi_package_name_and_version(&filename)
+                .map(|(_, version)| version)
+                .unwrap_or_default();
+            Some(IndexedVersion {
+                name,
+                version,
+                modified: modified_nanos(&path).unwrap_or(0),
+            })
+        })
+        .collect()
+}
+
+fn scan_npm(data_dir: &Path) -> Vec<IndexedVersion> {
+    let metadata_dir = data_dir.join("npm/metadata");
+    let mut documents = files_in(&metadata_dir);
+    // Scoped packages are stored as `@scope/name.json`
+    let scopes = std::fs::read_dir(&metadata_dir)
+        .into_iter()
+        .flatten()
+        .flatten();
+    for scope in scopes
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+    {
+        let Some(scope_name) = scope.file_name().and_then(|n| n.to_str()) else {
+            continue;
+        };
+        if !scope_name.starts_with('@') {
+            continue;
+        }
+        documents.extend(
+            files_in(&scope)
+                .into_iter()
+                .map(|(path, filename)| (path, format!("{scope_name}/{filename}"))),
+        );
+    }
+    documents
+        .into_iter()
+        .filter_map(|(path, filename)| {
+            let name = filename.strip_suffix(".json")?.to_string();
+            let version = std::fs::read_to_string(&path)
+                .ok()
+                .and_then(|content| serde_json::from_str::<Value>(&content).ok())
+                .and_then(|metadata| metadata["dist-tags"]["latest"].as_str().map(str::to_string))
+                .unwrap_or_default();
+            Some(IndexedVersion {
+                name,
+                version,
+                modified: modified_nanos(&path).unwrap_or(0),
+            })
+        })
+        .collect()
+}
+
+fn scan_cargo(data_dir: &Path) -> Vec<IndexedVersion> {
+    let crates_dir = data_dir.join("cargo/crates");
+    let mut rows = Vec::new();
+    let mut pending = vec![data_dir.join("cargo/index")];
+
+    while let Some(dir) = pending.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for path in entries.flatten().map(|entry| entry.path()) {
+            if path.is_dir() {
+                pending.push(path);
+                continue;
+            }
+            let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+                continue;
+            };
+            if name == "config.json" {
+                continue;
+            }
+            let index_modified = modified_nanos(&path).unwrap_or(0);
+            let content = std::fs::read_to_string(&path).unwrap_or_default();
+            let versions: Vec<String> = content
+                .lines()
+                .filter_map(|line| serde_json::from_str::<Value>(line).ok())
+                .filter_map(|entry| entry["vers"].as_str().map(str::to_string))
+                .collect();
+
+            if versions.is_empty() {
+                rows.push(IndexedVersion {
+                    name: name.to_string(),
+                    version: String::new(),
+                    modified: index_modified,
+                });
+            }
+            // Index files are append-only, so a later line never predates an earlier one
+            // even when file timestamps are too coarse to tell them apart
+            let mut previous = i64::MIN;
+            for version in versions {
+                let crate_file = crates_dir.join(format!("{name}-{version}.crate"));
+                let modified = modified_nanos(&crate_file)
+                    .unwrap_or(index_modified)
+                    .max(previous.saturating_add(1));
+                previous = modified;
+                rows.push(IndexedVersion {
+                    name: name.to_string(),
+                    modified,
+                    version,
+                });
+            }
+        }
+    }
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn index_with_dirs() -> (PackageIndex, TempDir) {
+        let temp_dir = TempDir::new().unwrap();
+        for (_, dirs) in SOURCES {
+            for dir in *dirs {
+                std::fs::create_dir_all(temp_dir.path().join(dir)).unwrap();
+            }
+        }
+        let index = PackageIndex::open(temp_dir.path()).unwrap();
+        (index, temp_dir)
+    }
+
+    #[test]
+    fn rebuilds_when_files_change_on_disk() {
+        let (index, temp_dir) = index_with_dirs();
+        assert_eq!(index.count("pypi").unwrap(), 0);
+
+        let packages = temp_dir.path().join("pypi/packages");
+        std::fs::write(packages.join("requests-2.31.0-py3-none-any.whl"), b"x").unwrap();
+        std::fs::write(packages.join("requests-2.32.0.tar.gz"), b"x").unwrap();
+        std::fs::write(packages.join("Flask-3.0.0-py3-none-any.whl"), b"x").unwrap();
+
+        assert_eq!(index.count("pypi").unwrap(), 2);
+        assert_eq!(index.list("pypi").unwrap(), vec!["flask", "requests"]);
+    }
+
+    #[test]
+    fn rebuilds_when_nested_files_change() {
+        let (index, temp_dir) = index_with_dirs();
+        assert_eq!(index.count("npm").unwrap(), 0);
+
+        let scope_dir = temp_dir.path().join("npm/metadata/@acme");
+        std::fs::create_dir_all(&scope_dir).unwrap();
+        assert_eq!(index.count("npm").unwrap(), 0);
+        std::fs::write(
+            scope_dir.join("widget.json"),
+            r#"{"dist-tags": {"latest": "1.0.0"}}"#,
+        )
+        .unwrap();
+        assert_eq!(index.list("npm").unwrap(), vec!["@acme/widget"]);
+
+        let index_file = temp_dir.path().join("cargo/index/3/f/foo");
+        std::fs::create_dir_all(index_file.parent().unwrap()).unwrap();
+        std::fs::write(&index_file, "{\"name\":\"foo\",\"vers\":\"0.1.0\"}\n").unwrap();
+        assert_eq!(index.count("cargo").unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn run_queries_off_the_async_runtime() {
+        let (index, _temp_dir) = index_with_dirs();
+        let index = Arc::new(index);
+        index
+            .run(|index| index.record("cargo", "serde", "1.0.0"))
+            .await
+            .unwrap();
+        assert_eq!(index.run(|index| index.count("cargo")).await.unwrap(), 1);
+    }
+
+    #[test]
+    fn uploads_and_deletions_update_rows() {
+        let (index, _temp_dir) = index_with_dirs();
+
+        index.record("cargo", "serde", "1.0.0").unwrap();
+        index.record("cargo", "serde", "1.0.1").unwrap();
+        index.record("cargo", "anyhow", "1.0.0").unwrap();
+        assert_eq!(index.count("cargo").unwrap(), 2);
+        assert_eq!(
+            index.recent("cargo", 1).unwrap(),
+            vec![("anyhow".to_string(), "1.0.0".to_string())]
+        );
+
+        index.remove("cargo", "anyhow", None).unwrap();
+        assert_eq!(
+            index.recent("cargo", 5).unwrap(),
+            vec![("serde".to_string(), "1.0.1".to_string())]
+        );
+
+        index.record_latest("npm", "left-pad", "1.3.0").unwrap();
+        index.record_latest("npm", "left-pad", "1.4.0").unwrap();
+        assert_eq!(
+            index.recent("npm", 5).unwrap(),
+            vec![("left-pad".to_string(), "1.4.0".to_string())]
+        );
+    }
+
+    #[test]
+    fn summaries_include_audited_downloads() {
+        let (index, _temp_dir) = index_with_dirs();
+        index.record("cargo", "serde", "1.0.0").unwrap();
+        index.record("cargo", "anyhow", "1.0.0").unwrap();
+        for _ in 0..3 {
+            index
+                .record_served("cargo", "anyhow", "1.0.0", "anyhow-1.0.0.crate", "local")
+                .unwrap();
+        }
+        index
+            .record_served("cargo", "anyhow", "1.0.0", "anyhow-1.0.0.crate", "upstream")
+            .unwrap();
+
+        let summaries = index.summaries("cargo").unwrap();
+        let names: Vec<&str> = summaries.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec!["anyhow", "serde"]);
+        assert_eq!(summaries[0].downloads, 4);
+        assert_eq!(summaries[1].downloads, 0);
+        assert!(summaries[0].modified >= summaries[1].modified);
+        assert_eq!(index.download_counts("cargo").unwrap()["anyhow"], 4);
+    }
+
+    #[test]
+    fn cargo_versions_come_from_the_sparse_index() {
+        let (index, temp_dir) = index_with_dirs();
+        let index_file = temp_dir.path().join("cargo/index/3/f/foo");
+        std::fs::create_dir_all(index_file.parent().unwrap()).unwrap();
+        std::fs::write(
+            &index_file,
+            "{\"name\":\"foo\",\"vers\":\"0.1.0\"}\n{\"name\":\"foo\",\"vers\":\"0.2.0\"}\n",
+        )
+        .unwrap();
+        std::fs::write(temp_dir.path().join("cargo/crates/foo-0.2.0.crate"), b"x").unwrap();
+
+        let counts = index.rebuild_all().unwrap();
+        assert!(counts.contains(&("cargo", 1)));
+        assert_eq!(
+            index.recent("cargo", 5).unwrap(),
+            vec![("foo".to_string(), "0.2.0".to_string())]
+        );
+    }
+}
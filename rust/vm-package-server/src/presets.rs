@@ -0,0 +1,210 @@
+//! # Preset Registry
+//!
+//! Stores versioned vm box/provision presets so teams can distribute preset updates
+//! without a tool release. Presets are immutable once published: each version is a
+//! YAML document plus a SHA-256 checksum recorded in a per-preset index, which
+//! clients verify before caching.
+//!
+//! ## Routes
+//!
+//! - `PUT /presets/{org}/{name}/{version}` - publish a version (raw YAML body)
+//! - `GET /presets/{org}/{name}` - list versions with checksums
+//! - `GET /presets/{org}/{name}/{version}` - download a version
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path as AxumPath, State},
+    http::HeaderMap,
+    response::Json,
+};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, info};
+
+use crate::tokens::TokenScope;
+use crate::validation::MAX_METADATA_SIZE;
+use crate::{sha256_hash, storage, validation, AppError, AppResult, AppState, SuccessResponse};
+
+/// A published preset version
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PresetVersion {
+    pub sha256: String,
+    pub published_at: String,
+}
+
+/// Versions published for one preset
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PresetIndex {
+    pub name: String,
+    #[serde(default)]
+    pub versions: BTreeMap<String, PresetVersion>,
+}
+
+/// The highest published version, comparing dot-separated numeric components
+pub fn latest_version(index: &PresetIndex) -> Option<&str> {
+    fn key(version: &str) -> Vec<u64> {
+        version
+            .split(['.', '-', '+'])
+            .map(|part| part.parse().unwrap_or(0))
+            .collect()
+    }
+    index
+        .versions
+        .keys()
+        .max_by(|a, b| key(a).cmp(&key(b)))
+        .map(String::as_str)
+}
+
+fn validate_preset_ref(org: &str, name: &str, version: Option<&str>) -> AppResult<()> {
+    for (label, value) in [("organization", org), ("preset name", name)] {
+        validation::validate_package_name(value, "npm")
+            .map_err(|e| AppError::BadRequest(format!("Invalid {label} '{value}': {e}")))?;
+    }
+    if let Some(version) = version {
+        validation::validate_version(version)
+            .map_err(|e| AppError::BadRequest(format!("Invalid version '{version}': {e}")))?;
+    }
+    Ok(())
+}
+
+fn preset_dir(state: &AppState, org: &str, name: &str) -> PathBuf {
+    state.data_dir.join("presets").join(org).join(name)
+}
+
+async fn read_index(state: &AppState, org: &str, name: &str) -> AppResult<Option<PresetIndex>> {
+    let index_path = preset_dir(state, org, name).join("index.json");
+    match storage::read_file_string(&index_path).await {
+        Ok(content) => Ok(Some(serde_json::from_str(&content)?)),
+        Err(_) => Ok(None),
+    }
+}
+
+/// Lists the published versions of a preset and their checksums.
+///
+/// # Route
+/// `GET /presets/{org}/{name}`
+pub async fn preset_index(
+    AxumPath((org, name)): AxumPath<(String, String)>,
+    State(state): State<Arc<AppState>>,
+) -> AppResult<Json<PresetIndex>> {
+    validate_preset_ref(&org, &name, None)?;
+    debug!(org = %org, name = %name, "Incoming preset index request");
+
+    read_index(&state, &org, &name)
+        .await?
+        .map(Json)
+        .ok_or_else(|| AppError::NotFound(format!("Preset not found: {org}/{name}")))
+}
+
+/// Downloads the YAML document for a published preset version.
+///
+/// # Route
+/// `GET /presets/{org}/{name}/{version}`
+pub async fn download_preset(
+    AxumPath((org, name, version)): AxumPath<(String, String, String)>,
+    State(state): State<Arc<AppState>>,
+) -> AppResult<Vec<u8>> {
+    validate_preset_ref(&org, &name, Some(&version))?;
+    debug!(org = %org, name = %name, version = %version, "Incoming preset download request");
+
+    let path = preset_dir(&state, &org, &name).join(format!("{version}.yaml"));
+    storage::read_file(&path)
+        .await
+        .map_err(|_| AppError::NotFound(format!("Preset not found: {org}/{name}@{version}")))
+}
+
+/// Publishes a new preset version.
+///
+/// The body must be a YAML mapping. Versions are immutable, so republishing an
+/// existing version is rejected.
+///
+/// # Route
+/// `PUT /presets/{org}/{name}/{version}`
+pub async fn publish_preset(
+    AxumPath((org, name, version)): AxumPath<(String, String, String)>,
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    body: axum::body::Bytes,
+) -> AppResult<Json<SuccessResponse>> {
+    if crate::auth::is_auth_required(&state.config.load())
+        && !crate::auth::is_allowed(&state, &headers, "presets", TokenScope::Publish)
+    {
+        return Err(AppError::Unauthorized(
+            "Missing or invalid API key".to_string(),
+        ));
+    }
+    validate_preset_ref(&org, &name, Some(&version))?;
+    if body.len() > MAX_METADATA_SIZE {
+        return Err(AppError::BadRequest(format!(
+            "Preset is too large ({} bytes, max {MAX_METADATA_SIZE})",
+            body.len()
+        )));
+    }
+    let content = std::str::from_utf8(&body)
+        .map_err(|_| AppError::BadRequest("Preset must be UTF-8 YAML".to_string()))?;
+    if !content.lines().any(|line| line.starts_with("preset:")) {
+        return Err(AppError::BadRequest(
+            "Preset must contain a top-level 'preset:' metadata section".to_string(),
+        ));
+    }
+
+    let mut index = read_index(&state, &org, &name)
+        .await?
+        .unwrap_or_else(|| PresetIndex {
+            name: format!("{org}/{name}"),
+            ..Default::default()
+        });
+    if index.versions.contains_key(&version) {
+        return Err(AppError::BadRequest(format!(
+            "Preset {org}/{name}@{version} is already published; bump the version"
+        )));
+    }
+
+    let dir = preset_dir(&state, &org, &name);
+    storage::save_file(dir.join(format!("{version}.yaml")), &body).await?;
+
+    let sha256 = sha256_hash(&body);
+    index.versions.insert(
+        version.clone(),
+        PresetVersion {
+            sha256: sha256.clone(),
+            published_at: chrono::Utc::now().to_rfc3339(),
+        },
+    );
+    storage::save_file(dir.join("index.json"), serde_json::to_vec_pretty(&index)?).await?;
+
+    info!(org = %org, name = %name, version = %version, checksum = %sha256, "Preset published");
+    Ok(Json(SuccessResponse {
+        message: format!("Preset {org}/{name}@{version} published"),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_latest_version_compares_numerically() {
+        let mut index = PresetIndex::default();
+        for version in ["1.2.0", "1.10.0", "1.9.3"] {
+            index.versions.insert(
+                version.to_string(),
+                PresetVersion {
+                    sha256: String::new(),
+                    published_at: String::new(),
+                },
+            );
+        }
+        assert_eq!(latest_version(&index), Some("1.10.0"));
+        assert_eq!(latest_version(&PresetIndex::default()), None);
+    }
+
+    #[test]
+    fn test_preset_refs_are_validated() {
+        assert!(validate_preset_ref("acme", "rails", Some("1.2.0")).is_ok());
+        assert!(validate_preset_ref("../etc", "rails", None).is_err());
+        assert!(validate_preset_ref("acme", "rails", Some("1.0/../x")).is_err());
+    }
+}
@@ -0,0 +1,298 @@
+//! # Package provenance
+//!
+//! Audit mode records, for every artifact the server serves, whether it was
+//! published to this server or fetched from an upstream registry. Teams use it
+//! to confirm that builds resolve internal packages locally and to find which
+//! public packages their machines actually pull in.
+//!
+//! Recording is off by default and enabled in `data/config.json`:
+//!
+//! ```json
+//! { "audit": { "provenance": true } }
+//! ```
+//!
+//! Each download bumps a counter in the `served` table of the package index, keyed
+//! by file and source. The records for one package are returned by
+//! `GET /api/provenance/{registry}/{package}` and listed on its page in the web UI.
+//!
+//! Go modules fetched from upstream are stored next to published ones, so a module
+//! zip counts as upstream only if audit mode saw it arrive from the Go proxy.
+
+use crate::auth;
+use crate::package_index::ServedFile;
+use crate::state::AppState;
+use crate::tokens::TokenScope;
+use crate::{AppError, AppResult};
+use axum::extract::{Path, State};
+use axum::http::HeaderMap;
+use axum::Json;
+use chrono::DateTime;
+use serde::Serialize;
+use std::sync::Arc;
+use tracing::warn;
+
+/// Registries whose downloads are recorded
+const REGISTRIES: &[&str] = &["pypi", "npm", "cargo", "go", "helm"];
+
+/// Where a served artifact came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Source {
+    /// Published to this server
+    Local,
+    /// Fetched from, or cached from, an upstream registry
+    Upstream,
+}
+
+impl Source {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Source::Local => "local",
+            Source::Upstream => "upstream",
+        }
+    }
+}
+
+/// A served file as reported by the API and the web UI
+#[derive(Debug, Clone, Serialize, utoipa::ToSchema)]
+pub struct ServedArtifact {
+    pub version: String,
+    pub filename: String,
+    pub source: String,
+    pub downloads: u64,
+    /// RFC 3339 timestamps
+    pub first_served: String,
+    pub last_served: String,
+}
+
+impl From<ServedFile> for ServedArtifact {
+    fn from(file: ServedFile) -> Self {
+        Self {
+            version: file.version,
+            filename: file.filename,
+            source: file.source,
+            downloads: file.downloads,
+            first_served: rfc3339(file.first_served),
+            last_served: rfc3339(file.last_served),
+        }
+    }
+}
+
+fn rfc3339(secs: i64) -> String {
+    DateTime::from_timestamp(secs, 0)
+        .map(|time| time.to_rfc3339())
+        .unwrap_or_default()
+}
+
+/// Response of `GET /api/provenance/{registry}/{package}`
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct ProvenanceResponse {
+    pub registry: String,
+    pub package: String,
+    /// Whether downloads are currently being recorded
+    pub audit_enabled: bool,
+    /// Downloads served from local uploads
+    pub local_downloads: u64,
+    /// Downloads served from upstream or the upstream cache
+    pub upstream_downloads: u64,
+    pub artifacts: Vec<ServedArtifact>,
+}
+
+/// Record that `filename` was served from `source`, when audit mode is on
+pub async fn record(
+    state: &AppState,
+    registry: &str,
+    package: &str,
+    version: &str,
+    filename: &str,
+    source: Source,
+) {
+    if !state.config.load().audit.provenance {
+        return;
+    }
+    let (registry, package, version, filename) = (
+        registry.to_string(),
+        recorded_name(registry, package),
+        version.to_string(),
+        filename.to_string(),
+    );
+    let recorded = state.package_index.run({
+        let (registry, filename) = (registry.clone(), filename.clone());
+        move |index| index.record_served(&registry, &package, &version, &filename, source.as_str())
+    });
+    if let Err(e) = recorded.await {
+        warn!(registry = %registry, filename = %filename, error = %e, "Failed to record package provenance");
+    }
+}
+
+/// Whether audit mode has seen `filename` arrive from upstream
+pub async fn fetched_upstream(
+    state: &AppState,
+    registry: &str,
+    package: &str,
+    filename: &str,
+) -> bool {
+    let (registry, package, filename) = (
+        registry.to_string(),
+        recorded_name(registry, package),
+        filename.to_string(),
+    );
+    state
+        .package_index
+        .run(move |index| {
+            index.was_served_from(&registry, &package, &filename, Source::Upstream.as_str())
+        })
+        .await
+        .unwrap_or(false)
+}
+
+/// Served files of a package, for the web UI
+pub async fn artifacts(state: &AppState, registry: &str, package: &str) -> Vec<ServedArtifact> {
+    let (recorded_registry, name) = (registry.to_string(), recorded_name(registry, package));
+    match state
+        .package_index
+        .run(move |index| index.served(&recorded_registry, &name))
+        .await
+    {
+        Ok(files) => files.into_iter().map(ServedArtifact::from).collect(),
+        Err(e) => {
+            warn!(registry = %registry, package = %package, error = %e, "Failed to load package provenance");
+            Vec::new()
+        }
+    }
+}
+
+/// Name downloads of `package` are recorded under
+fn recorded_name(registry: &str, package: &str) -> String {
+    match registry {
+        "pypi" => crate::normalize_pypi_name(package),
+        "cargo" => package.to_lowercase(),
+        _ => package.to_string(),
+    }
+}
+
+/// Where each served file of a package came from and how often it was downloaded
+///
+/// # Route
+/// `GET /api/provenance/{registry}/{package}`
+pub async fn provenance_handler(
+    Path((registry, package)): Path<(String, String)>,
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> AppResult<Json<ProvenanceResponse>> {
+    if !REGISTRIES.contains(&registry.as_str()) {
+        return Err(AppError::BadRequest(format!(
+            "Unknown registry '{registry}'; expected one of {}",
+            REGISTRIES.join(", ")
+        )));
+    }
+    if registry == "npm" {
+        auth::check_npm_scope(
+            &state.config.load(),
+            &state.tokens,
+            &headers,
+            &package,
+            TokenScope::Read,
+        )?;
+    }
+    let name = recorded_name(&registry, &package);
+    let artifacts: Vec<ServedArtifact> = state
+        .package_index
+        .run({
+            let (registry, name) = (registry.clone(), name.clone());
+            move |index| index.served(&registry, &name)
+        })
+        .await?
+        .into_iter()
+        .map(ServedArtifact::from)
+        .collect();
+    let downloads = |source: Source| {
+        artifacts
+            .iter()
+            .filter(|artifact| artifact.source == source.as_str())
+            .map(|artifact| artifact.downloads)
+            .sum()
+    };
+
+    Ok(Json(ProvenanceResponse {
+        local_downloads: downloads(Source::Local),
+        upstream_downloads: downloads(Source::Upstream),
+        audit_enabled: state.config.load().audit.provenance,
+        registry,
+        package: name,
+        artifacts,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{AuditConfig, Config};
+
+    fn audited(state: Arc<AppState>) -> Arc<AppState> {
+        let mut state = (*state).clone();
+        state.config = Arc::new(Config {
+            audit: AuditConfig { provenance: true },
+            ..Config::default()
+        })
+        .into();
+        Arc::new(state)
+    }
+
+    #[tokio::test]
+    async fn test_records_downloads_per_source() {
+        let (state, _dir) = crate::test_utils::create_pypi_test_state();
+        let state = audited(state);
+        let wheel = "my_pkg-1.0.0-py3-none-any.whl";
+        record(&state, "pypi", "my-pkg", "1.0.0", wheel, Source::Upstream).await;
+        record(&state, "pypi", "my-pkg", "1.0.0", wheel, Source::Upstream).await;
+        record(
+            &state,
+            "pypi",
+            "my-pkg",
+            "2.0.0",
+            "my_pkg-2.0.0.tar.gz",
+            Source::Local,
+        )
+        .await;
+
+        let Json(response) = provenance_handler(
+            Path(("pypi".to_string(), "My_Pkg".to_string())),
+            State(state.clone()),
+            HeaderMap::new(),
+        )
+        .await
+        .unwrap();
+        assert!(response.audit_enabled);
+        assert_eq!(response.package, "my-pkg");
+        assert_eq!(response.local_downloads, 1);
+        assert_eq!(response.upstream_downloads, 2);
+        assert_eq!(response.artifacts.len(), 2);
+        assert_eq!(response.artifacts[0].source, "upstream");
+        assert!(fetched_upstream(&state, "pypi", "my-pkg", wheel).await);
+        assert!(!fetched_upstream(&state, "pypi", "my-pkg", "my_pkg-2.0.0.tar.gz").await);
+    }
+
+    #[tokio::test]
+    async fn test_nothing_recorded_when_disabled() {
+        let (state, _dir) = crate::test_utils::create_npm_test_state();
+        record(
+            &state,
+            "npm",
+            "left-pad",
+            "1.0.0",
+            "left-pad-1.0.0.tgz",
+            Source::Local,
+        )
+        .await;
+        assert!(artifacts(&state, "npm", "left-pad").await.is_empty());
+
+        let result = provenance_handler(
+            Path(("maven".to_string(), "junit".to_string())),
+            State(state),
+            HeaderMap::new(),
+        )
+        .await;
+        assert!(matches!(result, Err(AppError::BadRequest(_))));
+    }
+}
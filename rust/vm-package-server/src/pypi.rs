@@ -3,10 +3,13 @@ use std::sync::Arc;
 
 use axum::{
     extract::{Multipart, Path as AxumPath, State},
+    http::HeaderMap,
     response::Html,
 };
 use tracing::{debug, info, warn};
 
+use crate::cache;
+use crate::provenance::Source;
 use crate::validation;
 use crate::validation_utils::FileStreamValidator;
 use crate::{
@@ -19,6 +22,33 @@ async fn list_package_files(pypi_dir: &Path) -> AppResult<Vec<PathBuf>> {
     package_utils::list_files_with_extensions(pypi_dir, &[".whl", ".tar.gz"]).await
 }
 
+/// Path of the PEP 658 core metadata file served next to a wheel
+fn core_metadata_path(wheel_path: &Path) -> PathBuf {
+    let mut path = wheel_path.as_os_str().to_owned();
+    path.push(".metadata");
+    PathBuf::from(path)
+}
+
+/// Core metadata of a locally stored wheel.
+///
+/// Wheels uploaded before metadata files were written get theirs extracted and
+/// saved on first use.
+async fn core_metadata(wheel_path: &Path) -> Option<Vec<u8>> {
+    let metadata_path = core_metadata_path(wheel_path);
+    if let Ok(metadata) = storage::read_file(&metadata_path).await {
+        return Some(metadata);
+    }
+    if !wheel_path.to_string_lossy().ends_with(".whl") {
+        return None;
+    }
+    let wheel = storage::read_file(wheel_path).await.ok()?;
+    let metadata = crate::pypi_utils::wheel_core_metadata(&wheel)?;
+    if let Err(e) = storage::save_file(&metadata_path, &metadata).await {
+        warn!(path = %metadata_path.display(), error = %e, "Failed to save core metadata");
+    }
+    Some(metadata)
+}
+
 /// Returns the PyPI simple package index as HTML.
 ///
 /// This endpoint implements the PEP 503 simple repository API, providing a list of all
@@ -77,7 +107,9 @@ pub async fn simple_index(State(state): State<Arc<AppState>>) -> AppResult<Html<
 ///
 /// This endpoint implements the PEP 503 simple repository API for individual packages,
 /// providing download links for all available versions with SHA256 hashes for integrity
-/// verification.
+/// verification. Wheel links carry the PEP 658 `data-dist-info-metadata` hash (and its
+/// PEP 714 `data-core-metadata` spelling), so pip can fetch `{file}.metadata` to resolve
+/// dependencies without downloading the wheel.
 ///
 /// # Route
 /// `GET /pypi/simple/{package}/`
@@ -95,7 +127,7 @@ pub async fn simple_index(State(state): State<Arc<AppState>>) -> AppResult<Html<
 ///   <head><title>Links for package-name</title></head>
 ///   <body>
 ///     <h1>Links for package-name</h1>
-///     <a href="../../packages/package_name-1.0.0-py3-none-any.whl#sha256=abcd...">package_name-1.0.0-py3-none-any.whl</a><br/>
+///     <a href="../../packages/package_name-1.0.0-py3-none-any.whl#sha256=abcd..." data-dist-info-metadata="sha256=ef01..." data-core-metadata="sha256=ef01...">package_name-1.0.0-py3-none-any.whl</a><br/>
 ///   </body>
 /// </html>
 /// ```
@@ -120,7 +152,9 @@ pub async fn package_index(
                     ));
                     match storage::read_file_string(&meta_path).await {
                         Ok(hash) => {
-                            files.push((name.to_string(), hash.trim().to_string()));
+                            let metadata_hash =
+                                core_metadata(&path).await.map(|data| sha256_hash(&data));
+                            files.push((name.to_string(), hash.trim().to_string(), metadata_hash));
                             debug!(filename = %name, hash = %hash.trim(), "Added file to package index from meta");
                         }
                         Err(_) => {
@@ -137,6 +171,7 @@ pub async fn package_index(
         debug!(package = %package, "No local files found, checking upstream PyPI");
         match state
             .upstream_client
+            .load()
             .fetch_pypi_simple(&normalized_package)
             .await
         {
@@ -144,24 +179,37 @@ pub async fn package_index(
                 info!(package = %package, "Found package on upstream PyPI, proxying response");
                 return Ok(Html(upstream_html));
             }
+            Err(e @ AppError::UpstreamBlocked(_)) => return Err(e),
             Err(_) => {
                 debug!(package = %package, "Package not found on upstream PyPI either");
             }
         }
     }
 
+    // PEP 792 status of a project deprecated through /api/metadata
+    let status_tags = crate::package_edits::load(&state.data_dir, "pypi", &normalized_package)
+        .await
+        .map(|edits| crate::package_edits::pypi_status_tags(&edits))
+        .unwrap_or_default();
     let mut html = format!(
         r#"<!DOCTYPE html>
 <html>
-  <head><title>Links for {package}</title></head>
+  <head><title>Links for {package}</title>{status_tags}</head>
   <body>
     <h1>Links for {package}</h1>
 "#
     );
 
-    for (filename, hash) in files {
+    for (filename, hash, metadata_hash) in files {
+        let metadata_attrs = metadata_hash
+            .map(|hash| {
+                format!(
+                    r#" data-dist-info-metadata="sha256={hash}" data-core-metadata="sha256={hash}""#
+                )
+            })
+            .unwrap_or_default();
         html.push_str(&format!(
-            r#"    <a href="{host}/pypi/packages/{filename}#sha256={hash}">{filename}</a><br/>"#
+            r#"    <a href="{host}/pypi/packages/{filename}#sha256={hash}"{metadata_attrs}>{filename}</a><br/>"#
         ));
     }
 
@@ -173,8 +221,9 @@ pub async fn package_index(
 
 /// Downloads a specific PyPI package file.
 ///
-/// Serves package files (.whl, .tar.gz) with fallback to upstream PyPI if the file
-/// is not found locally. Validates filename to prevent path traversal attacks.
+/// Serves package files (.whl, .tar.gz) and PEP 658 wheel metadata files
+/// (`.whl.metadata`) with fallback to upstream PyPI if the file is not found
+/// locally. Validates filename to prevent path traversal attacks.
 ///
 /// # Route
 /// `GET /pypi/packages/{filename}`
@@ -203,18 +252,51 @@ pub async fn download_file(
     info!(filename = %filename, "Downloading PyPI package file");
     let file_path = state.data_dir.join("pypi/packages").join(&filename);
 
+    let cache_key = crate::utils::extract_pypi_package_name_and_version(&filename)
+        .map(|(name, version)| (normalize_pypi_name(&name), version));
+    let (state_ref, cache_key_ref, filename_ref) = (&state, &cache_key, &filename);
+    let provenance = move |source| async move {
+        if let Some((name, version)) = cache_key_ref {
+            crate::provenance::record(state_ref, "pypi", name, version, filename_ref, source).await;
+        }
+    };
+
     // Try local file first
     match storage::read_file(&file_path).await {
         Ok(data) => {
             debug!(filename = %filename, size = data.len(), "Serving file from local storage");
+            provenance(Source::Local).await;
             Ok(data)
         }
         Err(_) => {
-            // File not found locally, try upstream PyPI
+            if let Some(wheel) = filename.strip_suffix(".metadata") {
+                if let Some(data) =
+                    core_metadata(&state.data_dir.join("pypi/packages").join(wheel)).await
+                {
+                    return Ok(data);
+                }
+            }
+            // File not found locally, try the upstream cache and then upstream PyPI
+            if let Some((name, version)) = &cache_key {
+                if let Some(data) = cache::read(&state, "pypi", name, version, &filename).await {
+                    debug!(filename = %filename, size = data.len(), "Serving file from upstream cache");
+                    provenance(Source::Upstream).await;
+                    return Ok(data);
+                }
+            }
             debug!(filename = %filename, "File not found locally, checking upstream PyPI");
-            match state.upstream_client.stream_pypi_file(&filename).await {
+            match state
+                .upstream_client
+                .load()
+                .stream_pypi_file(&filename)
+                .await
+            {
                 Ok(bytes) => {
                     info!(filename = %filename, size = bytes.len(), "Streaming file from upstream PyPI");
+                    if let Some((name, version)) = &cache_key {
+                        cache::store(&state, "pypi", name, version, &filename, &bytes).await;
+                    }
+                    provenance(Source::Upstream).await;
                     Ok(bytes.to_vec())
                 }
                 Err(e) => {
@@ -253,13 +335,17 @@ pub async fn download_file(
 /// ```
 pub async fn upload_package(
     State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
     mut multipart: Multipart,
 ) -> AppResult<axum::Json<SuccessResponse>> {
     info!("Processing PyPI package upload");
+    let scope = crate::auth::pypi_upload_scope(&state.config.load(), &state.tokens, &headers)?;
     let pypi_dir = state.data_dir.join("pypi/packages");
 
     let mut field_count = 0;
     let mut total_size = 0u64;
+    let mut upload = None;
+    let mut signature = None;
 
     while let Some(field) = multipart.next_field().await? {
         field_count += 1;
@@ -295,6 +381,14 @@ pub async fn upload_package(
                 ));
             }
 
+            let project = crate::utils::extract_pypi_package_name(&filename).unwrap_or_default();
+            if !scope.allows(&project) {
+                warn!(filename = %filename, project = %project, "Token not scoped for project");
+                return Err(AppError::Unauthorized(format!(
+                    "API token is not scoped for project '{project}'"
+                )));
+            }
+
             // Read the data with size constraints to prevent memory exhaustion
             let data = field.bytes().await?;
             debug!(size = data.len(), "Read package data");
@@ -310,27 +404,8 @@ pub async fn upload_package(
                 AppError::UploadError(format!("Multipart upload limits exceeded: {e}"))
             })?;
 
-            // Calculate hash once during upload
-            let hash = sha256_hash(&data);
-
-            // Save the file using storage utility
-            let file_path = pypi_dir.join(&filename);
-            storage::save_file(&file_path, &data).await?;
-
-            // Save the hash to a .meta file
-            let meta_path = file_path.with_extension(format!(
-                "{}.meta",
-                file_path
-                    .extension()
-                    .and_then(|ext| ext.to_str())
-                    .unwrap_or("")
-            ));
-            storage::save_file(meta_path, hash.as_bytes()).await?;
-
-            info!(filename = %filename, size = data.len(), "PyPI package uploaded successfully");
-            return Ok(axum::Json(SuccessResponse {
-                message: "Upload successful".to_string(),
-            }));
+            // twine may send the signature after the file, so store once all fields are read
+            upload = Some((filename, data));
         } else {
             // For non-content fields, we still need to read and count them for size validation
             let field_data = field.bytes().await?;
@@ -340,11 +415,79 @@ pub async fn upload_package(
             FileStreamValidator::validate_total_upload_size(total_size, "PyPI")?;
 
             debug!(field_name = %name, size = field_data.len(), "Processed non-content field");
+            if name == "gpg_signature" {
+                signature = Some(field_data);
+            }
         }
     }
 
-    warn!("No content field found in multipart upload");
-    Err(AppError::BadRequest("No content field found".to_string()))
+    let Some((filename, data)) = upload else {
+        warn!("No content field found in multipart upload");
+        return Err(AppError::BadRequest("No content field found".to_string()));
+    };
+
+    crate::signatures::verify_pypi_upload(
+        &state.config.load().security.signatures.pypi,
+        &filename,
+        &data,
+        signature.as_deref(),
+    )
+    .await?;
+
+    let release = crate::utils::extract_pypi_package_name_and_version(&filename);
+    let reservation = crate::quotas::check_upload(
+        &state,
+        &headers,
+        &crate::quotas::Upload {
+            registry: "pypi",
+            package: release.as_ref().map_or(&filename, |(project, _)| project),
+            version: release.as_ref().map(|(_, version)| version.as_str()),
+            size: data.len(),
+        },
+    )
+    .await?;
+
+    // Calculate hash once during upload
+    let hash = sha256_hash(&data);
+
+    // Save the file using storage utility
+    let file_path = pypi_dir.join(&filename);
+    storage::save_file(&file_path, &data).await?;
+
+    // Save the hash to a .meta file
+    let meta_path = file_path.with_extension(format!(
+        "{}.meta",
+        file_path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .unwrap_or("")
+    ));
+    storage::save_file(meta_path, hash.as_bytes()).await?;
+    // PEP 658 core metadata, so pip can resolve dependencies without the wheel
+    if filename.ends_with(".whl") {
+        match crate::pypi_utils::wheel_core_metadata(&data) {
+            Some(metadata) => storage::save_file(core_metadata_path(&file_path), metadata).await?,
+            None => warn!(filename = %filename, "Wheel has no .dist-info/METADATA"),
+        }
+    }
+    reservation.commit();
+
+    if let Some((project, version)) = release {
+        let (name, recorded) = (project.clone(), version.clone());
+        if let Err(e) = state
+            .package_index
+            .run(move |index| index.record("pypi", &name, &recorded))
+            .await
+        {
+            warn!(filename = %filename, error = %e, "Failed to update package index");
+        }
+        crate::webhooks::package_published(&state, "pypi", &project, &version);
+    }
+
+    info!(filename = %filename, size = data.len(), "PyPI package uploaded successfully");
+    Ok(axum::Json(SuccessResponse {
+        message: "Upload successful".to_string(),
+    }))
 }
 
 #[cfg(test)]
@@ -370,13 +513,22 @@ mod tests {
             .expect("should create pypi simple dir");
 
         let config = Arc::new(crate::config::Config::default());
+        let package_index = Arc::new(
+            crate::package_index::PackageIndex::open(&data_dir)
+                .expect("Failed to open package index"),
+        );
+        let tokens = Arc::new(
+            crate::tokens::TokenStore::open(&data_dir).expect("Failed to open token store"),
+        );
         let state = Arc::new(AppState {
             data_dir,
             server_addr: "http://127.0.0.1:3080".to_string(),
-            upstream_client: Arc::new(UpstreamClient::disabled()),
-            config,
+            upstream_client: Arc::new(UpstreamClient::disabled()).into(),
+            config: config.into(),
             npm_registry: crate::registry::NpmRegistry::new(),
             pypi_registry: crate::registry::PypiRegistry::new(),
+            package_index,
+            tokens,
         });
 
         (state, temp_dir)
@@ -536,4 +688,68 @@ mod tests {
         assert_eq!(response.status_code(), StatusCode::OK);
         assert_eq!(response.as_bytes().to_vec(), content.to_vec());
     }
+
+    fn build_wheel(metadata: &[u8]) -> Vec<u8> {
+        use std::io::Write;
+
+        let mut wheel = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        let options = zip::write::SimpleFileOptions::default();
+        wheel.start_file("demo/__init__.py", options).unwrap();
+        wheel
+            .start_file("demo-1.0.0.dist-info/METADATA", options)
+            .unwrap();
+        wheel.write_all(metadata).unwrap();
+        wheel.finish().unwrap().into_inner()
+    }
+
+    #[tokio::test]
+    async fn test_wheels_serve_pep_658_metadata() {
+        let (state, _temp_dir) = create_pypi_test_state();
+        let app = axum::Router::new()
+            .route("/pypi/", axum::routing::post(upload_package))
+            .route("/pypi/simple/{package}/", axum::routing::get(package_index))
+            .route(
+                "/pypi/packages/{filename}",
+                axum::routing::get(download_file),
+            )
+            .with_state(state.clone());
+        let server = TestServer::new(app).expect("should create test server");
+
+        let metadata = b"Metadata-Version: 2.1\nName: demo\nRequires-Dist: numpy\n";
+        let part = Part::bytes(build_wheel(metadata))
+            .file_name("demo-1.0.0-py3-none-any.whl")
+            .mime_type("application/octet-stream");
+        let response = server
+            .post("/pypi/")
+            .multipart(MultipartForm::new().add_part("content", part))
+            .await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+
+        // A wheel stored before metadata files were written gets one on first use
+        let packages = state.data_dir.join("pypi/packages");
+        let older = build_wheel(b"Name: demo\n");
+        std::fs::write(packages.join("demo-0.9.0-py3-none-any.whl"), &older).unwrap();
+        std::fs::write(
+            packages.join("demo-0.9.0-py3-none-any.whl.meta"),
+            sha256_hash(&older),
+        )
+        .unwrap();
+
+        let body = server.get("/pypi/simple/demo/").await.text();
+        let expected = format!(
+            r#"data-dist-info-metadata="sha256={0}" data-core-metadata="sha256={0}""#,
+            sha256_hash(metadata)
+        );
+        assert!(body.contains(&expected), "{body}");
+        assert!(body.contains(&sha256_hash(b"Name: demo\n")));
+
+        let response = server
+            .get("/pypi/packages/demo-1.0.0-py3-none-any.whl.metadata")
+            .await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+        assert_eq!(response.as_bytes().to_vec(), metadata.to_vec());
+        assert!(packages
+            .join("demo-0.9.0-py3-none-any.whl.metadata")
+            .exists());
+    }
 }
@@ -1,6 +1,7 @@
 //! PyPI-specific utility functions
 
 use regex::Regex;
+use std::io::{Cursor, Read};
 use std::sync::OnceLock;
 
 /// Normalize PyPI package name according to PEP 503.
@@ -24,6 +25,26 @@ pub fn normalize_pypi_name(name: &str) -> String {
     re.replace_all(&name.to_lowercase(), "-").to_string()
 }
 
+/// Extract the core metadata (`{name}-{version}.dist-info/METADATA`) from a wheel.
+///
+/// Returns `None` when the file is not a readable wheel or has no top-level
+/// `.dist-info` directory.
+pub fn wheel_core_metadata(wheel: &[u8]) -> Option<Vec<u8>> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(wheel)).ok()?;
+    let entry = archive.file_names().find(|name| {
+        name.split_once('/')
+            .is_some_and(|(dir, file)| dir.ends_with(".dist-info") && file == "METADATA")
+    })?;
+    let entry = entry.to_string();
+    let mut metadata = Vec::new();
+    archive
+        .by_name(&entry)
+        .ok()?
+        .read_to_end(&mut metadata)
+        .ok()?;
+    Some(metadata)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -37,4 +58,26 @@ mod tests {
         assert_eq!(normalize_pypi_name("some_package"), "some-package");
         assert_eq!(normalize_pypi_name("package.name"), "package-name");
     }
+
+    #[test]
+    fn test_wheel_core_metadata() {
+        use std::io::Write;
+
+        let mut wheel = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        let options = zip::write::SimpleFileOptions::default();
+        wheel.start_file("demo/__init__.py", options).unwrap();
+        wheel
+            .start_file("demo-1.0.dist-info/METADATA", options)
+            .unwrap();
+        wheel
+            .write_all(b"Metadata-Version: 2.1\nName: demo\n")
+            .unwrap();
+        let wheel = wheel.finish().unwrap().into_inner();
+
+        assert_eq!(
+            wheel_core_metadata(&wheel).as_deref(),
+            Some(&b"Metadata-Version: 2.1\nName: demo\n"[..])
+        );
+        assert_eq!(wheel_core_metadata(b"not a zip"), None);
+    }
 }
@@ -0,0 +1,338 @@
+//! # Upload quotas
+//!
+//! Limits on what can be published, configured in the `quotas` section of
+//! `data/config.json`:
+//!
+//! ```json
+//! {
+//!   "quotas": {
+//!     "max_upload_mb_per_token": 2048,
+//!     "max_versions_per_package": 50,
+//!     "max_artifact_mb": { "npm": 20, "pypi": 200 }
+//!   }
+//! }
+//! ```
+//!
+//! - `max_upload_mb_per_token`: total size of everything published with one API
+//!   token. Usage is counted per token in `quota-usage.json` in the data
+//!   directory and is not refunded when packages are deleted; delete an entry
+//!   there to reset it. Uploads without a token aren't counted. The size is
+//!   reserved when the upload is checked, so concurrent uploads can't overrun
+//!   the quota together, and handed back if the upload then fails.
+//! - `max_versions_per_package`: new versions of a PyPI, npm or Cargo package are
+//!   rejected once it has this many. Re-uploading an existing version is allowed.
+//! - `max_artifact_mb`: the largest file accepted per registry, on top of the
+//!   server-wide upload size limit.
+//!
+//! Exceeding the token or version quota returns `403` with the `quota_exceeded`
+//! error code; an oversized artifact returns `413` like any other size limit.
+
+use crate::hash_utils::sha256_hash;
+use crate::{AppError, AppResult, AppState};
+use axum::http::HeaderMap;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tracing::warn;
+
+/// Per-token usage file in the data directory
+const USAGE_FILE: &str = "quota-usage.json";
+
+/// Serializes read-modify-write cycles on the usage file
+static USAGE_LOCK: Mutex<()> = Mutex::new(());
+
+const MB: u64 = 1024 * 1024;
+
+/// An upload about to be stored
+#[derive(Debug, Clone, Copy)]
+pub struct Upload<'a> {
+    pub registry: &'a str,
+    pub package: &'a str,
+    /// Version being published, if known
+    pub version: Option<&'a str>,
+    pub size: usize,
+}
+
+/// Name usage is counted under: the id of an issued token, otherwise a hash
+/// of the token so the secret is never written to disk
+fn uploader(state: &AppState, headers: &HeaderMap) -> Option<String> {
+    let token = crate::auth::request_token(headers)?;
+    Some(match state.tokens.find(&token) {
+        Some(issued) => format!("token:{}", issued.id),
+        None => format!("key:{}", &sha256_hash(token.as_bytes())[..16]),
+    })
+}
+
+fn load_usage(data_dir: &Path) -> HashMap<String, u64> {
+    std::fs::read_to_string(data_dir.join(USAGE_FILE))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save_usage(data_dir: &Path, usage: &HashMap<String, u64>) -> std::io::Result<()> {
+    let json = serde_json::to_vec_pretty(usage).map_err(std::io::Error::from)?;
+    std::fs::write(data_dir.join(USAGE_FILE), json)
+}
+
+/// Add `size` to an uploader's usage unless that would exceed `limit`.
+///
+/// Returns the usage before the upload when it doesn't fit.
+fn try_reserve(data_dir: &Path, uploader: &str, size: u64, limit: u64) -> Result<(), u64> {
+    let _guard = USAGE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut usage = load_usage(data_dir);
+    let used = usage.get(uploader).copied().unwrap_or(0);
+    if used + size > limit {
+        return Err(used);
+    }
+    usage.insert(uploader.to_string(), used + size);
+    if let Err(e) = save_usage(data_dir, &usage) {
+        warn!(error = %e, "Failed to save upload quota usage");
+    }
+    Ok(())
+}
+
+fn release(data_dir: &Path, uploader: &str, size: u64) {
+    let _guard = USAGE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut usage = load_usage(data_dir);
+    if let Some(used) = usage.get_mut(uploader) {
+        *used = used.saturating_sub(size);
+    }
+    if let Err(e) = save_usage(data_dir, &usage) {
+        warn!(error = %e, "Failed to save upload quota usage");
+    }
+}
+
+/// Quota held for an upload that passed [`check_upload`].
+///
+/// Call [`Reservation::commit`] once the upload is stored; dropping the
+/// reservation instead (e.g. when storing fails) hands the quota back.
+#[must_use = "commit the reservation once the upload is stored"]
+#[derive(Debug, Default)]
+pub struct Reservation {
+    held: Option<(PathBuf, String, u64)>,
+}
+
+impl Reservation {
+    /// Keep the reserved quota: the upload was stored
+    pub fn commit(mut self) {
+        self.held = None;
+    }
+}
+
+impl Drop for Reservation {
+    fn drop(&mut self) {
+        let Some((data_dir, uploader, size)) = self.held.take() else {
+            return;
+        };
+        match tokio::runtime::Handle::try_current() {
+            Ok(runtime) => {
+                runtime.spawn_blocking(move || release(&data_dir, &uploader, size));
+            }
+            Err(_) => release(&data_dir, &uploader, size),
+        }
+    }
+}
+
+/// Reject an upload that would exceed a quota, reserving its size against the
+/// uploader's token quota
+pub async fn check_upload(
+    state: &AppState,
+    headers: &HeaderMap,
+    upload: &Upload<'_>,
+) -> AppResult<Reservation> {
+    let quotas = &state.config.load().quotas;
+
+    if let Some(&max_mb) = quotas
+        .max_artifact_mb
+        .get(upload.registry)
+        .filter(|&&max_mb| max_mb > 0)
+    {
+        if upload.size as u64 > max_mb * MB {
+            warn!(registry = upload.registry, package = %upload.package, size = upload.size, "Artifact exceeds registry quota");
+            return Err(AppError::UploadError(format!(
+                "{} is {:.1} MB; {} artifacts may be at most {max_mb} MB",
+                upload.package,
+                upload.size as f64 / MB as f64,
+                upload.registry
+            )));
+        }
+    }
+
+    if quotas.max_versions_per_package > 0 {
+        if let Some(version) = upload.version {
+            let versions =
+                crate::registry::package_versions(state, upload.registry, upload.package).await?;
+            if !versions.contains(version) && versions.len() >= quotas.max_versions_per_package {
+                warn!(registry = upload.registry, package = %upload.package, "Package version quota reached");
+                return Err(AppError::QuotaExceeded(format!(
+                    "{} already has {} versions, the most allowed; delete old versions before publishing {version}",
+                    upload.package,
+                    versions.len()
+                )));
+            }
+        }
+    }
+
+    if quotas.max_upload_mb_per_token == 0 {
+        return Ok(Reservation::default());
+    }
+    let Some(uploader) = uploader(state, headers) else {
+        return Ok(Reservation::default());
+    };
+    let limit = quotas.max_upload_mb_per_token * MB;
+    let size = upload.size as u64;
+    let data_dir = state.data_dir.clone();
+    let reserved = {
+        let (data_dir, uploader) = (data_dir.clone(), uploader.clone());
+        tokio::task::spawn_blocking(move || try_reserve(&data_dir, &uploader, size, limit))
+            .await
+            .map_err(|e| AppError::InternalError(format!("Quota check failed: {e}")))?
+    };
+    if let Err(used) = reserved {
+        warn!(uploader = %uploader, used, "Token upload quota reached");
+        return Err(AppError::QuotaExceeded(format!(
+            "This token has uploaded {:.1} MB of its {} MB quota; {} ({:.1} MB) does not fit",
+            used as f64 / MB as f64,
+            quotas.max_upload_mb_per_token,
+            upload.package,
+            upload.size as f64 / MB as f64
+        )));
+    }
+    Ok(Reservation {
+        held: Some((data_dir, uploader, size)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Config, QuotaConfig};
+    use tempfile::TempDir;
+
+    fn state_with_quotas(quotas: QuotaConfig) -> (AppState, TempDir) {
+        let (state, dir) = crate::test_utils::create_npm_test_state();
+        let mut state = (*state).clone();
+        state.config = std::sync::Arc::new(Config {
+            quotas,
+            ..Config::default()
+        })
+        .into();
+        (state, dir)
+    }
+
+    fn bearer(token: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            axum::http::header::AUTHORIZATION,
+            format!("Bearer {token}").parse().unwrap(),
+        );
+        headers
+    }
+
+    fn upload(size: usize) -> Upload<'static> {
+        Upload {
+            registry: "npm",
+            package: "widget",
+            version: Some("1.0.0"),
+            size,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_token_quota_accumulates() {
+        let (state, _dir) = state_with_quotas(QuotaConfig {
+            max_upload_mb_per_token: 1,
+            ..Default::default()
+        });
+        let headers = bearer("ci-key");
+        let half = (MB / 2) as usize + 1;
+
+        check_upload(&state, &headers, &upload(half))
+            .await
+            .unwrap()
+            .commit();
+        assert!(matches!(
+            check_upload(&state, &headers, &upload(half)).await,
+            Err(AppError::QuotaExceeded(_))
+        ));
+        // Other tokens and anonymous uploads have their own allowance
+        check_upload(&state, &bearer("other"), &upload(half))
+            .await
+            .unwrap()
+            .commit();
+        check_upload(&state, &HeaderMap::new(), &upload(half))
+            .await
+            .unwrap()
+            .commit();
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_concurrent_uploads_cannot_overrun_quota() {
+        let (state, _dir) = state_with_quotas(QuotaConfig {
+            max_upload_mb_per_token: 1,
+            ..Default::default()
+        });
+        let headers = bearer("ci-key");
+        let half = (MB / 2) as usize + 1;
+
+        // Both checks run before either upload is stored
+        let artifact = upload(half);
+        let (first, second) = tokio::join!(
+            check_upload(&state, &headers, &artifact),
+            check_upload(&state, &headers, &artifact)
+        );
+        assert_eq!(
+            [&first, &second]
+                .iter()
+                .filter(|result| matches!(result, Err(AppError::QuotaExceeded(_))))
+                .count(),
+            1
+        );
+
+        // A failed upload hands its reservation back
+        drop(first.or(second).unwrap());
+        for _ in 0..50 {
+            if load_usage(&state.data_dir).values().all(|&used| used == 0) {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        check_upload(&state, &headers, &upload(half))
+            .await
+            .unwrap()
+            .commit();
+    }
+
+    #[tokio::test]
+    async fn test_artifact_size_and_version_quotas() {
+        let (state, _dir) = state_with_quotas(QuotaConfig {
+            max_versions_per_package: 1,
+            max_artifact_mb: HashMap::from([("npm".to_string(), 1)]),
+            ..Default::default()
+        });
+        let headers = HeaderMap::new();
+        assert!(matches!(
+            check_upload(&state, &headers, &upload(MB as usize + 1)).await,
+            Err(AppError::UploadError(_))
+        ));
+
+        std::fs::write(
+            state.data_dir.join("npm/metadata/widget.json"),
+            r#"{"versions": {"1.0.0": {"dist": {"tarball": "x/widget-1.0.0.tgz"}}}}"#,
+        )
+        .unwrap();
+        check_upload(&state, &headers, &upload(10))
+            .await
+            .unwrap()
+            .commit();
+        let next = Upload {
+            version: Some("1.1.0"),
+            ..upload(10)
+        };
+        assert!(matches!(
+            check_upload(&state, &headers, &next).await,
+            Err(AppError::QuotaExceeded(_))
+        ));
+    }
+}
@@ -37,6 +37,7 @@
 
 use crate::{AppError, AppResult, AppState};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
 
 /// Metadata for package uploads across all registries.
 ///
@@ -279,6 +280,38 @@ pub trait PackageRegistry: Send + Sync {
         metadata: PackageMetadata,
     ) -> AppResult<()>;
 
+    /// Search packages by name, version and description.
+    ///
+    /// Returns every package matching all terms of `query`, unsorted; ranking and
+    /// pagination across registries happen in [`search::SearchPage`]. The default
+    /// implementation matches names and versions only, since not every registry
+    /// stores descriptions.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let hits = registry.search(&state, "json").await?;
+    /// for hit in hits {
+    ///     println!("{} {} (score {})", hit.name, hit.version, hit.score);
+    /// }
+    /// ```
+    async fn search(&self, state: &AppState, query: &str) -> AppResult<Vec<SearchHit>> {
+        let mut hits = Vec::new();
+        for name in self.list_all_packages(state).await? {
+            let versions: Vec<String> = self
+                .get_package_versions(state, &name)
+                .await?
+                .into_iter()
+                .map(|(version, ..)| version)
+                .collect();
+            if let Some(hit) = SearchHit::rank(self.registry_name(), &name, &versions, None, query)
+            {
+                hits.push(hit);
+            }
+        }
+        Ok(hits)
+    }
+
     /// Get the registry name identifier.
     ///
     /// Returns a string identifier for the registry type (e.g., "npm", "pypi", "cargo").
@@ -299,10 +332,12 @@ pub trait PackageRegistry: Send + Sync {
 // Submodules
 pub mod npm_registry;
 pub mod pypi_registry;
+pub mod search;
 
 // Re-export implementations
 pub use npm_registry::NpmRegistry;
 pub use pypi_registry::PypiRegistry;
+pub use search::SearchHit;
 
 /// Get a registry implementation by name.
 ///
@@ -376,6 +411,36 @@ pub const NPM: NpmRegistry = NpmRegistry;
 /// ```
 pub const PYPI: PypiRegistry = PypiRegistry;
 
+/// Versions of a package the server holds in the `pypi`, `npm` or `cargo`
+/// registry; empty for other registries
+pub async fn package_versions(
+    state: &AppState,
+    registry: &str,
+    package: &str,
+) -> AppResult<BTreeSet<String>> {
+    let versions = match registry {
+        "pypi" => PYPI
+            .get_package_versions(state, package)
+            .await?
+            .into_iter()
+            .map(|(version, ..)| version)
+            .collect(),
+        "npm" => NPM
+            .get_package_versions(state, package)
+            .await?
+            .into_iter()
+            .map(|(version, ..)| version)
+            .collect(),
+        "cargo" => crate::cargo::get_crate_versions(state, package)
+            .await?
+            .into_iter()
+            .map(|(version, ..)| version)
+            .collect(),
+        _ => BTreeSet::new(),
+    };
+    Ok(versions)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -30,7 +30,7 @@
 //! # }
 //! ```
 
-use crate::registry::{PackageMetadata, PackageRegistry};
+use crate::registry::{PackageMetadata, PackageRegistry, SearchHit};
 use crate::{package_utils, storage, AppResult, AppState};
 use serde_json::Value;
 use tracing::debug;
@@ -73,27 +73,11 @@ impl Default for NpmRegistry {
 #[async_trait::async_trait]
 impl PackageRegistry for NpmRegistry {
     async fn count_packages(&self, state: &AppState) -> AppResult<usize> {
-        package_utils::count_packages_by_pattern(
-            &state.data_dir,
-            &package_utils::RegistryPattern::NPM,
-            |filename| {
-                // Extract package name from filename (remove .json extension)
-                filename.strip_suffix(".json").map(|name| name.to_string())
-            },
-        )
-        .await
+        state.package_index.run(|index| index.count("npm")).await
     }
 
     async fn list_all_packages(&self, state: &AppState) -> AppResult<Vec<String>> {
-        package_utils::list_packages_by_pattern(
-            &state.data_dir,
-            &package_utils::RegistryPattern::NPM,
-            |filename| {
-                // Extract package name from filename (remove .json extension)
-                filename.strip_suffix(".json").map(|name| name.to_string())
-            },
-        )
-        .await
+        state.package_index.run(|index| index.list("npm")).await
     }
 
     async fn get_package_versions(
@@ -145,37 +129,11 @@ impl PackageRegistry for NpmRegistry {
         state: &AppState,
         limit: usize,
     ) -> AppResult<Vec<(String, String)>> {
-        // Get recent metadata files using pattern helper, then read JSON to extract latest versions
-        let recent_files = package_utils::get_recent_packages_by_pattern(
-            &state.data_dir,
-            &package_utils::RegistryPattern::NPM,
-            limit,
-            |filename| {
-                // Extract package name from filename (remove .json extension)
-                filename
-                    .strip_suffix(".json")
-                    .map(|name| (name.to_string(), String::new()))
-            },
-        )
-        .await?;
-
-        let mut recent = Vec::new();
-        for (package_name, _) in recent_files {
-            // Read metadata file to get latest version
-            let metadata_path = state
-                .data_dir
-                .join("npm/metadata")
-                .join(format!("{package_name}.json"));
-            if let Ok(content) = storage::read_file_string(&metadata_path).await {
-                if let Ok(metadata) = serde_json::from_str::<Value>(&content) {
-                    if let Some(latest) = metadata["dist-tags"]["latest"].as_str() {
-                        recent.push((package_name, latest.to_string()));
-                    }
-                }
-            }
-        }
-
-        Ok(recent)
+        // The index records each package's `latest` dist-tag
+        state
+            .package_index
+            .run(move |index| index.recent("npm", limit))
+            .await
     }
 
     async fn download_package(
@@ -202,7 +160,12 @@ impl PackageRegistry for NpmRegistry {
 
                 // Construct the tarball URL for upstream
                 let tarball_url = format!("/{package_name}/-/{version}");
-                match state.upstream_client.stream_npm_tarball(&tarball_url).await {
+                match state
+                    .upstream_client
+                    .load()
+                    .stream_npm_tarball(&tarball_url)
+                    .await
+                {
                     Ok(bytes) => {
                         debug!(package = %package_name, filename = %version, size = bytes.len(), "Streaming tarball from upstream NPM");
                         Ok(bytes.to_vec())
@@ -235,6 +198,37 @@ impl PackageRegistry for NpmRegistry {
         ))
     }
 
+    async fn search(&self, state: &AppState, query: &str) -> AppResult<Vec<SearchHit>> {
+        let mut hits = Vec::new();
+        for name in self.list_all_packages(state).await? {
+            let metadata_path = state
+                .data_dir
+                .join("npm/metadata")
+                .join(format!("{name}.json"));
+            let Ok(content) = storage::read_file_string(&metadata_path).await else {
+                continue;
+            };
+            let Ok(metadata) = serde_json::from_str::<Value>(&content) else {
+                continue;
+            };
+
+            let versions: Vec<String> = metadata["versions"]
+                .as_object()
+                .map(|versions| versions.keys().cloned().collect())
+                .unwrap_or_default();
+            // The package-level description mirrors the latest publish
+            let latest = metadata["dist-tags"]["latest"].as_str().unwrap_or("");
+            let description = metadata["description"]
+                .as_str()
+                .or_else(|| metadata["versions"][latest]["description"].as_str());
+
+            if let Some(hit) = SearchHit::rank("npm", &name, &versions, description, query) {
+                hits.push(hit);
+            }
+        }
+        Ok(hits)
+    }
+
     fn registry_name(&self) -> &str {
         "npm"
     }
@@ -331,6 +325,32 @@ mod tests {
         assert_eq!(versions[1].2, "abc123");
     }
 
+    #[tokio::test]
+    async fn test_search_matches_descriptions() {
+        let (state, _temp_dir) = create_npm_test_state();
+
+        let metadata = json!({
+            "name": "left-pad",
+            "description": "String padding utility",
+            "dist-tags": {"latest": "1.3.0"},
+            "versions": {"1.0.0": {}, "1.3.0": {}}
+        });
+        std::fs::write(
+            state.data_dir.join("npm/metadata/left-pad.json"),
+            serde_json::to_string(&metadata).unwrap(),
+        )
+        .unwrap();
+
+        let registry = NpmRegistry::new();
+        let hits = registry.search(&state, "padding").await.unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].name, "left-pad");
+        assert_eq!(hits[0].version, "1.3.0");
+        assert_eq!(hits[0].score, 10);
+
+        assert!(registry.search(&state, "lodash").await.unwrap().is_empty());
+    }
+
     #[tokio::test]
     async fn test_registry_name() {
         let registry = NpmRegistry::new();
@@ -33,7 +33,6 @@
 
 use crate::registry::{PackageMetadata, PackageRegistry};
 use crate::{normalize_pypi_name, package_utils, storage, AppResult, AppState};
-use std::collections::HashSet;
 use std::path::Path;
 use tracing::debug;
 
@@ -86,21 +85,11 @@ impl Default for PypiRegistry {
 #[async_trait::async_trait]
 impl PackageRegistry for PypiRegistry {
     async fn count_packages(&self, state: &AppState) -> AppResult<usize> {
-        package_utils::count_packages_by_pattern(
-            &state.data_dir,
-            &package_utils::RegistryPattern::PYPI,
-            crate::utils::extract_pypi_package_name,
-        )
-        .await
+        state.package_index.run(|index| index.count("pypi")).await
     }
 
     async fn list_all_packages(&self, state: &AppState) -> AppResult<Vec<String>> {
-        package_utils::list_packages_by_pattern(
-            &state.data_dir,
-            &package_utils::RegistryPattern::PYPI,
-            crate::utils::extract_pypi_package_name,
-        )
-        .await
+        state.package_index.run(|index| index.list("pypi")).await
     }
 
     async fn get_package_versions(
@@ -157,30 +146,11 @@ impl PackageRegistry for PypiRegistry {
         state: &AppState,
         limit: usize,
     ) -> AppResult<Vec<(String, String)>> {
-        // Get raw recent files using pattern helper, then apply PyPI deduplication
-        let recent_files = package_utils::get_recent_packages_by_pattern(
-            &state.data_dir,
-            &package_utils::RegistryPattern::PYPI,
-            limit, // Helper already handles limit * 2 internally for deduplication
-            crate::utils::extract_pypi_package_name_and_version,
-        )
-        .await?;
-
-        // Deduplicate by package name (keep first occurrence which is most recent)
-        let mut seen_packages = HashSet::new();
-        let mut recent = Vec::new();
-
-        for (package_name, version) in recent_files {
-            if !seen_packages.contains(&package_name) {
-                seen_packages.insert(package_name.clone());
-                recent.push((package_name, version));
-                if recent.len() >= limit {
-                    break;
-                }
-            }
-        }
-
-        Ok(recent)
+        // Names are normalized in the index, so each project appears once
+        state
+            .package_index
+            .run(move |index| index.recent("pypi", limit))
+            .await
     }
 
     async fn download_package(
@@ -204,7 +174,7 @@ impl PackageRegistry for PypiRegistry {
             Err(_) => {
                 // File not found locally, try upstream PyPI
                 debug!(filename = %version, "File not found locally, checking upstream PyPI");
-                match state.upstream_client.stream_pypi_file(version).await {
+                match state.upstream_client.load().stream_pypi_file(version).await {
                     Ok(bytes) => {
                         debug!(filename = %version, size = bytes.len(), "Streaming file from upstream PyPI");
                         Ok(bytes.to_vec())
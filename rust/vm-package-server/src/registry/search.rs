@@ -0,0 +1,217 @@
+//! # Cross-Registry Search
+//!
+//! Ranks packages against a free-text query. Every whitespace-separated term has
+//! to match the package name, one of its versions or its description; the score
+//! adds up how well each term matched, with name matches weighing the most:
+//!
+//! | Match                      | Score |
+//! |----------------------------|-------|
+//! | Name equals the term       | 100   |
+//! | Name starts with the term  | 60    |
+//! | Name contains the term     | 40    |
+//! | A version starts with it   | 20    |
+//! | Description contains it    | 10    |
+//!
+//! Matching is case-insensitive, and `-`, `_` and `.` in names are treated alike
+//! so `typing_extensions` finds `typing-extensions`.
+
+use serde::{Deserialize, Serialize};
+
+use super::{PackageRegistry, NPM, PYPI};
+use crate::{AppResult, AppState};
+
+/// Results per page unless the client asks for another size
+pub const DEFAULT_PER_PAGE: usize = 20;
+/// Largest page a client can request
+pub const MAX_PER_PAGE: usize = 100;
+
+/// A package matching a search query
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SearchHit {
+    /// Registry the package lives in ("pypi", "npm", "cargo")
+    pub registry: String,
+    pub name: String,
+    /// Newest known version
+    pub version: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub score: u32,
+}
+
+impl SearchHit {
+    /// Score a package against `query`; `None` when some term does not match
+    pub fn rank(
+        registry: &str,
+        name: &str,
+        versions: &[String],
+        description: Option<&str>,
+        query: &str,
+    ) -> Option<Self> {
+        let normalized_name = normalize(name);
+        let description_lower = description.map(str::to_lowercase);
+        let mut score = 0;
+        let mut terms = query.split_whitespace().peekable();
+        terms.peek()?;
+
+        for term in terms {
+            let term_lower = term.to_lowercase();
+            let term_name = normalize(term);
+            let term_score = if normalized_name == term_name {
+                100
+            } else if normalized_name.starts_with(&term_name) {
+                60
+            } else if normalized_name.contains(&term_name) {
+                40
+            } else if versions.iter().any(|v| v.starts_with(&term_lower)) {
+                20
+            } else if description_lower
+                .as_deref()
+                .is_some_and(|d| d.contains(&term_lower))
+            {
+                10
+            } else {
+                return None;
+            };
+            score += term_score;
+        }
+
+        Some(Self {
+            registry: registry.to_string(),
+            name: name.to_string(),
+            version: latest_version(versions).unwrap_or_default(),
+            description: description.map(str::to_string),
+            score,
+        })
+    }
+}
+
+/// One page of search results
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchPage {
+    pub query: String,
+    /// Matches across all pages
+    pub total: usize,
+    pub page: usize,
+    pub per_page: usize,
+    pub results: Vec<SearchHit>,
+}
+
+impl SearchPage {
+    /// Sort hits best first (ties by name) and cut out the requested 1-based page
+    pub fn paginate(query: &str, mut hits: Vec<SearchHit>, page: usize, per_page: usize) -> Self {
+        let page = page.max(1);
+        let per_page = per_page.clamp(1, MAX_PER_PAGE);
+        hits.sort_by(|a, b| {
+            b.score
+                .cmp(&a.score)
+                .then_with(|| a.name.cmp(&b.name))
+                .then_with(|| a.registry.cmp(&b.registry))
+        });
+        let total = hits.len();
+        let results = hits
+            .into_iter()
+            .skip((page - 1).saturating_mul(per_page))
+            .take(per_page)
+            .collect();
+        Self {
+            query: query.to_string(),
+            total,
+            page,
+            per_page,
+            results,
+        }
+    }
+}
+
+/// Search PyPI, npm and Cargo packages
+pub async fn search_all(state: &AppState, query: &str) -> AppResult<Vec<SearchHit>> {
+    let mut hits = PYPI.search(state, query).await?;
+    hits.extend(NPM.search(state, query).await?);
+    hits.extend(crate::cargo::search_crates(state, query).await?);
+    Ok(hits)
+}
+
+fn normalize(name: &str) -> String {
+    name.to_lowercase().replace(['_', '.'], "-")
+}
+
+/// The highest version, comparing dot-separated numeric components
+pub fn latest_version(versions: &[String]) -> Option<String> {
+    fn key(version: &str) -> Vec<u64> {
+        version
+            .split(['.', '-', '+'])
+            .map(|part| part.parse().unwrap_or(0))
+            .collect()
+    }
+    versions.iter().max_by_key(|v| key(v)).cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn versions(list: &[&str]) -> Vec<String> {
+        list.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn ranks_name_matches_above_descriptions() {
+        let exact = SearchHit::rank("npm", "react", &versions(&["18.2.0"]), None, "react").unwrap();
+        let prefix =
+            SearchHit::rank("npm", "react-dom", &versions(&["18.2.0"]), None, "react").unwrap();
+        let described = SearchHit::rank(
+            "npm",
+            "preact",
+            &versions(&["10.0.0"]),
+            Some("Fast React alternative"),
+            "react",
+        )
+        .unwrap();
+        let in_description = SearchHit::rank(
+            "npm",
+            "inferno",
+            &versions(&["8.0.0"]),
+            Some("A React-like library"),
+            "react",
+        )
+        .unwrap();
+
+        assert_eq!(exact.score, 100);
+        assert_eq!(prefix.score, 60);
+        assert_eq!(described.score, 40);
+        assert_eq!(in_description.score, 10);
+    }
+
+    #[test]
+    fn every_term_must_match() {
+        let v = versions(&["1.2.0", "2.0.1"]);
+        let hit = SearchHit::rank("pypi", "typing_extensions", &v, None, "typing-ext 2.0").unwrap();
+        assert_eq!(hit.score, 60 + 20);
+        assert_eq!(hit.version, "2.0.1");
+
+        assert!(SearchHit::rank("pypi", "requests", &v, None, "requests 3").is_none());
+        assert!(SearchHit::rank("pypi", "requests", &v, None, "   ").is_none());
+    }
+
+    #[test]
+    fn paginates_best_first() {
+        let hits: Vec<SearchHit> = ["serde", "serde_json", "serde_yaml"]
+            .iter()
+            .map(|name| SearchHit::rank("cargo", name, &versions(&["1.0.0"]), None, "serde"))
+            .collect::<Option<_>>()
+            .unwrap();
+
+        let first = SearchPage::paginate("serde", hits.clone(), 1, 2);
+        assert_eq!(first.total, 3);
+        assert_eq!(first.results[0].name, "serde");
+        assert_eq!(first.results.len(), 2);
+
+        let second = SearchPage::paginate("serde", hits.clone(), 2, 2);
+        assert_eq!(second.results.len(), 1);
+        assert_eq!(second.results[0].name, "serde_yaml");
+
+        let beyond = SearchPage::paginate("serde", hits, usize::MAX, usize::MAX);
+        assert_eq!(beyond.per_page, MAX_PER_PAGE);
+        assert!(beyond.results.is_empty());
+    }
+}
@@ -0,0 +1,694 @@
+//! # Replication
+//!
+//! Keeps package servers in sync, for example one server per office. Each server
+//! lists the files it holds at `GET /api/replication/manifest`. Replication
+//! compares the source's manifest with the target's and uploads every missing
+//! file through the target's normal publish endpoints, so indexes, webhooks and
+//! signature policies apply exactly as they do for a manual upload.
+//!
+//! - **pull**: copy packages from a peer to this server
+//! - **push**: copy packages from this server to a peer
+//!
+//! Replication runs on demand with `pkg-server replicate` and periodically for
+//! the peers in the `replication` section of `data/config.json`:
+//!
+//! ```json
+//! {
+//!   "replication": {
+//!     "interval_minutes": 30,
+//!     "peers": [
+//!       { "url": "http://pkg.berlin.example:3080", "mode": "pull" },
+//!       { "url": "http://pkg.tokyo.example:3080", "mode": "push", "registries": ["npm"], "token": "..." }
+//!     ]
+//!   }
+//! }
+//! ```
+//!
+//! PyPI, npm and Cargo packages published to a server are replicated. Packages
+//! cached from upstream registries are not, and nothing is ever deleted on the
+//! target.
+
+use crate::api::PackageServerClient;
+use crate::auth;
+use crate::config::{ReplicationMode, ReplicationPeer};
+use crate::state::AppState;
+use crate::tokens::TokenScope;
+use crate::{AppError, AppResult};
+use anyhow::Result;
+use axum::{extract::State, http::HeaderMap, Json};
+use base64::{engine::general_purpose, Engine as _};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+/// Route serving the manifest of locally held files
+pub const MANIFEST_ROUTE: &str = "/api/replication/manifest";
+
+/// Registries that can be replicated. The manifest only walks the PyPI, npm and
+/// Cargo storage layouts and uploads through their publish endpoints; Go modules
+/// and Helm charts are not replicated yet.
+pub const REGISTRIES: [&str; 3] = ["pypi", "npm", "cargo"];
+
+/// Delay before the first background run, so the server is listening
+const STARTUP_DELAY: Duration = Duration::from_secs(10);
+
+/// A package file held by a server
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReplicatedFile {
+    pub registry: String,
+    pub package: String,
+    pub version: String,
+    pub filename: String,
+    /// Download path on the server holding the file
+    pub path: String,
+    /// What is needed to republish the file: the npm version manifest or the
+    /// Cargo index entry
+    #[serde(default, skip_serializing_if = "Value::is_null")]
+    pub metadata: Value,
+}
+
+/// List the package files published to the server in `data_dir`
+pub fn build_manifest(data_dir: &Path) -> Vec<ReplicatedFile> {
+    let mut files = pypi_files(data_dir);
+    files.extend(npm_files(data_dir));
+    files.extend(cargo_files(data_dir));
+    files
+}
+
+fn pypi_files(data_dir: &Path) -> Vec<ReplicatedFile> {
+    let Ok(entries) = std::fs::read_dir(data_dir.join("pypi/packages")) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter(|entry| entry.path().is_file())
+        .filter_map(|entry| {
+            let filename = entry.file_name().to_str()?.to_string();
+            let (package, version) =
+                crate::utils::extract_pypi_package_name_and_version(&filename)?;
+            Some(ReplicatedFile {
+                registry: "pypi".to_string(),
+                package,
+                version,
+                path: format!("/pypi/packages/{filename}"),
+                filename,
+                metadata: Value::Null,
+            })
+        })
+        .collect()
+}
+
+fn npm_files(data_dir: &Path) -> Vec<ReplicatedFile> {
+    let metadata_dir = data_dir.join("npm/metadata");
+    let Ok(entries) = std::fs::read_dir(&metadata_dir) else {
+        return Vec::new();
+    };
+    // Scoped packages are stored as `@scope/name.json`
+    let mut documents = Vec::new();
+    for path in entries.flatten().map(|entry| entry.path()) {
+        let scope = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .filter(|name| name.starts_with('@') && path.is_dir());
+        match scope {
+            Some(scope) => {
+                let Ok(scoped) = std::fs::read_dir(&path) else {
+                    continue;
+                };
+                documents.extend(
+                    scoped
+                        .flatten()
+                        .map(|entry| (Some(scope.to_string()), entry.path())),
+                );
+            }
+            None => documents.push((None, path)),
+        }
+    }
+
+    let tarballs = data_dir.join("npm/tarballs");
+    let mut files = Vec::new();
+    for (scope, path) in documents {
+        let Some(name) = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.strip_suffix(".json"))
+        else {
+            continue;
+        };
+        let package = match &scope {
+            Some(scope) => format!("{scope}/{name}"),
+            None => name.to_string(),
+        };
+        let Some(document) = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str::<Value>(&content).ok())
+        else {
+            continue;
+        };
+        let Some(versions) = document["versions"].as_object() else {
+            continue;
+        };
+        for (version, manifest) in versions {
+            let filename = manifest["dist"]["tarball"]
+                .as_str()
+                .and_then(|url| url.rsplit('/').next())
+                .map(str::to_string)
+                .unwrap_or_else(|| format!("{name}-{version}.tgz"));
+            if !tarballs.join(&filename).is_file() {
+                continue;
+            }
+            files.push(ReplicatedFile {
+                registry: "npm".to_string(),
+                package: package.clone(),
+                version: version.clone(),
+                path: format!("/npm/{}/-/{filename}", package.replace('/', "%2f")),
+                filename,
+                metadata: manifest.clone(),
+            });
+        }
+    }
+    files
+}
+
+fn cargo_files(data_dir: &Path) -> Vec<ReplicatedFile> {
+    let crates_dir = data_dir.join("cargo/crates");
+    let mut files = Vec::new();
+    let mut pending = vec![data_dir.join("cargo/index")];
+    while let Some(dir) = pending.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for path in entries.flatten().map(|entry| entry.path()) {
+            if path.is_dir() {
+                pending.push(path);
+                continue;
+            }
+            if path.file_name().and_then(|n| n.to_str()) == Some("config.json") {
+                continue;
+            }
+            let content = std::fs::read_to_string(&path).unwrap_or_default();
+            for entry in content
+                .lines()
+                .filter_map(|line| serde_json::from_str::<Value>(line).ok())
+            {
+                let (Some(name), Some(version)) = (entry["name"].as_str(), entry["vers"].as_str())
+                else {
+                    continue;
+                };
+                let filename = format!("{name}-{version}.crate");
+                if !crates_dir.join(&filename).is_file() {
+                    continue;
+                }
+                files.push(ReplicatedFile {
+                    registry: "cargo".to_string(),
+                    package: name.to_string(),
+                    version: version.to_string(),
+                    path: format!("/cargo/api/v1/crates/{name}/{version}/download"),
+                    filename,
+                    metadata: entry.clone(),
+                });
+            }
+        }
+    }
+    files
+}
+
+/// Serve the manifest of files published to this server.
+///
+/// With `require_authentication` the request needs read access, and only lists
+/// the registries it may read. npm packages in a private scope are listed only
+/// for requests allowed to read them.
+pub async fn manifest(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> AppResult<Json<Vec<ReplicatedFile>>> {
+    let config = state.config.load();
+    let readable: Vec<&str> = REGISTRIES
+        .into_iter()
+        .filter(|registry| {
+            !auth::is_auth_required(&config)
+                || auth::is_allowed(&state, &headers, registry, TokenScope::Read)
+        })
+        .collect();
+    if readable.is_empty() {
+        return Err(AppError::Unauthorized(
+            "Missing or invalid API key".to_string(),
+        ));
+    }
+
+    let data_dir = state.data_dir.clone();
+    let files = tokio::task::spawn_blocking(move || build_manifest(&data_dir))
+        .await
+        .map_err(|e| AppError::InternalError(format!("Failed to build manifest: {e}")))?;
+    let files = files
+        .into_iter()
+        .filter(|file| readable.contains(&file.registry.as_str()))
+        .filter(|file| {
+            file.registry != "npm"
+                || auth::check_npm_scope(
+                    &config,
+                    &state.tokens,
+                    &headers,
+                    &file.package,
+                    TokenScope::Read,
+                )
+                .is_ok()
+        })
+        .collect();
+    Ok(Json(files))
+}
+
+/// Which packages to replicate; empty lists match everything
+#[derive(Debug, Clone, Default)]
+pub struct ReplicationFilter {
+    pub registries: Vec<String>,
+    pub packages: Vec<String>,
+}
+
+impl ReplicationFilter {
+    fn matches(&self, file: &ReplicatedFile) -> bool {
+        let registry_matches = self.registries.is_empty()
+            || self
+                .registries
+                .iter()
+                .any(|registry| registry.eq_ignore_ascii_case(&file.registry));
+        let package_matches = self.packages.is_empty()
+            || self.packages.iter().any(|package| {
+                if file.registry == "pypi" {
+                    crate::pypi_utils::normalize_pypi_name(package)
+                        == crate::pypi_utils::normalize_pypi_name(&file.package)
+                } else {
+                    *package == file.package
+                }
+            });
+        registry_matches && package_matches
+    }
+}
+
+/// Outcome of a replication run
+#[derive(Debug, Default)]
+pub struct ReplicationReport {
+    /// Files uploaded to the target
+    pub copied: usize,
+    /// Matching files the target already had
+    pub up_to_date: usize,
+    /// Files that could not be copied, with the reason
+    pub failed: Vec<(String, String)>,
+}
+
+/// Files in `source` matching `filter` that `target` lacks, oldest version first
+pub fn missing_files<'a>(
+    source: &'a [ReplicatedFile],
+    target: &[ReplicatedFile],
+    filter: &ReplicationFilter,
+) -> Vec<&'a ReplicatedFile> {
+    let present: HashSet<(&str, &str)> = target
+        .iter()
+        .map(|file| (file.registry.as_str(), file.filename.as_str()))
+        .collect();
+    let mut missing: Vec<&ReplicatedFile> = source
+        .iter()
+        .filter(|file| filter.matches(file))
+        .filter(|file| !present.contains(&(file.registry.as_str(), file.filename.as_str())))
+        .collect();
+    missing.sort_by(|a, b| {
+        (
+            &a.registry,
+            &a.package,
+            crate::cache::version_key(&a.version),
+        )
+            .cmp(&(
+                &b.registry,
+                &b.package,
+                crate::cache::version_key(&b.version),
+            ))
+    });
+    missing
+}
+
+/// Copy the files `target` is missing from `source`.
+///
+/// Individual files that fail are reported and skipped; failing to read either
+/// manifest is an error.
+pub fn replicate(
+    source: &PackageServerClient,
+    target: &PackageServerClient,
+    filter: &ReplicationFilter,
+) -> Result<ReplicationReport> {
+    if let Some(unknown) = filter
+        .registries
+        .iter()
+        .find(|registry| !REGISTRIES.contains(&registry.to_ascii_lowercase().as_str()))
+    {
+        anyhow::bail!(
+            "Cannot replicate registry '{unknown}'; supported registries are {}",
+            REGISTRIES.join(", ")
+        );
+    }
+
+    let source_files = source.get_replication_manifest()?;
+    let target_files = target.get_replication_manifest()?;
+    let missing = missing_files(&source_files, &target_files, filter);
+    let matching = source_files
+        .iter()
+        .filter(|file| filter.matches(file))
+        .count();
+    let mut report = ReplicationReport {
+        up_to_date: matching - missing.len(),
+        ..Default::default()
+    };
+    debug!(
+        source = %source.base_url(),
+        target = %target.base_url(),
+        missing = missing.len(),
+        "Replicating packages"
+    );
+
+    // An npm publish replaces the whole package document, so keep the target's
+    // versions and add each copied one
+    let mut npm_versions: HashMap<String, Map<String, Value>> = HashMap::new();
+    for file in target_files.iter().filter(|file| file.registry == "npm") {
+        npm_versions
+            .entry(file.package.clone())
+            .or_default()
+            .insert(file.version.clone(), file.metadata.clone());
+    }
+
+    let workdir = tempfile::tempdir()?;
+    for file in missing {
+        let result = source
+            .download(&file.path)
+            .and_then(|data| copy_file(target, file, &data, workdir.path(), &mut npm_versions));
+        match result {
+            Ok(()) => {
+                info!(registry = %file.registry, file = %file.filename, "Replicated package");
+                report.copied += 1;
+            }
+            Err(e) => {
+                warn!(registry = %file.registry, file = %file.filename, error = %e, "Failed to replicate package");
+                report
+                    .failed
+                    .push((file.filename.clone(), format!("{e:#}")));
+            }
+        }
+    }
+    Ok(report)
+}
+
+fn copy_file(
+    target: &PackageServerClient,
+    file: &ReplicatedFile,
+    data: &[u8],
+    workdir: &Path,
+    npm_versions: &mut HashMap<String, Map<String, Value>>,
+) -> Result<()> {
+    match file.registry.as_str() {
+        "pypi" => {
+            let path = workdir.join(&file.filename);
+            std::fs::write(&path, data)?;
+            let result = target.upload_pypi_package(&path);
+            let _ = std::fs::remove_file(&path);
+            result
+        }
+        "npm" => {
+            let mut versions = npm_versions.get(&file.package).cloned().unwrap_or_default();
+            versions.insert(file.version.clone(), file.metadata.clone());
+            let document = npm_publish_document(&file.package, &versions, &file.filename, data);
+            target.upload_npm_package(&file.package, data, document)?;
+            npm_versions.insert(file.package.clone(), versions);
+            Ok(())
+        }
+        "cargo" => {
+            let entry = &file.metadata;
+            let metadata = json!({
+                "name": entry["name"],
+                "vers": entry["vers"],
+                "deps": entry["deps"],
+                "features": entry["features"],
+                "links": entry["links"],
+            });
+            target.publish_cargo_crate(&metadata, data, None)
+        }
+        other => anyhow::bail!("Replication of {other} packages is not supported"),
+    }
+}
+
+/// An npm publish document carrying `versions` and the tarball for one of them
+fn npm_publish_document(
+    package: &str,
+    versions: &Map<String, Value>,
+    filename: &str,
+    tarball: &[u8],
+) -> Value {
+    let latest = versions
+        .keys()
+        .max_by_key(|version| crate::cache::version_key(version));
+    json!({
+        "name": package,
+        "versions": versions,
+        "dist-tags": { "latest": latest },
+        "_attachments": {
+            filename: {
+                "content_type": "application/octet-stream",
+                "data": general_purpose::STANDARD.encode(tarball),
+                "length": tarball.len(),
+            }
+        }
+    })
+}
+
+/// Replicate between the server at `local` and a configured peer
+pub fn replicate_with_peer(
+    local: &PackageServerClient,
+    peer: &ReplicationPeer,
+) -> Result<ReplicationReport> {
+    let remote = PackageServerClient::new(&peer.url).with_auth_token(peer.token.clone());
+    let filter = ReplicationFilter {
+        registries: peer.registries.clone(),
+        packages: peer.packages.clone(),
+    };
+    match peer.mode {
+        ReplicationMode::Pull => replicate(&remote, local, &filter),
+        ReplicationMode::Push => replicate(local, &remote, &filter),
+    }
+}
+
+/// Replicate with every configured peer every `interval_minutes`
+pub fn spawn_replication_task(state: Arc<AppState>) {
+    let config = state.config.load().replication.clone();
+    if config.peers.is_empty() || config.interval_minutes == 0 {
+        return;
+    }
+    // The server talks to itself over loopback, with its own API key if it has one
+    let local_url = state.server_addr.replace("://0.0.0.0", "://127.0.0.1");
+    let local_token = state.config.load().security.api_keys.first().cloned();
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval_at(
+            tokio::time::Instant::now() + STARTUP_DELAY,
+            Duration::from_secs(config.interval_minutes * 60),
+        );
+        loop {
+            interval.tick().await;
+            if vm_core::offline::is_offline() {
+                debug!("Skipping replication in offline mode");
+                continue;
+            }
+            for peer in &config.peers {
+                let peer = peer.clone();
+                let local_url = local_url.clone();
+                let local_token = local_token.clone();
+                let url = peer.url.clone();
+                let result = tokio::task::spawn_blocking(move || {
+                    let local = PackageServerClient::new(&local_url).with_auth_token(local_token);
+                    replicate_with_peer(&local, &peer)
+                })
+                .await;
+                match result {
+                    Ok(Ok(report)) if report.copied > 0 || !report.failed.is_empty() => info!(
+                        peer = %url,
+                        copied = report.copied,
+                        failed = report.failed.len(),
+                        "Replication finished"
+                    ),
+                    Ok(Ok(_)) => debug!(peer = %url, "Replication found nothing to copy"),
+                    Ok(Err(e)) => warn!(peer = %url, error = %e, "Replication failed"),
+                    Err(e) => warn!(peer = %url, error = %e, "Replication task failed"),
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(registry: &str, package: &str, version: &str) -> ReplicatedFile {
+        ReplicatedFile {
+            registry: registry.to_string(),
+            package: package.to_string(),
+            version: version.to_string(),
+            filename: format!("{package}-{version}.bin"),
+            path: String::new(),
+            metadata: Value::Null,
+        }
+    }
+
+    #[test]
+    fn test_missing_files_respect_filter_and_order() {
+        let source = vec![
+            file("npm", "left-pad", "1.10.0"),
+            file("npm", "left-pad", "1.9.0"),
+            file("npm", "left-pad", "1.0.0"),
+            file("pypi", "My_Package", "2.0"),
+            file("cargo", "serde", "1.0.0"),
+        ];
+        let target = vec![file("npm", "left-pad", "1.0.0")];
+
+        let all: Vec<_> = missing_files(&source, &target, &ReplicationFilter::default())
+            .into_iter()
+            .map(|f| f.filename.as_str())
+            .collect();
+        assert_eq!(
+            all,
+            [
+                "serde-1.0.0.bin",
+                "left-pad-1.9.0.bin",
+                "left-pad-1.10.0.bin",
+                "My_Package-2.0.bin"
+            ]
+        );
+
+        let filter = ReplicationFilter {
+            registries: vec!["PyPI".to_string()],
+            packages: vec!["my-package".to_string()],
+        };
+        assert_eq!(missing_files(&source, &target, &filter).len(), 1);
+    }
+
+    #[test]
+    fn test_manifest_lists_published_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let data = dir.path();
+        std::fs::create_dir_all(data.join("pypi/packages")).unwrap();
+        std::fs::write(data.join("pypi/packages/demo-1.0.tar.gz"), b"sdist").unwrap();
+
+        std::fs::create_dir_all(data.join("npm/metadata")).unwrap();
+        std::fs::create_dir_all(data.join("npm/tarballs")).unwrap();
+        std::fs::write(
+            data.join("npm/metadata/widget.json"),
+            json!({"versions": {
+                "1.0.0": {"dist": {"tarball": "http://old:3080/npm/widget/-/widget-1.0.0.tgz"}},
+                "2.0.0": {"dist": {"tarball": "http://old:3080/npm/widget/-/widget-2.0.0.tgz"}}
+            }})
+            .to_string(),
+        )
+        .unwrap();
+        std::fs::write(data.join("npm/tarballs/widget-1.0.0.tgz"), b"tgz").unwrap();
+        std::fs::create_dir_all(data.join("npm/metadata/@acme")).unwrap();
+        std::fs::write(
+            data.join("npm/metadata/@acme/gizmo.json"),
+            json!({"versions": {"0.1.0": {}}}).to_string(),
+        )
+        .unwrap();
+        std::fs::write(data.join("npm/tarballs/gizmo-0.1.0.tgz"), b"tgz").unwrap();
+
+        std::fs::create_dir_all(data.join("cargo/index/de/mo")).unwrap();
+        std::fs::create_dir_all(data.join("cargo/crates")).unwrap();
+        std::fs::write(
+            data.join("cargo/index/de/mo/democrate"),
+            "{\"name\":\"democrate\",\"vers\":\"0.1.0\",\"deps\":[],\"features\":{}}\n",
+        )
+        .unwrap();
+        std::fs::write(data.join("cargo/crates/democrate-0.1.0.crate"), b"crate").unwrap();
+
+        let mut paths: Vec<String> = build_manifest(data).into_iter().map(|f| f.path).collect();
+        paths.sort();
+        assert_eq!(
+            paths,
+            [
+                "/cargo/api/v1/crates/democrate/0.1.0/download",
+                "/npm/@acme%2fgizmo/-/gizmo-0.1.0.tgz",
+                "/npm/widget/-/widget-1.0.0.tgz",
+                "/pypi/packages/demo-1.0.tar.gz",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_manifest_requires_read_access_and_hides_private_scopes() {
+        let (state, _temp_dir) = crate::test_utils::create_npm_test_state();
+        let mut state = (*state).clone();
+        let mut config = crate::config::Config::default();
+        config.security.require_authentication = true;
+        config.security.api_keys = vec!["admin-key".to_string()];
+        config.security.private_npm_scopes = vec![crate::config::NpmScopeAcl {
+            scope: "@acme".to_string(),
+            tokens: vec!["ci".to_string()],
+        }];
+        state.config = Arc::new(config).into();
+        let data = state.data_dir.clone();
+        for (document, tarball) in [
+            ("widget.json", "widget-1.0.0.tgz"),
+            ("@acme/gizmo.json", "gizmo-1.0.0.tgz"),
+        ] {
+            let path = data.join("npm/metadata").join(document);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, json!({"versions": {"1.0.0": {}}}).to_string()).unwrap();
+            std::fs::write(data.join("npm/tarballs").join(tarball), b"tgz").unwrap();
+        }
+        let (_, reader) = state
+            .tokens
+            .create("reader", vec![TokenScope::Read], vec![])
+            .unwrap();
+
+        let app = axum::Router::new()
+            .route(MANIFEST_ROUTE, axum::routing::get(manifest))
+            .with_state(Arc::new(state));
+        let server = axum_test::TestServer::new(app).unwrap();
+
+        let response = server.get(MANIFEST_ROUTE).await;
+        assert_eq!(response.status_code(), axum::http::StatusCode::UNAUTHORIZED);
+
+        let packages = |response: axum_test::TestResponse| {
+            let mut packages: Vec<String> = response
+                .json::<Vec<ReplicatedFile>>()
+                .into_iter()
+                .map(|file| file.package)
+                .collect();
+            packages.sort();
+            packages
+        };
+        let response = server
+            .get(MANIFEST_ROUTE)
+            .add_header("authorization", format!("Bearer {reader}"))
+            .await;
+        assert_eq!(packages(response), ["widget"]);
+
+        let response = server
+            .get(MANIFEST_ROUTE)
+            .add_header("authorization", "Bearer admin-key")
+            .await;
+        assert_eq!(packages(response), ["@acme/gizmo", "widget"]);
+    }
+
+    #[test]
+    fn test_npm_document_keeps_existing_versions() {
+        let mut versions = Map::new();
+        versions.insert("1.9.0".to_string(), json!({"version": "1.9.0"}));
+        versions.insert("1.10.0".to_string(), json!({"version": "1.10.0"}));
+        let document = npm_publish_document("widget", &versions, "widget-1.9.0.tgz", b"tgz");
+
+        assert_eq!(document["dist-tags"]["latest"], "1.10.0");
+        assert_eq!(document["versions"].as_object().unwrap().len(), 2);
+        assert_eq!(
+            document["_attachments"]["widget-1.9.0.tgz"]["data"],
+            general_purpose::STANDARD.encode(b"tgz")
+        );
+    }
+}
@@ -0,0 +1,130 @@
+//! # Request IDs
+//!
+//! Assigns every request an ID, taken from a well-formed incoming `x-request-id`
+//! header or generated, and records it on the request's tracing span and on the
+//! response header. Error responses produced by [`AppError`](crate::AppError) are
+//! re-rendered here in the format the calling client understands (see
+//! [`ErrorProtocol`]) with the request ID included, so a user can quote it and
+//! an operator can find the matching log lines.
+
+use axum::{
+    extract::Request,
+    http::{HeaderName, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use tracing::Instrument;
+
+use crate::error::{ApiErrorResponse, ErrorProtocol};
+
+/// Header carrying the request ID in both directions
+pub static REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+/// The ID assigned to the current request, available as a request extension
+#[derive(Debug, Clone)]
+pub struct RequestId(pub String);
+
+/// Accept client-supplied IDs only if they are short and header-safe
+fn incoming_request_id(req: &Request) -> Option<String> {
+    let value = req.headers().get(&REQUEST_ID_HEADER)?.to_str().ok()?;
+    let valid = !value.is_empty()
+        && value.len() <= 128
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    valid.then(|| value.to_string())
+}
+
+/// Middleware assigning request IDs and rendering protocol-appropriate error bodies
+pub async fn request_id_middleware(mut req: Request, next: Next) -> Response {
+    let request_id = incoming_request_id(&req).unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    let protocol = ErrorProtocol::for_path(req.uri().path());
+    req.extensions_mut().insert(RequestId(request_id.clone()));
+
+    let span = tracing::info_span!(
+        "request",
+        request_id = %request_id,
+        method = %req.method(),
+        path = %req.uri().path()
+    );
+    let mut response = next.run(req).instrument(span).await;
+
+    if let Some(mut error) = response.extensions_mut().remove::<ApiErrorResponse>() {
+        error.request_id = Some(request_id.clone());
+        let status = response.status();
+        let headers = std::mem::take(response.headers_mut());
+        response = error.render(protocol, status);
+        for (name, value) in headers.iter() {
+            // Keep headers like WWW-Authenticate; the body's type comes from the new render
+            if name != axum::http::header::CONTENT_TYPE
+                && name != axum::http::header::CONTENT_LENGTH
+            {
+                response.headers_mut().append(name.clone(), value.clone());
+            }
+        }
+    }
+
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        response
+            .headers_mut()
+            .insert(REQUEST_ID_HEADER.clone(), value);
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AppError;
+    use axum::{http::StatusCode, routing::get, Router};
+    use axum_test::TestServer;
+
+    async fn missing() -> Result<(), AppError> {
+        Err(AppError::NotFound("Package not found".to_string()))
+    }
+
+    fn app() -> Router {
+        Router::new()
+            .route("/api/thing", get(missing))
+            .route("/cargo/thing", get(missing))
+            .route("/npm/thing", get(missing))
+            .route("/pypi/thing", get(missing))
+            .layer(axum::middleware::from_fn(request_id_middleware))
+    }
+
+    #[tokio::test]
+    async fn renders_errors_per_protocol_with_request_id() {
+        let server = TestServer::new(app()).unwrap();
+
+        let response = server
+            .get("/api/thing")
+            .add_header(
+                REQUEST_ID_HEADER.clone(),
+                HeaderValue::from_static("req-123"),
+            )
+            .await;
+        response.assert_status(StatusCode::NOT_FOUND);
+        assert_eq!(response.header("x-request-id"), "req-123");
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["code"], "not_found");
+        assert_eq!(body["message"], "Package not found");
+        assert_eq!(body["request_id"], "req-123");
+
+        let body: serde_json::Value = server.get("/cargo/thing").await.json();
+        assert!(body["errors"][0]["detail"]
+            .as_str()
+            .unwrap()
+            .starts_with("Package not found (request id "));
+
+        let body: serde_json::Value = server.get("/npm/thing").await.json();
+        assert!(body["error"]
+            .as_str()
+            .unwrap()
+            .starts_with("Package not found"));
+
+        let response = server.get("/pypi/thing").await;
+        assert!(response
+            .text()
+            .starts_with("Package not found (request id "));
+    }
+}
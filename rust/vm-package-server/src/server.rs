@@ -4,34 +4,57 @@
 //! supporting npm, PyPI, and Cargo package registry operations.
 
 use std::collections::HashMap;
+use std::future::IntoFuture;
 use std::net::SocketAddr;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 use anyhow::Result;
 use axum::{
     extract::{Query, State},
     http::{header, HeaderMap, StatusCode},
-    response::{Html, IntoResponse, Response},
-    routing::{get, post, put},
+    middleware,
+    response::{Html, IntoResponse, Json, Response},
+    routing::{any, delete, get, post, put},
     Router,
 };
 use serde::Deserialize;
-use tokio::net::TcpListener;
 use tracing::{error, info, warn};
 
 use crate::{
-    cargo,
+    auth, cargo,
     config::Config,
-    npm, pypi,
-    registry::{NpmRegistry, PypiRegistry},
-    state::AppState,
+    gomod, helm, lifecycle, npm, npm_audit,
+    package_index::PackageIndex,
+    presets, pypi,
+    registry::{
+        search::{self, SearchPage},
+        NpmRegistry, PypiRegistry,
+    },
+    request_id,
+    state::{AppState, Reloadable},
+    tenants, tls,
+    tokens::{self, TokenStore},
+    trusted_publishing, ui,
     upstream::{UpstreamClient, UpstreamConfig},
+    upstream_policy::{self, UpstreamPolicy},
 };
 use vm_core::validation as core_validation;
 
+/// How the server process is hosted, which decides signal handling and socket handoff
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ServeMode {
+    /// Own process: handles SIGTERM/SIGINT and SIGUSR2 handoffs, writes a pid file
+    Standalone,
+    /// Replaces the standalone server with this pid once the port is bound
+    #[cfg_attr(not(unix), allow(dead_code))]
+    Handoff(u32),
+    /// Hosted inside another process; stops only via the shutdown receiver
+    Embedded,
+}
+
 pub async fn run_server_background(host: String, port: u16, data_dir: PathBuf) -> Result<()> {
-    run_server_with_shutdown(host, port, data_dir, None).await
+    run_server_internal(host, port, data_dir, None, ServeMode::Standalone).await
 }
 
 pub async fn run_server_with_shutdown(
@@ -40,11 +63,136 @@ pub async fn run_server_with_shutdown(
     data_dir: PathBuf,
     shutdown_receiver: Option<tokio::sync::oneshot::Receiver<()>>,
 ) -> Result<()> {
-    run_server_internal(host, port, data_dir, shutdown_receiver).await
+    run_server_internal(host, port, data_dir, shutdown_receiver, ServeMode::Embedded).await
 }
 
 pub async fn run_server(host: String, port: u16, data_dir: PathBuf) -> Result<()> {
-    run_server_internal(host, port, data_dir, None).await
+    run_server_internal(host, port, data_dir, None, ServeMode::Standalone).await
+}
+
+/// Restart the standalone server that uses `data_dir`.
+///
+/// With `graceful`, the new server binds the port alongside the running one and
+/// then asks it to drain, so no connections are refused and in-flight uploads
+/// finish. Otherwise the old server drains and exits before the new one binds.
+pub async fn restart_server(
+    host: String,
+    port: u16,
+    data_dir: PathBuf,
+    graceful: bool,
+) -> Result<()> {
+    #[cfg(unix)]
+    {
+        let previous =
+            lifecycle::read_pid_file(&data_dir).filter(|pid| lifecycle::is_running(*pid));
+        let mode = match previous {
+            Some(pid) if graceful => ServeMode::Handoff(pid),
+            Some(_) => {
+                let drain_timeout = load_config(&data_dir)?.shutdown.drain_timeout();
+                lifecycle::stop_previous(&data_dir, drain_timeout).await?;
+                ServeMode::Standalone
+            }
+            None => {
+                info!("No running server found for this data directory; starting a new one");
+                ServeMode::Standalone
+            }
+        };
+        run_server_internal(host, port, data_dir, None, mode).await
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = (host, port, data_dir, graceful);
+        anyhow::bail!("Restarting the package server requires a Unix platform")
+    }
+}
+
+/// Ask the standalone server that uses `data_dir` to reload `config.json` and
+/// `upstream-policy.json`
+pub fn reload_server(data_dir: PathBuf) -> Result<()> {
+    #[cfg(unix)]
+    {
+        let pid = lifecycle::read_pid_file(&data_dir)
+            .filter(|pid| lifecycle::is_running(*pid))
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "No running server found for data directory {}",
+                    data_dir.display()
+                )
+            })?;
+        // Validate here so mistakes are reported to the caller, not only in the server log
+        let config = load_config(&data_dir)?;
+        build_upstream_client(&config, &data_dir)?;
+        lifecycle::request_reload(pid)?;
+        info!(pid, "Asked server to reload its configuration");
+        Ok(())
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = data_dir;
+        anyhow::bail!("Reloading the package server requires a Unix platform")
+    }
+}
+
+/// Settings for the server in `data_dir`: the defaults, then `config.json`, then
+/// the environment
+fn load_config(data_dir: &Path) -> Result<Config> {
+    let mut config = Config::default();
+    let security_file = data_dir.join("config.json");
+    config.apply_security_file(&security_file).map_err(|e| {
+        error!(path = %security_file.display(), error = %e, "Invalid security, cache, webhook or bandwidth settings");
+        anyhow::anyhow!(
+            "Invalid security, cache, webhook or bandwidth settings in {}: {e}",
+            security_file.display()
+        )
+    })?;
+    config.apply_env_overrides();
+    Ok(config)
+}
+
+fn build_upstream_client(config: &Config, data_dir: &Path) -> Result<UpstreamClient> {
+    Ok(
+        UpstreamClient::new(UpstreamConfig::default().with_upstreams(&config.upstreams))?
+            .with_bandwidth_limits(&config.bandwidth)
+            .with_policy(UpstreamPolicy::open(data_dir, &config.upstream_policy)?),
+    )
+}
+
+/// Reload the configuration on every SIGHUP.
+///
+/// Requests that are already running keep the settings they started with, so
+/// uploads in flight are not interrupted. If the new settings are invalid, the
+/// error is logged and the current settings stay in place.
+#[cfg(unix)]
+fn spawn_reload_task(state: Arc<AppState>, tenants: Arc<tenants::Tenants>) {
+    let Some(mut hangup) = lifecycle::reload_signal() else {
+        return;
+    };
+    tokio::spawn(async move {
+        while hangup.recv().await.is_some() {
+            info!("Received SIGHUP; reloading configuration");
+            match reload(&state, &tenants) {
+                Ok(()) => info!("🔄 Configuration reloaded"),
+                Err(e) => {
+                    error!(error = %e, "Configuration reload failed; keeping the current settings")
+                }
+            }
+        }
+    });
+}
+
+/// Swap in freshly loaded settings and upstream client, then rebuild the
+/// virtual registries on top of them
+#[cfg(unix)]
+fn reload(state: &AppState, tenants: &tenants::Tenants) -> Result<()> {
+    let config = load_config(&state.data_dir)?;
+    let upstream_client = build_upstream_client(&config, &state.data_dir)?;
+    if config.tls != state.config.load().tls {
+        warn!("TLS settings changed; restart the server to apply them");
+    }
+    state.config.store(Arc::new(config));
+    state.upstream_client.store(Arc::new(upstream_client));
+    tenants.reload()?;
+    Ok(())
 }
 
 async fn run_server_internal(
@@ -52,6 +200,7 @@ async fn run_server_internal(
     port: u16,
     data_dir: PathBuf,
     shutdown_receiver: Option<tokio::sync::oneshot::Receiver<()>>,
+    mode: ServeMode,
 ) -> Result<()> {
     info!("🚀 Starting Goobits Package Server...");
 
@@ -83,105 +232,401 @@ async fn run_server_internal(
     info!(host = %host, port = %port, "Starting server");
 
     // Create required components for AppState
-    let upstream_config = UpstreamConfig::default();
-    let upstream_client = Arc::new(UpstreamClient::new(upstream_config)?);
-    let config = Arc::new(Config::default());
-    let server_addr = format!("http://{host}:{port}");
+    let config = load_config(&abs_data_dir)?;
+    if crate::auth::is_auth_required(&config) {
+        info!("🔒 Token authentication enabled for Cargo endpoints");
+    }
+    if let Some(oidc) = &config.security.oidc {
+        info!(issuer = %oidc.issuer, "🔑 Accepting OIDC tokens");
+    }
+    if let Some(ldap) = &config.security.ldap {
+        info!(url = %ldap.url, "🔑 Accepting LDAP credentials");
+    }
+    if !config.security.private_npm_scopes.is_empty()
+        && config.security.api_keys.is_empty()
+        && !config.security.has_identity_provider()
+    {
+        warn!("Private npm scopes are configured but no API keys are set; their packages cannot be read or published");
+    }
+    let upstream_client = Arc::new(build_upstream_client(&config, &abs_data_dir)?);
+    let config = Arc::new(config);
+    let tls = match tls::prepare(&config.tls, &abs_data_dir, &host)? {
+        Some(files) => {
+            let server_config = tls::server_config(&files)?;
+            Some((files, server_config))
+        }
+        None => None,
+    };
+    let scheme = if tls.is_some() { "https" } else { "http" };
+    let server_addr = format!("{scheme}://{host}:{port}");
 
-    let state = AppState {
+    let package_index = Arc::new(PackageIndex::open(&abs_data_dir)?);
+    let tokens = Arc::new(TokenStore::open(&abs_data_dir)?);
+
+    let data_dir_for_pid = abs_data_dir.clone();
+    let state = Arc::new(AppState {
         data_dir: abs_data_dir,
         server_addr,
-        upstream_client,
-        config,
+        upstream_client: upstream_client.into(),
+        config: config.into(),
         npm_registry: NpmRegistry::new(),
         pypi_registry: PypiRegistry::new(),
-    };
+        package_index,
+        tokens,
+    });
+    crate::cache::spawn_gc_task(state.clone());
+    crate::replication::spawn_replication_task(state.clone());
+    crate::mirror::spawn_mirror_task(state.clone());
+
+    let tenants = crate::tenants::Tenants::load(state.clone())?;
+    #[cfg(unix)]
+    if mode != ServeMode::Embedded {
+        spawn_reload_task(state.clone(), tenants.clone());
+    }
+    let drain_config = state.config.clone();
+    let tenant_routes = Router::new()
+        .route("/api/tenants", get(tenants::list_tenants))
+        .route(
+            "/api/tenants/{name}",
+            put(tenants::put_tenant).delete(tenants::delete_tenant),
+        )
+        .route("/r/{tenant}/{*path}", any(tenants::dispatch))
+        .with_state(tenants);
 
     let app = Router::new()
         .route("/", get(index_handler))
-        .route("/status", get(status_handler))
-        .route("/api/status", get(status_handler))
         .route("/setup.sh", get(setup_script_handler))
+        .route("/tls/cert.pem", get(tls::certificate_handler))
         .route("/health", get(health_handler))
-        .route("/api/packages", get(list_packages_handler))
-        .route("/shutdown", post(shutdown_handler))
-        .route("/npm/{package}", put(npm::publish_package))
-        .route("/npm/{package}/-/{filename}", get(npm::download_tarball))
-        .route("/npm/{package}", get(npm::package_metadata))
-        .route("/pypi/simple/{package}/", get(pypi::package_index))
-        .route("/pypi/packages/{filename}", get(pypi::download_file))
-        .route("/pypi/legacy/api/pypi", get(pypi::simple_index))
-        .route("/pypi/legacy/api/pypi/{package}/", get(pypi::package_index))
+        .route("/healthz", get(health_handler))
+        .route("/readyz", get(readyz_handler))
         .route(
-            "/pypi/legacy/api/pypi/{package}/{version}",
-            get(pypi::package_index),
+            crate::replication::MANIFEST_ROUTE,
+            get(crate::replication::manifest),
         )
-        .route("/pypi/upload", put(pypi::upload_package))
-        .route("/cargo/api/v1/crates/new", put(cargo::publish_crate))
+        .route(crate::mirror::SYNC_ROUTE, post(crate::mirror::sync_handler))
         .route(
-            "/cargo/api/v1/crates/{crate}/{version}/download",
-            get(cargo::download_crate),
+            crate::warm::WARM_ROUTE,
+            post(crate::warm::warm_handler).layer(axum::extract::DefaultBodyLimit::max(
+                crate::warm::MAX_LOCKFILE_SIZE,
+            )),
         )
         .route(
-            "/cargo/api/v1/crates/{crate}",
-            get(cargo::get_crate_versions_api),
+            crate::backup::BACKUP_ROUTE,
+            get(crate::backup::backup_handler),
         )
         .route(
-            "/cargo/api/v1/crates/{crate}/{version}",
-            get(cargo::download_crate),
+            crate::backup::RESTORE_ROUTE,
+            post(crate::backup::restore_handler).layer(axum::extract::DefaultBodyLimit::disable()),
+        )
+        .route(
+            "/api/upstream/policy",
+            get(upstream_policy::get_policy).put(upstream_policy::replace_policy),
+        )
+        .route(
+            "/api/upstream/policy/{list}",
+            post(upstream_policy::add_rule),
+        )
+        .route(
+            "/api/upstream/policy/{list}/{registry}/{*package}",
+            delete(upstream_policy::remove_rule),
         )
-        .with_state(Arc::new(state));
+        .route("/shutdown", post(shutdown_handler))
+        // The web UI links with absolute `/ui/...` paths, so it is only served
+        // for the root registry
+        .route("/ui", get(ui::home))
+        .route("/ui/{pkg_type}", get(ui::list_packages))
+        .route("/ui/pypi/{pkg_name}", get(ui::pypi_package_detail))
+        .route("/ui/npm/{*pkg_name}", get(ui::npm_package_detail))
+        .route("/ui/cargo/{pkg_name}", get(ui::cargo_package_detail))
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            crate::external_auth::identity_middleware,
+        ))
+        .with_state(state.clone())
+        .merge(registry_routes(state))
+        .merge(tenant_routes)
+        .merge(crate::openapi::docs_router())
+        .layer(middleware::from_fn(crate::backup::write_gate))
+        .layer(middleware::from_fn(request_id::request_id_middleware));
 
     let addr: SocketAddr = format!("{host}:{port}").parse().map_err(|e| {
         error!(host = %host, port = %port, error = %e, "Invalid socket address");
         anyhow::anyhow!("Invalid socket address {host}:{port}: {e}")
     })?;
 
-    let listener = TcpListener::bind(&addr).await.map_err(|e| {
+    let standalone = mode != ServeMode::Embedded;
+    let listener = match mode {
+        #[cfg(unix)]
+        ServeMode::Handoff(previous) => lifecycle::bind_handoff_listener(addr, previous).await,
+        _ => lifecycle::bind_listener(addr, false),
+    }
+    .map_err(|e| {
         error!(addr = %addr, error = %e, "Failed to bind to address");
-        anyhow::anyhow!("Failed to bind to {host}:{port}: {e}")
+        anyhow::anyhow!("Failed to bind to {host}:{port}: {e:#}")
     })?;
 
-    info!("✅ Server is running on http://{}:{}", host, port);
+    if standalone {
+        if let ServeMode::Handoff(previous) = mode {
+            // Both servers now share the port; the old one stops accepting and drains
+            #[cfg(unix)]
+            lifecycle::request_drain(previous)?;
+            info!(
+                previous_pid = previous,
+                "🔁 Took over listening socket from previous server"
+            );
+        }
+        #[cfg(unix)]
+        if let Err(e) = lifecycle::spawn_handoff_task(&listener) {
+            warn!(error = %e, "Graceful restarts of this server are disabled");
+        }
+        if let Err(e) = lifecycle::write_pid_file(&data_dir_for_pid) {
+            warn!(error = %e, "Could not record server pid; restarts will not find this server");
+        }
+    }
+
+    info!("✅ Server is running on {}://{}:{}", scheme, host, port);
     info!("🌐 Server is accessible at:");
-    info!("   Local:      http://localhost:{}", port);
-    info!("   Network:    http://<your-ip>:{}", port);
+    info!("   Local:      {}://localhost:{}", scheme, port);
+    info!("   Network:    {}://<your-ip>:{}", scheme, port);
     info!("");
     info!("🔧 Configure other machines:");
-    info!("   curl http://<your-ip>:{}/setup.sh | bash", port);
+    info!("   curl {}://<your-ip>:{}/setup.sh | bash", scheme, port);
     info!("");
     info!("📋 Quick commands:");
-    info!("   Status:     curl http://localhost:{}/status", port);
-    info!("   Health:     curl http://localhost:{}/health", port);
-    info!("   Setup:      curl http://localhost:{}/setup.sh", port);
+    info!("   Status:     curl {}://localhost:{}/status", scheme, port);
+    info!(
+        "   Health:     curl {}://localhost:{}/healthz",
+        scheme, port
+    );
+    info!("   Ready:      curl {}://localhost:{}/readyz", scheme, port);
+    info!(
+        "   Setup:      curl {}://localhost:{}/setup.sh",
+        scheme, port
+    );
+    if let Some((files, _)) = &tls {
+        tls::print_trust_instructions(files, port);
+    }
     info!("Server listening on {}", addr);
 
-    match shutdown_receiver {
-        Some(shutdown_rx) => {
-            // Use graceful shutdown when shutdown receiver is provided
-            axum::serve(listener, app)
-                .with_graceful_shutdown(async {
-                    shutdown_rx.await.ok();
-                    info!("Received shutdown signal, stopping server gracefully");
-                })
-                .await
-                .map_err(|e| {
-                    error!(error = %e, "Server error");
-                    anyhow::anyhow!("Server error: {e}")
-                })?;
-        }
-        None => {
-            // Original behavior - run indefinitely
-            axum::serve(listener, app).await.map_err(|e| {
-                error!(error = %e, "Server error");
-                anyhow::anyhow!("Server error: {e}")
-            })?;
+    let shutdown = wait_for_shutdown(shutdown_receiver, standalone);
+    let result = match tls {
+        Some((_, tls_config)) => {
+            let listener = tls::TlsListener::new(listener, tls_config)?;
+            serve_until_drained(listener, app, shutdown, drain_config).await
         }
+        None => serve_until_drained(listener, app, shutdown, drain_config).await,
+    };
+
+    if standalone {
+        lifecycle::remove_pid_file(&data_dir_for_pid);
     }
+    result?;
+    info!("Server stopped");
 
     Ok(())
 }
 
+/// Package routes and the management API of one registry, served at `/` for the
+/// main registry and under `/r/{name}` for each virtual registry
+pub(crate) fn registry_routes(state: Arc<AppState>) -> Router {
+    // Cargo endpoints, including the sparse index, share token authentication so the
+    // registry can be exposed beyond localhost
+    let cargo_routes = Router::new()
+        .route("/cargo/config.json", get(cargo::config))
+        .route("/cargo/api/v1/crates/new", put(cargo::publish_crate))
+        .route(
+            "/cargo/api/v1/crates/{crate}/{version}/download",
+            get(cargo::download_crate),
+        )
+        .route(
+            "/cargo/api/v1/crates/{crate}",
+            get(cargo::get_crate_versions_api),
+        )
+        .route(
+            "/cargo/api/v1/crates/{crate}/{version}",
+            get(cargo::download_crate),
+        )
+        .route(
+            "/cargo/api/v1/crates/{crate}/{version}/yank",
+            delete(cargo::yank_crate),
+        )
+        .route(
+            "/cargo/api/v1/crates/{crate}/{version}/unyank",
+            put(cargo::unyank_crate),
+        )
+        .route(
+            "/cargo/api/v1/crates/{crate}/owners",
+            get(cargo::list_owners)
+                .put(cargo::add_owners)
+                .delete(cargo::remove_owners),
+        )
+        .route("/cargo/{*path}", get(cargo::sparse_index))
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            auth::cargo_auth_middleware,
+        ));
+
+    Router::new()
+        .route("/status", get(status_handler))
+        .route("/api/status", get(status_handler))
+        .route("/api/packages", get(list_packages_handler))
+        .route(
+            "/api/provenance/{registry}/{*package}",
+            get(crate::provenance::provenance_handler),
+        )
+        .route(
+            "/api/{registry}/packages/{name}/latest",
+            get(crate::badges::latest_handler),
+        )
+        .route(
+            "/badge/{registry}/{*name}",
+            get(crate::badges::badge_handler),
+        )
+        .route(
+            "/api/metadata/{registry}/{*package}",
+            get(crate::package_edits::get_edits).patch(crate::package_edits::update_edits),
+        )
+        .route("/api/search", get(search_handler))
+        .route("/api/index/rebuild", post(rebuild_index_handler))
+        .route(
+            "/api/tokens",
+            get(tokens::list_tokens).post(tokens::create_token),
+        )
+        .route("/api/tokens/{id}", delete(tokens::revoke_token))
+        .route(
+            "/api/cargo/crate/{crate}",
+            delete(cargo::delete_all_versions),
+        )
+        .route(
+            "/api/cargo/{crate}/{version}",
+            delete(cargo::delete_crate_version),
+        )
+        .route("/npm/{package}", put(npm::publish_package))
+        .route("/npm/{package}/-/{filename}", get(npm::download_tarball))
+        .route("/npm/{package}", get(npm::package_metadata))
+        .route(
+            "/npm/-/npm/v1/security/advisories/bulk",
+            post(npm_audit::advisories_bulk),
+        )
+        .route("/npm/-/npm/v1/security/audits", post(npm_audit::audits))
+        .route(
+            "/npm/-/npm/v1/security/audits/quick",
+            post(npm_audit::audits_quick),
+        )
+        .route("/pypi/simple/{package}/", get(pypi::package_index))
+        .route("/pypi/packages/{filename}", get(pypi::download_file))
+        .route("/pypi/legacy/api/pypi", get(pypi::simple_index))
+        .route("/pypi/legacy/api/pypi/{package}/", get(pypi::package_index))
+        .route(
+            "/pypi/legacy/api/pypi/{package}/{version}",
+            get(pypi::package_index),
+        )
+        .route("/pypi/", post(pypi::upload_package))
+        .route("/pypi/upload", put(pypi::upload_package))
+        .route("/pypi/_/oidc/audience", get(trusted_publishing::audience))
+        .route(
+            "/pypi/_/oidc/mint-token",
+            post(trusted_publishing::mint_token),
+        )
+        // `cargo login` sends users here to find out how to get a token, so it
+        // stays reachable without one
+        .route("/cargo/me", get(cargo::login_page))
+        .merge(cargo_routes)
+        .route(
+            "/go/{*path}",
+            get(gomod::get_module_file).put(gomod::publish_module_file),
+        )
+        .route("/helm/index.yaml", get(helm::repository_index))
+        .route("/helm/charts/{filename}", get(helm::download_chart))
+        .route("/helm/api/charts", post(helm::upload_chart))
+        .route("/presets/{org}/{name}", get(presets::preset_index))
+        .route(
+            "/presets/{org}/{name}/{version}",
+            get(presets::download_preset).put(presets::publish_preset),
+        )
+        // Each registry verifies OIDC/LDAP credentials against its own settings
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            crate::external_auth::identity_middleware,
+        ))
+        .with_state(state)
+}
+
+/// Serve until `shutdown` resolves, then give in-flight requests up to the
+/// drain timeout configured at that moment to finish
+async fn serve_until_drained<L>(
+    listener: L,
+    app: Router,
+    shutdown: impl std::future::Future<Output = ()> + Send + 'static,
+    config: Reloadable<Config>,
+) -> Result<()>
+where
+    L: axum::serve::Listener,
+    L::Addr: std::fmt::Debug,
+{
+    let (draining_tx, mut draining_rx) = tokio::sync::watch::channel(None);
+    let server = axum::serve(listener, app).with_graceful_shutdown(async move {
+        shutdown.await;
+        let drain_timeout = config.load().shutdown.drain_timeout();
+        info!(
+            "Stopping gracefully; draining in-flight requests for up to {}s",
+            drain_timeout.as_secs()
+        );
+        let _ = draining_tx.send(Some(drain_timeout));
+    });
+
+    tokio::select! {
+        result = server.into_future() => result.map_err(|e| {
+            error!(error = %e, "Server error");
+            anyhow::anyhow!("Server error: {e}")
+        }),
+        _ = async {
+            let drain_timeout = draining_rx
+                .wait_for(Option::is_some)
+                .await
+                .ok()
+                .and_then(|timeout| *timeout);
+            let Some(drain_timeout) = drain_timeout else {
+                // The server finished on its own
+                return std::future::pending().await;
+            };
+            tokio::time::sleep(drain_timeout).await;
+        } => {
+            warn!("Drain timeout elapsed; closing remaining connections");
+            Ok(())
+        }
+    }
+}
+
+/// Resolve on the first shutdown request: the embedding process's receiver or,
+/// for standalone servers, SIGTERM/SIGINT
+async fn wait_for_shutdown(
+    receiver: Option<tokio::sync::oneshot::Receiver<()>>,
+    handle_signals: bool,
+) {
+    let requested = async {
+        match receiver {
+            Some(receiver) => {
+                receiver.await.ok();
+            }
+            None => std::future::pending::<()>().await,
+        }
+    };
+    let signalled = async {
+        if handle_signals {
+            lifecycle::termination_signal().await
+        } else {
+            std::future::pending().await
+        }
+    };
+
+    tokio::select! {
+        _ = requested => info!("Received shutdown signal"),
+        signal = signalled => info!("Received {}", signal),
+    }
+}
+
 async fn index_handler() -> Html<&'static str> {
     Html(include_str!("../static/index.html"))
 }
@@ -201,7 +646,7 @@ async fn status_handler(State(state): State<Arc<AppState>>) -> impl IntoResponse
   "service": "goobits-pkg-server",
   "version": "{}",
   "data_directory": "{}",
-  "registries": ["npm", "pypi", "cargo"]
+  "registries": ["npm", "pypi", "cargo", "go", "helm"]
 }}"#,
         env!("CARGO_PKG_VERSION"),
         data_dir_str
@@ -234,7 +679,22 @@ async fn health_handler() -> impl IntoResponse {
     (StatusCode::OK, headers, response)
 }
 
-async fn list_packages_handler(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+/// Readiness probe: 200 when ready or degraded, 503 when a required check fails
+async fn readyz_handler(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let report = crate::health::readiness(&state).await;
+    let status = if report.is_ready() {
+        StatusCode::OK
+    } else {
+        warn!(checks = ?report.checks, "Package server is not ready");
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (status, Json(report))
+}
+
+async fn list_packages_handler(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
     // Pass data directory directly to avoid thread-unsafe directory changes
     let data_dir = state.data_dir.clone();
     let result =
@@ -243,7 +703,10 @@ async fn list_packages_handler(State(state): State<Arc<AppState>>) -> impl IntoR
 
     match result {
         Ok(inner_result) => match inner_result {
-            Ok(packages) => {
+            Ok(mut packages) => {
+                if let Some(npm) = packages.get_mut("npm") {
+                    npm.retain(|name| auth::may_read_npm(&state, &headers, name));
+                }
                 let json = serde_json::to_string(&packages).unwrap_or_else(|_| "{}".to_string());
                 let mut headers = HeaderMap::new();
                 headers.insert(
@@ -282,6 +745,58 @@ async fn list_packages_handler(State(state): State<Arc<AppState>>) -> impl IntoR
     }
 }
 
+#[derive(Deserialize)]
+struct SearchQuery {
+    #[serde(default)]
+    q: String,
+    page: Option<usize>,
+    per_page: Option<usize>,
+}
+
+/// `GET /api/search?q=<terms>&page=<n>&per_page=<n>` - ranked search across registries
+async fn search_handler(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<SearchQuery>,
+    headers: HeaderMap,
+) -> crate::AppResult<Json<SearchPage>> {
+    let query = params.q.trim();
+    if query.is_empty() {
+        return Err(crate::AppError::BadRequest(
+            "Missing search query: use /api/search?q=<terms>".to_string(),
+        ));
+    }
+    let mut hits = search::search_all(&state, query).await?;
+    hits.retain(|hit| hit.registry != "npm" || auth::may_read_npm(&state, &headers, &hit.name));
+    Ok(Json(SearchPage::paginate(
+        query,
+        hits,
+        params.page.unwrap_or(1),
+        params.per_page.unwrap_or(search::DEFAULT_PER_PAGE),
+    )))
+}
+
+/// `POST /api/index/rebuild` - rebuild the package index from the files on disk
+async fn rebuild_index_handler(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> crate::AppResult<Json<serde_json::Value>> {
+    if auth::is_auth_required(&state.config.load())
+        && !auth::is_authorized(&state.config.load(), &headers)
+    {
+        return Err(crate::AppError::Unauthorized(
+            "A valid API token is required to rebuild the package index".to_string(),
+        ));
+    }
+    let counts = state.package_index.run(|index| index.rebuild_all()).await?;
+    let packages: serde_json::Map<String, serde_json::Value> = counts
+        .into_iter()
+        .map(|(registry, count)| (registry.to_string(), count.into()))
+        .collect();
+    Ok(Json(
+        serde_json::json!({ "rebuilt": true, "packages": packages }),
+    ))
+}
+
 async fn shutdown_handler() -> impl IntoResponse {
     info!("Shutdown endpoint called");
     let response = r#"{"status": "shutdown_initiated"}"#;
@@ -303,11 +818,19 @@ struct SetupQuery {
     port: Option<u16>,
 }
 
-async fn setup_script_handler(Query(params): Query<SetupQuery>) -> Response {
+async fn setup_script_handler(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<SetupQuery>,
+) -> Response {
     let registry = params.registry.as_deref().unwrap_or("npm");
     let port = params.port.unwrap_or(8080);
+    let scheme = if state.config.load().tls.is_enabled() {
+        "https"
+    } else {
+        "http"
+    };
 
-    let script = serve_setup_script(registry, port);
+    let script = serve_setup_script(registry, scheme, port);
 
     let mut headers = HeaderMap::new();
     headers.insert(
@@ -324,8 +847,8 @@ async fn setup_script_handler(Query(params): Query<SetupQuery>) -> Response {
     (StatusCode::OK, headers, script).into_response()
 }
 
-fn serve_setup_script(registry: &str, port: u16) -> String {
-    let server_url = format!("http://$(hostname -I | cut -d' ' -f1):{port}");
+fn serve_setup_script(registry: &str, scheme: &str, port: u16) -> String {
+    let server_url = format!("{scheme}://$(hostname -I | cut -d' ' -f1):{port}");
 
     match registry {
         "npm" => format!(
@@ -435,3 +958,89 @@ echo "   curl {server_url}/setup.sh?registry=cargo | bash"
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::routing::get;
+
+    /// Server with a private `@acme` scope, one package in it and one public package
+    fn private_scope_server() -> (axum_test::TestServer, tempfile::TempDir) {
+        let (state, temp_dir) = crate::test_utils::create_npm_test_state();
+        let mut state = (*state).clone();
+        let mut config = Config::default();
+        config.security.api_keys = vec!["admin-key".to_string()];
+        config.security.private_npm_scopes = vec![crate::config::NpmScopeAcl {
+            scope: "@acme".to_string(),
+            tokens: vec![],
+        }];
+        state.config = Arc::new(config).into();
+        let metadata = state.data_dir.join("npm/metadata");
+        std::fs::create_dir_all(metadata.join("@acme")).unwrap();
+        std::fs::write(metadata.join("@acme/widgets.json"), "{}").unwrap();
+        std::fs::write(metadata.join("widgets-lite.json"), "{}").unwrap();
+        for name in ["@acme/widgets", "widgets-lite"] {
+            state.package_index.record("npm", name, "1.0.0").unwrap();
+        }
+
+        let app = axum::Router::new()
+            .route("/api/packages", get(list_packages_handler))
+            .route("/api/search", get(search_handler))
+            .route(
+                "/api/provenance/{registry}/{*package}",
+                get(crate::provenance::provenance_handler),
+            )
+            .with_state(Arc::new(state));
+        let server = axum_test::TestServer::new(app).expect("should create test server");
+        (server, temp_dir)
+    }
+
+    fn names(values: &serde_json::Value) -> Vec<&str> {
+        values
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|value| {
+                value
+                    .as_str()
+                    .unwrap_or_else(|| value["name"].as_str().unwrap())
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_private_npm_scope_hidden_from_api_without_token() {
+        let (server, _temp_dir) = private_scope_server();
+
+        let packages: serde_json::Value = server.get("/api/packages").await.json();
+        assert_eq!(names(&packages["npm"]), vec!["widgets-lite"]);
+        let search: serde_json::Value = server.get("/api/search?q=widgets").await.json();
+        assert_eq!(names(&search["results"]), vec!["widgets-lite"]);
+        let response = server.get("/api/provenance/npm/@acme/widgets").await;
+        assert_eq!(response.status_code(), StatusCode::UNAUTHORIZED);
+        let response = server.get("/api/provenance/npm/widgets-lite").await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+
+        let admin = "Bearer admin-key";
+        let packages: serde_json::Value = server
+            .get("/api/packages")
+            .add_header("authorization", admin)
+            .await
+            .json();
+        assert_eq!(
+            names(&packages["npm"]),
+            vec!["@acme/widgets", "widgets-lite"]
+        );
+        let search: serde_json::Value = server
+            .get("/api/search?q=widgets")
+            .add_header("authorization", admin)
+            .await
+            .json();
+        assert_eq!(search["total"], 2);
+        let response = server
+            .get("/api/provenance/npm/@acme/widgets")
+            .add_header("authorization", admin)
+            .await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+    }
+}
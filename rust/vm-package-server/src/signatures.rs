@@ -0,0 +1,316 @@
+//! # Upload signatures
+//!
+//! Optional signature enforcement for uploaded packages, configured per
+//! registry in `security.signatures` of `data/config.json`:
+//!
+//! ```json
+//! {
+//!   "security": {
+//!     "signatures": {
+//!       "pypi": { "required": true, "trusted_keys": ["/etc/pkg-server/release-team.asc"] },
+//!       "cargo": { "required": true, "trusted_keys": ["/etc/pkg-server/cosign.pub"] }
+//!     }
+//!   }
+//! }
+//! ```
+//!
+//! - **PyPI**: the detached PGP signature twine sends as the `gpg_signature`
+//!   field (`twine upload --sign`). Verified with `gpg` against `trusted_keys`.
+//! - **npm / Cargo**: a base64 ECDSA P-256 signature of the tarball or `.crate`
+//!   file, as produced by `cosign sign-blob --key cosign.key`, sent in the
+//!   `X-Package-Signature` header. Verified against PEM public keys.
+//!
+//! A signature that is present is always checked when keys are configured, so a
+//! bad signature is rejected even when signatures aren't required. `npm publish`
+//! has no way to send the header, so npm signatures can't be required; see
+//! [`validate_config`].
+
+use crate::config::{SignaturePolicy, UploadSignatureConfig};
+use crate::{AppError, AppResult};
+use axum::http::HeaderMap;
+use base64::{engine::general_purpose, Engine as _};
+use p256::ecdsa::{signature::Verifier, Signature, VerifyingKey};
+use p256::pkcs8::DecodePublicKey;
+use std::path::Path;
+use tracing::{debug, warn};
+
+/// Header carrying the cosign signature of an npm or Cargo upload
+pub const SIGNATURE_HEADER: &str = "x-package-signature";
+
+/// Reject signature settings that would turn away every upload: `npm publish`
+/// cannot attach the `X-Package-Signature` header, so npm only supports checking
+/// signatures that are present.
+pub fn validate_config(config: &UploadSignatureConfig) -> AppResult<()> {
+    if config.npm.required {
+        return Err(AppError::BadRequest(
+            "security.signatures.npm.required is not supported because `npm publish` cannot \
+             send the X-Package-Signature header; configure npm trusted_keys without \
+             `required` to verify signed uploads"
+                .to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Read a trusted key given inline or as a path to a key file
+fn load_key(key: &str) -> AppResult<String> {
+    if key.trim_start().starts_with("-----BEGIN") {
+        return Ok(key.to_string());
+    }
+    std::fs::read_to_string(key).map_err(|e| {
+        AppError::InternalError(format!("Failed to read trusted signing key {key}: {e}"))
+    })
+}
+
+/// Decide whether an upload must be verified, rejecting unsigned uploads when
+/// the policy requires a signature.
+fn needs_check(
+    policy: &SignaturePolicy,
+    registry: &str,
+    name: &str,
+    has_signature: bool,
+) -> AppResult<bool> {
+    if policy.required && policy.trusted_keys.is_empty() {
+        return Err(AppError::InternalError(format!(
+            "{registry} uploads require signatures but no trusted keys are configured"
+        )));
+    }
+    if !has_signature {
+        if policy.required {
+            warn!(registry, package = %name, "Rejected unsigned upload");
+            return Err(AppError::Unauthorized(format!(
+                "{registry} uploads must be signed; '{name}' has no signature"
+            )));
+        }
+        return Ok(false);
+    }
+    Ok(!policy.trusted_keys.is_empty())
+}
+
+fn invalid_signature(registry: &str, name: &str) -> AppError {
+    warn!(registry, package = %name, "Rejected upload with invalid signature");
+    AppError::Unauthorized(format!(
+        "Signature for '{name}' is not valid for any trusted {registry} key"
+    ))
+}
+
+/// Check a PyPI upload's detached PGP signature against the policy.
+pub async fn verify_pypi_upload(
+    policy: &SignaturePolicy,
+    filename: &str,
+    data: &[u8],
+    signature: Option<&[u8]>,
+) -> AppResult<()> {
+    if !needs_check(policy, "PyPI", filename, signature.is_some())? {
+        return Ok(());
+    }
+    let Some(signature) = signature else {
+        return Ok(());
+    };
+
+    let keys = policy
+        .trusted_keys
+        .iter()
+        .map(|key| load_key(key))
+        .collect::<AppResult<Vec<_>>>()?;
+    let data = data.to_vec();
+    let signature = signature.to_vec();
+    let valid = tokio::task::spawn_blocking(move || gpg_verify(&keys, &data, &signature))
+        .await
+        .map_err(|e| AppError::InternalError(format!("Signature check failed: {e}")))??;
+
+    if !valid {
+        return Err(invalid_signature("PyPI", filename));
+    }
+    debug!(filename, "PyPI upload signature verified");
+    Ok(())
+}
+
+/// Verify a detached PGP signature with a throwaway gpg home holding `keys`.
+fn gpg_verify(keys: &[String], data: &[u8], signature: &[u8]) -> AppResult<bool> {
+    let home = tempfile::tempdir()?;
+    let gpg = |args: &[&std::ffi::OsStr]| {
+        std::process::Command::new("gpg")
+            .arg("--homedir")
+            .arg(home.path())
+            .args(["--batch", "--no-tty", "--status-fd", "1"])
+            .args(args)
+            .output()
+            .map_err(|e| {
+                AppError::InternalError(format!(
+                    "gpg is required to verify PyPI upload signatures: {e}"
+                ))
+            })
+    };
+
+    for (i, key) in keys.iter().enumerate() {
+        let key_path = home.path().join(format!("key-{i}.asc"));
+        std::fs::write(&key_path, key)?;
+        let output = gpg(&["--import".as_ref(), key_path.as_os_str()])?;
+        if !output.status.success() {
+            return Err(AppError::InternalError(format!(
+                "Failed to import trusted PyPI signing key #{}: {}",
+                i + 1,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+    }
+
+    let data_path = home.path().join("upload");
+    let signature_path = home.path().join("upload.asc");
+    std::fs::write(&data_path, data)?;
+    std::fs::write(&signature_path, signature)?;
+    let output = gpg(&[
+        "--verify".as_ref(),
+        signature_path.as_os_str(),
+        data_path.as_os_str(),
+    ])?;
+    Ok(output.status.success() && has_valid_signature_status(&output.stdout))
+}
+
+/// Whether gpg's `--status-fd` output reports a good signature
+fn has_valid_signature_status(status: &[u8]) -> bool {
+    String::from_utf8_lossy(status)
+        .lines()
+        .any(|line| line.starts_with("[GNUPG:] VALIDSIG "))
+}
+
+/// Check an npm or Cargo upload's cosign signature header against the policy.
+pub fn verify_blob_upload(
+    policy: &SignaturePolicy,
+    registry: &str,
+    name: &str,
+    data: &[u8],
+    headers: &HeaderMap,
+) -> AppResult<()> {
+    let signature = headers
+        .get(SIGNATURE_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(str::trim)
+        .filter(|value| !value.is_empty());
+    if !needs_check(policy, registry, name, signature.is_some())? {
+        return Ok(());
+    }
+    let Some(signature) = signature else {
+        return Ok(());
+    };
+
+    let signature = general_purpose::STANDARD
+        .decode(signature)
+        .ok()
+        .and_then(|der| Signature::from_der(&der).ok())
+        .ok_or_else(|| {
+            AppError::BadRequest(format!(
+                "{SIGNATURE_HEADER} must be a base64 DER ECDSA signature (cosign sign-blob output)"
+            ))
+        })?;
+
+    for key in &policy.trusted_keys {
+        let pem = load_key(key)?;
+        let key = VerifyingKey::from_public_key_pem(&pem).map_err(|e| {
+            AppError::InternalError(format!(
+                "Trusted {registry} key is not a PEM ECDSA P-256 public key: {e}"
+            ))
+        })?;
+        if key.verify(data, &signature).is_ok() {
+            debug!(registry, package = %name, "Upload signature verified");
+            return Ok(());
+        }
+    }
+    Err(invalid_signature(registry, name))
+}
+
+/// Signature sidecar for a file being uploaded, if one exists next to it
+pub fn sidecar(path: &Path, extension: &str) -> Option<Vec<u8>> {
+    let mut sidecar = path.as_os_str().to_owned();
+    sidecar.push(extension);
+    std::fs::read(sidecar).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use p256::ecdsa::{signature::Signer, SigningKey};
+    use p256::pkcs8::{EncodePublicKey, LineEnding};
+
+    fn signing_key() -> SigningKey {
+        SigningKey::from_slice(&[3u8; 32]).unwrap()
+    }
+
+    fn policy(required: bool) -> SignaturePolicy {
+        SignaturePolicy {
+            required,
+            trusted_keys: vec![signing_key()
+                .verifying_key()
+                .to_public_key_pem(LineEnding::LF)
+                .unwrap()],
+        }
+    }
+
+    fn signed_headers(data: &[u8]) -> HeaderMap {
+        let signature: Signature = signing_key().sign(data);
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            SIGNATURE_HEADER,
+            general_purpose::STANDARD
+                .encode(signature.to_der())
+                .parse()
+                .unwrap(),
+        );
+        headers
+    }
+
+    #[test]
+    fn test_required_npm_signatures_are_rejected() {
+        let mut config = UploadSignatureConfig {
+            cargo: policy(true),
+            ..Default::default()
+        };
+        assert!(validate_config(&config).is_ok());
+
+        config.npm = policy(true);
+        let err = validate_config(&config).unwrap_err();
+        assert!(err.to_string().contains("npm publish"));
+    }
+
+    #[test]
+    fn test_cosign_signature_verification() {
+        let data = b"crate contents";
+        assert!(
+            verify_blob_upload(&policy(true), "Cargo", "demo", data, &signed_headers(data)).is_ok()
+        );
+
+        let err = verify_blob_upload(
+            &policy(false),
+            "Cargo",
+            "demo",
+            b"tampered",
+            &signed_headers(data),
+        )
+        .unwrap_err();
+        assert!(matches!(err, AppError::Unauthorized(_)));
+    }
+
+    #[test]
+    fn test_unsigned_uploads_follow_policy() {
+        let headers = HeaderMap::new();
+        assert!(verify_blob_upload(&policy(false), "npm", "demo", b"x", &headers).is_ok());
+        assert!(matches!(
+            verify_blob_upload(&policy(true), "npm", "demo", b"x", &headers),
+            Err(AppError::Unauthorized(_))
+        ));
+        assert!(
+            verify_blob_upload(&SignaturePolicy::default(), "npm", "demo", b"x", &headers).is_ok()
+        );
+    }
+
+    #[test]
+    fn test_gpg_status_parsing() {
+        assert!(has_valid_signature_status(
+            b"[GNUPG:] NEWSIG\n[GNUPG:] GOODSIG ABC test\n[GNUPG:] VALIDSIG ABCDEF 2024-01-01\n"
+        ));
+        assert!(!has_valid_signature_status(
+            b"[GNUPG:] NEWSIG\n[GNUPG:] BADSIG ABC test\n"
+        ));
+    }
+}
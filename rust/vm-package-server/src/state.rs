@@ -19,6 +19,8 @@
 //! use vm_package_server::upstream::{UpstreamClient, UpstreamConfig};
 //! use vm_package_server::config::Config;
 //! use vm_package_server::registry::{NpmRegistry, PypiRegistry};
+//! use vm_package_server::package_index::PackageIndex;
+//! use vm_package_server::tokens::TokenStore;
 //!
 //! let upstream_config = UpstreamConfig::default();
 //! let upstream_client = Arc::new(UpstreamClient::new(upstream_config)?);
@@ -27,20 +29,65 @@
 //! let state = Arc::new(AppState {
 //!     data_dir: "/path/to/data".into(),
 //!     server_addr: "http://localhost:3080".to_string(),
-//!     upstream_client,
-//!     config,
+//!     upstream_client: upstream_client.into(),
+//!     config: config.into(),
 //!     npm_registry: NpmRegistry::new(),
 //!     pypi_registry: PypiRegistry::new(),
+//!     package_index: Arc::new(PackageIndex::open(std::path::Path::new("/path/to/data"))?),
+//!     tokens: Arc::new(TokenStore::open(std::path::Path::new("/path/to/data"))?),
 //! });
 //! # Ok::<(), Box<dyn std::error::Error>>(())
 //! ```
 
 use crate::config::Config;
+use crate::package_index::PackageIndex;
 use crate::registry::{NpmRegistry, PypiRegistry};
+use crate::tokens::TokenStore;
 use crate::upstream::UpstreamClient;
 use serde::Serialize;
 use std::path::PathBuf;
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
+
+/// A shared value that can be replaced while the server runs.
+///
+/// Readers take a snapshot with [`Reloadable::load`] and keep using it until
+/// they finish, so a reload never changes settings under an in-flight request.
+/// Clones share the same slot.
+pub struct Reloadable<T>(Arc<RwLock<Arc<T>>>);
+
+impl<T> Reloadable<T> {
+    pub fn new(value: Arc<T>) -> Self {
+        Self(Arc::new(RwLock::new(value)))
+    }
+
+    /// The current value
+    pub fn load(&self) -> Arc<T> {
+        match self.0.read() {
+            Ok(value) => value.clone(),
+            Err(poisoned) => poisoned.into_inner().clone(),
+        }
+    }
+
+    /// Replace the value for requests that start from now on
+    pub fn store(&self, value: Arc<T>) {
+        match self.0.write() {
+            Ok(mut slot) => *slot = value,
+            Err(poisoned) => *poisoned.into_inner() = value,
+        }
+    }
+}
+
+impl<T> Clone for Reloadable<T> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<T> From<Arc<T>> for Reloadable<T> {
+    fn from(value: Arc<T>) -> Self {
+        Self::new(value)
+    }
+}
 
 /// Application state containing shared configuration and resources.
 ///
@@ -61,6 +108,8 @@ use std::sync::Arc;
 /// * `config` - Application configuration including security settings
 /// * `npm_registry` - NPM registry implementation using the PackageRegistry trait
 /// * `pypi_registry` - PyPI registry implementation using the PackageRegistry trait
+/// * `package_index` - SQLite index used to count and list packages without scanning storage
+/// * `tokens` - Scoped API tokens issued through `/api/tokens`
 #[derive(Clone)]
 pub struct AppState {
     /// Base directory path where all package files are stored
@@ -68,13 +117,17 @@ pub struct AppState {
     /// Full server address including scheme, host, and port (e.g., "http://localhost:3080")
     pub server_addr: String,
     /// Shared HTTP client for upstream registry communication
-    pub upstream_client: Arc<UpstreamClient>,
+    pub upstream_client: Reloadable<UpstreamClient>,
     /// Application configuration
-    pub config: Arc<Config>,
+    pub config: Reloadable<Config>,
     /// NPM registry implementation
     pub npm_registry: NpmRegistry,
     /// PyPI registry implementation
     pub pypi_registry: PypiRegistry,
+    /// Index of stored packages, kept in sync on upload and delete
+    pub package_index: Arc<PackageIndex>,
+    /// Scoped API tokens, persisted in the data directory
+    pub tokens: Arc<TokenStore>,
 }
 
 /// Standardized success response for API consistency.
@@ -85,7 +138,7 @@ pub struct AppState {
 /// # JSON Format
 ///
 /// Serializes to: `{"message": "Operation completed successfully"}`
-#[derive(Serialize)]
+#[derive(Serialize, utoipa::ToSchema)]
 pub struct SuccessResponse {
     /// Human-readable success message describing the completed operation
     pub message: String,
@@ -1,7 +1,9 @@
 use crate::error::{AppError, AppResult};
 use crate::validation_utils::FileStreamValidator;
+use std::io::SeekFrom;
 use std::path::Path;
 use tokio::fs;
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
 use tracing::{debug, info, warn};
 
 /// Save file content to the specified path atomically
@@ -48,7 +50,9 @@ pub async fn read_file_string<P: AsRef<Path>>(path: P) -> AppResult<String> {
     FileStreamValidator::validate_and_read_file_string(path).await
 }
 
-/// Append content to a file, creating it if it doesn't exist, with size validation
+/// Append content as a line to a file, creating it if it doesn't exist, with size
+/// validation. The existing content is not rewritten, so appends stay cheap as
+/// the file grows.
 pub async fn append_to_file<P: AsRef<Path>, C: AsRef<[u8]>>(path: P, content: C) -> AppResult<()> {
     let path = path.as_ref();
 
@@ -62,7 +66,8 @@ pub async fn append_to_file<P: AsRef<Path>, C: AsRef<[u8]>>(path: P, content: C)
     let content_str = std::str::from_utf8(content)?;
 
     // Check existing file size and validate total size after append
-    let existing_content = if path.exists() {
+    let mut line = String::with_capacity(content_str.len() + 2);
+    if path.exists() {
         let metadata = fs::metadata(path).await?;
         let existing_size = metadata.len();
 
@@ -73,22 +78,31 @@ pub async fn append_to_file<P: AsRef<Path>, C: AsRef<[u8]>>(path: P, content: C)
         let total_size = existing_size + content.len() as u64 + 2; // +2 for potential newlines
         FileStreamValidator::validate_total_upload_size(total_size, "file append")?;
 
-        fs::read_to_string(path).await?
+        // Only the last byte is needed to know whether a line break is missing
+        if existing_size > 0 {
+            let mut file = fs::File::open(path).await?;
+            file.seek(SeekFrom::End(-1)).await?;
+            let mut last = [0u8; 1];
+            file.read_exact(&mut last).await?;
+            if last[0] != b'\n' {
+                line.push('\n');
+            }
+        }
     } else {
         // Use centralized validation for new content size
         FileStreamValidator::validate_total_upload_size(content.len() as u64, "file content")?;
-
-        String::new()
-    };
-
-    let mut new_content = existing_content;
-    if !new_content.is_empty() && !new_content.ends_with('\n') {
-        new_content.push('\n');
     }
-    new_content.push_str(content_str);
-    new_content.push('\n');
-
-    fs::write(path, new_content).await?;
+    line.push_str(content_str);
+    line.push('\n');
+
+    // A single write in append mode so concurrent appends don't interleave
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .await?;
+    file.write_all(line.as_bytes()).await?;
+    file.flush().await?;
     info!(
         path = %path.display(),
         appended_size = content.len(),
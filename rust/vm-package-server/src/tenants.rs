@@ -0,0 +1,449 @@
+//! # Virtual registries
+//!
+//! One server can host several named registries, each with its own packages,
+//! tokens and authentication, so an organization can run a single server
+//! instead of one per team. A registry named `team-a` serves the usual routes
+//! under `/r/team-a`:
+//!
+//! ```text
+//! npm config set registry http://pkg.example.com:3080/r/team-a/npm/
+//! pip install --index-url http://pkg.example.com:3080/r/team-a/pypi/simple/ mypkg
+//! ```
+//!
+//! Each registry keeps its data in `tenants/{name}` in the data directory, and
+//! its `security` settings (API keys, scoped tokens, private npm scopes, ...) in
+//! `tenants/{name}/config.json`. The rest of the configuration, the upstream
+//! client and its policy are shared with the main registry at `/`.
+//!
+//! Admins of the main registry manage virtual registries at runtime:
+//!
+//! - `GET /api/tenants` lists them
+//! - `PUT /api/tenants/{name}` creates one or replaces its `security` settings
+//! - `DELETE /api/tenants/{name}` removes one together with its packages
+
+use crate::config::SecurityConfig;
+use crate::package_index::PackageIndex;
+use crate::registry::{NpmRegistry, PypiRegistry};
+use crate::tokens::TokenStore;
+use crate::{AppError, AppResult, AppState};
+use axum::extract::{Path as AxumPath, Request, State};
+use axum::http::{HeaderMap, Uri};
+use axum::response::{IntoResponse, Response};
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use tower::ServiceExt;
+use tracing::{info, warn};
+
+/// Directory in the data directory holding one directory per virtual registry
+const TENANTS_DIR: &str = "tenants";
+
+/// Prefix of the routes of a virtual registry
+const ROUTE_PREFIX: &str = "/r/";
+
+/// Longest accepted registry name
+const MAX_NAME_LENGTH: usize = 64;
+
+/// Settings file of a virtual registry
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct TenantFile {
+    #[serde(default)]
+    security: SecurityConfig,
+}
+
+struct Tenant {
+    state: Arc<AppState>,
+    router: Router,
+}
+
+/// The virtual registries served next to the main one
+pub struct Tenants {
+    root: Arc<AppState>,
+    tenants: RwLock<BTreeMap<String, Tenant>>,
+}
+
+/// A virtual registry as listed by the admin API
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, utoipa::ToSchema)]
+pub struct TenantSummary {
+    pub name: String,
+    /// Base URL of the registry; clients append `/npm/`, `/pypi/simple/`, ...
+    pub url: String,
+    pub require_authentication: bool,
+    /// Number of admin API keys (the keys themselves are not shown)
+    pub api_keys: usize,
+}
+
+fn validate_name(name: &str) -> AppResult<()> {
+    let valid = !name.is_empty()
+        && name.len() <= MAX_NAME_LENGTH
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        && !name.starts_with(['-', '_']);
+    if valid {
+        Ok(())
+    } else {
+        Err(AppError::BadRequest(format!(
+            "Invalid registry name '{name}': use up to {MAX_NAME_LENGTH} letters, digits, '-' and '_'"
+        )))
+    }
+}
+
+fn tenant_dir(root: &Path, name: &str) -> PathBuf {
+    root.join(TENANTS_DIR).join(name)
+}
+
+impl Tenants {
+    /// Load the virtual registries in `root`'s data directory
+    pub fn load(root: Arc<AppState>) -> AppResult<Arc<Self>> {
+        let tenants = Arc::new(Self {
+            root,
+            tenants: RwLock::new(BTreeMap::new()),
+        });
+        let dir = tenants.root.data_dir.join(TENANTS_DIR);
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            return Ok(tenants);
+        };
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if !entry.path().is_dir() || validate_name(&name).is_err() {
+                continue;
+            }
+            let security = read_security(&entry.path())?;
+            tenants.insert(&name, security)?;
+        }
+        let count = tenants.read().len();
+        if count > 0 {
+            info!(count, "Serving virtual registries under /r/");
+        }
+        Ok(tenants)
+    }
+
+    /// Rebuild every virtual registry from its `config.json` and the main
+    /// registry's current settings, after the configuration was reloaded
+    pub fn reload(&self) -> AppResult<()> {
+        let names: Vec<String> = self.read().keys().cloned().collect();
+        for name in names {
+            let security = read_security(&tenant_dir(&self.root.data_dir, &name))?;
+            self.insert(&name, security)?;
+        }
+        Ok(())
+    }
+
+    fn read(&self) -> std::sync::RwLockReadGuard<'_, BTreeMap<String, Tenant>> {
+        self.tenants.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn write(&self) -> std::sync::RwLockWriteGuard<'_, BTreeMap<String, Tenant>> {
+        self.tenants.write().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Serve (or re-serve) `name` with `security`, keeping its package index and
+    /// tokens if it is already being served
+    fn insert(&self, name: &str, security: SecurityConfig) -> AppResult<TenantSummary> {
+        let data_dir = tenant_dir(&self.root.data_dir, name);
+        std::fs::create_dir_all(&data_dir)?;
+
+        let existing = self.read().get(name).map(|tenant| tenant.state.clone());
+        let (package_index, tokens) = match existing {
+            Some(state) => (state.package_index.clone(), state.tokens.clone()),
+            None => (
+                Arc::new(PackageIndex::open(&data_dir)?),
+                Arc::new(TokenStore::open(&data_dir)?),
+            ),
+        };
+        let mut config = (*self.root.config.load()).clone();
+        config.security = security;
+        let state = Arc::new(AppState {
+            data_dir,
+            server_addr: format!("{}{ROUTE_PREFIX}{name}", self.root.server_addr),
+            upstream_client: self.root.upstream_client.clone(),
+            config: Arc::new(config).into(),
+            npm_registry: NpmRegistry::new(),
+            pypi_registry: PypiRegistry::new(),
+            package_index,
+            tokens,
+        });
+        let summary = summary(name, &state);
+        let router = crate::server::registry_routes(state.clone());
+        self.write()
+            .insert(name.to_string(), Tenant { state, router });
+        Ok(summary)
+    }
+
+    fn router(&self, name: &str) -> Option<Router> {
+        self.read().get(name).map(|tenant| tenant.router.clone())
+    }
+
+    pub fn list(&self) -> Vec<TenantSummary> {
+        self.read()
+            .iter()
+            .map(|(name, tenant)| summary(name, &tenant.state))
+            .collect()
+    }
+}
+
+fn summary(name: &str, state: &AppState) -> TenantSummary {
+    TenantSummary {
+        name: name.to_string(),
+        url: state.server_addr.clone(),
+        require_authentication: state.config.load().security.require_authentication,
+        api_keys: state.config.load().security.api_keys.len(),
+    }
+}
+
+fn read_security(dir: &Path) -> AppResult<SecurityConfig> {
+    let path = dir.join("config.json");
+    if !path.exists() {
+        return Ok(SecurityConfig::default());
+    }
+    let file: TenantFile = serde_json::from_str(&std::fs::read_to_string(&path)?)?;
+    Ok(file.security)
+}
+
+/// Serves a request for a virtual registry with that registry's routes.
+///
+/// # Route
+/// `ANY /r/{tenant}/{path}`
+pub async fn dispatch(
+    State(tenants): State<Arc<Tenants>>,
+    AxumPath((tenant, _)): AxumPath<(String, String)>,
+    mut request: Request,
+) -> Response {
+    let Some(router) = tenants.router(&tenant) else {
+        return AppError::NotFound(format!("No registry named '{tenant}'")).into_response();
+    };
+
+    // Strip the prefix from the raw path so percent-encoded names (`@scope%2fname`) survive
+    let uri = request.uri();
+    let rest = uri
+        .path()
+        .strip_prefix(ROUTE_PREFIX)
+        .and_then(|path| path.split_once('/'))
+        .map_or("", |(_, rest)| rest);
+    let path_and_query = match uri.query() {
+        Some(query) => format!("/{rest}?{query}"),
+        None => format!("/{rest}"),
+    };
+    match path_and_query.parse::<Uri>() {
+        Ok(uri) => *request.uri_mut() = uri,
+        Err(e) => return AppError::BadRequest(format!("Invalid path: {e}")).into_response(),
+    }
+
+    match router.oneshot(request).await {
+        Ok(response) => response,
+        Err(never) => match never {},
+    }
+}
+
+/// Lists the virtual registries.
+///
+/// # Route
+/// `GET /api/tenants`
+pub async fn list_tenants(
+    State(tenants): State<Arc<Tenants>>,
+    headers: HeaderMap,
+) -> AppResult<Json<Vec<TenantSummary>>> {
+    crate::tokens::require_admin(&tenants.root, &headers)?;
+    Ok(Json(tenants.list()))
+}
+
+/// Creates a virtual registry or replaces its security settings.
+///
+/// # Route
+/// `PUT /api/tenants/{name}` with the registry's `security` section as body, e.g.
+/// `{"require_authentication": true, "api_keys": ["team-a-admin-key"]}`
+pub async fn put_tenant(
+    AxumPath(name): AxumPath<String>,
+    State(tenants): State<Arc<Tenants>>,
+    headers: HeaderMap,
+    Json(security): Json<SecurityConfig>,
+) -> AppResult<Json<TenantSummary>> {
+    crate::tokens::require_admin(&tenants.root, &headers)?;
+    validate_name(&name)?;
+    if security.require_authentication && security.api_keys.is_empty() {
+        warn!(tenant = %name, "Virtual registry requires authentication but has no API keys");
+    }
+
+    let dir = tenant_dir(&tenants.root.data_dir, &name);
+    crate::storage::save_file(
+        dir.join("config.json"),
+        serde_json::to_string_pretty(&TenantFile {
+            security: security.clone(),
+        })?
+        .as_bytes(),
+    )
+    .await?;
+    let summary = tenants.insert(&name, security)?;
+    info!(tenant = %name, require_authentication = summary.require_authentication, "Saved virtual registry");
+    Ok(Json(summary))
+}
+
+/// Removes a virtual registry and deletes its packages.
+///
+/// # Route
+/// `DELETE /api/tenants/{name}`
+pub async fn delete_tenant(
+    AxumPath(name): AxumPath<String>,
+    State(tenants): State<Arc<Tenants>>,
+    headers: HeaderMap,
+) -> AppResult<Json<serde_json::Value>> {
+    crate::tokens::require_admin(&tenants.root, &headers)?;
+    validate_name(&name)?;
+    if tenants.write().remove(&name).is_none() {
+        return Err(AppError::NotFound(format!("No registry named '{name}'")));
+    }
+    let dir = tenant_dir(&tenants.root.data_dir, &name);
+    if dir.exists() {
+        tokio::fs::remove_dir_all(&dir).await?;
+    }
+    info!(tenant = %name, "Deleted virtual registry");
+    Ok(Json(serde_json::json!({ "deleted": name })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::{header, StatusCode};
+
+    fn admin_state() -> (Arc<AppState>, tempfile::TempDir) {
+        let (state, dir) = crate::test_utils::create_npm_test_state();
+        let mut config = (*state.config.load()).clone();
+        config.security.api_keys = vec!["root-key".to_string()];
+        let state = Arc::new(AppState {
+            config: Arc::new(config).into(),
+            ..(*state).clone()
+        });
+        (state, dir)
+    }
+
+    fn admin_headers() -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::AUTHORIZATION, "Bearer root-key".parse().unwrap());
+        headers
+    }
+
+    #[test]
+    fn test_validate_name() {
+        assert!(validate_name("teamA").is_ok());
+        assert!(validate_name("team-b_2").is_ok());
+        for name in ["", "..", "team/a", "-team", "a".repeat(65).as_str()] {
+            assert!(validate_name(name).is_err(), "{name:?} should be rejected");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tenants_are_isolated_and_persisted() {
+        let (root, _dir) = admin_state();
+        let tenants = Tenants::load(root.clone()).unwrap();
+
+        let unauthorized = put_tenant(
+            AxumPath("team-a".to_string()),
+            State(tenants.clone()),
+            HeaderMap::new(),
+            Json(SecurityConfig::default()),
+        )
+        .await;
+        assert!(matches!(unauthorized, Err(AppError::Unauthorized(_))));
+
+        let security = SecurityConfig {
+            require_authentication: true,
+            api_keys: vec!["team-a-key".to_string()],
+            ..Default::default()
+        };
+        let Json(summary) = put_tenant(
+            AxumPath("team-a".to_string()),
+            State(tenants.clone()),
+            admin_headers(),
+            Json(security),
+        )
+        .await
+        .unwrap();
+        assert_eq!(summary.url, "http://localhost:3080/r/team-a");
+        assert_eq!(summary.api_keys, 1);
+
+        // The tenant's own key works there, the main registry's doesn't
+        let request = |key: &str| {
+            Request::builder()
+                .uri("/r/team-a/api/tokens")
+                .header(header::AUTHORIZATION, format!("Bearer {key}"))
+                .body(Body::empty())
+                .unwrap()
+        };
+        let ok = dispatch(
+            State(tenants.clone()),
+            AxumPath(("team-a".to_string(), "api/tokens".to_string())),
+            request("team-a-key"),
+        )
+        .await;
+        assert_eq!(ok.status(), StatusCode::OK);
+        let denied = dispatch(
+            State(tenants.clone()),
+            AxumPath(("team-a".to_string(), "api/tokens".to_string())),
+            request("root-key"),
+        )
+        .await;
+        assert_eq!(denied.status(), StatusCode::UNAUTHORIZED);
+
+        let missing = dispatch(
+            State(tenants.clone()),
+            AxumPath(("team-b".to_string(), "npm/x".to_string())),
+            Request::builder()
+                .uri("/r/team-b/npm/x")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+        assert_eq!(missing.status(), StatusCode::NOT_FOUND);
+
+        // Reloaded from disk with the same settings
+        let reloaded = Tenants::load(root.clone()).unwrap();
+        assert_eq!(reloaded.list(), tenants.list());
+
+        let Json(deleted) = delete_tenant(
+            AxumPath("team-a".to_string()),
+            State(tenants.clone()),
+            admin_headers(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(deleted["deleted"], "team-a");
+        assert!(tenants.list().is_empty());
+        assert!(!tenant_dir(&root.data_dir, "team-a").exists());
+    }
+
+    #[test]
+    fn test_reload_applies_new_settings() {
+        let (root, _dir) = admin_state();
+        let tenants = Tenants::load(root.clone()).unwrap();
+        let dir = tenant_dir(&root.data_dir, "team-a");
+        tenants.insert("team-a", SecurityConfig::default()).unwrap();
+        let before = tenants.read()["team-a"].state.config.load();
+
+        let mut config = (*root.config.load()).clone();
+        config.quotas.max_upload_mb_per_token = 5;
+        root.config.store(Arc::new(config));
+        let file = TenantFile {
+            security: SecurityConfig {
+                api_keys: vec!["team-a-key".to_string()],
+                ..Default::default()
+            },
+        };
+        std::fs::write(
+            dir.join("config.json"),
+            serde_json::to_string(&file).unwrap(),
+        )
+        .unwrap();
+        tenants.reload().unwrap();
+
+        let after = tenants.read()["team-a"].state.config.load();
+        assert_eq!(after.quotas.max_upload_mb_per_token, 5);
+        assert_eq!(after.security.api_keys, ["team-a-key"]);
+        // Requests that started earlier keep their settings
+        assert_eq!(before.quotas.max_upload_mb_per_token, 0);
+        assert!(before.security.api_keys.is_empty());
+    }
+}
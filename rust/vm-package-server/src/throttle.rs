@@ -0,0 +1,164 @@
+//! # Upstream bandwidth limits
+//!
+//! Token-bucket throttling for package downloads from upstream registries, so
+//! warming the cache on a new machine doesn't saturate a shared connection.
+//! Limits are configured in the `bandwidth` section of `data/config.json`:
+//!
+//! ```json
+//! {
+//!   "bandwidth": {
+//!     "max_kbps": 4096,
+//!     "registries": { "npm": 1024, "pypi": 2048 }
+//!   }
+//! }
+//! ```
+//!
+//! `max_kbps` caps all upstream downloads together; `registries` adds a cap per
+//! ecosystem (`pypi`, `npm`, `cargo`, `go`). A download is held to whichever
+//! limit is tighter. Only package files are throttled; index and metadata
+//! requests are small and stay fast.
+
+use crate::config::BandwidthConfig;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+use tracing::warn;
+
+/// Registries that upstream downloads are accounted against. Helm charts are
+/// never fetched from upstream, so they are left out.
+const REGISTRIES: [&str; 4] = ["pypi", "npm", "cargo", "go"];
+
+/// A token bucket refilled at a fixed byte rate.
+///
+/// Callers reserve bytes up front and sleep off any deficit, which keeps
+/// concurrent downloads sharing the limit fairly.
+#[derive(Debug)]
+pub struct RateLimiter {
+    bytes_per_sec: f64,
+    bucket: Mutex<Bucket>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    available: f64,
+    updated: Instant,
+}
+
+impl RateLimiter {
+    /// Create a limiter allowing `kbps` KiB per second with a one second burst
+    pub fn new(kbps: u64) -> Self {
+        let bytes_per_sec = (kbps * 1024) as f64;
+        Self {
+            bytes_per_sec,
+            bucket: Mutex::new(Bucket {
+                available: bytes_per_sec,
+                updated: Instant::now(),
+            }),
+        }
+    }
+
+    /// Reserve `bytes`, returning how long the caller must wait before using them
+    async fn reserve(&self, bytes: usize) -> Duration {
+        let mut bucket = self.bucket.lock().await;
+        let now = Instant::now();
+        let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+        bucket.available =
+            (bucket.available + elapsed * self.bytes_per_sec).min(self.bytes_per_sec);
+        bucket.updated = now;
+        bucket.available -= bytes as f64;
+        if bucket.available < 0.0 {
+            Duration::from_secs_f64(-bucket.available / self.bytes_per_sec)
+        } else {
+            Duration::ZERO
+        }
+    }
+}
+
+/// Global and per-registry limits for upstream downloads
+#[derive(Debug, Default)]
+pub struct UpstreamThrottle {
+    global: Option<RateLimiter>,
+    registries: HashMap<String, RateLimiter>,
+}
+
+impl UpstreamThrottle {
+    /// Build limiters from configuration; zero means unlimited
+    pub fn new(config: &BandwidthConfig) -> Self {
+        let registries = config
+            .registries
+            .iter()
+            .filter(|(_, kbps)| **kbps > 0)
+            .filter_map(|(registry, kbps)| {
+                let registry = registry.to_ascii_lowercase();
+                if REGISTRIES.contains(&registry.as_str()) {
+                    Some((registry, RateLimiter::new(*kbps)))
+                } else {
+                    warn!(registry = %registry, "Ignoring bandwidth limit for unknown registry");
+                    None
+                }
+            })
+            .collect();
+        Self {
+            global: (config.max_kbps > 0).then(|| RateLimiter::new(config.max_kbps)),
+            registries,
+        }
+    }
+
+    /// Whether downloads from `registry` are throttled at all
+    pub fn is_limited(&self, registry: &str) -> bool {
+        self.global.is_some() || self.registries.contains_key(registry)
+    }
+
+    /// Account for `bytes` downloaded from `registry`, sleeping as needed
+    pub async fn consume(&self, registry: &str, bytes: usize) {
+        let mut wait = Duration::ZERO;
+        if let Some(global) = &self.global {
+            wait = wait.max(global.reserve(bytes).await);
+        }
+        if let Some(limiter) = self.registries.get(registry) {
+            wait = wait.max(limiter.reserve(bytes).await);
+        }
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_reservations_beyond_burst_must_wait() {
+        let limiter = RateLimiter::new(1);
+        assert_eq!(limiter.reserve(1024).await, Duration::ZERO);
+        let wait = limiter.reserve(512).await;
+        assert!(
+            wait > Duration::from_millis(400) && wait <= Duration::from_millis(500),
+            "unexpected wait {wait:?}"
+        );
+    }
+
+    #[test]
+    fn test_limits_from_config() {
+        let config = BandwidthConfig {
+            max_kbps: 0,
+            registries: HashMap::from([
+                ("NPM".to_string(), 512),
+                ("pypi".to_string(), 0),
+                ("rubygems".to_string(), 64),
+            ]),
+        };
+        let throttle = UpstreamThrottle::new(&config);
+        assert!(throttle.is_limited("npm"));
+        assert!(!throttle.is_limited("pypi"));
+        assert!(!throttle.is_limited("rubygems"));
+
+        let global = UpstreamThrottle::new(&BandwidthConfig {
+            max_kbps: 2048,
+            ..Default::default()
+        });
+        assert!(global.is_limited("cargo"));
+        assert!(!UpstreamThrottle::default().is_limited("go"));
+    }
+}
@@ -0,0 +1,349 @@
+//! # Native TLS
+//!
+//! Serves HTTPS directly, so pip, npm and cargo on other machines can use the
+//! registry without a reverse proxy in front of it. Configure a certificate in
+//! the `tls` section of `data/config.json`:
+//!
+//! ```json
+//! { "tls": { "cert_path": "/etc/pkg/fullchain.pem", "key_path": "/etc/pkg/privkey.pem" } }
+//! ```
+//!
+//! or let the server generate a self-signed one:
+//!
+//! ```json
+//! { "tls": { "self_signed": true, "hostnames": ["pkg.lan", "192.168.1.20"] } }
+//! ```
+//!
+//! Self-signed certificates are stored in `data/tls/` and reused across restarts,
+//! so clients only need to trust them once. They cover `localhost`, the loopback
+//! addresses, the bind address, this machine's LAN address and `hostnames`; the
+//! certificate is regenerated when that list changes. The server prints how to
+//! trust it at startup and serves it at `GET /tls/cert.pem`.
+
+use crate::config::TlsConfig;
+use crate::state::AppState;
+use crate::AppError;
+use anyhow::{Context, Result};
+use axum::{
+    extract::State,
+    http::header,
+    response::{IntoResponse, Response},
+};
+use chrono::Datelike;
+use std::collections::HashSet;
+use std::net::{IpAddr, SocketAddr, UdpSocket};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use tokio_rustls::rustls::ServerConfig;
+use tokio_rustls::server::TlsStream;
+use tokio_rustls::TlsAcceptor;
+use tracing::{debug, info, warn};
+
+/// Directory in the data directory holding the self-signed certificate
+pub const SELF_SIGNED_DIR: &str = "tls";
+
+/// Self-signed certificates stay within the 825 days Apple platforms accept
+const SELF_SIGNED_VALIDITY_DAYS: i64 = 825;
+
+/// Handshakes slower than this are dropped so they can't pile up
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Handshaken connections waiting to be served
+const ACCEPT_QUEUE: usize = 128;
+
+/// Certificate and key the server presents
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TlsFiles {
+    pub cert: PathBuf,
+    pub key: PathBuf,
+    /// Generated by the server rather than configured
+    pub self_signed: bool,
+}
+
+/// Certificate files to serve, generating a self-signed certificate if configured.
+///
+/// Returns `None` when TLS is off. `host` is the address the server binds to.
+pub fn prepare(config: &TlsConfig, data_dir: &Path, host: &str) -> Result<Option<TlsFiles>> {
+    if let (Some(cert), Some(key)) = (&config.cert_path, &config.key_path) {
+        return Ok(Some(TlsFiles {
+            cert: data_dir.join(cert),
+            key: data_dir.join(key),
+            self_signed: false,
+        }));
+    }
+    if !config.self_signed {
+        return Ok(None);
+    }
+
+    let dir = data_dir.join(SELF_SIGNED_DIR);
+    let files = TlsFiles {
+        cert: dir.join("cert.pem"),
+        key: dir.join("key.pem"),
+        self_signed: true,
+    };
+    let names = certificate_names(config, host);
+    let names_file = dir.join("names");
+    let current = std::fs::read_to_string(&names_file).unwrap_or_default();
+    if files.cert.exists() && files.key.exists() && current == names.join("\n") {
+        debug!(path = %files.cert.display(), "Reusing self-signed certificate");
+        return Ok(Some(files));
+    }
+
+    info!(names = ?names, "🔐 Generating self-signed TLS certificate");
+    let (cert_pem, key_pem) = generate_self_signed(&names)?;
+    std::fs::create_dir_all(&dir)?;
+    write_private(&files.key, key_pem.as_bytes())?;
+    std::fs::write(&files.cert, cert_pem)?;
+    std::fs::write(&names_file, names.join("\n"))?;
+    Ok(Some(files))
+}
+
+/// Names the self-signed certificate is issued for
+fn certificate_names(config: &TlsConfig, host: &str) -> Vec<String> {
+    let mut names = vec![
+        "localhost".to_string(),
+        "127.0.0.1".to_string(),
+        "::1".to_string(),
+    ];
+    match host.parse::<IpAddr>() {
+        Ok(ip) if ip.is_unspecified() => names.extend(lan_address().map(|ip| ip.to_string())),
+        _ => names.push(host.to_string()),
+    }
+    names.extend(config.hostnames.iter().map(|name| name.trim().to_string()));
+    names.retain(|name| !name.is_empty());
+    let mut seen = HashSet::new();
+    names.retain(|name| seen.insert(name.clone()));
+    names
+}
+
+/// The address other machines reach this one on, found by routing (not sending)
+/// a UDP packet towards a public address
+fn lan_address() -> Option<IpAddr> {
+    let socket = UdpSocket::bind("0.0.0.0:0").ok()?;
+    socket.connect("192.0.2.1:80").ok()?;
+    let ip = socket.local_addr().ok()?.ip();
+    (!ip.is_loopback() && !ip.is_unspecified()).then_some(ip)
+}
+
+/// A certificate and PKCS#8 key, both PEM encoded
+fn generate_self_signed(names: &[String]) -> Result<(String, String)> {
+    let mut params = rcgen::CertificateParams::new(names.to_vec())
+        .context("Invalid host name for the self-signed certificate")?;
+    params
+        .distinguished_name
+        .push(rcgen::DnType::CommonName, "Goobits Package Server");
+    let today = chrono::Utc::now().date_naive();
+    let expires = today + chrono::Duration::days(SELF_SIGNED_VALIDITY_DAYS);
+    params.not_before = rcgen::date_time_ymd(today.year(), today.month() as u8, today.day() as u8);
+    params.not_after =
+        rcgen::date_time_ymd(expires.year(), expires.month() as u8, expires.day() as u8);
+
+    let key = rcgen::KeyPair::generate().context("Failed to generate TLS key")?;
+    let cert = params
+        .self_signed(&key)
+        .context("Failed to sign TLS certificate")?;
+    Ok((cert.pem(), key.serialize_pem()))
+}
+
+/// Write a file readable only by its owner
+fn write_private(path: &Path, content: &[u8]) -> std::io::Result<()> {
+    #[cfg(unix)]
+    {
+        use std::io::Write;
+        use std::os::unix::fs::OpenOptionsExt;
+        let mut file = std::fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .mode(0o600)
+            .open(path)?;
+        file.write_all(content)
+    }
+    #[cfg(not(unix))]
+    {
+        std::fs::write(path, content)
+    }
+}
+
+/// Load the certificate chain and key into a rustls server configuration
+pub fn server_config(files: &TlsFiles) -> Result<Arc<ServerConfig>> {
+    let read = |path: &Path| {
+        std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))
+    };
+    let certs: Vec<CertificateDer<'static>> =
+        rustls_pemfile::certs(&mut read(&files.cert)?.as_slice())
+            .collect::<Result<_, _>>()
+            .with_context(|| format!("Invalid certificate in {}", files.cert.display()))?;
+    if certs.is_empty() {
+        anyhow::bail!("No certificate found in {}", files.cert.display());
+    }
+    let key: PrivateKeyDer<'static> =
+        rustls_pemfile::private_key(&mut read(&files.key)?.as_slice())
+            .with_context(|| format!("Invalid private key in {}", files.key.display()))?
+            .with_context(|| format!("No private key found in {}", files.key.display()))?;
+
+    let mut config = ServerConfig::builder_with_provider(Arc::new(
+        tokio_rustls::rustls::crypto::ring::default_provider(),
+    ))
+    .with_safe_default_protocol_versions()
+    .context("Failed to set up TLS")?
+    .with_no_client_auth()
+    .with_single_cert(certs, key)
+    .context("TLS certificate and key do not match")?;
+    config.alpn_protocols = vec![b"http/1.1".to_vec()];
+    Ok(Arc::new(config))
+}
+
+/// Log how clients can trust a self-signed certificate
+pub fn print_trust_instructions(files: &TlsFiles, port: u16) {
+    if !files.self_signed {
+        return;
+    }
+    let cert = files.cert.display();
+    info!("🔐 Serving HTTPS with a self-signed certificate: {}", cert);
+    info!("   Trust it on other machines (download: curl -k https://<your-ip>:{}/tls/cert.pem -o pkg-server.pem):", port);
+    info!("   pip:    pip config set global.cert /path/to/pkg-server.pem");
+    info!("   npm:    npm config set cafile /path/to/pkg-server.pem");
+    info!("   cargo:  export CARGO_HTTP_CAINFO=/path/to/pkg-server.pem");
+    info!("   pkg-server / vm:  export VM_CA_BUNDLE=/path/to/pkg-server.pem");
+    info!("   System: sudo cp pkg-server.pem /usr/local/share/ca-certificates/pkg-server.crt && sudo update-ca-certificates");
+}
+
+/// A listener that completes TLS handshakes before handing connections to axum.
+///
+/// Handshakes run in their own tasks so one slow client can't hold up the others.
+pub struct TlsListener {
+    connections: mpsc::Receiver<(TlsStream<TcpStream>, SocketAddr)>,
+    local_addr: SocketAddr,
+    acceptor_task: tokio::task::JoinHandle<()>,
+}
+
+impl TlsListener {
+    pub fn new(listener: TcpListener, config: Arc<ServerConfig>) -> std::io::Result<Self> {
+        let local_addr = listener.local_addr()?;
+        let acceptor = TlsAcceptor::from(config);
+        let (sender, connections) = mpsc::channel(ACCEPT_QUEUE);
+        let acceptor_task = tokio::spawn(async move {
+            loop {
+                let (stream, addr) = match listener.accept().await {
+                    Ok(accepted) => accepted,
+                    Err(e) => {
+                        // Usually running out of file descriptors; back off instead of spinning
+                        warn!(error = %e, "Failed to accept connection");
+                        tokio::time::sleep(Duration::from_millis(100)).await;
+                        continue;
+                    }
+                };
+                let acceptor = acceptor.clone();
+                let sender = sender.clone();
+                tokio::spawn(async move {
+                    match tokio::time::timeout(HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await {
+                        Ok(Ok(tls)) => {
+                            let _ = sender.send((tls, addr)).await;
+                        }
+                        Ok(Err(e)) => debug!(peer = %addr, error = %e, "TLS handshake failed"),
+                        Err(_) => debug!(peer = %addr, "TLS handshake timed out"),
+                    }
+                });
+            }
+        });
+        Ok(Self {
+            connections,
+            local_addr,
+            acceptor_task,
+        })
+    }
+}
+
+impl Drop for TlsListener {
+    fn drop(&mut self) {
+        self.acceptor_task.abort();
+    }
+}
+
+impl axum::serve::Listener for TlsListener {
+    type Io = TlsStream<TcpStream>;
+    type Addr = SocketAddr;
+
+    async fn accept(&mut self) -> (Self::Io, Self::Addr) {
+        match self.connections.recv().await {
+            Some(connection) => connection,
+            // The acceptor task only stops when the listener is dropped
+            None => std::future::pending().await,
+        }
+    }
+
+    fn local_addr(&self) -> std::io::Result<Self::Addr> {
+        Ok(self.local_addr)
+    }
+}
+
+/// Serves the certificate so clients can download it to trust.
+///
+/// # Route
+/// `GET /tls/cert.pem`
+pub async fn certificate_handler(State(state): State<Arc<AppState>>) -> Response {
+    let config = &state.config.load().tls;
+    let path = match &config.cert_path {
+        Some(cert) if config.key_path.is_some() => state.data_dir.join(cert),
+        _ if config.self_signed => state.data_dir.join(SELF_SIGNED_DIR).join("cert.pem"),
+        _ => return AppError::NotFound("This server does not use TLS".to_string()).into_response(),
+    };
+    match tokio::fs::read(&path).await {
+        Ok(pem) => ([(header::CONTENT_TYPE, "application/x-pem-file")], pem).into_response(),
+        Err(e) => AppError::from(e).into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_self_signed_certificate_is_reused() {
+        let dir = TempDir::new().unwrap();
+        let config = TlsConfig {
+            self_signed: true,
+            hostnames: vec!["pkg.lan".to_string()],
+            ..Default::default()
+        };
+        let files = prepare(&config, dir.path(), "127.0.0.1").unwrap().unwrap();
+        assert!(files.self_signed);
+        server_config(&files).unwrap();
+        let first = std::fs::read(&files.cert).unwrap();
+
+        prepare(&config, dir.path(), "127.0.0.1").unwrap();
+        assert_eq!(std::fs::read(&files.cert).unwrap(), first);
+
+        // A new host name needs a new certificate
+        let renamed = TlsConfig {
+            hostnames: vec!["pkg.example".to_string()],
+            ..config
+        };
+        prepare(&renamed, dir.path(), "127.0.0.1").unwrap();
+        assert_ne!(std::fs::read(&files.cert).unwrap(), first);
+    }
+
+    #[test]
+    fn test_configured_paths_and_disabled() {
+        let dir = TempDir::new().unwrap();
+        assert!(prepare(&TlsConfig::default(), dir.path(), "0.0.0.0")
+            .unwrap()
+            .is_none());
+
+        let config = TlsConfig {
+            cert_path: Some("certs/fullchain.pem".into()),
+            key_path: Some("/etc/pkg/key.pem".into()),
+            ..Default::default()
+        };
+        let files = prepare(&config, dir.path(), "0.0.0.0").unwrap().unwrap();
+        assert_eq!(files.cert, dir.path().join("certs/fullchain.pem"));
+        assert_eq!(files.key, PathBuf::from("/etc/pkg/key.pem"));
+        assert!(server_config(&files).is_err());
+    }
+}
@@ -0,0 +1,386 @@
+//! # Scoped API Tokens
+//!
+//! Tokens issued through `/api/tokens` let team members publish, delete or read
+//! without sharing the server's admin API keys. Each token carries a set of
+//! [`TokenScope`]s and may be restricted to particular registries. Tokens are
+//! persisted in `tokens.json` in the data directory; only a SHA-256 hash of each
+//! secret is stored, so the secret is shown once, when the token is created.
+//!
+//! Managing tokens requires one of the admin keys from `security.api_keys` (or
+//! `PKG_SERVER_API_KEYS`).
+
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+use axum::{
+    extract::{Path as AxumPath, State},
+    http::{HeaderMap, StatusCode},
+    response::Json,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tracing::info;
+
+use crate::hash_utils::sha256_hash;
+use crate::{AppError, AppResult, AppState};
+
+/// Registries a token can be restricted to
+pub const REGISTRIES: &[&str] = &["cargo", "npm", "pypi", "go", "helm", "presets"];
+
+/// What a token is allowed to do
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, utoipa::ToSchema,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum TokenScope {
+    /// Read from registries that require authentication (the Cargo index and downloads)
+    Read,
+    /// Publish new packages and versions
+    Publish,
+    /// Delete or yank published packages
+    Delete,
+}
+
+/// A stored API token
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiToken {
+    pub id: String,
+    pub name: String,
+    /// SHA-256 of the secret
+    token_hash: String,
+    pub scopes: Vec<TokenScope>,
+    /// Registries the token may be used with; empty means all
+    #[serde(default)]
+    pub registries: Vec<String>,
+    pub created_at: String,
+}
+
+impl ApiToken {
+    /// Whether this token grants `scope` on `registry`
+    pub fn allows(&self, scope: TokenScope, registry: &str) -> bool {
+        self.scopes.contains(&scope)
+            && (self.registries.is_empty() || self.registries.iter().any(|r| r == registry))
+    }
+
+    /// Public description of the token, without its hash
+    fn summary(&self) -> Value {
+        json!({
+            "id": self.id,
+            "name": self.name,
+            "scopes": self.scopes,
+            "registries": self.registries,
+            "created_at": self.created_at,
+        })
+    }
+}
+
+/// Tokens persisted in `<data_dir>/tokens.json`
+pub struct TokenStore {
+    path: PathBuf,
+    tokens: RwLock<Vec<ApiToken>>,
+}
+
+impl TokenStore {
+    /// Load the token file from `data_dir`, starting empty if there is none
+    pub fn open(data_dir: &Path) -> AppResult<Self> {
+        let path = data_dir.join("tokens.json");
+        let tokens = match std::fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Self {
+            path,
+            tokens: RwLock::new(tokens),
+        })
+    }
+
+    /// Data directory of the registry these tokens belong to
+    pub fn registry_dir(&self) -> &Path {
+        self.path.parent().unwrap_or(Path::new("."))
+    }
+
+    /// Re-read `tokens.json`, after it was replaced on disk (e.g. by a restore)
+    pub fn reload(&self) -> AppResult<()> {
+        let reloaded = Self::open(self.registry_dir())?;
+        let tokens = reloaded.tokens.into_inner().unwrap_or_default();
+        *self.write_lock()? = tokens;
+        Ok(())
+    }
+
+    /// Issue a new token, returning its record and the secret to hand to the user
+    pub fn create(
+        &self,
+        name: &str,
+        scopes: Vec<TokenScope>,
+        registries: Vec<String>,
+    ) -> AppResult<(ApiToken, String)> {
+        let secret = format!(
+            "vmpkg_{}{}",
+            uuid::Uuid::new_v4().simple(),
+            uuid::Uuid::new_v4().simple()
+        );
+        let token = ApiToken {
+            id: uuid::Uuid::new_v4().simple().to_string()[..12].to_string(),
+            name: name.to_string(),
+            token_hash: sha256_hash(secret.as_bytes()),
+            scopes,
+            registries,
+            created_at: chrono::Utc::now().to_rfc3339(),
+        };
+
+        let mut tokens = self.write_lock()?;
+        tokens.push(token.clone());
+        if let Err(e) = self.save(&tokens) {
+            tokens.pop();
+            return Err(e);
+        }
+        Ok((token, secret))
+    }
+
+    /// All stored tokens, oldest first
+    pub fn list(&self) -> Vec<ApiToken> {
+        self.tokens
+            .read()
+            .map(|tokens| tokens.clone())
+            .unwrap_or_default()
+    }
+
+    /// Remove the token with `id`; returns whether it existed
+    pub fn revoke(&self, id: &str) -> AppResult<bool> {
+        let mut tokens = self.write_lock()?;
+        let Some(position) = tokens.iter().position(|token| token.id == id) else {
+            return Ok(false);
+        };
+        let removed = tokens.remove(position);
+        if let Err(e) = self.save(&tokens) {
+            tokens.insert(position, removed);
+            return Err(e);
+        }
+        Ok(true)
+    }
+
+    /// Look up the token matching a presented secret
+    pub fn find(&self, secret: &str) -> Option<ApiToken> {
+        let hash = sha256_hash(secret.as_bytes());
+        self.tokens.read().ok()?.iter().find_map(|token| {
+            crate::auth::tokens_match(&token.token_hash, &hash).then(|| token.clone())
+        })
+    }
+
+    fn write_lock(&self) -> AppResult<std::sync::RwLockWriteGuard<'_, Vec<ApiToken>>> {
+        self.tokens
+            .write()
+            .map_err(|_| AppError::InternalError("Token store is unavailable".to_string()))
+    }
+
+    fn save(&self, tokens: &[ApiToken]) -> AppResult<()> {
+        let temp = self.path.with_extension("json.tmp");
+        std::fs::write(&temp, serde_json::to_vec_pretty(tokens)?)?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&temp, std::fs::Permissions::from_mode(0o600))?;
+        }
+        std::fs::rename(&temp, &self.path)?;
+        Ok(())
+    }
+}
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct CreateTokenRequest {
+    name: String,
+    scopes: Vec<TokenScope>,
+    #[serde(default)]
+    registries: Vec<String>,
+}
+
+/// Token and policy management is limited to holders of an admin API key
+pub(crate) fn require_admin(state: &AppState, headers: &HeaderMap) -> AppResult<()> {
+    if state.config.load().security.api_keys.is_empty() {
+        return Err(AppError::Unauthorized(
+            "This endpoint requires an admin API key; set security.api_keys or PKG_SERVER_API_KEYS"
+                .to_string(),
+        ));
+    }
+    if !crate::auth::is_authorized(&state.config.load(), headers) {
+        return Err(AppError::Unauthorized(
+            "An admin API key is required for this endpoint".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Lists issued tokens (without their secrets).
+///
+/// # Route
+/// `GET /api/tokens`
+pub async fn list_tokens(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> AppResult<Json<Value>> {
+    require_admin(&state, &headers)?;
+    let tokens: Vec<Value> = state.tokens.list().iter().map(ApiToken::summary).collect();
+    Ok(Json(json!({ "tokens": tokens })))
+}
+
+/// Issues a token. The secret is only ever returned in this response.
+///
+/// # Route
+/// `POST /api/tokens` with body
+/// `{"name": "ci", "scopes": ["publish"], "registries": ["npm"]}`
+pub async fn create_token(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(request): Json<CreateTokenRequest>,
+) -> AppResult<(StatusCode, Json<Value>)> {
+    require_admin(&state, &headers)?;
+
+    let name = request.name.trim();
+    if name.is_empty() || name.len() > 64 {
+        return Err(AppError::BadRequest(
+            "Token name must be 1-64 characters".to_string(),
+        ));
+    }
+    let mut scopes = request.scopes;
+    scopes.sort();
+    scopes.dedup();
+    if scopes.is_empty() {
+        return Err(AppError::BadRequest(
+            "At least one scope (read, publish, delete) is required".to_string(),
+        ));
+    }
+    let mut registries = request.registries;
+    if let Some(unknown) = registries
+        .iter()
+        .find(|registry| !REGISTRIES.contains(&registry.as_str()))
+    {
+        return Err(AppError::BadRequest(format!(
+            "Unknown registry '{unknown}' (expected one of: {})",
+            REGISTRIES.join(", ")
+        )));
+    }
+    registries.sort();
+    registries.dedup();
+
+    let (token, secret) = state.tokens.create(name, scopes, registries)?;
+    info!(token_id = %token.id, name = %token.name, scopes = ?token.scopes, "Issued API token");
+
+    let mut body = token.summary();
+    body["token"] = json!(secret);
+    Ok((StatusCode::CREATED, Json(body)))
+}
+
+/// Revokes a token.
+///
+/// # Route
+/// `DELETE /api/tokens/{id}`
+pub async fn revoke_token(
+    AxumPath(id): AxumPath<String>,
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> AppResult<Json<Value>> {
+    require_admin(&state, &headers)?;
+    if !state.tokens.revoke(&id)? {
+        return Err(AppError::NotFound(format!("No token with id '{id}'")));
+    }
+    info!(token_id = %id, "Revoked API token");
+    Ok(Json(json!({ "revoked": id })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn tokens_persist_and_match_by_secret() {
+        let dir = TempDir::new().unwrap();
+        let store = TokenStore::open(dir.path()).unwrap();
+        let (token, secret) = store
+            .create("ci", vec![TokenScope::Publish], vec!["npm".to_string()])
+            .unwrap();
+
+        let on_disk = std::fs::read_to_string(dir.path().join("tokens.json")).unwrap();
+        assert!(!on_disk.contains(&secret), "secrets must not be stored");
+
+        let reopened = TokenStore::open(dir.path()).unwrap();
+        let found = reopened.find(&secret).expect("token should be found");
+        assert_eq!(found.id, token.id);
+        assert!(reopened.find("vmpkg_wrong").is_none());
+
+        assert!(reopened.revoke(&token.id).unwrap());
+        assert!(!reopened.revoke(&token.id).unwrap());
+        assert!(TokenStore::open(dir.path())
+            .unwrap()
+            .find(&secret)
+            .is_none());
+    }
+
+    #[test]
+    fn scopes_and_registries_limit_tokens() {
+        let dir = TempDir::new().unwrap();
+        let store = TokenStore::open(dir.path()).unwrap();
+        let (npm_publisher, _) = store
+            .create("npm", vec![TokenScope::Publish], vec!["npm".to_string()])
+            .unwrap();
+        assert!(npm_publisher.allows(TokenScope::Publish, "npm"));
+        assert!(!npm_publisher.allows(TokenScope::Publish, "cargo"));
+        assert!(!npm_publisher.allows(TokenScope::Delete, "npm"));
+
+        let (reader, _) = store.create("all", vec![TokenScope::Read], vec![]).unwrap();
+        assert!(reader.allows(TokenScope::Read, "cargo"));
+        assert!(!reader.allows(TokenScope::Publish, "cargo"));
+    }
+
+    #[tokio::test]
+    async fn token_management_requires_admin_key() {
+        let (state, _dir) = crate::test_utils::create_cargo_test_state();
+        let mut config = crate::config::Config::default();
+        config.security.require_authentication = true;
+        config.security.api_keys = vec!["admin-key".to_string()];
+        let mut state = (*state).clone();
+        state.config = Arc::new(config).into();
+        let state = Arc::new(state);
+
+        let request = || {
+            Json(CreateTokenRequest {
+                name: "ci".to_string(),
+                scopes: vec![TokenScope::Publish],
+                registries: vec!["cargo".to_string()],
+            })
+        };
+        let mut admin = HeaderMap::new();
+        admin.insert("authorization", "Bearer admin-key".parse().unwrap());
+        let (status, Json(body)) = create_token(State(state.clone()), admin.clone(), request())
+            .await
+            .unwrap();
+        assert_eq!(status, StatusCode::CREATED);
+        let secret = body["token"].as_str().unwrap().to_string();
+
+        // Issued tokens cannot manage tokens themselves
+        let mut issued = HeaderMap::new();
+        issued.insert("authorization", format!("Bearer {secret}").parse().unwrap());
+        assert!(
+            create_token(State(state.clone()), issued.clone(), request())
+                .await
+                .is_err()
+        );
+        assert!(crate::auth::is_allowed(
+            &state,
+            &issued,
+            "cargo",
+            TokenScope::Publish
+        ));
+        assert!(!crate::auth::is_allowed(
+            &state,
+            &issued,
+            "cargo",
+            TokenScope::Delete
+        ));
+
+        let Json(listing) = list_tokens(State(state.clone()), admin).await.unwrap();
+        assert_eq!(listing["tokens"].as_array().unwrap().len(), 1);
+        assert!(listing["tokens"][0].get("token").is_none());
+    }
+}
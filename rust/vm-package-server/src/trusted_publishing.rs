@@ -0,0 +1,297 @@
+//! # PyPI Trusted Publishing
+//!
+//! Implements the OIDC token exchange used by pypi.org trusted publishing so CI
+//! jobs can upload without storing a long-lived API token. A workflow requests an
+//! identity token for the audience served by `GET /pypi/_/oidc/audience`, then
+//! exchanges it at `POST /pypi/_/oidc/mint-token` for a short-lived upload token
+//! scoped to the projects of every matching [`TrustedPublisher`].
+//!
+//! Identity tokens are verified against the issuer's published JWKS. Only issuers
+//! named in the configured trusted publishers are ever contacted.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use axum::{extract::State, response::Json};
+use base64::Engine;
+use jsonwebtoken::{jwk::JwkSet, Algorithm, DecodingKey, Validation};
+use once_cell::sync::Lazy;
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::{json, Value};
+use tracing::{info, warn};
+
+use crate::config::TrustedPublisher;
+use crate::{normalize_pypi_name, AppError, AppResult, AppState};
+
+/// Audience CI providers must request identity tokens for
+pub const OIDC_AUDIENCE: &str = "vm-package-server";
+
+/// Lifetime of minted upload tokens (matches pypi.org)
+const MINTED_TOKEN_TTL: Duration = Duration::from_secs(15 * 60);
+
+struct MintedToken {
+    projects: Vec<String>,
+    expires_at: Instant,
+}
+
+static MINTED_TOKENS: Lazy<Mutex<HashMap<String, MintedToken>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Claims used to match a CI identity against trusted publishers
+#[derive(Debug, Deserialize)]
+pub struct OidcClaims {
+    pub iss: String,
+    #[serde(default)]
+    pub repository: Option<String>,
+    #[serde(default)]
+    pub job_workflow_ref: Option<String>,
+    #[serde(default)]
+    pub environment: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct MintTokenRequest {
+    token: String,
+}
+
+/// Check whether verified identity claims satisfy a trusted publisher entry
+pub fn publisher_matches(publisher: &TrustedPublisher, claims: &OidcClaims) -> bool {
+    if publisher.issuer.trim_end_matches('/') != claims.iss.trim_end_matches('/') {
+        return false;
+    }
+    if claims.repository.as_deref() != Some(publisher.repository.as_str()) {
+        return false;
+    }
+    if let Some(workflow) = &publisher.workflow {
+        // job_workflow_ref: "<owner>/<repo>/.github/workflows/<file>@<ref>"
+        let expected = format!("{}/.github/workflows/{}@", publisher.repository, workflow);
+        if !claims
+            .job_workflow_ref
+            .as_deref()
+            .is_some_and(|workflow_ref| workflow_ref.starts_with(&expected))
+        {
+            return false;
+        }
+    }
+    if let Some(environment) = &publisher.environment {
+        if claims.environment.as_deref() != Some(environment.as_str()) {
+            return false;
+        }
+    }
+    true
+}
+
+/// Projects a minted token may upload, or `None` if it is unknown or expired
+pub fn minted_token_projects(token: &str) -> Option<Vec<String>> {
+    let mut tokens = MINTED_TOKENS.lock().ok()?;
+    let now = Instant::now();
+    tokens.retain(|_, minted| minted.expires_at > now);
+    tokens.get(token).map(|minted| minted.projects.clone())
+}
+
+/// Returns the audience CI jobs must request identity tokens for.
+///
+/// # Route
+/// `GET /pypi/_/oidc/audience`
+pub async fn audience() -> Json<Value> {
+    Json(json!({ "audience": OIDC_AUDIENCE }))
+}
+
+/// Exchanges a CI identity token for a short-lived, project-scoped upload token.
+///
+/// # Route
+/// `POST /pypi/_/oidc/mint-token` with body `{"token": "<oidc jwt>"}`
+pub async fn mint_token(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<MintTokenRequest>,
+) -> AppResult<Json<Value>> {
+    let publishers = &state.config.load().security.trusted_publishers;
+    if publishers.is_empty() {
+        return Err(AppError::Unauthorized(
+            "Trusted publishing is not configured on this server".to_string(),
+        ));
+    }
+
+    let issuer = unverified_issuer(&request.token)?;
+    if !publishers
+        .iter()
+        .any(|p| p.issuer.trim_end_matches('/') == issuer.trim_end_matches('/'))
+    {
+        warn!(issuer = %issuer, "Rejected identity token from untrusted issuer");
+        return Err(AppError::Unauthorized(format!(
+            "Issuer '{issuer}' is not a trusted publisher"
+        )));
+    }
+
+    let claims: OidcClaims = verify_identity_token(&request.token, &issuer, OIDC_AUDIENCE).await?;
+
+    let mut projects: Vec<String> = publishers
+        .iter()
+        .filter(|publisher| publisher_matches(publisher, &claims))
+        .flat_map(|publisher| publisher.projects.iter().map(|p| normalize_pypi_name(p)))
+        .collect();
+    projects.sort();
+    projects.dedup();
+    if projects.is_empty() {
+        warn!(repository = ?claims.repository, workflow = ?claims.job_workflow_ref, "No trusted publisher matches identity");
+        return Err(AppError::Unauthorized(
+            "No trusted publisher matches this identity".to_string(),
+        ));
+    }
+
+    let token = format!(
+        "pypi-{}{}",
+        uuid::Uuid::new_v4().simple(),
+        uuid::Uuid::new_v4().simple()
+    );
+    let expires = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|now| (now + MINTED_TOKEN_TTL).as_secs())
+        .unwrap_or_default();
+    MINTED_TOKENS
+        .lock()
+        .map_err(|_| AppError::InternalError("Token store is unavailable".to_string()))?
+        .insert(
+            token.clone(),
+            MintedToken {
+                projects: projects.clone(),
+                expires_at: Instant::now() + MINTED_TOKEN_TTL,
+            },
+        );
+
+    info!(repository = ?claims.repository, projects = ?projects, "Minted trusted publishing token");
+    Ok(Json(json!({
+        "success": true,
+        "token": token,
+        "expires": expires,
+    })))
+}
+
+/// Read the `iss` claim without verifying the signature, to pick the JWKS to verify with
+fn unverified_issuer(token: &str) -> AppResult<String> {
+    let payload = token
+        .split('.')
+        .nth(1)
+        .ok_or_else(|| AppError::BadRequest("Malformed identity token".to_string()))?;
+    let bytes = base64::engine::general_purpose::URL_SAFE_NO_PAD
+        .decode(payload.trim_end_matches('='))
+        .map_err(|_| AppError::BadRequest("Malformed identity token".to_string()))?;
+    let claims: Value = serde_json::from_slice(&bytes)?;
+    claims
+        .get("iss")
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .ok_or_else(|| AppError::BadRequest("Identity token has no issuer".to_string()))
+}
+
+/// Verify a JWT signed by `issuer` for `audience` and decode its claims
+pub(crate) async fn verify_identity_token<T: DeserializeOwned>(
+    token: &str,
+    issuer: &str,
+    audience: &str,
+) -> AppResult<T> {
+    let header = jsonwebtoken::decode_header(token)
+        .map_err(|e| AppError::Unauthorized(format!("Invalid identity token: {e}")))?;
+    if matches!(
+        header.alg,
+        Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512
+    ) {
+        return Err(AppError::Unauthorized(
+            "Identity tokens must be signed with an asymmetric key".to_string(),
+        ));
+    }
+
+    if vm_core::offline::is_offline() {
+        return Err(AppError::Unauthorized(vm_core::offline::offline_message(
+            &format!("verify identity tokens from {issuer}"),
+        )));
+    }
+
+    let client = vm_core::network::client_builder()
+        .timeout(Duration::from_secs(10))
+        .build()
+        .map_err(|e| AppError::InternalError(format!("Failed to build HTTP client: {e}")))?;
+    let discovery: Value = client
+        .get(format!(
+            "{}/.well-known/openid-configuration",
+            issuer.trim_end_matches('/')
+        ))
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| AppError::Unauthorized(format!("Failed to reach issuer {issuer}: {e}")))?
+        .json()
+        .await
+        .map_err(|e| AppError::Unauthorized(format!("Invalid issuer configuration: {e}")))?;
+    let jwks_uri = discovery
+        .get("jwks_uri")
+        .and_then(Value::as_str)
+        .ok_or_else(|| AppError::Unauthorized("Issuer does not publish a JWKS".to_string()))?;
+    let jwks: JwkSet = client
+        .get(jwks_uri)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| AppError::Unauthorized(format!("Failed to fetch issuer keys: {e}")))?
+        .json()
+        .await
+        .map_err(|e| AppError::Unauthorized(format!("Invalid issuer keys: {e}")))?;
+
+    let jwk = match &header.kid {
+        Some(kid) => jwks.find(kid),
+        None => jwks.keys.first(),
+    }
+    .ok_or_else(|| AppError::Unauthorized("Identity token signed with unknown key".to_string()))?;
+    let key = DecodingKey::from_jwk(jwk)
+        .map_err(|e| AppError::Unauthorized(format!("Unsupported issuer key: {e}")))?;
+
+    let mut validation = Validation::new(header.alg);
+    validation.set_audience(&[audience]);
+    validation.set_issuer(&[issuer]);
+    jsonwebtoken::decode::<T>(token, &key, &validation)
+        .map(|data| data.claims)
+        .map_err(|e| AppError::Unauthorized(format!("Identity token rejected: {e}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn publisher(workflow: Option<&str>) -> TrustedPublisher {
+        TrustedPublisher {
+            issuer: "https://token.actions.githubusercontent.com".to_string(),
+            repository: "acme/widgets".to_string(),
+            workflow: workflow.map(str::to_string),
+            environment: None,
+            projects: vec!["Widgets".to_string()],
+        }
+    }
+
+    fn claims(repository: &str, workflow_ref: &str) -> OidcClaims {
+        OidcClaims {
+            iss: "https://token.actions.githubusercontent.com".to_string(),
+            repository: Some(repository.to_string()),
+            job_workflow_ref: Some(workflow_ref.to_string()),
+            environment: None,
+        }
+    }
+
+    #[test]
+    fn matches_repository_and_workflow() {
+        let release = claims(
+            "acme/widgets",
+            "acme/widgets/.github/workflows/release.yml@refs/tags/v1.0.0",
+        );
+        assert!(publisher_matches(&publisher(None), &release));
+        assert!(publisher_matches(&publisher(Some("release.yml")), &release));
+        assert!(!publisher_matches(&publisher(Some("ci.yml")), &release));
+        assert!(!publisher_matches(
+            &publisher(None),
+            &claims(
+                "evil/widgets",
+                "evil/widgets/.github/workflows/release.yml@main"
+            )
+        ));
+    }
+}
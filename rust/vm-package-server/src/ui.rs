@@ -2,13 +2,18 @@ use std::sync::Arc;
 
 use askama::Template;
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
+    http::HeaderMap,
     response::Html,
 };
+use serde::Deserialize;
 use tracing::{error, warn};
 
+use crate::package_index::PackageSummary;
+use crate::provenance::{self, ServedArtifact};
 use crate::registry::PackageRegistry;
-use crate::{AppError, AppResult, AppState};
+use crate::tokens::TokenScope;
+use crate::{auth, AppError, AppResult, AppState};
 
 /// Format file size in human-readable format
 fn format_size(size: u64) -> String {
@@ -34,6 +39,7 @@ struct IndexTemplate {
     pypi_count: usize,
     npm_count: usize,
     cargo_count: usize,
+    helm_count: usize,
     recent_packages: Vec<RecentPackage>,
     version: String,
     live_reload_script: String,
@@ -50,11 +56,166 @@ struct RecentPackage {
     version: String,
 }
 
+/// Packages shown per page of a registry listing
+const PAGE_SIZE: usize = 50;
+
 #[derive(Template)]
 #[template(path = "package_list.html")]
 struct PackageListTemplate {
     pkg_type: String,
-    packages: Vec<String>,
+    packages: Vec<PackageSummary>,
+    /// Packages in the registry, and those matching the filter
+    registry_total: usize,
+    matching: usize,
+    filter: String,
+    sort: &'static str,
+    sorts: Vec<SortLink>,
+    /// 1-based positions of the first and last package shown
+    first: usize,
+    last: usize,
+    page: usize,
+    pages: usize,
+    prev_link: Option<String>,
+    next_link: Option<String>,
+}
+
+struct SortLink {
+    label: &'static str,
+    href: String,
+    active: bool,
+}
+
+/// Query parameters of `/ui/{pkg_type}`
+#[derive(Debug, Default, Deserialize)]
+pub struct ListQuery {
+    /// Only show packages whose name contains this, ignoring case
+    #[serde(default)]
+    q: String,
+    #[serde(default)]
+    sort: SortOrder,
+    /// 1-based page number
+    page: Option<usize>,
+}
+
+/// Order of a registry listing
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum SortOrder {
+    #[default]
+    Name,
+    /// Most recently published first
+    Recent,
+    /// Most downloaded first (counted while provenance auditing is on)
+    Downloads,
+}
+
+impl SortOrder {
+    const ALL: [SortOrder; 3] = [SortOrder::Name, SortOrder::Recent, SortOrder::Downloads];
+
+    fn as_str(self) -> &'static str {
+        match self {
+            SortOrder::Name => "name",
+            SortOrder::Recent => "recent",
+            SortOrder::Downloads => "downloads",
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            SortOrder::Name => "Name",
+            SortOrder::Recent => "Recently updated",
+            SortOrder::Downloads => "Downloads",
+        }
+    }
+
+    fn sort(self, packages: &mut [PackageSummary]) {
+        match self {
+            SortOrder::Name => packages.sort_by(|a, b| a.name.cmp(&b.name)),
+            SortOrder::Recent => packages.sort_by(|a, b| {
+                b.modified
+                    .cmp(&a.modified)
+                    .then_with(|| a.name.cmp(&b.name))
+            }),
+            SortOrder::Downloads => packages.sort_by(|a, b| {
+                b.downloads
+                    .cmp(&a.downloads)
+                    .then_with(|| a.name.cmp(&b.name))
+            }),
+        }
+    }
+}
+
+/// Link to another view of the listing of `pkg_type`
+fn list_link(pkg_type: &str, filter: &str, sort: SortOrder, page: usize) -> String {
+    let mut query = url::form_urlencoded::Serializer::new(String::new());
+    if !filter.is_empty() {
+        query.append_pair("q", filter);
+    }
+    if sort != SortOrder::Name {
+        query.append_pair("sort", sort.as_str());
+    }
+    if page > 1 {
+        query.append_pair("page", &page.to_string());
+    }
+    let query = query.finish();
+    if query.is_empty() {
+        format!("/ui/{pkg_type}")
+    } else {
+        format!("/ui/{pkg_type}?{query}")
+    }
+}
+
+/// Filter, sort and paginate `packages` for one page of the listing
+fn list_page(
+    pkg_type: String,
+    mut packages: Vec<PackageSummary>,
+    query: ListQuery,
+) -> PackageListTemplate {
+    let registry_total = packages.len();
+    let filter = query.q.trim().to_string();
+    if !filter.is_empty() {
+        let needle = if pkg_type == "pypi" {
+            crate::normalize_pypi_name(&filter)
+        } else {
+            filter.to_lowercase()
+        };
+        packages.retain(|package| package.name.to_lowercase().contains(&needle));
+    }
+    query.sort.sort(&mut packages);
+
+    let matching = packages.len();
+    let pages = matching.div_ceil(PAGE_SIZE).max(1);
+    let page = query.page.unwrap_or(1).clamp(1, pages);
+    let start = (page - 1) * PAGE_SIZE;
+    let packages: Vec<PackageSummary> = packages.into_iter().skip(start).take(PAGE_SIZE).collect();
+
+    let link = |sort: SortOrder, page: usize| list_link(&pkg_type, &filter, sort, page);
+    let sorts = SortOrder::ALL
+        .iter()
+        .map(|&sort| SortLink {
+            label: sort.label(),
+            href: link(sort, 1),
+            active: sort == query.sort,
+        })
+        .collect();
+    let prev_link = (page > 1).then(|| link(query.sort, page - 1));
+    let next_link = (page < pages).then(|| link(query.sort, page + 1));
+
+    PackageListTemplate {
+        first: if packages.is_empty() { 0 } else { start + 1 },
+        last: start + packages.len(),
+        pkg_type,
+        packages,
+        registry_total,
+        matching,
+        filter,
+        sort: query.sort.as_str(),
+        sorts,
+        page,
+        pages,
+        prev_link,
+        next_link,
+    }
 }
 
 #[derive(Template)]
@@ -62,6 +223,7 @@ struct PackageListTemplate {
 struct PyPiDetailTemplate {
     package_name: String,
     versions: Vec<PyPiVersion>,
+    provenance: Vec<ServedArtifact>,
 }
 
 #[derive(Clone)]
@@ -75,6 +237,7 @@ struct PyPiVersion {
 struct NpmDetailTemplate {
     package_name: String,
     versions: Vec<NpmVersion>,
+    provenance: Vec<ServedArtifact>,
 }
 
 #[derive(Clone)]
@@ -92,6 +255,7 @@ struct NpmVersion {
 struct CargoDetailTemplate {
     package_name: String,
     versions: Vec<CargoVersion>,
+    provenance: Vec<ServedArtifact>,
 }
 
 #[derive(Clone)]
@@ -114,7 +278,10 @@ pub struct PackageFile {
 }
 
 /// Render the home page with package statistics and recent packages
-pub async fn home(State(state): State<Arc<AppState>>) -> AppResult<Html<String>> {
+pub async fn home(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> AppResult<Html<String>> {
     // Fetch package counts with fallback to 0 on error
     let pypi_count = match state.pypi_registry.count_packages(&state).await {
         Ok(count) => count,
@@ -140,8 +307,10 @@ pub async fn home(State(state): State<Arc<AppState>>) -> AppResult<Html<String>>
         }
     };
 
+    let helm_count = crate::helm::chart_names(&state.data_dir).await.len();
+
     // Fetch recent packages with fallback to empty list on error
-    let recent_packages = match get_recent_packages(&state).await {
+    let recent_packages = match get_recent_packages(&state, &headers).await {
         Ok(packages) => packages,
         Err(e) => {
             warn!("Failed to get recent packages: {}", e);
@@ -160,6 +329,7 @@ pub async fn home(State(state): State<Arc<AppState>>) -> AppResult<Html<String>>
         pypi_count,
         npm_count,
         cargo_count,
+        helm_count,
         recent_packages,
         version: env!("CARGO_PKG_VERSION").to_string(),
         live_reload_script,
@@ -171,19 +341,42 @@ pub async fn home(State(state): State<Arc<AppState>>) -> AppResult<Html<String>>
     })?))
 }
 
-/// List all packages of a given type (npm, pypi, cargo)
+/// List the packages of a given type (npm, pypi, cargo, helm), one page at a time
+///
+/// `?q=` filters by name, `?sort=` orders by `name`, `recent` or `downloads`, and
+/// `?page=` selects the page.
 pub async fn list_packages(
     Path(pkg_type): Path<String>,
+    Query(query): Query<ListQuery>,
     State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
 ) -> AppResult<Html<String>> {
     let packages = match pkg_type.as_str() {
-        "pypi" => state.pypi_registry.list_all_packages(&state).await?,
-        "npm" => state.npm_registry.list_all_packages(&state).await?,
-        "cargo" => crate::cargo::list_all_crates(&state).await?,
+        "pypi" | "cargo" => {
+            let registry = pkg_type.clone();
+            state
+                .package_index
+                .run(move |index| index.summaries(&registry))
+                .await?
+        }
+        "npm" => state
+            .package_index
+            .run(|index| index.summaries("npm"))
+            .await?
+            .into_iter()
+            .filter(|package| auth::may_read_npm(&state, &headers, &package.name))
+            .collect(),
+        "helm" => {
+            let downloads = state
+                .package_index
+                .run(|index| index.download_counts("helm"))
+                .await?;
+            crate::helm::chart_summaries(&state.data_dir, &downloads).await
+        }
         _ => return Err(AppError::NotFound("Invalid package type".to_string())),
     };
 
-    let template = PackageListTemplate { pkg_type, packages };
+    let template = list_page(pkg_type, packages, query);
 
     Ok(Html(template.render().map_err(|e| {
         error!("Template render error: {}", e);
@@ -225,6 +418,7 @@ pub async fn pypi_package_detail(
     pypi_versions.sort_by(|a, b| b.version.cmp(&a.version));
 
     let template = PyPiDetailTemplate {
+        provenance: provenance::artifacts(&state, "pypi", &pkg_name).await,
         package_name: pkg_name,
         versions: pypi_versions,
     };
@@ -235,11 +429,20 @@ pub async fn pypi_package_detail(
     })?))
 }
 
-/// Show detailed information for a specific npm package
+/// Show detailed information for a specific npm package; packages in a private
+/// scope need the same credentials as `npm install`
 pub async fn npm_package_detail(
     Path(pkg_name): Path<String>,
     State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
 ) -> AppResult<Html<String>> {
+    auth::check_npm_scope(
+        &state.config.load(),
+        &state.tokens,
+        &headers,
+        &pkg_name,
+        TokenScope::Read,
+    )?;
     // Use the trait method
     let versions = state
         .npm_registry
@@ -247,6 +450,7 @@ pub async fn npm_package_detail(
         .await?;
 
     let template = NpmDetailTemplate {
+        provenance: provenance::artifacts(&state, "npm", &pkg_name).await,
         package_name: pkg_name,
         versions: versions
             .into_iter()
@@ -274,6 +478,7 @@ pub async fn cargo_package_detail(
     let versions = crate::cargo::get_crate_versions(&state, &pkg_name).await?;
 
     let template = CargoDetailTemplate {
+        provenance: provenance::artifacts(&state, "cargo", &pkg_name).await,
         package_name: pkg_name,
         versions: versions
             .into_iter()
@@ -302,7 +507,10 @@ pub async fn upload_page() -> AppResult<Html<String>> {
     })?))
 }
 
-async fn get_recent_packages(state: &AppState) -> AppResult<Vec<RecentPackage>> {
+async fn get_recent_packages(
+    state: &AppState,
+    headers: &HeaderMap,
+) -> AppResult<Vec<RecentPackage>> {
     let mut recent = Vec::new();
 
     // Fetch recent PyPI packages with proper error handling
@@ -326,6 +534,9 @@ async fn get_recent_packages(state: &AppState) -> AppResult<Vec<RecentPackage>>
     match state.npm_registry.get_recent_packages(state, 5).await {
         Ok(npm_packages) => {
             for (name, version) in npm_packages {
+                if !auth::may_read_npm(state, headers, &name) {
+                    continue;
+                }
                 recent.push(RecentPackage {
                     name,
                     pkg_type: "npm".to_string(),
@@ -356,6 +567,132 @@ async fn get_recent_packages(state: &AppState) -> AppResult<Vec<RecentPackage>>
         }
     }
 
+    for (name, version) in crate::helm::recent_charts(&state.data_dir, 5).await {
+        recent.push(RecentPackage {
+            name,
+            pkg_type: "helm".to_string(),
+            version,
+        });
+    }
+
     recent.truncate(10);
     Ok(recent)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary(name: &str, modified: i64, downloads: u64) -> PackageSummary {
+        PackageSummary {
+            name: name.to_string(),
+            modified,
+            downloads,
+        }
+    }
+
+    #[test]
+    fn test_list_page_filters_sorts_and_paginates() {
+        let packages: Vec<PackageSummary> = (0..120)
+            .map(|i| summary(&format!("pkg-{i:03}"), i, 0))
+            .collect();
+
+        let page = list_page(
+            "npm".to_string(),
+            packages.clone(),
+            ListQuery {
+                sort: SortOrder::Recent,
+                page: Some(3),
+                ..ListQuery::default()
+            },
+        );
+        assert_eq!((page.page, page.pages), (3, 3));
+        assert_eq!((page.first, page.last), (101, 120));
+        assert_eq!(page.packages[0].name, "pkg-019");
+        assert_eq!(
+            page.prev_link.as_deref(),
+            Some("/ui/npm?sort=recent&page=2")
+        );
+        assert!(page.next_link.is_none());
+        let html = page.render().unwrap();
+        assert!(html.contains("Page 3 of 3"));
+        assert!(html.contains(r#"<input type="hidden" name="sort" value="recent">"#));
+
+        let page = list_page(
+            "npm".to_string(),
+            packages,
+            ListQuery {
+                q: "PKG-11".to_string(),
+                page: Some(9),
+                ..ListQuery::default()
+            },
+        );
+        assert_eq!((page.matching, page.registry_total), (10, 120));
+        assert_eq!(page.page, 1);
+        assert_eq!(page.packages[0].name, "pkg-110");
+        assert_eq!(page.sorts[2].href, "/ui/npm?q=PKG-11&sort=downloads");
+    }
+
+    #[test]
+    fn test_list_page_sorts_by_downloads_and_normalizes_pypi_filter() {
+        let packages = vec![
+            summary("flask-login", 1, 2),
+            summary("requests", 2, 10),
+            summary("flask", 3, 10),
+        ];
+        let page = list_page(
+            "pypi".to_string(),
+            packages.clone(),
+            ListQuery {
+                sort: SortOrder::Downloads,
+                ..ListQuery::default()
+            },
+        );
+        let names: Vec<&str> = page.packages.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["flask", "requests", "flask-login"]);
+
+        let page = list_page(
+            "pypi".to_string(),
+            packages,
+            ListQuery {
+                q: "Flask_Login".to_string(),
+                ..ListQuery::default()
+            },
+        );
+        assert_eq!(page.packages.len(), 1);
+        assert_eq!(page.packages[0].name, "flask-login");
+    }
+
+    #[tokio::test]
+    async fn test_private_npm_scope_detail_needs_credentials() {
+        let (state, _temp_dir) = crate::test_utils::create_npm_test_state();
+        let mut state = (*state).clone();
+        let mut config = crate::config::Config::default();
+        config.security.api_keys = vec!["admin-key".to_string()];
+        config.security.private_npm_scopes = vec![crate::config::NpmScopeAcl {
+            scope: "@acme".to_string(),
+            tokens: vec![],
+        }];
+        state.config = Arc::new(config).into();
+        let app = axum::Router::new()
+            .route(
+                "/ui/npm/{*pkg_name}",
+                axum::routing::get(npm_package_detail),
+            )
+            .with_state(Arc::new(state));
+        let server = axum_test::TestServer::new(app).expect("should create test server");
+
+        let response = server.get("/ui/npm/@acme/widgets").await;
+        assert_eq!(response.status_code(), axum::http::StatusCode::UNAUTHORIZED);
+
+        let response = server
+            .get("/ui/npm/@acme/widgets")
+            .add_header("authorization", "Bearer admin-key")
+            .await;
+        assert_eq!(response.status_code(), axum::http::StatusCode::OK);
+        assert!(response.text().contains("@acme/widgets"));
+
+        let response = server.get("/ui/npm/left-pad").await;
+        assert_eq!(response.status_code(), axum::http::StatusCode::OK);
+    }
+}
@@ -1,7 +1,13 @@
+use crate::config::{BandwidthConfig, UpstreamsConfig};
+use crate::throttle::UpstreamThrottle;
+use crate::upstream_policy::UpstreamPolicy;
 use crate::validation_utils::FileStreamValidator;
 use crate::{AppError, AppResult};
 use reqwest::Client;
 use serde_json::Value;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 use tracing::{debug, info, warn};
 use url::Url;
@@ -9,14 +15,18 @@ use url::Url;
 /// Configuration for upstream package registries.
 ///
 /// This struct defines the connection settings and URLs for communicating with
-/// upstream package registries (PyPI, npm, crates.io). It controls how the server
+/// upstream package registries (PyPI, npm, crates.io, the Go module proxy). It controls how the server
 /// fetches packages and metadata from external sources when they're not available locally.
 ///
+/// Each registry has a list of URLs tried in order, so later entries are fallbacks
+/// for packages the earlier ones don't have or when they are unreachable.
+///
 /// # Fields
 ///
-/// * `pypi_url` - Base URL for the PyPI registry (default: "https://pypi.org")
-/// * `npm_url` - Base URL for the npm registry (default: "https://registry.npmjs.org")
-/// * `cargo_url` - Base URL for the Cargo registry (default: "https://index.crates.io")
+/// * `pypi_urls` - Base URLs for PyPI registries (default: "https://pypi.org")
+/// * `npm_urls` - Base URLs for npm registries (default: "https://registry.npmjs.org")
+/// * `cargo_urls` - Cargo sparse index URLs (default: "https://index.crates.io")
+/// * `go_urls` - Base URLs for Go module proxies (default: "https://proxy.golang.org")
 /// * `timeout` - HTTP request timeout for upstream calls
 /// * `enabled` - Whether upstream registry lookups are enabled
 ///
@@ -29,23 +39,29 @@ use url::Url;
 /// // Use default configuration
 /// let config = UpstreamConfig::default();
 ///
-/// // Custom configuration
+/// // Custom configuration: an internal npm registry, falling back to npmjs.org
 /// let config = UpstreamConfig {
-///     pypi_url: "https://pypi.org".to_string(),
-///     npm_url: "https://registry.npmjs.org".to_string(),
-///     cargo_url: "https://index.crates.io".to_string(),
+///     pypi_urls: vec!["https://pypi.org".to_string()],
+///     npm_urls: vec![
+///         "https://artifactory.example.com/api/npm/npm-remote".to_string(),
+///         "https://registry.npmjs.org".to_string(),
+///     ],
+///     cargo_urls: vec!["https://index.crates.io".to_string()],
+///     go_urls: vec!["https://proxy.golang.org".to_string()],
 ///     timeout: Duration::from_secs(30),
 ///     enabled: true,
 /// };
 /// ```
 #[derive(Clone)]
 pub struct UpstreamConfig {
-    /// Base URL for PyPI registry API
-    pub pypi_url: String,
-    /// Base URL for npm registry API
-    pub npm_url: String,
-    /// Base URL for Cargo registry index
-    pub cargo_url: String,
+    /// Base URLs for PyPI registry APIs, in the order they are tried
+    pub pypi_urls: Vec<String>,
+    /// Base URLs for npm registry APIs, in the order they are tried
+    pub npm_urls: Vec<String>,
+    /// Cargo sparse index URLs, in the order they are tried
+    pub cargo_urls: Vec<String>,
+    /// Base URLs for Go module proxies, in the order they are tried
+    pub go_urls: Vec<String>,
     /// HTTP request timeout for upstream calls
     pub timeout: Duration,
     /// Whether upstream registry lookups are enabled
@@ -55,15 +71,65 @@ pub struct UpstreamConfig {
 impl Default for UpstreamConfig {
     fn default() -> Self {
         Self {
-            pypi_url: "https://pypi.org".to_string(),
-            npm_url: "https://registry.npmjs.org".to_string(),
-            cargo_url: "https://index.crates.io".to_string(),
+            pypi_urls: vec!["https://pypi.org".to_string()],
+            npm_urls: vec!["https://registry.npmjs.org".to_string()],
+            cargo_urls: vec![CRATES_IO_INDEX.to_string()],
+            go_urls: vec!["https://proxy.golang.org".to_string()],
             timeout: Duration::from_secs(30),
             enabled: true,
         }
     }
 }
 
+impl UpstreamConfig {
+    /// Use the registries configured in the `upstreams` section, keeping the
+    /// public registry for ecosystems without any.
+    pub fn with_upstreams(mut self, upstreams: &UpstreamsConfig) -> Self {
+        let lists = [
+            (&mut self.pypi_urls, &upstreams.pypi),
+            (&mut self.npm_urls, &upstreams.npm),
+            (&mut self.cargo_urls, &upstreams.cargo),
+            (&mut self.go_urls, &upstreams.go),
+        ];
+        for (urls, configured) in lists {
+            if !configured.is_empty() {
+                *urls = configured
+                    .iter()
+                    .map(|url| url.trim_end_matches('/').to_string())
+                    .collect();
+            }
+        }
+        self
+    }
+}
+
+/// The crates.io sparse index, whose crates are downloaded through the crates.io API
+const CRATES_IO_INDEX: &str = "https://index.crates.io";
+
+/// Try each upstream URL in order, returning the first success or the last error.
+async fn first_success<T, F, Fut>(urls: &[String], mut fetch: F) -> AppResult<T>
+where
+    F: FnMut(String) -> Fut,
+    Fut: Future<Output = AppResult<T>>,
+{
+    let mut last_error = None;
+    for url in urls {
+        match fetch(url.clone()).await {
+            Ok(value) => return Ok(value),
+            // A refused package is refused everywhere
+            Err(e @ AppError::UpstreamBlocked(_)) => return Err(e),
+            Err(e) => {
+                if urls.len() > 1 {
+                    debug!(upstream = %url, error = %e, "Upstream failed, trying the next one");
+                }
+                last_error = Some(e);
+            }
+        }
+    }
+    Err(last_error
+        .unwrap_or_else(|| AppError::NotFound("No upstream registry configured".to_string())))
+}
+
 /// HTTP client for upstream registry communication.
 ///
 /// This struct provides methods to fetch packages, metadata, and other resources
@@ -102,6 +168,10 @@ impl Default for UpstreamConfig {
 pub struct UpstreamClient {
     client: Option<Client>,
     config: UpstreamConfig,
+    throttle: UpstreamThrottle,
+    policy: Arc<UpstreamPolicy>,
+    /// Crate download URL templates (`dl`) of the Cargo indexes, by index URL
+    cargo_downloads: Mutex<HashMap<String, String>>,
 }
 
 impl UpstreamClient {
@@ -123,7 +193,7 @@ impl UpstreamClient {
     ///
     /// Returns an error if the underlying HTTP client cannot be created.
     pub fn new(config: UpstreamConfig) -> AppResult<Self> {
-        let client = Client::builder()
+        let client = vm_core::network::client_builder()
             .timeout(config.timeout)
             .user_agent("goobits-pkg-server/0.1.0")
             .build()
@@ -132,9 +202,24 @@ impl UpstreamClient {
         Ok(Self {
             client: Some(client),
             config,
+            throttle: UpstreamThrottle::default(),
+            policy: Arc::default(),
+            cargo_downloads: Mutex::default(),
         })
     }
 
+    /// Apply bandwidth limits to package downloads from upstream registries.
+    pub fn with_bandwidth_limits(mut self, bandwidth: &BandwidthConfig) -> Self {
+        self.throttle = UpstreamThrottle::new(bandwidth);
+        self
+    }
+
+    /// Refuse packages according to the upstream allow and deny lists.
+    pub fn with_policy(mut self, policy: UpstreamPolicy) -> Self {
+        self.policy = Arc::new(policy);
+        self
+    }
+
     /// Create a disabled upstream client for testing.
     ///
     /// This constructor creates a client with `enabled = false` and NO HTTP client.
@@ -155,6 +240,9 @@ impl UpstreamClient {
         Self {
             client: None,
             config,
+            throttle: UpstreamThrottle::default(),
+            policy: Arc::default(),
+            cargo_downloads: Mutex::default(),
         }
     }
 
@@ -167,6 +255,78 @@ impl UpstreamClient {
         })
     }
 
+    /// Whether upstream registry lookups are enabled
+    pub fn is_enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    /// Allow and deny lists applied to every package request
+    pub fn policy(&self) -> &UpstreamPolicy {
+        &self.policy
+    }
+
+    /// Fail fast when upstream lookups are disabled or the host is offline.
+    ///
+    /// Both cases surface as `NotFound`, so handlers fall back to local and
+    /// cached packages without waiting on a network timeout.
+    fn ensure_upstream(&self) -> AppResult<()> {
+        if !self.config.enabled {
+            return Err(AppError::NotFound(
+                "Upstream registry lookup is disabled in configuration".to_string(),
+            ));
+        }
+        if vm_core::offline::is_offline() {
+            return Err(AppError::NotFound(vm_core::offline::offline_message(
+                "reach upstream package registries",
+            )));
+        }
+        Ok(())
+    }
+
+    /// Check that each upstream registry answers HTTP requests.
+    ///
+    /// Any HTTP response counts as reachable; only connection failures and
+    /// timeouts are reported. Returns the unreachable registries with the error.
+    pub async fn unreachable_registries(&self) -> Vec<(String, String)> {
+        let Ok(client) = self.get_client() else {
+            return Vec::new();
+        };
+        if self.ensure_upstream().is_err() {
+            return Vec::new();
+        }
+
+        let registries = [
+            ("pypi", &self.config.pypi_urls),
+            ("npm", &self.config.npm_urls),
+            ("cargo", &self.config.cargo_urls),
+            ("go", &self.config.go_urls),
+        ];
+        // Registries with fallbacks name the URL that failed
+        let targets = registries.iter().flat_map(|(registry, urls)| {
+            urls.iter().map(move |url| {
+                let name = if urls.len() > 1 {
+                    format!("{registry} ({url})")
+                } else {
+                    registry.to_string()
+                };
+                (name, url)
+            })
+        });
+        let probes = targets.map(|(name, url)| async move {
+            let result = client
+                .head(url.as_str())
+                .timeout(Duration::from_secs(5))
+                .send()
+                .await;
+            result.err().map(|e| (name, e.to_string()))
+        });
+        futures_util::future::join_all(probes)
+            .await
+            .into_iter()
+            .flatten()
+            .collect()
+    }
+
     /// Fetch PyPI simple index HTML for a package.
     ///
     /// Retrieves the simple API index page for a PyPI package, which contains
@@ -194,18 +354,30 @@ impl UpstreamClient {
     /// # }
     /// ```
     pub async fn fetch_pypi_simple(&self, package_name: &str) -> AppResult<String> {
-        if !self.config.enabled {
-            return Err(AppError::NotFound(
-                "Upstream registry lookup is disabled in configuration".to_string(),
-            ));
-        }
+        let (html, _) = self.fetch_pypi_simple_page(package_name).await?;
+        Ok(html)
+    }
 
-        let url = format!("{}/simple/{}/", self.config.pypi_url, package_name);
+    /// Fetch PyPI simple index HTML with the URL it came from, against which
+    /// relative file links resolve.
+    pub async fn fetch_pypi_simple_page(&self, package_name: &str) -> AppResult<(String, String)> {
+        self.ensure_upstream()?;
+        self.policy.check("pypi", package_name)?;
+
+        first_success(&self.config.pypi_urls, |base| async move {
+            let url = format!("{base}/simple/{package_name}/");
+            let html = self.fetch_pypi_simple_from(&url, package_name).await?;
+            Ok((html, url))
+        })
+        .await
+    }
+
+    async fn fetch_pypi_simple_from(&self, url: &str, package_name: &str) -> AppResult<String> {
         debug!(url = %url, "Fetching PyPI simple index");
 
         let response = self
             .get_client()?
-            .get(&url)
+            .get(url)
             .header("Accept", "text/html,application/vnd.pypi.simple.v1+html")
             .send()
             .await
@@ -229,16 +401,23 @@ impl UpstreamClient {
 
     /// Stream a file from PyPI with proper streaming and size validation
     pub async fn stream_pypi_file(&self, filename: &str) -> AppResult<bytes::Bytes> {
-        if !self.config.enabled {
-            return Err(AppError::NotFound(
-                "Upstream registry lookup is disabled in configuration".to_string(),
-            ));
+        first_success(&self.config.pypi_urls, |base| async move {
+            let url = format!("{base}/packages/{filename}");
+            self.stream_pypi_url(&url, filename).await
+        })
+        .await
+    }
+
+    /// Stream a PyPI file from a link in the upstream simple index
+    pub async fn stream_pypi_url(&self, url: &str, filename: &str) -> AppResult<bytes::Bytes> {
+        self.ensure_upstream()?;
+        if let Some((package, _)) = crate::utils::extract_pypi_package_name_and_version(filename) {
+            self.policy.check("pypi", &package)?;
         }
 
-        let url = format!("{}/packages/{}", self.config.pypi_url, filename);
         debug!(url = %url, "Streaming file from PyPI");
 
-        let response = self.get_client()?.get(&url).send().await.map_err(|e| {
+        let response = self.get_client()?.get(url).send().await.map_err(|e| {
             warn!(error = %e, "Failed to fetch file from PyPI");
             AppError::NotFound(format!("File not found on PyPI: {filename}"))
         })?;
@@ -250,7 +429,13 @@ impl UpstreamClient {
         }
 
         // Use centralized validation and streaming logic
-        FileStreamValidator::validate_and_stream_response(response, "PyPI", filename).await
+        FileStreamValidator::validate_and_stream_response(
+            response,
+            "PyPI",
+            filename,
+            &self.throttle,
+        )
+        .await
     }
 
     /// Fetch npm package metadata as JSON.
@@ -269,13 +454,17 @@ impl UpstreamClient {
     /// * `Err(AppError::NotFound)` if the package doesn't exist or upstream is disabled
     /// * `Err(AppError::InternalError)` if the request failed or JSON parsing failed
     pub async fn fetch_npm_metadata(&self, package_name: &str) -> AppResult<Value> {
-        if !self.config.enabled {
-            return Err(AppError::NotFound(
-                "Upstream registry lookup is disabled in configuration".to_string(),
-            ));
-        }
+        self.ensure_upstream()?;
+        self.policy.check("npm", package_name)?;
 
-        let url = format!("{}/{}", self.config.npm_url, package_name);
+        first_success(&self.config.npm_urls, |base| async move {
+            self.fetch_npm_metadata_from(&base, package_name).await
+        })
+        .await
+    }
+
+    async fn fetch_npm_metadata_from(&self, base: &str, package_name: &str) -> AppResult<Value> {
+        let url = format!("{base}/{package_name}");
         debug!(url = %url, "Fetching NPM metadata");
 
         let response = self
@@ -302,66 +491,178 @@ impl UpstreamClient {
         }
     }
 
-    /// Stream an NPM tarball with proper streaming and size validation
+    /// Stream an NPM tarball with proper streaming and size validation.
+    ///
+    /// `tarball_url` is a path such as `/{package}/-/{filename}`, tried on each
+    /// npm registry in turn, or an absolute URL. Absolute URLs below a configured
+    /// registry fall back to the others as well.
     pub async fn stream_npm_tarball(&self, tarball_url: &str) -> AppResult<bytes::Bytes> {
-        if !self.config.enabled {
-            return Err(AppError::NotFound(
-                "Upstream registry lookup is disabled in configuration".to_string(),
-            ));
-        }
+        self.ensure_upstream()?;
 
         // Handle both absolute and relative URLs
-        let full_url = if tarball_url.starts_with("http") {
-            tarball_url.to_string()
+        let path = if tarball_url.starts_with("http") {
+            self.config
+                .npm_urls
+                .iter()
+                .find_map(|base| tarball_url.strip_prefix(base.as_str()))
         } else {
-            format!("{}{}", self.config.npm_url, tarball_url)
+            Some(tarball_url)
+        };
+        let package = match path {
+            Some(path) => npm_tarball_package(path),
+            None => Url::parse(tarball_url)
+                .ok()
+                .and_then(|url| npm_tarball_package(url.path())),
         };
+        if let Some(package) = package {
+            self.policy.check("npm", &package)?;
+        }
+
+        match path {
+            Some(path) => {
+                first_success(&self.config.npm_urls, |base| async move {
+                    self.stream_npm_tarball_from(&format!("{base}{path}")).await
+                })
+                .await
+            }
+            None => self.stream_npm_tarball_from(tarball_url).await,
+        }
+    }
 
+    async fn stream_npm_tarball_from(&self, full_url: &str) -> AppResult<bytes::Bytes> {
         debug!(url = %full_url, "Streaming tarball from NPM");
 
-        let response = self
-            .get_client()?
-            .get(&full_url)
-            .send()
-            .await
-            .map_err(|e| {
-                warn!(error = %e, "Failed to fetch tarball from NPM");
-                AppError::NotFound("Tarball not found on NPM".to_string())
-            })?;
+        let response = self.get_client()?.get(full_url).send().await.map_err(|e| {
+            warn!(error = %e, "Failed to fetch tarball from NPM");
+            AppError::NotFound("Tarball not found on NPM".to_string())
+        })?;
 
         if !response.status().is_success() {
             return Err(AppError::NotFound("Tarball not found on NPM".to_string()));
         }
 
         // Use centralized validation and streaming logic
-        FileStreamValidator::validate_and_stream_response(response, "NPM", &full_url).await
+        FileStreamValidator::validate_and_stream_response(response, "NPM", full_url, &self.throttle)
+            .await
     }
 
-    /// Fetch Cargo crate index
-    pub async fn fetch_cargo_index(&self, crate_name: &str, index_path: &str) -> AppResult<String> {
-        if !self.config.enabled {
-            return Err(AppError::NotFound(
-                "Upstream registry lookup is disabled in configuration".to_string(),
-            ));
+    /// Forward an `npm audit` request to the upstream security API.
+    ///
+    /// `endpoint` is the path below `/-/npm/v1/security/`, such as
+    /// `advisories/bulk`. The request body is passed through unchanged, so a
+    /// gzip-compressed body must come with its `content_encoding`.
+    pub async fn post_npm_audit(
+        &self,
+        endpoint: &str,
+        body: bytes::Bytes,
+        content_encoding: Option<&str>,
+    ) -> AppResult<bytes::Bytes> {
+        self.ensure_upstream()?;
+
+        first_success(&self.config.npm_urls, |base| {
+            let body = body.clone();
+            async move {
+                self.post_npm_audit_to(&base, endpoint, body, content_encoding)
+                    .await
+            }
+        })
+        .await
+    }
+
+    async fn post_npm_audit_to(
+        &self,
+        base: &str,
+        endpoint: &str,
+        body: bytes::Bytes,
+        content_encoding: Option<&str>,
+    ) -> AppResult<bytes::Bytes> {
+        let url = format!("{base}/-/npm/v1/security/{endpoint}");
+        debug!(url = %url, "Forwarding npm audit request");
+
+        let mut request = self
+            .get_client()?
+            .post(&url)
+            .header("Content-Type", "application/json")
+            .body(body);
+        if let Some(encoding) = content_encoding {
+            request = request.header("Content-Encoding", encoding);
+        }
+        let response = request.send().await.map_err(|e| {
+            warn!(error = %e, "Failed to reach npm security API");
+            AppError::InternalError(format!("npm audit request failed: {e}"))
+        })?;
+
+        if !response.status().is_success() {
+            return Err(AppError::InternalError(format!(
+                "npm audit request failed with status {}",
+                response.status()
+            )));
+        }
+
+        response
+            .bytes()
+            .await
+            .map_err(|e| AppError::InternalError(format!("Failed to read npm audit response: {e}")))
+    }
+
+    /// Fetch a Cargo sparse index file from crates.io or the configured indexes.
+    ///
+    /// Returns `Ok(None)` when no index has such a crate, so callers can cache
+    /// the miss; connection failures and other errors are returned as `Err`,
+    /// unless another index has the crate.
+    pub async fn fetch_cargo_index(
+        &self,
+        crate_name: &str,
+        index_path: &str,
+    ) -> AppResult<Option<String>> {
+        self.ensure_upstream()?;
+        self.policy.check("cargo", crate_name)?;
+
+        let mut error = None;
+        for base in &self.config.cargo_urls {
+            match self
+                .fetch_cargo_index_from(base, crate_name, index_path)
+                .await
+            {
+                Ok(Some(content)) => return Ok(Some(content)),
+                Ok(None) => {}
+                Err(e) => {
+                    debug!(upstream = %base, error = %e, "Cargo index failed, trying the next one");
+                    error = Some(e);
+                }
+            }
         }
+        // A miss is only cached when every index answered
+        error.map_or(Ok(None), Err)
+    }
 
-        let url = format!("{}/{}", self.config.cargo_url, index_path);
+    async fn fetch_cargo_index_from(
+        &self,
+        base: &str,
+        crate_name: &str,
+        index_path: &str,
+    ) -> AppResult<Option<String>> {
+        let url = format!("{base}/{index_path}");
         debug!(url = %url, "Fetching Cargo index");
 
         let response = self.get_client()?.get(&url).send().await.map_err(|e| {
             warn!(error = %e, "Failed to fetch from Cargo index");
-            AppError::NotFound(format!("Crate not found on crates.io: {crate_name}"))
+            AppError::InternalError(format!("Failed to reach crates.io index: {e}"))
         })?;
 
-        if response.status().is_success() {
+        let status = response.status();
+        if status.is_success() {
             let content = response.text().await.map_err(|e| {
                 AppError::InternalError(format!("Failed to read Cargo response: {e}"))
             })?;
             info!(crate_name = %crate_name, "Successfully fetched from crates.io");
-            Ok(content)
+            Ok(Some(content))
+        } else if matches!(status.as_u16(), 404 | 410 | 451) {
+            debug!(crate_name = %crate_name, "Crate not found on crates.io");
+            Ok(None)
         } else {
-            Err(AppError::NotFound(format!(
-                "Crate not found on crates.io: {crate_name}"
+            Err(AppError::InternalError(format!(
+                "crates.io index returned {status} for {crate_name}"
             )))
         }
     }
@@ -372,18 +673,79 @@ impl UpstreamClient {
         crate_name: &str,
         version: &str,
     ) -> AppResult<bytes::Bytes> {
-        if !self.config.enabled {
-            return Err(AppError::NotFound(
-                "Upstream registry lookup is disabled in configuration".to_string(),
+        self.ensure_upstream()?;
+        self.policy.check("cargo", crate_name)?;
+
+        first_success(&self.config.cargo_urls, |index| async move {
+            let url = self.cargo_download_url(&index, crate_name, version).await?;
+            self.stream_cargo_crate_from(&url, crate_name, version)
+                .await
+        })
+        .await
+    }
+
+    /// Download URL of a crate from the index at `index`, following the `dl`
+    /// template in the index's `config.json`
+    async fn cargo_download_url(
+        &self,
+        index: &str,
+        crate_name: &str,
+        version: &str,
+    ) -> AppResult<String> {
+        if index == CRATES_IO_INDEX {
+            return Ok(format!(
+                "https://crates.io/api/v1/crates/{crate_name}/{version}/download"
             ));
         }
+        let cached = self
+            .cargo_downloads
+            .lock()
+            .ok()
+            .and_then(|downloads| downloads.get(index).cloned());
+        let template = match cached {
+            Some(template) => template,
+            None => {
+                let url = format!("{index}/config.json");
+                let config: Value = self
+                    .get_client()?
+                    .get(&url)
+                    .send()
+                    .await
+                    .and_then(|response| response.error_for_status())
+                    .map_err(|e| {
+                        AppError::NotFound(format!("Failed to read Cargo index config {url}: {e}"))
+                    })?
+                    .json()
+                    .await
+                    .map_err(|e| {
+                        AppError::InternalError(format!("Invalid Cargo index config {url}: {e}"))
+                    })?;
+                let template = config["dl"]
+                    .as_str()
+                    .ok_or_else(|| {
+                        AppError::InternalError(format!("Cargo index config {url} has no 'dl'"))
+                    })?
+                    .trim_end_matches('/')
+                    .to_string();
+                if let Ok(mut downloads) = self.cargo_downloads.lock() {
+                    downloads.insert(index.to_string(), template.clone());
+                }
+                template
+            }
+        };
+        Ok(cargo_download_from_template(&template, crate_name, version))
+    }
 
-        // Construct download URL from crates.io
-        let url = format!("https://crates.io/api/v1/crates/{crate_name}/{version}/download");
-        debug!(url = %url, "Streaming crate from crates.io");
+    async fn stream_cargo_crate_from(
+        &self,
+        url: &str,
+        crate_name: &str,
+        version: &str,
+    ) -> AppResult<bytes::Bytes> {
+        debug!(url = %url, "Streaming crate from Cargo registry");
 
-        let response = self.get_client()?.get(&url).send().await.map_err(|e| {
-            warn!(error = %e, "Failed to fetch crate from crates.io");
+        let response = self.get_client()?.get(url).send().await.map_err(|e| {
+            warn!(error = %e, "Failed to fetch crate from Cargo registry");
             AppError::NotFound(format!("Crate not found: {crate_name}-{version}"))
         })?;
 
@@ -395,7 +757,55 @@ impl UpstreamClient {
 
         // Use centralized validation and streaming logic
         let crate_filename = format!("{crate_name}-{version}.crate");
-        FileStreamValidator::validate_and_stream_response(response, "Cargo", &crate_filename).await
+        FileStreamValidator::validate_and_stream_response(
+            response,
+            "Cargo",
+            &crate_filename,
+            &self.throttle,
+        )
+        .await
+    }
+
+    /// Fetch a file from the upstream Go module proxy.
+    ///
+    /// `module` is the case-encoded module path and `file` the protocol path below
+    /// it, such as `@v/list` or `@v/v1.2.3.zip`. The proxy answers 404 or 410 for
+    /// unknown modules; both are reported as not found so `go` can fall through to
+    /// the next `GOPROXY` entry.
+    pub async fn fetch_go_module_file(&self, module: &str, file: &str) -> AppResult<bytes::Bytes> {
+        self.ensure_upstream()?;
+        self.policy
+            .check("go", &crate::gomod::decode_case(module))?;
+
+        first_success(&self.config.go_urls, |base| async move {
+            self.fetch_go_module_file_from(&base, module, file).await
+        })
+        .await
+    }
+
+    async fn fetch_go_module_file_from(
+        &self,
+        base: &str,
+        module: &str,
+        file: &str,
+    ) -> AppResult<bytes::Bytes> {
+        let url = format!("{base}/{module}/{file}");
+        debug!(url = %url, "Fetching from Go module proxy");
+
+        let response = self.get_client()?.get(&url).send().await.map_err(|e| {
+            warn!(error = %e, "Failed to fetch from Go module proxy");
+            AppError::NotFound(format!("Module not found: {module}"))
+        })?;
+
+        if !response.status().is_success() {
+            return Err(AppError::NotFound(format!(
+                "Module not found on Go proxy: {module}/{file}"
+            )));
+        }
+
+        // Use centralized validation and streaming logic
+        FileStreamValidator::validate_and_stream_response(response, "Go", file, &self.throttle)
+            .await
     }
 
     /// Update tarball URLs in npm metadata to point to the current server.
@@ -447,3 +857,85 @@ impl UpstreamClient {
         metadata
     }
 }
+
+/// Package a tarball path belongs to: `/@scope/name/-/name-1.0.0.tgz`
+fn npm_tarball_package(path: &str) -> Option<String> {
+    let (package, _) = path.split_once("/-/")?;
+    let package = package
+        .trim_start_matches('/')
+        .replace("%2f", "/")
+        .replace("%2F", "/");
+    (!package.is_empty()).then_some(package)
+}
+
+/// Expand a Cargo `dl` template. Without any markers, the registry serves
+/// `{dl}/{crate}/{version}/download`.
+fn cargo_download_from_template(template: &str, crate_name: &str, version: &str) -> String {
+    const MARKERS: [&str; 4] = ["{crate}", "{version}", "{prefix}", "{lowerprefix}"];
+    if !MARKERS.iter().any(|marker| template.contains(marker)) {
+        return format!("{template}/{crate_name}/{version}/download");
+    }
+    let prefix = match crate_name.len() {
+        1 => "1".to_string(),
+        2 => "2".to_string(),
+        3 => format!("3/{}", &crate_name[..1]),
+        _ => format!("{}/{}", &crate_name[..2], &crate_name[2..4]),
+    };
+    template
+        .replace("{crate}", crate_name)
+        .replace("{version}", version)
+        .replace("{lowerprefix}", &prefix.to_lowercase())
+        .replace("{prefix}", &prefix)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cargo_download_from_template() {
+        assert_eq!(
+            cargo_download_from_template("https://mirror.example/api/v1/crates", "serde", "1.0.0"),
+            "https://mirror.example/api/v1/crates/serde/1.0.0/download"
+        );
+        assert_eq!(
+            cargo_download_from_template(
+                "https://static.example/crates/{lowerprefix}/{crate}/{crate}-{version}.crate",
+                "Inflector",
+                "0.11.4"
+            ),
+            "https://static.example/crates/in/fl/Inflector/Inflector-0.11.4.crate"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_blocked_package_skips_fallbacks() {
+        let urls = vec![
+            "https://a.example".to_string(),
+            "https://b.example".to_string(),
+        ];
+        let mut tried = Vec::new();
+        let result: AppResult<()> = first_success(&urls, |url| {
+            tried.push(url);
+            async { Err(AppError::UpstreamBlocked("denied".to_string())) }
+        })
+        .await;
+        assert!(matches!(result, Err(AppError::UpstreamBlocked(_))));
+        assert_eq!(tried, ["https://a.example"]);
+
+        let mut tried = Vec::new();
+        let result = first_success(&urls, |url| {
+            tried.push(url.clone());
+            async move {
+                if url.contains("b.") {
+                    Ok(url)
+                } else {
+                    Err(AppError::NotFound("missing".to_string()))
+                }
+            }
+        })
+        .await;
+        assert_eq!(result.unwrap(), "https://b.example");
+        assert_eq!(tried.len(), 2);
+    }
+}
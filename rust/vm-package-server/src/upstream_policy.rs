@@ -0,0 +1,380 @@
+//! # Upstream package policies
+//!
+//! Allow and deny lists for packages proxied from upstream registries, for
+//! keeping known typosquats, packages with banned licenses or anything not
+//! reviewed yet out of builds. Rules are seeded from the `upstream_policy`
+//! section of `data/config.json`:
+//!
+//! ```json
+//! {
+//!   "upstream_policy": {
+//!     "deny": [
+//!       { "registry": "npm", "package": "crossenv", "reason": "typosquat of cross-env" }
+//!     ],
+//!     "allow": [
+//!       { "registry": "pypi", "package": "requests" },
+//!       { "registry": "pypi", "package": "django*" }
+//!     ]
+//!   }
+//! }
+//! ```
+//!
+//! A package is refused when it matches a `deny` rule, or when its registry has
+//! any `allow` rules and it matches none of them. Package names are compared
+//! after PyPI normalization (and lowercasing for Cargo), and `*`, `?` and `[...]`
+//! glob patterns are supported.
+//!
+//! The rules are enforced by [`UpstreamClient`](crate::upstream::UpstreamClient)
+//! before any request leaves the server, and cached copies of refused packages
+//! are no longer served. Locally published packages are never affected. Refused
+//! requests fail with `403` and the `upstream_blocked` error code.
+//!
+//! Admins change the lists at runtime through `/api/upstream/policy`. Changes take
+//! effect immediately and are saved to `upstream-policy.json` in the data
+//! directory, which then takes precedence over `data/config.json`.
+
+use crate::config::{PolicyRule, UpstreamPolicyConfig};
+use crate::{AppError, AppResult, AppState};
+use axum::{
+    extract::{Path as AxumPath, State},
+    http::HeaderMap,
+    Json,
+};
+use glob::Pattern;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use tracing::{info, warn};
+
+/// Rules saved by the admin API, in the data directory
+pub const POLICY_FILE: &str = "upstream-policy.json";
+
+/// Registries proxied from upstream. Helm charts are only served from local
+/// uploads, so there is no upstream to allow or block.
+const REGISTRIES: &[&str] = &["pypi", "npm", "cargo", "go"];
+
+/// Which list a rule belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PolicyList {
+    Allow,
+    Deny,
+}
+
+impl PolicyList {
+    fn as_str(self) -> &'static str {
+        match self {
+            PolicyList::Allow => "allow",
+            PolicyList::Deny => "deny",
+        }
+    }
+}
+
+/// Allow and deny rules in force, shared by the upstream client and the admin API
+#[derive(Debug, Default)]
+pub struct UpstreamPolicy {
+    /// Where runtime changes are saved; `None` keeps them in memory
+    path: Option<PathBuf>,
+    rules: RwLock<UpstreamPolicyConfig>,
+}
+
+impl UpstreamPolicy {
+    /// Rules from `upstream-policy.json` in `data_dir`, or `config` when there is none
+    pub fn open(data_dir: &Path, config: &UpstreamPolicyConfig) -> AppResult<Self> {
+        let path = data_dir.join(POLICY_FILE);
+        let rules = match std::fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => config.clone(),
+            Err(e) => return Err(e.into()),
+        };
+        validate(&rules)?;
+        Ok(Self {
+            path: Some(path),
+            rules: RwLock::new(rules),
+        })
+    }
+
+    /// Current rules
+    pub fn rules(&self) -> UpstreamPolicyConfig {
+        self.rules
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+
+    /// Refuse `package` if the rules don't allow proxying it from `registry`
+    pub fn check(&self, registry: &str, package: &str) -> AppResult<()> {
+        let rules = self
+            .rules
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let name = normalize(registry, package);
+
+        if let Some(rule) = rules
+            .deny
+            .iter()
+            .find(|rule| matches(rule, registry, &name))
+        {
+            warn!(registry = %registry, package = %name, "Upstream package denied by policy");
+            return Err(AppError::UpstreamBlocked(match &rule.reason {
+                Some(reason) => format!("{registry} package {name} is denied: {reason}"),
+                None => format!("{registry} package {name} is denied"),
+            }));
+        }
+        let mut allow = rules
+            .allow
+            .iter()
+            .filter(|rule| rule.registry == registry)
+            .peekable();
+        if allow.peek().is_some() && !allow.any(|rule| matches(rule, registry, &name)) {
+            warn!(registry = %registry, package = %name, "Upstream package not on allowlist");
+            return Err(AppError::UpstreamBlocked(format!(
+                "{registry} package {name} is not on the upstream allowlist"
+            )));
+        }
+        Ok(())
+    }
+
+    /// Whether `package` may be proxied from `registry`
+    pub fn allows(&self, registry: &str, package: &str) -> bool {
+        self.check(registry, package).is_ok()
+    }
+
+    /// Replace every rule
+    pub fn replace(&self, rules: UpstreamPolicyConfig) -> AppResult<()> {
+        validate(&rules)?;
+        let mut current = self
+            .rules
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        self.save(&rules)?;
+        *current = rules;
+        Ok(())
+    }
+
+    /// Add a rule to `list`, replacing an existing rule for the same package
+    pub fn add(&self, list: PolicyList, rule: PolicyRule) -> AppResult<()> {
+        validate_rule(&rule)?;
+        self.update(|rules| {
+            let rules = list_mut(rules, list);
+            rules.retain(|existing| {
+                existing.registry != rule.registry || existing.package != rule.package
+            });
+            rules.push(rule);
+        })
+    }
+
+    /// Remove the rule for `package` from `list`, returning whether there was one
+    pub fn remove(&self, list: PolicyList, registry: &str, package: &str) -> AppResult<bool> {
+        let mut removed = false;
+        self.update(|rules| {
+            let rules = list_mut(rules, list);
+            let before = rules.len();
+            rules.retain(|rule| rule.registry != registry || rule.package != package);
+            removed = rules.len() != before;
+        })?;
+        Ok(removed)
+    }
+
+    fn update(&self, change: impl FnOnce(&mut UpstreamPolicyConfig)) -> AppResult<()> {
+        let mut rules = self
+            .rules
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let mut updated = rules.clone();
+        change(&mut updated);
+        self.save(&updated)?;
+        *rules = updated;
+        Ok(())
+    }
+
+    fn save(&self, rules: &UpstreamPolicyConfig) -> AppResult<()> {
+        if let Some(path) = &self.path {
+            std::fs::write(path, serde_json::to_vec_pretty(rules)?)?;
+        }
+        Ok(())
+    }
+}
+
+fn list_mut(rules: &mut UpstreamPolicyConfig, list: PolicyList) -> &mut Vec<PolicyRule> {
+    match list {
+        PolicyList::Allow => &mut rules.allow,
+        PolicyList::Deny => &mut rules.deny,
+    }
+}
+
+/// Name packages are matched by
+fn normalize(registry: &str, package: &str) -> String {
+    match registry {
+        "pypi" => crate::normalize_pypi_name(package),
+        "cargo" => package.to_lowercase(),
+        _ => package.to_string(),
+    }
+}
+
+fn matches(rule: &PolicyRule, registry: &str, name: &str) -> bool {
+    rule.registry == registry
+        && Pattern::new(&normalize(registry, &rule.package))
+            .is_ok_and(|pattern| pattern.matches(name))
+}
+
+fn validate(rules: &UpstreamPolicyConfig) -> AppResult<()> {
+    rules
+        .allow
+        .iter()
+        .chain(&rules.deny)
+        .try_for_each(validate_rule)
+}
+
+fn validate_rule(rule: &PolicyRule) -> AppResult<()> {
+    if !REGISTRIES.contains(&rule.registry.as_str()) {
+        return Err(AppError::BadRequest(format!(
+            "Unknown registry '{}' (expected one of: {})",
+            rule.registry,
+            REGISTRIES.join(", ")
+        )));
+    }
+    if rule.package.trim().is_empty() {
+        return Err(AppError::BadRequest(
+            "Policy rules need a package name or pattern".to_string(),
+        ));
+    }
+    Pattern::new(&rule.package).map_err(|e| {
+        AppError::BadRequest(format!("Invalid package pattern '{}': {e}", rule.package))
+    })?;
+    Ok(())
+}
+
+/// Returns the allow and deny rules in force.
+///
+/// # Route
+/// `GET /api/upstream/policy`
+pub async fn get_policy(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> AppResult<Json<UpstreamPolicyConfig>> {
+    crate::tokens::require_admin(&state, &headers)?;
+    Ok(Json(state.upstream_client.load().policy().rules()))
+}
+
+/// Replaces every rule.
+///
+/// # Route
+/// `PUT /api/upstream/policy` with body `{"allow": [...], "deny": [...]}`
+pub async fn replace_policy(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(rules): Json<UpstreamPolicyConfig>,
+) -> AppResult<Json<UpstreamPolicyConfig>> {
+    crate::tokens::require_admin(&state, &headers)?;
+    let upstream = state.upstream_client.load();
+    let policy = upstream.policy();
+    policy.replace(rules)?;
+    info!("Replaced upstream policy");
+    Ok(Json(policy.rules()))
+}
+
+/// Adds a rule to the allow or deny list.
+///
+/// # Route
+/// `POST /api/upstream/policy/{list}` with body
+/// `{"registry": "npm", "package": "crossenv", "reason": "typosquat"}`
+pub async fn add_rule(
+    AxumPath(list): AxumPath<PolicyList>,
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(rule): Json<PolicyRule>,
+) -> AppResult<Json<UpstreamPolicyConfig>> {
+    crate::tokens::require_admin(&state, &headers)?;
+    info!(list = list.as_str(), registry = %rule.registry, package = %rule.package, "Adding upstream policy rule");
+    let upstream = state.upstream_client.load();
+    let policy = upstream.policy();
+    policy.add(list, rule)?;
+    Ok(Json(policy.rules()))
+}
+
+/// Removes a rule from the allow or deny list.
+///
+/// # Route
+/// `DELETE /api/upstream/policy/{list}/{registry}/{package}`
+pub async fn remove_rule(
+    AxumPath((list, registry, package)): AxumPath<(PolicyList, String, String)>,
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> AppResult<Json<UpstreamPolicyConfig>> {
+    crate::tokens::require_admin(&state, &headers)?;
+    let upstream = state.upstream_client.load();
+    let policy = upstream.policy();
+    if !policy.remove(list, &registry, &package)? {
+        return Err(AppError::NotFound(format!(
+            "No {} rule for {registry} package {package}",
+            list.as_str()
+        )));
+    }
+    info!(list = list.as_str(), registry = %registry, package = %package, "Removed upstream policy rule");
+    Ok(Json(policy.rules()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn rule(registry: &str, package: &str) -> PolicyRule {
+        PolicyRule {
+            registry: registry.to_string(),
+            package: package.to_string(),
+            reason: None,
+        }
+    }
+
+    #[test]
+    fn test_deny_and_allow_lists() {
+        let policy = UpstreamPolicy::default();
+        policy
+            .replace(UpstreamPolicyConfig {
+                allow: vec![rule("pypi", "requests"), rule("pypi", "django*")],
+                deny: vec![PolicyRule {
+                    reason: Some("typosquat".to_string()),
+                    ..rule("npm", "crossenv")
+                }],
+            })
+            .unwrap();
+
+        let denied = policy.check("npm", "crossenv").unwrap_err();
+        assert!(denied.to_string().contains("typosquat"));
+        assert!(policy.allows("npm", "cross-env"));
+        assert!(policy.allows("pypi", "Requests"));
+        assert!(policy.allows("pypi", "django-rest-framework"));
+        assert!(!policy.allows("pypi", "flask"));
+        // Registries without allow rules stay open
+        assert!(policy.allows("cargo", "serde"));
+    }
+
+    #[test]
+    fn test_runtime_changes_are_saved() {
+        let dir = TempDir::new().unwrap();
+        let seed = UpstreamPolicyConfig {
+            deny: vec![rule("cargo", "bad-crate")],
+            ..Default::default()
+        };
+        let policy = UpstreamPolicy::open(dir.path(), &seed).unwrap();
+        assert!(!policy.allows("cargo", "Bad-Crate"));
+
+        policy
+            .add(PolicyList::Deny, rule("go", "example.com/*"))
+            .unwrap();
+        assert!(policy
+            .remove(PolicyList::Deny, "cargo", "bad-crate")
+            .unwrap());
+        assert!(!policy
+            .remove(PolicyList::Allow, "cargo", "bad-crate")
+            .unwrap());
+        assert!(policy.add(PolicyList::Allow, rule("maven", "x")).is_err());
+
+        // The saved file wins over the configuration it was seeded from
+        let reopened = UpstreamPolicy::open(dir.path(), &seed).unwrap();
+        assert!(reopened.allows("cargo", "bad-crate"));
+        assert!(!reopened.allows("go", "example.com/tool"));
+    }
+}
@@ -4,6 +4,7 @@
 //! across file handling, streaming operations, and Docker parameter validation.
 
 use crate::error::AppError;
+use crate::throttle::UpstreamThrottle;
 use crate::validation::{
     validate_file_size, MAX_PACKAGE_FILE_SIZE, MAX_UPLOAD_SIZE, MEMORY_THRESHOLD,
 };
@@ -26,43 +27,56 @@ impl FileStreamValidator {
         response: Response,
         registry_type: &str,
         filename: &str,
+        throttle: &UpstreamThrottle,
     ) -> Result<Bytes, AppError> {
         // Check content length header for size validation
-        if let Some(content_length) = response.content_length() {
+        let content_length = response.content_length();
+        if let Some(content_length) = content_length {
             validate_file_size(content_length, Some(MAX_UPLOAD_SIZE)).map_err(|e| {
                 AppError::BadRequest(format!("{registry_type} file too large: {e}"))
             })?;
-
-            // For small files, load into memory directly
-            if content_length <= MEMORY_THRESHOLD as u64 {
-                let bytes = response.bytes().await.map_err(|e| {
-                    AppError::InternalError(format!("Failed to read {registry_type} file: {e}"))
-                })?;
-                info!(
-                    filename = %filename,
-                    size = bytes.len(),
-                    registry = %registry_type,
-                    "Successfully streamed (small file)"
-                );
-                return Ok(bytes);
-            }
         }
 
-        // For large files or unknown size, collect all bytes directly
-        // Note: This still loads into memory but adds size validation during the process
-        let bytes = response.bytes().await.map_err(|e| {
+        let registry = registry_type.to_ascii_lowercase();
+        let bytes = if throttle.is_limited(&registry) {
+            Self::read_throttled(response, &registry, throttle).await
+        } else {
+            response.bytes().await
+        }
+        .map_err(|e| {
             AppError::InternalError(format!("Failed to read {registry_type} file: {e}"))
         })?;
 
+        // Small files are loaded into memory directly; larger or unknown sizes
+        // still load into memory but were size-checked above
+        let kind = if content_length.is_some_and(|len| len <= MEMORY_THRESHOLD as u64) {
+            "small file"
+        } else {
+            "large file"
+        };
         info!(
             filename = %filename,
             size = bytes.len(),
             registry = %registry_type,
-            "Successfully streamed (large file)"
+            "Successfully streamed ({kind})"
         );
         Ok(bytes)
     }
 
+    /// Read a response body chunk by chunk, holding it to the bandwidth limits
+    async fn read_throttled(
+        mut response: Response,
+        registry: &str,
+        throttle: &UpstreamThrottle,
+    ) -> reqwest::Result<Bytes> {
+        let mut body = bytes::BytesMut::new();
+        while let Some(chunk) = response.chunk().await? {
+            throttle.consume(registry, chunk.len()).await;
+            body.extend_from_slice(&chunk);
+        }
+        Ok(body.freeze())
+    }
+
     /// Validate and read file from disk with size constraints and memory optimization
     ///
     /// This consolidates the pattern used across storage.rs for file reading operations
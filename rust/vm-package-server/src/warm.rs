@@ -0,0 +1,482 @@
+//! # Cache warming
+//!
+//! Fetches every package pinned in a lockfile into the upstream cache ahead of
+//! time, so a machine can install the project offline afterwards:
+//!
+//! ```text
+//! pkg-server warm --from requirements.txt --from package-lock.json --from Cargo.lock
+//! ```
+//!
+//! The CLI posts each file to `POST /api/cache/warm?format=<format>`:
+//!
+//! | Format         | File                                        | Packages warmed                      |
+//! |----------------|---------------------------------------------|--------------------------------------|
+//! | `requirements` | `requirements.txt`                          | `name==version` pins, or the latest  |
+//! | `package-lock` | `package-lock.json`, `npm-shrinkwrap.json`  | Every registry tarball in the tree   |
+//! | `cargo-lock`   | `Cargo.lock`                                | Every crates.io dependency           |
+//!
+//! Files are fetched the same way mirror manifests are (see [`crate::mirror`]), but
+//! are not pinned, so cache retention still applies to them. Packages published to
+//! this server and packages in private npm scopes are skipped.
+
+use crate::state::AppState;
+use crate::{auth, mirror, AppError, AppResult};
+use axum::extract::{Query, State};
+use axum::http::HeaderMap;
+use axum::Json;
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeSet, HashSet};
+use std::path::Path;
+use std::sync::Arc;
+use tracing::{info, warn};
+
+/// Route that warms the cache from a lockfile
+pub const WARM_ROUTE: &str = "/api/cache/warm";
+
+/// Largest lockfile accepted, in bytes
+pub const MAX_LOCKFILE_SIZE: usize = 32 * 1024 * 1024;
+
+/// Packages fetched from upstream at the same time
+const CONCURRENCY: usize = 8;
+
+/// Lockfile formats `warm` understands
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LockfileFormat {
+    Requirements,
+    PackageLock,
+    CargoLock,
+}
+
+impl LockfileFormat {
+    /// Format of a lockfile, from its file name
+    pub fn detect(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_str()?;
+        match name {
+            "package-lock.json" | "npm-shrinkwrap.json" => Some(Self::PackageLock),
+            "Cargo.lock" => Some(Self::CargoLock),
+            _ if name.ends_with(".txt") || name.ends_with(".in") => Some(Self::Requirements),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Requirements => "requirements",
+            Self::PackageLock => "package-lock",
+            Self::CargoLock => "cargo-lock",
+        }
+    }
+}
+
+/// A package named by a lockfile
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct LockedPackage {
+    pub registry: &'static str,
+    pub name: String,
+    /// `None` warms the latest version
+    pub version: Option<String>,
+}
+
+impl LockedPackage {
+    fn new(registry: &'static str, name: &str, version: Option<&str>) -> Self {
+        Self {
+            registry,
+            name: name.to_string(),
+            version: version.map(str::to_string),
+        }
+    }
+
+    fn label(&self) -> String {
+        match &self.version {
+            Some(version) => format!("{}:{}@{version}", self.registry, self.name),
+            None => format!("{}:{}", self.registry, self.name),
+        }
+    }
+}
+
+/// Outcome of a warm run
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WarmReport {
+    /// Packages named by the lockfile
+    pub packages: usize,
+    /// Files fetched into the upstream cache
+    pub fetched: usize,
+    /// Packages that were already cached, or are published here
+    pub up_to_date: usize,
+    /// Packages that could not be fetched, with the reason
+    pub failed: Vec<(String, String)>,
+}
+
+/// Packages listed in `content`, without duplicates
+pub fn parse(format: LockfileFormat, content: &str) -> AppResult<Vec<LockedPackage>> {
+    let packages = match format {
+        LockfileFormat::Requirements => parse_requirements(content),
+        LockfileFormat::PackageLock => parse_package_lock(content)?,
+        LockfileFormat::CargoLock => parse_cargo_lock(content),
+    };
+    let unique: BTreeSet<LockedPackage> = packages.into_iter().collect();
+    Ok(unique.into_iter().collect())
+}
+
+/// Requirement lines, with `==` pins giving the version. Options, includes and
+/// direct URL references are skipped.
+fn parse_requirements(content: &str) -> Vec<LockedPackage> {
+    let content = content.replace("\\\r\n", " ").replace("\\\n", " ");
+    content
+        .lines()
+        .filter_map(|line| {
+            let line = match line.find(" #") {
+                Some(comment) => &line[..comment],
+                None => line,
+            };
+            // Drop environment markers and per-requirement options like --hash
+            let line = line.split(';').next()?;
+            let line = line.split(" --").next()?.trim();
+            if line.is_empty() || line.starts_with(['#', '-']) || line.contains("://") {
+                return None;
+            }
+            let name_end = line
+                .find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.')))
+                .unwrap_or(line.len());
+            let (name, rest) = line.split_at(name_end);
+            if name.is_empty() {
+                return None;
+            }
+            let rest = match rest.trim_start().strip_prefix('[') {
+                Some(extras) => extras.split_once(']').map(|(_, rest)| rest)?,
+                None => rest,
+            };
+            let rest = rest.trim();
+            let version = rest
+                .strip_prefix("===")
+                .or_else(|| rest.strip_prefix("=="))
+                .map(str::trim)
+                .filter(|version| !version.is_empty() && !version.contains([',', '*', ' ']));
+            Some(LockedPackage::new("pypi", name, version))
+        })
+        .collect()
+}
+
+/// Registry packages in a `package-lock.json`, lockfile versions 1 to 3
+fn parse_package_lock(content: &str) -> AppResult<Vec<LockedPackage>> {
+    let lock: Value = serde_json::from_str(content)
+        .map_err(|e| AppError::BadRequest(format!("Invalid package-lock.json: {e}")))?;
+    let mut packages = Vec::new();
+    if let Some(entries) = lock["packages"].as_object() {
+        for (path, entry) in entries {
+            let Some((_, name)) = path.rsplit_once("node_modules/") else {
+                // The root project
+                continue;
+            };
+            if entry["link"] == true || !from_registry(entry) {
+                continue;
+            }
+            let name = entry["name"].as_str().unwrap_or(name);
+            if let Some(version) = entry["version"].as_str() {
+                packages.push(LockedPackage::new("npm", name, Some(version)));
+            }
+        }
+    } else {
+        collect_v1_dependencies(&lock["dependencies"], &mut packages);
+    }
+    Ok(packages)
+}
+
+/// Walk the nested `dependencies` tree of a version 1 lockfile
+fn collect_v1_dependencies(dependencies: &Value, packages: &mut Vec<LockedPackage>) {
+    let Some(dependencies) = dependencies.as_object() else {
+        return;
+    };
+    for (name, entry) in dependencies {
+        if from_registry(entry) {
+            if let Some(version) = entry["version"].as_str() {
+                // Aliases are recorded as `npm:real-name@version`
+                let (name, version) = version
+                    .strip_prefix("npm:")
+                    .and_then(|alias| alias.rsplit_once('@'))
+                    .filter(|(real, _)| !real.is_empty())
+                    .unwrap_or((name, version));
+                packages.push(LockedPackage::new("npm", name, Some(version)));
+            }
+        }
+        collect_v1_dependencies(&entry["dependencies"], packages);
+    }
+}
+
+/// Whether a lockfile entry was resolved to a registry tarball (not git, a file or a link)
+fn from_registry(entry: &Value) -> bool {
+    match entry["resolved"].as_str() {
+        Some(resolved) => resolved.starts_with("http") && resolved.ends_with(".tgz"),
+        None => entry["version"]
+            .as_str()
+            .is_some_and(|version| !version.contains(['/', ':']) || version.starts_with("npm:")),
+    }
+}
+
+/// crates.io dependencies in a `Cargo.lock`; workspace members, path and git
+/// dependencies have no crates.io source and are skipped
+fn parse_cargo_lock(content: &str) -> Vec<LockedPackage> {
+    let mut packages = Vec::new();
+    for block in content.split("[[package]]").skip(1) {
+        let field = |key: &str| {
+            block.lines().find_map(|line| {
+                let (name, value) = line.split_once('=')?;
+                (name.trim() == key).then(|| value.trim().trim_matches('"').to_string())
+            })
+        };
+        let from_crates_io = field("source").is_some_and(|source| {
+            source.starts_with("registry+https://github.com/rust-lang/crates.io-index")
+                || source.starts_with("sparse+https://index.crates.io/")
+        });
+        if let (true, Some(name), Some(version)) = (from_crates_io, field("name"), field("version"))
+        {
+            packages.push(LockedPackage::new("cargo", &name, Some(&version)));
+        }
+    }
+    packages
+}
+
+/// Names published to this server in `registry`, as the index records them
+async fn published(state: &AppState, registry: &'static str) -> HashSet<String> {
+    state
+        .package_index
+        .run(move |index| index.list(registry))
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .map(|name| name.to_lowercase())
+        .collect()
+}
+
+/// Fetch every package in `packages` into the upstream cache
+pub async fn warm(state: &AppState, packages: Vec<LockedPackage>) -> AppResult<WarmReport> {
+    if !state.config.load().cache.enabled {
+        return Err(AppError::BadRequest(
+            "Warming requires the upstream cache; enable `cache.enabled`".to_string(),
+        ));
+    }
+    if vm_core::offline::is_offline() {
+        return Err(AppError::BadRequest(
+            "Cannot warm the cache in offline mode".to_string(),
+        ));
+    }
+
+    let mut report = WarmReport {
+        packages: packages.len(),
+        ..WarmReport::default()
+    };
+    let mut local: Vec<(&str, HashSet<String>)> = Vec::new();
+    for registry in ["pypi", "npm", "cargo"] {
+        local.push((registry, published(state, registry).await));
+    }
+    let is_local = |package: &LockedPackage| {
+        let name = match package.registry {
+            "pypi" => crate::normalize_pypi_name(&package.name),
+            _ => package.name.to_lowercase(),
+        };
+        local
+            .iter()
+            .any(|(registry, names)| *registry == package.registry && names.contains(&name))
+            || (package.registry == "npm"
+                && auth::private_npm_scope(&state.config.load(), &package.name).is_some())
+    };
+    let (local, remote): (Vec<_>, Vec<_>) = packages.into_iter().partition(is_local);
+    report.up_to_date += local.len();
+
+    let mut results = futures_util::stream::iter(remote)
+        .map(|package| async move {
+            let version = package.version.as_deref();
+            let result = match package.registry {
+                "npm" => mirror::sync_npm(state, &package.name, version).await,
+                "pypi" => mirror::sync_pypi(state, &package.name, version).await,
+                _ => mirror::sync_cargo(state, &package.name, version).await,
+            };
+            (package, result)
+        })
+        .buffer_unordered(CONCURRENCY);
+    while let Some((package, result)) = results.next().await {
+        match result {
+            Ok((_, 0)) => report.up_to_date += 1,
+            Ok((_, fetched)) => report.fetched += fetched,
+            Err(e) => {
+                warn!(package = %package.label(), error = %e, "Failed to warm package");
+                report.failed.push((package.label(), e.to_string()));
+            }
+        }
+    }
+    report.failed.sort();
+    info!(
+        packages = report.packages,
+        fetched = report.fetched,
+        failed = report.failed.len(),
+        "Cache warm finished"
+    );
+    Ok(report)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct WarmQuery {
+    pub format: LockfileFormat,
+}
+
+/// Warm the upstream cache from the lockfile in the request body
+///
+/// # Route
+/// `POST /api/cache/warm?format=requirements|package-lock|cargo-lock`
+pub async fn warm_handler(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<WarmQuery>,
+    headers: HeaderMap,
+    body: String,
+) -> AppResult<Json<WarmReport>> {
+    if auth::is_auth_required(&state.config.load())
+        && !auth::is_authorized(&state.config.load(), &headers)
+    {
+        return Err(AppError::Unauthorized(
+            "A valid API token is required to warm the cache".to_string(),
+        ));
+    }
+    let packages = parse(query.format, &body)?;
+    Ok(Json(warm(&state, packages).await?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(packages: &[LockedPackage]) -> Vec<(&str, Option<&str>)> {
+        packages
+            .iter()
+            .map(|package| (package.name.as_str(), package.version.as_deref()))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_requirements() {
+        let content = "\
+# pinned by pip-compile
+requests==2.31.0 \\
+    --hash=sha256:abc
+Flask[async]==3.0.0 ; python_version >= \"3.8\"
+numpy>=1.26,<2
+urllib3===2.2.1  # exact
+-r other.txt
+--index-url https://pypi.org/simple
+-e ./local
+pkg @ https://example.com/pkg.whl
+";
+        let packages = parse(LockfileFormat::Requirements, content).unwrap();
+        assert_eq!(
+            names(&packages),
+            vec![
+                ("Flask", Some("3.0.0")),
+                ("numpy", None),
+                ("requests", Some("2.31.0")),
+                ("urllib3", Some("2.2.1")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_package_lock() {
+        let v3 = serde_json::json!({
+            "lockfileVersion": 3,
+            "packages": {
+                "": { "name": "app", "version": "1.0.0" },
+                "node_modules/react": {
+                    "version": "18.2.0",
+                    "resolved": "https://registry.npmjs.org/react/-/react-18.2.0.tgz"
+                },
+                "node_modules/a/node_modules/@types/node": {
+                    "version": "20.11.5",
+                    "resolved": "https://registry.npmjs.org/@types/node/-/node-20.11.5.tgz"
+                },
+                "node_modules/lodash-es": {
+                    "name": "lodash",
+                    "version": "4.17.21",
+                    "resolved": "https://registry.npmjs.org/lodash/-/lodash-4.17.21.tgz"
+                },
+                "node_modules/internal": { "resolved": "packages/internal", "link": true },
+                "node_modules/from-git": {
+                    "version": "1.0.0",
+                    "resolved": "git+ssh://git@github.com/acme/from-git.git#abc"
+                }
+            }
+        });
+        let packages = parse(LockfileFormat::PackageLock, &v3.to_string()).unwrap();
+        assert_eq!(
+            names(&packages),
+            vec![
+                ("@types/node", Some("20.11.5")),
+                ("lodash", Some("4.17.21")),
+                ("react", Some("18.2.0")),
+            ]
+        );
+
+        let v1 = serde_json::json!({
+            "lockfileVersion": 1,
+            "dependencies": {
+                "express": {
+                    "version": "4.18.2",
+                    "dependencies": { "debug": { "version": "2.6.9" } }
+                },
+                "old-lodash": { "version": "npm:lodash@3.10.1" }
+            }
+        });
+        let packages = parse(LockfileFormat::PackageLock, &v1.to_string()).unwrap();
+        assert_eq!(
+            names(&packages),
+            vec![
+                ("debug", Some("2.6.9")),
+                ("express", Some("4.18.2")),
+                ("lodash", Some("3.10.1")),
+            ]
+        );
+        assert!(parse(LockfileFormat::PackageLock, "not json").is_err());
+    }
+
+    #[test]
+    fn test_parse_cargo_lock() {
+        let content = r#"
+version = 3
+
+[[package]]
+name = "app"
+version = "0.1.0"
+dependencies = ["serde"]
+
+[[package]]
+name = "serde"
+version = "1.0.197"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+checksum = "abc"
+
+[[package]]
+name = "anyhow"
+version = "1.0.80"
+source = "sparse+https://index.crates.io/"
+
+[[package]]
+name = "forked"
+version = "0.2.0"
+source = "git+https://github.com/acme/forked#abc"
+"#;
+        let packages = parse(LockfileFormat::CargoLock, content).unwrap();
+        assert_eq!(
+            names(&packages),
+            vec![("anyhow", Some("1.0.80")), ("serde", Some("1.0.197"))]
+        );
+        assert_eq!(
+            LockfileFormat::detect(Path::new("web/package-lock.json")),
+            Some(LockfileFormat::PackageLock)
+        );
+        assert_eq!(
+            LockfileFormat::detect(Path::new("requirements-dev.txt")),
+            Some(LockfileFormat::Requirements)
+        );
+        assert_eq!(LockfileFormat::detect(Path::new("yarn.lock")), None);
+    }
+}
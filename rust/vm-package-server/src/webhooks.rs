@@ -0,0 +1,390 @@
+//! # Webhooks
+//!
+//! Notifies external services (CI, chat bots) when packages are published to,
+//! yanked from or deleted from the registry. Endpoints are configured in the `webhooks` section
+//! of `data/config.json`:
+//!
+//! ```json
+//! {
+//!   "webhooks": {
+//!     "endpoints": [
+//!       { "url": "https://ci.example.com/hooks/registry", "events": ["package.published"], "secret": "s3cret" }
+//!     ]
+//!   }
+//! }
+//! ```
+//!
+//! Each event is POSTed as JSON in a background task so publishing never waits on
+//! a slow endpoint. Network errors, `429` and `5xx` responses are retried with
+//! exponential backoff; other responses are treated as final. When a `secret` is
+//! set, the body is signed with HMAC-SHA256 and sent as
+//! `X-Pkg-Server-Signature: sha256=<hex>`.
+
+use crate::config::{WebhookEndpoint, WebhooksConfig};
+use crate::state::AppState;
+use hmac::{Hmac, Mac};
+use serde::Serialize;
+use sha2::Sha256;
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+/// Header carrying the event name
+pub const EVENT_HEADER: &str = "x-pkg-server-event";
+/// Header carrying a unique ID per event, identical across retries
+pub const DELIVERY_HEADER: &str = "x-pkg-server-delivery";
+/// Header carrying the HMAC-SHA256 signature of the body
+pub const SIGNATURE_HEADER: &str = "x-pkg-server-signature";
+
+/// Kind of registry change an event describes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum WebhookEventKind {
+    #[serde(rename = "package.published")]
+    Published,
+    #[serde(rename = "version.deleted")]
+    Deleted,
+    #[serde(rename = "version.yanked")]
+    Yanked,
+    #[serde(rename = "version.unyanked")]
+    Unyanked,
+}
+
+impl WebhookEventKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Published => "package.published",
+            Self::Deleted => "version.deleted",
+            Self::Yanked => "version.yanked",
+            Self::Unyanked => "version.unyanked",
+        }
+    }
+}
+
+/// JSON body sent to webhook endpoints
+#[derive(Debug, Clone, Serialize)]
+pub struct WebhookEvent {
+    pub event: WebhookEventKind,
+    /// Registry the package belongs to (`pypi`, `npm`, `cargo`, `go`, `helm`)
+    pub registry: String,
+    pub name: String,
+    /// Affected version; `None` when every version of a package was deleted
+    pub version: Option<String>,
+    /// RFC 3339 time the change was made
+    pub timestamp: String,
+    /// Address of the server that sent the event
+    pub server: String,
+}
+
+impl WebhookEvent {
+    pub fn new(
+        event: WebhookEventKind,
+        registry: &str,
+        name: &str,
+        version: Option<&str>,
+        server: &str,
+    ) -> Self {
+        Self {
+            event,
+            registry: registry.to_string(),
+            name: name.to_string(),
+            version: version.map(str::to_string),
+            timestamp: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            server: server.to_string(),
+        }
+    }
+}
+
+impl WebhookEndpoint {
+    fn wants(&self, event: &WebhookEvent) -> bool {
+        (self.events.is_empty() || self.events.iter().any(|e| e == event.event.as_str()))
+            && (self.registries.is_empty() || self.registries.contains(&event.registry))
+    }
+}
+
+/// Send a `package.published` event for a newly stored version.
+pub fn package_published(state: &AppState, registry: &str, name: &str, version: &str) {
+    notify(
+        state,
+        WebhookEvent::new(
+            WebhookEventKind::Published,
+            registry,
+            name,
+            Some(version),
+            &state.server_addr,
+        ),
+    );
+}
+
+/// Send a `version.deleted` event; `version` is `None` when all versions were removed.
+pub fn version_deleted(state: &AppState, registry: &str, name: &str, version: Option<&str>) {
+    notify(
+        state,
+        WebhookEvent::new(
+            WebhookEventKind::Deleted,
+            registry,
+            name,
+            version,
+            &state.server_addr,
+        ),
+    );
+}
+
+/// Send a `version.yanked` or, when `yanked` is false, `version.unyanked` event.
+pub fn version_yanked(state: &AppState, registry: &str, name: &str, version: &str, yanked: bool) {
+    let kind = if yanked {
+        WebhookEventKind::Yanked
+    } else {
+        WebhookEventKind::Unyanked
+    };
+    notify(
+        state,
+        WebhookEvent::new(kind, registry, name, Some(version), &state.server_addr),
+    );
+}
+
+/// Deliver `event` to every matching endpoint in the background.
+pub fn notify(state: &AppState, event: WebhookEvent) {
+    let config = &state.config.load().webhooks;
+    let endpoints: Vec<WebhookEndpoint> = config
+        .endpoints
+        .iter()
+        .filter(|endpoint| endpoint.wants(&event))
+        .cloned()
+        .collect();
+    if endpoints.is_empty() {
+        return;
+    }
+    if vm_core::offline::is_offline() {
+        debug!(
+            event = event.event.as_str(),
+            "Offline mode; not delivering webhooks"
+        );
+        return;
+    }
+
+    let body = match serde_json::to_vec(&event) {
+        Ok(body) => body,
+        Err(e) => {
+            warn!(error = %e, "Failed to serialize webhook event");
+            return;
+        }
+    };
+    let delivery_id = uuid::Uuid::new_v4().to_string();
+    for endpoint in endpoints {
+        tokio::spawn(deliver(
+            config.clone(),
+            endpoint,
+            event.event,
+            delivery_id.clone(),
+            body.clone(),
+        ));
+    }
+}
+
+fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(body);
+    let digest = mac.finalize().into_bytes();
+    let hex: String = digest.iter().map(|b| format!("{b:02x}")).collect();
+    format!("sha256={hex}")
+}
+
+/// Delay before retry number `attempt` (1-based)
+fn backoff(config: &WebhooksConfig, attempt: u32) -> Duration {
+    let factor = 1u64 << (attempt - 1).min(16);
+    Duration::from_millis(
+        config
+            .initial_backoff_ms
+            .saturating_mul(factor)
+            .min(config.max_backoff_ms),
+    )
+}
+
+/// POST one event to one endpoint, retrying transient failures.
+///
+/// Returns whether the endpoint accepted the event.
+async fn deliver(
+    config: WebhooksConfig,
+    endpoint: WebhookEndpoint,
+    kind: WebhookEventKind,
+    delivery_id: String,
+    body: Vec<u8>,
+) -> bool {
+    let client = match vm_core::network::client_builder()
+        .timeout(Duration::from_secs(config.timeout_seconds))
+        .build()
+    {
+        Ok(client) => client,
+        Err(e) => {
+            warn!(error = %e, "Failed to create webhook HTTP client");
+            return false;
+        }
+    };
+    let signature = endpoint.secret.as_deref().map(|secret| sign(secret, &body));
+
+    let max_attempts = config.max_attempts.max(1);
+    for attempt in 1..=max_attempts {
+        let mut request = client
+            .post(&endpoint.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(EVENT_HEADER, kind.as_str())
+            .header(DELIVERY_HEADER, &delivery_id)
+            .body(body.clone());
+        if let Some(signature) = &signature {
+            request = request.header(SIGNATURE_HEADER, signature);
+        }
+
+        let retryable = match request.send().await {
+            Ok(response) if response.status().is_success() => {
+                info!(url = %endpoint.url, event = kind.as_str(), attempt, "Webhook delivered");
+                return true;
+            }
+            Ok(response) => {
+                let status = response.status();
+                warn!(url = %endpoint.url, status = %status, attempt, "Webhook endpoint rejected event");
+                status.is_server_error() || status.as_u16() == 429
+            }
+            Err(e) => {
+                warn!(url = %endpoint.url, error = %e, attempt, "Webhook delivery failed");
+                true
+            }
+        };
+
+        if !retryable || attempt == max_attempts {
+            break;
+        }
+        let delay = backoff(&config, attempt);
+        debug!(url = %endpoint.url, delay_ms = delay.as_millis() as u64, "Retrying webhook");
+        tokio::time::sleep(delay).await;
+    }
+
+    warn!(url = %endpoint.url, event = kind.as_str(), delivery = %delivery_id, "Giving up on webhook delivery");
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{extract::State, http::HeaderMap, http::StatusCode, routing::post, Router};
+    use std::sync::{Arc, Mutex};
+
+    fn endpoint(url: &str) -> WebhookEndpoint {
+        WebhookEndpoint {
+            url: url.to_string(),
+            events: Vec::new(),
+            registries: Vec::new(),
+            secret: None,
+        }
+    }
+
+    #[test]
+    fn test_endpoint_filters() {
+        let event = WebhookEvent::new(
+            WebhookEventKind::Published,
+            "cargo",
+            "serde",
+            Some("1.0.0"),
+            "http://localhost:3080",
+        );
+        assert!(endpoint("http://hook").wants(&event));
+
+        let mut deletes_only = endpoint("http://hook");
+        deletes_only.events = vec!["version.deleted".to_string()];
+        assert!(!deletes_only.wants(&event));
+
+        let mut npm_only = endpoint("http://hook");
+        npm_only.registries = vec!["npm".to_string()];
+        assert!(!npm_only.wants(&event));
+
+        let body = serde_json::to_value(&event).unwrap();
+        assert_eq!(body["event"], "package.published");
+        assert_eq!(body["version"], "1.0.0");
+
+        let mut yanks_only = endpoint("http://hook");
+        yanks_only.events = vec!["version.yanked".to_string()];
+        let yank = WebhookEvent::new(
+            WebhookEventKind::Yanked,
+            "cargo",
+            "serde",
+            Some("1.0.0"),
+            "http://localhost:3080",
+        );
+        assert!(yanks_only.wants(&yank));
+        assert!(!yanks_only.wants(&event));
+        assert_eq!(
+            serde_json::to_value(&yank).unwrap()["event"],
+            yank.event.as_str()
+        );
+    }
+
+    #[test]
+    fn test_backoff_doubles_up_to_limit() {
+        let config = WebhooksConfig {
+            initial_backoff_ms: 500,
+            max_backoff_ms: 3000,
+            ..Default::default()
+        };
+        let delays: Vec<u64> = (1..=5)
+            .map(|attempt| backoff(&config, attempt).as_millis() as u64)
+            .collect();
+        assert_eq!(delays, [500, 1000, 2000, 3000, 3000]);
+    }
+
+    #[tokio::test]
+    async fn test_delivery_retries_server_errors_and_signs_body() {
+        // The receiver fails the first request, then accepts
+        type Received = Arc<Mutex<Vec<(Option<String>, Vec<u8>)>>>;
+        let received: Received = Arc::default();
+        let app = Router::new()
+            .route(
+                "/hook",
+                post(
+                    |State(received): State<Received>,
+                     headers: HeaderMap,
+                     body: axum::body::Bytes| async move {
+                        let mut received = received.lock().unwrap();
+                        let signature = headers
+                            .get(SIGNATURE_HEADER)
+                            .and_then(|v| v.to_str().ok())
+                            .map(str::to_string);
+                        received.push((signature, body.to_vec()));
+                        if received.len() == 1 {
+                            StatusCode::SERVICE_UNAVAILABLE
+                        } else {
+                            StatusCode::OK
+                        }
+                    },
+                ),
+            )
+            .with_state(received.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let config = WebhooksConfig {
+            initial_backoff_ms: 10,
+            ..Default::default()
+        };
+        let mut target = endpoint(&format!("http://{addr}/hook"));
+        target.secret = Some("s3cret".to_string());
+        let body = br#"{"event":"package.published"}"#.to_vec();
+
+        let delivered = deliver(
+            config,
+            target,
+            WebhookEventKind::Published,
+            "delivery-1".to_string(),
+            body.clone(),
+        )
+        .await;
+
+        assert!(delivered);
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 2);
+        assert_eq!(received[1].1, body);
+        assert_eq!(
+            received[1].0.as_deref(),
+            Some(sign("s3cret", &body).as_str())
+        );
+    }
+}
@@ -50,13 +50,23 @@ pub async fn create_test_setup() -> Result<TestSetup> {
     let upstream_client = Arc::new(UpstreamClient::new(upstream_config).unwrap());
     let config = Arc::new(Config::default());
 
+    let package_index = Arc::new(
+        vm_package_server::package_index::PackageIndex::open(&data_dir)
+            .expect("Failed to open package index"),
+    );
+
+    let tokens = Arc::new(
+        vm_package_server::tokens::TokenStore::open(&data_dir).expect("Failed to open token store"),
+    );
     let app_state = Arc::new(AppState {
         data_dir: data_dir.clone(),
         server_addr: "http://localhost:8080".to_string(),
-        upstream_client,
-        config,
+        upstream_client: upstream_client.into(),
+        config: config.into(),
         npm_registry: vm_package_server::registry::NpmRegistry::new(),
         pypi_registry: vm_package_server::registry::PypiRegistry::new(),
+        package_index,
+        tokens,
     });
 
     // Find available port for testing
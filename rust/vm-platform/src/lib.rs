@@ -73,6 +73,11 @@ pub mod platform {
         current().total_memory_gb()
     }
 
+    /// Show a desktop notification using the platform's native mechanism
+    pub fn send_notification(title: &str, body: &str) -> Result<()> {
+        current().send_notification(title, body)
+    }
+
     /// Get Docker host gateway address for container-to-host communication
     pub fn get_host_gateway() -> &'static str {
         if cfg!(target_os = "linux") {
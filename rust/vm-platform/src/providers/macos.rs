@@ -208,6 +208,23 @@ impl PlatformProvider for MacOSPlatform {
 
     // === Process Operations ===
 
+    fn send_notification(&self, title: &str, body: &str) -> Result<()> {
+        let script = format!(
+            "display notification {} with title {}",
+            applescript_string(body),
+            applescript_string(title)
+        );
+        let status = Command::new("osascript")
+            .args(["-e", &script])
+            .output()
+            .context("Failed to run osascript")?
+            .status;
+        if !status.success() {
+            anyhow::bail!("osascript exited with {status}");
+        }
+        Ok(())
+    }
+
     fn path_separator(&self) -> char {
         self.default_path_separator()
     }
@@ -221,6 +238,11 @@ impl PlatformProvider for MacOSPlatform {
     }
 }
 
+/// Quote a string as an AppleScript string literal
+fn applescript_string(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
 impl MacOSPlatform {
     fn try_get_python_paths(&self, cmd: &str, paths: &mut Vec<PathBuf>) -> Result<bool> {
         let output = Command::new(cmd)
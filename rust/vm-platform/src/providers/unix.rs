@@ -200,6 +200,18 @@ impl PlatformProvider for UnixPlatform {
 
     // === Process Operations ===
 
+    fn send_notification(&self, title: &str, body: &str) -> Result<()> {
+        let status = Command::new("notify-send")
+            .args(["--app-name=vm", title, body])
+            .output()
+            .context("Failed to run notify-send (install libnotify-bin)")?
+            .status;
+        if !status.success() {
+            anyhow::bail!("notify-send exited with {status}");
+        }
+        Ok(())
+    }
+
     fn path_separator(&self) -> char {
         self.default_path_separator()
     }
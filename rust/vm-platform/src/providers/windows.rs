@@ -220,6 +220,28 @@ impl PlatformProvider for WindowsPlatform {
 
     // === Process Operations ===
 
+    fn send_notification(&self, title: &str, body: &str) -> Result<()> {
+        let script = format!(
+            "[Windows.UI.Notifications.ToastNotificationManager, Windows.UI.Notifications, ContentType = WindowsRuntime] > $null; \
+             $t = [Windows.UI.Notifications.ToastNotificationManager]::GetTemplateContent([Windows.UI.Notifications.ToastTemplateType]::ToastText02); \
+             $x = $t.GetElementsByTagName('text'); \
+             $x.Item(0).AppendChild($t.CreateTextNode({})) > $null; \
+             $x.Item(1).AppendChild($t.CreateTextNode({})) > $null; \
+             [Windows.UI.Notifications.ToastNotificationManager]::CreateToastNotifier('vm').Show([Windows.UI.Notifications.ToastNotification]::new($t))",
+            powershell_string(title),
+            powershell_string(body)
+        );
+        let status = Command::new("powershell")
+            .args(["-NoProfile", "-NonInteractive", "-Command", &script])
+            .output()
+            .context("Failed to run PowerShell")?
+            .status;
+        if !status.success() {
+            anyhow::bail!("PowerShell toast exited with {status}");
+        }
+        Ok(())
+    }
+
     fn path_separator(&self) -> char {
         ';'
     }
@@ -233,6 +255,11 @@ impl PlatformProvider for WindowsPlatform {
     }
 }
 
+/// Quote a string as a PowerShell single-quoted literal
+fn powershell_string(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
 /// Windows process provider
 pub struct WindowsProcessProvider;
 
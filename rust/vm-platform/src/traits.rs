@@ -167,6 +167,16 @@ pub trait PlatformProvider: Send + Sync {
     /// or if system information is not accessible.
     fn total_memory_gb(&self) -> Result<u64>;
 
+    // === Notifications ===
+
+    /// Show a desktop notification (Notification Center, libnotify, or a toast)
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the platform notification tool is unavailable
+    /// or fails to display the notification.
+    fn send_notification(&self, title: &str, body: &str) -> Result<()>;
+
     // === Process Operations ===
 
     /// Get the PATH environment variable separator
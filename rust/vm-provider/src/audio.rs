@@ -1,100 +0,0 @@
-#[cfg(target_os = "macos")]
-use {
-    std::process::{Command, Stdio},
-    tracing::info,
-    vm_core::{
-        error::{Result, VmError},
-        vm_error,
-    },
-};
-
-#[cfg(target_os = "macos")]
-/// Manages PulseAudio server on macOS for container audio.
-pub struct MacOSAudioManager;
-
-#[cfg(target_os = "macos")]
-impl MacOSAudioManager {
-    /// Ensures PulseAudio is installed and running.
-    pub fn setup() -> Result<()> {
-        if !is_pulseaudio_installed()? {
-            info!("🎧 Installing PulseAudio via Homebrew...");
-            install_pulseaudio()?;
-        }
-        start_pulseaudio_daemon()
-    }
-
-    /// Stops the PulseAudio daemon.
-    pub fn cleanup() -> Result<()> {
-        info!("⏹️ Stopping audio services...");
-        Command::new("pulseaudio")
-            .arg("-k")
-            .status()
-            .map_err(|e| VmError::Internal(format!("Failed to stop PulseAudio daemon: {}", e)))?;
-        Ok(())
-    }
-}
-
-#[cfg(target_os = "macos")]
-fn is_pulseaudio_installed() -> Result<bool> {
-    Ok(Command::new("brew")
-        .args(["list", "pulseaudio"])
-        .stdout(Stdio::null())
-        .stderr(Stdio::null())
-        .status()?
-        .success())
-}
-
-#[cfg(target_os = "macos")]
-fn install_pulseaudio() -> Result<()> {
-    let status = Command::new("brew")
-        .args(["install", "pulseaudio"])
-        .status()
-        .map_err(|e| {
-            VmError::Internal(format!(
-                "Failed to execute 'brew install pulseaudio'. Make sure Homebrew is installed: {}",
-                e
-            ))
-        })?;
-    if !status.success() {
-        vm_error!("'brew install pulseaudio' failed.");
-        return Err(VmError::Internal(
-            "brew install pulseaudio failed".to_string(),
-        ));
-    }
-    Ok(())
-}
-
-#[cfg(target_os = "macos")]
-fn start_pulseaudio_daemon() -> Result<()> {
-    info!("🎧 Starting audio services...");
-    let status = Command::new("pulseaudio")
-        .args([
-            "--load=module-native-protocol-unix",
-            "--exit-idle-time=-1",
-            "--daemon",
-        ])
-        .status()
-        .map_err(|e| VmError::Internal(format!("Failed to start PulseAudio daemon: {}", e)))?;
-    if !status.success() {
-        vm_error!("Failed to start PulseAudio daemon.");
-        return Err(VmError::Internal(
-            "Failed to start PulseAudio daemon".to_string(),
-        ));
-    }
-    Ok(())
-}
-
-// Stub implementation for non-macOS platforms to allow compilation.
-#[cfg(not(target_os = "macos"))]
-pub struct MacOSAudioManager;
-
-#[cfg(not(target_os = "macos"))]
-impl MacOSAudioManager {
-    pub fn setup() {
-        // Do nothing on non-macOS platforms.
-    }
-
-    pub fn cleanup() {
-        // Do nothing on non-macOS platforms.
-    }
-}
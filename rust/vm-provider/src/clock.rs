@@ -0,0 +1,114 @@
+//! Clock skew between the host and a VM.
+//!
+//! Containers share the host kernel's clock, but full VMs keep their own and
+//! can fall behind after the host sleeps. Once the difference passes a few
+//! seconds, TLS handshakes and JWT validation fail with errors that never
+//! mention the clock, so `vm status` and `vm doctor` measure it explicitly.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use vm_core::error::{Result, VmError};
+
+/// Skew tolerated before warning
+pub const SKEW_TOLERANCE: Duration = Duration::from_secs(5);
+
+/// Guest command printing its clock as Unix seconds
+pub const GUEST_TIME_COMMAND: &str = "date -u +%s";
+
+/// How far a VM's clock is from the host's
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClockSkew {
+    /// Guest time minus host time, in seconds
+    pub seconds: i64,
+}
+
+impl ClockSkew {
+    /// Whether the skew is large enough to break TLS or token validation
+    pub fn is_significant(&self) -> bool {
+        self.seconds.unsigned_abs() > SKEW_TOLERANCE.as_secs()
+    }
+
+    /// Human-readable skew, e.g. `3m 12s behind the host`
+    pub fn describe(&self) -> String {
+        if self.seconds == 0 {
+            return "in sync with the host".to_string();
+        }
+        let total = self.seconds.unsigned_abs();
+        let (hours, minutes, seconds) = (total / 3600, total % 3600 / 60, total % 60);
+        let amount = if hours > 0 {
+            format!("{hours}h {minutes}m")
+        } else if minutes > 0 {
+            format!("{minutes}m {seconds}s")
+        } else {
+            format!("{seconds}s")
+        };
+        let direction = if self.seconds > 0 {
+            "ahead of"
+        } else {
+            "behind"
+        };
+        format!("{amount} {direction} the host")
+    }
+}
+
+fn unix_seconds(time: SystemTime) -> f64 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs_f64()
+}
+
+/// Measure skew from the output of [`GUEST_TIME_COMMAND`].
+///
+/// The guest time is compared with the midpoint of the host clock before and
+/// after `read_guest_time`, which cancels out most of the command's latency.
+pub fn measure(read_guest_time: impl FnOnce() -> Result<String>) -> Result<ClockSkew> {
+    let before = unix_seconds(SystemTime::now());
+    let output = read_guest_time()?;
+    let after = unix_seconds(SystemTime::now());
+
+    let guest: i64 = output.trim().parse().map_err(|_| {
+        VmError::Provider(format!(
+            "Unexpected output from `{GUEST_TIME_COMMAND}`: {}",
+            output.trim()
+        ))
+    })?;
+    let host = ((before + after) / 2.0).round() as i64;
+    Ok(ClockSkew {
+        seconds: guest - host,
+    })
+}
+
+/// Shell script that sets the guest clock to the host's current time.
+///
+/// Handles both Linux (`date -s @<epoch>`) and macOS (`date -f %s <epoch>`) guests.
+pub fn sync_script() -> String {
+    let now = unix_seconds(SystemTime::now()).round() as i64;
+    format!(
+        "if [ \"$(uname)\" = Darwin ]; then sudo date -u -f %s {now}; else sudo date -u -s @{now}; fi"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_measure_and_describe_skew() {
+        let now = unix_seconds(SystemTime::now()) as i64;
+        let skew = measure(|| Ok(format!("{}\n", now - 192))).unwrap();
+        assert!((-193..=-191).contains(&skew.seconds));
+        assert!(skew.is_significant());
+        assert!(skew.describe().ends_with("behind the host"));
+
+        assert!(!ClockSkew { seconds: 3 }.is_significant());
+        assert_eq!(
+            ClockSkew {
+                seconds: 3 * 3600 + 120
+            }
+            .describe(),
+            "3h 2m ahead of the host"
+        );
+        assert_eq!(ClockSkew { seconds: 0 }.describe(), "in sync with the host");
+
+        assert!(measure(|| Ok("Thu Jan 1".to_string())).is_err());
+    }
+}
@@ -0,0 +1,471 @@
+//! Host bridge for browser launches and the clipboard
+//!
+//! While `vm ssh` or `vm exec` runs, a small HTTP listener on the host serves
+//! helpers installed in the VM:
+//!
+//! - `GET /<token>/open?url=<url>` from the `vm-open` shim (also linked as
+//!   `xdg-open`, `open`, `sensible-browser` and set as `$BROWSER`) opens the URL
+//!   in the host's browser, so OAuth logins in CLIs work from a headless VM.
+//! - `POST /<token>/clipboard` and `GET /<token>/clipboard` from `vm-clip` (also
+//!   linked as `pbcopy` and `pbpaste`) copy to and paste from the host clipboard
+//!   when `host_sync.clipboard` is on.
+//!
+//! The listener binds only the address containers use to reach the host,
+//! requires a random per-session token and only opens http(s) URLs. Without a
+//! bridge `vm-open` prints the URL and `vm-clip` copies through OSC 52 escapes.
+
+use sha2::{Digest, Sha256};
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+use tracing::{debug, warn};
+
+/// Environment variable that tells the shims where the bridge listens
+pub const BRIDGE_ENV: &str = "VM_HOST_BRIDGE";
+
+/// Environment variable that turns on `vm-clip`
+pub const CLIPBOARD_ENV: &str = "VM_CLIPBOARD";
+
+/// Path of the browser shim inside the VM
+pub const SHIM_PATH: &str = "/usr/local/bin/vm-open";
+
+/// Longest request head accepted
+const MAX_HEAD: usize = 16 * 1024;
+
+/// Largest clipboard content accepted
+const MAX_BODY: usize = 4 * 1024 * 1024;
+
+/// What a bridge forwards to the host
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BridgeFeatures {
+    /// Open http(s) URLs in the host browser
+    pub browser: bool,
+    /// Copy to and paste from the host clipboard
+    pub clipboard: bool,
+}
+
+/// Forwards browser launches and clipboard access from a VM to the host until dropped
+pub struct HostBridge {
+    url: String,
+    addr: SocketAddr,
+    features: BridgeFeatures,
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl HostBridge {
+    /// Start a bridge for containers run by `executable`.
+    ///
+    /// Returns `None` when no feature is enabled, containers of this runtime
+    /// can't reach a listener on the host (Podman) or the address can't be bound.
+    pub fn start(executable: &str, features: BridgeFeatures) -> Option<Self> {
+        if executable != "docker" || features == BridgeFeatures::default() {
+            return None;
+        }
+        let gateway = vm_platform::platform::get_host_gateway();
+        // Docker Desktop forwards host.docker.internal to the host's loopback
+        let bind_host = if cfg!(target_os = "linux") {
+            gateway
+        } else {
+            "127.0.0.1"
+        };
+        let listener = match TcpListener::bind((bind_host, 0)) {
+            Ok(listener) => listener,
+            Err(e) => {
+                debug!(address = bind_host, error = %e, "Host bridge unavailable");
+                return None;
+            }
+        };
+        let addr = listener.local_addr().ok()?;
+        let token = session_token();
+        let url = format!("http://{gateway}:{}/{token}", addr.port());
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let handle = {
+            let stop = stop.clone();
+            std::thread::spawn(move || serve(&listener, &token, features, &stop))
+        };
+
+        Some(Self {
+            url,
+            addr,
+            features,
+            stop,
+            handle: Some(handle),
+        })
+    }
+
+    /// `KEY=value` variables that point the shims at this bridge
+    pub fn env(&self) -> Vec<String> {
+        let mut env = vec![format!("{BRIDGE_ENV}={}", self.url)];
+        if self.features.browser {
+            env.push(format!("BROWSER={SHIM_PATH}"));
+        }
+        if self.features.clipboard {
+            env.push(format!("{CLIPBOARD_ENV}=1"));
+        }
+        env
+    }
+}
+
+impl Drop for HostBridge {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        // Wake the accept loop so it sees the stop flag
+        let _ = TcpStream::connect_timeout(&self.addr, Duration::from_millis(200));
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// Random token, so other processes that can reach the port can't use the bridge
+fn session_token() -> String {
+    let mut seed = [0u8; 32];
+    let from_os = std::fs::File::open("/dev/urandom")
+        .and_then(|mut random| random.read_exact(&mut seed))
+        .is_ok();
+    if !from_os {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_nanos())
+            .unwrap_or_default();
+        seed = Sha256::digest(format!("{}:{nanos}:{:p}", std::process::id(), &seed)).into();
+    }
+    Sha256::digest(seed)
+        .iter()
+        .take(16)
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+fn serve(listener: &TcpListener, token: &str, features: BridgeFeatures, stop: &AtomicBool) {
+    for stream in listener.incoming() {
+        if stop.load(Ordering::Relaxed) {
+            break;
+        }
+        if let Ok(stream) = stream {
+            handle_connection(stream, token, features);
+        }
+    }
+}
+
+/// A request to the bridge, reduced to what the routes need
+#[derive(Debug, Default)]
+struct Request {
+    method: String,
+    target: String,
+    body: Vec<u8>,
+}
+
+/// What a valid request asks the host to do
+#[derive(Debug, PartialEq, Eq)]
+enum Action {
+    Open(String),
+    Copy(Vec<u8>),
+    Paste,
+}
+
+fn handle_connection(mut stream: TcpStream, token: &str, features: BridgeFeatures) {
+    let _ = stream.set_read_timeout(Some(Duration::from_secs(5)));
+    let (status, body) = match read_request(&mut stream) {
+        Some(request) => match route(request, token, features) {
+            Ok(action) => perform(action),
+            Err(status) => (status, Vec::new()),
+        },
+        None => ("400 Bad Request", Vec::new()),
+    };
+    let _ = write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        body.len()
+    );
+    let _ = stream.write_all(&body);
+}
+
+fn read_request(stream: &mut impl Read) -> Option<Request> {
+    let mut data = Vec::new();
+    let mut chunk = [0u8; 8192];
+    let head_end = loop {
+        if let Some(end) = data.windows(4).position(|window| window == b"\r\n\r\n") {
+            break end;
+        }
+        if data.len() > MAX_HEAD {
+            return None;
+        }
+        match stream.read(&mut chunk) {
+            Ok(0) | Err(_) => return None,
+            Ok(read) => data.extend_from_slice(&chunk[..read]),
+        }
+    };
+
+    let head = String::from_utf8_lossy(&data[..head_end]).into_owned();
+    let mut lines = head.lines();
+    let mut request_line = lines.next()?.split_whitespace();
+    let method = request_line.next()?.to_string();
+    let target = request_line.next()?.to_string();
+    let content_length = lines
+        .filter_map(|line| line.split_once(':'))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("content-length"))
+        .map(|(_, value)| value.trim().parse::<usize>().ok())
+        .unwrap_or(Some(0))?;
+    if content_length > MAX_BODY {
+        return None;
+    }
+
+    let mut body = data.split_off(head_end + 4);
+    while body.len() < content_length {
+        match stream.read(&mut chunk) {
+            Ok(0) | Err(_) => return None,
+            Ok(read) => body.extend_from_slice(&chunk[..read]),
+        }
+    }
+    body.truncate(content_length);
+    Some(Request {
+        method,
+        target,
+        body,
+    })
+}
+
+/// Check the token and feature for `request`, returning the error status otherwise
+fn route(request: Request, token: &str, features: BridgeFeatures) -> Result<Action, &'static str> {
+    let (path, query) = request
+        .target
+        .split_once('?')
+        .unwrap_or((&request.target, ""));
+    let endpoint = path
+        .strip_prefix('/')
+        .and_then(|path| path.strip_prefix(token))
+        .and_then(|path| path.strip_prefix('/'))
+        .ok_or("404 Not Found")?;
+
+    match (request.method.as_str(), endpoint) {
+        ("GET", "open") if features.browser => {
+            let url = query
+                .split('&')
+                .find_map(|pair| pair.strip_prefix("url="))
+                .and_then(percent_decode)
+                .ok_or("400 Bad Request")?;
+            let scheme_ok = url.starts_with("http://") || url.starts_with("https://");
+            if !scheme_ok || url.chars().any(|ch| ch.is_control() || ch.is_whitespace()) {
+                return Err("400 Bad Request");
+            }
+            Ok(Action::Open(url))
+        }
+        ("POST", "clipboard") if features.clipboard => Ok(Action::Copy(request.body)),
+        ("GET", "clipboard") if features.clipboard => Ok(Action::Paste),
+        (_, "open") if features.browser => Err("405 Method Not Allowed"),
+        (_, "clipboard") if features.clipboard => Err("405 Method Not Allowed"),
+        _ => Err("404 Not Found"),
+    }
+}
+
+fn perform(action: Action) -> (&'static str, Vec<u8>) {
+    let result = match action {
+        Action::Open(url) => open_in_browser(&url).map(|()| Vec::new()),
+        Action::Copy(content) => copy_to_clipboard(&content).map(|()| Vec::new()),
+        Action::Paste => paste_from_clipboard(),
+    };
+    match result {
+        Ok(body) if body.is_empty() => ("204 No Content", body),
+        Ok(body) => ("200 OK", body),
+        Err(e) => {
+            warn!(error = %e, "Host bridge request failed");
+            ("502 Bad Gateway", Vec::new())
+        }
+    }
+}
+
+fn percent_decode(value: &str) -> Option<String> {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        match bytes[index] {
+            b'%' => {
+                let hex = std::str::from_utf8(bytes.get(index + 1..index + 3)?).ok()?;
+                decoded.push(u8::from_str_radix(hex, 16).ok()?);
+                index += 3;
+            }
+            b'+' => {
+                decoded.push(b' ');
+                index += 1;
+            }
+            byte => {
+                decoded.push(byte);
+                index += 1;
+            }
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+fn open_in_browser(url: &str) -> std::io::Result<()> {
+    let mut command = if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else if cfg!(windows) {
+        let mut command = Command::new("rundll32");
+        command.arg("url.dll,FileProtocolHandler");
+        command
+    } else {
+        Command::new("xdg-open")
+    };
+    command
+        .arg(url)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map(|_| ())
+}
+
+/// The host's clipboard tool for copying, or pasting when `paste` is set
+fn clipboard_command(paste: bool) -> Command {
+    if cfg!(target_os = "macos") {
+        Command::new(if paste { "pbpaste" } else { "pbcopy" })
+    } else if cfg!(windows) {
+        if paste {
+            let mut command = Command::new("powershell");
+            command.args(["-NoProfile", "-Command", "Get-Clipboard -Raw"]);
+            command
+        } else {
+            Command::new("clip")
+        }
+    } else if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+        let mut command = Command::new(if paste { "wl-paste" } else { "wl-copy" });
+        if paste {
+            command.arg("--no-newline");
+        }
+        command
+    } else {
+        let mut command = Command::new("xclip");
+        command.args(["-selection", "clipboard"]);
+        if paste {
+            command.arg("-o");
+        }
+        command
+    }
+}
+
+fn copy_to_clipboard(content: &[u8]) -> std::io::Result<()> {
+    let mut child = clipboard_command(false)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(content)?;
+    }
+    let status = child.wait()?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            format!("clipboard tool exited with {status}"),
+        ))
+    }
+}
+
+fn paste_from_clipboard() -> std::io::Result<Vec<u8>> {
+    let output = clipboard_command(true)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()?;
+    if output.status.success() {
+        Ok(output.stdout)
+    } else {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            format!("clipboard tool exited with {}", output.status),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BOTH: BridgeFeatures = BridgeFeatures {
+        browser: true,
+        clipboard: true,
+    };
+
+    fn request(raw: &str) -> Request {
+        read_request(&mut raw.as_bytes()).expect("request should parse")
+    }
+
+    #[test]
+    fn test_open_requests_need_token_and_web_url() {
+        let token = "abc123";
+        assert_eq!(
+            route(
+                request("GET /abc123/open?url=https%3A%2F%2Fgithub.com%2Flogin%3Fcode%3D1 HTTP/1.1\r\n\r\n"),
+                token,
+                BOTH
+            ),
+            Ok(Action::Open("https://github.com/login?code=1".to_string()))
+        );
+        assert_eq!(
+            route(
+                request("GET /wrong/open?url=https%3A%2F%2Fa.dev HTTP/1.1\r\n\r\n"),
+                token,
+                BOTH
+            ),
+            Err("404 Not Found")
+        );
+        assert_eq!(
+            route(
+                request("GET /abc123/open?url=file%3A%2F%2F%2Fetc%2Fpasswd HTTP/1.1\r\n\r\n"),
+                token,
+                BOTH
+            ),
+            Err("400 Bad Request")
+        );
+        assert_eq!(
+            route(
+                request("POST /abc123/open?url=https%3A%2F%2Fa.dev HTTP/1.1\r\n\r\n"),
+                token,
+                BOTH
+            ),
+            Err("405 Method Not Allowed")
+        );
+        assert_eq!(session_token().len(), 32);
+    }
+
+    #[test]
+    fn test_clipboard_requests_only_when_enabled() {
+        let token = "abc123";
+        let copy = "POST /abc123/clipboard HTTP/1.1\r\nContent-Length: 5\r\n\r\nhello";
+        assert_eq!(
+            route(request(copy), token, BOTH),
+            Ok(Action::Copy(b"hello".to_vec()))
+        );
+        assert_eq!(
+            route(
+                request("GET /abc123/clipboard HTTP/1.1\r\n\r\n"),
+                token,
+                BOTH
+            ),
+            Ok(Action::Paste)
+        );
+
+        let browser_only = BridgeFeatures {
+            browser: true,
+            clipboard: false,
+        };
+        assert_eq!(
+            route(request(copy), token, browser_only),
+            Err("404 Not Found")
+        );
+        assert!(read_request(
+            &mut "POST / HTTP/1.1\r\nContent-Length: 99999999\r\n\r\n".as_bytes()
+        )
+        .is_none());
+    }
+}
@@ -0,0 +1,126 @@
+//! Host path translation for bind mounts.
+//!
+//! Mount specs are written as `source:target[:mode]`, so a Windows source such as
+//! `C:\Users\me\app` is ambiguous (the drive colon) and its backslashes mean nothing
+//! to the Linux side. On Windows hosts, sources are rewritten into the form each
+//! provider's backend accepts before they go into a compose file. Paths on other
+//! hosts are passed through untouched.
+
+use std::borrow::Cow;
+
+/// How a provider expects Windows host paths in a mount source
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MountPathStyle {
+    /// Docker Desktop: `C:/Users/me/app`
+    DockerDesktop,
+    /// Podman machine (a WSL distribution): `/mnt/c/Users/me/app`
+    PodmanMachine,
+}
+
+impl MountPathStyle {
+    /// Style for the container runtime binary (`docker`, `podman`, or a path to either)
+    pub fn for_executable(executable: &str) -> Self {
+        if executable.contains("podman") {
+            Self::PodmanMachine
+        } else {
+            Self::DockerDesktop
+        }
+    }
+}
+
+/// Rewrite a Windows path for a mount source, or `None` if it isn't one.
+///
+/// Handles drive paths (`C:\dir`, `C:/dir`), UNC shares (`\\server\share\dir`) and
+/// the `\\?\` verbatim prefix that `std::fs::canonicalize` adds on Windows.
+pub fn translate_windows_path(path: &str, style: MountPathStyle) -> Option<String> {
+    let path = if let Some(unc) = path.strip_prefix(r"\\?\UNC\") {
+        Cow::Owned(format!(r"\\{unc}"))
+    } else {
+        Cow::Borrowed(path.strip_prefix(r"\\?\").unwrap_or(path))
+    };
+
+    // UNC shares look the same to both backends
+    if let Some(share) = path.strip_prefix(r"\\") {
+        return Some(format!("//{}", share.replace('\\', "/")));
+    }
+
+    let mut chars = path.chars();
+    if let (Some(drive), Some(':')) = (chars.next(), chars.next()) {
+        if drive.is_ascii_alphabetic() {
+            let rest = path[2..].replace('\\', "/");
+            let rest = rest.trim_start_matches('/');
+            return Some(match style {
+                MountPathStyle::DockerDesktop => {
+                    format!("{}:/{rest}", drive.to_ascii_uppercase())
+                }
+                MountPathStyle::PodmanMachine => {
+                    format!("/mnt/{}/{rest}", drive.to_ascii_lowercase())
+                }
+            });
+        }
+    }
+
+    path.contains('\\').then(|| path.replace('\\', "/"))
+}
+
+/// A host path in the form to write into a mount spec for this provider
+pub fn host_mount_path(path: &str, style: MountPathStyle) -> Cow<'_, str> {
+    if cfg!(windows) {
+        if let Some(translated) = translate_windows_path(path, style) {
+            return Cow::Owned(translated);
+        }
+    }
+    Cow::Borrowed(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drive_letters_follow_the_provider_style() {
+        let path = r"C:\Users\me\my app";
+        assert_eq!(
+            translate_windows_path(path, MountPathStyle::DockerDesktop).as_deref(),
+            Some("C:/Users/me/my app")
+        );
+        assert_eq!(
+            translate_windows_path(path, MountPathStyle::PodmanMachine).as_deref(),
+            Some("/mnt/c/Users/me/my app")
+        );
+        assert_eq!(
+            translate_windows_path("d:/src", MountPathStyle::DockerDesktop).as_deref(),
+            Some("D:/src")
+        );
+    }
+
+    #[test]
+    fn verbatim_and_unc_prefixes_are_normalized() {
+        assert_eq!(
+            translate_windows_path(r"\\?\C:\work", MountPathStyle::PodmanMachine).as_deref(),
+            Some("/mnt/c/work")
+        );
+        assert_eq!(
+            translate_windows_path(r"\\fileserver\team\repo", MountPathStyle::DockerDesktop)
+                .as_deref(),
+            Some("//fileserver/team/repo")
+        );
+        assert_eq!(
+            translate_windows_path(r"\\?\UNC\fileserver\team", MountPathStyle::DockerDesktop)
+                .as_deref(),
+            Some("//fileserver/team")
+        );
+    }
+
+    #[test]
+    fn unix_paths_are_left_alone() {
+        assert_eq!(
+            translate_windows_path("/home/me/app", MountPathStyle::DockerDesktop),
+            None
+        );
+        assert_eq!(
+            host_mount_path("/home/me/app", MountPathStyle::PodmanMachine),
+            "/home/me/app"
+        );
+    }
+}
@@ -0,0 +1,129 @@
+//! Per-instance environment overrides
+//!
+//! Instances of the same project share one vm.yaml. To let a `test` instance
+//! differ from the default one, each instance may have a `KEY=VALUE` file in
+//! state (see [`vm_core::user_paths::instance_env_path`]) whose entries override
+//! `environment` when the compose file is generated. Values passed with
+//! `vm start --env` are applied last.
+
+use indexmap::IndexMap;
+use std::fs;
+use vm_core::error::{Result, VmError};
+
+/// Parse a single `KEY=VALUE` assignment
+pub fn parse_env_assignment(assignment: &str) -> Result<(String, String)> {
+    let (key, value) = assignment.split_once('=').ok_or_else(|| {
+        VmError::validation(
+            format!("Invalid environment variable '{assignment}'. Expected KEY=VALUE"),
+            None::<String>,
+        )
+    })?;
+    let key = key.trim();
+    let valid_key = !key.is_empty()
+        && !key.starts_with(|c: char| c.is_ascii_digit())
+        && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !valid_key {
+        return Err(VmError::validation(
+            format!("Invalid environment variable name '{key}'"),
+            None::<String>,
+        ));
+    }
+    Ok((key.to_string(), value.to_string()))
+}
+
+/// Parse the contents of an override file, skipping blank lines and comments
+pub fn parse_env_file(content: &str) -> Result<Vec<(String, String)>> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(parse_env_assignment)
+        .collect()
+}
+
+/// Load the override file for an instance, if there is one
+pub fn load_instance_env(project: &str, instance: &str) -> Result<Vec<(String, String)>> {
+    let path = vm_core::user_paths::instance_env_path(project, instance)?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    parse_env_file(&fs::read_to_string(&path)?)
+}
+
+/// Merge variables into an instance's override file, replacing existing keys
+pub fn save_instance_env(project: &str, instance: &str, vars: &[(String, String)]) -> Result<()> {
+    let mut merged: IndexMap<String, String> =
+        load_instance_env(project, instance)?.into_iter().collect();
+    apply_env_overrides(&mut merged, vars);
+
+    let path = vm_core::user_paths::instance_env_path(project, instance)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let content: String = merged
+        .iter()
+        .map(|(key, value)| format!("{key}={value}\n"))
+        .collect();
+    fs::write(&path, content)?;
+    Ok(())
+}
+
+/// Apply overrides to an environment, later entries winning
+pub fn apply_env_overrides(environment: &mut IndexMap<String, String>, vars: &[(String, String)]) {
+    for (key, value) in vars {
+        environment.insert(key.clone(), value.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_override_file() {
+        let vars = parse_env_file("# test instance\nDATABASE=test_db\n\nLOG_LEVEL=debug=verbose\n")
+            .unwrap();
+        assert_eq!(
+            vars,
+            vec![
+                ("DATABASE".to_string(), "test_db".to_string()),
+                ("LOG_LEVEL".to_string(), "debug=verbose".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_assignments() {
+        assert!(parse_env_assignment("NO_EQUALS").is_err());
+        assert!(parse_env_assignment("=value").is_err());
+        assert!(parse_env_assignment("1KEY=value").is_err());
+        assert!(parse_env_assignment("BAD-KEY=value").is_err());
+        assert_eq!(
+            parse_env_assignment("EMPTY=").unwrap(),
+            ("EMPTY".to_string(), String::new())
+        );
+    }
+
+    #[test]
+    fn overrides_replace_existing_keys_in_place() {
+        let mut environment = IndexMap::new();
+        environment.insert("A".to_string(), "1".to_string());
+        environment.insert("B".to_string(), "2".to_string());
+        apply_env_overrides(
+            &mut environment,
+            &[
+                ("B".to_string(), "3".to_string()),
+                ("C".to_string(), "4".to_string()),
+            ],
+        );
+        let pairs: Vec<_> = environment.into_iter().collect();
+        assert_eq!(
+            pairs,
+            vec![
+                ("A".to_string(), "1".to_string()),
+                ("B".to_string(), "3".to_string()),
+                ("C".to_string(), "4".to_string()),
+            ]
+        );
+    }
+}
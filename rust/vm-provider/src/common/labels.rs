@@ -0,0 +1,143 @@
+//! Resource labels and the adoption registry
+//!
+//! Everything a provider creates carries the labels from [`vm_core::labels`].
+//! Container engines can't add labels to a container after it is created, so a
+//! container brought under management with `vm adopt` is recorded in
+//! `~/.vm/adopted.json` instead and merged into instance listings from there.
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+use vm_core::error::{Result, VmError};
+
+/// A pre-existing container registered with `vm adopt`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AdoptedContainer {
+    pub id: String,
+    pub provider: String,
+    /// Labels the container would carry had vm created it
+    pub labels: BTreeMap<String, String>,
+    pub adopted_at: String,
+}
+
+impl AdoptedContainer {
+    pub fn new(id: &str, provider: &str, project: &str, instance: &str, config_hash: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            provider: provider.to_string(),
+            labels: vm_core::labels::resource_labels(project, instance, config_hash)
+                .into_iter()
+                .collect(),
+            adopted_at: Utc::now().to_rfc3339(),
+        }
+    }
+
+    pub fn project(&self) -> Option<&str> {
+        self.labels
+            .get(vm_core::labels::PROJECT)
+            .map(String::as_str)
+    }
+}
+
+/// Containers adopted into vm management, keyed by container name
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct AdoptionRegistry {
+    #[serde(default)]
+    pub containers: BTreeMap<String, AdoptedContainer>,
+}
+
+impl AdoptionRegistry {
+    /// Load the registry, starting empty if it doesn't exist yet
+    pub fn load() -> Result<Self> {
+        Self::load_from(&vm_core::user_paths::adopted_containers_path()?)
+    }
+
+    /// Load the registry for listings, warning about and skipping a corrupt file
+    /// so it can't break `vm list`
+    pub fn load_or_warn() -> Self {
+        Self::load().unwrap_or_else(|e| {
+            vm_core::vm_warning!("Ignoring adopted containers: {}", e);
+            Self::default()
+        })
+    }
+
+    fn load_from(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(path)?;
+        serde_json::from_str(&content).map_err(|e| {
+            VmError::Serialization(format!(
+                "Invalid adoption registry at {}: {e}",
+                path.display()
+            ))
+        })
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = vm_core::user_paths::adopted_containers_path()?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Adopted containers belonging to one provider
+    pub fn for_provider<'a>(
+        &'a self,
+        provider: &'a str,
+    ) -> impl Iterator<Item = (&'a String, &'a AdoptedContainer)> + 'a {
+        self.containers
+            .iter()
+            .filter(move |(_, container)| container.provider == provider)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vm_config::config::VmConfig;
+
+    #[test]
+    fn config_hash_is_stable_and_sensitive_to_changes() {
+        let config = VmConfig::default();
+        assert_eq!(config.config_hash(), config.clone().config_hash());
+        assert_eq!(config.config_hash().len(), 12);
+
+        let mut changed = config.clone();
+        changed.provider = Some("podman".to_string());
+        assert_ne!(config.config_hash(), changed.config_hash());
+    }
+
+    #[test]
+    fn adopted_container_records_full_label_set() {
+        let adopted = AdoptedContainer::new("abc", "docker", "legacy", "default", "0123");
+        assert_eq!(adopted.project(), Some("legacy"));
+        assert_eq!(
+            adopted.labels.get(vm_core::labels::MANAGED),
+            Some(&"true".to_string())
+        );
+        assert_eq!(
+            adopted.labels.get(vm_core::labels::CONFIG_HASH),
+            Some(&"0123".to_string())
+        );
+    }
+
+    #[test]
+    fn missing_registry_is_empty_and_corrupt_one_is_an_error() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("adopted.json");
+        assert!(AdoptionRegistry::load_from(&path)
+            .unwrap()
+            .containers
+            .is_empty());
+
+        std::fs::write(&path, "{ not json").unwrap();
+        assert!(matches!(
+            AdoptionRegistry::load_from(&path),
+            Err(VmError::Serialization(_))
+        ));
+    }
+}
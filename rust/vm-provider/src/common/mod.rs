@@ -1 +1,7 @@
+pub mod host_bridge;
+pub mod host_paths;
 pub mod instance;
+pub mod instance_env;
+pub mod labels;
+pub mod script;
+pub mod shell;
@@ -0,0 +1,75 @@
+//! Running host scripts inside a VM
+//!
+//! `vm exec --file` and `vm exec --stdin` send the script body over the exec
+//! session's stdin instead of splicing it into a command line, so nothing in the
+//! script needs escaping. Inside the VM the body is written to a temporary file,
+//! made executable and run with the caller's arguments, which keeps the shebang
+//! line meaningful and lets the script's exit code come back unchanged.
+//!
+//! With a read-only root the Docker compose template mounts `/tmp` as an `exec`
+//! tmpfs, since tmpfs mounts are `noexec` by default.
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use vm_core::error::{Result, VmError};
+
+/// Shell program that receives the script on stdin; arguments follow as `$@`
+pub const SCRIPT_RUNNER: &str = r#"f=$(mktemp "${TMPDIR:-/tmp}/vm-exec.XXXXXX") || exit 1
+trap 'rm -f "$f"' EXIT
+cat > "$f" && chmod +x "$f" && "$f" "$@""#;
+
+/// Command (after any provider-specific prefix) that runs [`SCRIPT_RUNNER`]
+pub fn runner_command(args: &[String]) -> Vec<String> {
+    let mut cmd = vec![
+        "sh".to_string(),
+        "-c".to_string(),
+        SCRIPT_RUNNER.to_string(),
+        "vm-script".to_string(),
+    ];
+    cmd.extend(args.iter().cloned());
+    cmd
+}
+
+/// Run `program args...`, write `script` to its stdin and return its exit code
+pub fn run_with_script(program: &str, args: &[String], script: &[u8]) -> Result<i32> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .spawn()
+        .map_err(|e| VmError::Command(format!("Failed to run {program}: {e}")))?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        // A script that exits early closes the pipe; its exit code says what happened
+        if let Err(e) = stdin.write_all(script) {
+            if e.kind() != std::io::ErrorKind::BrokenPipe {
+                return Err(e.into());
+            }
+        }
+    }
+
+    let status = child.wait()?;
+    Ok(status.code().unwrap_or(1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn runner_passes_arguments_through_unquoted() {
+        let cmd = runner_command(&["it's".to_string(), "$HOME".to_string()]);
+        assert_eq!(&cmd[..2], ["sh", "-c"]);
+        assert_eq!(cmd[3], "vm-script");
+        assert_eq!(&cmd[4..], ["it's", "$HOME"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn script_runs_with_arguments_and_exit_code() {
+        let script = b"#!/bin/sh\n[ \"$1\" = \"a b\" ] || exit 9\nexit 3\n";
+        let code = run_with_script("sh", &runner_command(&["a b".to_string()])[1..], script)
+            .expect("sh should run");
+        assert_eq!(code, 3);
+    }
+}
@@ -21,6 +21,12 @@ pub struct ProviderContext {
     pub is_snapshot: bool,
     /// Force refresh of all packages (bypasses cache for security updates)
     pub refresh_packages: bool,
+    /// Registry reference to import/export BuildKit layer caches (e.g. `localhost:5000/vm-cache/app:buildcache`)
+    pub build_cache_ref: Option<String>,
+    /// Environment variables overriding `environment` in vm.yaml for this run
+    pub env_overrides: Vec<(String, String)>,
+    /// Read-only workspace and no persistent volumes (`vm create --ephemeral`)
+    pub ephemeral: bool,
 }
 
 impl ProviderContext {
@@ -67,6 +73,24 @@ impl ProviderContext {
         self
     }
 
+    /// Set the registry reference used for shared build layer caches
+    pub fn with_build_cache(mut self, cache_ref: Option<String>) -> Self {
+        self.build_cache_ref = cache_ref;
+        self
+    }
+
+    /// Set environment variables that override the configured environment
+    pub fn with_env_overrides(mut self, env: Vec<(String, String)>) -> Self {
+        self.env_overrides = env;
+        self
+    }
+
+    /// Set whether the VM leaves nothing behind on the host
+    pub fn ephemeral(mut self, ephemeral: bool) -> Self {
+        self.ephemeral = ephemeral;
+        self
+    }
+
     /// Check if verbose mode is enabled (CLI flag or environment variable)
     pub fn is_verbose(&self) -> bool {
         self.verbose || env::var("VM_VERBOSE").is_ok() || env::var("VM_DEBUG").is_ok()
@@ -0,0 +1,360 @@
+//! Host device passthrough (`devices` in vm.yaml).
+//!
+//! Audio, webcams and USB devices are resolved to host device nodes before a VM
+//! is created, so a missing device or a provider that can't pass it through
+//! fails early with an explanation instead of a Docker error halfway through.
+//!
+//! | Device | Docker/Podman on Linux | Docker/Podman on macOS | Tart |
+//! |--------|------------------------|------------------------|------|
+//! | audio  | /dev/snd + PulseAudio  | PulseAudio over a socket | virtual sound device |
+//! | webcam | /dev/video*            | not supported          | not supported |
+//! | usb    | /dev/bus/usb nodes     | not supported          | not supported |
+
+use std::path::{Path, PathBuf};
+use vm_config::config::{UsbDevice, VmConfig};
+use vm_core::error::{Result, VmError};
+
+/// Devices resolved for a VM
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DevicePassthrough {
+    /// Share the host sound server
+    pub audio: bool,
+    /// Host device nodes to map into the container
+    pub nodes: Vec<String>,
+    /// Groups the VM user joins to access `nodes`
+    pub groups: Vec<String>,
+}
+
+/// Check that `provider` can pass through every device `config` asks for and
+/// resolve them to device nodes.
+pub fn preflight(config: &VmConfig, provider: &str) -> Result<DevicePassthrough> {
+    let requested = config.requested_devices();
+    if requested.is_empty() {
+        return Ok(DevicePassthrough::default());
+    }
+
+    let unsupported = |device: &str, reason: &str| {
+        Err(VmError::Config(format!(
+            "{device} passthrough is not available with the {provider} provider: {reason}. Remove it from 'devices' in vm.yaml."
+        )))
+    };
+
+    if provider == "tart" {
+        if requested.webcam {
+            return unsupported("Webcam", "Tart VMs can't access host cameras");
+        }
+        if !requested.usb.is_empty() {
+            return unsupported("USB", "Tart VMs can't access host USB devices");
+        }
+        // Tart VMs come with a virtual sound device that plays on the host
+        return Ok(DevicePassthrough {
+            audio: requested.audio,
+            ..Default::default()
+        });
+    }
+
+    if !cfg!(target_os = "linux") {
+        let reason = "containers run inside a Linux VM that can't see host devices";
+        if requested.webcam {
+            return unsupported("Webcam", reason);
+        }
+        if !requested.usb.is_empty() {
+            return unsupported("USB", reason);
+        }
+        if requested.audio && !cfg!(target_os = "macos") {
+            return unsupported("Audio", "it needs a Linux or macOS host");
+        }
+        // macOS audio goes through the PulseAudio socket started by MacOSAudioManager
+        return Ok(DevicePassthrough {
+            audio: requested.audio,
+            ..Default::default()
+        });
+    }
+
+    resolve_linux(
+        requested.audio,
+        requested.webcam,
+        &requested.usb,
+        Path::new("/dev"),
+        Path::new("/sys/bus/usb/devices"),
+    )
+}
+
+/// Resolve device nodes under `dev`, looking USB IDs up in `usb_sysfs`
+fn resolve_linux(
+    audio: bool,
+    webcam: bool,
+    usb: &[String],
+    dev: &Path,
+    usb_sysfs: &Path,
+) -> Result<DevicePassthrough> {
+    let mut devices = DevicePassthrough {
+        audio,
+        ..Default::default()
+    };
+    let host_path = |path: &Path| path.to_string_lossy().into_owned();
+
+    if audio {
+        let snd = dev.join("snd");
+        if !snd.is_dir() {
+            return Err(VmError::Config(format!(
+                "Audio passthrough needs a sound card, but {} does not exist on this host",
+                snd.display()
+            )));
+        }
+        devices.nodes.push(host_path(&snd));
+        devices.groups.push("audio".to_string());
+    }
+
+    if webcam {
+        let cameras = webcams(dev);
+        if cameras.is_empty() {
+            return Err(VmError::Config(format!(
+                "Webcam passthrough is enabled, but no {}/video* devices were found. Is a camera connected?",
+                dev.display()
+            )));
+        }
+        devices
+            .nodes
+            .extend(cameras.iter().map(|camera| host_path(camera)));
+        devices.groups.push("video".to_string());
+    }
+
+    for spec in usb {
+        let node = match UsbDevice::parse(spec) {
+            Some(UsbDevice::Path(path)) => PathBuf::from(path),
+            Some(UsbDevice::Id { vendor, product }) => {
+                find_usb_device(usb_sysfs, vendor, product)
+                    .map(|(bus, device)| dev.join(format!("bus/usb/{bus:03}/{device:03}")))
+                    .ok_or_else(|| {
+                        VmError::Config(format!("USB device {spec} is not connected to this host"))
+                    })?
+            }
+            None => {
+                return Err(VmError::Config(format!(
+                    "Invalid USB device '{spec}': expected a vendor:product ID like 046d:0825 or a /dev/bus/usb path"
+                )))
+            }
+        };
+        if !node.exists() {
+            return Err(VmError::Config(format!(
+                "USB device {spec} ({}) does not exist on this host",
+                node.display()
+            )));
+        }
+        devices.nodes.push(host_path(&node));
+    }
+
+    Ok(devices)
+}
+
+/// `video*` nodes in `dev`, in order
+fn webcams(dev: &Path) -> Vec<PathBuf> {
+    let mut cameras: Vec<PathBuf> = std::fs::read_dir(dev)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| {
+            entry
+                .file_name()
+                .to_str()
+                .and_then(|name| name.strip_prefix("video"))
+                .is_some_and(|index| !index.is_empty() && index.bytes().all(|b| b.is_ascii_digit()))
+        })
+        .map(|entry| entry.path())
+        .collect();
+    cameras.sort();
+    cameras
+}
+
+/// Bus and device number of the first USB device with this vendor and product ID
+fn find_usb_device(usb_sysfs: &Path, vendor: u16, product: u16) -> Option<(u32, u32)> {
+    let read = |dir: &Path, name: &str| {
+        std::fs::read_to_string(dir.join(name))
+            .ok()
+            .map(|value| value.trim().to_string())
+    };
+    let mut entries: Vec<PathBuf> = std::fs::read_dir(usb_sysfs)
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .collect();
+    entries.sort();
+    entries.into_iter().find_map(|dir| {
+        let id = |name| read(&dir, name).and_then(|id| u16::from_str_radix(&id, 16).ok());
+        if id("idVendor")? != vendor || id("idProduct")? != product {
+            return None;
+        }
+        Some((
+            read(&dir, "busnum")?.parse().ok()?,
+            read(&dir, "devnum")?.parse().ok()?,
+        ))
+    })
+}
+
+#[cfg(target_os = "macos")]
+use {
+    std::process::{Command, Stdio},
+    tracing::info,
+    vm_core::vm_error,
+};
+
+#[cfg(target_os = "macos")]
+/// Manages PulseAudio server on macOS for container audio.
+pub struct MacOSAudioManager;
+
+#[cfg(target_os = "macos")]
+impl MacOSAudioManager {
+    /// Ensures PulseAudio is installed and running.
+    pub fn setup() -> Result<()> {
+        if !is_pulseaudio_installed()? {
+            info!("🎧 Installing PulseAudio via Homebrew...");
+            install_pulseaudio()?;
+        }
+        start_pulseaudio_daemon()
+    }
+
+    /// Stops the PulseAudio daemon.
+    pub fn cleanup() -> Result<()> {
+        info!("⏹️ Stopping audio services...");
+        Command::new("pulseaudio")
+            .arg("-k")
+            .status()
+            .map_err(|e| VmError::Internal(format!("Failed to stop PulseAudio daemon: {}", e)))?;
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn is_pulseaudio_installed() -> Result<bool> {
+    Ok(Command::new("brew")
+        .args(["list", "pulseaudio"])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()?
+        .success())
+}
+
+#[cfg(target_os = "macos")]
+fn install_pulseaudio() -> Result<()> {
+    let status = Command::new("brew")
+        .args(["install", "pulseaudio"])
+        .status()
+        .map_err(|e| {
+            VmError::Internal(format!(
+                "Failed to execute 'brew install pulseaudio'. Make sure Homebrew is installed: {}",
+                e
+            ))
+        })?;
+    if !status.success() {
+        vm_error!("'brew install pulseaudio' failed.");
+        return Err(VmError::Internal(
+            "brew install pulseaudio failed".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "macos")]
+fn start_pulseaudio_daemon() -> Result<()> {
+    info!("🎧 Starting audio services...");
+    let status = Command::new("pulseaudio")
+        .args([
+            "--load=module-native-protocol-unix",
+            "--exit-idle-time=-1",
+            "--daemon",
+        ])
+        .status()
+        .map_err(|e| VmError::Internal(format!("Failed to start PulseAudio daemon: {}", e)))?;
+    if !status.success() {
+        vm_error!("Failed to start PulseAudio daemon.");
+        return Err(VmError::Internal(
+            "Failed to start PulseAudio daemon".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+// Stub implementation for non-macOS platforms to allow compilation.
+#[cfg(not(target_os = "macos"))]
+pub struct MacOSAudioManager;
+
+#[cfg(not(target_os = "macos"))]
+impl MacOSAudioManager {
+    pub fn setup() {
+        // Do nothing on non-macOS platforms.
+    }
+
+    pub fn cleanup() {
+        // Do nothing on non-macOS platforms.
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn fake_usb_device(sysfs: &Path, name: &str, ids: (&str, &str), numbers: (&str, &str)) {
+        let dir = sysfs.join(name);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("idVendor"), format!("{}\n", ids.0)).unwrap();
+        fs::write(dir.join("idProduct"), format!("{}\n", ids.1)).unwrap();
+        fs::write(dir.join("busnum"), numbers.0).unwrap();
+        fs::write(dir.join("devnum"), numbers.1).unwrap();
+    }
+
+    #[test]
+    fn test_resolve_linux_device_nodes() {
+        let root = tempfile::tempdir().unwrap();
+        let dev = root.path().join("dev");
+        let sysfs = root.path().join("sys");
+        fs::create_dir_all(dev.join("snd")).unwrap();
+        fs::create_dir_all(dev.join("bus/usb/003")).unwrap();
+        fs::write(dev.join("bus/usb/003/012"), "").unwrap();
+        for node in ["video2", "video0", "video-loopback"] {
+            fs::write(dev.join(node), "").unwrap();
+        }
+        fake_usb_device(&sysfs, "1-1", ("1d6b", "0002"), ("1", "1"));
+        fake_usb_device(&sysfs, "3-2", ("046d", "0825"), ("3", "12"));
+
+        let devices = resolve_linux(true, true, &["046d:0825".to_string()], &dev, &sysfs).unwrap();
+        let node = |path: &str| dev.join(path).to_string_lossy().into_owned();
+        assert!(devices.audio);
+        assert_eq!(
+            devices.nodes,
+            vec![
+                node("snd"),
+                node("video0"),
+                node("video2"),
+                node("bus/usb/003/012")
+            ]
+        );
+        assert_eq!(devices.groups, vec!["audio", "video"]);
+
+        let missing = resolve_linux(false, false, &["1234:5678".to_string()], &dev, &sysfs);
+        assert!(missing.unwrap_err().to_string().contains("not connected"));
+    }
+
+    #[test]
+    fn test_preflight_rejects_unsupported_devices() {
+        let mut config = VmConfig::default();
+        assert_eq!(
+            preflight(&config, "tart").unwrap(),
+            DevicePassthrough::default()
+        );
+
+        config.devices = Some(vm_config::config::DevicesConfig {
+            audio: true,
+            ..Default::default()
+        });
+        assert!(preflight(&config, "tart").unwrap().audio);
+
+        config.devices = Some(vm_config::config::DevicesConfig {
+            webcam: true,
+            ..Default::default()
+        });
+        let error = preflight(&config, "tart").unwrap_err().to_string();
+        assert!(error.contains("Webcam passthrough is not available with the tart provider"));
+    }
+}
@@ -13,7 +13,7 @@ use vm_core::error::{Result, VmError};
 use vm_core::{vm_dbg, vm_info};
 
 // Internal imports
-use super::{DockerOps, UserConfig};
+use super::{generated, DockerOps, UserConfig};
 use crate::resources;
 use crate::BoxConfig;
 use vm_config::config::VmConfig;
@@ -238,47 +238,10 @@ impl<'a> BuildOperations<'a> {
 
                 vm_println!("Loading base image from snapshot '@{}'...", name);
 
-                let manager = SnapshotManager::new()?;
-                let snapshot_dir = manager.get_snapshot_dir(SnapshotScope::Global, name);
-
-                if !snapshot_dir.exists() {
-                    return Err(VmError::Config(format!(
-                        "Snapshot '@{}' not found. Create or import it first:\n  vm snapshot create @{} --from-dockerfile <dockerfile>\n  vm snapshot import <file>",
-                        name, name
-                    )));
-                }
-
-                // Load metadata to get image tag
-                let metadata_path = snapshot_dir.join("metadata.json");
-                if !metadata_path.exists() {
-                    return Err(VmError::Config(format!(
-                        "Snapshot '@{}' is corrupted (metadata.json not found)",
-                        name
-                    )));
-                }
-
-                let metadata_content = std::fs::read_to_string(&metadata_path).map_err(|e| {
-                    VmError::Internal(format!("Failed to read metadata file: {}", e))
-                })?;
-
-                let metadata: serde_json::Value =
-                    serde_json::from_str(&metadata_content).map_err(|e| {
-                        VmError::Internal(format!("Failed to parse metadata.json: {}", e))
-                    })?;
-
-                // Get the image tag from first service (base image snapshot always has one service)
-                let image_tag = metadata
-                    .get("services")
-                    .and_then(|s| s.as_array())
-                    .and_then(|arr| arr.first())
-                    .and_then(|svc| svc.get("image_tag"))
-                    .and_then(|tag| tag.as_str())
-                    .ok_or_else(|| {
-                        VmError::Config(format!(
-                            "Snapshot '@{}' is corrupted (image_tag not found in metadata)",
-                            name
-                        ))
-                    })?;
+                let image_tag = Self::snapshot_image_tag(name)?;
+                let image_tag = image_tag.as_str();
+                let snapshot_dir =
+                    SnapshotManager::new()?.get_snapshot_dir(SnapshotScope::Global, name);
 
                 // Check if image is already loaded
                 let image_exists = match Command::new(self.executable)
@@ -341,6 +304,50 @@ impl<'a> BuildOperations<'a> {
         Ok((build_context, base_image, is_snapshot))
     }
 
+    /// Image tag recorded in a global snapshot's metadata
+    fn snapshot_image_tag(name: &str) -> Result<String> {
+        let manager = SnapshotManager::new()?;
+        let snapshot_dir = manager.get_snapshot_dir(SnapshotScope::Global, name);
+
+        if !snapshot_dir.exists() {
+            return Err(VmError::Config(format!(
+                "Snapshot '@{}' not found. Create or import it first:\n  vm snapshot create @{} --from-dockerfile <dockerfile>\n  vm snapshot import <file>",
+                name, name
+            )));
+        }
+
+        // Load metadata to get image tag
+        let metadata_path = snapshot_dir.join("metadata.json");
+        if !metadata_path.exists() {
+            return Err(VmError::Config(format!(
+                "Snapshot '@{}' is corrupted (metadata.json not found)",
+                name
+            )));
+        }
+
+        let metadata_content = std::fs::read_to_string(&metadata_path)
+            .map_err(|e| VmError::Internal(format!("Failed to read metadata file: {}", e)))?;
+
+        let metadata: serde_json::Value = serde_json::from_str(&metadata_content)
+            .map_err(|e| VmError::Internal(format!("Failed to parse metadata.json: {}", e)))?;
+
+        // Get the image tag from first service (base image snapshot always has one service)
+        let image_tag = metadata
+            .get("services")
+            .and_then(|s| s.as_array())
+            .and_then(|arr| arr.first())
+            .and_then(|svc| svc.get("image_tag"))
+            .and_then(|tag| tag.as_str())
+            .ok_or_else(|| {
+                VmError::Config(format!(
+                    "Snapshot '@{}' is corrupted (image_tag not found in metadata)",
+                    name
+                ))
+            })?;
+
+        Ok(image_tag.to_string())
+    }
+
     pub fn build_context_dir(&self) -> PathBuf {
         self.temp_dir.join("build_context")
     }
@@ -380,7 +387,7 @@ Thumbs.db
 *.bak
 .cache
 "#;
-        Self::write_if_changed(&build_context.join(".dockerignore"), dockerignore_content)?;
+        generated::write_if_changed(&build_context.join(".dockerignore"), dockerignore_content)?;
 
         // Create shared directory and copy embedded resources
         let shared_dir = build_context.join("shared");
@@ -390,27 +397,59 @@ Thumbs.db
         resources::copy_embedded_resources(&shared_dir)?;
 
         // Generate Dockerfile from template
-        // For custom Dockerfiles, generate a minimal wrapper that uses the pre-built image
         let dockerfile_path = build_context.join("Dockerfile.generated");
-        if matches!(self.get_box_config()?, BoxConfig::Dockerfile { .. }) {
-            // Custom Dockerfile case: Generate minimal Dockerfile that uses the pre-built image
-            self.generate_dockerfile_from_image(&dockerfile_path, &self.get_custom_image_name())?;
-        } else {
-            // Standard case: Generate full Dockerfile from template
-            self.generate_dockerfile(&dockerfile_path)?;
-        }
+        generated::write_if_changed(&dockerfile_path, &self.render_generated_dockerfile()?)?;
 
         // Copy vm-worktree.sh script to build context
         // The Dockerfile will COPY this into the container
         let worktree_script = include_str!("vm-worktree.sh");
         let worktree_script_path = build_context.join("vm-worktree.sh");
-        Self::write_if_changed(&worktree_script_path, worktree_script)?;
+        generated::write_if_changed(&worktree_script_path, worktree_script)?;
+
+        // Copy vm-open.sh, which forwards browser launches to the host
+        let open_script_path = build_context.join("vm-open.sh");
+        generated::write_if_changed(&open_script_path, include_str!("vm-open.sh"))?;
+
+        // Copy vm-clip.sh, which shares the clipboard with the host
+        let clip_script_path = build_context.join("vm-clip.sh");
+        generated::write_if_changed(&clip_script_path, include_str!("vm-clip.sh"))?;
 
         Ok(build_context)
     }
 
+    /// Render the Dockerfile placed in the build context.
+    ///
+    /// For custom Dockerfiles this is a minimal wrapper that uses the pre-built image.
+    pub fn render_generated_dockerfile(&self) -> Result<String> {
+        if matches!(self.get_box_config()?, BoxConfig::Dockerfile { .. }) {
+            Ok(self.render_dockerfile_from_image(&self.get_custom_image_name()))
+        } else {
+            self.render_dockerfile()
+        }
+    }
+
+    /// Base image name `vm create` builds on, without pulling, building or loading it
+    pub fn planned_base_image(&self) -> Result<String> {
+        match self.get_box_config()? {
+            BoxConfig::DockerImage(image) => Ok(image),
+            BoxConfig::Dockerfile { .. } => Ok(self.get_custom_image_name()),
+            BoxConfig::Snapshot(name) => Self::snapshot_image_tag(&name),
+            _ => Err(VmError::Internal(
+                "Invalid box configuration for Docker provider".to_string(),
+            )),
+        }
+    }
+
     /// Generate Dockerfile from template with build args
     pub fn generate_dockerfile(&self, output_path: &Path) -> Result<()> {
+        let content = self.render_dockerfile()?;
+        generated::write_if_changed(output_path, &content)?;
+
+        Ok(())
+    }
+
+    /// Render the Dockerfile template
+    fn render_dockerfile(&self) -> Result<String> {
         // Use shared template engine instead of creating new instance
         let tera = super::get_dockerfile_tera();
 
@@ -433,12 +472,26 @@ Thumbs.db
         let worktrees_base_dir = format!("{}/.vm/worktrees/{}", home, project_name);
         context.insert("worktrees_base_dir", &worktrees_base_dir);
 
-        let content = tera
-            .render("Dockerfile", &context)
-            .map_err(|e| VmError::Internal(format!("Failed to render Dockerfile template: {e}")))?;
-        Self::write_if_changed(output_path, &content)?;
+        // Natively provisioned boxes never run the playbook, so skip installing Ansible,
+        // and only install Python when the project asks for Python packages or a version,
+        // or has devcontainer features, whose installer is a Python script
+        let native_provisioner = self
+            .config
+            .vm
+            .as_ref()
+            .is_some_and(|vm| vm.uses_native_provisioner());
+        let needs_python = !self.config.pip_packages.is_empty()
+            || self
+                .config
+                .versions
+                .as_ref()
+                .is_some_and(|versions| versions.python.is_some())
+            || !self.config.features.is_empty();
+        context.insert("install_ansible", &!native_provisioner);
+        context.insert("install_python", &(!native_provisioner || needs_python));
 
-        Ok(())
+        tera.render("Dockerfile", &context)
+            .map_err(|e| VmError::Internal(format!("Failed to render Dockerfile template: {e}")))
     }
 
     /// Generate minimal Dockerfile that uses a pre-built custom image as base
@@ -452,9 +505,15 @@ Thumbs.db
         output_path: &Path,
         base_image: &str,
     ) -> Result<()> {
+        let content = self.render_dockerfile_from_image(base_image);
+        generated::write_if_changed(output_path, &content)?;
+        Ok(())
+    }
+
+    fn render_dockerfile_from_image(&self, base_image: &str) -> String {
         let user_config = self.get_user_config();
 
-        let content = format!(
+        format!(
             r#"# Generated Dockerfile wrapper for custom base image
 FROM {base_image}
 
@@ -471,6 +530,14 @@ COPY shared/ /app/shared/
 # Copy git worktree helper script with executable permissions
 COPY --chmod=755 vm-worktree.sh /usr/local/bin/vm-worktree
 
+# Forward xdg-open/open/$BROWSER to the host browser
+COPY --chmod=755 vm-open.sh /usr/local/bin/vm-open
+RUN for name in xdg-open open sensible-browser www-browser; do ln -sf vm-open /usr/local/bin/$name; done
+
+# Share the clipboard with the host (host_sync.clipboard)
+COPY --chmod=755 vm-clip.sh /usr/local/bin/vm-clip
+RUN ln -sf vm-clip /usr/local/bin/pbcopy && ln -sf vm-clip /usr/local/bin/pbpaste
+
 # Switch back to the project user (if the base image set one)
 USER ${user}
 
@@ -484,10 +551,7 @@ CMD ["tail", "-f", "/dev/null"]
             uid = user_config.uid,
             gid = user_config.gid,
             user = user_config.username,
-        );
-
-        Self::write_if_changed(output_path, &content)?;
-        Ok(())
+        )
     }
 
     /// Gather all package lists and format as build arguments
@@ -561,6 +625,18 @@ CMD ["tail", "-f", "/dev/null"]
             args.push(format!("--build-arg=TZ={}", timezone));
         }
 
+        // Generate the host locale in the image so propagated LANG/LC_ALL values resolve
+        if self
+            .config
+            .host_sync
+            .as_ref()
+            .is_some_and(|host_sync| host_sync.locale)
+        {
+            if let Some(locale) = vm_config::detector::os::detect_locale() {
+                args.push(format!("--build-arg=HOST_LOCALE={}", locale));
+            }
+        }
+
         // Apply host Git identity at runtime for snapshot-based creates to preserve cache reuse.
         if !is_snapshot {
             if let Some(git_config) = &self.config.git_config {
@@ -667,11 +743,4 @@ CMD ["tail", "-f", "/dev/null"]
     fn get_user_config(&self) -> UserConfig {
         UserConfig::from_vm_config(self.config)
     }
-
-    fn write_if_changed(path: &Path, content: &str) -> Result<()> {
-        match fs::read(path) {
-            Ok(existing) if existing == content.as_bytes() => Ok(()),
-            _ => fs::write(path, content).map_err(Into::into),
-        }
-    }
 }
@@ -1,6 +1,6 @@
 use super::*;
 use std::fs;
-use vm_config::config::{BoxSpec, VmConfig, VmSettings};
+use vm_config::config::{BoxSpec, FeatureSpec, VmConfig, VmSettings};
 use vm_config::detector::git::GitConfig;
 
 #[test]
@@ -140,3 +140,86 @@ fn test_derived_image_tag_snapshot_ignores_host_identity_inputs() {
 
     assert_eq!(tag_a, tag_b);
 }
+
+#[test]
+fn test_generate_dockerfile_omits_ansible_for_native_provisioner() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let temp_path = temp_dir.path().to_path_buf();
+    let dockerfile = temp_dir.path().join("Dockerfile.generated");
+
+    let ansible_config = VmConfig::default();
+    BuildOperations::new(&ansible_config, &temp_path, "docker")
+        .generate_dockerfile(&dockerfile)
+        .unwrap();
+    assert!(fs::read_to_string(&dockerfile)
+        .unwrap()
+        .contains("        ansible \\\n"));
+
+    let native_config = VmConfig {
+        vm: Some(VmSettings {
+            provisioner: Some("native".to_string()),
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    BuildOperations::new(&native_config, &temp_path, "docker")
+        .generate_dockerfile(&dockerfile)
+        .unwrap();
+    let content = fs::read_to_string(&dockerfile).unwrap();
+    assert!(!content.contains("        ansible \\\n"));
+    assert!(!content.contains("python3-venv"));
+
+    let native_python_config = VmConfig {
+        pip_packages: vec!["black".to_string()],
+        ..native_config
+    };
+    BuildOperations::new(&native_python_config, &temp_path, "docker")
+        .generate_dockerfile(&dockerfile)
+        .unwrap();
+    assert!(fs::read_to_string(&dockerfile)
+        .unwrap()
+        .contains("python3-venv"));
+}
+
+#[test]
+fn test_generate_dockerfile_keeps_python_for_native_features() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let temp_path = temp_dir.path().to_path_buf();
+    let dockerfile = temp_dir.path().join("Dockerfile.generated");
+
+    let config = VmConfig {
+        vm: Some(VmSettings {
+            provisioner: Some("native".to_string()),
+            ..Default::default()
+        }),
+        features: vec![FeatureSpec::Id(
+            "ghcr.io/devcontainers/features/node:1".to_string(),
+        )],
+        ..Default::default()
+    };
+    BuildOperations::new(&config, &temp_path, "docker")
+        .generate_dockerfile(&dockerfile)
+        .unwrap();
+    let content = fs::read_to_string(&dockerfile).unwrap();
+    assert!(content.contains("python3-venv"));
+    assert!(!content.contains("        ansible \\\n"));
+}
+
+#[test]
+fn test_planned_base_image_does_not_touch_build_context() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let temp_path = temp_dir.path().to_path_buf();
+    let config = VmConfig {
+        vm: Some(VmSettings {
+            r#box: Some(BoxSpec::String("debian:bookworm".to_string())),
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    let build_ops = BuildOperations::new(&config, &temp_path, "docker");
+
+    assert_eq!(build_ops.planned_base_image().unwrap(), "debian:bookworm");
+    let dockerfile = build_ops.render_generated_dockerfile().unwrap();
+    assert!(dockerfile.contains("FROM"));
+    assert!(!build_ops.build_context_dir().exists());
+}
@@ -0,0 +1,230 @@
+//! BuildKit builder selection for image builds
+//!
+//! When `build.remote_builder` is set in the global config, `vm create` hands the
+//! image build to a `docker buildx` builder backed by a remote BuildKit daemon.
+//! Build output is still streamed to the local terminal and the finished image
+//! is loaded back into the local Docker daemon.
+//!
+//! Otherwise, when the Docker registry service is running, builds go through a
+//! local `docker-container` builder that imports and exports layer caches from
+//! the registry, so everyone pointed at the same registry shares build caches.
+
+use std::net::{SocketAddr, TcpStream};
+use std::process::Command;
+use std::time::Duration;
+
+use vm_config::global_config::RemoteBuilderSettings;
+use vm_config::GlobalConfig;
+use vm_core::error::{Result, VmError};
+use vm_core::{vm_dbg, vm_info};
+
+pub struct RemoteBuilder<'a> {
+    settings: &'a RemoteBuilderSettings,
+    executable: &'a str,
+}
+
+impl<'a> RemoteBuilder<'a> {
+    pub fn new(settings: &'a RemoteBuilderSettings, executable: &'a str) -> Self {
+        Self {
+            settings,
+            executable,
+        }
+    }
+
+    /// Make sure the configured builder exists and its BuildKit daemon is reachable
+    pub fn ensure_ready(&self) -> Result<()> {
+        if !self.builder_exists() {
+            let Some(endpoint) = &self.settings.endpoint else {
+                return Err(VmError::Config(format!(
+                    "Remote builder '{}' is not configured in docker buildx.\n\n\
+                     Fixes:\n\
+                       • Set build.remote_builder.endpoint in ~/.vm/config.yaml\n\
+                       • Or create it manually: docker buildx create --name {} --driver remote tcp://<host>:<port>",
+                    self.settings.name, self.settings.name
+                )));
+            };
+
+            vm_info!(
+                "Creating remote builder '{}' for {}...",
+                self.settings.name,
+                endpoint
+            );
+            let output = Command::new(self.executable)
+                .args(self.create_args())
+                .output()?;
+            if !output.status.success() {
+                return Err(VmError::Internal(format!(
+                    "Failed to create remote builder '{}': {}",
+                    self.settings.name,
+                    String::from_utf8_lossy(&output.stderr).trim()
+                )));
+            }
+        }
+
+        // Bootstrapping connects to the daemon, so an unreachable endpoint fails here
+        // instead of halfway through the build.
+        let output = Command::new(self.executable)
+            .args(["buildx", "inspect", "--bootstrap", &self.settings.name])
+            .output()?;
+        if !output.status.success() {
+            return Err(VmError::Internal(format!(
+                "Remote builder '{}' is not reachable: {}",
+                self.settings.name,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+
+        vm_dbg!("Using remote builder '{}'", &self.settings.name);
+        Ok(())
+    }
+
+    /// Extra `docker compose build` flags that route the build to this builder
+    pub fn compose_build_flags(&self) -> Vec<String> {
+        vec!["--builder".to_string(), self.settings.name.clone()]
+    }
+
+    fn builder_exists(&self) -> bool {
+        Command::new(self.executable)
+            .args(["buildx", "inspect", &self.settings.name])
+            .output()
+            .map(|output| output.status.success())
+            .unwrap_or(false)
+    }
+
+    pub(super) fn create_args(&self) -> Vec<String> {
+        let mut args = vec![
+            "buildx".to_string(),
+            "create".to_string(),
+            "--name".to_string(),
+            self.settings.name.clone(),
+            "--driver".to_string(),
+            "remote".to_string(),
+        ];
+        for (key, value) in &self.settings.driver_opts {
+            args.push("--driver-opt".to_string());
+            args.push(format!("{key}={value}"));
+        }
+        if let Some(endpoint) = &self.settings.endpoint {
+            args.push(endpoint.clone());
+        }
+        args
+    }
+}
+
+/// Local builder used to export layer caches (the default `docker` driver can't)
+pub const CACHE_BUILDER_NAME: &str = "vm-cache";
+
+/// Registry reference for a project's shared layer cache, when the registry
+/// service is enabled and accepting connections
+pub fn registry_cache_ref(global_config: &GlobalConfig, project_name: &str) -> Option<String> {
+    let registry = &global_config.services.docker_registry;
+    if !registry.enabled {
+        return None;
+    }
+
+    let addr = SocketAddr::from(([127, 0, 0, 1], registry.port));
+    TcpStream::connect_timeout(&addr, Duration::from_millis(200)).ok()?;
+
+    Some(cache_ref(registry.port, project_name))
+}
+
+fn cache_ref(port: u16, project_name: &str) -> String {
+    let repository: String = project_name
+        .to_lowercase()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-') {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    format!(
+        "localhost:{port}/{}/{repository}:buildcache",
+        vm_docker_registry::BUILD_CACHE_NAMESPACE
+    )
+}
+
+/// Create the local cache builder if needed.
+///
+/// It runs on the host network so BuildKit can reach the registry on localhost.
+pub fn ensure_cache_builder(executable: &str) -> Result<()> {
+    let exists = Command::new(executable)
+        .args(["buildx", "inspect", CACHE_BUILDER_NAME])
+        .output()
+        .map(|output| output.status.success())
+        .unwrap_or(false);
+    if exists {
+        return Ok(());
+    }
+
+    vm_dbg!("Creating build cache builder '{}'", CACHE_BUILDER_NAME);
+    let output = Command::new(executable)
+        .args([
+            "buildx",
+            "create",
+            "--name",
+            CACHE_BUILDER_NAME,
+            "--driver",
+            "docker-container",
+            "--driver-opt",
+            "network=host",
+        ])
+        .output()?;
+    if !output.status.success() {
+        return Err(VmError::Internal(format!(
+            "Failed to create builder '{}': {}",
+            CACHE_BUILDER_NAME,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_create_args_include_driver_opts_and_endpoint() {
+        let mut settings = RemoteBuilderSettings {
+            name: "shared".to_string(),
+            endpoint: Some("tcp://buildkitd.internal:1234".to_string()),
+            driver_opts: Default::default(),
+        };
+        settings
+            .driver_opts
+            .insert("cacert".to_string(), "/certs/ca.pem".to_string());
+
+        let builder = RemoteBuilder::new(&settings, "docker");
+        assert_eq!(
+            builder.create_args(),
+            vec![
+                "buildx",
+                "create",
+                "--name",
+                "shared",
+                "--driver",
+                "remote",
+                "--driver-opt",
+                "cacert=/certs/ca.pem",
+                "tcp://buildkitd.internal:1234",
+            ]
+        );
+        assert_eq!(builder.compose_build_flags(), vec!["--builder", "shared"]);
+    }
+
+    #[test]
+    fn test_cache_ref_sanitizes_project_name() {
+        assert_eq!(
+            cache_ref(5000, "My App"),
+            "localhost:5000/vm-cache/my-app:buildcache"
+        );
+    }
+
+    #[test]
+    fn test_registry_cache_ref_requires_enabled_registry() {
+        assert_eq!(registry_cache_ref(&GlobalConfig::default(), "app"), None);
+    }
+}
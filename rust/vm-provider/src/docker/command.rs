@@ -350,13 +350,18 @@ impl DockerOps {
         Ok(output.lines().any(|line| line.trim() == network_name))
     }
 
-    /// Create a Docker network with the specified name.
-    pub fn create_network(executable: Option<&str>, network_name: &str) -> Result<()> {
+    /// Create a Docker network with the specified name and labels.
+    pub fn create_network(
+        executable: Option<&str>,
+        network_name: &str,
+        labels: &[String],
+    ) -> Result<()> {
         vm_dbg!("Creating Docker network: {}", network_name);
 
         DockerCommand::new(executable)
             .subcommand("network")
             .arg("create")
+            .args(labels)
             .arg(network_name)
             .execute()
             .map_err(|e| {
@@ -367,12 +372,17 @@ impl DockerOps {
             })
     }
 
-    /// Ensure all specified networks exist, creating them if necessary.
-    pub fn ensure_networks_exist(executable: Option<&str>, networks: &[String]) -> Result<()> {
+    /// Ensure all specified networks exist, creating missing ones with `labels`
+    /// (`--label key=value` arguments).
+    pub fn ensure_networks_exist(
+        executable: Option<&str>,
+        networks: &[String],
+        labels: &[String],
+    ) -> Result<()> {
         for network in networks {
             if !Self::network_exists(executable, network)? {
                 vm_dbg!("Network '{}' does not exist, creating it...", network);
-                Self::create_network(executable, network)?;
+                Self::create_network(executable, network, labels)?;
             } else {
                 vm_dbg!("Network '{}' already exists", network);
             }
@@ -5,19 +5,27 @@ use std::path::{Path, PathBuf};
 use std::process::Command;
 
 // External crates
+use indexmap::IndexMap;
 use tera::Context as TeraContext;
 use vm_core::error::{Result, VmError};
 
 // Internal imports
 use super::build::BuildOperations;
+use super::generated;
 use super::host_packages::{
     detect_packages, get_package_env_vars, get_volume_mounts, PackageManager,
 };
 use super::{ComposeCommand, DockerOps, UserConfig};
+use crate::common::host_paths::{host_mount_path, MountPathStyle};
+use crate::common::instance_env::{apply_env_overrides, load_instance_env};
 use crate::user_home::resolve_home_dir;
 use crate::ProviderContext;
 use crate::TempVmState;
-use vm_config::{config::VmConfig, detect_worktrees};
+use vm_config::detector::os::{detect_host_entries, detect_keyboard_layout, detect_locale};
+use vm_config::{
+    config::{is_host_sysctl, ServiceConfig, VmConfig},
+    detect_worktrees,
+};
 use vm_core::command_stream::{stream_command, stream_command_visible};
 
 pub struct ComposeOperations<'a> {
@@ -68,7 +76,7 @@ fn get_ssh_config_path() -> Option<String> {
 }
 
 /// Configure SSH agent forwarding in tera context if enabled
-fn configure_ssh_agent(config: &VmConfig, tera_context: &mut TeraContext) {
+fn configure_ssh_agent(config: &VmConfig, style: MountPathStyle, tera_context: &mut TeraContext) {
     let ssh_agent_enabled = config
         .host_sync
         .as_ref()
@@ -94,9 +102,162 @@ fn configure_ssh_agent(config: &VmConfig, tera_context: &mut TeraContext) {
 
     if ssh_config_enabled {
         if let Some(ssh_config_path) = get_ssh_config_path() {
-            tera_context.insert("ssh_config_path", &ssh_config_path);
+            tera_context.insert("ssh_config_path", &host_mount_path(&ssh_config_path, style));
+        }
+    }
+}
+
+/// Configure host locale and keyboard layout propagation in tera context
+fn configure_host_environment(config: &VmConfig, tera_context: &mut TeraContext) {
+    let Some(host_sync) = config.host_sync.as_ref() else {
+        return;
+    };
+    let mut env_vars: Vec<(String, String)> = Vec::new();
+
+    if host_sync.locale {
+        if let Some(locale) = detect_locale() {
+            env_vars.push(("LANG".to_string(), locale.clone()));
+            env_vars.push(("LC_ALL".to_string(), locale.clone()));
+            // LANGUAGE takes precedence for message catalogs; keep it consistent
+            let language = locale.split('.').next().unwrap_or(&locale).to_string();
+            env_vars.push(("LANGUAGE".to_string(), language));
+        }
+    }
+
+    if host_sync.keyboard {
+        if let Some(keyboard) = detect_keyboard_layout() {
+            env_vars.push(("XKB_DEFAULT_LAYOUT".to_string(), keyboard.layout));
+            for (name, value) in [
+                ("XKB_DEFAULT_VARIANT", keyboard.variant),
+                ("XKB_DEFAULT_MODEL", keyboard.model),
+                ("XKB_DEFAULT_OPTIONS", keyboard.options),
+            ] {
+                if let Some(value) = value {
+                    env_vars.push((name.to_string(), value));
+                }
+            }
         }
     }
+
+    // Explicit vm.yaml environment always wins over detected host values
+    env_vars.retain(|(name, _)| !config.environment.contains_key(name));
+    if !env_vars.is_empty() {
+        tera_context.insert("host_sync_env_vars", &env_vars);
+    }
+}
+
+/// Collect /etc/hosts entries from `networking.extra_hosts` and host sync into tera context
+fn configure_extra_hosts(config: &VmConfig, tera_context: &mut TeraContext) {
+    let mut extra_hosts: Vec<String> = config
+        .networking
+        .as_ref()
+        .map(|networking| networking.extra_hosts.clone())
+        .unwrap_or_default();
+    if let Some(host_sync) = config.host_sync.as_ref() {
+        if host_sync.etc_hosts {
+            extra_hosts.extend(
+                detect_host_entries()
+                    .into_iter()
+                    .map(|(hostname, ip)| format!("{hostname}:{ip}")),
+            );
+        }
+        extra_hosts.extend(host_sync.extra_hosts.iter().cloned());
+    }
+    if !extra_hosts.is_empty() {
+        tera_context.insert("extra_hosts", &extra_hosts);
+    }
+}
+
+/// Port the `mock` service listens on inside its container
+const MOCK_CONTAINER_PORT: u16 = 4010;
+
+/// The `mock` service container, generated from an OpenAPI spec or WireMock fixtures
+#[derive(Debug, serde::Serialize)]
+struct MockService {
+    image: String,
+    command: Vec<String>,
+    /// Host directory mounted read-only into the container
+    source: String,
+    target: &'static str,
+    port: Option<u16>,
+    container_port: u16,
+}
+
+impl MockService {
+    fn from_config(
+        service: &ServiceConfig,
+        project_dir: &Path,
+        style: MountPathStyle,
+    ) -> Result<Self> {
+        let resolve = |path: &Path| {
+            let resolved = project_dir.join(path);
+            if resolved.exists() {
+                Ok(resolved)
+            } else {
+                Err(VmError::Config(format!(
+                    "Mock service source not found: {}",
+                    resolved.display()
+                )))
+            }
+        };
+        let port = MOCK_CONTAINER_PORT.to_string();
+
+        let (image, command, source, target) = match (&service.spec, &service.fixtures) {
+            (Some(spec), None) => {
+                // Mount the spec's directory so relative `$ref`s to sibling files resolve
+                let spec = resolve(spec)?;
+                let file_name = spec
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+                let dir = spec.parent().unwrap_or(project_dir).to_path_buf();
+                (
+                    format!(
+                        "stoplight/prism:{}",
+                        service.version.as_deref().unwrap_or("5")
+                    ),
+                    vec![
+                        "mock".to_string(),
+                        "-h".to_string(),
+                        "0.0.0.0".to_string(),
+                        "-p".to_string(),
+                        port,
+                        format!("/mock/{file_name}"),
+                    ],
+                    dir,
+                    "/mock",
+                )
+            }
+            (None, Some(fixtures)) => (
+                format!(
+                    "wiremock/wiremock:{}",
+                    service.version.as_deref().unwrap_or("3.9.1")
+                ),
+                vec![
+                    "--port".to_string(),
+                    port,
+                    "--enable-stub-cors".to_string(),
+                    "--disable-banner".to_string(),
+                ],
+                resolve(fixtures)?,
+                "/home/wiremock",
+            ),
+            _ => {
+                return Err(VmError::Config(
+                    "Service 'mock' needs exactly one of 'spec' or 'fixtures'".to_string(),
+                ))
+            }
+        };
+
+        Ok(Self {
+            image,
+            command,
+            source: host_mount_path(&source.to_string_lossy(), style).into_owned(),
+            target,
+            port: service.port,
+            container_port: MOCK_CONTAINER_PORT,
+        })
+    }
 }
 
 /// Expand tilde (~) in path to home directory (zero-copy for paths without tilde)
@@ -112,6 +273,80 @@ fn expand_tilde(path: &str) -> Option<Cow<'_, str>> {
     }
 }
 
+/// Where the extra CA bundle is mounted in VMs, so `update-ca-certificates` picks it up
+const VM_EXTRA_CA_PATH: &str = "/usr/local/share/ca-certificates/vm-extra-ca.crt";
+
+/// System CA bundle in Debian-based VMs, which includes the extra CA once provisioned
+const VM_SYSTEM_CA_BUNDLE: &str = "/etc/ssl/certs/ca-certificates.crt";
+
+/// Environment that carries the host's proxy settings into a VM.
+///
+/// Proxies listening on the host's loopback are rewritten to the host gateway,
+/// and the gateway itself bypasses the proxy so vm's own services stay reachable.
+fn vm_proxy_env(
+    proxy_env: &[(String, String)],
+    host_gateway: &str,
+    has_extra_ca: bool,
+) -> Vec<(String, String)> {
+    let mut vars: Vec<(String, String)> = proxy_env
+        .iter()
+        .map(|(name, value)| {
+            let value = if name.eq_ignore_ascii_case("NO_PROXY") {
+                format!("{value},{host_gateway}")
+            } else {
+                value
+                    .replacen("://localhost", &format!("://{host_gateway}"), 1)
+                    .replacen("://127.0.0.1", &format!("://{host_gateway}"), 1)
+            };
+            (name.clone(), value)
+        })
+        .collect();
+
+    let lookup = |name: &str| {
+        vars.iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.clone())
+    };
+    let http = lookup("HTTP_PROXY");
+    let https = lookup("HTTPS_PROXY");
+    let mut tool_vars = Vec::new();
+    if let Some(http) = &http {
+        tool_vars.push(("npm_config_proxy".to_string(), http.clone()));
+    }
+    if let Some(https) = &https {
+        tool_vars.push(("npm_config_https_proxy".to_string(), https.clone()));
+    }
+    if let Some(proxy) = https.or(http) {
+        tool_vars.push(("PIP_PROXY".to_string(), proxy));
+    }
+    if has_extra_ca {
+        tool_vars.extend([
+            (
+                "NODE_EXTRA_CA_CERTS".to_string(),
+                VM_EXTRA_CA_PATH.to_string(),
+            ),
+            ("PIP_CERT".to_string(), VM_SYSTEM_CA_BUNDLE.to_string()),
+            (
+                "REQUESTS_CA_BUNDLE".to_string(),
+                VM_SYSTEM_CA_BUNDLE.to_string(),
+            ),
+        ]);
+    }
+    vars.extend(tool_vars);
+    vars
+}
+
+/// Rewrite the host side of `(host_path, container_path)` mounts for the provider
+fn translate_host_mounts(
+    mounts: &[(String, String)],
+    style: MountPathStyle,
+) -> Vec<(String, String)> {
+    mounts
+        .iter()
+        .map(|(host, container)| (host_mount_path(host, style).into_owned(), container.clone()))
+        .collect()
+}
+
 /// Process dotfiles configuration and return validated paths
 /// Returns Vec of (host_path, container_path) tuples
 fn process_dotfiles(config: &VmConfig, username: &str) -> Vec<(String, String)> {
@@ -156,6 +391,33 @@ fn process_dotfiles(config: &VmConfig, username: &str) -> Vec<(String, String)>
         .collect()
 }
 
+/// `key=value` sysctls for the dev container: raised inotify limits for hot reload
+/// (webpack, vite, nodemon, etc.), then `vm.sysctls` without the host-wide keys
+fn container_sysctls(config: &VmConfig) -> Vec<String> {
+    let mut sysctls: IndexMap<String, String> = IndexMap::new();
+    if !cfg!(target_os = "macos") {
+        sysctls.insert(
+            "fs.inotify.max_user_watches".to_string(),
+            "524288".to_string(),
+        );
+        sysctls.insert(
+            "fs.inotify.max_user_instances".to_string(),
+            "256".to_string(),
+        );
+    }
+    if let Some(vm) = &config.vm {
+        for (key, value) in &vm.sysctls {
+            if !is_host_sysctl(key) {
+                sysctls.insert(key.clone(), value.to_string());
+            }
+        }
+    }
+    sysctls
+        .into_iter()
+        .map(|(key, value)| format!("{key}={value}"))
+        .collect()
+}
+
 impl<'a> ComposeOperations<'a> {
     pub fn new(
         config: &'a VmConfig,
@@ -232,16 +494,16 @@ impl<'a> ComposeOperations<'a> {
     fn build_host_package_context(&self, context: &ProviderContext) -> Result<HostPackageContext> {
         // Detect host package locations for mounting (only if package linking is enabled)
         let mut host_info = super::host_packages::HostPackageInfo::new();
-
-        // Check pip packages only if pip linking is enabled
-        if self
+        // Ephemeral VMs get nothing from the host besides the read-only workspace
+        let package_links = self
             .config
             .host_sync
             .as_ref()
             .and_then(|hs| hs.package_links.as_ref())
-            .is_some_and(|p| p.pip)
-            && !self.config.pip_packages.is_empty()
-        {
+            .filter(|_| !context.ephemeral);
+
+        // Check pip packages only if pip linking is enabled
+        if package_links.is_some_and(|p| p.pip) && !self.config.pip_packages.is_empty() {
             let pip_info = detect_packages(&self.config.pip_packages, PackageManager::Pip);
             host_info.pip_site_packages = pip_info.pip_site_packages;
             host_info.pipx_base_dir = pip_info.pipx_base_dir;
@@ -253,14 +515,7 @@ impl<'a> ComposeOperations<'a> {
         }
 
         // Check npm packages only if npm linking is enabled
-        if self
-            .config
-            .host_sync
-            .as_ref()
-            .and_then(|hs| hs.package_links.as_ref())
-            .is_some_and(|p| p.npm)
-            && !self.config.npm_packages.is_empty()
-        {
+        if package_links.is_some_and(|p| p.npm) && !self.config.npm_packages.is_empty() {
             let npm_info = detect_packages(&self.config.npm_packages, PackageManager::Npm);
             host_info.npm_global_dir = npm_info.npm_global_dir;
             host_info.npm_local_dir = npm_info.npm_local_dir;
@@ -270,14 +525,7 @@ impl<'a> ComposeOperations<'a> {
         }
 
         // Check cargo packages only if cargo linking is enabled
-        if self
-            .config
-            .host_sync
-            .as_ref()
-            .and_then(|hs| hs.package_links.as_ref())
-            .is_some_and(|p| p.cargo)
-            && !self.config.cargo_packages.is_empty()
-        {
+        if package_links.is_some_and(|p| p.cargo) && !self.config.cargo_packages.is_empty() {
             let cargo_info = detect_packages(&self.config.cargo_packages, PackageManager::Cargo);
             host_info.cargo_registry = cargo_info.cargo_registry;
             host_info.cargo_bin = cargo_info.cargo_bin;
@@ -287,12 +535,27 @@ impl<'a> ComposeOperations<'a> {
         }
 
         // Get volume mounts and environment variables
-        let host_mounts = get_volume_mounts(&host_info)
+        let mut host_mounts: Vec<(String, String)> = get_volume_mounts(&host_info)
             .into_iter()
             .map(|(path, container_path)| (path.to_string_lossy().to_string(), container_path))
             .collect();
         let mut host_env_vars = get_package_env_vars(&host_info);
 
+        // Proxy and extra CA settings (exported from the global config's `proxy` section)
+        let extra_ca =
+            vm_core::network::ca_bundle_path().filter(|path| !context.ephemeral && path.is_file());
+        if let Some(ca_bundle) = &extra_ca {
+            host_mounts.push((
+                ca_bundle.to_string_lossy().to_string(),
+                VM_EXTRA_CA_PATH.to_string(),
+            ));
+        }
+        host_env_vars.extend(vm_proxy_env(
+            &vm_core::network::proxy_env(),
+            vm_platform::platform::get_host_gateway(),
+            extra_ca.is_some(),
+        ));
+
         // Add package registry environment variables from global config
         if let Some(global_cfg) = context.global_config.as_ref() {
             if global_cfg.services.package_registry.enabled {
@@ -453,6 +716,7 @@ impl<'a> ComposeOperations<'a> {
         let build_context_str = BuildOperations::path_to_string(build_context_dir)?;
 
         let user_config = UserConfig::from_vm_config(self.config);
+        let path_style = MountPathStyle::for_executable(self.executable);
 
         // Build host package context (consolidated package detection and env setup)
         let pkg_context = self.build_host_package_context(context)?;
@@ -465,15 +729,34 @@ impl<'a> ComposeOperations<'a> {
             .unwrap_or("vm-project");
 
         // Handle instance name modification if provided
-        let (final_config, final_project_name) = match instance_name {
+        let (mut final_config, final_project_name) = match instance_name {
             Some(instance) => self.create_instance_config(base_project_name, instance),
             None => (self.config.clone(), base_project_name.to_string()),
         };
 
+        // Instance override file first, then one-off `--env` values
+        let instance_env = load_instance_env(
+            base_project_name,
+            instance_name.unwrap_or(vm_core::labels::DEFAULT_INSTANCE),
+        )?;
+        apply_env_overrides(&mut final_config.environment, &instance_env);
+        apply_env_overrides(&mut final_config.environment, &context.env_overrides);
+
         let mut tera_context = TeraContext::new();
         tera_context.insert("config", &final_config);
         tera_context.insert("project_name", &final_project_name);
-        tera_context.insert("project_dir", &project_dir_str);
+        tera_context.insert(
+            "instance_name",
+            instance_name.unwrap_or(vm_core::labels::DEFAULT_INSTANCE),
+        );
+        tera_context.insert("config_hash", &self.config.config_hash());
+        let port_labels: Vec<_> = final_config
+            .port_labels()
+            .iter()
+            .map(|label| (label.key(), label.value()))
+            .collect();
+        tera_context.insert("port_labels", &port_labels);
+        tera_context.insert("project_dir", &host_mount_path(project_dir_str, path_style));
         tera_context.insert("build_context_dir", &build_context_str);
         tera_context.insert("project_uid", &user_config.uid.to_string());
         tera_context.insert("project_gid", &user_config.gid.to_string());
@@ -489,16 +772,28 @@ impl<'a> ComposeOperations<'a> {
                 .map(std::string::ToString::to_string)
                 .unwrap_or_else(|| format!("{final_project_name}:latest")),
         );
+        tera_context.insert("registry_cache_ref", &context.build_cache_ref);
         tera_context.insert("is_macos", &cfg!(target_os = "macos"));
-        tera_context.insert("host_mounts", &pkg_context.host_mounts);
+        tera_context.insert("sysctls", &container_sysctls(&final_config));
+        let devices = crate::devices::preflight(&final_config, self.executable)?;
+        tera_context.insert("audio_passthrough", &(devices.audio && !context.ephemeral));
+        tera_context.insert("device_nodes", &devices.nodes);
+        tera_context.insert("device_groups", &devices.groups);
+        tera_context.insert(
+            "host_mounts",
+            &translate_host_mounts(&pkg_context.host_mounts, path_style),
+        );
         tera_context.insert("host_env_vars", &pkg_context.host_env_vars);
 
-        // AI sync flags for template
-        if let Some(ai_sync) = &self
+        tera_context.insert("ephemeral", &context.ephemeral);
+
+        // AI sync flags for template (ephemeral VMs never write back to the host)
+        if let Some(ai_sync) = self
             .config
             .host_sync
             .as_ref()
             .and_then(|hs| hs.ai_tools.as_ref())
+            .filter(|_| !context.ephemeral)
         {
             tera_context.insert("claude_sync_enabled", &ai_sync.is_claude_enabled());
             tera_context.insert("gemini_sync_enabled", &ai_sync.is_gemini_enabled());
@@ -512,22 +807,40 @@ impl<'a> ComposeOperations<'a> {
         tera_context.insert("local_env_vars", &local_env_vars);
 
         // SSH agent forwarding
-        configure_ssh_agent(self.config, &mut tera_context);
+        if !context.ephemeral {
+            configure_ssh_agent(self.config, path_style, &mut tera_context);
+        }
+
+        // Host locale, keyboard layout and /etc/hosts entries
+        configure_host_environment(self.config, &mut tera_context);
+        configure_extra_hosts(self.config, &mut tera_context);
+
+        if let Some(mock) = final_config.services.get("mock").filter(|s| s.enabled) {
+            tera_context.insert(
+                "mock",
+                &MockService::from_config(mock, self.project_dir, path_style)?,
+            );
+        }
 
         // Dotfiles sync
         let dotfile_mounts = process_dotfiles(self.config, &user_config.username);
-        if !dotfile_mounts.is_empty() {
-            tera_context.insert("dotfile_mounts", &dotfile_mounts);
+        if !dotfile_mounts.is_empty() && !context.ephemeral {
+            tera_context.insert(
+                "dotfile_mounts",
+                &translate_host_mounts(&dotfile_mounts, path_style),
+            );
         }
 
         // Get home directory for template (needed for AI tools sync)
         let home_dir = resolve_home_dir()
             .map(|home| home.to_string_lossy().to_string())
             .unwrap_or_else(|| "/home/developer".to_string());
-        tera_context.insert("home_dir", &home_dir);
+        tera_context.insert("home_dir", &host_mount_path(&home_dir, path_style));
 
         // Git worktrees volume
-        self.configure_worktrees(&mut tera_context, &home_dir, &final_project_name);
+        if !context.ephemeral {
+            self.configure_worktrees(&mut tera_context, &home_dir, &final_project_name);
+        }
 
         // Get or generate passwords for database services
         // Note: Using sync version since we're in a non-async context
@@ -577,7 +890,7 @@ impl<'a> ComposeOperations<'a> {
         let content = self.render_docker_compose(build_context_dir, context)?;
 
         let path = self.temp_dir.join("docker-compose.yml");
-        write_if_changed(&path, &content)?;
+        generated::write_tracked(&path, &content)?;
 
         Ok(path)
     }
@@ -596,7 +909,7 @@ impl<'a> ComposeOperations<'a> {
             self.render_docker_compose_with_instance(build_context_dir, instance_name, context)?;
 
         let path = self.temp_dir.join("docker-compose.yml");
-        write_if_changed(&path, &content)?;
+        generated::write_tracked(&path, &content)?;
 
         Ok(path)
     }
@@ -623,7 +936,7 @@ impl<'a> ComposeOperations<'a> {
             self.render_docker_compose_internal(build_context_dir, None, context, Some(image_tag))?;
 
         let path = self.temp_dir.join("docker-compose.yml");
-        write_if_changed(&path, &content)?;
+        generated::write_tracked(&path, &content)?;
 
         Ok(path)
     }
@@ -645,7 +958,7 @@ impl<'a> ComposeOperations<'a> {
         )?;
 
         let path = self.temp_dir.join("docker-compose.yml");
-        write_if_changed(&path, &content)?;
+        generated::write_tracked(&path, &content)?;
 
         Ok(path)
     }
@@ -657,7 +970,20 @@ impl<'a> ComposeOperations<'a> {
         let mut context = TeraContext::new();
         context.insert("config", &self.config);
         context.insert("container_name", &state.container_name);
-        context.insert("mounts", &state.mounts);
+        let path_style = MountPathStyle::for_executable(self.executable);
+        let mounts: Vec<serde_json::Value> = state
+            .mounts
+            .iter()
+            .map(|mount| {
+                let source = mount.source.to_string_lossy();
+                serde_json::json!({
+                    "source": host_mount_path(&source, path_style),
+                    "target": mount.target,
+                    "permissions": mount.permissions,
+                })
+            })
+            .collect();
+        context.insert("mounts", &mounts);
 
         let content = tera.render("docker-compose.yml", &context).map_err(|e| {
             VmError::Internal(format!("Failed to render docker-compose template: {e}"))
@@ -682,8 +1008,8 @@ impl<'a> ComposeOperations<'a> {
         context: &ProviderContext,
     ) -> Result<()> {
         let compose_path = self.temp_dir.join("docker-compose.yml");
-        if !compose_path.exists() {
-            // Fallback: prepare build context and generate compose file
+        if !generated::is_intact(&compose_path) {
+            // Missing or damaged: prepare build context and regenerate the compose file
             let build_ops = BuildOperations::new(self.config, self.temp_dir, self.executable);
             let build_context = build_ops.prepare_compose_build_context()?;
             if let Some(instance_name) = self.instance_name_from_container(container_name) {
@@ -735,7 +1061,7 @@ impl<'a> ComposeOperations<'a> {
         })
     }
 
-    fn instance_name_from_container(&self, container_name: &str) -> Option<String> {
+    pub(super) fn instance_name_from_container(&self, container_name: &str) -> Option<String> {
         let project_name = self
             .config
             .project
@@ -843,22 +1169,28 @@ impl<'a> ComposeOperations<'a> {
     }
 }
 
-fn write_if_changed(path: &Path, content: &str) -> Result<()> {
-    match fs::read(path) {
-        Ok(existing) if existing == content.as_bytes() => Ok(()),
-        _ => fs::write(path, content).map_err(Into::into),
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::sync::OnceLock;
     use tempfile::TempDir;
     use vm_config::{
         config::{HostSyncConfig, ProjectConfig, VmConfig, WorktreesConfig},
         global_config::{GlobalConfig, WorktreesGlobalSettings},
     };
 
+    /// Point HOME at an empty directory for the whole test run, so rendering never
+    /// reads the user's `~/.vm` (instance env files, generated project files)
+    fn temp_home() -> &'static Path {
+        static HOME: OnceLock<TempDir> = OnceLock::new();
+        HOME.get_or_init(|| {
+            let home = TempDir::new().unwrap();
+            std::env::set_var("HOME", home.path());
+            home
+        })
+        .path()
+    }
+
     fn setup_test_env() -> (TempDir, PathBuf, PathBuf) {
         let temp_dir = TempDir::new().unwrap();
         let project_dir = temp_dir.path().to_path_buf();
@@ -868,6 +1200,7 @@ mod tests {
 
     #[test]
     fn test_package_registry_env_vars_injection() {
+        temp_home();
         // Create a temporary directory
         let temp_dir = TempDir::new().unwrap();
         let project_dir = temp_dir.path().to_path_buf();
@@ -891,6 +1224,7 @@ mod tests {
                     enabled: true,
                     port: 3080,
                     max_storage_gb: 10,
+                    max_download_kbps: 0,
                 },
                 ..Default::default()
             },
@@ -940,6 +1274,7 @@ mod tests {
 
     #[test]
     fn test_package_registry_disabled_no_env_vars() {
+        temp_home();
         // Create a temporary directory
         let temp_dir = TempDir::new().unwrap();
         let project_dir = temp_dir.path().to_path_buf();
@@ -963,6 +1298,7 @@ mod tests {
                     enabled: false,
                     port: 3080,
                     max_storage_gb: 10,
+                    max_download_kbps: 0,
                 },
                 ..Default::default()
             },
@@ -994,6 +1330,7 @@ mod tests {
 
     #[test]
     fn test_no_global_config_no_env_vars() {
+        temp_home();
         // Create a temporary directory
         let temp_dir = TempDir::new().unwrap();
         let project_dir = temp_dir.path().to_path_buf();
@@ -1035,6 +1372,7 @@ mod tests {
 
     #[test]
     fn test_host_gateway_detection() {
+        temp_home();
         let host = vm_platform::platform::get_host_gateway();
 
         #[cfg(target_os = "linux")]
@@ -1049,6 +1387,7 @@ mod tests {
 
     #[test]
     fn test_start_with_compose_regenerates_with_new_config() {
+        temp_home();
         use tempfile::TempDir;
         use vm_config::GlobalConfig;
 
@@ -1131,6 +1470,7 @@ mod tests {
 
     #[test]
     fn test_start_with_compose_can_disable_registry() {
+        temp_home();
         use tempfile::TempDir;
         use vm_config::GlobalConfig;
 
@@ -1194,6 +1534,7 @@ mod tests {
 
     #[test]
     fn test_worktrees_disabled_by_default() {
+        temp_home();
         let (_temp_dir, project_dir, temp_path) = setup_test_env();
         let config = VmConfig::default();
         let context = ProviderContext::default();
@@ -1207,6 +1548,7 @@ mod tests {
 
     #[test]
     fn test_worktrees_enabled_globally() {
+        temp_home();
         let (_temp_dir, project_dir, temp_path) = setup_test_env();
         let config = VmConfig {
             project: Some(ProjectConfig {
@@ -1231,6 +1573,7 @@ mod tests {
 
     #[test]
     fn test_worktrees_enabled_per_project() {
+        temp_home();
         let (_temp_dir, project_dir, temp_path) = setup_test_env();
         let config = VmConfig {
             project: Some(ProjectConfig {
@@ -1259,6 +1602,7 @@ mod tests {
 
     #[test]
     fn test_worktrees_project_overrides_global_disabled() {
+        temp_home();
         let (_temp_dir, project_dir, temp_path) = setup_test_env();
         let config = VmConfig {
             project: Some(ProjectConfig {
@@ -1288,6 +1632,7 @@ mod tests {
 
     #[test]
     fn test_worktrees_custom_base_path_from_project() {
+        temp_home();
         let (_temp_dir, project_dir, temp_path) = setup_test_env();
         let config = VmConfig {
             project: Some(ProjectConfig {
@@ -1315,6 +1660,7 @@ mod tests {
 
     #[test]
     fn test_worktrees_custom_base_path_from_global() {
+        temp_home();
         let (_temp_dir, project_dir, temp_path) = setup_test_env();
         let config = VmConfig {
             project: Some(ProjectConfig {
@@ -1342,6 +1688,7 @@ mod tests {
 
     #[test]
     fn test_worktrees_project_base_path_overrides_global() {
+        temp_home();
         let (_temp_dir, project_dir, temp_path) = setup_test_env();
         let config = VmConfig {
             project: Some(ProjectConfig {
@@ -1373,4 +1720,286 @@ mod tests {
         // Custom base_path is now deprecated - worktrees detected dynamically
         assert!(!rendered.is_empty());
     }
+
+    #[test]
+    fn test_proxy_env_reaches_vm_through_host_gateway() {
+        temp_home();
+        let host_env = vec![
+            (
+                "HTTPS_PROXY".to_string(),
+                "http://127.0.0.1:3128".to_string(),
+            ),
+            (
+                "NO_PROXY".to_string(),
+                "localhost,corp.internal".to_string(),
+            ),
+        ];
+        let vars = vm_proxy_env(&host_env, "host.docker.internal", true);
+        let get = |name: &str| {
+            vars.iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.as_str())
+        };
+
+        assert_eq!(get("HTTPS_PROXY"), Some("http://host.docker.internal:3128"));
+        assert_eq!(
+            get("NO_PROXY"),
+            Some("localhost,corp.internal,host.docker.internal")
+        );
+        assert_eq!(
+            get("npm_config_https_proxy"),
+            Some("http://host.docker.internal:3128")
+        );
+        assert_eq!(get("PIP_PROXY"), Some("http://host.docker.internal:3128"));
+        assert_eq!(get("npm_config_proxy"), None);
+        assert_eq!(get("NODE_EXTRA_CA_CERTS"), Some(VM_EXTRA_CA_PATH));
+
+        assert!(vm_proxy_env(&[], "host.docker.internal", false).is_empty());
+    }
+
+    #[test]
+    fn test_published_ports_carry_purpose_labels() {
+        temp_home();
+        let (_temp_dir, project_dir, temp_path) = setup_test_env();
+        let build_dir = temp_path.join("build");
+        std::fs::create_dir_all(&build_dir).unwrap();
+
+        let vm_config: VmConfig = serde_yaml_ng::from_str(
+            r#"
+project:
+  name: test-project
+ports:
+  mappings:
+    - host: 3100
+      guest: 3000
+    - host: 3101
+      guest: 9000
+      name: docs
+"#,
+        )
+        .unwrap();
+
+        let compose_ops = ComposeOperations::new(&vm_config, &temp_path, &project_dir, "docker");
+        let content = compose_ops
+            .render_docker_compose(&build_dir, &ProviderContext::default())
+            .unwrap();
+
+        assert!(content.contains("- \"com.vm.port.3100=web:3000\""));
+        assert!(content.contains("- \"com.vm.port.3101=docs:9000\""));
+    }
+
+    #[test]
+    fn test_mock_service_rendered_from_spec() {
+        temp_home();
+        let (_temp_dir, project_dir, temp_path) = setup_test_env();
+        let build_dir = temp_path.join("build");
+        std::fs::create_dir_all(&build_dir).unwrap();
+        std::fs::create_dir_all(project_dir.join("api")).unwrap();
+        std::fs::write(project_dir.join("api/openapi.yaml"), "openapi: 3.0.0\n").unwrap();
+
+        let vm_config: VmConfig = serde_yaml_ng::from_str(
+            r#"
+project:
+  name: test-project
+services:
+  mock:
+    enabled: true
+    port: 4010
+    spec: api/openapi.yaml
+"#,
+        )
+        .unwrap();
+
+        let compose_ops = ComposeOperations::new(&vm_config, &temp_path, &project_dir, "docker");
+        let content = compose_ops
+            .render_docker_compose(&build_dir, &ProviderContext::default())
+            .unwrap();
+
+        let compose: serde_yaml_ng::Value = serde_yaml_ng::from_str(&content).unwrap();
+        let mock = &compose["services"]["mock"];
+        assert_eq!(mock["image"].as_str(), Some("stoplight/prism:5"));
+        assert_eq!(mock["container_name"].as_str(), Some("test-project-mock"));
+        assert_eq!(mock["command"][5].as_str(), Some("/mock/openapi.yaml"));
+        assert_eq!(mock["ports"][0].as_str(), Some("4010:4010"));
+        let volume = mock["volumes"][0].as_str().unwrap();
+        assert!(volume.ends_with("/api:/mock:ro"), "{volume}");
+
+        let dev = &compose["services"]["test-project-dev"];
+        assert_eq!(dev["depends_on"][0].as_str(), Some("mock"));
+        assert!(content.contains("- MOCK_API_URL=http://mock:4010"));
+
+        // A missing spec fails before anything is created
+        std::fs::remove_file(project_dir.join("api/openapi.yaml")).unwrap();
+        assert!(compose_ops
+            .render_docker_compose(&build_dir, &ProviderContext::default())
+            .is_err());
+    }
+
+    #[test]
+    fn test_networking_dns_and_extra_hosts_rendered() {
+        temp_home();
+        let (_temp_dir, project_dir, temp_path) = setup_test_env();
+        let build_dir = temp_path.join("build");
+        std::fs::create_dir_all(&build_dir).unwrap();
+
+        let vm_config: VmConfig = serde_yaml_ng::from_str(
+            r#"
+project:
+  name: test-project
+networking:
+  dns:
+    - 10.0.0.2
+  extra_hosts:
+    - "git.corp.internal:10.1.2.3"
+host_sync:
+  extra_hosts:
+    - "db.internal:10.0.0.5"
+"#,
+        )
+        .unwrap();
+
+        let compose_ops = ComposeOperations::new(&vm_config, &temp_path, &project_dir, "docker");
+        let content = compose_ops
+            .render_docker_compose(&build_dir, &ProviderContext::default())
+            .unwrap();
+
+        let compose: serde_yaml_ng::Value = serde_yaml_ng::from_str(&content).unwrap();
+        let dev = &compose["services"]["test-project-dev"];
+        assert_eq!(dev["dns"][0].as_str(), Some("10.0.0.2"));
+        assert_eq!(
+            dev["extra_hosts"][0].as_str(),
+            Some("git.corp.internal:10.1.2.3")
+        );
+        assert_eq!(dev["extra_hosts"][1].as_str(), Some("db.internal:10.0.0.5"));
+    }
+
+    #[test]
+    fn test_ephemeral_mounts_workspace_read_only_without_volumes() {
+        temp_home();
+        let temp_dir = TempDir::new().unwrap();
+        let project_dir = temp_dir.path().to_path_buf();
+        let build_dir = temp_dir.path().join("build");
+        std::fs::create_dir_all(&build_dir).unwrap();
+
+        let vm_config = VmConfig {
+            project: Some(vm_config::config::ProjectConfig {
+                name: Some("test-project".to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let temp_path = temp_dir.path().to_path_buf();
+        let compose_ops = ComposeOperations::new(&vm_config, &temp_path, &project_dir, "docker");
+        let context = ProviderContext::default().ephemeral(true);
+        let content = compose_ops
+            .render_docker_compose(&build_dir, &context)
+            .unwrap();
+
+        assert!(content.contains(":/workspace:ro"));
+        assert!(!content.contains(":/workspace:rw"));
+        assert!(!content.contains("test-project_shell_history"));
+        assert!(content.contains("- \"com.vm.ephemeral=true\""));
+        let compose: serde_yaml_ng::Value = serde_yaml_ng::from_str(&content).unwrap();
+        assert!(compose.get("volumes").is_none());
+
+        // Read-only root with in-memory scratch space, and the workspace as the only mount
+        let service = &compose["services"]["test-project-dev"];
+        assert_eq!(service["read_only"], serde_yaml_ng::Value::Bool(true));
+        let tmpfs = service["tmpfs"].as_sequence().unwrap();
+        assert!(tmpfs.iter().any(|t| t.as_str() == Some("/tmp:exec")));
+        assert!(tmpfs.iter().any(|t| t.as_str() == Some("/run")));
+        assert_eq!(service["volumes"].as_sequence().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_read_only_root_keeps_tmp_executable_for_scripts() {
+        temp_home();
+        let temp_dir = TempDir::new().unwrap();
+        let project_dir = temp_dir.path().to_path_buf();
+        let build_dir = temp_dir.path().join("build");
+        std::fs::create_dir_all(&build_dir).unwrap();
+
+        let vm_config = VmConfig {
+            project: Some(vm_config::config::ProjectConfig {
+                name: Some("test-project".to_string()),
+                ..Default::default()
+            }),
+            security: Some(vm_config::config::SecurityConfig {
+                read_only_root: true,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let temp_path = temp_dir.path().to_path_buf();
+        let compose_ops = ComposeOperations::new(&vm_config, &temp_path, &project_dir, "docker");
+        let content = compose_ops
+            .render_docker_compose(&build_dir, &ProviderContext::default())
+            .unwrap();
+
+        // Docker and Podman mount tmpfs noexec unless told otherwise, and
+        // `vm exec --file` runs its script from $TMPDIR (/tmp)
+        let compose: serde_yaml_ng::Value = serde_yaml_ng::from_str(&content).unwrap();
+        let service = &compose["services"]["test-project-dev"];
+        assert_eq!(service["read_only"], serde_yaml_ng::Value::Bool(true));
+        let tmpfs = service["tmpfs"].as_sequence().unwrap();
+        assert!(tmpfs.iter().any(|t| t.as_str() == Some("/tmp:exec")));
+        assert!(crate::common::script::SCRIPT_RUNNER.contains("${TMPDIR:-/tmp}"));
+    }
+
+    #[test]
+    fn test_env_overrides_replace_configured_environment() {
+        let home = temp_home();
+        let temp_dir = TempDir::new().unwrap();
+        let project_dir = temp_dir.path().to_path_buf();
+        let build_dir = temp_dir.path().join("build");
+        std::fs::create_dir_all(&build_dir).unwrap();
+
+        let mut vm_config = VmConfig {
+            project: Some(vm_config::config::ProjectConfig {
+                name: Some("env-override-project".to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        vm_config
+            .environment
+            .insert("APP_ENV".to_string(), "development".to_string());
+
+        let temp_path = temp_dir.path().to_path_buf();
+        let compose_ops = ComposeOperations::new(&vm_config, &temp_path, &project_dir, "docker");
+        let context = ProviderContext::default()
+            .with_env_overrides(vec![("APP_ENV".to_string(), "test".to_string())]);
+        let content = compose_ops
+            .render_docker_compose(&build_dir, &context)
+            .unwrap();
+        assert!(content.contains("- APP_ENV=test"));
+        assert!(!content.contains("APP_ENV=development"));
+
+        // A named instance's saved file applies, with `--env` values winning over it
+        crate::common::instance_env::save_instance_env(
+            "env-override-project",
+            "qa",
+            &[
+                ("APP_ENV".to_string(), "staging".to_string()),
+                ("DATABASE".to_string(), "qa_db".to_string()),
+            ],
+        )
+        .unwrap();
+        assert!(
+            vm_core::user_paths::instance_env_path("env-override-project", "qa")
+                .unwrap()
+                .starts_with(home)
+        );
+        let content = compose_ops
+            .render_docker_compose_with_instance(&build_dir, "qa", &context)
+            .unwrap();
+        assert!(content.contains("- APP_ENV=test"));
+        assert!(content.contains("- DATABASE=qa_db"));
+        let content = compose_ops
+            .render_docker_compose(&build_dir, &ProviderContext::default())
+            .unwrap();
+        assert!(!content.contains("DATABASE=qa_db"));
+    }
 }
@@ -29,6 +29,7 @@ mod tests {
                     enabled: true,
                     port: 3080,
                     max_storage_gb: 10,
+                    max_download_kbps: 0,
                 },
                 ..Default::default()
             },
@@ -105,6 +106,7 @@ mod tests {
                     enabled: false,
                     port: 3080,
                     max_storage_gb: 10,
+                    max_download_kbps: 0,
                 },
                 ..Default::default()
             },
@@ -194,4 +196,110 @@ mod tests {
         #[cfg(not(target_os = "linux"))]
         assert_eq!(host, "host.docker.internal", "macOS/Windows should use host.docker.internal");
     }
+
+    #[test]
+    fn test_host_sync_extra_hosts_rendered() {
+        let temp_dir = TempDir::new().unwrap();
+        let project_dir = temp_dir.path().to_path_buf();
+        let build_dir = temp_dir.path().join("build");
+        std::fs::create_dir_all(&build_dir).unwrap();
+
+        let vm_config = VmConfig {
+            project: Some(vm_config::config::ProjectConfig {
+                name: Some("test-project".to_string()),
+                ..Default::default()
+            }),
+            host_sync: Some(vm_config::config::HostSyncConfig {
+                extra_hosts: vec!["db.internal:10.0.0.5".to_string()],
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let temp_path = temp_dir.path().to_path_buf();
+        let compose_ops = ComposeOperations::new(&vm_config, &temp_path, &project_dir, "docker");
+        let content = compose_ops
+            .render_docker_compose(&build_dir, &ProviderContext::default())
+            .unwrap();
+
+        assert!(content.contains("extra_hosts:"));
+        assert!(content.contains("- \"db.internal:10.0.0.5\""));
+    }
+
+    #[test]
+    fn test_resources_carry_management_labels() {
+        let temp_dir = TempDir::new().unwrap();
+        let project_dir = temp_dir.path().to_path_buf();
+        let build_dir = temp_dir.path().join("build");
+        std::fs::create_dir_all(&build_dir).unwrap();
+
+        let vm_config = VmConfig {
+            project: Some(vm_config::config::ProjectConfig {
+                name: Some("test-project".to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let temp_path = temp_dir.path().to_path_buf();
+        let compose_ops = ComposeOperations::new(&vm_config, &temp_path, &project_dir, "docker");
+        let content = compose_ops
+            .render_docker_compose(&build_dir, &ProviderContext::default())
+            .unwrap();
+
+        let hash = vm_config.config_hash();
+        assert!(content.contains("- \"com.vm.instance=default\""));
+        assert!(content.contains(&format!("- \"com.vm.config-hash={hash}\"")));
+        // The shell history volume is labeled too
+        assert!(content.contains("com.vm.project: \"test-project\""));
+    }
+
+    #[test]
+    fn test_sysctls_and_ulimits_rendered() {
+        let temp_dir = TempDir::new().unwrap();
+        let project_dir = temp_dir.path().to_path_buf();
+        let build_dir = temp_dir.path().join("build");
+        std::fs::create_dir_all(&build_dir).unwrap();
+
+        let vm_settings: vm_config::config::VmSettings = serde_yaml_ng::from_str(
+            r#"
+sysctls:
+  net.core.somaxconn: 1024
+  vm.max_map_count: 262144
+ulimits:
+  nofile: { soft: 1024, hard: 65536 }
+  nproc: 4096
+"#,
+        )
+        .unwrap();
+        let vm_config = VmConfig {
+            project: Some(vm_config::config::ProjectConfig {
+                name: Some("test-project".to_string()),
+                ..Default::default()
+            }),
+            vm: Some(vm_settings),
+            ..Default::default()
+        };
+
+        let temp_path = temp_dir.path().to_path_buf();
+        let compose_ops = ComposeOperations::new(&vm_config, &temp_path, &project_dir, "docker");
+        let content = compose_ops
+            .render_docker_compose(&build_dir, &ProviderContext::default())
+            .unwrap();
+
+        let compose: serde_yaml_ng::Value = serde_yaml_ng::from_str(&content).unwrap();
+        let dev = &compose["services"]["test-project-dev"];
+        let sysctls: Vec<&str> = dev["sysctls"]
+            .as_sequence()
+            .unwrap()
+            .iter()
+            .filter_map(|value| value.as_str())
+            .collect();
+        assert!(sysctls.contains(&"net.core.somaxconn=1024"));
+        // Host-wide sysctls are checked on the host, never passed to the container
+        assert!(!sysctls.iter().any(|sysctl| sysctl.starts_with("vm.")));
+        assert_eq!(dev["ulimits"]["nofile"]["soft"].as_i64(), Some(1024));
+        assert_eq!(dev["ulimits"]["nofile"]["hard"].as_i64(), Some(65536));
+        assert_eq!(dev["ulimits"]["nproc"].as_i64(), Some(4096));
+    }
 }
@@ -0,0 +1,153 @@
+//! Generated provider files
+//!
+//! docker-compose.yml, Dockerfile.generated and the build context live in
+//! `~/.vm/projects/<project>-<hash>/` rather than a throwaway temp directory, so
+//! `docker compose` keeps working after a reboot or a `vm` process that exited
+//! without cleaning up, and compose sees the same project directory every time.
+//! The hash is taken from the project's path on the host, so projects with the
+//! same name in different directories get separate files. `vm destroy` removes
+//! the directory.
+//!
+//! Files are written to a temporary file named after their content hash and
+//! renamed into place, so a crash never leaves a half-written file behind.
+//! Tracked files also record that hash in `<file>.sha256`; a file that no longer
+//! matches it is treated as missing and regenerated.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use sha2::{Digest, Sha256};
+use vm_config::config::VmConfig;
+use vm_core::error::{Result, VmError};
+use vm_core::user_paths;
+
+/// Persistent directory holding the generated files for the project in `config`
+pub fn project_dir(config: &VmConfig) -> Result<PathBuf> {
+    let project_name = config
+        .project
+        .as_ref()
+        .and_then(|p| p.name.as_deref())
+        .unwrap_or("vm-project");
+    // The directory holding vm.yaml, or the working directory without one
+    let root = match config.source_path.as_deref().and_then(Path::parent) {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+        _ => std::env::current_dir()?,
+    };
+    let root = root.canonicalize().unwrap_or(root);
+    let root_hash = content_hash(root.to_string_lossy().as_bytes());
+    user_paths::project_generated_dir(project_name, &root_hash[..12])
+}
+
+/// Write `content` to `path` unless it already holds exactly that content
+pub fn write_if_changed(path: &Path, content: &str) -> Result<()> {
+    match fs::read(path) {
+        Ok(existing) if existing == content.as_bytes() => Ok(()),
+        _ => write_atomic(path, content.as_bytes(), &content_hash(content.as_bytes())),
+    }
+}
+
+/// Write `content` like [`write_if_changed`] and record its hash for [`is_intact`]
+pub fn write_tracked(path: &Path, content: &str) -> Result<()> {
+    let hash = content_hash(content.as_bytes());
+    if is_intact(path) && fs::read(path).is_ok_and(|existing| existing == content.as_bytes()) {
+        return Ok(());
+    }
+    write_atomic(path, content.as_bytes(), &hash)?;
+    write_atomic(&checksum_path(path), hash.as_bytes(), &hash)
+}
+
+/// Whether a tracked file exists and still matches its recorded hash
+pub fn is_intact(path: &Path) -> bool {
+    let (Ok(content), Ok(recorded)) = (fs::read(path), fs::read_to_string(checksum_path(path)))
+    else {
+        return false;
+    };
+    recorded.trim() == content_hash(&content)
+}
+
+fn checksum_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".sha256");
+    path.with_file_name(name)
+}
+
+fn content_hash(content: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(content))
+}
+
+/// Write through `.<name>.<hash>.tmp` and rename it over `path`
+fn write_atomic(path: &Path, content: &[u8], hash: &str) -> Result<()> {
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| VmError::Internal(format!("Invalid generated file path: {path:?}")))?;
+    let temp_path = path.with_file_name(format!(
+        ".{}.{}.tmp",
+        file_name.to_string_lossy(),
+        &hash[..12]
+    ));
+
+    fs::write(&temp_path, content)?;
+    if let Err(e) = fs::rename(&temp_path, path) {
+        let _ = fs::remove_file(&temp_path);
+        return Err(VmError::Filesystem(format!(
+            "Failed to move generated file into place at {}: {e}",
+            path.display()
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_project_dir_is_keyed_by_project_path() {
+        let (first, second) = (TempDir::new().unwrap(), TempDir::new().unwrap());
+        let config_in = |dir: &TempDir| VmConfig {
+            project: Some(vm_config::config::ProjectConfig {
+                name: Some("app".to_string()),
+                ..Default::default()
+            }),
+            source_path: Some(dir.path().join("vm.yaml")),
+            ..Default::default()
+        };
+
+        let first_dir = project_dir(&config_in(&first)).unwrap();
+        assert_eq!(first_dir, project_dir(&config_in(&first)).unwrap());
+        assert_ne!(first_dir, project_dir(&config_in(&second)).unwrap());
+        assert!(first_dir
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .starts_with("app-"));
+    }
+
+    #[test]
+    fn test_tracked_file_detects_interrupted_write() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("docker-compose.yml");
+        assert!(!is_intact(&path));
+
+        write_tracked(&path, "services: {}\n").unwrap();
+        assert!(is_intact(&path));
+        assert_eq!(
+            fs::read_to_string(dir.path().join("docker-compose.yml.sha256")).unwrap(),
+            content_hash(b"services: {}\n")
+        );
+
+        // A truncated or hand-edited file no longer matches its recorded hash
+        fs::write(&path, "servi").unwrap();
+        assert!(!is_intact(&path));
+
+        write_tracked(&path, "services: {}\n").unwrap();
+        assert!(is_intact(&path));
+        let leftovers: Vec<_> = fs::read_dir(dir.path())
+            .unwrap()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_name().to_string_lossy().ends_with(".tmp"))
+            .collect();
+        assert!(leftovers.is_empty());
+    }
+}
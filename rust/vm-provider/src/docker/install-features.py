@@ -0,0 +1,187 @@
+"""Install devcontainer features from OCI registries.
+
+Invoked by vm inside the container (as root) with a JSON payload:
+
+    {"features": [{"id": "ghcr.io/devcontainers/features/node:1", "options": {...}}],
+     "remote_user": "developer", "remote_user_home": "/home/developer", "quiet": true}
+
+Features are installed in order. Each one is pulled as an OCI artifact, its
+install.sh runs with the feature's option defaults overridden by the options
+from vm.yaml, and its containerEnv is persisted to /etc/profile.d.
+"""
+
+import hashlib
+import io
+import json
+import os
+import re
+import subprocess
+import sys
+import tarfile
+import tempfile
+import urllib.error
+import urllib.parse
+import urllib.request
+
+MARKER_DIR = "/var/lib/vm/features"
+PROFILE_PATH = "/etc/profile.d/vm-features.sh"
+FEATURE_LAYER_TYPE = "application/vnd.devcontainers.layer.v1+tar"
+MANIFEST_ACCEPT = ", ".join(
+    [
+        "application/vnd.oci.image.manifest.v1+json",
+        "application/vnd.docker.distribution.manifest.v2+json",
+    ]
+)
+
+
+def parse_ref(ref):
+    """Split `registry/path[:tag|@digest]` into its parts."""
+    if "@" in ref:
+        repo, reference = ref.split("@", 1)
+    else:
+        repo, sep, reference = ref.rpartition(":")
+        if not sep or "/" in reference:
+            repo, reference = ref, "latest"
+    registry, _, path = repo.partition("/")
+    if not path:
+        raise ValueError(f"invalid feature reference '{ref}'")
+    return registry, path, reference
+
+
+def fetch(url, accept, auth):
+    """GET `url`, answering a registry Bearer challenge once if needed."""
+    request = urllib.request.Request(url)
+    if accept:
+        request.add_header("Accept", accept)
+    if auth.get("token"):
+        # Not forwarded on redirects to blob storage
+        request.add_unredirected_header("Authorization", "Bearer " + auth["token"])
+    try:
+        with urllib.request.urlopen(request, timeout=120) as response:
+            return response.read()
+    except urllib.error.HTTPError as error:
+        if error.code != 401 or auth.get("token"):
+            raise
+        challenge = error.headers.get("WWW-Authenticate", "")
+        params = dict(re.findall(r'(\w+)="([^"]*)"', challenge))
+        realm = params.pop("realm", None)
+        if not realm:
+            raise
+        with urllib.request.urlopen(
+            realm + "?" + urllib.parse.urlencode(params), timeout=60
+        ) as response:
+            body = json.loads(response.read())
+        auth["token"] = body.get("token") or body.get("access_token")
+        return fetch(url, accept, auth)
+
+
+def extract(blob, dest):
+    root = os.path.realpath(dest)
+    with tarfile.open(fileobj=io.BytesIO(blob)) as tar:
+        for member in tar.getmembers():
+            paths = [os.path.join(root, member.name)]
+            if member.issym():
+                paths.append(os.path.join(root, os.path.dirname(member.name), member.linkname))
+            elif member.islnk():
+                paths.append(os.path.join(root, member.linkname))
+            for path in paths:
+                target = os.path.realpath(path)
+                if target != root and not target.startswith(root + os.sep):
+                    raise ValueError(f"unsafe path in feature archive: {member.name}")
+        tar.extractall(root)
+
+
+def option_env_name(name):
+    """Option id to environment variable, as defined by the features spec."""
+    name = re.sub(r"[^\w_]", "_", name)
+    name = re.sub(r"^[\d_]+", "_", name)
+    return name.upper()
+
+
+def option_value(value):
+    if isinstance(value, bool):
+        return "true" if value else "false"
+    return str(value)
+
+
+def install(feature, payload):
+    ref = feature["id"]
+    options = feature.get("options", {})
+    key = hashlib.sha256(json.dumps(feature, sort_keys=True).encode()).hexdigest()[:16]
+    marker = os.path.join(MARKER_DIR, key)
+    if os.path.exists(marker):
+        print(f"  ✓ {ref} (already installed)")
+        return
+
+    print(f"  📦 Installing feature {ref}...", flush=True)
+    registry, path, reference = parse_ref(ref)
+    auth = {}
+    base = f"https://{registry}/v2/{path}"
+    manifest = json.loads(fetch(f"{base}/manifests/{reference}", MANIFEST_ACCEPT, auth))
+    layers = manifest.get("layers", [])
+    layer = next((l for l in layers if l.get("mediaType") == FEATURE_LAYER_TYPE), None)
+    if layer is None:
+        raise ValueError(f"{ref} is not a devcontainer feature (no feature layer)")
+    blob = fetch(f"{base}/blobs/{layer['digest']}", None, auth)
+
+    with tempfile.TemporaryDirectory(prefix="vm-feature-") as workdir:
+        extract(blob, workdir)
+        metadata_path = os.path.join(workdir, "devcontainer-feature.json")
+        metadata = {}
+        if os.path.exists(metadata_path):
+            with open(metadata_path) as f:
+                metadata = json.load(f)
+
+        env = dict(os.environ)
+        for name, spec in metadata.get("options", {}).items():
+            if isinstance(spec, dict) and "default" in spec:
+                env[option_env_name(name)] = option_value(spec["default"])
+        for name, value in options.items():
+            env[option_env_name(name)] = option_value(value)
+        env.update(
+            {
+                "_REMOTE_USER": payload["remote_user"],
+                "_REMOTE_USER_HOME": payload["remote_user_home"],
+                "_CONTAINER_USER": payload["remote_user"],
+                "_CONTAINER_USER_HOME": payload["remote_user_home"],
+            }
+        )
+
+        result = subprocess.run(
+            ["sh", "-c", "chmod +x ./install.sh && ./install.sh"],
+            cwd=workdir,
+            env=env,
+            stdout=subprocess.PIPE if payload.get("quiet") else None,
+            stderr=subprocess.STDOUT if payload.get("quiet") else None,
+        )
+        if result.returncode != 0:
+            if result.stdout:
+                sys.stdout.write(result.stdout.decode(errors="replace"))
+            raise RuntimeError(f"install.sh for {ref} exited with {result.returncode}")
+
+        container_env = metadata.get("containerEnv", {})
+        if container_env:
+            with open(PROFILE_PATH, "a") as profile:
+                profile.write(f"# {ref}\n")
+                for name, value in container_env.items():
+                    profile.write(f'export {name}="{value}"\n')
+
+    os.makedirs(MARKER_DIR, exist_ok=True)
+    with open(marker, "w") as f:
+        f.write(ref + "\n")
+    print(f"  ✓ {ref}")
+
+
+def main():
+    payload = json.loads(sys.argv[1])
+    for feature in payload["features"]:
+        try:
+            install(feature, payload)
+        except Exception as error:  # noqa: BLE001 - report and stop on first failure
+            print(f"  ✗ Failed to install feature {feature['id']}: {error}", file=sys.stderr)
+            return 1
+    return 0
+
+
+if __name__ == "__main__":
+    sys.exit(main())
@@ -8,9 +8,14 @@ use tracing::{error, info, warn};
 
 use super::LifecycleOperations;
 use crate::{
-    audio::MacOSAudioManager,
     context::ProviderContext,
-    docker::{build::BuildOperations, compose::ComposeOperations, ComposeCommand, DockerOps},
+    devices::{self, MacOSAudioManager},
+    docker::{
+        build::BuildOperations,
+        builder::{ensure_cache_builder, registry_cache_ref, RemoteBuilder, CACHE_BUILDER_NAME},
+        compose::ComposeOperations,
+        ComposeCommand, DockerOps,
+    },
 };
 use vm_config::config::VmConfig;
 use vm_core::msg;
@@ -108,16 +113,15 @@ impl<'a> LifecycleOperations<'a> {
             };
         }
 
-        // Only setup audio if it's enabled in the configuration
-        if let Some(audio_service) = self.config.services.get("audio") {
-            if audio_service.enabled {
-                #[cfg(target_os = "macos")]
-                if let Err(e) = MacOSAudioManager::setup() {
-                    warn!("Audio setup failed: {}", e);
-                }
-                #[cfg(not(target_os = "macos"))]
-                MacOSAudioManager::setup();
+        // Fail before building if a requested device can't be passed through
+        let devices = devices::preflight(self.config, self.executable)?;
+        if devices.audio {
+            #[cfg(target_os = "macos")]
+            if let Err(e) = MacOSAudioManager::setup() {
+                warn!("Audio setup failed: {}", e);
             }
+            #[cfg(not(target_os = "macos"))]
+            MacOSAudioManager::setup();
         }
 
         let _vm_name = self
@@ -130,6 +134,9 @@ impl<'a> LifecycleOperations<'a> {
 
         // Messages are now handled at the command level for consistency
 
+        // Catch unusable bind mounts now instead of as EACCES inside the VM
+        self.check_mount_permissions(context.ephemeral);
+
         // Step 1: Filter pipx-managed packages from pip_packages
         let modified_config = self.prepare_config_for_build()?;
 
@@ -141,7 +148,16 @@ impl<'a> LifecycleOperations<'a> {
         if let Some(networking) = &modified_config.networking {
             if !networking.networks.is_empty() {
                 info!("Ensuring Docker networks exist: {:?}", networking.networks);
-                DockerOps::ensure_networks_exist(Some(self.executable), &networking.networks)?;
+                let labels = vm_core::labels::label_args(
+                    self.project_name(),
+                    instance_name.unwrap_or(vm_core::labels::DEFAULT_INSTANCE),
+                    &self.config.config_hash(),
+                );
+                DockerOps::ensure_networks_exist(
+                    Some(self.executable),
+                    &networking.networks,
+                    &labels,
+                )?;
             }
         }
 
@@ -155,16 +171,32 @@ impl<'a> LifecycleOperations<'a> {
         // Step 4: Gather build arguments for packages
         let build_args = build_ops.gather_build_args(&base_image);
         let image_tag = build_ops.derived_image_tag(&base_image, &build_context)?;
+        let needs_build = !build_ops.image_exists(&image_tag)?;
+
+        // Builds go to a remote BuildKit builder when configured; otherwise they share
+        // layer caches through the Docker registry service when it's running.
+        let remote_builder = if needs_build {
+            self.remote_builder(context)?
+        } else {
+            None
+        };
+        let registry_cache = if needs_build && remote_builder.is_none() {
+            self.registry_build_cache(context)
+        } else {
+            None
+        };
+        let compose_context = context.clone().with_build_cache(registry_cache.clone());
+
         let compose_path = match instance_name {
             Some(name) => compose_ops.write_docker_compose_with_instance_and_image_tag(
                 &build_context,
                 name,
-                context,
+                &compose_context,
                 &image_tag,
             )?,
             None => compose_ops.write_docker_compose_with_image_tag(
                 &build_context,
-                context,
+                &compose_context,
                 &image_tag,
             )?,
         };
@@ -176,16 +208,21 @@ impl<'a> LifecycleOperations<'a> {
         let has_orphaned_services = self.check_for_orphaned_containers(instance_name, context)?;
 
         // Step 5: Build with all package arguments
-        if build_ops.image_exists(&image_tag)? {
+        if !needs_build {
             vm_dbg!("Reusing cached derived image '{}'", image_tag);
         } else {
-            let build_flags = if context.is_verbose() {
-                Vec::new()
-            } else {
-                vec!["--quiet"]
+            // Remote builds always stream their logs so progress on the build server is visible
+            let mut build_flags = match (&remote_builder, &registry_cache) {
+                (Some(builder), _) => builder.compose_build_flags(),
+                (None, Some(_)) => vec!["--builder".to_string(), CACHE_BUILDER_NAME.to_string()],
+                (None, None) => Vec::new(),
             };
+            if remote_builder.is_none() && !context.is_verbose() {
+                build_flags.push("--quiet".to_string());
+            }
+            let build_flags_refs: Vec<&str> = build_flags.iter().map(|s| s.as_str()).collect();
             let base_compose_args =
-                ComposeCommand::build_args(&compose_path, "build", &build_flags)?;
+                ComposeCommand::build_args(&compose_path, "build", &build_flags_refs)?;
 
             // Combine compose args with dynamic build args
             let mut all_args = Vec::with_capacity(base_compose_args.len() + build_args.len());
@@ -251,6 +288,49 @@ impl<'a> LifecycleOperations<'a> {
         Ok(())
     }
 
+    /// Resolve and prepare the remote BuildKit builder from the global config, if any
+    fn remote_builder<'c>(
+        &'c self,
+        context: &'c ProviderContext,
+    ) -> Result<Option<RemoteBuilder<'c>>> {
+        let Some(settings) = context
+            .global_config
+            .as_ref()
+            .and_then(|g| g.build.remote_builder.as_ref())
+        else {
+            return Ok(None);
+        };
+
+        if !self.executable.ends_with("docker") {
+            warn!(
+                "Remote builders require docker buildx; building locally with {}",
+                self.executable
+            );
+            return Ok(None);
+        }
+
+        let builder = RemoteBuilder::new(settings, self.executable);
+        builder.ensure_ready()?;
+        Ok(Some(builder))
+    }
+
+    /// Registry reference for the shared build layer cache, if the Docker registry
+    /// service is running and a cache-capable builder is available
+    fn registry_build_cache(&self, context: &ProviderContext) -> Option<String> {
+        let global_config = context.global_config.as_ref()?;
+        if !self.executable.ends_with("docker") {
+            return None;
+        }
+        let cache_ref = registry_cache_ref(global_config, self.project_name())?;
+
+        if let Err(e) = ensure_cache_builder(self.executable) {
+            warn!("Shared build cache unavailable, building without it: {}", e);
+            return None;
+        }
+        vm_dbg!("Using shared build cache '{}'", &cache_ref);
+        Some(cache_ref)
+    }
+
     /// Helper to start orphaned services and dev container avoiding conflicts
     fn start_orphaned_services_and_dev_container(
         &self,
@@ -485,7 +565,7 @@ impl<'a> LifecycleOperations<'a> {
     }
 
     #[must_use = "config preparation results should be checked"]
-    fn prepare_config_for_build(&self) -> Result<Cow<'_, VmConfig>> {
+    pub(super) fn prepare_config_for_build(&self) -> Result<Cow<'_, VmConfig>> {
         let pipx_managed_packages = if let Some(pipx_json) = self.get_pipx_json()? {
             self.extract_pipx_managed_packages(&pipx_json)
         } else {
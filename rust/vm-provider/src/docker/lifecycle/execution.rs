@@ -1,9 +1,10 @@
 //! Container lifecycle execution (start/stop/restart/kill)
 use super::LifecycleOperations;
 use crate::{
-    audio::MacOSAudioManager,
     context::ProviderContext,
+    devices::MacOSAudioManager,
     docker::{compose::ComposeOperations, DockerOps},
+    ResourceLimits,
 };
 use tracing::{info, warn};
 use vm_core::{
@@ -65,6 +66,8 @@ impl<'a> LifecycleOperations<'a> {
 
         // Check if container exists before attempting destruction
         if !DockerOps::container_exists(Some(self.executable), &target_container).unwrap_or(false) {
+            // Nothing left to run the generated files against
+            self.remove_generated_files();
             return Err(VmError::Internal(format!(
                 "Container '{target_container}' does not exist"
             )));
@@ -103,29 +106,32 @@ impl<'a> LifecycleOperations<'a> {
         }
 
         // Only cleanup audio if it was enabled in the configuration
-        if let Some(audio_service) = self.config.services.get("audio") {
-            if audio_service.enabled {
-                #[cfg(target_os = "macos")]
-                if let Err(e) = MacOSAudioManager::cleanup() {
-                    vm_warning!("Audio cleanup warning: {}", e);
-                }
-                #[cfg(not(target_os = "macos"))]
-                MacOSAudioManager::cleanup();
+        if self.config.requested_devices().audio {
+            #[cfg(target_os = "macos")]
+            if let Err(e) = MacOSAudioManager::cleanup() {
+                vm_warning!("Audio cleanup warning: {}", e);
             }
+            #[cfg(not(target_os = "macos"))]
+            MacOSAudioManager::cleanup();
         }
 
-        // Clean up the temporary instance directory to prevent disk leaks
+        self.remove_generated_files();
+
+        result
+    }
+
+    /// Remove the project's generated compose files and build context so they
+    /// don't pile up in `~/.vm/projects`
+    fn remove_generated_files(&self) {
         if self.temp_dir.exists() {
             if let Err(e) = std::fs::remove_dir_all(self.temp_dir) {
                 vm_warning!(
-                    "Failed to remove temp directory {}: {}",
+                    "Failed to remove generated files in {}: {}",
                     self.temp_dir.display(),
                     e
                 );
             }
         }
-
-        result
     }
 
     #[must_use = "container restart results should be handled"]
@@ -151,6 +157,42 @@ impl<'a> LifecycleOperations<'a> {
         compose_ops.start_named_with_compose(&target_container, context)
     }
 
+    /// Update CPU and memory limits of a running container in place
+    #[must_use = "resource update results should be handled"]
+    pub fn update_container_resources(
+        &self,
+        container: Option<&str>,
+        limits: &ResourceLimits,
+    ) -> Result<()> {
+        let target_container = self.resolve_target_container(container)?;
+        let cpus = limits.cpus.to_string();
+        let memory = format!("{}m", limits.memory_mb);
+        // Docker requires memory-swap >= memory; mirror its 2x default when swap is unset
+        let memory_swap = format!(
+            "{}m",
+            limits.memory_mb + limits.swap_mb.unwrap_or(limits.memory_mb)
+        );
+        stream_command(
+            self.executable,
+            &[
+                "update",
+                "--cpus",
+                &cpus,
+                "--memory",
+                &memory,
+                "--memory-swap",
+                &memory_swap,
+                &target_container,
+            ],
+        )
+        .map_err(|e| {
+            VmError::Internal(format!(
+                "Failed to update resources for container '{}': {}",
+                target_container, e
+            ))
+        })
+    }
+
     #[must_use = "container kill results should be handled"]
     pub fn kill_container(&self, container: Option<&str>) -> Result<()> {
         let container_name = self.container_name();
@@ -0,0 +1,114 @@
+//! Devcontainer feature installation
+//!
+//! Features listed under `features:` in vm.yaml are installed after
+//! provisioning by an embedded Python helper that pulls each feature's OCI
+//! artifact and runs its `install.sh` inside the container.
+use super::LifecycleOperations;
+use crate::context::ProviderContext;
+use crate::docker::UserConfig;
+use vm_core::command_stream::stream_command_visible;
+use vm_core::error::{Result, VmError};
+use vm_core::vm_println;
+
+const INSTALL_FEATURES_SCRIPT: &str = include_str!("../install-features.py");
+
+impl<'a> LifecycleOperations<'a> {
+    /// Install the configured devcontainer features into a running container
+    pub(super) fn install_features(
+        &self,
+        container_name: &str,
+        context: &ProviderContext,
+    ) -> Result<()> {
+        if self.config.features.is_empty() {
+            return Ok(());
+        }
+
+        vm_println!(
+            "🧩 Installing {} devcontainer feature{}...",
+            self.config.features.len(),
+            if self.config.features.len() == 1 {
+                ""
+            } else {
+                "s"
+            }
+        );
+
+        let payload = self.features_payload(context);
+        stream_command_visible(
+            self.executable,
+            &[
+                "exec",
+                "-u",
+                "root",
+                container_name,
+                "python3",
+                "-c",
+                INSTALL_FEATURES_SCRIPT,
+                &payload.to_string(),
+            ],
+        )
+        .map_err(|e| {
+            VmError::Internal(format!(
+                "Devcontainer feature installation failed. Run `vm create --verbose` for the full install output. Error: {e}"
+            ))
+        })
+    }
+
+    fn features_payload(&self, context: &ProviderContext) -> serde_json::Value {
+        let user_config = UserConfig::from_vm_config(self.config);
+        let features: Vec<serde_json::Value> = self
+            .config
+            .features
+            .iter()
+            .map(|feature| {
+                serde_json::json!({
+                    "id": feature.id(),
+                    "options": feature.options(),
+                })
+            })
+            .collect();
+
+        serde_json::json!({
+            "features": features,
+            "remote_user": user_config.username,
+            "remote_user_home": format!("/home/{}", user_config.username),
+            "quiet": !context.is_verbose(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use vm_config::config::{FeatureSpec, VmConfig};
+
+    #[test]
+    fn test_features_payload_includes_options_as_strings() {
+        let mut options = indexmap::IndexMap::new();
+        options.insert("version".to_string(), serde_json::json!("3.12"));
+        options.insert("installTools".to_string(), serde_json::json!(false));
+
+        let config = VmConfig {
+            features: vec![
+                FeatureSpec::Id("ghcr.io/devcontainers/features/node:1".to_string()),
+                FeatureSpec::WithOptions {
+                    id: "ghcr.io/devcontainers/features/python:1".to_string(),
+                    options,
+                },
+            ],
+            ..Default::default()
+        };
+        let dir = PathBuf::from("/tmp");
+        let ops = LifecycleOperations::new(&config, &dir, &dir, "docker");
+
+        let payload = ops.features_payload(&ProviderContext::with_verbose(true));
+        assert_eq!(
+            payload["features"][0]["id"],
+            "ghcr.io/devcontainers/features/node:1"
+        );
+        assert_eq!(payload["features"][1]["options"]["version"], "3.12");
+        assert_eq!(payload["features"][1]["options"]["installTools"], "false");
+        assert_eq!(payload["quiet"], false);
+    }
+}
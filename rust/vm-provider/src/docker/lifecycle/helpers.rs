@@ -5,9 +5,22 @@ use crate::{
     context::ProviderContext,
     docker::{build::BuildOperations, compose::ComposeOperations},
 };
+use vm_config::config::LineEndings;
 use vm_core::error::{Result, VmError};
 use vm_core::vm_error_with_details;
 
+/// Whether Git will write CRLF into the working tree: `core.autocrlf=true` and no
+/// `.gitattributes` rule pinning files to `eol=lf`
+pub(super) fn checks_out_crlf(gitattributes: Option<&str>, autocrlf: Option<&str>) -> bool {
+    let pinned_to_lf = gitattributes.is_some_and(|attributes| {
+        attributes.lines().any(|line| {
+            let line = line.trim();
+            !line.starts_with('#') && line.split_whitespace().any(|attr| attr == "eol=lf")
+        })
+    });
+    !pinned_to_lf && autocrlf.map(str::trim) == Some("true")
+}
+
 // Constants (moved from top of lifecycle.rs)
 pub(super) const DEFAULT_PROJECT_NAME: &str = "vm-project";
 pub(super) const CONTAINER_SUFFIX: &str = "-dev";
@@ -88,6 +101,72 @@ impl<'a> LifecycleOperations<'a> {
         }
     }
 
+    /// Warn when the host kernel is below a host-wide sysctl from `vm.sysctls`.
+    /// These can't be set per container, so the user has to raise them on the host.
+    pub(super) fn check_host_sysctls(&self, vm_config: &vm_config::config::VmSettings) {
+        for (key, wanted) in &vm_config.sysctls {
+            if !vm_config::config::is_host_sysctl(key) {
+                continue;
+            }
+            let Ok(wanted_value) = wanted.to_string().parse::<i64>() else {
+                continue;
+            };
+            // Only readable on Linux; Docker Desktop's VM already uses generous defaults
+            let current = std::fs::read_to_string(format!("/proc/sys/{}", key.replace('.', "/")))
+                .ok()
+                .and_then(|value| value.trim().parse::<i64>().ok());
+            if let Some(current) = current.filter(|current| *current < wanted_value) {
+                vm_core::vm_warning!(
+                    "Host {} is {} but this VM needs {}. Raise it with: sudo sysctl -w {}={}",
+                    key,
+                    current,
+                    wanted_value,
+                    key,
+                    wanted_value
+                );
+            }
+        }
+    }
+
+    /// Warn on Windows hosts when Git will check the project out with CRLF line endings,
+    /// which break shell scripts and shebangs inside the Linux container.
+    pub(super) fn check_line_endings(&self) {
+        if !cfg!(windows) {
+            return;
+        }
+        let setting = self
+            .config
+            .host_sync
+            .as_ref()
+            .and_then(|hs| hs.line_endings)
+            .unwrap_or_default();
+        if setting == LineEndings::Ignore {
+            return;
+        }
+
+        let gitattributes = std::fs::read_to_string(self.project_dir.join(".gitattributes")).ok();
+        let autocrlf = std::process::Command::new("git")
+            .arg("-C")
+            .arg(self.project_dir)
+            .args(["config", "--get", "core.autocrlf"])
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .map(|output| String::from_utf8_lossy(&output.stdout).into_owned());
+
+        if checks_out_crlf(gitattributes.as_deref(), autocrlf.as_deref()) {
+            vm_core::vm_warning!(
+                "Git converts line endings to CRLF on checkout (core.autocrlf=true); scripts in /workspace may fail with \"$'\\r': command not found\""
+            );
+            vm_core::vm_error_hint!(
+                "Pin LF in .gitattributes: echo \"* text=auto eol=lf\" >> .gitattributes && git add --renormalize ."
+            );
+            vm_core::vm_error_hint!(
+                "Or silence this check for the project: vm config set host_sync.line_endings ignore"
+            );
+        }
+    }
+
     #[must_use = "Docker daemon status should be checked"]
     pub(super) fn check_daemon_is_running(&self) -> Result<()> {
         crate::docker::DockerOps::check_daemon_running(Some(self.executable))
@@ -176,7 +255,9 @@ impl<'a> LifecycleOperations<'a> {
         // Check for port conflicts and provide helpful guidance
         if let Some(vm_config) = &self.config.vm {
             self.check_memory_allocation(vm_config);
+            self.check_host_sysctls(vm_config);
         }
+        self.check_line_endings();
 
         // Check Docker daemon status more thoroughly
         if DockerCommand::new(Some(self.executable))
@@ -309,17 +390,40 @@ impl<'a> LifecycleOperations<'a> {
             self.project_dir,
             self.executable,
         );
+        // An ephemeral VM stays ephemeral across restarts
+        let mut context = context.clone();
+        context.ephemeral = context.ephemeral || self.is_ephemeral(container);
+
         if let Some(instance_name) = self.resolve_instance_name_for_target(container)? {
             compose_ops.write_docker_compose_with_instance(
                 &build_context,
                 &instance_name,
-                context,
+                &context,
             )?;
         } else {
-            compose_ops.write_docker_compose(&build_context, context)?;
+            compose_ops.write_docker_compose(&build_context, &context)?;
         }
         Ok(compose_ops)
     }
+
+    /// Whether the target container was created with `vm create --ephemeral`
+    pub(super) fn is_ephemeral(&self, container: Option<&str>) -> bool {
+        let Ok(target) = self.resolve_target_container(container) else {
+            return false;
+        };
+        DockerCommand::new(Some(self.executable))
+            .subcommand("inspect")
+            .arg("--format")
+            .arg(format!(
+                "{{{{index .Config.Labels \"{}\"}}}}",
+                vm_core::labels::EPHEMERAL
+            ))
+            .arg(target)
+            .execute_raw()
+            .is_ok_and(|output| {
+                output.status.success() && String::from_utf8_lossy(&output.stdout).trim() == "true"
+            })
+    }
 }
 
 #[cfg(test)]
@@ -354,6 +458,20 @@ mod tests {
         });
     }
 
+    #[test]
+    fn crlf_checkouts_need_autocrlf_without_an_lf_rule() {
+        use super::checks_out_crlf;
+
+        assert!(checks_out_crlf(None, Some("true\n")));
+        assert!(checks_out_crlf(
+            Some("# eol=lf\n*.png binary"),
+            Some("true")
+        ));
+        assert!(!checks_out_crlf(Some("*.sh text eol=lf"), Some("true")));
+        assert!(!checks_out_crlf(None, Some("input")));
+        assert!(!checks_out_crlf(None, None));
+    }
+
     #[test]
     fn resolves_instance_target_suffix() {
         with_test_ops(|ops| {
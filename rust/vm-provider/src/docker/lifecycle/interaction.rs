@@ -2,7 +2,11 @@
 use std::io::IsTerminal;
 use std::path::Path;
 
+use super::logs::logs_args;
 use super::LifecycleOperations;
+use crate::common::host_bridge::{BridgeFeatures, HostBridge, CLIPBOARD_ENV};
+use crate::common::script;
+use crate::LogsRequest;
 use crate::{docker::UserConfig, security::SecurityValidator};
 use vm_core::msg;
 use vm_core::{
@@ -47,6 +51,24 @@ impl<'a> LifecycleOperations<'a> {
         Ok(())
     }
 
+    /// Start the host bridge for the browser (unless `host_sync.browser` is off)
+    /// and the clipboard (when `host_sync.clipboard` is on), with the variables
+    /// that point the session at it
+    fn host_bridge(&self) -> (Option<HostBridge>, Vec<String>) {
+        let host_sync = self.config.host_sync.as_ref();
+        let features = BridgeFeatures {
+            browser: host_sync.map(|hs| hs.browser).unwrap_or(true),
+            clipboard: host_sync.is_some_and(|hs| hs.clipboard),
+        };
+        let bridge = HostBridge::start(self.executable, features);
+        let mut env = bridge.as_ref().map(HostBridge::env).unwrap_or_default();
+        if features.clipboard && bridge.is_none() {
+            // vm-clip can still copy through OSC 52
+            env.push(format!("{CLIPBOARD_ENV}=1"));
+        }
+        (bridge, env)
+    }
+
     #[must_use = "SSH connection results should be handled"]
     pub fn ssh_into_container(&self, container: Option<&str>, relative_path: &Path) -> Result<()> {
         let workspace_path = self
@@ -135,32 +157,36 @@ impl<'a> LifecycleOperations<'a> {
 
         self.ensure_shell_history_writable(&container_name, &user_config)?;
 
+        // Kept alive for the whole session so `xdg-open` and `vm-clip` in the VM reach the host
+        let (_bridge, bridge_env) = self.host_bridge();
+
         // Container is running, proceed with exec
-        let result = duct::cmd(
-            self.executable,
-            &[
-                "exec",
-                tty_flag,
-                &container_name,
-                "sudo",
-                "-Hu",
-                project_user,
-                "env",
-                &format!("HOME={project_home}"),
-                &format!("USER={project_user}"),
-                &format!("LOGNAME={project_user}"),
-                &format!("SHELL={shell}"),
-                "sh",
-                "-lc",
-                &format!(
-                    "export VM_TARGET_DIR='{target_dir}' && cd \"$VM_TARGET_DIR\" && exec \"$SHELL\" -il",
-                    target_dir = target_dir_escaped
-                ),
-            ],
-        )
-        .env("DOCKER_CLI_HINTS", "false")
-        .unchecked() // Allow all exit codes - we'll handle them below
-        .run();
+        let mut args: Vec<String> = vec![
+            "exec".to_string(),
+            tty_flag.to_string(),
+            container_name.clone(),
+            "sudo".to_string(),
+            "-Hu".to_string(),
+            project_user.to_string(),
+            "env".to_string(),
+            format!("HOME={project_home}"),
+            format!("USER={project_user}"),
+            format!("LOGNAME={project_user}"),
+            format!("SHELL={shell}"),
+        ];
+        args.extend(bridge_env);
+        args.extend([
+            "sh".to_string(),
+            "-lc".to_string(),
+            format!(
+                "export VM_TARGET_DIR='{target_dir}' && cd \"$VM_TARGET_DIR\" && exec \"$SHELL\" -il",
+                target_dir = target_dir_escaped
+            ),
+        ]);
+        let result = duct::cmd(self.executable, &args)
+            .env("DOCKER_CLI_HINTS", "false")
+            .unchecked() // Allow all exit codes - we'll handle them below
+            .run();
 
         match result {
             // Interactive shell exit code reflects the last command the user ran,
@@ -192,9 +218,13 @@ impl<'a> LifecycleOperations<'a> {
         }
     }
 
-    #[must_use = "command execution results should be handled"]
-    pub fn exec_in_container(&self, container: Option<&str>, cmd: &[String]) -> Result<()> {
-        let target_container = self.resolve_target_container(container)?;
+    /// `docker exec` arguments that run a command as the project user in the workspace
+    fn workspace_exec_args(
+        &self,
+        target_container: String,
+        attach_stdin: bool,
+        extra_env: Vec<String>,
+    ) -> Vec<String> {
         let workspace_path = self
             .config
             .project
@@ -212,8 +242,11 @@ impl<'a> LifecycleOperations<'a> {
             .unwrap_or(DEFAULT_SHELL);
         let workspace_escaped = Self::shell_escape_single_quotes(workspace_path);
 
-        let mut args: Vec<String> = vec![
-            "exec".to_string(),
+        let mut args: Vec<String> = vec!["exec".to_string()];
+        if attach_stdin {
+            args.push("-i".to_string());
+        }
+        args.extend([
             target_container,
             "sudo".to_string(),
             "-Hu".to_string(),
@@ -223,16 +256,41 @@ impl<'a> LifecycleOperations<'a> {
             format!("USER={project_user}"),
             format!("LOGNAME={project_user}"),
             format!("SHELL={shell}"),
+        ]);
+        args.extend(extra_env);
+        args.extend([
             shell.to_string(),
             "-ilc".to_string(),
             format!("cd '{workspace_escaped}' && exec \"$@\""),
             "vm-exec".to_string(),
-        ];
+        ]);
+        args
+    }
+
+    #[must_use = "command execution results should be handled"]
+    pub fn exec_in_container(&self, container: Option<&str>, cmd: &[String]) -> Result<()> {
+        let target_container = self.resolve_target_container(container)?;
+        let (_bridge, bridge_env) = self.host_bridge();
+        let mut args = self.workspace_exec_args(target_container, false, bridge_env);
         args.extend(cmd.iter().cloned());
         let arg_refs: Vec<&str> = args.iter().map(String::as_str).collect();
         stream_command(self.executable, &arg_refs)
     }
 
+    /// Run a script in the workspace, returning its exit code
+    #[must_use = "script execution results should be handled"]
+    pub fn exec_script_in_container(
+        &self,
+        container: Option<&str>,
+        script: &[u8],
+        script_args: &[String],
+    ) -> Result<i32> {
+        let target_container = self.resolve_target_container(container)?;
+        let mut args = self.workspace_exec_args(target_container, true, Vec::new());
+        args.extend(script::runner_command(script_args));
+        script::run_with_script(self.executable, &args, script)
+    }
+
     #[must_use = "log display results should be handled"]
     pub fn show_logs(&self, container: Option<&str>) -> Result<()> {
         // Show recent logs without following (-f) to avoid hanging indefinitely
@@ -249,61 +307,66 @@ impl<'a> LifecycleOperations<'a> {
     pub fn show_logs_extended(
         &self,
         container: Option<&str>,
-        follow: bool,
-        tail: usize,
-        service: Option<&str>,
+        request: &LogsRequest,
         _config: &vm_config::config::VmConfig,
     ) -> Result<()> {
         // If service flag is set, map to container name
-        let target_container = if let Some(svc) = service {
-            self.map_service_to_container(svc)?
+        let target_container = if let Some(svc) = &request.service {
+            if svc == "mock" {
+                Self::mock_container_name(&self.resolve_target_container(container)?)
+            } else {
+                self.map_service_to_container(svc)?
+            }
         } else {
             self.resolve_target_container(container)?
         };
 
-        // Build docker logs command
-        let mut args = vec!["logs"];
-
-        if follow {
-            args.push("--follow");
-        }
-
-        args.push("--tail");
-        let tail_str = tail.to_string();
-        args.push(&tail_str);
-
-        args.push("--timestamps");
-        args.push(&target_container);
+        let args = logs_args(request, &target_container);
+        let arg_refs: Vec<&str> = args.iter().map(String::as_str).collect();
 
         // Show helpful header
-        if follow {
+        if request.follow {
             vm_println!(
                 "📜 Following logs for '{}' (press Ctrl+C to stop)",
                 target_container
             );
             vm_println!("──────────────────────────────────────────\n");
         } else {
-            vm_println!("📜 Logs for '{}' (last {} lines)", target_container, tail);
+            vm_println!(
+                "📜 Logs for '{}' (last {} lines)",
+                target_container,
+                request.tail
+            );
             vm_println!("──────────────────────────────────────────\n");
         }
 
-        stream_command(self.executable, &args)
+        stream_command(self.executable, &arg_refs)
             .map_err(|e| VmError::Internal(format!("Failed to show logs: {e}")))
     }
 
+    /// Global container name for a service, if it runs in one
+    pub(super) fn service_container_name(service: &str) -> Option<&'static str> {
+        match service {
+            "postgresql" | "postgres" => Some("vm-postgres-global"),
+            "redis" => Some("vm-redis-global"),
+            "mongodb" | "mongo" => Some("vm-mongodb-global"),
+            "mysql" => Some("vm-mysql-global"),
+            _ => None,
+        }
+    }
+
+    /// The project's `mock` service container, next to the workspace container `main`
+    pub(super) fn mock_container_name(main: &str) -> String {
+        format!("{}-mock", main.strip_suffix("-dev").unwrap_or(main))
+    }
+
     /// Map service names to global container names
-    fn map_service_to_container(&self, service: &str) -> Result<String> {
-        let container = match service {
-            "postgresql" | "postgres" => "vm-postgres-global",
-            "redis" => "vm-redis-global",
-            "mongodb" | "mongo" => "vm-mongodb-global",
-            "mysql" => "vm-mysql-global",
-            _ => {
-                return Err(VmError::Internal(format!(
-                    "Unknown service: '{}'. Available: postgresql, redis, mongodb, mysql",
-                    service
-                )))
-            }
+    pub(super) fn map_service_to_container(&self, service: &str) -> Result<String> {
+        let Some(container) = Self::service_container_name(service) else {
+            return Err(VmError::Internal(format!(
+                "Unknown service: '{}'. Available: postgresql, redis, mongodb, mysql, mock",
+                service
+            )));
         };
 
         // Check if container exists
@@ -0,0 +1,272 @@
+//! Combined logs for a VM and its service containers
+//!
+//! `vm logs --all-services` shows the workspace container together with every
+//! enabled service container, each line prefixed with a colored name the way
+//! `docker compose logs` does. Without `--follow` the lines are merged by their
+//! timestamps; when following they are printed as they arrive.
+
+use std::io::{BufRead, BufReader, Read};
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+use std::thread;
+
+use chrono::{DateTime, FixedOffset};
+use colored::{Color, Colorize};
+use vm_config::config::VmConfig;
+use vm_core::error::{Result, VmError};
+use vm_core::vm_println;
+
+use super::LifecycleOperations;
+use crate::LogsRequest;
+
+const PREFIX_COLORS: [Color; 6] = [
+    Color::Cyan,
+    Color::Yellow,
+    Color::Green,
+    Color::Magenta,
+    Color::Blue,
+    Color::Red,
+];
+
+/// Arguments for `<engine> logs` built from the request's filters
+pub(super) fn logs_args(request: &LogsRequest, container: &str) -> Vec<String> {
+    let mut args = vec!["logs".to_string()];
+    if request.follow {
+        args.push("--follow".to_string());
+    }
+    args.extend(["--tail".to_string(), request.tail.to_string()]);
+    if let Some(since) = &request.since {
+        args.extend(["--since".to_string(), since.clone()]);
+    }
+    if let Some(until) = &request.until {
+        args.extend(["--until".to_string(), until.clone()]);
+    }
+    args.extend(["--timestamps".to_string(), container.to_string()]);
+    args
+}
+
+/// Leading RFC 3339 timestamp added by `--timestamps`
+fn timestamp(line: &str) -> Option<DateTime<FixedOffset>> {
+    let (stamp, _) = line.split_once(' ')?;
+    DateTime::parse_from_rfc3339(stamp).ok()
+}
+
+/// Merge each source's lines by timestamp.
+///
+/// Lines without a timestamp (continuations of multi-line output) stay after the
+/// line they follow, and ties keep each source's own order.
+fn merge_by_timestamp(sources: Vec<Vec<String>>) -> Vec<(usize, String)> {
+    let mut lines = Vec::new();
+    for (source, source_lines) in sources.into_iter().enumerate() {
+        let mut last = None;
+        for line in source_lines {
+            last = timestamp(&line).or(last);
+            lines.push((last, source, line));
+        }
+    }
+    lines.sort_by_key(|(stamp, _, _)| *stamp);
+    lines
+        .into_iter()
+        .map(|(_, source, line)| (source, line))
+        .collect()
+}
+
+/// Lines of a child stream, sent to `tx` tagged with `source`
+fn forward_lines(
+    source: usize,
+    stream: impl Read + Send + 'static,
+    tx: mpsc::Sender<(usize, String)>,
+) {
+    thread::spawn(move || {
+        for line in BufReader::new(stream)
+            .lines()
+            .map_while(std::io::Result::ok)
+        {
+            if tx.send((source, line)).is_err() {
+                break;
+            }
+        }
+    });
+}
+
+impl LifecycleOperations<'_> {
+    /// Show the workspace container's logs interleaved with its service containers
+    pub fn show_all_service_logs(
+        &self,
+        container: Option<&str>,
+        request: &LogsRequest,
+        config: &VmConfig,
+    ) -> Result<()> {
+        let main = self.resolve_target_container(container)?;
+        let mut targets = vec![(main.clone(), main.clone())];
+        if config.services.get("mock").is_some_and(|mock| mock.enabled) {
+            targets.push(("mock".to_string(), Self::mock_container_name(&main)));
+        }
+        for (name, service) in &config.services {
+            if !service.enabled || Self::service_container_name(name).is_none() {
+                continue;
+            }
+            match self.map_service_to_container(name) {
+                Ok(service_container) => targets.push((name.clone(), service_container)),
+                Err(_) => vm_println!("⚠️  Skipping '{}': its container is not running", name),
+            }
+        }
+
+        let width = targets
+            .iter()
+            .map(|(label, _)| label.len())
+            .max()
+            .unwrap_or(0);
+        let prefixes: Vec<String> = targets
+            .iter()
+            .enumerate()
+            .map(|(i, (label, _))| {
+                format!("{label:<width$} |")
+                    .color(PREFIX_COLORS[i % PREFIX_COLORS.len()])
+                    .to_string()
+            })
+            .collect();
+        let names: Vec<&str> = targets.iter().map(|(label, _)| label.as_str()).collect();
+
+        if request.follow {
+            vm_println!(
+                "📜 Following logs for {} (press Ctrl+C to stop)",
+                names.join(", ")
+            );
+            vm_println!("──────────────────────────────────────────\n");
+            return self.follow_logs(&targets, &prefixes, request);
+        }
+
+        vm_println!(
+            "📜 Logs for {} (last {} lines each)",
+            names.join(", "),
+            request.tail
+        );
+        vm_println!("──────────────────────────────────────────\n");
+        let mut sources = Vec::with_capacity(targets.len());
+        for (label, target) in &targets {
+            let output = Command::new(self.executable)
+                .args(logs_args(request, target))
+                .output()
+                .map_err(|e| {
+                    VmError::Internal(format!("Failed to read logs for '{label}': {e}"))
+                })?;
+            if !output.status.success() {
+                return Err(VmError::Internal(format!(
+                    "Failed to read logs for '{label}': {}",
+                    String::from_utf8_lossy(&output.stderr).trim()
+                )));
+            }
+            // Container stderr arrives on the logs command's stderr
+            let mut lines: Vec<String> = String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .map(str::to_string)
+                .collect();
+            lines.extend(
+                String::from_utf8_lossy(&output.stderr)
+                    .lines()
+                    .map(str::to_string),
+            );
+            sources.push(lines);
+        }
+        for (source, line) in merge_by_timestamp(sources) {
+            println!("{} {}", prefixes[source], line);
+        }
+        Ok(())
+    }
+
+    fn follow_logs(
+        &self,
+        targets: &[(String, String)],
+        prefixes: &[String],
+        request: &LogsRequest,
+    ) -> Result<()> {
+        let (tx, rx) = mpsc::channel();
+        let mut children = Vec::with_capacity(targets.len());
+        for (source, (label, target)) in targets.iter().enumerate() {
+            let mut child = Command::new(self.executable)
+                .args(logs_args(request, target))
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                .spawn()
+                .map_err(|e| {
+                    VmError::Internal(format!("Failed to follow logs for '{label}': {e}"))
+                })?;
+            if let Some(stdout) = child.stdout.take() {
+                forward_lines(source, stdout, tx.clone());
+            }
+            if let Some(stderr) = child.stderr.take() {
+                forward_lines(source, stderr, tx.clone());
+            }
+            children.push(child);
+        }
+        drop(tx);
+
+        for (source, line) in rx {
+            println!("{} {}", prefixes[source], line);
+        }
+        for mut child in children {
+            let _ = child.wait();
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn logs_args_include_filters() {
+        let request = LogsRequest {
+            follow: true,
+            tail: 20,
+            since: Some("10m".to_string()),
+            until: Some("2024-01-01T12:00:00Z".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            logs_args(&request, "web-dev"),
+            [
+                "logs",
+                "--follow",
+                "--tail",
+                "20",
+                "--since",
+                "10m",
+                "--until",
+                "2024-01-01T12:00:00Z",
+                "--timestamps",
+                "web-dev"
+            ]
+        );
+    }
+
+    #[test]
+    fn lines_merge_by_timestamp() {
+        let app = vec![
+            "2024-01-01T12:00:00Z app started".to_string(),
+            "2024-01-01T12:00:02.5Z request failed".to_string(),
+            "  at handler".to_string(),
+        ];
+        let db = vec![
+            "2024-01-01T12:00:00.500000000Z db ready".to_string(),
+            "2024-01-01T12:00:03Z checkpoint".to_string(),
+        ];
+        let merged = merge_by_timestamp(vec![app, db]);
+        let order: Vec<(usize, &str)> = merged
+            .iter()
+            .map(|(source, line)| (*source, line.as_str()))
+            .collect();
+        assert_eq!(
+            order,
+            [
+                (0, "2024-01-01T12:00:00Z app started"),
+                (1, "2024-01-01T12:00:00.500000000Z db ready"),
+                (0, "2024-01-01T12:00:02.5Z request failed"),
+                (0, "  at handler"),
+                (1, "2024-01-01T12:00:03Z checkpoint"),
+            ]
+        );
+    }
+}
@@ -3,10 +3,15 @@
 // Module declarations in dependency order
 pub mod creation;
 pub mod execution;
+pub mod features;
 pub mod health;
 pub mod helpers;
 pub mod interaction;
+pub mod logs;
+pub mod native;
 pub mod packages;
+pub mod permissions;
+pub mod plan;
 pub mod provisioning;
 pub mod status;
 
@@ -92,6 +97,7 @@ impl<'a> TempProvider for LifecycleOperations<'a> {
         let progress = ProgressReporter::new();
         let phase = progress.start_phase("Recreating container configuration");
         ProgressReporter::task(&phase, "Generating updated docker-compose.yml...");
+        self.check_temp_mount_permissions(state);
 
         let temp_config = self.prepare_temp_config()?;
         let compose_ops = ComposeOperations::new(
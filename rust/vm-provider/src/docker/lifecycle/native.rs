@@ -0,0 +1,563 @@
+//! Native provisioning engine
+//!
+//! Alternative to the Ansible playbook selected with `vm.provisioner: native`.
+//! The merged configuration (preset + vm.yaml) is turned into an ordered plan of
+//! shell steps, each run through `docker exec` as root or as the project user.
+//! Service enablement is driven by the plain-YAML manifest in
+//! `resources/services/native_services.yml`, so nothing inside the box needs
+//! Python or Ansible.
+use super::LifecycleOperations;
+use crate::common::shell::render_zshrc;
+use crate::context::ProviderContext;
+use crate::docker::UserConfig;
+use crate::progress::{format_duration, ProgressReporter, StepTimer};
+use crate::resources::NATIVE_SERVICES;
+use base64::Engine;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::process::Command;
+use vm_core::command_stream::stream_command_visible;
+use vm_core::error::{Result, VmError};
+use vm_core::{vm_println, vm_warning};
+
+const DEFAULT_NODE_VERSION: &str = "22";
+const DEFAULT_PNPM_VERSION: &str = "10.12.3";
+const DEFAULT_WORKSPACE_PATH: &str = "/workspace";
+
+/// Who a provisioning step runs as inside the container
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum StepUser {
+    Root,
+    Project,
+}
+
+/// One unit of work in a native provisioning plan
+#[derive(Debug, Clone)]
+pub(crate) struct ProvisionStep {
+    pub description: String,
+    pub user: StepUser,
+    pub script: String,
+}
+
+impl ProvisionStep {
+    fn root(description: impl Into<String>, script: impl Into<String>) -> Self {
+        Self {
+            description: description.into(),
+            user: StepUser::Root,
+            script: script.into(),
+        }
+    }
+
+    fn project(description: impl Into<String>, script: impl Into<String>) -> Self {
+        Self {
+            description: description.into(),
+            user: StepUser::Project,
+            script: script.into(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ServiceManifest {
+    services: HashMap<String, NativeService>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct NativeService {
+    /// Runs as a separate container; only client packages go into the box
+    external: bool,
+    default_port: Option<u16>,
+    packages: Vec<String>,
+    config_lines: Vec<ConfigLine>,
+    post_install: Vec<String>,
+    supervisor: Option<SupervisorProgram>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ConfigLine {
+    path: String,
+    regexp: String,
+    line: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct SupervisorProgram {
+    command: String,
+    user: Option<String>,
+}
+
+fn load_service_manifest() -> Result<ServiceManifest> {
+    serde_yaml_ng::from_str(NATIVE_SERVICES)
+        .map_err(|e| VmError::Internal(format!("Failed to parse native service manifest: {e}")))
+}
+
+/// Single-quote a value for safe interpolation into a shell script
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\"'\"'"))
+}
+
+fn shell_quote_all(values: &[String]) -> String {
+    values
+        .iter()
+        .map(|v| shell_quote(v))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+impl<'a> LifecycleOperations<'a> {
+    /// Provision a running container with the native engine
+    pub(super) fn run_native_provisioning(
+        &self,
+        container_name: &str,
+        context: &ProviderContext,
+    ) -> Result<()> {
+        let plan = self.native_plan(context)?;
+        let user_config = UserConfig::from_vm_config(self.config);
+        let home = format!("HOME=/home/{}", user_config.username);
+
+        vm_println!("🔧 Provisioning with the native engine...");
+        let reporter = ProgressReporter::new();
+        let mut timer = StepTimer::new(format!("{container_name}:native"));
+        for step in &plan {
+            let mut args = vec!["exec", "-u"];
+            match step.user {
+                StepUser::Root => args.push("root"),
+                StepUser::Project => {
+                    args.extend([user_config.username.as_str(), "-e", home.as_str()])
+                }
+            }
+            args.extend([container_name, "bash", "-c", step.script.as_str()]);
+
+            timer.start_step(&step.description);
+            if context.is_verbose() {
+                vm_println!("  ▶ {}", step.description);
+                stream_command_visible(self.executable, &args)
+                    .map_err(|e| Self::native_step_error(step, &e.to_string()))?;
+            } else {
+                let spinner = reporter.start_step(&step.description);
+                spinner.set_message(timer.status_line());
+                let output = Command::new(self.executable).args(&args).output();
+                spinner.finish_and_clear();
+                let output = output?;
+                if !output.status.success() {
+                    let mut log = String::from_utf8_lossy(&output.stdout).into_owned();
+                    log.push_str(&String::from_utf8_lossy(&output.stderr));
+                    return Err(Self::native_step_error(step, log.trim()));
+                }
+            }
+            let duration = timer.finish_step().unwrap_or_default();
+            vm_println!("  ✓ {} ({})", step.description, format_duration(duration));
+        }
+        timer.save();
+        vm_println!("  Provisioned in {}", format_duration(timer.elapsed()));
+
+        Ok(())
+    }
+
+    fn native_step_error(step: &ProvisionStep, detail: &str) -> VmError {
+        VmError::Internal(format!(
+            "Native provisioning failed at '{}'. Run `vm create --verbose` for the full output, \
+             or set `vm.provisioner: ansible` to use the playbook instead.\n{detail}",
+            step.description
+        ))
+    }
+
+    /// Build the ordered list of provisioning steps for this configuration
+    pub(crate) fn native_plan(&self, context: &ProviderContext) -> Result<Vec<ProvisionStep>> {
+        let manifest = load_service_manifest()?;
+        let user_config = UserConfig::from_vm_config(self.config);
+        let config = self.config;
+        let mut steps = Vec::new();
+
+        // System configuration
+        let project_name = config
+            .project
+            .as_ref()
+            .and_then(|p| p.name.as_deref())
+            .unwrap_or("project");
+        let hostname = config
+            .project
+            .as_ref()
+            .and_then(|p| p.hostname.clone())
+            .unwrap_or_else(|| format!("dev.{project_name}.local"));
+        let short_hostname = hostname.split('.').next().unwrap_or(&hostname);
+        steps.push(ProvisionStep::root(
+            "Configure hostname",
+            format!(
+                "grep -v '^127.0.1.1' /etc/hosts > /tmp/hosts.new\n\
+                 echo {} >> /tmp/hosts.new\n\
+                 cat /tmp/hosts.new > /etc/hosts",
+                shell_quote(&format!("127.0.1.1 {hostname} {short_hostname}"))
+            ),
+        ));
+
+        // Snapshots already carry the base system setup
+        if !context.is_snapshot {
+            let timezone = config
+                .vm
+                .as_ref()
+                .and_then(|vm| vm.timezone.as_deref())
+                .unwrap_or("UTC");
+            steps.push(ProvisionStep::root(
+                "Set timezone",
+                format!(
+                    "tz={}\n\
+                     if [ -f \"/usr/share/zoneinfo/$tz\" ]; then\n\
+                       ln -sf \"/usr/share/zoneinfo/$tz\" /etc/localtime\n\
+                       echo \"$tz\" > /etc/timezone\n\
+                     fi",
+                    shell_quote(timezone)
+                ),
+            ));
+        }
+
+        // Packages: one apt transaction for vm.yaml packages, toolchain
+        // prerequisites and service packages
+        let enabled_services: Vec<(&String, &NativeService)> = config
+            .services
+            .iter()
+            .filter(|(_, service)| service.enabled)
+            .filter_map(|(name, _)| match manifest.services.get(name) {
+                Some(definition) => Some((name, definition)),
+                None => {
+                    vm_warning!(
+                        "Service '{}' is not supported by the native provisioner; set `vm.provisioner: ansible` to install it",
+                        name
+                    );
+                    None
+                }
+            })
+            .collect();
+
+        let mut apt_packages = config.apt_packages.clone();
+        if !config.pip_packages.is_empty() {
+            apt_packages.extend(["pipx".to_string(), "python3-pip".to_string()]);
+        }
+        for (_, definition) in &enabled_services {
+            apt_packages.extend(definition.packages.iter().cloned());
+        }
+        let mut seen = std::collections::HashSet::new();
+        apt_packages.retain(|pkg| seen.insert(pkg.clone()));
+
+        if !apt_packages.is_empty() {
+            let packages = shell_quote_all(&apt_packages);
+            let script = if context.refresh_packages {
+                format!(
+                    "export DEBIAN_FRONTEND=noninteractive\n\
+                     apt-get update -qq\n\
+                     apt-get install -y --reinstall {packages}"
+                )
+            } else {
+                format!(
+                    "export DEBIAN_FRONTEND=noninteractive\n\
+                     missing=\"\"\n\
+                     for pkg in {packages}; do\n\
+                       dpkg -s \"$pkg\" >/dev/null 2>&1 || missing=\"$missing $pkg\"\n\
+                     done\n\
+                     if [ -n \"$missing\" ]; then\n\
+                       apt-get update -qq\n\
+                       apt-get install -y $missing\n\
+                     fi"
+                )
+            };
+            steps.push(ProvisionStep::root("Install system packages", script));
+        }
+
+        // Language toolchains (packages themselves are installed at image build time)
+        let ai_tools = config.host_sync.as_ref().and_then(|h| h.ai_tools.as_ref());
+        let nodejs_required = !config.npm_packages.is_empty()
+            || ai_tools.is_some_and(|ai| ai.is_gemini_enabled() || ai.is_codex_enabled());
+        if nodejs_required && !context.is_snapshot {
+            let versions = config.versions.clone().unwrap_or_default();
+            let node = versions
+                .node
+                .unwrap_or_else(|| DEFAULT_NODE_VERSION.to_string());
+            let npm = versions.npm.unwrap_or_else(|| "latest".to_string());
+            let pnpm = versions
+                .pnpm
+                .unwrap_or_else(|| DEFAULT_PNPM_VERSION.to_string());
+            steps.push(ProvisionStep::project(
+                "Configure Node.js toolchain",
+                format!(
+                    "export NVM_DIR=\"$HOME/.nvm\"\n\
+                     . \"$NVM_DIR/nvm.sh\"\n\
+                     nvm install {node}\n\
+                     nvm alias default {node}\n\
+                     npm install -g npm@{npm}\n\
+                     corepack enable || true\n\
+                     corepack prepare pnpm@{pnpm} --activate || npm install -g pnpm@{pnpm}",
+                    node = shell_quote(&node),
+                    npm = shell_quote(&npm),
+                    pnpm = shell_quote(&pnpm),
+                ),
+            ));
+        }
+
+        if !config.pip_packages.is_empty() {
+            steps.push(ProvisionStep::project(
+                "Configure pipx",
+                "pipx ensurepath >/dev/null",
+            ));
+        }
+
+        if !config.cargo_packages.is_empty() {
+            steps.push(ProvisionStep::project(
+                "Configure Rust toolchain",
+                "if [ ! -x \"$HOME/.cargo/bin/cargo\" ]; then\n\
+                   curl --proto '=https' --tlsv1.2 -sSf https://sh.rustup.rs | \
+                 sh -s -- -y --default-toolchain stable --no-modify-path\n\
+                 fi",
+            ));
+        }
+
+        if let Some(ai) = ai_tools {
+            if ai.is_claude_enabled() {
+                steps.push(ProvisionStep::project(
+                    "Install Claude Code CLI",
+                    "mkdir -p \"$HOME/.local/bin\"\n\
+                     export PATH=\"$HOME/.local/bin:$PATH\"\n\
+                     command -v claude >/dev/null 2>&1 || curl -fsSL https://claude.ai/install.sh | bash",
+                ));
+            }
+            for (enabled, name, package) in [
+                (ai.is_gemini_enabled(), "Gemini", "@google/gemini-cli"),
+                (ai.is_codex_enabled(), "Codex", "@openai/codex"),
+            ] {
+                if enabled {
+                    steps.push(ProvisionStep::project(
+                        format!("Install {name} CLI"),
+                        format!(
+                            "export NVM_DIR=\"$HOME/.nvm\"\n\
+                             [ -s \"$NVM_DIR/nvm.sh\" ] && . \"$NVM_DIR/nvm.sh\"\n\
+                             npm list -g {package} >/dev/null 2>&1 || npm install -g {package}"
+                        ),
+                    ));
+                }
+            }
+        }
+
+        // Services
+        for (name, definition) in enabled_services {
+            if definition.external {
+                continue;
+            }
+            if let Some(script) =
+                Self::service_script(name, definition, config.services[name].port, &user_config)
+            {
+                steps.push(ProvisionStep::root(format!("Configure {name}"), script));
+            }
+        }
+
+        // Shell
+        let workspace_path = config
+            .project
+            .as_ref()
+            .and_then(|p| p.workspace_path.as_deref())
+            .unwrap_or(DEFAULT_WORKSPACE_PATH);
+        let zshrc = render_zshrc(config, workspace_path, "🐳")?;
+        let zshrc_b64 = base64::engine::general_purpose::STANDARD.encode(zshrc);
+        let home = format!("/home/{}", user_config.username);
+        let mut shell_script = format!(
+            "echo {zshrc_b64} | base64 -d > {home}/.zshrc\n\
+             chown {uid}:{gid} {home}/.zshrc\n\
+             chmod 0644 {home}/.zshrc",
+            home = shell_quote(&home),
+            uid = user_config.uid,
+            gid = user_config.gid,
+        );
+        if !context.is_snapshot {
+            shell_script.push_str(&format!(
+                "\nusermod -s /usr/bin/zsh {} || true",
+                shell_quote(&user_config.username)
+            ));
+        }
+        steps.push(ProvisionStep::root("Configure shell", shell_script));
+
+        steps.push(ProvisionStep::project(
+            "Configure Git safe directory",
+            format!(
+                "git config --global --get-all safe.directory 2>/dev/null | grep -Fxq {path} || \
+                 git config --global --add safe.directory {path}",
+                path = shell_quote(workspace_path)
+            ),
+        ));
+
+        Ok(steps)
+    }
+
+    /// Install-time configuration for one service from the native manifest
+    fn service_script(
+        name: &str,
+        definition: &NativeService,
+        port: Option<u16>,
+        user_config: &UserConfig,
+    ) -> Option<String> {
+        let port = port
+            .or(definition.default_port)
+            .map(|p| p.to_string())
+            .unwrap_or_default();
+        let substitute = |value: &str| {
+            value
+                .replace("{port}", &port)
+                .replace("{user}", &user_config.username)
+        };
+
+        let mut lines = Vec::new();
+        for config_line in &definition.config_lines {
+            // Same semantics as Ansible's lineinfile: replace matching lines, else append
+            lines.push(format!(
+                "if [ -f {path} ]; then\n\
+                   awk -v re={re} -v line={line} 'BEGIN {{ done = 0 }} $0 ~ re {{ print line; done = 1; next }} {{ print }} END {{ if (!done) print line }}' {path} > /tmp/vm-lineinfile\n\
+                   cat /tmp/vm-lineinfile > {path}\n\
+                   rm -f /tmp/vm-lineinfile\n\
+                 fi",
+                path = shell_quote(&config_line.path),
+                re = shell_quote(&config_line.regexp),
+                line = shell_quote(&substitute(&config_line.line)),
+            ));
+        }
+        for command in &definition.post_install {
+            lines.push(format!("{} || true", substitute(command)));
+        }
+        if let Some(program) = &definition.supervisor {
+            let conf = format!(
+                "[program:{name}]\n\
+                 command={command}\n\
+                 user={user}\n\
+                 autostart=true\n\
+                 autorestart=true\n\
+                 stderr_logfile=/dev/stderr\n\
+                 stderr_logfile_maxbytes=0\n\
+                 stdout_logfile=/dev/stdout\n\
+                 stdout_logfile_maxbytes=0\n",
+                command = substitute(&program.command),
+                user = program.user.as_deref().unwrap_or("root"),
+            );
+            lines.push(format!(
+                "mkdir -p /etc/supervisor/conf.d\n\
+                 echo {} | base64 -d > /etc/supervisor/conf.d/{name}.conf\n\
+                 if pgrep supervisord >/dev/null; then\n\
+                   supervisorctl reread && supervisorctl update\n\
+                 fi",
+                base64::engine::general_purpose::STANDARD.encode(conf)
+            ));
+        }
+
+        if lines.is_empty() {
+            None
+        } else {
+            Some(lines.join("\n"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use vm_config::config::{ServiceConfig, VmConfig, VmSettings};
+
+    fn plan_for(config: &VmConfig, context: &ProviderContext) -> Vec<ProvisionStep> {
+        let dir = PathBuf::from("/tmp");
+        LifecycleOperations::new(config, &dir, &dir, "docker")
+            .native_plan(context)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_native_service_manifest_parses() {
+        let manifest = load_service_manifest().unwrap();
+        assert!(manifest.services["postgresql"].external);
+        assert_eq!(manifest.services["mysql"].default_port, Some(3306));
+    }
+
+    #[test]
+    fn test_native_plan_batches_apt_packages_with_service_packages() {
+        let mut config = VmConfig {
+            apt_packages: vec!["jq".to_string(), "xvfb".to_string()],
+            vm: Some(VmSettings {
+                provisioner: Some("native".to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        config.services.insert(
+            "headless_browser".to_string(),
+            ServiceConfig {
+                enabled: true,
+                ..Default::default()
+            },
+        );
+
+        let plan = plan_for(&config, &ProviderContext::default());
+        let apt: Vec<_> = plan
+            .iter()
+            .filter(|s| s.description == "Install system packages")
+            .collect();
+        assert_eq!(apt.len(), 1);
+        assert_eq!(apt[0].user, StepUser::Root);
+        assert!(apt[0]
+            .script
+            .contains("for pkg in 'jq' 'xvfb' 'chromium-browser'; do"));
+
+        let service = plan
+            .iter()
+            .find(|s| s.description == "Configure headless_browser")
+            .unwrap();
+        assert!(service
+            .script
+            .contains("/etc/supervisor/conf.d/headless_browser.conf"));
+    }
+
+    #[test]
+    fn test_native_plan_skips_base_setup_for_snapshots() {
+        let config = VmConfig {
+            npm_packages: vec!["prettier".to_string()],
+            ..Default::default()
+        };
+
+        let fresh = plan_for(&config, &ProviderContext::default());
+        assert!(fresh.iter().any(|s| s.description == "Set timezone"));
+        assert!(
+            fresh
+                .iter()
+                .any(|s| s.description == "Configure Node.js toolchain"
+                    && s.user == StepUser::Project)
+        );
+
+        let snapshot = plan_for(&config, &ProviderContext::default().with_snapshot(true));
+        assert!(!snapshot.iter().any(|s| s.description == "Set timezone"));
+        assert!(!snapshot
+            .iter()
+            .any(|s| s.description == "Configure Node.js toolchain"));
+    }
+
+    #[test]
+    fn test_service_script_substitutes_port_and_user() {
+        let manifest = load_service_manifest().unwrap();
+        let user_config = UserConfig {
+            uid: 1000,
+            gid: 1000,
+            username: "dev".to_string(),
+        };
+
+        let mysql = LifecycleOperations::service_script(
+            "mysql",
+            &manifest.services["mysql"],
+            Some(3307),
+            &user_config,
+        )
+        .unwrap();
+        assert!(mysql.contains("-v line='port = 3307'"));
+
+        let docker = LifecycleOperations::service_script(
+            "docker",
+            &manifest.services["docker"],
+            None,
+            &user_config,
+        )
+        .unwrap();
+        assert!(docker.contains("usermod -a -G docker dev || true"));
+    }
+}
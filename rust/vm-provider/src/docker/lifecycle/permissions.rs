@@ -0,0 +1,250 @@
+//! Host directory permission preflight for bind mounts.
+//!
+//! Bind mounts keep host ownership, so a workspace owned by another UID shows up
+//! read-only (or unreadable) for the VM user and fails later with EACCES. These
+//! checks compare each mount's owner and mode bits against the UID/GID from
+//! [`UserConfig`] before the container is created, and explain how to fix a
+//! mismatch. Only Linux is checked: Docker Desktop's file sharing presents host
+//! files as owned by the container user.
+
+use std::path::{Path, PathBuf};
+
+use super::LifecycleOperations;
+use crate::docker::UserConfig;
+use crate::{MountPermission, TempVmState};
+
+/// A host path the container will bind mount
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct HostMount {
+    pub path: PathBuf,
+    pub writable: bool,
+}
+
+/// Ownership and mode of a host path
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct HostOwnership {
+    pub uid: u32,
+    pub gid: u32,
+    pub mode: u32,
+    pub is_dir: bool,
+}
+
+/// What the container user is missing on a mount
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct MountProblem {
+    pub path: PathBuf,
+    pub owner: (u32, u32),
+    pub missing: &'static str,
+}
+
+/// Check the owner/group/other permission bits that apply to `uid`/`gid`.
+/// Root bypasses mode bits, and directories also need the execute bit to be entered.
+pub(super) fn missing_access(
+    ownership: HostOwnership,
+    uid: u32,
+    gid: u32,
+    writable: bool,
+) -> Option<&'static str> {
+    if uid == 0 {
+        return None;
+    }
+    let shift = if ownership.uid == uid {
+        6
+    } else if ownership.gid == gid {
+        3
+    } else {
+        0
+    };
+    let bits = (ownership.mode >> shift) & 0o7;
+    let can_read = bits & 0o4 != 0 && (!ownership.is_dir || bits & 0o1 != 0);
+    let can_write = bits & 0o2 != 0;
+
+    match (can_read, writable && !can_write) {
+        (false, _) => Some("read"),
+        (true, true) => Some("write"),
+        (true, false) => None,
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn host_ownership(path: &Path) -> Option<HostOwnership> {
+    use std::os::unix::fs::MetadataExt;
+    let metadata = std::fs::metadata(path).ok()?;
+    Some(HostOwnership {
+        uid: metadata.uid(),
+        gid: metadata.gid(),
+        mode: metadata.mode(),
+        is_dir: metadata.is_dir(),
+    })
+}
+
+#[cfg(not(target_os = "linux"))]
+fn host_ownership(_path: &Path) -> Option<HostOwnership> {
+    None
+}
+
+/// Mounts the container user can't use; missing paths are skipped
+pub(super) fn find_problems(mounts: &[HostMount], user: &UserConfig) -> Vec<MountProblem> {
+    mounts
+        .iter()
+        .filter_map(|mount| {
+            let ownership = host_ownership(&mount.path)?;
+            let missing = missing_access(ownership, user.uid, user.gid, mount.writable)?;
+            Some(MountProblem {
+                path: mount.path.clone(),
+                owner: (ownership.uid, ownership.gid),
+                missing,
+            })
+        })
+        .collect()
+}
+
+impl<'a> LifecycleOperations<'a> {
+    /// Host paths mounted into the dev container: the workspace and synced dotfiles
+    fn project_mounts(&self, ephemeral: bool) -> Vec<HostMount> {
+        let mut mounts = vec![HostMount {
+            path: self.project_dir.clone(),
+            writable: !ephemeral,
+        }];
+        let home = crate::user_home::resolve_home_dir();
+        if let (Some(host_sync), Some(home)) = (self.config.host_sync.as_ref(), home) {
+            for dotfile in &host_sync.dotfiles {
+                let path = match dotfile.strip_prefix("~/") {
+                    Some(relative) => home.join(relative),
+                    None if dotfile == "~" => home.clone(),
+                    None => PathBuf::from(dotfile),
+                };
+                mounts.push(HostMount {
+                    path,
+                    writable: false,
+                });
+            }
+        }
+        mounts
+    }
+
+    /// Warn before creating the container if the VM user can't use a mounted directory
+    pub(super) fn check_mount_permissions(&self, ephemeral: bool) {
+        self.report_mount_problems(&self.project_mounts(ephemeral));
+    }
+
+    /// Same check for the mounts of a temporary VM
+    pub(super) fn check_temp_mount_permissions(&self, state: &TempVmState) {
+        let mounts: Vec<HostMount> = state
+            .mounts
+            .iter()
+            .map(|mount| HostMount {
+                path: mount.source.clone(),
+                writable: mount.permissions == MountPermission::ReadWrite,
+            })
+            .collect();
+        self.report_mount_problems(&mounts);
+    }
+
+    fn report_mount_problems(&self, mounts: &[HostMount]) {
+        let user = UserConfig::from_vm_config(self.config);
+        let problems = find_problems(mounts, &user);
+        if problems.is_empty() {
+            return;
+        }
+        let remapped = self.uses_userns_remap();
+
+        for problem in problems {
+            let path = problem.path.display();
+            let (owner_uid, owner_gid) = problem.owner;
+            let mut details = vec![
+                format!(
+                    "{path} is owned by {owner_uid}:{owner_gid}, but the VM user '{}' runs as {}:{}",
+                    user.username, user.uid, user.gid
+                ),
+                format!(
+                    "Give the VM user access: sudo chown -R {}:{} {path}",
+                    user.uid, user.gid
+                ),
+            ];
+            if owner_uid != 0 {
+                details.push(format!(
+                    "Or run the VM user as the owner: vm config set vm.uid {owner_uid} && vm config set vm.gid {owner_gid}"
+                ));
+            }
+            if remapped {
+                details.push(
+                    "Docker remaps user IDs (userns-remap), so host ownership is shifted by your /etc/subuid range; \
+                     chown to the remapped IDs or exclude this daemon from remapping"
+                        .to_string(),
+                );
+            }
+            vm_core::vm_error_with_details!(
+                format!("The VM will not be able to {} {path}", problem.missing),
+                &details
+            );
+        }
+    }
+
+    /// Whether the daemon runs containers in a remapped user namespace
+    fn uses_userns_remap(&self) -> bool {
+        std::process::Command::new(self.executable)
+            .args(["info", "--format", "{{json .SecurityOptions}}"])
+            .output()
+            .map(|output| String::from_utf8_lossy(&output.stdout).contains("name=userns"))
+            .unwrap_or(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn owned(uid: u32, gid: u32, mode: u32) -> HostOwnership {
+        HostOwnership {
+            uid,
+            gid,
+            mode,
+            is_dir: true,
+        }
+    }
+
+    #[test]
+    fn owner_group_and_other_bits_apply_in_order() {
+        // Owner with rwx
+        assert_eq!(
+            missing_access(owned(1000, 1000, 0o755), 1000, 1000, true),
+            None
+        );
+        // Same group, group bits r-x only
+        assert_eq!(
+            missing_access(owned(1001, 1000, 0o755), 1000, 1000, true),
+            Some("write")
+        );
+        assert_eq!(
+            missing_access(owned(1001, 1000, 0o755), 1000, 1000, false),
+            None
+        );
+        // Stranger on a private directory
+        assert_eq!(
+            missing_access(owned(1001, 1001, 0o700), 1000, 1000, false),
+            Some("read")
+        );
+        // Directories need the execute bit to be entered
+        assert_eq!(
+            missing_access(owned(1001, 1001, 0o704), 1000, 1000, false),
+            Some("read")
+        );
+        // Root ignores mode bits
+        assert_eq!(missing_access(owned(1001, 1001, 0o700), 0, 0, true), None);
+    }
+
+    #[test]
+    fn missing_paths_are_skipped() {
+        let user = UserConfig {
+            uid: 1000,
+            gid: 1000,
+            username: "developer".to_string(),
+        };
+        let mounts = [HostMount {
+            path: PathBuf::from("/nonexistent/vm-permission-check"),
+            writable: true,
+        }];
+        assert!(find_problems(&mounts, &user).is_empty());
+    }
+}
@@ -0,0 +1,159 @@
+//! Dry-run rendering of lifecycle actions
+//!
+//! Renders the same docker-compose.yml and Dockerfile that create/start would
+//! write, and lists the commands each action would run, without touching the
+//! build context or any containers.
+use std::path::Path;
+
+use super::LifecycleOperations;
+use crate::{
+    context::ProviderContext,
+    docker::{build::BuildOperations, compose::ComposeOperations, ComposeCommand, DockerOps},
+    ExecutionPlan, PlanAction,
+};
+use vm_core::error::Result;
+
+impl LifecycleOperations<'_> {
+    /// Render the files and commands `action` would use
+    pub fn plan(
+        &self,
+        action: PlanAction,
+        container: Option<&str>,
+        context: &ProviderContext,
+    ) -> Result<ExecutionPlan> {
+        match action {
+            PlanAction::Create => self.plan_create(container, context),
+            PlanAction::Start => self.plan_start(container, context),
+            PlanAction::Stop => {
+                let target = self.resolve_target_container(container)?;
+                Ok(ExecutionPlan {
+                    files: Vec::new(),
+                    commands: vec![self.command(["stop", "-t", "1", &target])],
+                })
+            }
+            PlanAction::Destroy => self.plan_destroy(container, context),
+        }
+    }
+
+    fn plan_create(
+        &self,
+        instance_name: Option<&str>,
+        context: &ProviderContext,
+    ) -> Result<ExecutionPlan> {
+        let config = self.prepare_config_for_build()?;
+        let build_ops = BuildOperations::new(&config, self.temp_dir, self.executable);
+        let compose_ops =
+            ComposeOperations::new(&config, self.temp_dir, self.project_dir, self.executable);
+        let build_context = build_ops.build_context_dir();
+        let compose_path = self.temp_dir.join("docker-compose.yml");
+
+        let compose = match instance_name {
+            Some(name) => {
+                compose_ops.render_docker_compose_with_instance(&build_context, name, context)?
+            }
+            None => compose_ops.render_docker_compose(&build_context, context)?,
+        };
+        let files = vec![
+            (compose_path.clone(), compose),
+            (
+                build_context.join("Dockerfile.generated"),
+                build_ops.render_generated_dockerfile()?,
+            ),
+        ];
+
+        let base_image = build_ops.planned_base_image()?;
+        let mut build = self.compose_command(&compose_path, "build", &[])?;
+        build.extend(build_ops.gather_build_args(&base_image));
+        let commands = vec![build, self.compose_command(&compose_path, "up", &["-d"])?];
+
+        Ok(ExecutionPlan { files, commands })
+    }
+
+    fn plan_start(
+        &self,
+        container: Option<&str>,
+        context: &ProviderContext,
+    ) -> Result<ExecutionPlan> {
+        let target = self.resolve_target_container(container)?;
+        let compose_ops = ComposeOperations::new(
+            self.config,
+            self.temp_dir,
+            self.project_dir,
+            self.executable,
+        );
+        let build_context =
+            BuildOperations::new(self.config, self.temp_dir, self.executable).build_context_dir();
+        let compose_path = self.temp_dir.join("docker-compose.yml");
+
+        let compose = match compose_ops.instance_name_from_container(&target) {
+            Some(name) => {
+                compose_ops.render_docker_compose_with_instance(&build_context, &name, context)?
+            }
+            None => compose_ops.render_docker_compose(&build_context, context)?,
+        };
+
+        // An existing dev container is started directly, along with its service containers
+        let commands =
+            if DockerOps::container_exists(Some(self.executable), &target).unwrap_or(false) {
+                compose_ops
+                    .get_expected_service_containers()
+                    .iter()
+                    .chain(std::iter::once(&target))
+                    .map(|name| self.command(["start", name.as_str()]))
+                    .collect()
+            } else {
+                vec![self.compose_command(&compose_path, "up", &["-d"])?]
+            };
+
+        Ok(ExecutionPlan {
+            files: vec![(compose_path, compose)],
+            commands,
+        })
+    }
+
+    fn plan_destroy(
+        &self,
+        container: Option<&str>,
+        context: &ProviderContext,
+    ) -> Result<ExecutionPlan> {
+        let target = self.resolve_target_container(container)?;
+        let mut commands = vec![self.command(["rm", "-f", &target])];
+        if !context.preserve_services {
+            let compose_ops = ComposeOperations::new(
+                self.config,
+                self.temp_dir,
+                self.project_dir,
+                self.executable,
+            );
+            for service in compose_ops.get_expected_service_containers() {
+                commands.push(self.command(["rm", "-f", &service]));
+            }
+        }
+        Ok(ExecutionPlan {
+            files: Vec::new(),
+            commands,
+        })
+    }
+
+    fn command<const N: usize>(&self, args: [&str; N]) -> Vec<String> {
+        std::iter::once(self.executable)
+            .chain(args)
+            .map(str::to_string)
+            .collect()
+    }
+
+    fn compose_command(
+        &self,
+        compose_path: &Path,
+        subcommand: &str,
+        extra_args: &[&str],
+    ) -> Result<Vec<String>> {
+        let mut command = vec![self.executable.to_string()];
+        command.extend(ComposeCommand::build_args(
+            compose_path,
+            subcommand,
+            extra_args,
+        )?);
+        Ok(command)
+    }
+}
@@ -149,10 +149,9 @@ impl<'a> LifecycleOperations<'a> {
         let parser = if context.is_verbose() {
             None
         } else {
-            Some(
-                Box::new(AnsibleParserAdapter(AnsibleProgressParser::new(false)))
-                    as Box<dyn CoreProgressParser>,
-            )
+            let parser =
+                AnsibleProgressParser::new(false).with_timing(format!("{container_name}:ansible"));
+            Some(Box::new(AnsibleParserAdapter(parser)) as Box<dyn CoreProgressParser>)
         };
 
         // Allow timeout override via environment variable for debugging
@@ -211,6 +210,20 @@ impl<'a> LifecycleOperations<'a> {
         Ok(())
     }
 
+    /// Run the provisioning engine selected by `vm.provisioner`
+    fn run_provisioning(&self, container_name: &str, context: &ProviderContext) -> Result<()> {
+        let native = self
+            .config
+            .vm
+            .as_ref()
+            .is_some_and(|vm| vm.uses_native_provisioner());
+        if native {
+            self.run_native_provisioning(container_name, context)
+        } else {
+            Self::run_ansible_provisioning(self.executable, container_name, context)
+        }
+    }
+
     /// Wait for container to become ready with exponential backoff
     async fn wait_for_container_ready_async(&self, container_name: &str) -> Result<()> {
         use tokio::time::{sleep, Duration, Instant};
@@ -277,6 +290,12 @@ impl<'a> LifecycleOperations<'a> {
             )));
         }
 
+        if self.is_ephemeral(container) {
+            return Err(VmError::Internal(format!(
+                "Container {target_container} is ephemeral; its root filesystem is read-only, so it cannot be provisioned"
+            )));
+        }
+
         self.provision_container_with_context(&context)
     }
 
@@ -301,7 +320,8 @@ impl<'a> LifecycleOperations<'a> {
 
         self.prepare_and_copy_config(&container_name)?;
 
-        Self::run_ansible_provisioning(self.executable, &container_name, context)
+        self.run_provisioning(&container_name, context)?;
+        self.install_features(&container_name, context)
     }
 
     /// Provision container with custom instance name and context
@@ -329,6 +349,7 @@ impl<'a> LifecycleOperations<'a> {
 
         self.prepare_and_copy_config(&container_name)?;
 
-        Self::run_ansible_provisioning(self.executable, &container_name, context)
+        self.run_provisioning(&container_name, context)?;
+        self.install_features(&container_name, context)
     }
 }
@@ -3,8 +3,39 @@ use super::LifecycleOperations;
 use crate::{docker::command::DockerCommand, ResourceUsage, ServiceStatus, VmStatusReport};
 use tracing::info;
 use vm_core::error::{Result, VmError};
+use vm_core::labels::PortLabel;
 
 impl<'a> LifecycleOperations<'a> {
+    /// Read the `com.vm.port.<host port>` labels recorded on the container, sorted by host port
+    pub fn port_labels(&self, container: Option<&str>) -> Result<Vec<PortLabel>> {
+        let target_container = self.resolve_target_container(container)?;
+        let output = DockerCommand::new(Some(self.executable))
+            .subcommand("inspect")
+            .arg("--format")
+            .arg("{{json .Config.Labels}}")
+            .arg(&target_container)
+            .execute_raw()
+            .map_err(|e| {
+                VmError::Internal(format!(
+                    "Failed to inspect container '{target_container}': {e}"
+                ))
+            })?;
+        if !output.status.success() {
+            return Err(VmError::NotFound(format!(
+                "Container '{target_container}' does not exist. Create it with: vm create"
+            )));
+        }
+
+        let labels: std::collections::HashMap<String, String> =
+            serde_json::from_slice(&output.stdout).unwrap_or_default();
+        let mut ports: Vec<_> = labels
+            .iter()
+            .filter_map(|(key, value)| PortLabel::parse(key, value))
+            .collect();
+        ports.sort_by_key(|label| label.host);
+        Ok(ports)
+    }
+
     #[must_use = "container listing results should be handled"]
     pub fn list_containers(&self) -> Result<()> {
         self.list_containers_with_stats()
@@ -1,11 +1,13 @@
 // Docker provider implementation split into logical modules
 
 pub mod build;
+pub mod builder;
 pub mod command;
 
 #[cfg(test)]
 mod build_tests;
 pub mod compose;
+pub mod generated;
 pub mod host_packages;
 pub mod lifecycle;
 
@@ -22,11 +24,14 @@ use std::sync::OnceLock;
 
 // External crates
 use tera::Tera;
-use uuid::Uuid;
 use vm_core::error::{Result, VmError};
 
 // Internal imports
-use crate::{context::ProviderContext, preflight, Provider, TempProvider, VmStatusReport};
+use crate::common::labels::{AdoptedContainer, AdoptionRegistry};
+use crate::{
+    context::ProviderContext, preflight, ExecutionPlan, PlanAction, Provider, ResourceLimits,
+    TempProvider, VmStatusReport,
+};
 use vm_config::config::VmConfig;
 use vm_core::command_stream::is_tool_installed;
 use vm_messages::messages::MESSAGES;
@@ -120,7 +125,7 @@ impl ComposeCommand {
 pub struct DockerProvider {
     config: VmConfig,
     _project_dir: PathBuf, // The root of the user's project
-    temp_dir: PathBuf, // Persistent project-specific directory for generated files like docker-compose.yml
+    temp_dir: PathBuf, // Persistent ~/.vm/projects/<name>-<hash> directory for generated files like docker-compose.yml
     executable: String,
 }
 
@@ -137,18 +142,11 @@ impl DockerProvider {
 
         let project_dir = std::env::current_dir()?;
 
-        // Create a unique instance-specific directory for docker-compose files
-        // Using UUID prevents stale config reuse and concurrent instance collisions
-        let project_name = config
-            .project
-            .as_ref()
-            .and_then(|p| p.name.as_deref())
-            .unwrap_or("vm-project");
-
-        let instance_id = Uuid::new_v4();
-        let temp_dir = std::env::temp_dir().join(format!("vm-{}-{}", project_name, instance_id));
+        // Generated files outlive this process so compose keeps working after a
+        // reboot or a crash; `vm create`/`vm destroy` hold the project lock
+        let temp_dir = generated::project_dir(&config)?;
         fs::create_dir_all(&temp_dir)
-            .map_err(|e| VmError::Internal(format!("Failed to create instance directory: {e}")))?;
+            .map_err(|e| VmError::Internal(format!("Failed to create project directory: {e}")))?;
 
         Ok(Self {
             config,
@@ -167,6 +165,45 @@ impl DockerProvider {
             &self.executable,
         )
     }
+
+    /// Look up an adopted container, skipping it if it has since been removed
+    fn inspect_adopted(
+        &self,
+        name: &str,
+        adopted: &AdoptedContainer,
+    ) -> Result<Option<crate::InstanceInfo>> {
+        let output = Command::new(&self.executable)
+            .args([
+                "inspect",
+                "--type",
+                "container",
+                "--format",
+                "{{.Id}}\t{{.State.Status}}\t{{.Created}}",
+                name,
+            ])
+            .output()
+            .map_err(|e| VmError::Internal(format!("Failed to inspect '{name}': {e}")))?;
+        if !output.status.success() {
+            return Ok(None);
+        }
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let fields: Vec<&str> = stdout.trim().split('\t').collect();
+        let id = fields.first().copied().unwrap_or(adopted.id.as_str());
+        // Same container name but a different container: the adopted one is gone
+        if !id.starts_with(&adopted.id) && !adopted.id.starts_with(id) {
+            return Ok(None);
+        }
+
+        Ok(Some(crate::common::instance::create_docker_instance_info(
+            name,
+            &id.chars().take(12).collect::<String>(),
+            fields.get(1).copied().unwrap_or("unknown"),
+            fields.get(2).copied(),
+            None,
+            adopted.project().map(str::to_string),
+        )))
+    }
 }
 
 /// Shared template engine for Docker compose operations
@@ -246,15 +283,6 @@ volumes:
     })
 }
 
-impl Drop for DockerProvider {
-    fn drop(&mut self) {
-        if self.temp_dir.exists() {
-            // Best-effort cleanup. Ignore errors if cleanup fails.
-            let _ = fs::remove_dir_all(&self.temp_dir);
-        }
-    }
-}
-
 impl Provider for DockerProvider {
     fn name(&self) -> &'static str {
         "docker"
@@ -326,6 +354,11 @@ impl Provider for DockerProvider {
         lifecycle.exec_in_container(container, cmd)
     }
 
+    fn exec_script(&self, container: Option<&str>, script: &[u8], args: &[String]) -> Result<i32> {
+        let lifecycle = self.lifecycle_ops();
+        lifecycle.exec_script_in_container(container, script, args)
+    }
+
     fn logs(&self, container: Option<&str>) -> Result<()> {
         let lifecycle = self.lifecycle_ops();
         lifecycle.show_logs(container)
@@ -334,13 +367,15 @@ impl Provider for DockerProvider {
     fn logs_extended(
         &self,
         container: Option<&str>,
-        follow: bool,
-        tail: usize,
-        service: Option<&str>,
+        request: &crate::LogsRequest,
         config: &VmConfig,
     ) -> Result<()> {
         let lifecycle = self.lifecycle_ops();
-        lifecycle.show_logs_extended(container, follow, tail, service, config)
+        if request.all_services {
+            lifecycle.show_all_service_logs(container, request, config)
+        } else {
+            lifecycle.show_logs_extended(container, request, config)
+        }
     }
 
     fn copy(&self, source: &str, destination: &str, container: Option<&str>) -> Result<()> {
@@ -453,6 +488,26 @@ impl Provider for DockerProvider {
         lifecycle.get_status_report(container)
     }
 
+    fn set_resource_limits(&self, container: Option<&str>, limits: &ResourceLimits) -> Result<()> {
+        let lifecycle = self.lifecycle_ops();
+        lifecycle.update_container_resources(container, limits)
+    }
+
+    fn port_labels(&self, container: Option<&str>) -> Result<Vec<vm_core::labels::PortLabel>> {
+        let lifecycle = self.lifecycle_ops();
+        lifecycle.port_labels(container)
+    }
+
+    fn plan(
+        &self,
+        action: PlanAction,
+        container: Option<&str>,
+        context: &ProviderContext,
+    ) -> Result<ExecutionPlan> {
+        let lifecycle = self.lifecycle_ops();
+        lifecycle.plan(action, container, context)
+    }
+
     fn get_sync_directory(&self) -> String {
         let lifecycle = self.lifecycle_ops();
         lifecycle.get_sync_directory()
@@ -533,9 +588,80 @@ impl Provider for DockerProvider {
             }
         }
 
+        // Adopted containers can't carry the label, so they come from the registry
+        let registry = AdoptionRegistry::load_or_warn();
+        for (name, adopted) in registry.for_provider(&self.executable) {
+            if instances.iter().any(|instance| &instance.name == name) {
+                continue;
+            }
+            if let Some(instance) = self.inspect_adopted(name, adopted)? {
+                instances.push(instance);
+            }
+        }
+
         Ok(instances)
     }
 
+    fn adopt(&self, container: &str) -> Result<String> {
+        let output = std::process::Command::new(&self.executable)
+            .args([
+                "inspect",
+                "--type",
+                "container",
+                "--format",
+                "{{.Id}}\t{{.Name}}\t{{index .Config.Labels \"com.vm.managed\"}}",
+                container,
+            ])
+            .output()
+            .map_err(|e| VmError::Internal(format!("Failed to inspect '{container}': {e}")))?;
+        if !output.status.success() {
+            return Err(VmError::NotFound(format!(
+                "No container named '{container}' exists"
+            )));
+        }
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let mut fields = stdout.trim().split('\t');
+        let id = fields.next().unwrap_or_default().to_string();
+        let name = fields
+            .next()
+            .unwrap_or(container)
+            .trim_start_matches('/')
+            .to_string();
+        if fields.next() == Some("true") {
+            return Err(VmError::Provider(format!(
+                "Container '{name}' is already managed by vm"
+            )));
+        }
+
+        let mut registry = AdoptionRegistry::load()?;
+        if registry.containers.contains_key(&name) {
+            return Err(VmError::Provider(format!(
+                "Container '{name}' has already been adopted"
+            )));
+        }
+        let project = crate::common::instance::extract_project_name(&self.config);
+        registry.containers.insert(
+            name.clone(),
+            AdoptedContainer::new(
+                &id,
+                &self.executable,
+                project,
+                vm_core::labels::DEFAULT_INSTANCE,
+                &self.config.config_hash(),
+            ),
+        );
+        registry.save()?;
+        tracing::info!(
+            "Adopted container '{}' ({}) into project '{}'",
+            name,
+            id,
+            project
+        );
+
+        Ok(name)
+    }
+
     fn snapshot(&self, request: &crate::SnapshotRequest) -> Result<()> {
         let lifecycle = self.lifecycle_ops();
         let target_container = lifecycle.resolve_target_container(None)?;
@@ -673,8 +799,15 @@ impl Provider for DockerProvider {
 
         let container_name = format!("{}-restored", project_name);
 
+        let labels = vm_core::labels::label_args(
+            project_name,
+            vm_core::labels::DEFAULT_INSTANCE,
+            &self.config.config_hash(),
+        );
         let run_output = std::process::Command::new(&self.executable)
-            .args(["run", "-d", "--name", &container_name, &image_tag])
+            .args(["run", "-d", "--name", &container_name])
+            .args(&labels)
+            .arg(&image_tag)
             .output()
             .map_err(|e| {
                 VmError::Internal(format!("Failed to create container from snapshot: {e}"))
@@ -0,0 +1,55 @@
+#!/bin/sh
+# vm-clip - Share the clipboard with the host
+# Part of the vm tool: https://github.com/goobits/vm
+#
+# Usage: vm-clip copy < file    (also installed as pbcopy)
+#        vm-clip paste > file   (also installed as pbpaste)
+#
+# Enabled by `host_sync.clipboard: true` in vm.yaml. `vm ssh` and `vm exec` then
+# set VM_CLIPBOARD and VM_HOST_BRIDGE. Copying goes through the host bridge, or
+# an OSC 52 escape that the terminal (and tmux) forwards to the host clipboard
+# when the bridge is unreachable. Pasting needs the bridge.
+
+case "$(basename "$0")" in
+    pbcopy) mode=copy ;;
+    pbpaste) mode=paste ;;
+    *) mode="${1:-}" ;;
+esac
+
+if [ "${VM_CLIPBOARD:-}" != "1" ]; then
+    echo "vm-clip: clipboard sharing is off; set host_sync.clipboard: true in vm.yaml" >&2
+    exit 1
+fi
+
+bridge_available() {
+    [ -n "${VM_HOST_BRIDGE:-}" ] && command -v curl >/dev/null 2>&1
+}
+
+case "$mode" in
+    copy)
+        content=$(mktemp)
+        trap 'rm -f "$content"' EXIT
+        cat >"$content"
+        if bridge_available &&
+            curl -fsS --max-time 5 --data-binary "@$content" "$VM_HOST_BRIDGE/clipboard" >/dev/null 2>&1; then
+            exit 0
+        fi
+        # OSC 52: ESC ] 52 ; c ; <base64> BEL
+        if { printf '\033]52;c;%s\a' "$(base64 <"$content" | tr -d '\n')" >/dev/tty; } 2>/dev/null; then
+            exit 0
+        fi
+        echo "vm-clip: no terminal or host bridge to copy through" >&2
+        exit 1
+        ;;
+    paste)
+        if bridge_available && curl -fsS --max-time 5 "$VM_HOST_BRIDGE/clipboard"; then
+            exit 0
+        fi
+        echo "vm-clip: pasting needs the host bridge of a vm ssh or vm exec session" >&2
+        exit 1
+        ;;
+    *)
+        echo "Usage: vm-clip copy|paste" >&2
+        exit 2
+        ;;
+esac
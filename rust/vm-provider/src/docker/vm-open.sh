@@ -0,0 +1,26 @@
+#!/bin/sh
+# vm-open - Open URLs in the host browser
+# Part of the vm tool: https://github.com/goobits/vm
+#
+# Installed as xdg-open, open, sensible-browser and www-browser, and set as
+# $BROWSER, so CLIs that launch a browser (OAuth logins, docs) reach the host.
+# `vm ssh` and `vm exec` set VM_HOST_BRIDGE while a bridge is listening; without
+# it the URL is printed so it can be opened by hand.
+
+if [ $# -eq 0 ]; then
+    echo "Usage: vm-open <url>" >&2
+    exit 2
+fi
+
+url="$1"
+
+case "$url" in
+    http://* | https://*)
+        if [ -n "${VM_HOST_BRIDGE:-}" ] && command -v curl >/dev/null 2>&1 &&
+            curl -fsS --max-time 5 --get --data-urlencode "url=$url" "$VM_HOST_BRIDGE/open" >/dev/null 2>&1; then
+            exit 0
+        fi
+        ;;
+esac
+
+echo "Open this URL in your browser: $url" >&2
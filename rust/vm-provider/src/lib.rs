@@ -1,7 +1,7 @@
 //! VM provider abstraction library.
 //!
 //! This library provides a unified interface for working with different VM providers
-//! such as Docker, Podman, and Tart. It defines core traits and factory functions
+//! such as Docker, Podman, Tart, and libvirt. It defines core traits and factory functions
 //! for provider instantiation and management.
 
 // Standard library
@@ -19,6 +19,15 @@ pub use common::instance::{InstanceInfo, InstanceResolver};
 pub use context::ProviderContext;
 pub use vm_core::error::{Result as VmResult, VmError};
 
+/// Concrete resource limits applied to a running VM
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResourceLimits {
+    pub cpus: u32,
+    pub memory_mb: u32,
+    /// Swap on top of memory; `None` keeps the provider's default
+    pub swap_mb: Option<u32>,
+}
+
 // Status report structures for enhanced dashboard
 #[derive(Debug, Clone, Default)]
 pub struct ResourceUsage {
@@ -66,6 +75,40 @@ pub struct SnapshotRestoreRequest {
     pub force: bool,
 }
 
+/// Lifecycle action previewed by `--dry-run`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlanAction {
+    Create,
+    Start,
+    Stop,
+    Destroy,
+}
+
+/// Files a provider would generate and commands it would run for an action
+#[derive(Debug, Clone, Default)]
+pub struct ExecutionPlan {
+    /// Generated files and their rendered contents
+    pub files: Vec<(PathBuf, String)>,
+    /// Commands in the order they would run, starting with the executable
+    pub commands: Vec<Vec<String>>,
+}
+
+/// Options for showing VM logs
+#[derive(Debug, Clone, Default)]
+pub struct LogsRequest {
+    pub follow: bool,
+    pub tail: usize,
+    /// Show a single service's container instead of the VM
+    pub service: Option<String>,
+    /// Interleave the VM's logs with every enabled service container
+    pub all_services: bool,
+    /// Only show logs since this time (timestamp or relative, e.g. `10m`)
+    pub since: Option<String>,
+    /// Only show logs before this time (timestamp or relative, e.g. `10m`)
+    pub until: Option<String>,
+}
+
+pub mod clock;
 pub mod common;
 pub mod context;
 pub mod progress;
@@ -77,12 +120,14 @@ pub use resources::ZSHRC_TEMPLATE;
 pub mod security;
 pub mod temp_models;
 
-pub mod audio;
+pub mod devices;
 pub mod preflight;
 mod user_home;
 
 #[cfg(feature = "docker")]
 pub mod docker;
+#[cfg(feature = "libvirt")]
+pub mod libvirt;
 #[cfg(feature = "docker")]
 pub mod podman;
 #[cfg(feature = "tart")]
@@ -297,6 +342,18 @@ pub trait Provider {
     /// Execute a command inside the VM.
     fn exec(&self, container: Option<&str>, cmd: &[String]) -> Result<()>;
 
+    /// Run a script inside the VM with the given arguments and return its exit code.
+    ///
+    /// The script is sent over stdin rather than on the command line, so it needs
+    /// no shell escaping.
+    fn exec_script(&self, container: Option<&str>, script: &[u8], args: &[String]) -> Result<i32> {
+        let _ = (container, script, args);
+        Err(vm_core::error::VmError::Provider(format!(
+            "Running scripts is not supported by the {} provider",
+            self.name()
+        )))
+    }
+
     /// Get the logs of the VM.
     fn logs(&self, container: Option<&str>) -> Result<()>;
 
@@ -305,13 +362,11 @@ pub trait Provider {
     fn logs_extended(
         &self,
         container: Option<&str>,
-        follow: bool,
-        tail: usize,
-        service: Option<&str>,
+        request: &LogsRequest,
         _config: &VmConfig,
     ) -> Result<()> {
         // Default implementation: ignore new params and fall back to basic logs
-        let _ = (follow, tail, service);
+        let _ = request;
         self.logs(container)
     }
 
@@ -422,6 +477,75 @@ pub trait Provider {
         ))
     }
 
+    /// Bring a container vm didn't create under vm management.
+    ///
+    /// Returns the adopted container's name.
+    /// For providers without label-based discovery: returns Unsupported error
+    fn adopt(&self, _container: &str) -> Result<String> {
+        Err(VmError::Provider(format!(
+            "The {} provider doesn't support adopting existing machines",
+            self.name()
+        )))
+    }
+
+    /// Measure how far the VM's clock is from the host's.
+    ///
+    /// Returns `None` for providers whose VMs share the host clock (containers).
+    fn clock_skew(&self, _container: Option<&str>) -> Result<Option<clock::ClockSkew>> {
+        Ok(None)
+    }
+
+    /// Set the VM's clock to the host's time.
+    ///
+    /// For providers whose VMs share the host clock: returns Unsupported error
+    fn sync_clock(&self, _container: Option<&str>) -> Result<()> {
+        Err(VmError::Provider(format!(
+            "The {} provider shares the host clock; there is nothing to sync",
+            self.name()
+        )))
+    }
+
+    /// Apply new CPU and memory limits to a running VM without restarting it.
+    ///
+    /// For providers that can't resize live: returns Unsupported error
+    fn set_resource_limits(
+        &self,
+        _container: Option<&str>,
+        _limits: &ResourceLimits,
+    ) -> Result<()> {
+        Err(VmError::Provider(format!(
+            "The {} provider can't change resources of a running VM",
+            self.name()
+        )))
+    }
+
+    /// List the published ports of a VM with their purpose, from the
+    /// `com.vm.port.<host port>` labels written at creation.
+    ///
+    /// For providers that don't label ports: returns Unsupported error
+    fn port_labels(&self, _container: Option<&str>) -> Result<Vec<vm_core::labels::PortLabel>> {
+        Err(VmError::Provider(format!(
+            "The {} provider doesn't record port labels",
+            self.name()
+        )))
+    }
+
+    /// Render the files and commands a lifecycle action would use, without applying it.
+    ///
+    /// For `Create`, `container` names the instance to create.
+    /// For providers that can't preview actions: returns Unsupported error
+    fn plan(
+        &self,
+        _action: PlanAction,
+        _container: Option<&str>,
+        _context: &ProviderContext,
+    ) -> Result<ExecutionPlan> {
+        Err(VmError::Provider(format!(
+            "The {} provider can't preview actions with --dry-run",
+            self.name()
+        )))
+    }
+
     /// Clone the provider into a new Box.
     fn clone_box(&self) -> Box<dyn Provider>;
 }
@@ -454,6 +578,8 @@ pub fn get_provider(config: VmConfig) -> Result<Box<dyn Provider>> {
         "podman" => Ok(Box::new(podman::PodmanProvider::new(config)?)),
         #[cfg(feature = "tart")]
         "tart" => Ok(Box::new(tart::TartProvider::new(config)?)),
+        #[cfg(feature = "libvirt")]
+        "libvirt" => Ok(Box::new(libvirt::LibvirtProvider::new(config)?)),
         _ => Err(VmError::Provider(format!(
             "Unknown provider: {provider_name}"
         ))),
@@ -0,0 +1,163 @@
+//! cloud-init seed generation
+//!
+//! Provisioning runs inside the guest on first boot: the NoCloud seed creates
+//! the VM user with the generated SSH key, installs `apt_packages`, sets the
+//! hostname and timezone and mounts the virtiofs workspace share.
+
+use super::domain::WORKSPACE_TAG;
+use serde_json::json;
+use std::fs;
+use std::path::{Path, PathBuf};
+use vm_config::config::VmConfig;
+use vm_core::command_stream::is_tool_installed;
+use vm_core::error::{Result, VmError};
+
+/// Login user when `vm.user` is unset
+pub const DEFAULT_USER: &str = "developer";
+
+/// Login user configured for the VM
+pub fn vm_user(config: &VmConfig) -> &str {
+    config
+        .vm
+        .as_ref()
+        .and_then(|vm| vm.user.as_deref())
+        .unwrap_or(DEFAULT_USER)
+}
+
+/// Render the `#cloud-config` user-data document
+pub fn user_data(
+    config: &VmConfig,
+    hostname: &str,
+    public_key: &str,
+    workspace: &str,
+) -> Result<String> {
+    let user = vm_user(config);
+    let shell = match config.terminal.as_ref().and_then(|t| t.shell.as_deref()) {
+        Some("zsh") => "/usr/bin/zsh",
+        Some("fish") => "/usr/bin/fish",
+        _ => "/bin/bash",
+    };
+
+    let mut packages = config.apt_packages.clone();
+    if shell != "/bin/bash" {
+        if let Some(name) = Path::new(shell).file_name() {
+            packages.push(name.to_string_lossy().into_owned());
+        }
+    }
+
+    let mut document = json!({
+        "hostname": hostname,
+        "users": [{
+            "name": user,
+            "shell": shell,
+            "groups": ["sudo"],
+            "sudo": "ALL=(ALL) NOPASSWD:ALL",
+            "ssh_authorized_keys": [public_key.trim()],
+        }],
+        "package_update": !packages.is_empty(),
+        "packages": packages,
+        "mounts": [[WORKSPACE_TAG, workspace, "virtiofs", "defaults,nofail", "0", "0"]],
+        "runcmd": [
+            ["mkdir", "-p", workspace],
+            ["mount", "-a"],
+            ["chown", format!("{user}:{user}"), workspace],
+        ],
+    });
+    if let Some(timezone) = config.vm.as_ref().and_then(|vm| vm.timezone.as_deref()) {
+        if timezone != "auto" {
+            document["timezone"] = json!(timezone);
+        }
+    }
+
+    let yaml = serde_yaml_ng::to_string(&document)
+        .map_err(|e| VmError::Internal(format!("Failed to render cloud-init user-data: {e}")))?;
+    Ok(format!("#cloud-config\n{yaml}"))
+}
+
+/// Render the NoCloud meta-data document
+pub fn meta_data(instance_id: &str, hostname: &str) -> String {
+    format!("instance-id: {instance_id}\nlocal-hostname: {hostname}\n")
+}
+
+/// Write user-data and meta-data into `dir` and pack them into a NoCloud seed ISO
+pub fn write_seed(dir: &Path, user_data: &str, meta_data: &str) -> Result<PathBuf> {
+    let user_data_path = dir.join("user-data");
+    let meta_data_path = dir.join("meta-data");
+    let seed = dir.join("seed.iso");
+    fs::write(&user_data_path, user_data)?;
+    fs::write(&meta_data_path, meta_data)?;
+
+    let mut command = if is_tool_installed("cloud-localds") {
+        let mut command = std::process::Command::new("cloud-localds");
+        command.arg(&seed).arg(&user_data_path).arg(&meta_data_path);
+        command
+    } else {
+        // The seed is found by its `cidata` volume label
+        let tool = ["genisoimage", "mkisofs", "xorrisofs"]
+            .into_iter()
+            .find(|tool| is_tool_installed(tool))
+            .ok_or_else(|| VmError::Dependency("cloud-localds, genisoimage or xorrisofs".into()))?;
+        let mut command = std::process::Command::new(tool);
+        command
+            .args(["-quiet", "-output"])
+            .arg(&seed)
+            .args(["-volid", "cidata", "-joliet", "-rock"])
+            .arg(&user_data_path)
+            .arg(&meta_data_path);
+        command
+    };
+
+    let output = command.output()?;
+    if !output.status.success() {
+        return Err(VmError::Provider(format!(
+            "Failed to build cloud-init seed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(seed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_user_data_provisions_user_packages_and_workspace() {
+        let config: VmConfig = serde_yaml_ng::from_str(
+            "vm:\n  user: alice\n  timezone: Europe/Berlin\napt_packages:\n  - git\nterminal:\n  shell: zsh\n",
+        )
+        .unwrap();
+        let rendered = user_data(
+            &config,
+            "my-project-dev",
+            "ssh-ed25519 AAAA vm\n",
+            "/workspace",
+        )
+        .unwrap();
+        assert!(rendered.starts_with("#cloud-config\n"));
+
+        let document: serde_yaml_ng::Value = serde_yaml_ng::from_str(&rendered).unwrap();
+        assert_eq!(document["hostname"].as_str(), Some("my-project-dev"));
+        assert_eq!(document["timezone"].as_str(), Some("Europe/Berlin"));
+        assert_eq!(document["users"][0]["name"].as_str(), Some("alice"));
+        assert_eq!(document["users"][0]["shell"].as_str(), Some("/usr/bin/zsh"));
+        assert_eq!(
+            document["users"][0]["ssh_authorized_keys"][0].as_str(),
+            Some("ssh-ed25519 AAAA vm")
+        );
+        assert_eq!(document["packages"][0].as_str(), Some("git"));
+        assert_eq!(document["packages"][1].as_str(), Some("zsh"));
+        assert_eq!(document["mounts"][0][0].as_str(), Some(WORKSPACE_TAG));
+        assert_eq!(document["mounts"][0][1].as_str(), Some("/workspace"));
+        assert_eq!(document["mounts"][0][2].as_str(), Some("virtiofs"));
+    }
+
+    #[test]
+    fn test_user_data_defaults() {
+        let rendered = user_data(&VmConfig::default(), "vm", "key", "/workspace").unwrap();
+        let document: serde_yaml_ng::Value = serde_yaml_ng::from_str(&rendered).unwrap();
+        assert_eq!(document["users"][0]["name"].as_str(), Some(DEFAULT_USER));
+        assert_eq!(document["package_update"].as_bool(), Some(false));
+        assert!(document.get("timezone").is_none());
+    }
+}
@@ -0,0 +1,229 @@
+//! libvirt domain XML generation
+//!
+//! Maps `VmConfig` memory and CPU settings onto a KVM domain that boots a
+//! qcow2 overlay of the box's cloud image, reads its cloud-init seed from a
+//! CD-ROM and shares the project directory over virtiofs. virtiofs needs
+//! shared guest memory, so every domain gets a shared memfd backing.
+
+use std::path::{Path, PathBuf};
+use vm_config::config::VmConfig;
+
+/// Memory given to a VM whose config sets no explicit limit
+pub const DEFAULT_MEMORY_MB: u32 = 4096;
+
+/// vCPUs given to a VM whose config sets no explicit limit
+pub const DEFAULT_CPUS: u32 = 2;
+
+/// virtiofs tag the guest mounts the workspace by
+pub const WORKSPACE_TAG: &str = "workspace";
+
+/// Host directory shared into the guest over virtiofs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorkspaceShare {
+    pub source: PathBuf,
+    pub tag: String,
+}
+
+/// Everything needed to render a domain definition
+#[derive(Debug, Clone)]
+pub struct DomainSpec {
+    pub name: String,
+    pub memory_mb: u32,
+    pub cpus: u32,
+    /// Guest architecture, matching the host (`x86_64` or `aarch64`)
+    pub arch: String,
+    pub disk: PathBuf,
+    pub seed: PathBuf,
+    pub console_log: PathBuf,
+    pub network: String,
+    pub workspace: Option<WorkspaceShare>,
+}
+
+/// Resolve `vm.memory` and `vm.cpus` against the host's capacity.
+///
+/// Percentages are taken of the host totals; unlimited or unset values fall
+/// back to [`DEFAULT_MEMORY_MB`] and [`DEFAULT_CPUS`], since a VM needs a
+/// fixed size.
+pub fn resolve_resources(config: &VmConfig, host_memory_mb: u64, host_cpus: u32) -> (u32, u32) {
+    let vm = config.vm.as_ref();
+    let memory_mb = vm
+        .and_then(|vm| vm.memory.as_ref())
+        .and_then(|memory| memory.resolve_percentage(host_memory_mb))
+        .unwrap_or(DEFAULT_MEMORY_MB);
+    let cpus = vm
+        .and_then(|vm| vm.cpus.as_ref())
+        .and_then(|cpus| cpus.resolve_percentage(host_cpus))
+        .unwrap_or(DEFAULT_CPUS)
+        .max(1);
+    (memory_mb, cpus)
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('\'', "&apos;")
+        .replace('"', "&quot;")
+}
+
+fn path_attr(path: &Path) -> String {
+    escape(&path.to_string_lossy())
+}
+
+impl DomainSpec {
+    /// Render the domain XML passed to `virsh define`
+    pub fn to_xml(&self) -> String {
+        // aarch64 guests have no legacy BIOS, so they boot through UEFI firmware
+        let (machine, firmware) = match self.arch.as_str() {
+            "aarch64" => ("virt", " firmware='efi'"),
+            _ => ("q35", ""),
+        };
+        let filesystem = self
+            .workspace
+            .as_ref()
+            .map(|share| {
+                format!(
+                    r#"    <filesystem type='mount' accessmode='passthrough'>
+      <driver type='virtiofs'/>
+      <source dir='{}'/>
+      <target dir='{}'/>
+    </filesystem>
+"#,
+                    path_attr(&share.source),
+                    escape(&share.tag)
+                )
+            })
+            .unwrap_or_default();
+
+        format!(
+            r#"<domain type='kvm'>
+  <name>{name}</name>
+  <metadata>
+    <vm:instance xmlns:vm='https://goobits.dev/vm'>managed</vm:instance>
+  </metadata>
+  <memory unit='MiB'>{memory}</memory>
+  <vcpu placement='static'>{cpus}</vcpu>
+  <os{firmware}>
+    <type arch='{arch}' machine='{machine}'>hvm</type>
+    <boot dev='hd'/>
+  </os>
+  <features>
+    <acpi/>
+  </features>
+  <cpu mode='host-passthrough'/>
+  <memoryBacking>
+    <source type='memfd'/>
+    <access mode='shared'/>
+  </memoryBacking>
+  <on_poweroff>destroy</on_poweroff>
+  <on_reboot>restart</on_reboot>
+  <on_crash>destroy</on_crash>
+  <devices>
+    <disk type='file' device='disk'>
+      <driver name='qemu' type='qcow2' discard='unmap'/>
+      <source file='{disk}'/>
+      <target dev='vda' bus='virtio'/>
+    </disk>
+    <disk type='file' device='cdrom'>
+      <driver name='qemu' type='raw'/>
+      <source file='{seed}'/>
+      <target dev='sda' bus='sata'/>
+      <readonly/>
+    </disk>
+{filesystem}    <interface type='network'>
+      <source network='{network}'/>
+      <model type='virtio'/>
+    </interface>
+    <serial type='pty'>
+      <log file='{console_log}' append='on'/>
+      <target port='0'/>
+    </serial>
+    <console type='pty'>
+      <target type='serial' port='0'/>
+    </console>
+    <channel type='unix'>
+      <target type='virtio' name='org.qemu.guest_agent.0'/>
+    </channel>
+    <rng model='virtio'>
+      <backend model='random'>/dev/urandom</backend>
+    </rng>
+  </devices>
+</domain>
+"#,
+            name = escape(&self.name),
+            memory = self.memory_mb,
+            cpus = self.cpus,
+            arch = escape(&self.arch),
+            disk = path_attr(&self.disk),
+            seed = path_attr(&self.seed),
+            network = escape(&self.network),
+            console_log = path_attr(&self.console_log),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec() -> DomainSpec {
+        DomainSpec {
+            name: "my-project-dev".to_string(),
+            memory_mb: 8192,
+            cpus: 4,
+            arch: "x86_64".to_string(),
+            disk: PathBuf::from("/var/lib/vm/my-project-dev/disk.qcow2"),
+            seed: PathBuf::from("/var/lib/vm/my-project-dev/seed.iso"),
+            console_log: PathBuf::from("/var/lib/vm/my-project-dev/console.log"),
+            network: "default".to_string(),
+            workspace: Some(WorkspaceShare {
+                source: PathBuf::from("/home/me/R&D"),
+                tag: WORKSPACE_TAG.to_string(),
+            }),
+        }
+    }
+
+    #[test]
+    fn test_domain_xml_maps_resources_and_devices() {
+        let xml = spec().to_xml();
+        assert!(xml.contains("<name>my-project-dev</name>"));
+        assert!(xml.contains("<memory unit='MiB'>8192</memory>"));
+        assert!(xml.contains("<vcpu placement='static'>4</vcpu>"));
+        assert!(xml.contains("machine='q35'"));
+        assert!(xml.contains("<source file='/var/lib/vm/my-project-dev/disk.qcow2'/>"));
+        assert!(xml.contains("<source file='/var/lib/vm/my-project-dev/seed.iso'/>"));
+        assert!(xml.contains("<driver type='virtiofs'/>"));
+        assert!(xml.contains("<source dir='/home/me/R&amp;D'/>"));
+        assert!(xml.contains("<target dir='workspace'/>"));
+        assert!(xml.contains("<access mode='shared'/>"));
+        assert!(xml.contains("<source network='default'/>"));
+    }
+
+    #[test]
+    fn test_domain_xml_without_workspace_and_on_arm() {
+        let mut spec = spec();
+        spec.workspace = None;
+        spec.arch = "aarch64".to_string();
+        let xml = spec.to_xml();
+        assert!(!xml.contains("virtiofs"));
+        assert!(xml.contains("<os firmware='efi'>"));
+        assert!(xml.contains("machine='virt'"));
+    }
+
+    #[test]
+    fn test_resolve_resources() {
+        let config: VmConfig = serde_yaml_ng::from_str("vm:\n  memory: 50%\n  cpus: 3\n").unwrap();
+        assert_eq!(resolve_resources(&config, 16384, 8), (8192, 3));
+
+        let unlimited: VmConfig = serde_yaml_ng::from_str("vm:\n  memory: unlimited\n").unwrap();
+        assert_eq!(
+            resolve_resources(&unlimited, 16384, 8),
+            (DEFAULT_MEMORY_MB, DEFAULT_CPUS)
+        );
+        assert_eq!(
+            resolve_resources(&VmConfig::default(), 16384, 8),
+            (DEFAULT_MEMORY_MB, DEFAULT_CPUS)
+        );
+    }
+}
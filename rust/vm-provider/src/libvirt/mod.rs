@@ -0,0 +1,33 @@
+//! QEMU/libvirt provider module.
+//!
+//! Runs full virtual machines on Linux hosts through libvirt, for projects
+//! that need a real kernel (kernel modules, systemd, nested containers).
+//!
+//! ## Key Features
+//! - **Cloud images**: Boxes are Ubuntu or Debian cloud images, or any qcow2 URL/path
+//! - **Copy-on-write disks**: Each VM gets a qcow2 overlay on the cached base image
+//! - **cloud-init provisioning**: User, SSH key, packages and mounts are set up on first boot
+//! - **virtiofs workspace**: The project directory is shared live, no sync needed
+//!
+//! ## Architecture
+//! - `domain` maps `VmConfig` memory and CPU settings to domain XML
+//! - `cloud_init` renders the NoCloud seed the guest provisions itself from
+//! - `provider` drives `virsh`, `qemu-img` and SSH for the lifecycle commands
+//!
+//! ## Example Configuration
+//! ```yaml
+//! provider: libvirt
+//! vm:
+//!   box: ubuntu:24.04
+//!   memory: 4096
+//!   cpus: 2
+//! libvirt:
+//!   uri: qemu:///system
+//!   disk_size: 60GB
+//! ```
+
+pub mod cloud_init;
+pub mod domain;
+mod provider;
+
+pub use provider::LibvirtProvider;
@@ -0,0 +1,725 @@
+use super::cloud_init::{self, vm_user};
+use super::domain::{resolve_resources, DomainSpec, WorkspaceShare, WORKSPACE_TAG};
+use crate::{
+    clock::{self, ClockSkew},
+    common::instance::{extract_project_name, InstanceInfo},
+    common::script,
+    context::ProviderContext,
+    security::SecurityValidator,
+    Provider, VmStatusReport,
+};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::thread;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+use vm_config::config::{BoxSpec, LibvirtConfig, VmConfig};
+use vm_core::command_stream::{is_tool_installed, stream_command};
+use vm_core::error::{Result, VmError};
+use vm_core::vm_println;
+
+const DEFAULT_URI: &str = "qemu:///system";
+const DEFAULT_NETWORK: &str = "default";
+const DEFAULT_DISK_GB: u32 = 40;
+
+/// How long to wait for a freshly booted VM to answer over SSH
+const BOOT_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Options passed to every `ssh`/`scp` call; keys are per VM and hosts are
+/// throwaway, so host keys aren't recorded
+const SSH_OPTIONS: [&str; 8] = [
+    "-o",
+    "StrictHostKeyChecking=no",
+    "-o",
+    "UserKnownHostsFile=/dev/null",
+    "-o",
+    "LogLevel=ERROR",
+    "-o",
+    "ConnectTimeout=5",
+];
+
+/// Cloud image used for a box name, or the default when no box is set
+fn cloud_image_url(box_name: Option<&str>, arch: &str) -> Result<String> {
+    let image_arch = match arch {
+        "aarch64" => "arm64",
+        _ => "amd64",
+    };
+    let ubuntu = |release: &str| {
+        format!(
+            "https://cloud-images.ubuntu.com/{release}/current/{release}-server-cloudimg-{image_arch}.img"
+        )
+    };
+    match box_name {
+        None | Some("ubuntu:24.04") | Some("ubuntu:noble") => Ok(ubuntu("noble")),
+        Some("ubuntu:22.04") | Some("ubuntu:jammy") => Ok(ubuntu("jammy")),
+        Some("debian:12") | Some("debian:bookworm") => Ok(format!(
+            "https://cloud.debian.org/images/cloud/bookworm/latest/debian-12-generic-{image_arch}.qcow2"
+        )),
+        Some(url) if url.starts_with("https://") || url.starts_with("http://") => {
+            Ok(url.to_string())
+        }
+        Some(other) => Err(VmError::Config(format!(
+            "libvirt can't boot box '{other}'. Use a cloud image URL, a local qcow2 path, or one of ubuntu:24.04, ubuntu:22.04, debian:12"
+        ))),
+    }
+}
+
+/// File name of the downloaded `url` in the image cache.
+///
+/// Distributions reuse names like `disk.qcow2`, so the name starts with a hash
+/// of the URL.
+fn cached_image_name(url: &str) -> String {
+    let digest = format!("{:x}", Sha256::digest(url.as_bytes()));
+    let base = url.rsplit('/').next().unwrap_or("base.img");
+    format!("{}-{base}", &digest[..16])
+}
+
+/// Quote a word for the remote shell that runs SSH commands
+fn shell_quote(word: &str) -> String {
+    format!("'{}'", word.replace('\'', "'\"'\"'"))
+}
+
+/// First IPv4 address in `virsh domifaddr` output
+fn parse_domifaddr(output: &str) -> Option<String> {
+    output.lines().find_map(|line| {
+        let mut fields = line.split_whitespace();
+        let address = fields.find(|field| *field == "ipv4").and(fields.next())?;
+        address.split('/').next().map(str::to_string)
+    })
+}
+
+#[derive(Clone)]
+pub struct LibvirtProvider {
+    config: VmConfig,
+}
+
+impl LibvirtProvider {
+    pub fn new(config: VmConfig) -> Result<Self> {
+        if !is_tool_installed("virsh") {
+            return Err(VmError::Dependency("libvirt (virsh)".into()));
+        }
+        if !is_tool_installed("qemu-img") {
+            return Err(VmError::Dependency("qemu-img".into()));
+        }
+        Ok(Self { config })
+    }
+
+    fn settings(&self) -> LibvirtConfig {
+        self.config.libvirt.clone().unwrap_or_default()
+    }
+
+    fn uri(&self) -> String {
+        self.settings()
+            .uri
+            .unwrap_or_else(|| DEFAULT_URI.to_string())
+    }
+
+    fn storage_dir(&self) -> Result<PathBuf> {
+        match self.settings().storage_path {
+            Some(path) => Ok(PathBuf::from(shellexpand::tilde(&path).into_owned())),
+            None => Ok(vm_core::user_paths::vm_state_dir()?.join("libvirt")),
+        }
+    }
+
+    fn instance_dir(&self, name: &str) -> Result<PathBuf> {
+        Ok(self.storage_dir()?.join(name))
+    }
+
+    fn key_path(&self, name: &str) -> Result<PathBuf> {
+        Ok(self.instance_dir(name)?.join("id_ed25519"))
+    }
+
+    fn virsh(&self) -> Command {
+        let mut command = Command::new("virsh");
+        command.args(["-c", &self.uri()]);
+        command
+    }
+
+    /// Run `virsh <args>` and return its stdout
+    fn virsh_output(&self, args: &[&str]) -> Result<String> {
+        let output = self.virsh().args(args).output()?;
+        if !output.status.success() {
+            return Err(VmError::Provider(format!(
+                "virsh {} failed: {}",
+                args.first().copied().unwrap_or_default(),
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+
+    fn stream_virsh(&self, args: &[&str]) -> Result<()> {
+        let uri = self.uri();
+        let mut full_args = vec!["-c", uri.as_str()];
+        full_args.extend_from_slice(args);
+        stream_command("virsh", &full_args)
+    }
+
+    /// Domain state as reported by `virsh domstate`, or `None` if it isn't defined
+    fn domain_state(&self, name: &str) -> Option<String> {
+        self.virsh_output(&["domstate", name])
+            .ok()
+            .map(|state| state.trim().to_string())
+    }
+
+    fn is_running(&self, name: &str) -> bool {
+        self.domain_state(name).as_deref() == Some("running")
+    }
+
+    fn host_workspace_path(&self) -> Result<PathBuf> {
+        let cwd = std::env::current_dir()?;
+        match &self.config.source_path {
+            Some(source) => {
+                let resolved = cwd.join(source);
+                if resolved.is_dir() {
+                    Ok(resolved)
+                } else {
+                    Ok(resolved.parent().map(Path::to_path_buf).unwrap_or(cwd))
+                }
+            }
+            None => Ok(cwd),
+        }
+    }
+
+    /// Local backing image for the VM's disk, downloading it into the cache if needed
+    fn base_image(&self) -> Result<PathBuf> {
+        let box_name = match self.config.vm.as_ref().and_then(|vm| vm.get_box_spec()) {
+            Some(BoxSpec::String(name)) => Some(name),
+            Some(BoxSpec::Build { .. }) => {
+                return Err(VmError::Config(
+                    "libvirt does not support Dockerfile builds".to_string(),
+                ))
+            }
+            None => None,
+        };
+
+        if let Some(name) = &box_name {
+            if name.starts_with('@') {
+                return Err(VmError::Config(
+                    "libvirt boxes are cloud images; snapshots are not supported".to_string(),
+                ));
+            }
+            let path = Path::new(name);
+            if name.starts_with("./") || name.starts_with("../") || path.is_absolute() {
+                let path = self.host_workspace_path()?.join(path);
+                if !path.is_file() {
+                    return Err(VmError::Config(format!(
+                        "Cloud image not found: {}",
+                        path.display()
+                    )));
+                }
+                return Ok(path);
+            }
+        }
+
+        let url = cloud_image_url(box_name.as_deref(), std::env::consts::ARCH)?;
+        let file_name = cached_image_name(&url);
+        let images = self.storage_dir()?.join("images");
+        let image = images.join(&file_name);
+        if image.is_file() {
+            return Ok(image);
+        }
+
+        vm_core::offline::ensure_online("download the libvirt base image")?;
+        fs::create_dir_all(&images)?;
+        vm_println!("⬇️  Downloading {}", url);
+        let partial = images.join(format!("{file_name}.part"));
+        let partial_str = partial.to_string_lossy().into_owned();
+        let bundle = vm_core::network::write_combined_ca_bundle(&images)?;
+        let bundle_str = bundle
+            .as_ref()
+            .map(|path| path.to_string_lossy().into_owned());
+        let mut args = vec!["-fL", "--progress-bar", "-o", &partial_str];
+        if let Some(bundle) = &bundle_str {
+            args.extend(["--cacert", bundle]);
+        }
+        args.push(&url);
+        let downloaded = stream_command("curl", &args);
+        if let Some(bundle) = &bundle {
+            let _ = fs::remove_file(bundle);
+        }
+        downloaded?;
+        fs::rename(&partial, &image)?;
+        Ok(image)
+    }
+
+    fn disk_size_gb(&self) -> Result<u32> {
+        match self.settings().disk_size {
+            Some(size) => size.to_gb().ok_or_else(|| {
+                VmError::Config("libvirt.disk_size must be a fixed size such as 40GB".to_string())
+            }),
+            None => Ok(DEFAULT_DISK_GB),
+        }
+    }
+
+    fn write_seed(&self, name: &str, instance_id: &str) -> Result<PathBuf> {
+        let dir = self.instance_dir(name)?;
+        let public_key = fs::read_to_string(dir.join("id_ed25519.pub"))?;
+        let user_data =
+            cloud_init::user_data(&self.config, name, &public_key, &self.get_sync_directory())?;
+        cloud_init::write_seed(&dir, &user_data, &cloud_init::meta_data(instance_id, name))
+    }
+
+    fn create_domain(&self, name: &str) -> Result<()> {
+        if self.domain_state(name).is_some() {
+            return Err(VmError::Provider(format!(
+                "libvirt domain '{name}' already exists. Run 'vm destroy' first"
+            )));
+        }
+
+        let dir = self.instance_dir(name)?;
+        fs::create_dir_all(&dir)?;
+        let base = self.base_image()?;
+
+        let disk = dir.join("disk.qcow2");
+        let size = format!("{}G", self.disk_size_gb()?);
+        let output = Command::new("qemu-img")
+            .args(["create", "-q", "-f", "qcow2", "-F", "qcow2", "-b"])
+            .arg(&base)
+            .arg(&disk)
+            .arg(&size)
+            .output()?;
+        if !output.status.success() {
+            return Err(VmError::Provider(format!(
+                "Failed to create VM disk: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+
+        let key = self.key_path(name)?;
+        if !key.exists() {
+            let status = Command::new("ssh-keygen")
+                .args(["-q", "-t", "ed25519", "-N", "", "-C", name, "-f"])
+                .arg(&key)
+                .status()?;
+            if !status.success() {
+                return Err(VmError::Provider("Failed to generate SSH key".to_string()));
+            }
+        }
+
+        let seed = self.write_seed(name, name)?;
+
+        let host_memory_mb = vm_core::system_check::get_total_memory_gb().unwrap_or(8) * 1024;
+        let host_cpus = vm_core::system_check::get_cpu_core_count().unwrap_or(2);
+        let (memory_mb, cpus) = resolve_resources(&self.config, host_memory_mb, host_cpus);
+        let spec = DomainSpec {
+            name: name.to_string(),
+            memory_mb,
+            cpus,
+            arch: std::env::consts::ARCH.to_string(),
+            disk,
+            seed,
+            console_log: dir.join("console.log"),
+            network: self
+                .settings()
+                .network
+                .unwrap_or_else(|| DEFAULT_NETWORK.to_string()),
+            workspace: Some(WorkspaceShare {
+                source: self.host_workspace_path()?,
+                tag: WORKSPACE_TAG.to_string(),
+            }),
+        };
+        let xml_path = dir.join("domain.xml");
+        fs::write(&xml_path, spec.to_xml())?;
+        self.virsh_output(&["define", &xml_path.to_string_lossy()])?;
+
+        vm_println!("🚀 Booting {} ({} MB, {} vCPUs)", name, memory_mb, cpus);
+        self.virsh_output(&["start", name])?;
+        self.wait_for_provisioning(name)
+    }
+
+    /// Wait for SSH and for cloud-init to finish its first-boot run
+    fn wait_for_provisioning(&self, name: &str) -> Result<()> {
+        vm_println!("⏳ Waiting for cloud-init to finish...");
+        let deadline = Instant::now() + BOOT_TIMEOUT;
+        loop {
+            if let Ok(args) = self.ssh_args(name) {
+                let ready = Command::new("ssh")
+                    .args(&args)
+                    .args(["-o", "BatchMode=yes", "true"])
+                    .output()
+                    .map(|output| output.status.success())
+                    .unwrap_or(false);
+                if ready {
+                    break;
+                }
+            }
+            if Instant::now() >= deadline {
+                return Err(VmError::Provider(format!(
+                    "Timed out waiting for '{name}' to boot. Check 'vm logs' for the console output"
+                )));
+            }
+            thread::sleep(Duration::from_secs(3));
+        }
+
+        let status = Command::new("ssh")
+            .args(self.ssh_args(name)?)
+            .args(["cloud-init", "status", "--wait"])
+            .status()?;
+        if !status.success() {
+            warn!("cloud-init reported errors; see /var/log/cloud-init-output.log in the VM");
+        }
+        Ok(())
+    }
+
+    fn ip_address(&self, name: &str) -> Result<String> {
+        let output = self.virsh_output(&["domifaddr", name, "--source", "lease"])?;
+        parse_domifaddr(&output)
+            .ok_or_else(|| VmError::Provider(format!("VM '{name}' has no IP address yet")))
+    }
+
+    fn destination(&self, name: &str) -> Result<String> {
+        Ok(format!(
+            "{}@{}",
+            vm_user(&self.config),
+            self.ip_address(name)?
+        ))
+    }
+
+    /// `ssh` arguments up to and including the destination
+    fn ssh_args(&self, name: &str) -> Result<Vec<String>> {
+        let mut args = vec![
+            "-i".to_string(),
+            self.key_path(name)?.to_string_lossy().into_owned(),
+        ];
+        args.extend(SSH_OPTIONS.iter().map(|option| option.to_string()));
+        args.push(self.destination(name)?);
+        Ok(args)
+    }
+
+    fn running_instance(&self, container: Option<&str>) -> Result<String> {
+        let name = self.resolve_instance_name(container)?;
+        if !self.is_running(&name) {
+            return Err(VmError::Provider(format!("VM '{name}' is not running")));
+        }
+        Ok(name)
+    }
+
+    /// Remote command that runs `words` from the sync directory
+    fn sync_dir_command(&self, words: &[String]) -> String {
+        let quoted: Vec<String> = words.iter().map(|word| shell_quote(word)).collect();
+        format!(
+            "cd {} && {}",
+            shell_quote(&self.get_sync_directory()),
+            quoted.join(" ")
+        )
+    }
+}
+
+impl Provider for LibvirtProvider {
+    fn name(&self) -> &'static str {
+        "libvirt"
+    }
+
+    fn create_with_context(&self, _context: &ProviderContext) -> Result<()> {
+        self.create_domain(&self.resolve_instance_name(None)?)
+    }
+
+    fn create_instance(&self, instance_name: &str) -> Result<()> {
+        let name = format!("{}-{}", extract_project_name(&self.config), instance_name);
+        self.create_domain(&name)
+    }
+
+    fn start(&self, container: Option<&str>) -> Result<()> {
+        let name = self.resolve_instance_name(container)?;
+        if self.is_running(&name) {
+            return Ok(());
+        }
+        self.stream_virsh(&["start", &name])
+    }
+
+    fn stop(&self, container: Option<&str>) -> Result<()> {
+        let name = self.resolve_instance_name(container)?;
+        self.stream_virsh(&["shutdown", &name])
+    }
+
+    fn destroy(&self, container: Option<&str>) -> Result<()> {
+        let name = self.resolve_instance_name(container)?;
+        if let Some(state) = self.domain_state(&name) {
+            if state == "running" || state == "paused" {
+                self.virsh_output(&["destroy", &name])?;
+            }
+            self.virsh_output(&["undefine", &name, "--nvram"])?;
+        }
+
+        let dir = self.instance_dir(&name)?;
+        if dir.exists() {
+            fs::remove_dir_all(&dir)?;
+        }
+        info!("Destroyed libvirt domain '{}'", name);
+        Ok(())
+    }
+
+    fn ssh(&self, container: Option<&str>, relative_path: &Path) -> Result<()> {
+        let name = self.running_instance(container)?;
+        let sync_dir = self.get_sync_directory();
+        let target = SecurityValidator::validate_relative_path(relative_path, &sync_dir)?;
+        let shell = self
+            .config
+            .terminal
+            .as_ref()
+            .and_then(|t| t.shell.as_deref())
+            .unwrap_or("bash");
+
+        let status = Command::new("ssh")
+            .arg("-t")
+            .args(self.ssh_args(&name)?)
+            .arg(format!(
+                "cd {} && exec {} -il",
+                shell_quote(&target.to_string_lossy()),
+                shell_quote(shell)
+            ))
+            .status()?;
+
+        match status.code() {
+            Some(0) | Some(130) => Ok(()),
+            Some(code) => Err(VmError::Provider(format!("Shell exited with code {code}"))),
+            None => Err(VmError::Provider(
+                "Shell terminated unexpectedly".to_string(),
+            )),
+        }
+    }
+
+    fn exec(&self, container: Option<&str>, cmd: &[String]) -> Result<()> {
+        let name = self.running_instance(container)?;
+        let mut args = self.ssh_args(&name)?;
+        args.push(self.sync_dir_command(cmd));
+        stream_command("ssh", &args)
+    }
+
+    fn exec_script(&self, container: Option<&str>, script: &[u8], args: &[String]) -> Result<i32> {
+        let name = self.running_instance(container)?;
+        let mut ssh_args = self.ssh_args(&name)?;
+        ssh_args.push(self.sync_dir_command(&script::runner_command(args)));
+        script::run_with_script("ssh", &ssh_args, script)
+    }
+
+    fn logs(&self, container: Option<&str>) -> Result<()> {
+        let name = self.resolve_instance_name(container)?;
+        let log = self.instance_dir(&name)?.join("console.log");
+        if !log.exists() {
+            return Err(VmError::Provider(format!(
+                "No console log for '{name}' at {}",
+                log.display()
+            )));
+        }
+        stream_command("tail", &["-f", &log.to_string_lossy()])
+    }
+
+    fn copy(&self, source: &str, destination: &str, container: Option<&str>) -> Result<()> {
+        let name = self.running_instance(container)?;
+        let remote = |path: &str| -> Result<String> {
+            let path = path.split_once(':').map_or(path, |(_, path)| path);
+            Ok(format!("{}:{}", self.destination(&name)?, path))
+        };
+        let (from, to) = if source.contains(':') {
+            (remote(source)?, destination.to_string())
+        } else {
+            (source.to_string(), remote(destination)?)
+        };
+
+        let mut args = vec![
+            "-r".to_string(),
+            "-i".to_string(),
+            self.key_path(&name)?.to_string_lossy().into_owned(),
+        ];
+        args.extend(SSH_OPTIONS.iter().map(|option| option.to_string()));
+        args.extend([from, to]);
+        stream_command("scp", &args)
+    }
+
+    fn status(&self, container: Option<&str>) -> Result<()> {
+        match container {
+            Some(_) => {
+                let name = self.resolve_instance_name(container)?;
+                self.stream_virsh(&["dominfo", &name])
+            }
+            None => self.list(),
+        }
+    }
+
+    fn get_status_report(&self, container: Option<&str>) -> Result<VmStatusReport> {
+        let name = self.resolve_instance_name(container)?;
+        let Some(state) = self.domain_state(&name) else {
+            return Err(VmError::Provider(format!(
+                "libvirt domain '{name}' not found"
+            )));
+        };
+        Ok(VmStatusReport {
+            name,
+            provider: "libvirt".into(),
+            is_running: state == "running",
+            ..Default::default()
+        })
+    }
+
+    fn clock_skew(&self, container: Option<&str>) -> Result<Option<ClockSkew>> {
+        let name = self.running_instance(container)?;
+        let ssh_args = self.ssh_args(&name)?;
+        clock::measure(|| {
+            let output = Command::new("ssh")
+                .args(&ssh_args)
+                .arg(clock::GUEST_TIME_COMMAND)
+                .output()?;
+            if !output.status.success() {
+                return Err(VmError::Provider(format!(
+                    "Failed to read the VM clock: {}",
+                    String::from_utf8_lossy(&output.stderr).trim()
+                )));
+            }
+            Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+        })
+        .map(Some)
+    }
+
+    fn sync_clock(&self, container: Option<&str>) -> Result<()> {
+        let name = self.running_instance(container)?;
+        let output = Command::new("ssh")
+            .args(self.ssh_args(&name)?)
+            .arg(clock::sync_script())
+            .output()?;
+        if !output.status.success() {
+            return Err(VmError::Provider(format!(
+                "Failed to set the VM clock: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        info!(instance = %name, "Synchronized VM clock with the host");
+        Ok(())
+    }
+
+    fn restart(&self, container: Option<&str>) -> Result<()> {
+        let name = self.resolve_instance_name(container)?;
+        if self.is_running(&name) {
+            self.stream_virsh(&["reboot", &name])
+        } else {
+            self.start(Some(&name))
+        }
+    }
+
+    fn provision(&self, container: Option<&str>) -> Result<()> {
+        let name = self.running_instance(container)?;
+
+        // A new instance-id makes cloud-init treat the next boot as a first boot.
+        // The domain is power-cycled so QEMU reopens the regenerated seed image.
+        let instance_id = format!("{name}-{}", chrono::Utc::now().timestamp());
+        self.write_seed(&name, &instance_id)?;
+        let status = Command::new("ssh")
+            .args(self.ssh_args(&name)?)
+            .args(["sudo", "cloud-init", "clean", "--logs"])
+            .status()?;
+        if !status.success() {
+            return Err(VmError::Provider(
+                "Failed to reset cloud-init in the VM".to_string(),
+            ));
+        }
+        self.virsh_output(&["destroy", &name])?;
+        self.virsh_output(&["start", &name])?;
+        self.wait_for_provisioning(&name)
+    }
+
+    fn list(&self) -> Result<()> {
+        self.stream_virsh(&["list", "--all"])
+    }
+
+    fn kill(&self, container: Option<&str>) -> Result<()> {
+        let name = self.resolve_instance_name(container)?;
+        warn!("Force stopping libvirt domain: {}", name);
+        self.virsh_output(&["destroy", &name])?;
+        Ok(())
+    }
+
+    fn get_sync_directory(&self) -> String {
+        self.config
+            .project
+            .as_ref()
+            .and_then(|p| p.workspace_path.as_deref())
+            .unwrap_or("/workspace")
+            .to_string()
+    }
+
+    fn supports_multi_instance(&self) -> bool {
+        true
+    }
+
+    fn list_instances(&self) -> Result<Vec<InstanceInfo>> {
+        let storage = self.storage_dir()?;
+        let names = self.virsh_output(&["list", "--all", "--name"])?;
+        Ok(names
+            .lines()
+            .map(str::trim)
+            // Only domains vm created have a directory under the storage path
+            .filter(|name| !name.is_empty() && storage.join(name).is_dir())
+            .map(|name| InstanceInfo {
+                name: name.to_string(),
+                id: name.to_string(),
+                status: self.domain_state(name).unwrap_or_default(),
+                provider: "libvirt".to_string(),
+                project: name.strip_suffix("-dev").map(str::to_string),
+                uptime: None,
+                created_at: None,
+            })
+            .collect())
+    }
+
+    fn resolve_instance_name(&self, instance: Option<&str>) -> Result<String> {
+        let project = extract_project_name(&self.config);
+        match instance {
+            None => Ok(format!("{project}-dev")),
+            Some(name) if self.domain_state(name).is_some() => Ok(name.to_string()),
+            Some(name) => Ok(format!("{project}-{name}")),
+        }
+    }
+
+    fn clone_box(&self) -> Box<dyn Provider> {
+        Box::new(self.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cloud_image_url() {
+        assert_eq!(
+            cloud_image_url(None, "x86_64").unwrap(),
+            "https://cloud-images.ubuntu.com/noble/current/noble-server-cloudimg-amd64.img"
+        );
+        assert_eq!(
+            cloud_image_url(Some("ubuntu:22.04"), "aarch64").unwrap(),
+            "https://cloud-images.ubuntu.com/jammy/current/jammy-server-cloudimg-arm64.img"
+        );
+        assert_eq!(
+            cloud_image_url(Some("https://example.com/fedora.qcow2"), "x86_64").unwrap(),
+            "https://example.com/fedora.qcow2"
+        );
+        assert!(cloud_image_url(Some("node:20"), "x86_64").is_err());
+    }
+
+    #[test]
+    fn test_cached_image_name_depends_on_the_url() {
+        let fedora = cached_image_name("https://example.com/fedora/disk.qcow2");
+        let alma = cached_image_name("https://example.com/alma/disk.qcow2");
+        assert!(fedora.ends_with("-disk.qcow2"));
+        assert_ne!(fedora, alma);
+    }
+
+    #[test]
+    fn test_parse_domifaddr() {
+        let output = " Name       MAC address          Protocol     Address
+-------------------------------------------------------------------------------
+ vnet0      52:54:00:6b:3c:58    ipv4         192.168.122.57/24
+";
+        assert_eq!(parse_domifaddr(output).as_deref(), Some("192.168.122.57"));
+        assert_eq!(parse_domifaddr(""), None);
+    }
+
+    #[test]
+    fn test_shell_quote() {
+        assert_eq!(shell_quote("it's"), "'it'\"'\"'s'");
+        assert_eq!(shell_quote("$HOME"), "'$HOME'");
+    }
+}
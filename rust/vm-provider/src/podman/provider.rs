@@ -8,8 +8,9 @@ use vm_core::command_stream::is_tool_installed;
 use vm_core::error::{Result, VmError};
 
 use crate::{
-    context::ProviderContext, docker::DockerProvider, InstanceInfo, Provider, SnapshotRequest,
-    SnapshotRestoreRequest, TempProvider, VmStatusReport,
+    context::ProviderContext, docker::DockerProvider, ExecutionPlan, InstanceInfo, LogsRequest,
+    PlanAction, Provider, ResourceLimits, SnapshotRequest, SnapshotRestoreRequest, TempProvider,
+    VmStatusReport,
 };
 use vm_config::config::VmConfig;
 
@@ -137,6 +138,10 @@ impl Provider for PodmanProvider {
         self.docker_provider.exec(container, cmd)
     }
 
+    fn exec_script(&self, container: Option<&str>, script: &[u8], args: &[String]) -> Result<i32> {
+        self.docker_provider.exec_script(container, script, args)
+    }
+
     fn logs(&self, container: Option<&str>) -> Result<()> {
         self.docker_provider.logs(container)
     }
@@ -144,13 +149,11 @@ impl Provider for PodmanProvider {
     fn logs_extended(
         &self,
         container: Option<&str>,
-        follow: bool,
-        tail: usize,
-        service: Option<&str>,
+        request: &LogsRequest,
         config: &VmConfig,
     ) -> Result<()> {
         self.docker_provider
-            .logs_extended(container, follow, tail, service, config)
+            .logs_extended(container, request, config)
     }
 
     fn copy(&self, source: &str, destination: &str, container: Option<&str>) -> Result<()> {
@@ -197,6 +200,23 @@ impl Provider for PodmanProvider {
         Ok(report)
     }
 
+    fn set_resource_limits(&self, container: Option<&str>, limits: &ResourceLimits) -> Result<()> {
+        self.docker_provider.set_resource_limits(container, limits)
+    }
+
+    fn port_labels(&self, container: Option<&str>) -> Result<Vec<vm_core::labels::PortLabel>> {
+        self.docker_provider.port_labels(container)
+    }
+
+    fn plan(
+        &self,
+        action: PlanAction,
+        container: Option<&str>,
+        context: &ProviderContext,
+    ) -> Result<ExecutionPlan> {
+        self.docker_provider.plan(action, container, context)
+    }
+
     fn get_sync_directory(&self) -> String {
         self.docker_provider.get_sync_directory()
     }
@@ -218,6 +238,10 @@ impl Provider for PodmanProvider {
         self.docker_provider.list_instances()
     }
 
+    fn adopt(&self, container: &str) -> Result<String> {
+        self.docker_provider.adopt(container)
+    }
+
     fn snapshot(&self, request: &SnapshotRequest) -> Result<()> {
         self.docker_provider.snapshot(request)
     }
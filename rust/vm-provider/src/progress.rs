@@ -1,9 +1,10 @@
 use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
 use regex::Regex;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::io::{self, Write};
 use std::sync::{Arc, Mutex, OnceLock};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tracing::info;
 use vm_core::msg;
 use vm_core::vm_println;
@@ -118,6 +119,156 @@ impl ProgressParser for DockerProgressParser {
     }
 }
 
+// --- Provisioning Step Timing --- //
+
+/// Duration of one provisioning step from a completed run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StepTiming {
+    pub name: String,
+    pub secs: f64,
+}
+
+/// Tracks elapsed time per provisioning step and estimates the time remaining
+/// from the previous successful run of the same container and engine.
+///
+/// Timings are persisted in `~/.vm/provision-timings.json`, keyed by
+/// `<container name>:<engine>`, so each instance of a project keeps its own history.
+#[derive(Debug)]
+pub struct StepTimer {
+    key: Option<String>,
+    previous: Vec<StepTiming>,
+    started: Instant,
+    current: Option<(String, Instant)>,
+    completed: Vec<StepTiming>,
+}
+
+impl StepTimer {
+    /// Start timing a run, loading the previous run's timings for `key`
+    pub fn new(key: impl Into<String>) -> Self {
+        let key = key.into();
+        let previous = Self::load_history().remove(&key).unwrap_or_default();
+        Self::with_previous(Some(key), previous)
+    }
+
+    /// Start timing a run against explicit previous timings (nothing is persisted
+    /// when `key` is `None`)
+    pub fn with_previous(key: Option<String>, previous: Vec<StepTiming>) -> Self {
+        Self {
+            key,
+            previous,
+            started: Instant::now(),
+            current: None,
+            completed: Vec::new(),
+        }
+    }
+
+    /// Mark the start of a step, finishing the one in progress
+    pub fn start_step(&mut self, name: &str) {
+        self.finish_step();
+        self.current = Some((name.to_string(), Instant::now()));
+    }
+
+    /// Mark the step in progress as finished and return how long it took
+    pub fn finish_step(&mut self) -> Option<Duration> {
+        let (name, started) = self.current.take()?;
+        let elapsed = started.elapsed();
+        self.completed.push(StepTiming {
+            name,
+            secs: elapsed.as_secs_f64(),
+        });
+        Some(elapsed)
+    }
+
+    /// Number of the step in progress (1-based), counting finished steps
+    pub fn step_number(&self) -> usize {
+        self.completed.len() + usize::from(self.current.is_some())
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    /// Estimated time remaining, based on the previous run.
+    ///
+    /// Steps that already finished in this run are not counted again, and the
+    /// step in progress only contributes whatever is left of its previous duration.
+    pub fn remaining(&self) -> Option<Duration> {
+        self.remaining_at(self.current.as_ref().map(|(_, started)| started.elapsed()))
+    }
+
+    fn remaining_at(&self, current_elapsed: Option<Duration>) -> Option<Duration> {
+        if self.previous.is_empty() {
+            return None;
+        }
+
+        let mut done: Vec<&str> = self.completed.iter().map(|s| s.name.as_str()).collect();
+        let current = self.current.as_ref().map(|(name, _)| name.as_str());
+        let mut secs = 0.0;
+        for step in &self.previous {
+            if let Some(pos) = done.iter().position(|name| *name == step.name) {
+                done.swap_remove(pos);
+            } else if Some(step.name.as_str()) == current {
+                let spent = current_elapsed.unwrap_or_default().as_secs_f64();
+                secs += (step.secs - spent).max(0.0);
+            } else {
+                secs += step.secs;
+            }
+        }
+        Some(Duration::from_secs_f64(secs))
+    }
+
+    /// One-line summary: step number, total elapsed and (when known) time remaining
+    pub fn status_line(&self) -> String {
+        let mut line = msg!(
+            MESSAGES.service.progress_step_timing,
+            step = self.step_number().to_string(),
+            elapsed = format_duration(self.elapsed())
+        );
+        if let Some(remaining) = self.remaining() {
+            line.push_str(&msg!(
+                MESSAGES.service.progress_step_eta,
+                remaining = format_duration(remaining)
+            ));
+        }
+        line
+    }
+
+    /// Record this run's timings as the baseline for the next one
+    pub fn save(&mut self) {
+        self.finish_step();
+        let Some(key) = &self.key else {
+            return;
+        };
+        let Ok(path) = vm_core::user_paths::provision_timings_path() else {
+            return;
+        };
+        if let Some(parent) = path.parent() {
+            let _ = std::fs::create_dir_all(parent);
+        }
+
+        let mut history = Self::load_history();
+        history.insert(key.clone(), self.completed.clone());
+        if let Ok(content) = serde_json::to_string_pretty(&history) {
+            // Timings only improve estimates; failing to persist them is harmless
+            let _ = std::fs::write(path, content);
+        }
+    }
+
+    fn load_history() -> HashMap<String, Vec<StepTiming>> {
+        vm_core::user_paths::provision_timings_path()
+            .ok()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+}
+
+/// Format a duration as `m:ss`
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    format!("{}:{:02}", secs / 60, secs % 60)
+}
+
 // --- Ansible Progress Parser --- //
 
 /// Progress tracking for Ansible playbook execution
@@ -125,6 +276,7 @@ impl ProgressParser for DockerProgressParser {
 pub struct AnsibleProgressParser {
     tasks: Arc<Mutex<Vec<TaskProgress>>>,
     current_task: Arc<Mutex<Option<String>>>,
+    timer: Arc<Mutex<StepTimer>>,
     show_output: bool,
 }
 
@@ -133,6 +285,7 @@ struct TaskProgress {
     name: String,
     status: TaskStatus,
     subtasks: Vec<String>,
+    duration: Option<Duration>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -150,10 +303,32 @@ impl AnsibleProgressParser {
         Self {
             tasks: Arc::new(Mutex::new(Vec::new())),
             current_task: Arc::new(Mutex::new(None)),
+            timer: Arc::new(Mutex::new(StepTimer::with_previous(None, Vec::new()))),
             show_output,
         }
     }
 
+    /// Time each task and estimate the remaining time from the previous run
+    /// recorded under `key`
+    pub fn with_timing(mut self, key: impl Into<String>) -> Self {
+        self.timer = Arc::new(Mutex::new(StepTimer::new(key)));
+        self
+    }
+
+    /// Finish timing the running task and mark it with `status`
+    fn complete_last_task(&self, tasks: &mut [TaskProgress], status: TaskStatus) {
+        if let Some(last_task) = tasks.last_mut() {
+            if last_task.status == TaskStatus::Running {
+                last_task.status = status;
+                last_task.duration = self
+                    .timer
+                    .lock()
+                    .expect("Mutex should not be poisoned")
+                    .finish_step();
+            }
+        }
+    }
+
     fn update_display(&self) {
         if self.show_output {
             return; // In verbose mode, don't show progress
@@ -173,7 +348,12 @@ impl AnsibleProgressParser {
                 TaskStatus::Pending => "  ○",
             };
 
-            println!("{} {}", icon, task.name);
+            match task.duration {
+                Some(duration) if duration.as_secs() > 0 => {
+                    println!("{} {} ({})", icon, task.name, format_duration(duration))
+                }
+                _ => println!("{} {}", icon, task.name),
+            }
 
             // Show subtasks for running task
             if task.status == TaskStatus::Running && !task.subtasks.is_empty() {
@@ -182,6 +362,10 @@ impl AnsibleProgressParser {
                 }
             }
         }
+        drop(tasks);
+
+        let timer = self.timer.lock().expect("Mutex should not be poisoned");
+        println!("\n{}", timer.status_line());
 
         let _ = io::stdout().flush();
     }
@@ -210,18 +394,19 @@ impl ProgressParser for AnsibleProgressParser {
             let mut tasks = self.tasks.lock().expect("Mutex should not be poisoned");
 
             // Mark previous task as completed
-            if let Some(last_task) = tasks.last_mut() {
-                if last_task.status == TaskStatus::Running {
-                    last_task.status = TaskStatus::Completed;
-                }
-            }
+            self.complete_last_task(&mut tasks, TaskStatus::Completed);
 
             // Add new task
             tasks.push(TaskProgress {
                 name: task_name.clone(),
                 status: TaskStatus::Running,
                 subtasks: Vec::new(),
+                duration: None,
             });
+            self.timer
+                .lock()
+                .expect("Mutex should not be poisoned")
+                .start_step(&task_name);
 
             *self
                 .current_task
@@ -233,17 +418,14 @@ impl ProgressParser for AnsibleProgressParser {
         // Detect task completion
         else if line.contains("ok:") || line.contains("changed:") {
             let mut tasks = self.tasks.lock().expect("Mutex should not be poisoned");
-            if let Some(last_task) = tasks.last_mut() {
-                if last_task.status == TaskStatus::Running {
-                    last_task.status = TaskStatus::Completed;
-                }
-            }
+            self.complete_last_task(&mut tasks, TaskStatus::Completed);
             drop(tasks);
             self.update_display();
         }
         // Detect skipped task
         else if line.contains("skipping:") {
             let mut tasks = self.tasks.lock().expect("Mutex should not be poisoned");
+            self.complete_last_task(&mut tasks, TaskStatus::Skipped);
             if let Some(last_task) = tasks.last_mut() {
                 last_task.status = TaskStatus::Skipped;
             }
@@ -290,6 +472,7 @@ impl ProgressParser for AnsibleProgressParser {
         if !self.show_output {
             let mut tasks = self.tasks.lock().expect("Mutex should not be poisoned");
             // Mark any remaining running tasks as completed
+            self.complete_last_task(&mut tasks, TaskStatus::Completed);
             for task in tasks.iter_mut() {
                 if task.status == TaskStatus::Running {
                     task.status = TaskStatus::Completed;
@@ -297,6 +480,10 @@ impl ProgressParser for AnsibleProgressParser {
             }
             drop(tasks);
             self.update_display();
+            self.timer
+                .lock()
+                .expect("Mutex should not be poisoned")
+                .save();
             vm_println!("{}", MESSAGES.service.progress_provisioning_complete);
         }
     }
@@ -334,6 +521,15 @@ impl ProgressReporter {
         pb
     }
 
+    /// Spinner for a single provisioning step; the message carries its timing
+    pub fn start_step(&self, name: &str) -> ProgressBar {
+        let pb = self.mp.add(ProgressBar::new_spinner());
+        pb.set_style(self.style.clone());
+        pb.set_prefix(name.to_string());
+        pb.enable_steady_tick(Duration::from_millis(100));
+        pb
+    }
+
     pub fn task(phase_pb: &ProgressBar, msg: &str) {
         phase_pb.set_message(format!("- {msg}"));
     }
@@ -507,3 +703,54 @@ pub fn confirm_prompt(message: &str) -> bool {
         Err(_) => false,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timing(name: &str, secs: f64) -> StepTiming {
+        StepTiming {
+            name: name.to_string(),
+            secs,
+        }
+    }
+
+    #[test]
+    fn test_step_timer_has_no_estimate_without_history() {
+        let mut timer = StepTimer::with_previous(None, Vec::new());
+        timer.start_step("Install packages");
+        assert_eq!(timer.remaining(), None);
+        assert_eq!(timer.step_number(), 1);
+    }
+
+    #[test]
+    fn test_step_timer_estimate_skips_finished_steps() {
+        let previous = vec![
+            timing("Set timezone", 2.0),
+            timing("Install packages", 30.0),
+            timing("Configure shell", 5.0),
+        ];
+        let mut timer = StepTimer::with_previous(None, previous);
+
+        timer.start_step("Set timezone");
+        timer.finish_step();
+        timer.start_step("Install packages");
+
+        assert_eq!(timer.step_number(), 2);
+        assert_eq!(
+            timer.remaining_at(Some(Duration::from_secs(10))),
+            Some(Duration::from_secs(25))
+        );
+        // A step running longer than last time contributes nothing
+        assert_eq!(
+            timer.remaining_at(Some(Duration::from_secs(60))),
+            Some(Duration::from_secs(5))
+        );
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(Duration::from_secs(5)), "0:05");
+        assert_eq!(format_duration(Duration::from_secs(125)), "2:05");
+    }
+}
@@ -8,6 +8,7 @@ use vm_core::error::Result;
 pub const ANSIBLE_PLAYBOOK: &str = include_str!("resources/ansible/playbook.yml");
 pub const MANAGE_SERVICE_TASK: &str = include_str!("resources/ansible/tasks/manage-service.yml");
 pub const SERVICE_DEFINITIONS: &str = include_str!("resources/services/service_definitions.yml");
+pub const NATIVE_SERVICES: &str = include_str!("resources/services/native_services.yml");
 pub const ZSHRC_TEMPLATE: &str = include_str!("resources/templates/zshrc.j2");
 pub const THEMES_JSON: &str = include_str!("resources/templates/themes.json");
 pub const CLAUDE_SETTINGS_TEMPLATE: &str =
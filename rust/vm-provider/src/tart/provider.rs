@@ -3,7 +3,9 @@ use super::{
     provisioner::TartProvisioner,
 };
 use crate::{
+    clock::{self, ClockSkew},
     common::instance::{extract_project_name, InstanceInfo, InstanceResolver},
+    common::script,
     context::ProviderContext,
     progress::ProgressReporter,
     security::SecurityValidator,
@@ -58,6 +60,39 @@ impl TartProvider {
         input.replace('\'', "'\"'\"'")
     }
 
+    /// `tart exec` arguments that run a command from the sync directory
+    fn sync_dir_exec_args(
+        &self,
+        container: Option<&str>,
+        attach_stdin: bool,
+    ) -> Result<Vec<String>> {
+        let vm_name = self.vm_name_with_instance(container)?;
+        let shell = self
+            .config
+            .terminal
+            .as_ref()
+            .and_then(|t| t.shell.as_deref())
+            .unwrap_or("zsh");
+        let sync_dir = self.get_sync_directory();
+        let sync_dir_escaped = Self::shell_escape_single_quotes(&sync_dir);
+
+        let mut args: Vec<String> = vec!["exec".to_string()];
+        if attach_stdin {
+            args.push("-i".to_string());
+        }
+        args.extend([
+            vm_name,
+            shell.to_string(),
+            "-ilc".to_string(),
+            format!(
+                "cd '{sync_dir}' && exec \"$@\"",
+                sync_dir = sync_dir_escaped
+            ),
+            "vm-exec".to_string(),
+        ]);
+        Ok(args)
+    }
+
     pub fn new(config: VmConfig) -> Result<Self> {
         if !is_tool_installed("tart") {
             return Err(VmError::Dependency("Tart".into()));
@@ -140,6 +175,18 @@ impl TartProvider {
         }
     }
 
+    /// Resolve `container` to an instance whose guest agent accepts commands
+    fn running_instance(&self, container: Option<&str>) -> Result<String> {
+        let instance_name = self.resolve_instance_name(container)?;
+        if !self.is_instance_running(&instance_name)? || !self.is_guest_agent_ready(&instance_name)
+        {
+            return Err(VmError::Provider(format!(
+                "Tart VM '{instance_name}' is not running"
+            )));
+        }
+        Ok(instance_name)
+    }
+
     fn collect_metrics(&self, instance: &str) -> Result<CollectedMetrics> {
         let metrics_script = include_str!("scripts/collect_metrics.sh");
         let output = cmd!("tart", "exec", instance, "sh", "-c", metrics_script)
@@ -355,6 +402,7 @@ impl TartProvider {
             None => "Creating Tart VM".to_string(),
         };
         let main_phase = progress.start_phase(&phase_msg);
+        crate::devices::preflight(config, "tart")?;
 
         // Check if VM already exists
         ProgressReporter::task(&main_phase, "Checking if VM exists...");
@@ -515,6 +563,44 @@ impl TartProvider {
     }
 }
 
+/// Writes `$1` as a tar stream on stdout
+const TAR_PACK: &str = r#"cd "$(dirname "$1")" && exec tar -cf - "$(basename "$1")""#;
+
+/// Unpacks the tar stream on stdin like `cp -R`: into `$1` when it is a directory,
+/// otherwise as `$1` itself. `$2` is the name of the archived entry.
+const TAR_UNPACK: &str = r#"if [ -d "$1" ]; then exec tar -C "$1" -xpf -; fi
+tmp=$(mktemp -d) || exit 1
+trap 'rm -rf "$tmp"' EXIT
+mkdir -p "$(dirname "$1")" && tar -C "$tmp" -xpf - && mv "$tmp/$2" "$1""#;
+
+/// Final path component, which names the entry inside the tar stream
+fn entry_name(path: &str) -> Result<String> {
+    Path::new(path.trim_end_matches('/'))
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .ok_or_else(|| VmError::Provider(format!("Cannot copy '{path}': no file name")))
+}
+
+/// Run `pack | unpack`, failing if either side fails
+fn pipe_tar(pack: &mut Command, unpack: &mut Command) -> std::io::Result<()> {
+    let mut producer = pack.stdout(Stdio::piped()).spawn()?;
+    let stream = producer.stdout.take().ok_or_else(|| {
+        std::io::Error::new(std::io::ErrorKind::Other, "tar produced no output stream")
+    })?;
+    let consumer = unpack.stdin(stream).status();
+    let produced = producer.wait()?;
+    let consumed = consumer?;
+    for (side, status) in [("archive", produced), ("extract", consumed)] {
+        if !status.success() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!("{side} step failed ({status})"),
+            ));
+        }
+    }
+    Ok(())
+}
+
 impl Provider for TartProvider {
     fn name(&self) -> &'static str {
         "tart"
@@ -661,32 +747,18 @@ impl Provider for TartProvider {
     }
 
     fn exec(&self, container: Option<&str>, cmd: &[String]) -> Result<()> {
-        let vm_name = self.vm_name_with_instance(container)?;
-        let shell = self
-            .config
-            .terminal
-            .as_ref()
-            .and_then(|t| t.shell.as_deref())
-            .unwrap_or("zsh");
-        let sync_dir = self.get_sync_directory();
-        let sync_dir_escaped = Self::shell_escape_single_quotes(&sync_dir);
-
-        let mut args: Vec<String> = vec![
-            "exec".to_string(),
-            vm_name,
-            shell.to_string(),
-            "-ilc".to_string(),
-            format!(
-                "cd '{sync_dir}' && exec \"$@\"",
-                sync_dir = sync_dir_escaped
-            ),
-            "vm-exec".to_string(),
-        ];
+        let mut args = self.sync_dir_exec_args(container, false)?;
         args.extend(cmd.iter().cloned());
         let arg_refs: Vec<&str> = args.iter().map(String::as_str).collect();
         stream_command("tart", &arg_refs)
     }
 
+    fn exec_script(&self, container: Option<&str>, script: &[u8], args: &[String]) -> Result<i32> {
+        let mut tart_args = self.sync_dir_exec_args(container, true)?;
+        tart_args.extend(script::runner_command(args));
+        script::run_with_script("tart", &tart_args, script)
+    }
+
     fn logs(&self, container: Option<&str>) -> Result<()> {
         let vm_name = self.vm_name_with_instance(container)?;
         // Try to read logs from ~/.tart/vms/{name}/app.log. The Tart log lives
@@ -750,36 +822,32 @@ impl Provider for TartProvider {
             (source, destination, true)
         };
 
-        // Use scp-like approach via tart exec
+        // Stream a tar archive through `tart exec` so directories and permissions survive
         if is_upload {
             // Upload: local -> VM
-            let copy_cmd = format!("cat > '{}'", remote_path.replace('\'', "'\"'\"'"));
-            let output = cmd!(
-                "sh",
-                "-c",
-                format!(
-                    "cat '{}' | tart exec {} sh -c \"{}\"",
-                    local_path.replace('\'', "'\"'\"'"),
-                    vm_name,
-                    copy_cmd
-                )
-            )
-            .run();
-
-            output.map_err(|e| VmError::Provider(format!("Failed to copy file to VM: {}", e)))?;
+            let local = Path::new(local_path);
+            let name = entry_name(local_path)?;
+            let parent = local
+                .parent()
+                .filter(|p| !p.as_os_str().is_empty())
+                .unwrap_or(Path::new("."));
+            let mut pack = Command::new("tar");
+            pack.arg("-C").arg(parent).args(["-cf", "-", &name]);
+            let mut unpack = Command::new("tart");
+            unpack.args(["exec", "-i", &vm_name, "sh", "-c", TAR_UNPACK, "sh"]);
+            unpack.args([remote_path, &name]);
+            pipe_tar(&mut pack, &mut unpack)
+                .map_err(|e| VmError::Provider(format!("Failed to copy file to VM: {e}")))
         } else {
             // Download: VM -> local
-            let copy_cmd = format!("cat '{}'", remote_path.replace('\'', "'\"'\"'"));
-            let result = cmd!("tart", "exec", &vm_name, "sh", "-c", &copy_cmd)
-                .stdout_capture()
-                .run()
-                .map_err(|e| VmError::Provider(format!("Failed to read file from VM: {}", e)))?;
-
-            std::fs::write(local_path, result.stdout)
-                .map_err(|e| VmError::Provider(format!("Failed to write local file: {}", e)))?;
+            let name = entry_name(remote_path)?;
+            let mut pack = Command::new("tart");
+            pack.args(["exec", &vm_name, "sh", "-c", TAR_PACK, "sh", remote_path]);
+            let mut unpack = Command::new("sh");
+            unpack.args(["-c", TAR_UNPACK, "sh", local_path, &name]);
+            pipe_tar(&mut pack, &mut unpack)
+                .map_err(|e| VmError::Provider(format!("Failed to copy file from VM: {e}")))
         }
-
-        Ok(())
     }
 
     fn status(&self, container: Option<&str>) -> Result<()> {
@@ -848,6 +916,42 @@ impl Provider for TartProvider {
         })
     }
 
+    fn clock_skew(&self, container: Option<&str>) -> Result<Option<ClockSkew>> {
+        let instance_name = self.running_instance(container)?;
+        clock::measure(|| {
+            cmd!(
+                "tart",
+                "exec",
+                &instance_name,
+                "sh",
+                "-c",
+                clock::GUEST_TIME_COMMAND
+            )
+            .stderr_capture()
+            .read()
+            .map_err(|e| VmError::Provider(format!("Failed to read the VM clock: {e}")))
+        })
+        .map(Some)
+    }
+
+    fn sync_clock(&self, container: Option<&str>) -> Result<()> {
+        let instance_name = self.running_instance(container)?;
+        cmd!(
+            "tart",
+            "exec",
+            &instance_name,
+            "sh",
+            "-c",
+            clock::sync_script()
+        )
+        .stderr_capture()
+        .stdout_null()
+        .run()
+        .map_err(|e| VmError::Provider(format!("Failed to set the VM clock: {e}")))?;
+        info!(instance = %instance_name, "Synchronized VM clock with the host");
+        Ok(())
+    }
+
     fn start_with_context(&self, container: Option<&str>, context: &ProviderContext) -> Result<()> {
         let instance_name = self.resolve_instance_name(container)?;
 
@@ -2,11 +2,8 @@ use super::host_sync::{
     collect_host_sync_mounts, expand_tilde, file_name, resolve_guest_home_path, resolve_home_dir,
 };
 use super::provider::tart_run_log_path;
-use crate::{THEMES_JSON, ZSHRC_TEMPLATE};
 use duct::cmd;
-use serde_json::json;
 use std::path::Path;
-use tera::{Context, Tera};
 use tracing::{info, warn};
 use vm_config::config::{BoxSpec, VmConfig};
 use vm_core::error::{Result, VmError};
@@ -419,97 +416,11 @@ fi"#,
     }
 
     fn render_canonical_zshrc(config: &VmConfig, project_path: &str) -> Result<String> {
-        let mut tera = Tera::default();
-        tera.add_raw_template("zshrc", ZSHRC_TEMPLATE)
-            .map_err(|e| VmError::Internal(format!("Failed to load zshrc template: {e}")))?;
-
-        let themes: serde_json::Value = serde_json::from_str(THEMES_JSON)
-            .map_err(|e| VmError::Internal(format!("Failed to parse themes.json: {e}")))?;
-
-        let terminal = config.terminal.clone().unwrap_or_default();
-        let theme_name = terminal.theme.unwrap_or_else(|| "dracula".to_string());
-        let colors = themes
-            .get(&theme_name)
-            .and_then(|t| t.get("colors"))
-            .cloned()
-            .or_else(|| themes.get("dracula").and_then(|t| t.get("colors")).cloned())
-            .unwrap_or_else(|| {
-                json!({
-                    "foreground": "#f8f8f2",
-                    "background": "#282a36",
-                    "red": "#ff5555",
-                    "green": "#50fa7b",
-                    "yellow": "#f1fa8c",
-                    "blue": "#bd93f9",
-                    "magenta": "#ff79c6",
-                    "cyan": "#8be9fd",
-                    "bright_black": "#6272a4"
-                })
-            });
-
-        let project_name = config
-            .project
-            .as_ref()
-            .and_then(|p| p.name.clone())
-            .unwrap_or_else(|| Self::default_project_name(project_path));
-        let project_path_b64 = {
-            use base64::Engine;
-            base64::engine::general_purpose::STANDARD.encode(project_path)
-        };
-        let project_aliases = config
-            .aliases
-            .iter()
-            .filter(|(key, _)| {
-                let mut chars = key.chars();
-                matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
-                    && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
-            })
-            .map(|(key, value)| {
-                use base64::Engine;
-                json!({
-                    "key": key,
-                    "value_b64": base64::engine::general_purpose::STANDARD.encode(value)
-                })
-            })
-            .collect::<Vec<_>>();
-        let project_ports = config
-            .ports
-            .mappings
-            .iter()
-            .map(|mapping| {
-                json!({
-                    "key": format!("{}/{}", mapping.host, format!("{:?}", mapping.protocol).to_lowercase()),
-                    "value": mapping.guest
-                })
-            })
-            .collect::<Vec<_>>();
-
-        let mut context = Context::new();
-        context.insert("project_name", &project_name);
-        context.insert("project_path", project_path);
-        context.insert("project_path_b64", &project_path_b64);
-        context.insert(
-            "project_config",
-            &serde_json::to_value(config).unwrap_or_else(|_| json!({})),
-        );
-        context.insert(
-            "terminal_emoji",
-            &terminal
-                .emoji
-                .unwrap_or_else(|| Self::default_terminal_emoji(config).to_string()),
-        );
-        context.insert(
-            "terminal_username",
-            &terminal.username.unwrap_or_else(|| "dev".to_string()),
-        );
-        context.insert("show_git_branch", &terminal.show_git_branch.unwrap_or(true));
-        context.insert("show_timestamp", &terminal.show_timestamp.unwrap_or(false));
-        context.insert("terminal_colors", &colors);
-        context.insert("project_aliases", &project_aliases);
-        context.insert("project_ports", &project_ports);
-
-        tera.render("zshrc", &context)
-            .map_err(|e| VmError::Internal(format!("Failed to render zshrc template: {e}")))
+        crate::common::shell::render_zshrc(
+            config,
+            project_path,
+            Self::default_terminal_emoji(config),
+        )
     }
 
     fn provision_generic_packages(&self, config: &VmConfig) -> Result<()> {
@@ -1074,15 +985,6 @@ fi"#
             _ => "🚀",
         }
     }
-
-    fn default_project_name(project_path: &str) -> String {
-        Path::new(project_path)
-            .file_name()
-            .and_then(|name| name.to_str())
-            .filter(|name| !name.is_empty())
-            .unwrap_or("project")
-            .to_string()
-    }
 }
 
 #[cfg(test)]
@@ -4,6 +4,7 @@ use crate::docker::{execute_docker_compose, execute_docker_streaming, execute_do
 use crate::manager::{SnapshotManager, SnapshotScope};
 use crate::metadata::{ServiceSnapshot, SnapshotMetadata, VolumeSnapshot};
 use crate::optimal_concurrency;
+use crate::secrets;
 use chrono::Utc;
 use futures::stream::{self, StreamExt};
 use rayon::prelude::*;
@@ -81,6 +82,7 @@ pub async fn handle_create(
     build_context: Option<&std::path::Path>,
     build_args: &[String],
     force: bool,
+    allow_secrets: bool,
 ) -> Result<()> {
     let manager = SnapshotManager::new()?;
 
@@ -95,6 +97,7 @@ pub async fn handle_create(
             ctx,
             build_args,
             force,
+            allow_secrets,
         )
         .await;
     }
@@ -330,6 +333,15 @@ pub async fn handle_create(
         .map_err(|e| VmError::filesystem(e, vm_config_file, "copy"))?;
     }
 
+    // Global snapshots are shared as base boxes, so check them for credentials
+    if matches!(scope, SnapshotScope::Global) {
+        if let Err(e) = secrets::check_snapshot_dir(executable, &snapshot_dir, allow_secrets).await
+        {
+            manager.delete_snapshot(scope, snapshot_name)?;
+            return Err(e);
+        }
+    }
+
     // Get git information
     let (git_commit, git_dirty, git_branch) = get_git_info(&project_dir).await?;
 
@@ -377,6 +389,7 @@ fn calculate_directory_size(path: &std::path::Path) -> Result<u64> {
 }
 
 /// Handle snapshot creation from a Dockerfile
+#[allow(clippy::too_many_arguments)]
 async fn handle_create_from_dockerfile(
     executable: &str,
     name: &str,
@@ -385,6 +398,7 @@ async fn handle_create_from_dockerfile(
     build_context: &std::path::Path,
     build_args: &[String],
     force: bool,
+    allow_secrets: bool,
 ) -> Result<()> {
     // Validate Dockerfile exists
     if !dockerfile_path.exists() {
@@ -507,6 +521,11 @@ async fn handle_create_from_dockerfile(
 
     execute_docker_streaming(executable, &["save", &image_tag, "-o", image_path_str]).await?;
 
+    if let Err(e) = secrets::check_snapshot_dir(executable, &snapshot_dir, allow_secrets).await {
+        manager.delete_snapshot(scope, snapshot_name)?;
+        return Err(e);
+    }
+
     // Get image digest
     let digest_output =
         execute_docker_with_output(executable, &["inspect", "--format={{.Id}}", &image_tag])
@@ -4,6 +4,7 @@ use crate::docker::execute_docker_with_output;
 use crate::manager::{SnapshotManager, SnapshotScope};
 use crate::metadata::SnapshotMetadata;
 use crate::optimal_concurrency;
+use crate::secrets;
 use futures::stream::{self, StreamExt};
 use std::path::Path;
 use vm_core::error::{Result, VmError};
@@ -16,6 +17,7 @@ pub async fn handle_export(
     output_path: Option<&Path>,
     compress_level: u8,
     project_override: Option<&str>,
+    allow_secrets: bool,
 ) -> Result<()> {
     let manager = SnapshotManager::new()?;
 
@@ -199,6 +201,8 @@ pub async fn handle_export(
         copy_dir_all(&compose_src, &compose_dest).await?;
     }
 
+    secrets::check_snapshot_dir(executable, &export_build_dir, allow_secrets).await?;
+
     vm_println!("  Compressing snapshot...");
 
     // Stream the tarball into a sibling `.tmp` file first and rename into
@@ -1,6 +1,7 @@
 //! VM snapshot management library
 //!
-//! Provides snapshot creation, restoration, export, and import functionality.
+//! Provides snapshot creation, restoration, export, and import functionality,
+//! plus SBOM generation and secret scanning for the images snapshots capture.
 
 pub mod create;
 pub mod docker;
@@ -9,6 +10,8 @@ pub mod import;
 pub mod manager;
 pub mod metadata;
 pub mod restore;
+pub mod sbom;
+pub mod secrets;
 
 // Re-export key types
 pub use manager::{SnapshotManager, SnapshotScope};
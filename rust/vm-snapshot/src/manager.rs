@@ -53,6 +53,14 @@ impl SnapshotManager {
         }
     }
 
+    /// Directory for SBOMs that aren't tied to a snapshot
+    ///
+    /// Hidden so it never collides with a snapshot name; it has no
+    /// metadata.json, so listings skip it.
+    pub fn sbom_dir(&self, scope: SnapshotScope<'_>) -> PathBuf {
+        self.snapshots_dir.join(scope.project_name()).join(".sbom")
+    }
+
     /// List all snapshots, optionally filtered by project
     pub fn list_snapshots(&self, project_filter: Option<&str>) -> Result<Vec<SnapshotMetadata>> {
         let mut snapshots = Vec::new();
@@ -104,7 +112,7 @@ impl SnapshotManager {
         }
 
         // Sort by creation time, newest first
-        snapshots.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+        snapshots.sort_by_key(|s| std::cmp::Reverse(s.created_at));
 
         Ok(snapshots)
     }
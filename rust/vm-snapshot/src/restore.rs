@@ -82,12 +82,14 @@ pub async fn handle_restore(
     if !metadata.volumes.is_empty() {
         vm_core::vm_println!("Restoring volumes in parallel...");
         let volumes_dir = snapshot_dir.join("volumes");
+        let config_hash = config.vm.config_hash();
 
         // Parallelize volume restoration for 2-4x faster restore
         let volume_futures = metadata.volumes.iter().map(|volume| {
             let volume_name = volume.name.clone();
             let archive_file = volume.archive_file.clone();
             let project_name = project_name.clone();
+            let config_hash = config_hash.clone();
             let volumes_dir = volumes_dir.clone();
 
             async move {
@@ -104,7 +106,15 @@ pub async fn handle_restore(
 
                 // Create volume - ignore "already exists" error since we'll restore over it
                 // This handles both the case where rm failed (volume in use) and force=false
-                let _ = execute_docker(executable, &["volume", "create", &full_volume_name]).await;
+                let mut create_args = vec!["volume".to_string(), "create".to_string()];
+                create_args.extend(vm_core::labels::label_args(
+                    &project_name,
+                    vm_core::labels::DEFAULT_INSTANCE,
+                    &config_hash,
+                ));
+                create_args.push(full_volume_name.clone());
+                let create_args: Vec<&str> = create_args.iter().map(String::as_str).collect();
+                let _ = execute_docker(executable, &create_args).await;
 
                 // Restore volume data with zstd decompression (3-5x faster than gzip)
                 // Support both .tar.zst (new) and .tar.gz (legacy) formats
@@ -0,0 +1,470 @@
+//! Software bill of materials (SBOM) generation for box images
+//!
+//! Packages are collected by running the image in a throwaway container and
+//! querying dpkg, pip and npm (the `builtin` scanner), or by delegating to
+//! `syft` when it is installed. Documents are stored next to snapshots so they
+//! travel with the images they describe.
+
+use crate::docker::execute_docker_with_output;
+use crate::manager::{SnapshotManager, SnapshotScope};
+use crate::metadata::SnapshotMetadata;
+use chrono::Utc;
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+use vm_config::AppConfig;
+use vm_core::error::{Result, VmError};
+
+/// Shell script run inside the image by the builtin scanner.
+///
+/// Each line is `<ecosystem>\t<name>\t<version>[\t<arch>]`.
+const INVENTORY_SCRIPT: &str = r#"
+if command -v dpkg-query >/dev/null 2>&1; then
+  dpkg-query -W -f='deb\t${Package}\t${Version}\t${Architecture}\n' 2>/dev/null
+fi
+for pip in pip3 pip; do
+  if command -v "$pip" >/dev/null 2>&1; then
+    "$pip" list --format=freeze 2>/dev/null | sed -n 's/^\([^=]*\)==\(.*\)$/pypi\t\1\t\2/p'
+    break
+  fi
+done
+if command -v npm >/dev/null 2>&1; then
+  npm ls -g --depth=0 --parseable --long 2>/dev/null | sed -n 's/^[^:]*:\(.*\)@\([^@:]*\)\(:.*\)\{0,1\}$/npm\t\1\t\2/p'
+fi
+true
+"#;
+
+/// SBOM output format
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SbomFormat {
+    Spdx,
+    CycloneDx,
+}
+
+impl SbomFormat {
+    pub fn parse(value: &str) -> Result<Self> {
+        match value {
+            "spdx" => Ok(Self::Spdx),
+            "cyclonedx" => Ok(Self::CycloneDx),
+            other => Err(VmError::validation(
+                format!("Unknown SBOM format '{}'", other),
+                Some("Use 'spdx' or 'cyclonedx'"),
+            )),
+        }
+    }
+
+    /// File extension used when storing documents
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Spdx => "spdx.json",
+            Self::CycloneDx => "cdx.json",
+        }
+    }
+
+    fn syft_output(self) -> &'static str {
+        match self {
+            Self::Spdx => "spdx-json",
+            Self::CycloneDx => "cyclonedx-json",
+        }
+    }
+}
+
+/// A package discovered inside an image
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Package {
+    pub ecosystem: String,
+    pub name: String,
+    pub version: String,
+    pub arch: Option<String>,
+}
+
+impl Package {
+    /// Package URL (purl) identifying this package
+    pub fn purl(&self) -> String {
+        match self.ecosystem.as_str() {
+            "deb" => {
+                let mut purl = format!("pkg:deb/ubuntu/{}@{}", self.name, self.version);
+                if let Some(arch) = &self.arch {
+                    purl.push_str(&format!("?arch={}", arch));
+                }
+                purl
+            }
+            "pypi" => format!(
+                "pkg:pypi/{}@{}",
+                self.name.to_lowercase().replace('_', "-"),
+                self.version
+            ),
+            "npm" => format!(
+                "pkg:npm/{}@{}",
+                self.name.replacen('@', "%40", 1),
+                self.version
+            ),
+            other => format!("pkg:generic/{}/{}@{}", other, self.name, self.version),
+        }
+    }
+}
+
+/// Parse the output of [`INVENTORY_SCRIPT`]
+pub fn parse_inventory(output: &str) -> Vec<Package> {
+    let mut packages: Vec<Package> = output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.trim_end().split('\t');
+            let ecosystem = fields.next()?.trim();
+            let name = fields.next()?.trim();
+            let version = fields.next()?.trim();
+            if ecosystem.is_empty() || name.is_empty() || version.is_empty() {
+                return None;
+            }
+            Some(Package {
+                ecosystem: ecosystem.to_string(),
+                name: name.to_string(),
+                version: version.to_string(),
+                arch: fields
+                    .next()
+                    .map(str::trim)
+                    .filter(|a| !a.is_empty())
+                    .map(str::to_string),
+            })
+        })
+        .collect();
+
+    packages.sort_by(|a, b| (&a.ecosystem, &a.name).cmp(&(&b.ecosystem, &b.name)));
+    packages.dedup();
+    packages
+}
+
+fn spdx_id(package: &Package) -> String {
+    let raw = format!("{}-{}-{}", package.ecosystem, package.name, package.version);
+    let sanitized: String = raw
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '.' || c == '-' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    format!("SPDXRef-Package-{}", sanitized)
+}
+
+/// Render an SPDX 2.3 JSON document
+pub fn render_spdx(image: &str, packages: &[Package]) -> Value {
+    let created = Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+    let spdx_packages: Vec<Value> = packages
+        .iter()
+        .map(|p| {
+            json!({
+                "SPDXID": spdx_id(p),
+                "name": p.name,
+                "versionInfo": p.version,
+                "downloadLocation": "NOASSERTION",
+                "filesAnalyzed": false,
+                "externalRefs": [{
+                    "referenceCategory": "PACKAGE-MANAGER",
+                    "referenceType": "purl",
+                    "referenceLocator": p.purl(),
+                }],
+            })
+        })
+        .collect();
+    let relationships: Vec<Value> = packages
+        .iter()
+        .map(|p| {
+            json!({
+                "spdxElementId": "SPDXRef-DOCUMENT",
+                "relationshipType": "DESCRIBES",
+                "relatedSpdxElement": spdx_id(p),
+            })
+        })
+        .collect();
+
+    json!({
+        "spdxVersion": "SPDX-2.3",
+        "dataLicense": "CC0-1.0",
+        "SPDXID": "SPDXRef-DOCUMENT",
+        "name": image,
+        "documentNamespace": format!("https://goobits.dev/vm/sbom/{}", uuid::Uuid::new_v4()),
+        "creationInfo": {
+            "created": created,
+            "creators": [format!("Tool: vm-{}", env!("CARGO_PKG_VERSION"))],
+        },
+        "packages": spdx_packages,
+        "relationships": relationships,
+    })
+}
+
+/// Render a CycloneDX 1.5 JSON document
+pub fn render_cyclonedx(image: &str, packages: &[Package]) -> Value {
+    let components: Vec<Value> = packages
+        .iter()
+        .map(|p| {
+            json!({
+                "type": "library",
+                "bom-ref": p.purl(),
+                "name": p.name,
+                "version": p.version,
+                "purl": p.purl(),
+            })
+        })
+        .collect();
+
+    json!({
+        "bomFormat": "CycloneDX",
+        "specVersion": "1.5",
+        "serialNumber": format!("urn:uuid:{}", uuid::Uuid::new_v4()),
+        "version": 1,
+        "metadata": {
+            "timestamp": Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            "tools": [{ "vendor": "goobits", "name": "vm", "version": env!("CARGO_PKG_VERSION") }],
+            "component": { "type": "container", "name": image },
+        },
+        "components": components,
+    })
+}
+
+/// Make an image reference safe to use in a file name
+fn sanitize_image(image: &str) -> String {
+    image
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '.' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Inventory an image's packages with the builtin scanner
+pub async fn collect_packages(executable: &str, image: &str) -> Result<Vec<Package>> {
+    let output = execute_docker_with_output(
+        executable,
+        &[
+            "run",
+            "--rm",
+            "--network",
+            "none",
+            "--entrypoint",
+            "sh",
+            image,
+            "-c",
+            INVENTORY_SCRIPT,
+        ],
+    )
+    .await?;
+    Ok(parse_inventory(&output))
+}
+
+async fn scan_syft(image: &str, format: SbomFormat) -> Result<Value> {
+    let output = tokio::process::Command::new("syft")
+        .args([image, "-q", "-o", format.syft_output()])
+        .output()
+        .await
+        .map_err(|e| VmError::general(e, "Failed to run syft (is it installed and on PATH?)"))?;
+
+    if !output.status.success() {
+        return Err(VmError::general(
+            std::io::Error::new(std::io::ErrorKind::Other, "syft failed"),
+            format!("syft failed: {}", String::from_utf8_lossy(&output.stderr)),
+        ));
+    }
+
+    serde_json::from_slice(&output.stdout)
+        .map_err(|e| VmError::general(e, "syft produced invalid JSON"))
+}
+
+/// Resolve the image for the project's dev container
+pub async fn project_image(executable: &str, project_name: &str) -> Result<String> {
+    let container = format!("{}-dev", project_name);
+    execute_docker_with_output(
+        executable,
+        &["inspect", "--format", "{{.Config.Image}}", &container],
+    )
+    .await
+    .map_err(|_| {
+        VmError::validation(
+            format!("Container '{}' not found", container),
+            Some("Run 'vm create' first, or pass --image or --snapshot"),
+        )
+    })
+}
+
+/// Pick the image to describe from a snapshot, preferring the dev service
+fn snapshot_image(metadata: &SnapshotMetadata) -> Option<String> {
+    metadata
+        .services
+        .iter()
+        .find(|s| s.name.ends_with("-dev") || s.name == "dev")
+        .or_else(|| metadata.services.first())
+        .map(|s| s.image_tag.clone())
+}
+
+/// Handle `vm sbom`
+#[allow(clippy::too_many_arguments)]
+pub async fn handle_sbom(
+    config: &AppConfig,
+    executable: &str,
+    format: &str,
+    scanner: &str,
+    image: Option<&str>,
+    snapshot: Option<&str>,
+    output: Option<&Path>,
+) -> Result<PathBuf> {
+    let format = SbomFormat::parse(format)?;
+    let project_name = config
+        .vm
+        .project
+        .as_ref()
+        .and_then(|p| p.name.clone())
+        .unwrap_or_else(|| "default".to_string());
+    let manager = SnapshotManager::new()?;
+
+    // Resolve the image and where the document belongs
+    let in_snapshot = image.is_none() && snapshot.is_some();
+    let (image, default_dir) = match (image, snapshot) {
+        (Some(image), _) => (
+            image.to_string(),
+            manager.sbom_dir(SnapshotScope::Project(&project_name)),
+        ),
+        (None, Some(name)) => {
+            let (scope, name) = SnapshotScope::from_name(name, Some(&project_name));
+            let snapshot_dir = manager.get_snapshot_dir(scope, name);
+            let metadata =
+                SnapshotMetadata::load(snapshot_dir.join("metadata.json")).map_err(|_| {
+                    VmError::validation(
+                        format!("Snapshot '{}' not found", name),
+                        Some("Run 'vm snapshot list' to see available snapshots"),
+                    )
+                })?;
+            let image = snapshot_image(&metadata).ok_or_else(|| {
+                VmError::validation(
+                    format!("Snapshot '{}' contains no images", name),
+                    None::<String>,
+                )
+            })?;
+            (image, snapshot_dir)
+        }
+        (None, None) => (
+            project_image(executable, &project_name).await?,
+            manager.sbom_dir(SnapshotScope::Project(&project_name)),
+        ),
+    };
+
+    vm_core::vm_println!("🔍 Scanning {} ({})...", image, scanner);
+    let document = match scanner {
+        "builtin" => {
+            let packages = collect_packages(executable, &image).await?;
+            vm_core::vm_println!("  Found {} packages", packages.len());
+            match format {
+                SbomFormat::Spdx => render_spdx(&image, &packages),
+                SbomFormat::CycloneDx => render_cyclonedx(&image, &packages),
+            }
+        }
+        "syft" => scan_syft(&image, format).await?,
+        other => {
+            return Err(VmError::validation(
+                format!("Unknown SBOM scanner '{}'", other),
+                Some("Use 'builtin' or 'syft'"),
+            ))
+        }
+    };
+
+    let path = match output {
+        Some(path) => path.to_path_buf(),
+        None => {
+            // Snapshots keep one SBOM per format next to metadata.json
+            let file_name = if in_snapshot {
+                format!("sbom.{}", format.extension())
+            } else {
+                format!(
+                    "{}-{}.{}",
+                    sanitize_image(&image),
+                    Utc::now().format("%Y%m%d%H%M%S"),
+                    format.extension()
+                )
+            };
+            default_dir.join(file_name)
+        }
+    };
+
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .map_err(|e| VmError::filesystem(e, parent.to_string_lossy(), "create_dir_all"))?;
+    }
+    let contents = serde_json::to_string_pretty(&document)
+        .map_err(|e| VmError::general(e, "Failed to serialize SBOM"))?;
+    tokio::fs::write(&path, contents)
+        .await
+        .map_err(|e| VmError::filesystem(e, path.to_string_lossy(), "write"))?;
+
+    vm_core::vm_println!("✅ SBOM written to {}", path.display());
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Vec<Package> {
+        parse_inventory(
+            "deb\tcurl\t7.81.0-1ubuntu1.15\tamd64\n\
+             pypi\tPyYAML\t6.0.1\n\
+             npm\t@anthropic-ai/sdk\t0.20.0\n\
+             garbage line\n\
+             deb\tcurl\t7.81.0-1ubuntu1.15\tamd64\n",
+        )
+    }
+
+    #[test]
+    fn test_parse_inventory_skips_invalid_and_duplicate_lines() {
+        let packages = sample();
+        assert_eq!(packages.len(), 3);
+        assert_eq!(packages[0].name, "curl");
+        assert_eq!(packages[0].arch.as_deref(), Some("amd64"));
+        assert_eq!(packages[1].ecosystem, "npm");
+        assert_eq!(packages[2].ecosystem, "pypi");
+    }
+
+    #[test]
+    fn test_package_purls() {
+        let packages = sample();
+        assert_eq!(
+            packages[0].purl(),
+            "pkg:deb/ubuntu/curl@7.81.0-1ubuntu1.15?arch=amd64"
+        );
+        assert_eq!(packages[1].purl(), "pkg:npm/%40anthropic-ai/sdk@0.20.0");
+        assert_eq!(packages[2].purl(), "pkg:pypi/pyyaml@6.0.1");
+    }
+
+    #[test]
+    fn test_render_documents() {
+        let packages = sample();
+
+        let spdx = render_spdx("myproject-image", &packages);
+        assert_eq!(spdx["spdxVersion"], "SPDX-2.3");
+        assert_eq!(spdx["packages"].as_array().unwrap().len(), 3);
+        assert_eq!(spdx["relationships"].as_array().unwrap().len(), 3);
+        assert_eq!(
+            spdx["packages"][1]["SPDXID"],
+            "SPDXRef-Package-npm--anthropic-ai-sdk-0.20.0"
+        );
+
+        let cdx = render_cyclonedx("myproject-image", &packages);
+        assert_eq!(cdx["bomFormat"], "CycloneDX");
+        assert_eq!(cdx["metadata"]["component"]["name"], "myproject-image");
+        assert_eq!(cdx["components"][2]["purl"], "pkg:pypi/pyyaml@6.0.1");
+    }
+
+    #[test]
+    fn test_format_parse() {
+        assert_eq!(SbomFormat::parse("spdx").unwrap(), SbomFormat::Spdx);
+        assert_eq!(
+            SbomFormat::parse("cyclonedx").unwrap().extension(),
+            "cdx.json"
+        );
+        assert!(SbomFormat::parse("xml").is_err());
+    }
+}
@@ -0,0 +1,329 @@
+//! Shared state store for the VM tool
+//!
+//! Port allocations, temp VM state and service reference counts all live in one
+//! SQLite database (`~/.vm/state.db`) instead of separate JSON and YAML files.
+//! Each kind of state is a namespace of JSON documents keyed by name. Changes go
+//! through [`StateStore::transaction`], which takes SQLite's write lock up front,
+//! so concurrent `vm` processes see each other's updates whole and commands that
+//! read several namespaces (list, prune, dashboards) get a consistent view.
+//!
+//! | Namespace   | Key           | Value                                  |
+//! |-------------|---------------|----------------------------------------|
+//! | `ports`     | Project name  | Allocated port range and project path  |
+//! | `services`  | Service name  | Reference count and registered VMs     |
+//! | `temp_vms`  | `current`     | The temp VM's container and mounts     |
+
+use rusqlite::{params, Connection, OptionalExtension, TransactionBehavior};
+use serde::{de::DeserializeOwned, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::{info, warn};
+use vm_core::error::{Result, VmError};
+use vm_core::user_paths;
+
+/// Port range allocations, keyed by project name
+pub const PORTS: &str = "ports";
+/// Shared service state, keyed by service name
+pub const SERVICES: &str = "services";
+/// Temp VM state, stored under a single key
+pub const TEMP_VMS: &str = "temp_vms";
+
+/// How long to wait for another process's write transaction before giving up
+const BUSY_TIMEOUT: Duration = Duration::from_secs(30);
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS entries (
+        namespace  TEXT NOT NULL,
+        key        TEXT NOT NULL,
+        value      TEXT NOT NULL,
+        updated_at INTEGER NOT NULL,
+        PRIMARY KEY (namespace, key)
+    );
+";
+
+/// Connection to the state database
+pub struct StateStore {
+    conn: Connection,
+    path: PathBuf,
+}
+
+impl StateStore {
+    /// Open the state database in the default location (`~/.vm/state.db`)
+    pub fn open_default() -> Result<Self> {
+        Self::open(&user_paths::state_db_path()?)
+    }
+
+    /// Open (or create) the state database at `path`
+    pub fn open(path: &Path) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let conn = Connection::open(path).map_err(state_error)?;
+        conn.busy_timeout(BUSY_TIMEOUT).map_err(state_error)?;
+        // WAL lets readers proceed while another process holds the write lock
+        conn.pragma_update(None, "journal_mode", "WAL")
+            .map_err(state_error)?;
+        conn.execute_batch(SCHEMA).map_err(state_error)?;
+        Ok(Self {
+            conn,
+            path: path.to_path_buf(),
+        })
+    }
+
+    /// Path of the database file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Read and deserialize one entry
+    pub fn get<T: DeserializeOwned>(&self, namespace: &str, key: &str) -> Result<Option<T>> {
+        get(&self.conn, namespace, key)
+    }
+
+    /// All entries in `namespace`, sorted by key
+    pub fn list<T: DeserializeOwned>(&self, namespace: &str) -> Result<Vec<(String, T)>> {
+        list(&self.conn, namespace)
+    }
+
+    /// Run `f` in a write transaction, committing if it returns `Ok`.
+    ///
+    /// The write lock is taken when the transaction starts, so values read inside
+    /// `f` can't change underneath it.
+    pub fn transaction<T, F>(&mut self, f: F) -> Result<T>
+    where
+        F: FnOnce(&StateTransaction<'_>) -> Result<T>,
+    {
+        let tx = self
+            .conn
+            .transaction_with_behavior(TransactionBehavior::Immediate)
+            .map_err(state_error)?;
+        let state_tx = StateTransaction { tx };
+        let value = f(&state_tx)?;
+        state_tx.tx.commit().map_err(state_error)?;
+        Ok(value)
+    }
+
+    /// Import a legacy state file into `namespace` the first time it is seen.
+    ///
+    /// `parse` turns the file's contents into entries. The file is renamed to
+    /// `<name>.migrated` afterwards so it is only imported once. A namespace that
+    /// already has entries is left alone.
+    pub fn import_legacy_file<F>(&mut self, namespace: &str, path: &Path, parse: F) -> Result<()>
+    where
+        F: FnOnce(&str) -> Result<Vec<(String, serde_json::Value)>>,
+    {
+        if !path.exists() {
+            return Ok(());
+        }
+
+        let imported = self.transaction(|tx| {
+            if !tx.list_raw(namespace)?.is_empty() {
+                return Ok(false);
+            }
+            let content = std::fs::read_to_string(path)?;
+            let entries = match parse(&content) {
+                Ok(entries) => entries,
+                Err(e) => {
+                    warn!("Skipping unreadable legacy state {}: {}", path.display(), e);
+                    return Ok(false);
+                }
+            };
+            for (key, value) in entries {
+                tx.put(namespace, &key, &value)?;
+            }
+            Ok(true)
+        })?;
+
+        if imported {
+            info!("Imported {} into {}", path.display(), self.path.display());
+            let mut migrated = path.as_os_str().to_owned();
+            migrated.push(".migrated");
+            if let Err(e) = std::fs::rename(path, &migrated) {
+                warn!("Failed to set aside {}: {}", path.display(), e);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Write transaction on the state database
+pub struct StateTransaction<'a> {
+    tx: rusqlite::Transaction<'a>,
+}
+
+impl StateTransaction<'_> {
+    /// Read and deserialize one entry
+    pub fn get<T: DeserializeOwned>(&self, namespace: &str, key: &str) -> Result<Option<T>> {
+        get(&self.tx, namespace, key)
+    }
+
+    /// All entries in `namespace`, sorted by key
+    pub fn list<T: DeserializeOwned>(&self, namespace: &str) -> Result<Vec<(String, T)>> {
+        list(&self.tx, namespace)
+    }
+
+    /// Raw JSON text of every entry in `namespace`, sorted by key
+    pub fn list_raw(&self, namespace: &str) -> Result<Vec<(String, String)>> {
+        list_raw(&self.tx, namespace)
+    }
+
+    /// Serialize and store `value`, replacing any existing entry
+    pub fn put<T: Serialize + ?Sized>(&self, namespace: &str, key: &str, value: &T) -> Result<()> {
+        let json = serde_json::to_string(value).map_err(|e| {
+            VmError::Serialization(format!("Failed to serialize {namespace}/{key}: {e}"))
+        })?;
+        self.put_raw(namespace, key, &json)
+    }
+
+    /// Store already-serialized JSON text
+    pub fn put_raw(&self, namespace: &str, key: &str, json: &str) -> Result<()> {
+        self.tx
+            .execute(
+                "INSERT INTO entries (namespace, key, value, updated_at) VALUES (?1, ?2, ?3, ?4)
+                 ON CONFLICT (namespace, key) DO UPDATE
+                 SET value = excluded.value, updated_at = excluded.updated_at",
+                params![namespace, key, json, now()],
+            )
+            .map_err(state_error)?;
+        Ok(())
+    }
+
+    /// Remove an entry, returning whether it existed
+    pub fn delete(&self, namespace: &str, key: &str) -> Result<bool> {
+        let removed = self
+            .tx
+            .execute(
+                "DELETE FROM entries WHERE namespace = ?1 AND key = ?2",
+                params![namespace, key],
+            )
+            .map_err(state_error)?;
+        Ok(removed > 0)
+    }
+
+    /// Remove every entry in `namespace`
+    pub fn clear(&self, namespace: &str) -> Result<()> {
+        self.tx
+            .execute(
+                "DELETE FROM entries WHERE namespace = ?1",
+                params![namespace],
+            )
+            .map_err(state_error)?;
+        Ok(())
+    }
+}
+
+fn get<T: DeserializeOwned>(conn: &Connection, namespace: &str, key: &str) -> Result<Option<T>> {
+    let json: Option<String> = conn
+        .query_row(
+            "SELECT value FROM entries WHERE namespace = ?1 AND key = ?2",
+            params![namespace, key],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(state_error)?;
+    json.map(|json| parse(namespace, key, &json)).transpose()
+}
+
+fn list<T: DeserializeOwned>(conn: &Connection, namespace: &str) -> Result<Vec<(String, T)>> {
+    list_raw(conn, namespace)?
+        .into_iter()
+        .map(|(key, json)| {
+            let value = parse(namespace, &key, &json)?;
+            Ok((key, value))
+        })
+        .collect()
+}
+
+fn list_raw(conn: &Connection, namespace: &str) -> Result<Vec<(String, String)>> {
+    let mut stmt = conn
+        .prepare("SELECT key, value FROM entries WHERE namespace = ?1 ORDER BY key")
+        .map_err(state_error)?;
+    let rows = stmt
+        .query_map(params![namespace], |row| Ok((row.get(0)?, row.get(1)?)))
+        .map_err(state_error)?
+        .collect::<std::result::Result<Vec<(String, String)>, _>>()
+        .map_err(state_error)?;
+    Ok(rows)
+}
+
+fn parse<T: DeserializeOwned>(namespace: &str, key: &str, json: &str) -> Result<T> {
+    serde_json::from_str(json)
+        .map_err(|e| VmError::Serialization(format!("Failed to parse {namespace}/{key}: {e}")))
+}
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+fn state_error(e: rusqlite::Error) -> VmError {
+    VmError::Internal(format!("State database error: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn store() -> (tempfile::TempDir, StateStore) {
+        let dir = tempfile::tempdir().unwrap();
+        let store = StateStore::open(&dir.path().join("state.db")).unwrap();
+        (dir, store)
+    }
+
+    #[test]
+    fn test_entries_round_trip_per_namespace() {
+        let (_dir, mut store) = store();
+        store
+            .transaction(|tx| {
+                tx.put(PORTS, "web", &json!({"range": "3000-3009"}))?;
+                tx.put(PORTS, "api", &json!({"range": "3010-3019"}))?;
+                tx.put(SERVICES, "redis", &json!({"reference_count": 1}))
+            })
+            .unwrap();
+
+        let ports: Vec<(String, serde_json::Value)> = store.list(PORTS).unwrap();
+        let keys: Vec<&str> = ports.iter().map(|(key, _)| key.as_str()).collect();
+        assert_eq!(keys, ["api", "web"]);
+        let redis: Option<serde_json::Value> = store.get(SERVICES, "redis").unwrap();
+        assert_eq!(redis, Some(json!({"reference_count": 1})));
+
+        store.transaction(|tx| tx.delete(PORTS, "web")).unwrap();
+        assert_eq!(store.list::<serde_json::Value>(PORTS).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_failed_transaction_rolls_back() {
+        let (_dir, mut store) = store();
+        let result: Result<()> = store.transaction(|tx| {
+            tx.put(PORTS, "web", &json!({}))?;
+            Err(VmError::Internal("boom".to_string()))
+        });
+        assert!(result.is_err());
+        assert!(store
+            .get::<serde_json::Value>(PORTS, "web")
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_legacy_file_imported_once() {
+        let (dir, mut store) = store();
+        let legacy = dir.path().join("ports.json");
+        std::fs::write(&legacy, r#"{"web": {"range": "3000-3009"}}"#).unwrap();
+
+        let parse = |content: &str| -> Result<Vec<(String, serde_json::Value)>> {
+            let map: serde_json::Map<String, serde_json::Value> =
+                serde_json::from_str(content).unwrap();
+            Ok(map.into_iter().collect())
+        };
+        store.import_legacy_file(PORTS, &legacy, parse).unwrap();
+
+        assert!(!legacy.exists());
+        assert!(dir.path().join("ports.json.migrated").exists());
+        let web: Option<serde_json::Value> = store.get(PORTS, "web").unwrap();
+        assert_eq!(web, Some(json!({"range": "3000-3009"})));
+    }
+}
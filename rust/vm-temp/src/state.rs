@@ -1,21 +1,21 @@
 //! Temporary VM state management.
 //!
 //! This module provides functionality for persisting and managing the state of temporary VMs,
-//! including state file operations, locking mechanisms, and validation.
+//! including persistence in the shared state database and validation.
 
 use crate::TempVmState;
-use fs2::FileExt;
 use serde_yaml_ng as serde_yaml;
-use std::fs::{self, File, OpenOptions};
+use std::fs::{self, OpenOptions};
 use std::io::Write;
 use std::path::{Path, PathBuf};
 use thiserror::Error;
 use vm_core::error::{Result, VmError};
+use vm_state::StateStore;
 
 /// Errors that can occur during state management operations.
 #[derive(Error, Debug)]
 pub enum StateError {
-    #[error("State file not found at {path}")]
+    #[error("No temp VM state in {path}")]
     StateNotFound { path: PathBuf },
     #[error("Invalid state file format: {0}")]
     InvalidFormat(#[from] serde_yaml::Error),
@@ -31,7 +31,7 @@ impl From<StateError> for VmError {
     fn from(err: StateError) -> Self {
         match err {
             StateError::StateNotFound { path } => {
-                VmError::Config(format!("State file not found at {}", path.display()))
+                VmError::Config(format!("No temp VM state in {}", path.display()))
             }
             StateError::InvalidFormat(e) => {
                 VmError::Serialization(format!("Invalid state file format: {e}"))
@@ -45,35 +45,39 @@ impl From<StateError> for VmError {
     }
 }
 
+/// Key of the temp VM entry in the state database
+const CURRENT_KEY: &str = "current";
+
 /// Manages temp VM state persistence and validation
 #[derive(Debug)]
 pub struct StateManager {
     state_dir: PathBuf,
-    state_file: PathBuf,
+    db_path: PathBuf,
     temp_file_registry: PathBuf,
-    lock_file: PathBuf,
 }
 
 impl StateManager {
     /// Creates a new state manager with the default state directory.
     ///
-    /// The default state directory is `~/.vm`.
+    /// The default state directory is `~/.vm`. Temp VM state lives in the shared
+    /// state database (`~/.vm/state.db`); a legacy `~/.vm/temp-vms.json` file is
+    /// imported the first time it is seen.
     ///
     /// # Returns
     /// A `Result` containing the new `StateManager` or an error if initialization fails.
     pub fn new() -> Result<Self> {
         let state_dir = Self::default_state_dir()?;
         fs::create_dir_all(&state_dir)?;
-        let state_file = vm_core::user_paths::temp_vms_state_path()?;
+        let db_path = vm_core::user_paths::state_db_path()?;
         let temp_file_registry = state_dir.join(".temp_files.registry");
-        let lock_file = state_dir.join(".temp-vm.lock");
 
-        Ok(Self {
+        let manager = Self {
             state_dir,
-            state_file,
+            db_path,
             temp_file_registry,
-            lock_file,
-        })
+        };
+        manager.import_legacy_state(&vm_core::user_paths::temp_vms_state_path()?)?;
+        Ok(manager)
     }
 
     /// Creates a new state manager with a custom state directory.
@@ -84,14 +88,12 @@ impl StateManager {
     /// # Returns
     /// A new `StateManager` instance using the specified directory.
     pub fn with_state_dir(state_dir: PathBuf) -> Self {
-        let state_file = state_dir.join("temp-vm.state");
+        let db_path = state_dir.join("state.db");
         let temp_file_registry = state_dir.join(".temp_files.registry");
-        let lock_file = state_dir.join(".temp-vm.lock");
         Self {
             state_dir,
-            state_file,
+            db_path,
             temp_file_registry,
-            lock_file,
         }
     }
 
@@ -106,140 +108,59 @@ impl StateManager {
             .map_err(|e| VmError::Internal(format!("Failed to get VM state directory: {e}")))
     }
 
-    /// Get the state file path
-    pub fn state_file_path(&self) -> &Path {
-        &self.state_file
+    /// Get the path of the state database holding the temp VM state
+    pub fn state_db_path(&self) -> &Path {
+        &self.db_path
     }
 
     /// Check if a temp VM state exists
     pub fn state_exists(&self) -> bool {
-        self.state_file.exists()
+        self.open_store()
+            .and_then(|store| store.get::<serde_json::Value>(vm_state::TEMP_VMS, CURRENT_KEY))
+            .map(|state| state.is_some())
+            .unwrap_or(false)
     }
 
-    /// Acquire an exclusive lock for state operations.
-    ///
-    /// A blocking `lock_exclusive()` would wait forever if a previous process
-    /// holding the lock crashed. We poll `try_lock_exclusive()` instead so the
-    /// CLI fails fast with a clear error rather than hanging indefinitely.
-    fn acquire_lock(&self) -> std::result::Result<File, StateError> {
-        const LOCK_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
-        const POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(100);
-
-        let lock_file = OpenOptions::new()
-            .create(true)
-            .truncate(true)
-            .write(true)
-            .open(&self.lock_file)?;
-
-        let lock_start = std::time::Instant::now();
-        loop {
-            match lock_file.try_lock_exclusive() {
-                Ok(()) => return Ok(lock_file),
-                Err(e) => {
-                    if lock_start.elapsed() > LOCK_TIMEOUT {
-                        return Err(StateError::Vm(VmError::Internal(format!(
-                            "Timed out after {}s waiting for temp VM state lock {}: {}. \
-                             If you're sure no other `vm` process is running, delete the lock file to recover.",
-                            LOCK_TIMEOUT.as_secs(),
-                            self.lock_file.display(),
-                            e
-                        ))));
-                    }
-                    std::thread::sleep(POLL_INTERVAL);
-                }
-            }
-        }
+    fn open_store(&self) -> Result<StateStore> {
+        StateStore::open(&self.db_path)
     }
 
-    /// Load temp VM state from disk
-    pub fn load_state(&self) -> std::result::Result<TempVmState, StateError> {
-        let _lock = self.acquire_lock()?;
-
-        if !self.state_file.exists() {
-            return Err(StateError::StateNotFound {
-                path: self.state_file.clone(),
-            });
-        }
-
-        let content =
-            fs::read_to_string(&self.state_file).map_err(|e| StateError::Vm(VmError::Io(e)))?;
+    /// Import a YAML state file written by older versions
+    fn import_legacy_state(&self, legacy_path: &Path) -> Result<()> {
+        self.open_store()?
+            .import_legacy_file(vm_state::TEMP_VMS, legacy_path, |content| {
+                let state: serde_json::Value = serde_yaml::from_str(content)
+                    .map_err(|e| VmError::from(StateError::InvalidFormat(e)))?;
+                Ok(vec![(CURRENT_KEY.to_string(), state)])
+            })
+    }
 
-        let state: TempVmState = serde_yaml::from_str(&content).map_err(|e| {
-            StateError::Vm(VmError::Serialization(format!(
-                "Failed to parse state file {}: {}",
-                self.state_file.display(),
-                e
-            )))
-        })?;
+    /// Load temp VM state from the state database
+    pub fn load_state(&self) -> std::result::Result<TempVmState, StateError> {
+        let state: TempVmState = self
+            .open_store()?
+            .get(vm_state::TEMP_VMS, CURRENT_KEY)?
+            .ok_or_else(|| StateError::StateNotFound {
+                path: self.db_path.clone(),
+            })?;
 
         Self::validate_state(&state)?;
         Ok(state)
     }
 
-    /// Save temp VM state to disk atomically
+    /// Save temp VM state to the state database in a single transaction
     pub fn save_state(&self, state: &TempVmState) -> std::result::Result<(), StateError> {
-        let _lock = self.acquire_lock()?;
-
         Self::validate_state(state)?;
 
-        // Create state directory if it doesn't exist
-        fs::create_dir_all(&self.state_dir).map_err(|e| {
-            StateError::Vm(VmError::Filesystem(format!(
-                "Failed to create state directory {}: {}",
-                self.state_dir.display(),
-                e
-            )))
-        })?;
-
-        // Serialize state to YAML
-        let yaml_content = serde_yaml::to_string(state).map_err(|e| {
-            StateError::Vm(VmError::Serialization(format!(
-                "Failed to serialize state to YAML: {e}"
-            )))
-        })?;
-
-        // Write atomically using a unique temporary file
-        let temp_file = tempfile::Builder::new()
-            .prefix("temp-vm-state-")
-            .suffix(".tmp")
-            .tempfile_in(&self.state_dir)?;
-
-        temp_file
-            .as_file()
-            .write_all(yaml_content.as_bytes())
-            .map_err(|e| {
-                StateError::Vm(VmError::Filesystem(format!(
-                    "Failed to write temporary state file {}: {}",
-                    temp_file.path().display(),
-                    e
-                )))
-            })?;
-
-        // Atomic move to final location
-        temp_file.persist(&self.state_file).map_err(|e| {
-            StateError::Vm(VmError::Filesystem(format!(
-                "Failed to move state file to final location {}: {}",
-                self.state_file.display(),
-                e.error
-            )))
-        })?;
-
+        self.open_store()?
+            .transaction(|tx| tx.put(vm_state::TEMP_VMS, CURRENT_KEY, state))?;
         Ok(())
     }
 
-    /// Delete the state file
+    /// Delete the temp VM state
     pub fn delete_state(&self) -> std::result::Result<(), StateError> {
-        let _lock = self.acquire_lock()?;
-
-        if self.state_file.exists() {
-            fs::remove_file(&self.state_file).map_err(|e| {
-                StateError::Vm(VmError::Filesystem(format!(
-                    "Failed to delete state file {}: {}",
-                    self.state_file.display(),
-                    e
-                )))
-            })?;
-        }
+        self.open_store()?
+            .transaction(|tx| tx.delete(vm_state::TEMP_VMS, CURRENT_KEY))?;
         Ok(())
     }
 
@@ -414,12 +335,7 @@ impl Default for StateManager {
             Err(_) => {
                 // Fallback to current directory if state directory creation fails
                 let fallback_dir = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
-                Self {
-                    state_dir: fallback_dir.clone(),
-                    state_file: fallback_dir.join(".vm_temp_state.yaml"),
-                    temp_file_registry: fallback_dir.join(".temp_files.registry"),
-                    lock_file: fallback_dir.join(".temp_vm.lock"),
-                }
+                Self::with_state_dir(fallback_dir)
             }
         }
     }
@@ -213,7 +213,7 @@ impl TempVmOps {
             // message and returns a VmError.
             return Err(VmError::Internal(format!(
                 "Config not found at: {}",
-                state_manager.state_file_path().display()
+                state_manager.state_db_path().display()
             )));
         }
 
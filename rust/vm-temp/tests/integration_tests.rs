@@ -1,10 +1,10 @@
-use serde_yaml_ng as serde_yaml;
 use std::fs;
 use std::sync::Arc;
 use std::thread;
 use std::time::Duration;
 use tempfile::TempDir;
 use vm_core::error::Result;
+use vm_state::StateStore;
 use vm_temp::{MountPermission, StateManager, TempVmState};
 
 /// Test fixture for integration testing with real filesystem operations
@@ -15,6 +15,12 @@ struct IntegrationTestFixture {
     mount_source: std::path::PathBuf,
 }
 
+/// Store raw JSON as the temp VM state, bypassing validation
+fn write_raw_state(state_manager: &StateManager, json: &str) -> Result<()> {
+    StateStore::open(state_manager.state_db_path())?
+        .transaction(|tx| tx.put_raw(vm_state::TEMP_VMS, "current", json))
+}
+
 impl IntegrationTestFixture {
     fn new() -> Result<Self> {
         let temp_dir = TempDir::new()?;
@@ -119,9 +125,9 @@ fn test_concurrent_state_operations() -> Result<()> {
         println!("This indicates atomic operations need improvement in StateManager");
     }
 
-    // State file should be valid YAML
-    let state_content = fs::read_to_string(state_manager.state_file_path())?;
-    let _: TempVmState = serde_yaml::from_str(&state_content)?;
+    // Stored state should still deserialize
+    let _: Option<TempVmState> =
+        StateStore::open(state_manager.state_db_path())?.get(vm_state::TEMP_VMS, "current")?;
 
     println!(
         "✅ Concurrent operations test passed - {} mounts added atomically",
@@ -200,9 +206,9 @@ fn test_state_corruption_recovery() -> Result<()> {
     let fixture = IntegrationTestFixture::new()?;
     let state_manager = fixture.create_state_manager();
 
-    // Test 1: Completely malformed YAML
-    let malformed_yaml = "this is not valid yaml: [unclosed bracket";
-    fs::write(state_manager.state_file_path(), malformed_yaml)?;
+    // Test 1: Completely malformed JSON
+    let malformed_json = r#"{"container_name": ["unclosed bracket"#;
+    write_raw_state(&state_manager, malformed_json)?;
 
     let result = state_manager.load_state();
     assert!(result.is_err());
@@ -211,28 +217,28 @@ fn test_state_corruption_recovery() -> Result<()> {
     let error_msg = result.unwrap_err().to_string();
     assert!(error_msg.contains("Invalid state file format") || error_msg.contains("parse"));
 
-    // Test 2: Valid YAML but invalid structure
-    let invalid_structure = r#"
-container_name: "test"
-provider: "docker"
-# Missing required fields like created_at, project_dir, mounts
-some_unknown_field: "value"
-"#;
-    fs::write(state_manager.state_file_path(), invalid_structure)?;
+    // Test 2: Valid JSON but invalid structure
+    // (missing required fields like created_at, project_dir, mounts)
+    let invalid_structure = r#"{
+        "container_name": "test",
+        "provider": "docker",
+        "some_unknown_field": "value"
+    }"#;
+    write_raw_state(&state_manager, invalid_structure)?;
 
     let result = state_manager.load_state();
     assert!(result.is_err());
 
     // Test 3: Valid structure but validation failures
-    let invalid_data = r#"
-container_name: ""
-provider: "docker"
-mounts: []
-created_at: "2024-01-01T00:00:00Z"
-project_dir: "/nonexistent/directory"
-auto_destroy: false
-"#;
-    fs::write(state_manager.state_file_path(), invalid_data)?;
+    let invalid_data = r#"{
+        "container_name": "",
+        "provider": "docker",
+        "mounts": [],
+        "created_at": "2024-01-01T00:00:00Z",
+        "project_dir": "/nonexistent/directory",
+        "auto_destroy": false
+    }"#;
+    write_raw_state(&state_manager, invalid_data)?;
 
     let result = state_manager.load_state();
     assert!(result.is_err());
@@ -438,18 +444,24 @@ fn test_state_file_atomic_operations() -> Result<()> {
     state.add_mount(fixture.mount_source.clone(), MountPermission::ReadWrite)?;
     state_manager.save_state(&state)?;
 
-    // Verify atomic write behavior: temp file should not exist after successful write
-    let state_file_path = state_manager.state_file_path();
-    let temp_file_path = state_file_path.with_extension("tmp");
-
-    assert!(state_file_path.exists());
-    assert!(
-        !temp_file_path.exists(),
-        "Temporary file should be cleaned up after atomic write"
-    );
+    // Writes go through the state database; nothing is left beside it
+    let state_dir_entries = || -> Result<Vec<String>> {
+        let mut names = fs::read_dir(&fixture.state_dir)?
+            .map(|entry| Ok(entry?.file_name().to_string_lossy().into_owned()))
+            .collect::<Result<Vec<_>>>()?;
+        names.sort();
+        Ok(names)
+    };
+    let read_raw_state = || -> Result<Option<serde_json::Value>> {
+        StateStore::open(state_manager.state_db_path())?.get(vm_state::TEMP_VMS, "current")
+    };
+    assert!(state_manager.state_exists());
+    assert!(state_dir_entries()?
+        .iter()
+        .all(|name| name.starts_with("state.db")));
 
-    // Test state file integrity after modifications
-    let original_content = fs::read_to_string(state_file_path)?;
+    // Test state integrity after modifications
+    let original_content = read_raw_state()?;
 
     // Create another mount directory and add it
     let another_mount_dir = fixture._temp_dir.path().join("another_mount");
@@ -457,16 +469,16 @@ fn test_state_file_atomic_operations() -> Result<()> {
     state.add_mount(another_mount_dir, MountPermission::ReadOnly)?;
     state_manager.save_state(&state)?;
 
-    // Verify file was updated atomically
-    let new_content = fs::read_to_string(state_file_path)?;
+    // Verify the entry was replaced in place
+    let new_content = read_raw_state()?;
     assert_ne!(original_content, new_content);
-    assert!(
-        !temp_file_path.exists(),
-        "Temp file should be cleaned up after second write"
-    );
+    assert!(state_dir_entries()?
+        .iter()
+        .all(|name| name.starts_with("state.db")));
 
     // Verify content integrity
-    let loaded_state: TempVmState = serde_yaml::from_str(&new_content)?;
+    let loaded_state: TempVmState =
+        serde_json::from_value(new_content.expect("Temp VM state should be stored after save"))?;
     assert_eq!(loaded_state.mount_count(), 2);
 
     println!("✅ Atomic operations test passed - no temp file leakage, atomic writes work");
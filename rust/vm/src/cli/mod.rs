@@ -28,6 +28,10 @@ pub struct Args {
     /// Show what would be executed without running
     #[arg(long, global = true)]
     pub dry_run: bool,
+
+    /// Wait for other vm commands changing this project instead of failing
+    #[arg(long, global = true)]
+    pub wait_lock: bool,
 }
 
 #[derive(Debug, Clone, Subcommand)]
@@ -102,12 +106,29 @@ pub enum ConfigSubcommand {
         /// Show preset details
         #[arg(long)]
         show: Option<String>,
+        /// Fetch and apply a preset from the package registry (e.g., registry:org/rails@1.2.0)
+        #[arg(long, conflicts_with = "names")]
+        from: Option<String>,
+        /// Resolve and cache all presets, registry presets and box images for offline use
+        #[arg(long, conflicts_with_all = ["names", "from", "export", "import"])]
+        prefetch: bool,
+        /// Write the preset cache to a bundle file (.tar.gz)
+        #[arg(long, value_name = "FILE", conflicts_with_all = ["names", "from", "import"])]
+        export: Option<std::path::PathBuf>,
+        /// Seed the preset cache from a bundle created with --export
+        #[arg(long, value_name = "FILE", conflicts_with_all = ["names", "from"])]
+        import: Option<std::path::PathBuf>,
     },
     /// Manage configuration profiles
     Profile {
         #[command(subcommand)]
         command: ConfigProfileSubcommand,
     },
+    /// Manage instance templates for `vm create --like`
+    Template {
+        #[command(subcommand)]
+        command: ConfigTemplateSubcommand,
+    },
     /// Fix port conflicts
     Ports {
         /// Fix port conflicts automatically
@@ -133,6 +154,25 @@ pub enum ConfigProfileSubcommand {
     },
 }
 
+#[derive(Debug, Clone, Subcommand)]
+pub enum ConfigTemplateSubcommand {
+    /// Save this project's effective configuration as a template in global config
+    Save {
+        /// Template name
+        name: String,
+        /// Replace an existing template with the same name
+        #[arg(long)]
+        force: bool,
+    },
+    /// List saved templates
+    List,
+    /// Delete a template
+    Remove {
+        /// Template name
+        name: String,
+    },
+}
+
 #[derive(Debug, Clone, clap::Args)]
 pub struct FleetTargetArgs {
     /// Provider filter (docker, podman, tart)
@@ -257,6 +297,10 @@ pub enum RegistrySubcommand {
         /// Specify package type(s) to publish (python,npm,cargo)
         #[arg(long, short = 't')]
         r#type: Option<String>,
+        /// Publish every package in the monorepo (Cargo workspace members, npm
+        /// workspaces, Python projects) in dependency order
+        #[arg(long)]
+        workspace: bool,
         /// Start server automatically without prompting
         #[arg(long, short = 'y')]
         yes: bool,
@@ -290,6 +334,32 @@ pub enum RegistrySubcommand {
         #[arg(long, default_value = "3080")]
         port: u16,
     },
+    /// Publish a preset file so projects can use it via `vm config preset --from`
+    PublishPreset {
+        /// Preset YAML file to publish
+        file: std::path::PathBuf,
+        /// Registry name in org/name form (e.g., acme/rails)
+        #[arg(long)]
+        name: String,
+        /// Version to publish (e.g., 1.2.0)
+        #[arg(long)]
+        version: String,
+        /// Start server automatically without prompting
+        #[arg(long, short = 'y')]
+        yes: bool,
+    },
+    /// Keep a Docker image in the registry cache even when no project uses it
+    Pin {
+        /// Image reference (e.g., node:20)
+        image: String,
+    },
+    /// Let the registry cache evict an image again, even if a project uses it
+    Unpin {
+        /// Image reference (e.g., node:20)
+        image: String,
+    },
+    /// List Docker images protected from cache eviction and why
+    Pins,
     /// Start registry server (internal use - for background process)
     #[command(hide = true)]
     Serve {
@@ -353,6 +423,47 @@ pub enum TunnelSubcommand {
     },
 }
 
+#[derive(Debug, Clone, Subcommand)]
+pub enum NetSubcommand {
+    /// Add latency or packet loss to a service's network
+    Chaos {
+        /// Service to degrade (postgres, redis, mongodb, mysql)
+        #[arg(long)]
+        service: String,
+        /// Delay added to each packet, e.g. 200ms or 1s
+        #[arg(long)]
+        latency: Option<String>,
+        /// Random variation of the delay, e.g. 20ms
+        #[arg(long)]
+        jitter: Option<String>,
+        /// Share of packets to drop, e.g. 2%
+        #[arg(long)]
+        loss: Option<String>,
+    },
+    /// Remove network chaos
+    Reset {
+        /// Service to restore (omit to restore all)
+        #[arg(long)]
+        service: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum ReportSubcommand {
+    /// Show CPU time, peak memory and disk per project
+    Usage {
+        /// Reporting window, e.g. 24h, 7d, 4w
+        #[arg(long, default_value = "7d")]
+        since: String,
+    },
+    /// Collect redacted diagnostics into a tarball for bug reports
+    Bundle {
+        /// Output file (defaults to vm-report-<timestamp>.tar.gz)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+}
+
 #[derive(Debug, Clone, Subcommand)]
 pub enum SecretsSubcommand {
     /// Check secrets proxy status
@@ -400,9 +511,14 @@ pub enum DbSubcommand {
         #[arg(long)]
         all: bool,
     },
+    /// Manage database backup files
+    Backups {
+        #[command(subcommand)]
+        command: DbBackupsSubcommand,
+    },
     /// Restore a database from a backup
     Restore {
-        /// Backup name to restore
+        /// Backup name to restore (or a path from `vm db backups list`)
         name: String,
         /// Target database name
         db_name: String,
@@ -432,6 +548,17 @@ pub enum DbSubcommand {
         /// Force reset without confirmation
         #[arg(long)]
         force: bool,
+        /// Skip loading `db.default_fixture` after the reset
+        #[arg(long)]
+        no_seed: bool,
+    },
+    /// Load a SQL file, dump, URL, or named fixture set into a database
+    Seed {
+        /// File path, http(s) URL, or fixture set name from `db.fixtures`
+        source: String,
+        /// Target database (defaults to the project database)
+        #[arg(long)]
+        db: Option<String>,
     },
     /// Show credentials for a database service
     Credentials {
@@ -440,6 +567,12 @@ pub enum DbSubcommand {
     },
 }
 
+#[derive(Debug, Clone, Subcommand)]
+pub enum DbBackupsSubcommand {
+    /// List backups with their age and size
+    List,
+}
+
 #[derive(Debug, Clone, Subcommand)]
 pub enum SnapshotSubcommand {
     /// Create a snapshot of the current VM state
@@ -467,6 +600,9 @@ pub enum SnapshotSubcommand {
         /// Overwrite existing snapshot with same name
         #[arg(long)]
         force: bool,
+        /// Save a global snapshot even if the secret scan finds credentials
+        #[arg(long)]
+        allow_secrets: bool,
     },
     /// List available snapshots
     List {
@@ -512,6 +648,9 @@ pub enum SnapshotSubcommand {
         /// Project name (auto-detected if omitted, not needed for @global snapshots)
         #[arg(long)]
         project: Option<String>,
+        /// Export even if the secret scan finds credentials
+        #[arg(long)]
+        allow_secrets: bool,
     },
     /// Import a snapshot from a portable file
     Import {
@@ -568,7 +707,7 @@ pub enum Command {
     /// Create/configure/start your environment
     Create {
         /// Provider to create for this command
-        #[arg(value_parser = ["docker", "podman", "tart"])]
+        #[arg(value_parser = ["docker", "podman", "tart", "libvirt"])]
         provider: Option<String>,
         /// Force recreation even if it already exists
         #[arg(long)]
@@ -588,12 +727,18 @@ pub enum Command {
         /// Reinstall packages even if already present
         #[arg(long)]
         refresh_packages: bool,
+        /// Mount the workspace read-only and keep no state on the host
+        #[arg(long, conflicts_with_all = ["save_as", "from_dockerfile"])]
+        ephemeral: bool,
+        /// Use a template saved with `vm config template save` instead of vm.yaml
+        #[arg(long, value_name = "TEMPLATE", conflicts_with = "from_dockerfile")]
+        like: Option<String>,
     },
     /// Zero to code in one command (init → create → start → ssh)
     #[command(about = "Get from zero to coding in one command")]
     Start {
         /// Provider to use for this start only
-        #[arg(value_parser = ["docker", "podman", "tart"])]
+        #[arg(value_parser = ["docker", "podman", "tart", "libvirt"])]
         provider: Option<String>,
         /// Command to execute (if not provided, opens interactive shell)
         #[arg(short = 'c', long)]
@@ -601,11 +746,20 @@ pub enum Command {
         /// Wait for services to be ready before continuing
         #[arg(long)]
         wait: bool,
+        /// Start a named instance instead of the default one (multi-instance providers only)
+        #[arg(long)]
+        instance: Option<String>,
+        /// Override an environment variable for this start (repeatable)
+        #[arg(long = "env", value_name = "KEY=VALUE")]
+        env: Vec<String>,
+        /// Keep the --env overrides for later starts of this instance
+        #[arg(long, requires = "env")]
+        save_env: bool,
     },
     /// Set the default provider for this project
     Use {
         /// Provider to use by default
-        #[arg(value_parser = ["docker", "podman", "tart"])]
+        #[arg(value_parser = ["docker", "podman", "tart", "libvirt"])]
         provider: String,
     },
     /// Stop your environment
@@ -668,11 +822,44 @@ pub enum Command {
         #[arg()]
         container: Option<String>,
     },
+    /// Bring an existing container under vm management
+    Adopt {
+        /// Name or ID of the container to adopt
+        #[arg()]
+        container: String,
+    },
+    /// Wait until services and their readiness gates are ready
+    Wait {
+        /// Container name, ID, or project name
+        #[arg()]
+        container: Option<String>,
+        /// Only wait for this service
+        #[arg(long)]
+        service: Option<String>,
+        /// Give up after this many seconds
+        #[arg(long, default_value_t = 60)]
+        timeout: u64,
+    },
+    /// List published ports and what they are for
+    Ports {
+        /// Container name, ID, or project name
+        #[arg()]
+        container: Option<String>,
+    },
     /// Manage port tunnels to your environment
     Tunnel {
         #[command(subcommand)]
         command: TunnelSubcommand,
     },
+    /// Switch between resource profiles without restarting
+    Mode {
+        /// Resource profile to apply (`focus` for full resources, `background` to park)
+        #[arg(value_parser = ["focus", "background"])]
+        mode: String,
+        /// Container name, ID, or project name
+        #[arg()]
+        container: Option<String>,
+    },
     /// Jump into your environment
     Ssh {
         /// Provider, container name, ID, or project name to connect to
@@ -702,10 +889,16 @@ pub enum Command {
         #[arg(long)]
         container: Option<String>,
         /// Provider to use for this command
-        #[arg(long, value_parser = ["docker", "podman", "tart"])]
+        #[arg(long, value_parser = ["docker", "podman", "tart", "libvirt"])]
         provider: Option<String>,
-        /// Command to execute inside VM
-        #[arg(required = true, num_args = 1..)]
+        /// Read a script from stdin and run it inside the VM
+        #[arg(long, conflicts_with = "file")]
+        stdin: bool,
+        /// Copy a local script into the VM and run it
+        #[arg(long, value_name = "SCRIPT")]
+        file: Option<PathBuf>,
+        /// Command to execute inside VM (script arguments with --stdin or --file)
+        #[arg(required_unless_present_any = ["stdin", "file"], num_args = 1..)]
         command: Vec<String>,
     },
     /// View environment logs
@@ -722,14 +915,23 @@ pub enum Command {
         /// Number of lines to show from end of logs
         #[arg(short = 'n', long, default_value = "50")]
         tail: usize,
-        /// Show logs for specific service (postgresql, redis, mongodb, mysql)
+        /// Show logs for specific service (postgresql, redis, mongodb, mysql, mock)
         #[arg(short = 's', long)]
         service: Option<String>,
+        /// Interleave logs from the VM and all of its service containers
+        #[arg(long, conflicts_with = "service")]
+        all_services: bool,
+        /// Only show logs since a timestamp or relative time (e.g. 10m, 2024-01-01T12:00:00)
+        #[arg(long)]
+        since: Option<String>,
+        /// Only show logs before a timestamp or relative time
+        #[arg(long)]
+        until: Option<String>,
     },
     /// Copy files to/from your environment
     Copy {
         /// Provider to use for this copy operation
-        #[arg(long, value_parser = ["docker", "podman", "tart"])]
+        #[arg(long, value_parser = ["docker", "podman", "tart", "libvirt"])]
         provider: Option<String>,
         /// Source path (local file or <container>:/path)
         source: String,
@@ -761,6 +963,9 @@ pub enum Command {
 
     /// Manage databases
     Db {
+        /// Database engine to operate on (defaults to the first database service in vm.yaml)
+        #[arg(long, global = true, value_parser = ["postgresql", "mysql", "mariadb", "mongodb"])]
+        engine: Option<String>,
         #[command(subcommand)]
         command: DbSubcommand,
     },
@@ -771,6 +976,47 @@ pub enum Command {
         command: SnapshotSubcommand,
     },
 
+    /// Generate a software bill of materials for the box image
+    Sbom {
+        /// Document format
+        #[arg(long, default_value = "spdx", value_parser = ["spdx", "cyclonedx"])]
+        format: String,
+        /// Package scanner (builtin inspects dpkg, pip and npm; syft must be installed)
+        #[arg(long, default_value = "builtin", value_parser = ["builtin", "syft"])]
+        scanner: String,
+        /// Image to scan (defaults to the project's container image)
+        #[arg(long, conflicts_with = "snapshot")]
+        image: Option<String>,
+        /// Scan a snapshot's image and store the SBOM inside the snapshot
+        #[arg(long)]
+        snapshot: Option<String>,
+        /// Write the document to this path instead of the snapshots directory
+        #[arg(short, long, value_name = "PATH")]
+        output: Option<PathBuf>,
+    },
+
+    /// Scan the box image for known vulnerabilities (OSV)
+    Scan {
+        /// Image to scan (defaults to the project's container image)
+        #[arg(long)]
+        image: Option<String>,
+        /// Exit non-zero at or above this severity (overrides scan.fail_on)
+        #[arg(long, value_parser = ["low", "medium", "high", "critical", "none"])]
+        fail_on: Option<String>,
+    },
+
+    /// Simulate degraded networks for services
+    Net {
+        #[command(subcommand)]
+        command: NetSubcommand,
+    },
+
+    /// Report resource usage or collect diagnostics
+    Report {
+        #[command(subcommand)]
+        command: ReportSubcommand,
+    },
+
     /// Build and validate provider-native base environments
     Base {
         #[command(subcommand)]
@@ -803,6 +1049,9 @@ pub enum Command {
         /// Force update even if already at latest version
         #[arg(long)]
         force: bool,
+        /// Install the download without checking its release signature
+        #[arg(long)]
+        skip_verify: bool,
     },
     /// Remove from your system
     Uninstall {
@@ -840,6 +1089,24 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_create_like_template_parsing() {
+        let args = Args::parse_from(["vm", "create", "--like", "backend-dev"]);
+        match args.command {
+            Command::Create { like, .. } => assert_eq!(like.as_deref(), Some("backend-dev")),
+            _ => panic!("Expected Command::Create"),
+        }
+        assert!(Args::try_parse_from([
+            "vm",
+            "create",
+            "--like",
+            "backend-dev",
+            "--from-dockerfile",
+            "Dockerfile"
+        ])
+        .is_err());
+    }
+
     #[test]
     fn test_start_command_parsing() {
         let args = Args::parse_from(["vm", "start", "tart", "-c", "echo hi", "--wait"]);
@@ -848,6 +1115,7 @@ mod tests {
                 provider,
                 command,
                 wait,
+                ..
             } => {
                 assert_eq!(provider, Some("tart".to_string()));
                 assert_eq!(command, Some("echo hi".to_string()));
@@ -857,6 +1125,32 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_start_saves_env_for_named_instance() {
+        let args = Args::parse_from([
+            "vm",
+            "start",
+            "--instance",
+            "test",
+            "--env",
+            "DATABASE=test_db",
+            "--save-env",
+        ]);
+        match args.command {
+            Command::Start {
+                instance,
+                env,
+                save_env,
+                ..
+            } => {
+                assert_eq!(instance, Some("test".to_string()));
+                assert_eq!(env, vec!["DATABASE=test_db".to_string()]);
+                assert!(save_env);
+            }
+            _ => panic!("Expected Command::Start"),
+        }
+    }
+
     #[test]
     fn test_use_command_parsing() {
         let args = Args::parse_from(["vm", "use", "docker"]);
@@ -923,7 +1217,7 @@ mod tests {
         let args = Args::parse_from(["vm", "registry", "add", "--type", "python", "-y"]);
         match args.command {
             Command::Registry { command } => match command {
-                RegistrySubcommand::Add { r#type, yes } => {
+                RegistrySubcommand::Add { r#type, yes, .. } => {
                     assert_eq!(r#type, Some("python".to_string()));
                     assert!(yes);
                 }
@@ -978,6 +1272,7 @@ mod tests {
                 container,
                 provider,
                 command,
+                ..
             } => {
                 assert_eq!(container, Some("my-vm".to_string()));
                 assert_eq!(provider, None);
@@ -995,6 +1290,7 @@ mod tests {
                 container,
                 provider,
                 command,
+                ..
             } => {
                 assert_eq!(container, None);
                 assert_eq!(provider, Some("tart".to_string()));
@@ -1004,6 +1300,32 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_exec_script_parsing() {
+        let args = Args::parse_from(["vm", "exec", "--file", "setup.sh", "--", "--fast", "x y"]);
+        match args.command {
+            Command::Exec {
+                stdin,
+                file,
+                command,
+                ..
+            } => {
+                assert!(!stdin);
+                assert_eq!(file, Some(std::path::PathBuf::from("setup.sh")));
+                assert_eq!(command, vec!["--fast", "x y"]);
+            }
+            _ => panic!("Expected Command::Exec"),
+        }
+
+        let args = Args::parse_from(["vm", "exec", "--stdin"]);
+        assert!(
+            matches!(args.command, Command::Exec { stdin: true, ref command, .. } if command.is_empty())
+        );
+
+        assert!(Args::try_parse_from(["vm", "exec"]).is_err());
+        assert!(Args::try_parse_from(["vm", "exec", "--stdin", "--file", "a.sh"]).is_err());
+    }
+
     #[test]
     fn test_copy_provider_command_parsing() {
         let args = Args::parse_from(["vm", "copy", "--provider", "docker", "a.txt", "/tmp/a.txt"]);
@@ -1050,4 +1372,20 @@ mod tests {
             _ => panic!("Expected Command::Base"),
         }
     }
+
+    #[test]
+    fn test_preset_prefetch_conflicts_with_bundle_flags() {
+        assert!(Args::try_parse_from(["vm", "config", "preset", "--prefetch"]).is_ok());
+        for flag in ["--export", "--import"] {
+            assert!(Args::try_parse_from([
+                "vm",
+                "config",
+                "preset",
+                "--prefetch",
+                flag,
+                "presets.tar.gz"
+            ])
+            .is_err());
+        }
+    }
 }
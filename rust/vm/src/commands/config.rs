@@ -4,15 +4,83 @@ use anyhow::Context;
 use std::path::PathBuf;
 use tracing::{debug, warn};
 
-use crate::cli::{ConfigProfileSubcommand, ConfigSubcommand};
+use crate::cli::{ConfigProfileSubcommand, ConfigSubcommand, ConfigTemplateSubcommand};
+use crate::commands::preset_bundle;
 use crate::error::{VmError, VmResult};
 use serde_yaml_ng as serde_yaml;
 use vm_config::ports::{PortRange, PortRegistry};
-use vm_config::{config::VmConfig, validator::ConfigValidator, ConfigOps};
+use vm_config::preset_registry::{self, RegistryPresetRef};
+use vm_config::GlobalConfig;
+use vm_config::{
+    config::{BoxSpec, VmConfig},
+    validator::ConfigValidator,
+    ConfigOps,
+};
 use vm_core::msg;
 use vm_core::{vm_println, vm_success};
 use vm_messages::messages::MESSAGES;
 
+/// Handle `vm config preset --from registry:<org>/<name>[@<version>]`.
+///
+/// Downloads the preset from the package registry, verifies its checksum, stores it
+/// in the local preset cache and applies it pinned to the resolved version so later
+/// loads are reproducible and work offline.
+fn handle_preset_from_registry(reference: &str, global: bool) -> VmResult<()> {
+    let registry_ref = RegistryPresetRef::parse(reference)?.ok_or_else(|| {
+        VmError::validation(
+            format!("'{reference}' is not a registry preset (expected registry:<org>/<name>[@<version>])"),
+            None::<String>,
+        )
+    })?;
+
+    let pinned = fetch_registry_preset(&registry_ref)?;
+    Ok(ConfigOps::preset(&pinned.to_string(), global, false, None)?)
+}
+
+/// Download a registry preset into the local preset cache.
+///
+/// Returns the reference pinned to the version that was fetched.
+pub(crate) fn fetch_registry_preset(
+    registry_ref: &RegistryPresetRef,
+) -> VmResult<RegistryPresetRef> {
+    if vm_core::offline::is_offline() {
+        // Use whatever is cached rather than failing on a preset we already have
+        preset_registry::load_cached(&vm_config::get_presets_dir(), registry_ref).map_err(
+            |_| {
+                VmError::from(vm_core::error::VmError::Network(
+                    vm_core::offline::offline_message(&format!("fetch preset {registry_ref}")),
+                ))
+            },
+        )?;
+        vm_println!("📦 Offline mode: using cached {}", registry_ref);
+        return Ok(registry_ref.clone());
+    }
+
+    let global_config = GlobalConfig::load().unwrap_or_default();
+    let server_url = std::env::var("VM_PRESET_REGISTRY_URL").unwrap_or_else(|_| {
+        format!(
+            "http://localhost:{}",
+            global_config.services.package_registry.port
+        )
+    });
+
+    vm_println!("📦 Fetching {} from {}", registry_ref, server_url);
+    let fetched = vm_package_server::api::PackageServerClient::new(&server_url)
+        .fetch_preset(&registry_ref.path(), registry_ref.version.as_deref())
+        .with_context(|| format!("Failed to fetch preset {registry_ref}"))?;
+
+    let pinned = registry_ref.pinned(&fetched.version);
+    preset_registry::store(
+        &vm_config::get_presets_dir(),
+        &pinned,
+        &fetched.version,
+        &fetched.content,
+        &fetched.sha256,
+    )?;
+    vm_success!("Verified {} (sha256 {})", pinned, &fetched.sha256[..12]);
+    Ok(pinned)
+}
+
 /// Handle the `vm config validate` command.
 fn handle_validate_command() -> VmResult<()> {
     let config = VmConfig::load(None)?;
@@ -130,6 +198,73 @@ fn handle_profile_set(name: &str) -> VmResult<()> {
     ConfigOps::set("default_profile", &values, false, false).map_err(VmError::from)
 }
 
+/// Save the project's effective configuration as an instance template
+fn handle_template_save(name: &str, force: bool, profile: Option<String>) -> VmResult<()> {
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || ch == '-' || ch == '_')
+    {
+        return Err(VmError::validation(
+            format!("Invalid template name '{name}': use letters, digits, '-' and '_'"),
+            Some("name"),
+        ));
+    }
+
+    let app_config = vm_config::AppConfig::load(None, profile, None)?;
+    let mut global_config = app_config.global;
+    if global_config.templates.contains_key(name) && !force {
+        return Err(VmError::validation(
+            format!("Template '{name}' already exists; pass --force to replace it"),
+            Some("name"),
+        ));
+    }
+
+    global_config
+        .templates
+        .insert(name.to_string(), app_config.vm.to_template());
+    global_config.save()?;
+    vm_success!("Saved template '{}'", name);
+    vm_println!("Create an instance from it with: vm create --like {}", name);
+    Ok(())
+}
+
+fn handle_template_list() -> VmResult<()> {
+    let global_config = GlobalConfig::load()?;
+    if global_config.templates.is_empty() {
+        vm_println!("No templates saved. Save one with: vm config template save <name>");
+        return Ok(());
+    }
+
+    vm_println!("Templates:");
+    for (name, template) in &global_config.templates {
+        let image = match template.vm.as_ref().and_then(|vm| vm.get_box_spec()) {
+            Some(BoxSpec::String(image)) => Some(image),
+            Some(BoxSpec::Build { dockerfile, .. }) => Some(dockerfile),
+            None => template.os.clone(),
+        };
+        let provider = template.provider.as_deref().unwrap_or("docker");
+        match image {
+            Some(image) => vm_println!("  - {} ({}, {})", name, provider, image),
+            None => vm_println!("  - {} ({})", name, provider),
+        }
+    }
+    Ok(())
+}
+
+fn handle_template_remove(name: &str) -> VmResult<()> {
+    let mut global_config = GlobalConfig::load()?;
+    if global_config.templates.shift_remove(name).is_none() {
+        return Err(VmError::validation(
+            format!("Template '{name}' not found"),
+            Some("name"),
+        ));
+    }
+    global_config.save()?;
+    vm_success!("Removed template '{}'", name);
+    Ok(())
+}
+
 /// Handle configuration management commands
 pub fn handle_config_command(
     command: &ConfigSubcommand,
@@ -146,11 +281,26 @@ pub fn handle_config_command(
         } => Ok(ConfigOps::set(field, values, *global, dry_run)?),
         ConfigSubcommand::Get { field, global } => Ok(ConfigOps::get(field.as_deref(), *global)?),
         ConfigSubcommand::Unset { field, global } => Ok(ConfigOps::unset(field, *global)?),
+        ConfigSubcommand::Preset { prefetch: true, .. } => preset_bundle::handle_prefetch(),
+        ConfigSubcommand::Preset {
+            export: Some(path), ..
+        } => preset_bundle::handle_export(path),
+        ConfigSubcommand::Preset {
+            import: Some(path), ..
+        } => preset_bundle::handle_import(path),
+        ConfigSubcommand::Preset {
+            global,
+            list: false,
+            show: None,
+            from: Some(reference),
+            ..
+        } => handle_preset_from_registry(reference, *global),
         ConfigSubcommand::Preset {
             names,
             global,
             list,
             show,
+            ..
         } => match (list, show, names) {
             (true, _, _) => Ok(ConfigOps::preset("", *global, true, None)?),
             (_, Some(show_name), _) => Ok(ConfigOps::preset("", *global, false, Some(show_name))?),
@@ -163,6 +313,13 @@ pub fn handle_config_command(
             ConfigProfileSubcommand::List => handle_profile_list(),
             ConfigProfileSubcommand::Set { name } => handle_profile_set(name),
         },
+        ConfigSubcommand::Template { command } => match command {
+            ConfigTemplateSubcommand::Save { name, force } => {
+                handle_template_save(name, *force, profile)
+            }
+            ConfigTemplateSubcommand::List => handle_template_list(),
+            ConfigTemplateSubcommand::Remove { name } => handle_template_remove(name),
+        },
         ConfigSubcommand::Ports { fix } => handle_ports_command(*fix),
         ConfigSubcommand::Clear { global } => Ok(ConfigOps::clear(*global)?),
     }
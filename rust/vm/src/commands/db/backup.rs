@@ -1,27 +1,41 @@
 //! DB backup and restore logic
+use super::engine::DbEngine;
+use super::utils::detect_container_runtime;
 use crate::error::{VmError, VmResult};
 use chrono::Local;
 use std::path::{Path, PathBuf};
 use vm_config::GlobalConfig;
 
-/// Get the base directory for backups
-fn get_backup_dir() -> VmResult<PathBuf> {
+/// Timestamp suffix of backup file names
+const BACKUP_TIMESTAMP_FORMAT: &str = "%Y%m%d_%H%M%S";
+
+/// Get the configured root directory holding all backups
+pub fn get_backup_root() -> VmResult<PathBuf> {
     let global_config = GlobalConfig::load()?;
 
     // Expand tilde in configured backup path
     let expanded_path = shellexpand::tilde(&global_config.backups.path);
-    let backup_dir = PathBuf::from(expanded_path.as_ref()).join("postgres");
+    Ok(PathBuf::from(expanded_path.as_ref()))
+}
+
+/// Get the base directory for an engine's backups
+fn get_backup_dir(engine: DbEngine) -> VmResult<PathBuf> {
+    let backup_dir = get_backup_root()?.join(engine.backup_subdir());
 
     std::fs::create_dir_all(&backup_dir)
         .map_err(|e| VmError::filesystem(e, backup_dir.to_string_lossy(), "create_dir_all"))?;
     Ok(backup_dir)
 }
 
-/// Execute a command in the postgres docker container
-async fn execute_docker_command(args: &[&str], input: Option<Vec<u8>>) -> VmResult<Vec<u8>> {
+/// Execute a command in the engine's service container
+pub(super) async fn execute_docker_command(
+    engine: DbEngine,
+    args: &[String],
+    input: Option<Vec<u8>>,
+) -> VmResult<Vec<u8>> {
     let executable = detect_container_runtime();
     let mut cmd = tokio::process::Command::new(&executable);
-    cmd.arg("exec").arg("-i").arg("vm-postgres-global");
+    cmd.arg("exec").arg("-i").arg(engine.container_name());
     cmd.args(args);
 
     if input.is_some() {
@@ -59,39 +73,38 @@ async fn execute_docker_command(args: &[&str], input: Option<Vec<u8>>) -> VmResu
     }
 }
 
-fn detect_container_runtime() -> String {
-    vm_config::AppConfig::load(None, None, None)
-        .ok()
-        .and_then(|config| {
-            config
-                .vm
-                .provider
-                .or(config.global.defaults.provider)
-                .filter(|provider| matches!(provider.as_str(), "docker" | "podman"))
-        })
-        .unwrap_or_else(|| "docker".to_string())
-}
-
 /// Backup a database
 pub async fn backup_db(
+    engine: DbEngine,
     db_name: &str,
     backup_name: Option<&str>,
     retention_count: u32,
 ) -> VmResult<()> {
-    let timestamp = Local::now().format("%Y%m%d_%H%M%S");
+    let backup_dir = get_backup_dir(engine)?;
+    backup_db_to(engine, &backup_dir, db_name, backup_name, retention_count).await
+}
+
+/// Backup a database into `backup_dir`, pruning old backups beyond `retention_count`
+pub async fn backup_db_to(
+    engine: DbEngine,
+    backup_dir: &Path,
+    db_name: &str,
+    backup_name: Option<&str>,
+    retention_count: u32,
+) -> VmResult<()> {
+    tokio::fs::create_dir_all(backup_dir)
+        .await
+        .map_err(|e| VmError::filesystem(e, backup_dir.to_string_lossy(), "create_dir_all"))?;
+
+    let timestamp = Local::now().format(BACKUP_TIMESTAMP_FORMAT);
+    let extension = engine.backup_extension();
     let backup_file_name = match backup_name {
-        Some(name) => format!("{name}_{timestamp}.dump"),
-        None => format!("{db_name}_{timestamp}.dump"),
+        Some(name) => format!("{name}_{timestamp}.{extension}"),
+        None => format!("{db_name}_{timestamp}.{extension}"),
     };
-    let backup_path = get_backup_dir()?.join(&backup_file_name);
+    let backup_path = backup_dir.join(&backup_file_name);
 
-    let output = execute_docker_command(
-        &[
-            "pg_dump", "-U", "postgres", "-d", db_name, "-F", "c", // Custom format, compressed
-        ],
-        None,
-    )
-    .await?;
+    let output = execute_docker_command(engine, &engine.dump_command(db_name), None).await?;
 
     tokio::fs::write(&backup_path, output)
         .await
@@ -100,15 +113,21 @@ pub async fn backup_db(
     vm_core::vm_success!("Database '{}' backed up to {:?}", db_name, backup_path);
 
     if retention_count > 0 {
-        clean_old_backups(db_name, retention_count).await?;
+        let name = backup_name.unwrap_or(db_name);
+        clean_old_backups(engine, backup_dir, name, retention_count).await?;
     }
 
     Ok(())
 }
 
 /// Restore a database
-pub async fn restore_db(backup_name: &str, db_name: &str) -> VmResult<()> {
-    let backup_path = get_backup_dir()?.join(backup_name);
+pub async fn restore_db(engine: DbEngine, backup_name: &str, db_name: &str) -> VmResult<()> {
+    let mut backup_path = get_backup_dir(engine)?.join(backup_name);
+    if !backup_path.exists() {
+        // Scheduled backups live under <backups>/<project>/, so also accept
+        // paths relative to the backup root as shown by `vm db backups list`
+        backup_path = get_backup_root()?.join(backup_name);
+    }
     if !backup_path.exists() {
         return Err(VmError::validation(
             "Backup file not found",
@@ -121,54 +140,19 @@ pub async fn restore_db(backup_name: &str, db_name: &str) -> VmResult<()> {
         .map_err(|e| VmError::filesystem(e, backup_path.to_string_lossy(), "read"))?;
 
     // Drop and recreate the database before restoring
-    execute_docker_command(
-        &[
-            "psql",
-            "-U",
-            "postgres",
-            "-c",
-            &format!("DROP DATABASE IF EXISTS \"{db_name}\";"),
-        ],
-        None,
-    )
-    .await?;
-    execute_docker_command(
-        &[
-            "psql",
-            "-U",
-            "postgres",
-            "-c",
-            &format!("CREATE DATABASE \"{db_name}\";"),
-        ],
-        None,
-    )
-    .await?;
-
-    execute_docker_command(
-        &[
-            "pg_restore",
-            "-U",
-            "postgres",
-            "-d",
-            db_name,
-            "--clean",
-            "--if-exists",
-        ],
-        Some(backup_data),
-    )
-    .await?;
+    for command in engine.reset_commands(db_name) {
+        execute_docker_command(engine, &command, None).await?;
+    }
+
+    execute_docker_command(engine, &engine.restore_command(db_name), Some(backup_data)).await?;
 
     vm_core::vm_success!("Database '{}' restored from '{}'", db_name, backup_name);
     Ok(())
 }
 
-/// Export a database to a SQL file
-pub async fn export_db(db_name: &str, file: &Path) -> VmResult<()> {
-    let output = execute_docker_command(
-        &["pg_dump", "-U", "postgres", "-d", db_name, "--clean"],
-        None,
-    )
-    .await?;
+/// Export a database to a file (SQL for PostgreSQL/MySQL, archive for MongoDB)
+pub async fn export_db(engine: DbEngine, db_name: &str, file: &Path) -> VmResult<()> {
+    let output = execute_docker_command(engine, &engine.export_command(db_name), None).await?;
 
     tokio::fs::write(file, output)
         .await
@@ -178,8 +162,8 @@ pub async fn export_db(db_name: &str, file: &Path) -> VmResult<()> {
     Ok(())
 }
 
-/// Import a database from a SQL file
-pub async fn import_db(db_name: &str, file: &Path) -> VmResult<()> {
+/// Import a database from a file produced by `export_db`
+pub async fn import_db(engine: DbEngine, db_name: &str, file: &Path) -> VmResult<()> {
     if !file.exists() {
         return Err(VmError::validation(
             "Import file not found",
@@ -187,18 +171,22 @@ pub async fn import_db(db_name: &str, file: &Path) -> VmResult<()> {
         ));
     }
 
-    let sql_data = tokio::fs::read(file)
+    let data = tokio::fs::read(file)
         .await
         .map_err(|e| VmError::filesystem(e, file.to_string_lossy(), "read"))?;
 
-    execute_docker_command(&["psql", "-U", "postgres", "-d", db_name], Some(sql_data)).await?;
+    if let Some(command) = engine.create_if_missing_command(db_name) {
+        execute_docker_command(engine, &command, None).await?;
+    }
+
+    execute_docker_command(engine, &engine.import_command(db_name), Some(data)).await?;
 
     vm_core::vm_success!("Database '{}' imported from {:?}", db_name, file);
     Ok(())
 }
 
-/// Reset a database
-pub async fn reset_db(db_name: &str, force: bool) -> VmResult<()> {
+/// Reset a database. Returns `false` if the user cancelled.
+pub async fn reset_db(engine: DbEngine, db_name: &str, force: bool) -> VmResult<bool> {
     if !force {
         vm_core::vm_println!(
             "⚠️  This will permanently delete all data in the '{}' database.",
@@ -215,41 +203,21 @@ pub async fn reset_db(db_name: &str, force: bool) -> VmResult<()> {
             .map_err(|e| VmError::general(e, "Failed to read user input"))?;
         if response.trim().to_lowercase() != "y" {
             vm_core::vm_println!("Database reset cancelled.");
-            return Ok(());
+            return Ok(false);
         }
     }
 
-    execute_docker_command(
-        &[
-            "psql",
-            "-U",
-            "postgres",
-            "-c",
-            &format!("DROP DATABASE IF EXISTS \"{db_name}\";"),
-        ],
-        None,
-    )
-    .await?;
-
-    execute_docker_command(
-        &[
-            "psql",
-            "-U",
-            "postgres",
-            "-c",
-            &format!("CREATE DATABASE \"{db_name}\";"),
-        ],
-        None,
-    )
-    .await?;
+    for command in engine.reset_commands(db_name) {
+        execute_docker_command(engine, &command, None).await?;
+    }
 
     vm_core::vm_success!("Database '{}' has been reset.", db_name);
-    Ok(())
+    Ok(true)
 }
 
 /// Get the number of backups for a specific database
-pub async fn count_backups(db_name: &str) -> VmResult<usize> {
-    let backup_dir = get_backup_dir()?;
+pub async fn count_backups(engine: DbEngine, db_name: &str) -> VmResult<usize> {
+    let backup_dir = get_backup_dir(engine)?;
 
     if !backup_dir.exists() {
         return Ok(0);
@@ -265,11 +233,7 @@ pub async fn count_backups(db_name: &str) -> VmResult<usize> {
         .await
         .map_err(|e| VmError::general(e, "Failed to read backup directory entries"))?
     {
-        if entry
-            .file_name()
-            .to_string_lossy()
-            .starts_with(&format!("{db_name}_"))
-        {
+        if is_backup_of(&entry.file_name().to_string_lossy(), db_name, engine) {
             count += 1;
         }
     }
@@ -277,15 +241,34 @@ pub async fn count_backups(db_name: &str) -> VmResult<usize> {
     Ok(count)
 }
 
+/// Whether `file_name` is a backup named `name`, i.e. `<name>_<YYYYmmdd_HHMMSS>.<ext>`.
+///
+/// Matching the timestamp exactly keeps `app` from claiming backups of `app_test`.
+pub(super) fn is_backup_of(file_name: &str, name: &str, engine: DbEngine) -> bool {
+    let Some(timestamp) = file_name
+        .strip_prefix(name)
+        .and_then(|rest| rest.strip_prefix('_'))
+        .and_then(|rest| rest.strip_suffix(engine.backup_extension()))
+        .and_then(|rest| rest.strip_suffix('.'))
+    else {
+        return false;
+    };
+    chrono::NaiveDateTime::parse_from_str(timestamp, BACKUP_TIMESTAMP_FORMAT).is_ok()
+}
+
 /// Get the backup directory path as a string
-pub fn get_backup_path() -> VmResult<String> {
-    Ok(get_backup_dir()?.to_string_lossy().to_string())
+pub fn get_backup_path(engine: DbEngine) -> VmResult<String> {
+    Ok(get_backup_dir(engine)?.to_string_lossy().to_string())
 }
 
-/// Clean up old backups, keeping only the most recent `retention_count`
-async fn clean_old_backups(db_name: &str, retention_count: u32) -> VmResult<()> {
-    let backup_dir = get_backup_dir()?;
-    let mut read_dir = tokio::fs::read_dir(&backup_dir)
+/// Clean up old backups named `name`, keeping only the most recent `retention_count`
+async fn clean_old_backups(
+    engine: DbEngine,
+    backup_dir: &Path,
+    name: &str,
+    retention_count: u32,
+) -> VmResult<()> {
+    let mut read_dir = tokio::fs::read_dir(backup_dir)
         .await
         .map_err(|e| VmError::filesystem(e, backup_dir.to_string_lossy(), "read_dir"))?;
 
@@ -300,26 +283,21 @@ async fn clean_old_backups(db_name: &str, retention_count: u32) -> VmResult<()>
             .await
             .map_err(|e| VmError::general(e, "Failed to get metadata"))?;
         if metadata.is_file() {
-            let created = metadata
-                .created()
-                .map_err(|e| VmError::general(e, "Failed to get creation time"))?;
-            entries_with_meta.push((entry, created));
+            let modified = metadata
+                .modified()
+                .map_err(|e| VmError::general(e, "Failed to get modification time"))?;
+            entries_with_meta.push((entry, modified));
         }
     }
     let mut backups = entries_with_meta;
 
-    // Filter for backups of the specified database and sort by creation time (newest first)
-    backups.sort_by_key(|(_, created)| *created);
+    // Filter for backups of the specified database and sort by modification time (newest first)
+    backups.sort_by_key(|(_, modified)| *modified);
     backups.reverse();
 
     let db_backups: Vec<_> = backups
         .into_iter()
-        .filter(|(entry, _)| {
-            entry
-                .file_name()
-                .to_string_lossy()
-                .starts_with(&format!("{db_name}_"))
-        })
+        .filter(|(entry, _)| is_backup_of(&entry.file_name().to_string_lossy(), name, engine))
         .collect();
 
     if db_backups.len() > retention_count as usize {
@@ -335,3 +313,26 @@ async fn clean_old_backups(db_name: &str, retention_count: u32) -> VmResult<()>
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_backup_of_matches_name_and_timestamp_exactly() {
+        let engine = DbEngine::Postgres;
+        assert!(is_backup_of("app_20260101_120000.dump", "app", engine));
+        assert!(is_backup_of(
+            "app_test_20260101_120000.dump",
+            "app_test",
+            engine
+        ));
+        assert!(!is_backup_of(
+            "app_test_20260101_120000.dump",
+            "app",
+            engine
+        ));
+        assert!(!is_backup_of("app_20260101_120000.sql", "app", engine));
+        assert!(!is_backup_of("app_latest.dump", "app", engine));
+    }
+}
@@ -0,0 +1,295 @@
+//! Database engine selection
+//!
+//! Maps the database services defined in `vm.yaml` onto the container and
+//! client tooling used by `vm db` subcommands.
+
+use crate::error::{VmError, VmResult};
+
+/// Databases excluded from `--all` backups, listings and size reports
+const MYSQL_SYSTEM_DATABASES: &str = "'mysql','information_schema','performance_schema','sys'";
+const MONGODB_SYSTEM_DATABASES: &[&str] = &["admin", "config", "local"];
+
+/// A database engine supported by `vm db`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DbEngine {
+    Postgres,
+    Mysql,
+    Mongodb,
+}
+
+impl DbEngine {
+    /// Resolve an engine from a service name as used in `vm.yaml`
+    pub fn from_service_name(service: &str) -> Option<Self> {
+        match service {
+            "postgresql" | "postgres" => Some(Self::Postgres),
+            "mysql" | "mariadb" => Some(Self::Mysql),
+            "mongodb" | "mongo" => Some(Self::Mongodb),
+            _ => None,
+        }
+    }
+
+    /// Resolve the engine to operate on.
+    ///
+    /// An explicit `--engine` always wins. Otherwise the first enabled database
+    /// service in the project configuration is used, falling back to PostgreSQL.
+    pub fn resolve(explicit: Option<&str>) -> VmResult<Self> {
+        if let Some(name) = explicit {
+            return Self::from_service_name(name).ok_or_else(|| {
+                VmError::validation(
+                    format!("Unsupported database engine '{name}'"),
+                    Some("Use one of: postgresql, mysql, mariadb, mongodb".to_string()),
+                )
+            });
+        }
+
+        let detected = vm_config::AppConfig::load(None, None, None)
+            .ok()
+            .and_then(|config| {
+                ["postgresql", "mysql", "mariadb", "mongodb"]
+                    .into_iter()
+                    .find(|name| config.vm.services.get(*name).is_some_and(|s| s.enabled))
+                    .and_then(Self::from_service_name)
+            });
+
+        Ok(detected.unwrap_or(Self::Postgres))
+    }
+
+    /// Managed service name used by the service manager and secrets store
+    pub fn service_name(self) -> &'static str {
+        match self {
+            Self::Postgres => "postgresql",
+            Self::Mysql => "mysql",
+            Self::Mongodb => "mongodb",
+        }
+    }
+
+    /// Human-readable engine name
+    pub fn display_name(self) -> &'static str {
+        match self {
+            Self::Postgres => "PostgreSQL",
+            Self::Mysql => "MySQL",
+            Self::Mongodb => "MongoDB",
+        }
+    }
+
+    /// Name of the global service container
+    pub fn container_name(self) -> &'static str {
+        match self {
+            Self::Postgres => "vm-postgres-global",
+            Self::Mysql => "vm-mysql-global",
+            Self::Mongodb => "vm-mongodb-global",
+        }
+    }
+
+    /// Subdirectory of the backup path holding this engine's backups
+    pub fn backup_subdir(self) -> &'static str {
+        match self {
+            Self::Postgres => "postgres",
+            Self::Mysql => "mysql",
+            Self::Mongodb => "mongodb",
+        }
+    }
+
+    /// File extension used for backups
+    pub fn backup_extension(self) -> &'static str {
+        match self {
+            Self::Postgres => "dump",
+            Self::Mysql => "sql",
+            Self::Mongodb => "archive",
+        }
+    }
+
+    /// Superuser account created by the service container
+    pub fn admin_user(self) -> &'static str {
+        match self {
+            Self::Postgres => "postgres",
+            Self::Mysql | Self::Mongodb => "root",
+        }
+    }
+
+    /// Connection URL scheme
+    pub fn url_scheme(self) -> &'static str {
+        match self {
+            Self::Postgres => "postgresql",
+            Self::Mysql => "mysql",
+            Self::Mongodb => "mongodb",
+        }
+    }
+
+    /// Command that runs a query and prints one `name|size` line per user database
+    pub fn list_databases_command(self) -> Vec<String> {
+        match self {
+            Self::Postgres => psql(
+                "SELECT datname || '|' || pg_size_pretty(pg_database_size(datname)) FROM pg_database WHERE datistemplate = false;",
+            ),
+            Self::Mysql => mysql_query(&format!(
+                "SELECT CONCAT(s.schema_name, '|', ROUND(COALESCE(SUM(t.data_length + t.index_length), 0) / 1024 / 1024, 2), ' MB') \
+                 FROM information_schema.schemata s \
+                 LEFT JOIN information_schema.tables t ON t.table_schema = s.schema_name \
+                 WHERE s.schema_name NOT IN ({MYSQL_SYSTEM_DATABASES}) \
+                 GROUP BY s.schema_name;"
+            )),
+            Self::Mongodb => mongo_eval(&format!(
+                "db.adminCommand({{ listDatabases: 1 }}).databases\
+                 .filter(d => !{}.includes(d.name))\
+                 .forEach(d => print(d.name + '|' + (d.sizeOnDisk / 1024 / 1024).toFixed(2) + ' MB'))",
+                serde_json::to_string(MONGODB_SYSTEM_DATABASES).unwrap_or_default()
+            )),
+        }
+    }
+
+    /// Command that runs a single ad-hoc query as the admin user
+    pub fn query_command(self, query: &str) -> Vec<String> {
+        match self {
+            Self::Postgres => psql(query),
+            Self::Mysql => mysql_query(query),
+            Self::Mongodb => mongo_eval(query),
+        }
+    }
+
+    /// Command that prints the user databases eligible for `--all` backups
+    pub fn backup_candidates_command(self) -> Vec<String> {
+        match self {
+            Self::Postgres => psql(
+                "SELECT datname FROM pg_database WHERE datistemplate = false AND datname NOT IN ('postgres');",
+            ),
+            Self::Mysql | Self::Mongodb => self.list_databases_command(),
+        }
+    }
+
+    /// Command that writes a restorable backup of `db_name` to stdout
+    pub fn dump_command(self, db_name: &str) -> Vec<String> {
+        match self {
+            // Custom format, compressed
+            Self::Postgres => args(&["pg_dump", "-U", "postgres", "-d", db_name, "-F", "c"]),
+            Self::Mysql => mysql_tool(
+                "\"$(command -v mysqldump || command -v mariadb-dump)\" -uroot --single-transaction --routines --triggers \"$1\"",
+                &[db_name],
+            ),
+            Self::Mongodb => mongo_tool(
+                "mongodump -u root -p \"$MONGO_INITDB_ROOT_PASSWORD\" --authenticationDatabase admin --db \"$1\" --archive --gzip",
+                &[db_name],
+            ),
+        }
+    }
+
+    /// Command that restores a backup read from stdin into `db_name`
+    pub fn restore_command(self, db_name: &str) -> Vec<String> {
+        match self {
+            Self::Postgres => args(&[
+                "pg_restore",
+                "-U",
+                "postgres",
+                "-d",
+                db_name,
+                "--clean",
+                "--if-exists",
+            ]),
+            Self::Mysql => self.import_command(db_name),
+            // Rename every namespace in the archive into the target database
+            Self::Mongodb => mongo_tool(
+                "mongorestore -u root -p \"$MONGO_INITDB_ROOT_PASSWORD\" --authenticationDatabase admin --archive --gzip --drop --nsFrom '$db$.$coll$' --nsTo \"$1.\\$coll\\$\"",
+                &[db_name],
+            ),
+        }
+    }
+
+    /// Command that writes a portable export of `db_name` to stdout
+    pub fn export_command(self, db_name: &str) -> Vec<String> {
+        match self {
+            Self::Postgres => args(&["pg_dump", "-U", "postgres", "-d", db_name, "--clean"]),
+            Self::Mysql => mysql_tool(
+                "\"$(command -v mysqldump || command -v mariadb-dump)\" -uroot --add-drop-table --routines --triggers \"$1\"",
+                &[db_name],
+            ),
+            Self::Mongodb => self.dump_command(db_name),
+        }
+    }
+
+    /// Command that imports an export read from stdin into `db_name`
+    pub fn import_command(self, db_name: &str) -> Vec<String> {
+        match self {
+            Self::Postgres => args(&["psql", "-U", "postgres", "-d", db_name]),
+            Self::Mysql => mysql_tool(
+                "\"$(command -v mysql || command -v mariadb)\" -uroot \"$1\"",
+                &[db_name],
+            ),
+            Self::Mongodb => self.restore_command(db_name),
+        }
+    }
+
+    /// Command that creates `db_name` when the client can't connect to a missing database
+    pub fn create_if_missing_command(self, db_name: &str) -> Option<Vec<String>> {
+        match self {
+            Self::Mysql => Some(mysql_query(&format!(
+                "CREATE DATABASE IF NOT EXISTS `{}`;",
+                db_name.replace('`', "``")
+            ))),
+            Self::Postgres | Self::Mongodb => None,
+        }
+    }
+
+    /// Commands that drop `db_name` and leave an empty database behind
+    pub fn reset_commands(self, db_name: &str) -> Vec<Vec<String>> {
+        match self {
+            Self::Postgres => vec![
+                psql(&format!("DROP DATABASE IF EXISTS \"{db_name}\";")),
+                psql(&format!("CREATE DATABASE \"{db_name}\";")),
+            ],
+            Self::Mysql => {
+                let ident = db_name.replace('`', "``");
+                vec![mysql_query(&format!(
+                    "DROP DATABASE IF EXISTS `{ident}`; CREATE DATABASE `{ident}`;"
+                ))]
+            }
+            // MongoDB creates databases lazily on first write
+            Self::Mongodb => vec![mongo_eval(&format!(
+                "db.getSiblingDB({}).dropDatabase()",
+                serde_json::to_string(db_name).unwrap_or_default()
+            ))],
+        }
+    }
+}
+
+fn args(parts: &[&str]) -> Vec<String> {
+    parts.iter().map(|s| s.to_string()).collect()
+}
+
+fn psql(query: &str) -> Vec<String> {
+    args(&["psql", "-U", "postgres", "-t", "-A", "-c", query])
+}
+
+/// Run a shell snippet in the MySQL container with root credentials taken from
+/// the container environment, so the password never appears on the host.
+/// Values are passed as positional parameters (`$1`, ...) to avoid quoting issues.
+fn mysql_tool(script: &str, values: &[&str]) -> Vec<String> {
+    let mut cmd = args(&[
+        "sh",
+        "-c",
+        &format!("MYSQL_PWD=\"${{MYSQL_ROOT_PASSWORD:-$MARIADB_ROOT_PASSWORD}}\" {script}"),
+        "sh",
+    ]);
+    cmd.extend(values.iter().map(|v| v.to_string()));
+    cmd
+}
+
+fn mysql_query(query: &str) -> Vec<String> {
+    mysql_tool(
+        "\"$(command -v mysql || command -v mariadb)\" -uroot -N -B -e \"$1\"",
+        &[query],
+    )
+}
+
+fn mongo_tool(script: &str, values: &[&str]) -> Vec<String> {
+    let mut cmd = args(&["sh", "-c", script, "sh"]);
+    cmd.extend(values.iter().map(|v| v.to_string()));
+    cmd
+}
+
+/// Evaluate JavaScript with `mongosh`, falling back to the legacy `mongo` shell
+fn mongo_eval(script: &str) -> Vec<String> {
+    mongo_tool(
+        "\"$(command -v mongosh || command -v mongo)\" --quiet -u root -p \"$MONGO_INITDB_ROOT_PASSWORD\" --authenticationDatabase admin --eval \"$1\"",
+        &[script],
+    )
+}
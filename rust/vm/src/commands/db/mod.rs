@@ -1,20 +1,46 @@
 //! DB subcommand handlers
 
 pub mod backup;
+pub mod engine;
+pub mod schedule;
+pub mod seed;
 pub mod utils;
 
-use crate::cli::DbSubcommand;
+use crate::cli::{DbBackupsSubcommand, DbSubcommand};
 use crate::error::VmResult;
 use vm_config::GlobalConfig;
 use vm_core::vm_println;
 
-async fn show_credentials(service_name: &str) -> VmResult<()> {
+use engine::DbEngine;
+
+async fn show_credentials(service_name: &str, global_config: &GlobalConfig) -> VmResult<()> {
+    let engine = DbEngine::from_service_name(service_name);
+    // Secrets are stored under the canonical service name (e.g. `mariadb` -> `mysql`)
+    let secret_name = engine.map_or(service_name, |engine| engine.service_name());
     let secrets_dir = vm_core::user_paths::secrets_dir()?;
-    let secret_file = secrets_dir.join(format!("{}.env", service_name));
+    let secret_file = secrets_dir.join(format!("{}.env", secret_name));
 
     if secret_file.exists() {
         let password = tokio::fs::read_to_string(secret_file).await?;
-        vm_println!("Password for {}: {}", service_name, password.trim());
+        let password = password.trim();
+        vm_println!("Password for {}: {}", service_name, password);
+
+        if let Some(engine) = engine {
+            let port = match engine {
+                DbEngine::Postgres => global_config.services.postgresql.port,
+                DbEngine::Mysql => global_config.services.mysql.port,
+                DbEngine::Mongodb => global_config.services.mongodb.port,
+            };
+            vm_println!("Username: {}", engine.admin_user());
+            vm_println!("Port: {}", port);
+            vm_println!(
+                "URL: {}://{}:{}@localhost:{}",
+                engine.url_scheme(),
+                engine.admin_user(),
+                password,
+                port
+            );
+        }
     } else {
         vm_println!(
             "No credentials found for service '{}'. Has it been started yet?",
@@ -24,23 +50,19 @@ async fn show_credentials(service_name: &str) -> VmResult<()> {
     Ok(())
 }
 
-pub async fn handle_db(command: DbSubcommand) -> VmResult<()> {
+fn load_project_config() -> VmResult<vm_config::config::VmConfig> {
+    Ok(vm_config::AppConfig::load(None, None, None)?.vm)
+}
+
+pub async fn handle_db(engine: Option<&str>, command: DbSubcommand) -> VmResult<()> {
     let global_config = GlobalConfig::load()?;
+    let engine = DbEngine::resolve(engine)?;
 
     match command {
         DbSubcommand::Backup { db_name, name, all } => {
             if all {
                 // Backup all databases except system ones
-                let result = utils::execute_psql_command(
-                    "SELECT datname FROM pg_database WHERE datistemplate = false AND datname NOT IN ('postgres');",
-                )
-                .await?;
-
-                let databases: Vec<String> = result
-                    .lines()
-                    .map(|line| line.trim().to_string())
-                    .filter(|db| !db.is_empty())
-                    .collect();
+                let databases = utils::backup_candidates(engine).await?;
 
                 if databases.is_empty() {
                     vm_println!("No databases found to backup.");
@@ -52,7 +74,9 @@ pub async fn handle_db(command: DbSubcommand) -> VmResult<()> {
                 let mut failed_count = 0;
 
                 for db in databases {
-                    match backup::backup_db(&db, None, global_config.backups.keep_count).await {
+                    match backup::backup_db(engine, &db, None, global_config.backups.keep_count)
+                        .await
+                    {
                         Ok(()) => {
                             success_count += 1;
                         }
@@ -69,7 +93,13 @@ pub async fn handle_db(command: DbSubcommand) -> VmResult<()> {
                     failed_count
                 );
             } else if let Some(db) = db_name {
-                backup::backup_db(&db, name.as_deref(), global_config.backups.keep_count).await?;
+                backup::backup_db(
+                    engine,
+                    &db,
+                    name.as_deref(),
+                    global_config.backups.keep_count,
+                )
+                .await?;
             } else {
                 return Err(crate::error::VmError::validation(
                     "Missing database name",
@@ -79,65 +109,89 @@ pub async fn handle_db(command: DbSubcommand) -> VmResult<()> {
                 ));
             }
         }
+        DbSubcommand::Backups { command } => match command {
+            DbBackupsSubcommand::List => {
+                let backups = schedule::list_backups().await?;
+                if backups.is_empty() {
+                    vm_println!("No backups found.");
+                    return Ok(());
+                }
+
+                vm_println!("💾 Backups:");
+                vm_println!("  {:<60} {:>6} {:>10}", "NAME", "AGE", "SIZE");
+                for backup in &backups {
+                    vm_println!(
+                        "  {:<60} {:>6} {:>10}",
+                        backup.relative_path.display(),
+                        schedule::format_age(backup.modified),
+                        crate::utils::format_size(backup.size)
+                    );
+                }
+
+                if let Ok(root) = backup::get_backup_root() {
+                    vm_println!("\n💾 Backups stored in: {}", root.display());
+                }
+            }
+        },
         DbSubcommand::Restore { name, db_name } => {
-            backup::restore_db(&name, &db_name).await?;
+            backup::restore_db(engine, &name, &db_name).await?;
         }
         DbSubcommand::List => {
-            let result = utils::execute_psql_command(
-                "SELECT datname, pg_size_pretty(pg_database_size(datname)) FROM pg_database WHERE datistemplate = false;",
-            )
-            .await?;
-
-            vm_println!("📊 Databases:");
-            for line in result.lines() {
-                let parts: Vec<&str> = line.split('|').map(|s| s.trim()).collect();
-                if parts.len() == 2 && !parts[0].is_empty() {
-                    let db_name = parts[0];
-                    let db_size = parts[1];
-                    let backup_count = backup::count_backups(db_name).await.unwrap_or(0);
-
-                    if backup_count > 0 {
-                        vm_println!(
-                            "  - {:<30} {} ({} backup{})",
-                            db_name,
-                            db_size,
-                            backup_count,
-                            if backup_count == 1 { "" } else { "s" }
-                        );
-                    } else {
-                        vm_println!("  - {:<30} {} (no backups)", db_name, db_size);
-                    }
+            let databases = utils::list_databases(engine).await?;
+
+            vm_println!("📊 {} databases:", engine.display_name());
+            for (db_name, db_size) in &databases {
+                let backup_count = backup::count_backups(engine, db_name).await.unwrap_or(0);
+
+                if backup_count > 0 {
+                    vm_println!(
+                        "  - {:<30} {} ({} backup{})",
+                        db_name,
+                        db_size,
+                        backup_count,
+                        if backup_count == 1 { "" } else { "s" }
+                    );
+                } else {
+                    vm_println!("  - {:<30} {} (no backups)", db_name, db_size);
                 }
             }
 
-            if let Ok(backup_path) = backup::get_backup_path() {
+            if let Ok(backup_path) = backup::get_backup_path(engine) {
                 vm_println!("\n💾 Backups stored in: {}", backup_path);
             }
         }
         DbSubcommand::Export { name, file } => {
-            backup::export_db(&name, &file).await?;
+            backup::export_db(engine, &name, &file).await?;
         }
         DbSubcommand::Import { file, db_name } => {
-            backup::import_db(&db_name, &file).await?;
+            backup::import_db(engine, &db_name, &file).await?;
         }
         DbSubcommand::Size => {
-            let result = utils::execute_psql_command(
-                "SELECT datname, pg_size_pretty(pg_database_size(datname)) FROM pg_database WHERE datistemplate = false;",
-            )
-            .await?;
+            let databases = utils::list_databases(engine).await?;
             vm_println!("Database Sizes:");
-            for line in result.lines() {
-                let parts: Vec<&str> = line.split('|').map(|s| s.trim()).collect();
-                if parts.len() == 2 && !parts[0].is_empty() {
-                    vm_println!("  - {:<30} {}", parts[0], parts[1]);
-                }
+            for (db_name, db_size) in &databases {
+                vm_println!("  - {:<30} {}", db_name, db_size);
+            }
+        }
+        DbSubcommand::Reset {
+            name,
+            force,
+            no_seed,
+        } => {
+            let reset = backup::reset_db(engine, &name, force).await?;
+            if reset && !no_seed {
+                let vm_config = load_project_config()?;
+                seed::seed_default_fixture(engine, &name, &vm_config).await?;
             }
         }
-        DbSubcommand::Reset { name, force } => {
-            backup::reset_db(&name, force).await?;
+        DbSubcommand::Seed { source, db } => {
+            let vm_config = load_project_config()?;
+            let db_name =
+                db.unwrap_or_else(|| utils::project_database(&vm_config, engine.service_name()));
+            seed::seed_db(engine, &db_name, &source, &vm_config).await?;
         }
         DbSubcommand::Credentials { service } => {
-            show_credentials(&service).await?;
+            show_credentials(&service, &global_config).await?;
         }
     }
     Ok(())
@@ -0,0 +1,348 @@
+//! Scheduled database backups
+//!
+//! The CLI has no long-running daemon, so `db.backup.schedule` is evaluated
+//! when the service manager brings a project's services up, and then on a timer
+//! for as long as the process that started them keeps running (a `vm ssh`
+//! session, for example). Each configured database is backed up into
+//! `<backups.path>/<project>/` if its newest scheduled backup is older than the
+//! configured interval.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, SystemTime};
+
+use tracing::debug;
+use vm_config::{config::VmConfig, GlobalConfig};
+use vm_core::{vm_println, vm_warning};
+
+use super::backup::{backup_db_to, get_backup_root, is_backup_of};
+use super::engine::DbEngine;
+use super::utils::project_database;
+use crate::error::{VmError, VmResult};
+
+/// Longest wait between two checks of the backup schedule
+const MAX_CHECK_INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+/// Backup timers of the VMs whose services this process started
+static TIMERS: OnceLock<Mutex<HashMap<String, tokio::task::AbortHandle>>> = OnceLock::new();
+
+fn timers() -> std::sync::MutexGuard<'static, HashMap<String, tokio::task::AbortHandle>> {
+    TIMERS
+        .get_or_init(Mutex::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+}
+
+/// A backup file discovered under the backup root
+#[derive(Debug)]
+pub struct BackupEntry {
+    /// Path relative to the backup root (accepted by `vm db restore`)
+    pub relative_path: PathBuf,
+    pub size: u64,
+    pub modified: SystemTime,
+}
+
+/// Run any scheduled backups that are due for this project
+pub async fn run_due_backups(vm_config: &VmConfig, global_config: &GlobalConfig) -> VmResult<()> {
+    let Some(backup_config) = vm_config.db.as_ref().and_then(|db| db.backup.as_ref()) else {
+        return Ok(());
+    };
+    if !global_config.backups.enabled {
+        return Ok(());
+    }
+    let Some(interval) = backup_config.interval() else {
+        if let Some(schedule) = &backup_config.schedule {
+            vm_warning!(
+                "Ignoring invalid db.backup.schedule '{}' (use hourly, daily, weekly, or e.g. 6h)",
+                schedule
+            );
+        }
+        return Ok(());
+    };
+
+    let project = project_name(vm_config);
+    let backup_dir = get_backup_root()?.join(&project);
+    let keep = backup_config
+        .keep
+        .unwrap_or(global_config.backups.keep_count);
+
+    for (engine, db_name) in scheduled_targets(vm_config) {
+        if !is_due(&backup_dir, engine, &db_name, interval).await {
+            debug!("Scheduled backup for '{}' is not due yet", db_name);
+            continue;
+        }
+
+        vm_println!("📦 Running scheduled backup for database: {}", db_name);
+        if let Err(e) = backup_db_to(engine, &backup_dir, &db_name, None, keep).await {
+            vm_warning!("Scheduled backup of '{}' failed: {}", db_name, e);
+        }
+    }
+
+    Ok(())
+}
+
+/// Keep running scheduled backups for `vm_name` while this process is alive.
+///
+/// The timer first fires one check interval from now, since the service
+/// manager has just run any overdue backups itself.
+pub fn start_backup_timer(vm_name: &str, vm_config: &VmConfig, global_config: &GlobalConfig) {
+    let Some(interval) = vm_config
+        .db
+        .as_ref()
+        .and_then(|db| db.backup.as_ref())
+        .and_then(|backup| backup.interval())
+    else {
+        return;
+    };
+    if !global_config.backups.enabled {
+        return;
+    }
+
+    let period = interval.min(MAX_CHECK_INTERVAL);
+    let vm_config = vm_config.clone();
+    let global_config = global_config.clone();
+    let timer = tokio::spawn(async move {
+        let mut ticks = tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+        ticks.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            ticks.tick().await;
+            if let Err(e) = run_due_backups(&vm_config, &global_config).await {
+                vm_warning!("Scheduled database backups failed: {}", e);
+            }
+        }
+    });
+
+    if let Some(previous) = timers().insert(vm_name.to_string(), timer.abort_handle()) {
+        previous.abort();
+    }
+}
+
+/// Stop the backup timer started for `vm_name`, if any
+pub fn stop_backup_timer(vm_name: &str) {
+    let timer = timers().remove(vm_name);
+    if let Some(timer) = timer {
+        timer.abort();
+    }
+}
+
+/// List all backup files under the backup root, newest first
+pub async fn list_backups() -> VmResult<Vec<BackupEntry>> {
+    let root = get_backup_root()?;
+    let mut entries = Vec::new();
+    if !root.exists() {
+        return Ok(entries);
+    }
+
+    // Backups live at most one directory deep: <root>/<engine|project>/<file>
+    let mut pending = vec![root.clone()];
+    while let Some(dir) = pending.pop() {
+        let mut read_dir = tokio::fs::read_dir(&dir)
+            .await
+            .map_err(|e| VmError::filesystem(e, dir.to_string_lossy(), "read_dir"))?;
+        while let Some(entry) = read_dir
+            .next_entry()
+            .await
+            .map_err(|e| VmError::general(e, "Failed to read backup directory entries"))?
+        {
+            let metadata = entry
+                .metadata()
+                .await
+                .map_err(|e| VmError::general(e, "Failed to get metadata"))?;
+            let path = entry.path();
+            if metadata.is_dir() && dir == root {
+                pending.push(path);
+            } else if metadata.is_file() {
+                entries.push(BackupEntry {
+                    relative_path: path.strip_prefix(&root).unwrap_or(&path).to_path_buf(),
+                    size: metadata.len(),
+                    modified: metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+                });
+            }
+        }
+    }
+
+    entries.sort_by_key(|e| std::cmp::Reverse(e.modified));
+    Ok(entries)
+}
+
+fn project_name(vm_config: &VmConfig) -> String {
+    vm_config
+        .project
+        .as_ref()
+        .and_then(|p| p.name.clone())
+        .unwrap_or_else(|| "default".to_string())
+}
+
+/// Resolve which databases to back up for each enabled database service.
+///
+/// `databases` entries are scoped to a service as `<service>:<database>`, e.g.
+/// `postgresql:orders`. An unscoped entry is only used when the project has a
+/// single database service, since it can't say which engine holds it. Services
+/// without any entry back up their configured database.
+fn scheduled_targets(vm_config: &VmConfig) -> Vec<(DbEngine, String)> {
+    let explicit = vm_config
+        .db
+        .as_ref()
+        .and_then(|db| db.backup.as_ref())
+        .map(|backup| backup.databases.clone())
+        .unwrap_or_default();
+
+    let services: Vec<(&String, DbEngine)> = vm_config
+        .services
+        .iter()
+        .filter(|(_, service_config)| service_config.enabled)
+        .filter_map(|(name, _)| DbEngine::from_service_name(name).map(|engine| (name, engine)))
+        .collect();
+
+    let mut scoped: Vec<(DbEngine, String)> = Vec::new();
+    let mut unscoped: Vec<String> = Vec::new();
+    for entry in &explicit {
+        match entry.split_once(':') {
+            Some((service, database)) => match DbEngine::from_service_name(service) {
+                Some(engine) => scoped.push((engine, database.to_string())),
+                None => vm_warning!(
+                    "Ignoring db.backup.databases entry '{}': '{}' is not a database service",
+                    entry,
+                    service
+                ),
+            },
+            None => unscoped.push(entry.clone()),
+        }
+    }
+    if !unscoped.is_empty() && services.len() > 1 {
+        vm_warning!(
+            "Ignoring db.backup.databases entries without a service ({}); write them as <service>:<database>",
+            unscoped.join(", ")
+        );
+        unscoped.clear();
+    }
+
+    let mut targets = Vec::new();
+    for (service_name, engine) in services {
+        let mut databases: Vec<String> = scoped
+            .iter()
+            .filter(|(scoped_engine, _)| *scoped_engine == engine)
+            .map(|(_, database)| database.clone())
+            .collect();
+        databases.extend(unscoped.iter().cloned());
+        if databases.is_empty() {
+            databases.push(project_database(vm_config, service_name));
+        }
+        targets.extend(databases.into_iter().map(|database| (engine, database)));
+    }
+    targets
+}
+
+/// A backup is due when no existing backup of `db_name` is younger than `interval`
+async fn is_due(backup_dir: &Path, engine: DbEngine, db_name: &str, interval: Duration) -> bool {
+    let Ok(mut read_dir) = tokio::fs::read_dir(backup_dir).await else {
+        return true;
+    };
+
+    while let Ok(Some(entry)) = read_dir.next_entry().await {
+        if !is_backup_of(&entry.file_name().to_string_lossy(), db_name, engine) {
+            continue;
+        }
+        let age = entry
+            .metadata()
+            .await
+            .ok()
+            .and_then(|m| m.modified().ok())
+            .and_then(|modified| modified.elapsed().ok());
+        if age.is_some_and(|age| age < interval) {
+            return false;
+        }
+    }
+    true
+}
+
+/// Format a file age like `5m`, `3h`, or `2d`
+pub fn format_age(modified: SystemTime) -> String {
+    let secs = modified.elapsed().map(|d| d.as_secs()).unwrap_or(0);
+    match secs {
+        0..=59 => format!("{secs}s"),
+        60..=3599 => format!("{}m", secs / 60),
+        3600..=86399 => format!("{}h", secs / 3600),
+        _ => format!("{}d", secs / 86400),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vm_config::config::{DbBackupConfig, DbConfig, ProjectConfig, ServiceConfig};
+
+    fn config_with(services: &[(&str, Option<&str>)], databases: Vec<String>) -> VmConfig {
+        let mut config = VmConfig {
+            project: Some(ProjectConfig {
+                name: Some("my-app".to_string()),
+                ..Default::default()
+            }),
+            db: Some(DbConfig {
+                backup: Some(DbBackupConfig {
+                    schedule: Some("daily".to_string()),
+                    keep: None,
+                    databases,
+                }),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        for (name, database) in services {
+            config.services.insert(
+                name.to_string(),
+                ServiceConfig {
+                    enabled: true,
+                    database: database.map(str::to_string),
+                    ..Default::default()
+                },
+            );
+        }
+        config
+    }
+
+    #[test]
+    fn test_scheduled_targets_default_database() {
+        let config = config_with(&[("postgresql", None), ("redis", None)], Vec::new());
+        assert_eq!(
+            scheduled_targets(&config),
+            vec![(DbEngine::Postgres, "my_app_dev".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_scheduled_targets_explicit_databases() {
+        let config = config_with(
+            &[("mysql", Some("ignored"))],
+            vec!["orders".to_string(), "users".to_string()],
+        );
+        assert_eq!(
+            scheduled_targets(&config),
+            vec![
+                (DbEngine::Mysql, "orders".to_string()),
+                (DbEngine::Mysql, "users".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_scheduled_targets_scope_databases_per_service() {
+        let config = config_with(
+            &[("postgresql", None), ("mysql", Some("shop"))],
+            vec![
+                "postgresql:orders".to_string(),
+                "postgres:users".to_string(),
+                "legacy".to_string(),
+            ],
+        );
+        assert_eq!(
+            scheduled_targets(&config),
+            vec![
+                (DbEngine::Postgres, "orders".to_string()),
+                (DbEngine::Postgres, "users".to_string()),
+                (DbEngine::Mysql, "shop".to_string())
+            ]
+        );
+    }
+}
@@ -0,0 +1,197 @@
+//! Database seeding from files, URLs, and fixture sets
+//!
+//! A seed source is either a local file (resolved relative to the directory
+//! containing `vm.yaml`), an `http(s)://` URL, or the name of a fixture set
+//! declared under `db.fixtures`.
+
+use std::path::{Path, PathBuf};
+
+use vm_config::config::VmConfig;
+use vm_core::{vm_println, vm_warning};
+
+use super::backup::execute_docker_command;
+use super::engine::DbEngine;
+use super::utils::project_database;
+use crate::error::{VmError, VmResult};
+
+/// Seed `db_name` from a file, URL, or fixture set name
+pub async fn seed_db(
+    engine: DbEngine,
+    db_name: &str,
+    source: &str,
+    vm_config: &VmConfig,
+) -> VmResult<()> {
+    let sources = match fixture(vm_config, source) {
+        Some(entries) => {
+            vm_println!(
+                "🌱 Loading fixture set '{}' ({} file{}) into '{}'...",
+                source,
+                entries.len(),
+                if entries.len() == 1 { "" } else { "s" },
+                db_name
+            );
+            entries.to_vec()
+        }
+        None => vec![source.to_string()],
+    };
+
+    let base_dir = config_dir(vm_config);
+    for entry in &sources {
+        load_source(engine, db_name, entry, &base_dir).await?;
+    }
+
+    vm_core::vm_success!("Database '{}' seeded from '{}'", db_name, source);
+    Ok(())
+}
+
+/// Load the configured `db.default_fixture` into `db_name`, if any.
+///
+/// Returns `true` when a fixture was applied.
+pub async fn seed_default_fixture(
+    engine: DbEngine,
+    db_name: &str,
+    vm_config: &VmConfig,
+) -> VmResult<bool> {
+    let Some(name) = vm_config
+        .db
+        .as_ref()
+        .and_then(|db| db.default_fixture.as_deref())
+    else {
+        return Ok(false);
+    };
+
+    if fixture(vm_config, name).is_none() {
+        return Err(VmError::validation(
+            format!("Unknown fixture set '{name}'"),
+            Some("db.default_fixture must name an entry in db.fixtures".to_string()),
+        ));
+    }
+
+    seed_db(engine, db_name, name, vm_config).await?;
+    Ok(true)
+}
+
+/// Seed every enabled database service after `vm create`.
+///
+/// Honors the per-service `seed_file` setting first, then `db.default_fixture`.
+/// Failures are reported but never abort VM creation.
+pub async fn seed_after_create(vm_config: &VmConfig) {
+    for (service_name, service_config) in &vm_config.services {
+        if !service_config.enabled {
+            continue;
+        }
+        let Some(engine) = DbEngine::from_service_name(service_name) else {
+            continue;
+        };
+        let db_name = project_database(vm_config, service_name);
+
+        if let Some(seed_file) = &service_config.seed_file {
+            vm_println!("🌱 Seeding database '{}' from {:?}...", db_name, seed_file);
+            let source = seed_file.to_string_lossy();
+            if let Err(e) = seed_db(engine, &db_name, &source, vm_config).await {
+                vm_warning!("Database seeding failed: {}", e);
+            }
+        }
+
+        if let Err(e) = seed_default_fixture(engine, &db_name, vm_config).await {
+            vm_warning!("Database seeding failed: {}", e);
+        }
+    }
+}
+
+fn fixture<'a>(vm_config: &'a VmConfig, name: &str) -> Option<&'a [String]> {
+    vm_config
+        .db
+        .as_ref()
+        .and_then(|db| db.fixtures.get(name))
+        .map(Vec::as_slice)
+}
+
+/// Directory relative seed paths are resolved against
+fn config_dir(vm_config: &VmConfig) -> PathBuf {
+    vm_config
+        .source_path
+        .as_deref()
+        .and_then(Path::parent)
+        .map(Path::to_path_buf)
+        .or_else(|| std::env::current_dir().ok())
+        .unwrap_or_default()
+}
+
+fn is_url(source: &str) -> bool {
+    source.starts_with("http://") || source.starts_with("https://")
+}
+
+async fn load_source(
+    engine: DbEngine,
+    db_name: &str,
+    source: &str,
+    base_dir: &Path,
+) -> VmResult<()> {
+    let data = if is_url(source) {
+        vm_println!("  ⬇️  Downloading {}", source);
+        let response = vm_core::network::client()
+            .get(source)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| VmError::network(e, Some(source), "Failed to download seed file"))?;
+        response
+            .bytes()
+            .await
+            .map_err(|e| VmError::network(e, Some(source), "Failed to read seed file"))?
+            .to_vec()
+    } else {
+        let path = base_dir.join(shellexpand::tilde(source).as_ref());
+        if !path.exists() {
+            return Err(VmError::validation(
+                "Seed file not found",
+                Some(format!("Seed file not found at: {path:?}")),
+            ));
+        }
+        vm_println!("  📄 Loading {}", path.display());
+        tokio::fs::read(&path)
+            .await
+            .map_err(|e| VmError::filesystem(e, path.to_string_lossy(), "read"))?
+    };
+
+    if let Some(command) = engine.create_if_missing_command(db_name) {
+        execute_docker_command(engine, &command, None).await?;
+    }
+
+    // Backup-format files (e.g. pg_dump custom format) need the restore tool
+    let command = if is_backup_format(engine, source) {
+        engine.restore_command(db_name)
+    } else {
+        engine.import_command(db_name)
+    };
+    execute_docker_command(engine, &command, Some(data)).await?;
+    Ok(())
+}
+
+fn is_backup_format(engine: DbEngine, source: &str) -> bool {
+    let path = source.split(['?', '#']).next().unwrap_or(source);
+    path.ends_with(&format!(".{}", engine.backup_extension()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_backup_format() {
+        assert!(is_backup_format(DbEngine::Postgres, "seed/base.dump"));
+        assert!(is_backup_format(
+            DbEngine::Postgres,
+            "https://example.com/base.dump?token=abc"
+        ));
+        assert!(!is_backup_format(DbEngine::Postgres, "seed/base.sql"));
+        assert!(is_backup_format(DbEngine::Mongodb, "users.archive"));
+    }
+
+    #[test]
+    fn test_is_url() {
+        assert!(is_url("https://example.com/seed.sql"));
+        assert!(!is_url("./seed.sql"));
+    }
+}
@@ -1,38 +1,28 @@
 //! DB utility functions
 
+use super::engine::DbEngine;
 use crate::error::{VmError, VmResult};
 use crate::service_manager::get_service_manager;
+use vm_config::config::VmConfig;
 
 pub async fn execute_psql_command(command: &str) -> VmResult<String> {
-    let service_manager = get_service_manager().map_err(|e| {
-        VmError::general(
-            std::io::Error::new(std::io::ErrorKind::Other, e.to_string()),
-            "Service manager not initialized",
-        )
-    })?;
-    let pg_state = service_manager.get_service_status("postgresql");
+    execute_db_command(
+        DbEngine::Postgres,
+        &["psql", "-U", "postgres", "-t", "-c", command].map(str::to_string),
+    )
+    .await
+}
 
-    if !pg_state.is_some_and(|s| s.is_running) {
-        return Err(VmError::general(
-            std::io::Error::new(
-                std::io::ErrorKind::Other,
-                "PostgreSQL service is not running.",
-            ),
-            "Please start a VM that uses the PostgreSQL service to use this command.",
-        ));
-    }
+/// Run a client command inside the engine's service container and return its stdout
+pub async fn execute_db_command(engine: DbEngine, args: &[String]) -> VmResult<String> {
+    ensure_service_running(engine)?;
 
     let executable = detect_container_runtime();
     let output = tokio::process::Command::new(&executable)
         .arg("exec")
         .arg("-i")
-        .arg("vm-postgres-global")
-        .arg("psql")
-        .arg("-U")
-        .arg("postgres")
-        .arg("-t") // Tuples only, no headers/footers
-        .arg("-c")
-        .arg(command)
+        .arg(engine.container_name())
+        .args(args)
         .output()
         .await
         .map_err(|e| VmError::general(e, "Failed to execute docker command"))?;
@@ -42,13 +32,86 @@ pub async fn execute_psql_command(command: &str) -> VmResult<String> {
     } else {
         let stderr = String::from_utf8_lossy(&output.stderr).to_string();
         Err(VmError::general(
-            std::io::Error::new(std::io::ErrorKind::Other, "Failed to execute psql command."),
+            std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!("Failed to execute {} command.", engine.display_name()),
+            ),
             stderr,
         ))
     }
 }
 
-fn detect_container_runtime() -> String {
+/// List user databases with their human-readable sizes
+pub async fn list_databases(engine: DbEngine) -> VmResult<Vec<(String, String)>> {
+    let output = execute_db_command(engine, &engine.list_databases_command()).await?;
+    Ok(parse_database_lines(&output))
+}
+
+/// Names of the databases included in `vm db backup --all`
+pub async fn backup_candidates(engine: DbEngine) -> VmResult<Vec<String>> {
+    let output = execute_db_command(engine, &engine.backup_candidates_command()).await?;
+    Ok(parse_database_lines(&output)
+        .into_iter()
+        .map(|(name, _)| name)
+        .collect())
+}
+
+/// Parse `name|size` lines, tolerating lines without a size column
+fn parse_database_lines(output: &str) -> Vec<(String, String)> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.splitn(2, '|').map(str::trim);
+            let name = parts.next().filter(|name| !name.is_empty())?;
+            let size = parts.next().unwrap_or_default();
+            Some((name.to_string(), size.to_string()))
+        })
+        .collect()
+}
+
+fn ensure_service_running(engine: DbEngine) -> VmResult<()> {
+    let service_manager = get_service_manager().map_err(|e| {
+        VmError::general(
+            std::io::Error::new(std::io::ErrorKind::Other, e.to_string()),
+            "Service manager not initialized",
+        )
+    })?;
+    let state = service_manager.get_service_status(engine.service_name());
+
+    if !state.is_some_and(|s| s.is_running) {
+        return Err(VmError::general(
+            std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!("{} service is not running.", engine.display_name()),
+            ),
+            format!(
+                "Please start a VM that uses the {} service to use this command.",
+                engine.display_name()
+            ),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Database a project uses for `service_name`: the service's `database` setting,
+/// falling back to `<project>_dev`
+pub fn project_database(vm_config: &VmConfig, service_name: &str) -> String {
+    vm_config
+        .services
+        .get(service_name)
+        .and_then(|service| service.database.clone())
+        .unwrap_or_else(|| {
+            let project = vm_config
+                .project
+                .as_ref()
+                .and_then(|p| p.name.as_deref())
+                .unwrap_or("default");
+            format!("{}_dev", project.replace('-', "_"))
+        })
+}
+
+pub(crate) fn detect_container_runtime() -> String {
     vm_config::AppConfig::load(None, None, None)
         .ok()
         .and_then(|config| {
@@ -60,3 +123,21 @@ fn detect_container_runtime() -> String {
         })
         .unwrap_or_else(|| "docker".to_string())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::parse_database_lines;
+
+    #[test]
+    fn test_parse_database_lines() {
+        let output = " app_dev|8192 kB\n\nanalytics | 1.50 MB\nlegacy\n";
+        assert_eq!(
+            parse_database_lines(output),
+            vec![
+                ("app_dev".to_string(), "8192 kB".to_string()),
+                ("analytics".to_string(), "1.50 MB".to_string()),
+                ("legacy".to_string(), String::new()),
+            ]
+        );
+    }
+}
@@ -4,23 +4,38 @@
 //! optionally attempts to fix common issues.
 
 use anyhow::Result;
+use std::net::{SocketAddr, TcpStream};
+use std::path::PathBuf;
 use std::process::Command;
+use std::time::Duration;
+use vm_config::{AppConfig, GlobalConfig};
 use vm_core::{vm_error, vm_println, vm_success};
+use vm_provider::clock::ClockSkew;
 use vm_provider::docker::validate_docker_environment;
+use vm_provider::{get_provider, Provider};
+
+/// Small image used to probe service connectivity from the container network
+const PROBE_IMAGE: &str = "busybox:stable";
 
 /// Run diagnostics without attempting fixes
 #[allow(dead_code)]
 pub fn run() -> Result<()> {
-    run_diagnostics(false)
+    run_diagnostics(false, None, None)
 }
 
-/// Run diagnostics with optional auto-fix
-pub fn run_with_fix(fix: bool) -> Result<()> {
-    run_diagnostics(fix)
+/// Run diagnostics with optional auto-fix.
+///
+/// `config_path` and `profile` select the project whose VM clock is checked.
+pub fn run_with_fix(
+    fix: bool,
+    config_path: Option<PathBuf>,
+    profile: Option<String>,
+) -> Result<()> {
+    run_diagnostics(fix, config_path, profile)
 }
 
 /// Internal diagnostic runner
-fn run_diagnostics(fix: bool) -> Result<()> {
+fn run_diagnostics(fix: bool, config_path: Option<PathBuf>, profile: Option<String>) -> Result<()> {
     vm_println!("🔍 Running diagnostics...\n");
     let mut all_ok = true;
     let mut issues_fixed = 0;
@@ -35,9 +50,11 @@ fn run_diagnostics(fix: bool) -> Result<()> {
 
     // Check Docker (critical)
     print!("  Docker environment... ");
+    let mut docker_ok = false;
     match validate_docker_environment("docker") {
         Ok(_) => {
             println!("✓");
+            docker_ok = true;
         }
         Err(e) => {
             all_ok = false;
@@ -92,6 +109,11 @@ fn run_diagnostics(fix: bool) -> Result<()> {
         }
     }
 
+    // Check that VMs can reach managed services over the container network
+    if docker_ok && !check_service_connectivity() {
+        all_ok = false;
+    }
+
     // Check SSH key permissions
     print!("  SSH key permissions... ");
     match check_ssh_permissions() {
@@ -126,6 +148,32 @@ fn run_diagnostics(fix: bool) -> Result<()> {
         // We don't auto-fix port conflicts as it could kill user processes
     }
 
+    // Check the project VM's clock, which drifts after the host sleeps
+    if let Some((provider, skew)) = measure_vm_clock(config_path, profile) {
+        print!("  VM clock... ");
+        if skew.is_significant() {
+            all_ok = false;
+            println!("⚠️");
+            vm_println!(
+                "  The VM clock is {}; TLS and token validation inside it may fail",
+                skew.describe()
+            );
+            if fix {
+                if try_sync_clock(provider.as_ref()) {
+                    vm_success!("  ✓ Synchronized the VM clock with the host");
+                    issues_fixed += 1;
+                    all_ok = true;
+                } else {
+                    vm_println!("  Could not set the VM clock. Restart the VM with: vm restart");
+                }
+            } else {
+                vm_println!("  💡 Run: vm doctor --fix");
+            }
+        } else {
+            println!("✓");
+        }
+    }
+
     // Check VM binary (implicit - we're running it)
     print!("  VM binary... ");
     println!("✓");
@@ -163,6 +211,177 @@ fn run_diagnostics(fix: bool) -> Result<()> {
     Ok(())
 }
 
+/// A managed service that VMs reach through the host gateway
+struct ServiceProbe {
+    name: &'static str,
+    port: u16,
+    path: &'static str,
+}
+
+/// Enabled global services that VMs are expected to reach
+fn enabled_service_probes(global_config: &GlobalConfig) -> Vec<ServiceProbe> {
+    let services = &global_config.services;
+    let mut probes = Vec::new();
+    if services.package_registry.enabled {
+        probes.push(ServiceProbe {
+            name: "Package registry",
+            port: services.package_registry.port,
+            path: "/health",
+        });
+    }
+    if services.auth_proxy.enabled {
+        probes.push(ServiceProbe {
+            name: "Auth proxy",
+            port: services.auth_proxy.port,
+            path: "/health",
+        });
+    }
+    if services.docker_registry.enabled {
+        probes.push(ServiceProbe {
+            name: "Docker registry",
+            port: services.docker_registry.port,
+            path: "/v2/",
+        });
+    }
+    probes
+}
+
+/// Build the probe script run inside the container.
+///
+/// Prints one `<index> ok|fail` line per probe so results can be matched back.
+fn probe_script(host: &str, probes: &[ServiceProbe]) -> String {
+    probes
+        .iter()
+        .enumerate()
+        .map(|(i, probe)| {
+            format!(
+                "if wget -q -T 5 -O /dev/null http://{host}:{}{} 2>/dev/null; then echo '{i} ok'; else echo '{i} fail'; fi",
+                probe.port, probe.path
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// Parse probe output into per-service reachability
+fn parse_probe_output(output: &str, count: usize) -> Vec<bool> {
+    let mut reachable = vec![false; count];
+    for line in output.lines() {
+        let mut parts = line.split_whitespace();
+        if let (Some(index), Some("ok")) = (parts.next(), parts.next()) {
+            if let Some(slot) = index
+                .parse::<usize>()
+                .ok()
+                .and_then(|i| reachable.get_mut(i))
+            {
+                *slot = true;
+            }
+        }
+    }
+    reachable
+}
+
+/// Check if a service is listening on the host
+fn is_listening_on_host(port: u16) -> bool {
+    let addr = SocketAddr::from(([127, 0, 0, 1], port));
+    TcpStream::connect_timeout(&addr, Duration::from_secs(2)).is_ok()
+}
+
+/// Check that enabled global services are reachable from inside a container.
+///
+/// Misrouted `host.docker.internal` / bridge gateway traffic doesn't fail
+/// builds, it just silently bypasses the caches, so probe it explicitly.
+/// Returns false if any enabled service is unreachable.
+fn check_service_connectivity() -> bool {
+    let global_config = match GlobalConfig::load() {
+        Ok(config) => config,
+        Err(_) => return true,
+    };
+    let probes = enabled_service_probes(&global_config);
+    if probes.is_empty() {
+        return true;
+    }
+
+    let host = vm_platform::platform::get_host_gateway();
+    print!("  Service connectivity from containers... ");
+    let output = Command::new("docker")
+        .args(["run", "--rm", PROBE_IMAGE, "sh", "-c"])
+        .arg(probe_script(host, &probes))
+        .output();
+
+    let stdout = match output {
+        Ok(output) if output.status.success() => {
+            String::from_utf8_lossy(&output.stdout).to_string()
+        }
+        _ => {
+            println!("⚠️");
+            vm_println!("  Could not run probe container ({})", PROBE_IMAGE);
+            return true;
+        }
+    };
+
+    let reachable = parse_probe_output(&stdout, probes.len());
+    if reachable.iter().all(|ok| *ok) {
+        println!("✓");
+        return true;
+    }
+
+    println!("❌");
+    for (probe, ok) in probes.iter().zip(&reachable) {
+        if *ok {
+            vm_println!("    ✓ {} reachable at {}:{}", probe.name, host, probe.port);
+        } else if is_listening_on_host(probe.port) {
+            vm_println!(
+                "    ❌ {} is running on port {} but unreachable from containers via {}",
+                probe.name,
+                probe.port,
+                host
+            );
+        } else {
+            vm_println!(
+                "    ❌ {} is not running on port {}",
+                probe.name,
+                probe.port
+            );
+        }
+    }
+    vm_println!("  VMs will silently bypass these caches until routing is fixed.");
+    if cfg!(target_os = "linux") {
+        vm_println!(
+            "  💡 Make sure the services listen on {} (not only 127.0.0.1) and your firewall allows the docker0 bridge",
+            host
+        );
+    } else {
+        vm_println!(
+            "  💡 Make sure {} resolves inside containers (Docker Desktop setting)",
+            host
+        );
+    }
+    false
+}
+
+/// Measure the clock skew of the current project's VM.
+///
+/// Returns `None` outside a project, when the VM isn't running, or for
+/// providers whose VMs share the host clock.
+fn measure_vm_clock(
+    config_path: Option<PathBuf>,
+    profile: Option<String>,
+) -> Option<(Box<dyn Provider>, ClockSkew)> {
+    let config = AppConfig::load(config_path, profile, None).ok()?;
+    let provider = get_provider(config.vm).ok()?;
+    let skew = provider.clock_skew(None).ok().flatten()?;
+    Some((provider, skew))
+}
+
+/// Set the VM clock from the host and check that it took
+fn try_sync_clock(provider: &dyn Provider) -> bool {
+    provider.sync_clock(None).is_ok()
+        && provider
+            .clock_skew(None)
+            .is_ok_and(|skew| skew.is_some_and(|skew| !skew.is_significant()))
+}
+
 /// Check SSH directory and key permissions
 fn check_ssh_permissions() -> Result<(), String> {
     let home = match dirs::home_dir() {
@@ -371,3 +590,31 @@ fn try_create_config_directory() -> bool {
 
     std::fs::create_dir_all(&config_dir).is_ok()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_probe_script_and_output_round_trip() {
+        let probes = vec![
+            ServiceProbe {
+                name: "Package registry",
+                port: 3080,
+                path: "/health",
+            },
+            ServiceProbe {
+                name: "Docker registry",
+                port: 5000,
+                path: "/v2/",
+            },
+        ];
+
+        let script = probe_script("172.17.0.1", &probes);
+        assert!(script.contains("http://172.17.0.1:3080/health"));
+        assert!(script.contains("http://172.17.0.1:5000/v2/"));
+
+        assert_eq!(parse_probe_output("0 ok\n1 fail\n", 2), vec![true, false]);
+        assert_eq!(parse_probe_output("garbage\n7 ok\n", 2), vec![false, false]);
+    }
+}
@@ -0,0 +1,78 @@
+//! Provider-rendered dry runs
+//!
+//! `--dry-run` for create/start/stop/destroy asks the provider for the files it
+//! would generate and the commands it would run, and prints them so they can be
+//! reviewed or diffed in CI before anything is applied.
+
+use crate::error::VmResult;
+use vm_core::vm_println;
+use vm_provider::{ExecutionPlan, PlanAction, Provider, ProviderContext};
+
+/// Print the plan `provider` renders for `action`
+pub fn handle_plan(
+    provider: Box<dyn Provider>,
+    action: PlanAction,
+    container: Option<&str>,
+    context: &ProviderContext,
+) -> VmResult<()> {
+    let plan = provider.plan(action, container, context)?;
+    print_plan(&plan);
+    Ok(())
+}
+
+fn print_plan(plan: &ExecutionPlan) {
+    for (path, contents) in &plan.files {
+        vm_println!("\n── {} ──", path.display());
+        vm_println!("{}", contents.trim_end());
+    }
+
+    if !plan.commands.is_empty() {
+        vm_println!("\nCommands:");
+        for command in &plan.commands {
+            vm_println!("  $ {}", format_command(command));
+        }
+    }
+}
+
+/// Join a command line, quoting arguments the shell would split or expand
+fn format_command(command: &[String]) -> String {
+    command
+        .iter()
+        .map(|arg| {
+            let safe = !arg.is_empty()
+                && arg
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "-_./:=@,+%".contains(c));
+            if safe {
+                arg.clone()
+            } else {
+                format!("'{}'", arg.replace('\'', "'\"'\"'"))
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_command_quotes_shell_words() {
+        let command = [
+            "docker",
+            "compose",
+            "-f",
+            "/tmp/my project/docker-compose.yml",
+            "build",
+            "--build-arg=APT_PACKAGES=git curl",
+            "--build-arg=NOTE=it's",
+        ]
+        .map(String::from);
+        assert_eq!(
+            format_command(&command),
+            "docker compose -f '/tmp/my project/docker-compose.yml' build \
+             '--build-arg=APT_PACKAGES=git curl' '--build-arg=NOTE=it'\"'\"'s'"
+        );
+    }
+}
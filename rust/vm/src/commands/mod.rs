@@ -1,14 +1,18 @@
 // Command handlers for VM operations
 
 use crate::error::{VmError, VmResult};
+use crate::state::ProjectLock;
 use tracing::debug;
 // Import the CLI types
 use crate::cli::{Args, Command, PluginSubcommand, TunnelSubcommand};
-use vm_config::{config::BoxSpec, AppConfig, ConfigOps};
+use vm_config::{
+    config::{BoxSpec, VmConfig},
+    AppConfig, ConfigOps, GlobalConfig,
+};
 use vm_core::msg;
 use vm_core::{vm_error, vm_println};
 use vm_messages::messages::MESSAGES;
-use vm_provider::get_provider;
+use vm_provider::{get_provider, LogsRequest, PlanAction, ProviderContext};
 
 const ZSH_COMPLETION_PRELUDE: &str = r#"# Ensure compdef is available when this file is sourced directly from .zshrc.
 if [[ -n ${ZSH_VERSION:-} && -z ${functions[compdef]+x} ]]; then
@@ -24,10 +28,20 @@ pub mod clean;
 pub mod config;
 pub mod db;
 pub mod doctor;
+pub mod dry_run;
 pub mod init;
+pub mod mode;
+pub mod net;
 pub mod plugin;
 pub mod plugin_new;
+pub mod ports;
+pub mod preset_bundle;
 pub mod registry;
+pub mod release_signature;
+pub mod report;
+pub mod report_bundle;
+pub mod sbom;
+pub mod scan;
 pub mod secrets;
 pub mod snapshot;
 pub mod start;
@@ -52,12 +66,16 @@ pub async fn execute_command(args: Args) -> VmResult<()> {
             if *clean {
                 clean::handle_clean(false, false).await?;
             }
-            doctor::run_with_fix(*fix).map_err(VmError::from)
+            doctor::run_with_fix(*fix, args.config.clone(), args.profile.clone())
+                .map_err(VmError::from)
         }
         Command::Start {
             provider,
             command,
             wait,
+            instance,
+            env,
+            save_env,
         } => {
             debug!("Handling start command");
             start::handle_start(
@@ -66,6 +84,9 @@ pub async fn execute_command(args: Args) -> VmResult<()> {
                 args.profile.clone(),
                 provider.clone(),
                 *wait,
+                instance.clone(),
+                env,
+                *save_env,
             )
             .await
         }
@@ -117,14 +138,51 @@ pub async fn execute_command(args: Args) -> VmResult<()> {
             debug!("Calling plugin operations");
             handle_plugin_command(command)
         }
-        Command::Db { command } => {
+        Command::Db { engine, command } => {
             debug!("Calling db operations");
-            db::handle_db(command.clone()).await
+            db::handle_db(engine.as_deref(), command.clone()).await
         }
         Command::Snapshot { command } => {
             debug!("Calling snapshot operations");
             snapshot::handle_snapshot(command.clone(), args.config, args.profile.clone()).await
         }
+        Command::Sbom {
+            format,
+            scanner,
+            image,
+            snapshot,
+            output,
+        } => {
+            debug!("Calling SBOM generation");
+            sbom::handle_sbom(
+                format,
+                scanner,
+                image.as_deref(),
+                snapshot.as_deref(),
+                output.as_deref(),
+                args.config,
+                args.profile.clone(),
+            )
+            .await
+        }
+        Command::Scan { image, fail_on } => {
+            debug!("Calling vulnerability scan");
+            scan::handle_scan(
+                image.as_deref(),
+                fail_on.as_deref(),
+                args.config,
+                args.profile.clone(),
+            )
+            .await
+        }
+        Command::Net { command } => {
+            debug!("Calling network chaos");
+            net::handle_net(command.clone()).await
+        }
+        Command::Report { command } => {
+            debug!("Calling report");
+            report::handle_report(command.clone(), args.config, args.profile.clone()).await
+        }
         Command::Base { command } => {
             debug!("Calling base workflow operations");
             base::handle_base(command.clone()).await
@@ -170,7 +228,10 @@ fn handle_use_provider(provider: &str) -> VmResult<()> {
 
 async fn handle_dry_run(args: &Args) -> VmResult<()> {
     match &args.command {
-        Command::Create { .. } | Command::Stop { .. } | Command::Destroy { .. } => {
+        Command::Create { .. }
+        | Command::Start { .. }
+        | Command::Stop { .. }
+        | Command::Destroy { .. } => {
             vm_println!("{}", MESSAGES.vm.dry_run_header);
             vm_println!(
                 "{}",
@@ -188,6 +249,12 @@ async fn handle_dry_run(args: &Args) -> VmResult<()> {
                     )
                 );
             }
+            if let Err(e) = show_provider_plan(args) {
+                vm_println!(
+                    "{}",
+                    msg!(MESSAGES.vm.dry_run_no_plan, error = e.to_string())
+                );
+            }
             vm_println!("{}", MESSAGES.vm.dry_run_complete);
             Ok(())
         }
@@ -211,6 +278,8 @@ async fn handle_dry_run(args: &Args) -> VmResult<()> {
         Command::Exec {
             container,
             provider,
+            stdin,
+            file,
             command,
         } => {
             let app_config =
@@ -221,11 +290,17 @@ async fn handle_dry_run(args: &Args) -> VmResult<()> {
                 .and_then(|p| p.name)
                 .unwrap_or_default();
             let target = container.as_deref().unwrap_or(&project_name);
-            vm_println!(
-                "Dry run: Would execute command `{}` on {}",
-                command.join(" "),
-                target
-            );
+            if let Some(file) = file {
+                vm_println!("Dry run: Would run script {} on {}", file.display(), target);
+            } else if *stdin {
+                vm_println!("Dry run: Would run script from stdin on {}", target);
+            } else {
+                vm_println!(
+                    "Dry run: Would execute command `{}` on {}",
+                    command.join(" "),
+                    target
+                );
+            }
             Ok(())
         }
         Command::Fleet { command } => vm_ops::handle_fleet_command(command, true).await,
@@ -238,6 +313,51 @@ async fn handle_dry_run(args: &Args) -> VmResult<()> {
     }
 }
 
+/// Print the files and commands the provider would use for a lifecycle command
+fn show_provider_plan(args: &Args) -> VmResult<()> {
+    let provider_override = match &args.command {
+        Command::Start { provider, .. } => provider.clone(),
+        command => provider_override_from_command(command),
+    };
+    let app_config = AppConfig::load(args.config.clone(), args.profile.clone(), provider_override)?;
+    let mut config = app_config.vm;
+    apply_create_overrides(&mut config, &app_config.global, &args.command)?;
+    let context = ProviderContext::with_verbose(false).with_config(app_config.global);
+
+    let (action, container, context) = match &args.command {
+        Command::Create {
+            instance,
+            refresh_packages,
+            ephemeral,
+            ..
+        } => (
+            PlanAction::Create,
+            instance.clone(),
+            context
+                .refresh_packages(*refresh_packages)
+                .ephemeral(*ephemeral),
+        ),
+        Command::Start { .. } => (PlanAction::Start, None, context),
+        Command::Stop { container } => (PlanAction::Stop, instance_arg(container.clone()), context),
+        Command::Destroy {
+            container,
+            all: false,
+            pattern: None,
+            preserve_services,
+            remove_services,
+            ..
+        } => (
+            PlanAction::Destroy,
+            instance_arg(container.clone()),
+            context.preserve_services(*preserve_services && !remove_services),
+        ),
+        _ => return Ok(()),
+    };
+
+    let provider = get_provider(config).map_err(VmError::from)?;
+    dry_run::handle_plan(provider, action, container.as_deref(), &context)
+}
+
 async fn handle_provider_command(args: Args) -> VmResult<()> {
     if matches!(args.command, Command::Status { container: None }) {
         return vm_ops::handle_list_enhanced(None);
@@ -272,47 +392,7 @@ async fn handle_provider_command(args: Args) -> VmResult<()> {
     let mut config = app_config.vm;
     let global_config = app_config.global;
 
-    if let Command::Create {
-        provider: _,
-        from_dockerfile,
-        save_as,
-        ..
-    } = &args.command
-    {
-        if let Some(dockerfile_path) = from_dockerfile {
-            if matches!(config.provider.as_deref(), Some("tart")) {
-                return Err(VmError::validation(
-                    "Dockerfile builds are not supported for the Tart provider.".to_string(),
-                    None::<String>,
-                ));
-            }
-
-            let mut vm_settings = config.vm.take().unwrap_or_default();
-            vm_settings.r#box = Some(BoxSpec::String(
-                dockerfile_path.to_string_lossy().to_string(),
-            ));
-            config.vm = Some(vm_settings);
-        }
-
-        if save_as.is_some() && from_dockerfile.is_some() {
-            let raw_name = save_as
-                .as_deref()
-                .unwrap_or("snapshot")
-                .trim_start_matches('@');
-            let mut safe_name: String = raw_name
-                .chars()
-                .map(|ch| if ch.is_ascii_alphanumeric() { ch } else { '-' })
-                .collect();
-            safe_name = safe_name.trim_matches('-').to_string();
-            if safe_name.is_empty() {
-                safe_name = "snapshot".to_string();
-            }
-
-            let mut project = config.project.take().unwrap_or_default();
-            project.name = Some(format!("snapshot-{}", safe_name));
-            config.project = Some(project);
-        }
-    }
+    apply_create_overrides(&mut config, &global_config, &args.command)?;
 
     debug!(
         "Loaded configuration: provider={:?}, project_name={:?}",
@@ -343,6 +423,22 @@ async fn handle_provider_command(args: Args) -> VmResult<()> {
     // Log provider being used
     debug!(provider = %provider.name(), "Using provider");
 
+    // Keep concurrent create/destroy runs from corrupting each other's generated files,
+    // port registrations and state
+    let _project_lock = if matches!(
+        args.command,
+        Command::Create { .. } | Command::Destroy { .. }
+    ) {
+        let project_name = config
+            .project
+            .as_ref()
+            .and_then(|p| p.name.as_deref())
+            .unwrap_or("vm-project");
+        Some(ProjectLock::acquire(project_name, args.wait_lock)?)
+    } else {
+        None
+    };
+
     // Execute the command with friendly error handling
     debug!("Executing command: {:?}", args.command);
     let result = match args.command {
@@ -354,6 +450,8 @@ async fn handle_provider_command(args: Args) -> VmResult<()> {
             save_as,
             from_dockerfile,
             refresh_packages,
+            ephemeral,
+            like: _,
         } => {
             vm_ops::handle_create(
                 provider,
@@ -366,6 +464,7 @@ async fn handle_provider_command(args: Args) -> VmResult<()> {
                 from_dockerfile,
                 true,
                 refresh_packages,
+                ephemeral,
             )
             .await
         }
@@ -379,8 +478,12 @@ async fn handle_provider_command(args: Args) -> VmResult<()> {
             )
             .await
         }
-        Command::Update { version, force } => {
-            update::handle_update(version.as_deref(), force)?;
+        Command::Update {
+            version,
+            force,
+            skip_verify,
+        } => {
+            update::handle_update(version.as_deref(), force, skip_verify)?;
             Ok(())
         }
         Command::Uninstall { keep_config, yes } => {
@@ -474,24 +577,65 @@ async fn handle_provider_command(args: Args) -> VmResult<()> {
                 global_config.clone(),
             ),
         },
+        Command::Wait {
+            container,
+            service,
+            timeout,
+        } => {
+            vm_ops::handle_wait(
+                provider,
+                container.as_deref(),
+                service.as_deref(),
+                timeout,
+                config,
+                global_config.clone(),
+            )
+            .await
+        }
+        Command::Adopt { container } => vm_ops::handle_adopt(provider, &container, &config),
+        Command::Mode {
+            mode: profile,
+            container,
+        } => mode::handle_mode(provider, &profile, container.as_deref(), &config),
+        Command::Ports { container } => ports::handle_ports(provider, container.as_deref()),
         Command::Exec {
-            container, command, ..
-        } => vm_ops::handle_exec(provider, container.as_deref(), command, config.clone()),
+            container,
+            stdin,
+            file,
+            command,
+            ..
+        } => {
+            if stdin || file.is_some() {
+                vm_ops::handle_exec_script(
+                    provider,
+                    container.as_deref(),
+                    file.as_deref(),
+                    command,
+                    config.clone(),
+                )
+            } else {
+                vm_ops::handle_exec(provider, container.as_deref(), command, config.clone())
+            }
+        }
         Command::Logs {
             container,
             follow,
             tail,
             service,
+            all_services,
+            since,
+            until,
         } => {
             let container = instance_arg(container);
-            vm_ops::handle_logs(
-                provider,
-                container.as_deref(),
-                config.clone(),
+            let request = LogsRequest {
                 follow,
                 tail,
-                service.as_deref(),
-            )
+                service,
+                all_services,
+                since,
+                until,
+            };
+            vm_ops::handle_logs(provider, container.as_deref(), config.clone(), &request)
         }
         Command::Copy {
             source,
@@ -515,7 +659,74 @@ async fn handle_provider_command(args: Args) -> VmResult<()> {
 }
 
 fn is_provider_selector(value: &str) -> bool {
-    matches!(value, "docker" | "podman" | "tart")
+    matches!(value, "docker" | "podman" | "tart" | "libvirt")
+}
+
+/// Apply `vm create` flags that change the VM config (`--like`, `--from-dockerfile`,
+/// `--save-as`)
+fn apply_create_overrides(
+    config: &mut VmConfig,
+    global_config: &GlobalConfig,
+    command: &Command,
+) -> VmResult<()> {
+    if let Command::Create {
+        provider,
+        from_dockerfile,
+        save_as,
+        like,
+        ..
+    } = command
+    {
+        if let Some(name) = like {
+            let template = global_config.templates.get(name).ok_or_else(|| {
+                VmError::validation(
+                    format!(
+                        "Template '{name}' not found. Save one with: vm config template save {name}"
+                    ),
+                    Some("like"),
+                )
+            })?;
+            config.apply_template(template);
+            if provider.is_some() {
+                config.provider = provider.clone();
+            }
+        }
+
+        if let Some(dockerfile_path) = from_dockerfile {
+            if matches!(config.provider.as_deref(), Some("tart")) {
+                return Err(VmError::validation(
+                    "Dockerfile builds are not supported for the Tart provider.".to_string(),
+                    None::<String>,
+                ));
+            }
+
+            let mut vm_settings = config.vm.take().unwrap_or_default();
+            vm_settings.r#box = Some(BoxSpec::String(
+                dockerfile_path.to_string_lossy().to_string(),
+            ));
+            config.vm = Some(vm_settings);
+        }
+
+        if save_as.is_some() && from_dockerfile.is_some() {
+            let raw_name = save_as
+                .as_deref()
+                .unwrap_or("snapshot")
+                .trim_start_matches('@');
+            let mut safe_name: String = raw_name
+                .chars()
+                .map(|ch| if ch.is_ascii_alphanumeric() { ch } else { '-' })
+                .collect();
+            safe_name = safe_name.trim_matches('-').to_string();
+            if safe_name.is_empty() {
+                safe_name = "snapshot".to_string();
+            }
+
+            let mut project = config.project.take().unwrap_or_default();
+            project.name = Some(format!("snapshot-{}", safe_name));
+            config.project = Some(project);
+        }
+    }
+    Ok(())
 }
 
 fn provider_override_from_command(command: &Command) -> Option<String> {
@@ -0,0 +1,76 @@
+//! Resource profile switching
+//!
+//! `vm mode background` parks a VM on minimal resources while its services
+//! keep running; `vm mode focus` restores full resources. Limits are applied
+//! to the running VM, so nothing restarts.
+
+use crate::error::VmResult;
+use vm_config::config::{ResourceMode, VmConfig};
+use vm_core::{get_cpu_core_count, get_total_memory_gb, vm_println};
+use vm_provider::{Provider, ResourceLimits};
+
+/// Handle `vm mode <focus|background>`
+pub fn handle_mode(
+    provider: Box<dyn Provider>,
+    mode: &str,
+    container: Option<&str>,
+    config: &VmConfig,
+) -> VmResult<()> {
+    let profile = config
+        .vm
+        .as_ref()
+        .map(|vm| vm.resource_mode(mode))
+        .unwrap_or_else(|| vm_config::config::VmSettings::default().resource_mode(mode));
+
+    let system_cpus = get_cpu_core_count().unwrap_or(2);
+    let system_memory_mb = get_total_memory_gb().unwrap_or(4) * 1024;
+    let limits = resolve_limits(&profile, system_cpus, system_memory_mb);
+
+    provider.set_resource_limits(container, &limits)?;
+
+    let icon = if mode == "background" { "💤" } else { "⚡" };
+    vm_println!(
+        "{} Switched to {} mode: {} CPU{}, {}MB memory",
+        icon,
+        mode,
+        limits.cpus,
+        if limits.cpus == 1 { "" } else { "s" },
+        limits.memory_mb
+    );
+    if mode == "background" {
+        vm_println!("💡 Services keep running. Resume full speed with: vm mode focus");
+    }
+    Ok(())
+}
+
+/// Turn a profile into concrete limits for the current host.
+///
+/// Unlimited (or unset) values resolve to the whole host, since a running
+/// container's limits can be raised but not removed.
+fn resolve_limits(
+    profile: &ResourceMode,
+    system_cpus: u32,
+    system_memory_mb: u64,
+) -> ResourceLimits {
+    let cpus = profile
+        .cpus
+        .as_ref()
+        .and_then(|cpus| cpus.resolve_percentage(system_cpus))
+        .unwrap_or(system_cpus)
+        .clamp(1, system_cpus.max(1));
+    let memory_mb = profile
+        .memory
+        .as_ref()
+        .and_then(|memory| memory.resolve_percentage(system_memory_mb))
+        .unwrap_or(system_memory_mb as u32);
+    let swap_mb = profile
+        .swap
+        .as_ref()
+        .and_then(|swap| swap.resolve_percentage(system_memory_mb));
+
+    ResourceLimits {
+        cpus,
+        memory_mb,
+        swap_mb,
+    }
+}
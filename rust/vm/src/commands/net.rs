@@ -0,0 +1,244 @@
+//! Network chaos for managed services
+//!
+//! `vm net chaos` degrades the network of a shared service container with
+//! tc/netem so applications can be tested against slow or lossy dependencies.
+//! Service images rarely ship `tc`, so the rules are applied from a short-lived
+//! helper container that joins the service's network namespace with
+//! `NET_ADMIN`. The rules shape traffic leaving the service, which delays or
+//! drops every response the application waits for. They last until
+//! `vm net reset` or until the service container is recreated.
+
+use std::time::Duration;
+
+use vm_config::GlobalConfig;
+use vm_core::{vm_println, vm_success};
+
+use crate::cli::NetSubcommand;
+use crate::error::{VmError, VmResult};
+use crate::services::{container_runtime, service_container};
+
+/// Image providing `tc`, run inside the service's network namespace
+const NETEM_IMAGE: &str = "nicolaka/netshoot:latest";
+
+/// Interface of the service container on the bridge network
+const INTERFACE: &str = "eth0";
+
+/// Services whose network can be degraded
+const SERVICES: &[&str] = &["postgresql", "redis", "mongodb", "mysql"];
+
+/// Handle `vm net` subcommands
+pub async fn handle_net(command: NetSubcommand) -> VmResult<()> {
+    let global_config = GlobalConfig::load().unwrap_or_default();
+    let executable = container_runtime(&global_config);
+    match command {
+        NetSubcommand::Chaos {
+            service,
+            latency,
+            jitter,
+            loss,
+        } => {
+            let container = resolve_service(&service)?;
+            let latency = latency.as_deref().map(parse_duration).transpose()?;
+            let jitter = jitter.as_deref().map(parse_duration).transpose()?;
+            let loss = loss.as_deref().map(parse_loss).transpose()?;
+            let args = netem_args(latency, jitter, loss)?;
+
+            let mut tc = vec!["qdisc", "replace", "dev", INTERFACE, "root", "netem"];
+            tc.extend(args.iter().map(String::as_str));
+            run_tc(executable, container, &tc).await?;
+            vm_success!("Applied to {}: {}", service, args.join(" "));
+            vm_println!("💡 Undo with: vm net reset --service {}", service);
+            Ok(())
+        }
+        NetSubcommand::Reset { service } => {
+            let services: Vec<&str> = match &service {
+                Some(service) => vec![service.as_str()],
+                None => SERVICES.to_vec(),
+            };
+            for service in services {
+                let container = resolve_service(service)?;
+                if !is_running(executable, container).await {
+                    continue;
+                }
+                let rules =
+                    run_tc(executable, container, &["qdisc", "show", "dev", INTERFACE]).await?;
+                if !rules.contains("netem") {
+                    vm_println!("  {} has no network chaos applied", service);
+                    continue;
+                }
+                run_tc(
+                    executable,
+                    container,
+                    &["qdisc", "del", "dev", INTERFACE, "root"],
+                )
+                .await?;
+                vm_success!("Restored the network of {}", service);
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Map a service name (or common alias) to its container
+fn resolve_service(service: &str) -> VmResult<&'static str> {
+    let canonical = match service.to_ascii_lowercase().as_str() {
+        "postgres" | "pg" => "postgresql".to_string(),
+        "mongo" => "mongodb".to_string(),
+        other => other.to_string(),
+    };
+    service_container(&canonical).ok_or_else(|| {
+        VmError::validation(
+            format!(
+                "Unknown service '{service}' (expected one of: {})",
+                SERVICES.join(", ")
+            ),
+            Some("service".to_string()),
+        )
+    })
+}
+
+/// Parse `200ms`, `1.5s` or a bare number of milliseconds
+fn parse_duration(value: &str) -> VmResult<Duration> {
+    let value = value.trim();
+    let (number, scale) = if let Some(ms) = value.strip_suffix("ms") {
+        (ms, 0.001)
+    } else if let Some(s) = value.strip_suffix('s') {
+        (s, 1.0)
+    } else {
+        (value, 0.001)
+    };
+    number
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|n| n.is_finite() && *n >= 0.0)
+        .map(|n| Duration::from_secs_f64(n * scale))
+        .ok_or_else(|| {
+            VmError::validation(
+                format!("Invalid duration '{value}' (use e.g. 200ms or 1s)"),
+                Some("latency".to_string()),
+            )
+        })
+}
+
+/// Parse `2%` or `2` as a packet loss percentage
+fn parse_loss(value: &str) -> VmResult<f64> {
+    let value = value.trim();
+    value
+        .trim_end_matches('%')
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|n| (0.0..=100.0).contains(n))
+        .ok_or_else(|| {
+            VmError::validation(
+                format!("Invalid packet loss '{value}' (use a percentage from 0% to 100%)"),
+                Some("loss".to_string()),
+            )
+        })
+}
+
+/// netem options for the requested degradation
+fn netem_args(
+    latency: Option<Duration>,
+    jitter: Option<Duration>,
+    loss: Option<f64>,
+) -> VmResult<Vec<String>> {
+    if latency.is_none() && loss.is_none() {
+        return Err(VmError::validation(
+            "Specify --latency, --loss or both".to_string(),
+            None::<String>,
+        ));
+    }
+    if jitter.is_some() && latency.is_none() {
+        return Err(VmError::validation(
+            "--jitter needs --latency".to_string(),
+            Some("jitter".to_string()),
+        ));
+    }
+
+    let mut args = Vec::new();
+    if let Some(latency) = latency {
+        args.push("delay".to_string());
+        args.push(format!("{}ms", latency.as_millis()));
+        if let Some(jitter) = jitter {
+            args.push(format!("{}ms", jitter.as_millis()));
+        }
+    }
+    if let Some(loss) = loss {
+        args.push("loss".to_string());
+        args.push(format!("{loss}%"));
+    }
+    Ok(args)
+}
+
+async fn is_running(executable: &str, container: &str) -> bool {
+    tokio::process::Command::new(executable)
+        .args(["inspect", "-f", "{{.State.Running}}", container])
+        .output()
+        .await
+        .map(|output| output.status.success() && output.stdout.starts_with(b"true"))
+        .unwrap_or(false)
+}
+
+/// Run `tc` in the network namespace of `container`, returning its output
+async fn run_tc(executable: &str, container: &str, tc_args: &[&str]) -> VmResult<String> {
+    if !is_running(executable, container).await {
+        return Err(VmError::general(
+            std::io::Error::new(std::io::ErrorKind::NotFound, container.to_string()),
+            format!("{container} is not running; start a VM that uses the service first"),
+        ));
+    }
+
+    let network = format!("container:{container}");
+    let output = tokio::process::Command::new(executable)
+        .args([
+            "run",
+            "--rm",
+            "--cap-add",
+            "NET_ADMIN",
+            "--network",
+            &network,
+        ])
+        .arg(NETEM_IMAGE)
+        .arg("tc")
+        .args(tc_args)
+        .output()
+        .await
+        .map_err(|e| VmError::general(e, format!("Failed to run {executable}")))?;
+    if output.status.success() {
+        return Ok(String::from_utf8_lossy(&output.stdout).into_owned());
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    Err(VmError::general(
+        std::io::Error::new(std::io::ErrorKind::Other, stderr.trim().to_string()),
+        format!("tc failed in {container}"),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_netem_args() {
+        let args = netem_args(
+            Some(parse_duration("200ms").unwrap()),
+            Some(parse_duration("0.02s").unwrap()),
+            Some(parse_loss("2%").unwrap()),
+        )
+        .unwrap();
+        assert_eq!(args, ["delay", "200ms", "20ms", "loss", "2%"]);
+        assert_eq!(
+            netem_args(None, None, Some(parse_loss("0.5").unwrap())).unwrap(),
+            ["loss", "0.5%"]
+        );
+
+        assert!(netem_args(None, None, None).is_err());
+        assert!(netem_args(None, Some(Duration::from_millis(5)), Some(1.0)).is_err());
+        assert!(parse_loss("120%").is_err());
+        assert!(parse_duration("fast").is_err());
+        assert_eq!(resolve_service("postgres").unwrap(), "vm-postgres-global");
+        assert!(resolve_service("kafka").is_err());
+    }
+}
@@ -0,0 +1,35 @@
+//! Published port listing
+//!
+//! `vm create` records what each published port is for in
+//! `com.vm.port.<host port>` container labels (`web`, `api`, `db`, a service
+//! name, or the `name` of a port mapping). `vm ports` reads them back, so the
+//! listing shows the ports of the VM as created rather than the current vm.yaml.
+
+use crate::error::VmResult;
+use vm_core::vm_println;
+use vm_provider::Provider;
+
+/// Handle `vm ports`
+pub fn handle_ports(provider: Box<dyn Provider>, container: Option<&str>) -> VmResult<()> {
+    let ports = provider.port_labels(container)?;
+    if ports.is_empty() {
+        vm_println!("No labeled ports. Ports get labels when the VM is created; recreate it with: vm create --force");
+        vm_println!("💡 Name a mapping in vm.yaml with ports.mappings[].name");
+        return Ok(());
+    }
+
+    let width = ports.iter().map(|port| port.name.len()).max().unwrap_or(0);
+    vm_println!("🔌 Published Ports\n");
+    for port in &ports {
+        let protocol = if port.udp { "/udp" } else { "" };
+        vm_println!(
+            "  {:<width$}  localhost:{} → {}{}",
+            port.name,
+            port.host,
+            port.guest,
+            protocol,
+            width = width
+        );
+    }
+    Ok(())
+}
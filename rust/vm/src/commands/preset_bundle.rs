@@ -0,0 +1,254 @@
+//! Offline preset cache: prefetching and bundle export/import
+//!
+//! `vm config preset --prefetch` resolves every preset this machine knows about,
+//! downloads registry presets referenced by the project and pulls the box images
+//! those presets use, so later `vm create` runs work without network access.
+//! The resulting cache can be written to a bundle with `--export` and unpacked
+//! on another machine with `--import`.
+
+use crate::commands::config::fetch_registry_preset;
+use crate::error::{VmError, VmResult};
+use std::collections::BTreeSet;
+use std::path::{Component, Path, PathBuf};
+use std::process::Command;
+use vm_config::config::{BoxSpec, VmConfig};
+use vm_config::preset::PresetDetector;
+use vm_config::preset_registry::{self, RegistryPresetRef};
+use vm_core::{vm_println, vm_success, vm_warning};
+use vm_provider::BoxConfig;
+
+/// Bundle directory holding the filesystem and registry preset cache
+const BUNDLE_PRESETS_DIR: &str = "presets";
+
+/// Bundle directory holding preset plugins
+const BUNDLE_PLUGINS_DIR: &str = "plugins/presets";
+
+fn plugin_presets_dir() -> PathBuf {
+    vm_platform::platform::vm_state_dir()
+        .unwrap_or_else(|_| PathBuf::from(".vm"))
+        .join("plugins")
+        .join("presets")
+}
+
+/// Docker image referenced by a box spec, if it is a registry image.
+///
+/// Dockerfiles and snapshots are built locally and have nothing to pull.
+fn box_image(config: &VmConfig, base_dir: &Path) -> Option<String> {
+    let spec: BoxSpec = config.vm.as_ref()?.get_box_spec()?;
+    match BoxConfig::parse_for_docker(&spec, base_dir).ok()? {
+        BoxConfig::DockerImage(image) => Some(image),
+        _ => None,
+    }
+}
+
+/// Handle `vm config preset --prefetch`.
+pub fn handle_prefetch() -> VmResult<()> {
+    let project_dir = std::env::current_dir()
+        .map_err(|e| VmError::filesystem(e, ".", "get current directory"))?;
+    let detector = PresetDetector::new(project_dir.clone(), vm_config::get_presets_dir());
+
+    let mut images = BTreeSet::new();
+    let mut failed = 0;
+
+    let names = detector.list_all_presets()?;
+    for name in &names {
+        match detector.load_preset(name) {
+            Ok(config) => images.extend(box_image(&config, &project_dir)),
+            Err(e) => {
+                vm_warning!("Skipping preset '{}': {}", name, e);
+                failed += 1;
+            }
+        }
+    }
+    vm_success!("Resolved {} presets", names.len() - failed);
+
+    // Registry presets referenced by the project, plus the project's own box
+    let project_config = project_dir.join("vm.yaml");
+    if project_config.exists() {
+        let config = VmConfig::from_file(&project_config)?;
+        images.extend(box_image(&config, &project_dir));
+
+        let presets = config.preset.as_deref().unwrap_or_default();
+        for reference in presets.split(',').map(str::trim) {
+            let Some(registry_ref) = RegistryPresetRef::parse(reference)? else {
+                continue;
+            };
+            if preset_registry::load_cached(&vm_config::get_presets_dir(), &registry_ref).is_ok() {
+                vm_println!("  ✓ {} (cached)", registry_ref);
+                continue;
+            }
+            if let Err(e) = fetch_registry_preset(&registry_ref) {
+                vm_warning!("Could not fetch {}: {}", registry_ref, e);
+                failed += 1;
+            }
+        }
+    }
+
+    // When the Docker registry service is running, the daemon is configured to
+    // pull through it, so these pulls also seed the shared image cache.
+    let runtime = crate::services::default_container_runtime();
+    for image in &images {
+        vm_println!("🐳 Pulling {}", image);
+        let pulled = Command::new(&runtime)
+            .args(["pull", image])
+            .status()
+            .is_ok_and(|status| status.success());
+        if !pulled {
+            vm_warning!("Failed to pull box image '{}'", image);
+            failed += 1;
+        }
+    }
+
+    if failed > 0 {
+        return Err(VmError::validation(
+            format!("Preset prefetch incomplete: {failed} item(s) could not be cached"),
+            None::<String>,
+        ));
+    }
+    vm_success!(
+        "Preset cache ready for offline use ({} box images)",
+        images.len()
+    );
+    Ok(())
+}
+
+/// Handle `vm config preset --export <file>`.
+pub fn handle_export(path: &Path) -> VmResult<()> {
+    let file = std::fs::File::create(path)
+        .map_err(|e| VmError::filesystem(e, path.display().to_string(), "create"))?;
+    let encoder = flate2::write::GzEncoder::new(file, flate2::Compression::default());
+    let mut tar = tar::Builder::new(encoder);
+    tar.follow_symlinks(false);
+
+    for (dir, name) in [
+        (vm_config::get_presets_dir(), BUNDLE_PRESETS_DIR),
+        (plugin_presets_dir(), BUNDLE_PLUGINS_DIR),
+    ] {
+        if dir.is_dir() {
+            tar.append_dir_all(name, &dir)
+                .map_err(|e| VmError::filesystem(e, dir.display().to_string(), "archive"))?;
+        }
+    }
+
+    tar.into_inner()
+        .and_then(|encoder| encoder.finish())
+        .map_err(|e| VmError::filesystem(e, path.display().to_string(), "write"))?;
+
+    vm_success!("Exported preset cache to {}", path.display());
+    Ok(())
+}
+
+/// Map a bundle entry to its destination, rejecting anything outside the
+/// known bundle directories or containing `..`/absolute components.
+fn bundle_destination(entry: &Path, presets_dir: &Path, plugins_dir: &Path) -> Option<PathBuf> {
+    if entry
+        .components()
+        .any(|c| !matches!(c, Component::Normal(_) | Component::CurDir))
+    {
+        return None;
+    }
+    if let Ok(rest) = entry.strip_prefix(BUNDLE_PLUGINS_DIR) {
+        return Some(plugins_dir.join(rest));
+    }
+    if let Ok(rest) = entry.strip_prefix(BUNDLE_PRESETS_DIR) {
+        return Some(presets_dir.join(rest));
+    }
+    None
+}
+
+/// Handle `vm config preset --import <file>`.
+///
+/// Existing files are overwritten by the bundle's copy. The checksum sidecars of
+/// registry presets come from the same bundle, so they catch corruption but not
+/// deliberate edits: a bundle is trusted as a whole, like its source machine.
+pub fn handle_import(path: &Path) -> VmResult<()> {
+    let file = std::fs::File::open(path)
+        .map_err(|e| VmError::filesystem(e, path.display().to_string(), "open"))?;
+    let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(file));
+    archive.set_preserve_permissions(false);
+
+    let presets_dir = vm_config::get_presets_dir();
+    let plugins_dir = plugin_presets_dir();
+    let mut imported = 0;
+
+    let entries = archive
+        .entries()
+        .map_err(|e| VmError::general(e, "Failed to read preset bundle"))?;
+    for entry in entries {
+        let mut entry = entry.map_err(|e| VmError::general(e, "Failed to read preset bundle"))?;
+        let entry_type = entry.header().entry_type();
+        if !(entry_type.is_file() || entry_type.is_dir()) {
+            continue;
+        }
+        let entry_path = entry
+            .path()
+            .map_err(|e| VmError::general(e, "Failed to decode preset bundle entry"))?
+            .into_owned();
+        let Some(dest) = bundle_destination(&entry_path, &presets_dir, &plugins_dir) else {
+            return Err(VmError::validation(
+                format!(
+                    "Preset bundle contains unexpected path '{}'; refusing to import",
+                    entry_path.display()
+                ),
+                None::<String>,
+            ));
+        };
+
+        if entry_type.is_dir() {
+            std::fs::create_dir_all(&dest)
+                .map_err(|e| VmError::filesystem(e, dest.display().to_string(), "create"))?;
+            continue;
+        }
+        if let Some(parent) = dest.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| VmError::filesystem(e, parent.display().to_string(), "create"))?;
+        }
+        entry
+            .unpack(&dest)
+            .map_err(|e| VmError::filesystem(e, dest.display().to_string(), "extract"))?;
+        imported += 1;
+    }
+
+    vm_success!("Imported {} preset files from {}", imported, path.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bundle_entries_map_to_cache_directories() {
+        let presets = Path::new("/cache/presets");
+        let plugins = Path::new("/state/plugins/presets");
+
+        assert_eq!(
+            bundle_destination(
+                Path::new("presets/registry/acme/rails/1.0.0.yaml"),
+                presets,
+                plugins
+            ),
+            Some(presets.join("registry/acme/rails/1.0.0.yaml"))
+        );
+        assert_eq!(
+            bundle_destination(
+                Path::new("plugins/presets/django/plugin.yaml"),
+                presets,
+                plugins
+            ),
+            Some(plugins.join("django/plugin.yaml"))
+        );
+        assert_eq!(
+            bundle_destination(Path::new("presets/../../etc/passwd"), presets, plugins),
+            None
+        );
+        assert_eq!(
+            bundle_destination(Path::new("/etc/passwd"), presets, plugins),
+            None
+        );
+        assert_eq!(
+            bundle_destination(Path::new("other/file"), presets, plugins),
+            None
+        );
+    }
+}
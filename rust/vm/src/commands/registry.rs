@@ -13,6 +13,7 @@ use dialoguer::Confirm;
 use vm_config::GlobalConfig;
 use vm_core::msg;
 use vm_core::{vm_error, vm_println, vm_success};
+use vm_docker_registry::Pins;
 use vm_messages::messages::MESSAGES;
 
 use vm_package_server;
@@ -24,9 +25,11 @@ pub async fn handle_registry_command(
 ) -> VmResult<()> {
     match command {
         RegistrySubcommand::Status { yes } => handle_status(*yes, &global_config).await,
-        RegistrySubcommand::Add { r#type, yes } => {
-            handle_add(r#type.as_deref(), *yes, &global_config).await
-        }
+        RegistrySubcommand::Add {
+            r#type,
+            workspace,
+            yes,
+        } => handle_add(r#type.as_deref(), *workspace, *yes, &global_config).await,
         RegistrySubcommand::Remove { force, yes } => {
             handle_remove(*force, *yes, &global_config).await
         }
@@ -35,6 +38,15 @@ pub async fn handle_registry_command(
         RegistrySubcommand::Use { shell, port } => {
             handle_use(shell.as_deref(), *port, &global_config).await
         }
+        RegistrySubcommand::PublishPreset {
+            file,
+            name,
+            version,
+            yes,
+        } => handle_publish_preset(file, name, version, *yes, &global_config).await,
+        RegistrySubcommand::Pin { image } => handle_pin(image),
+        RegistrySubcommand::Unpin { image } => handle_unpin(image),
+        RegistrySubcommand::Pins => handle_pins(),
         RegistrySubcommand::Serve { host, port, data } => {
             handle_serve(host, *port, data, &global_config).await
         }
@@ -105,6 +117,7 @@ async fn handle_status(yes: bool, global_config: &GlobalConfig) -> VmResult<()>
 /// Add package from current directory
 async fn handle_add(
     package_type: Option<&str>,
+    workspace: bool,
     yes: bool,
     global_config: &GlobalConfig,
 ) -> VmResult<()> {
@@ -118,6 +131,13 @@ async fn handle_add(
 
     vm_println!("{}", MESSAGES.vm.pkg_publishing);
 
+    if workspace {
+        vm_package_server::client_ops::add_workspace_packages(&server_url, package_type)
+            .map_err(VmError::from)?;
+        vm_success!("Workspace packages published successfully");
+        return Ok(());
+    }
+
     vm_package_server::client_ops::add_package(&server_url, package_type).map_err(VmError::from)?;
 
     vm_success!("Package published successfully");
@@ -157,6 +177,79 @@ async fn handle_list(yes: bool, global_config: &GlobalConfig) -> VmResult<()> {
     Ok(())
 }
 
+/// Publish a preset file to the registry
+async fn handle_publish_preset(
+    file: &std::path::Path,
+    name: &str,
+    version: &str,
+    yes: bool,
+    global_config: &GlobalConfig,
+) -> VmResult<()> {
+    let server_url = format!(
+        "http://localhost:{}",
+        global_config.services.package_registry.port
+    );
+
+    let content = std::fs::read_to_string(file)
+        .with_context(|| format!("Failed to read preset file {}", file.display()))?;
+    serde_yaml_ng::from_str::<vm_config::preset::PresetFile>(&content).map_err(|e| {
+        VmError::validation(
+            format!("{} is not a valid preset file: {e}", file.display()),
+            None::<String>,
+        )
+    })?;
+
+    // Ensure server is running before attempting to publish
+    start_server_if_needed(global_config, yes).await?;
+
+    vm_package_server::api::PackageServerClient::new(&server_url)
+        .publish_preset(name, version, &content)
+        .map_err(VmError::from)?;
+
+    vm_success!("Published preset {}@{}", name, version);
+    vm_println!(
+        "  Apply with: vm config preset --from registry:{}@{}",
+        name,
+        version
+    );
+    Ok(())
+}
+
+/// Protect a Docker image from cache eviction
+fn handle_pin(image: &str) -> VmResult<()> {
+    let mut pins = Pins::load()?;
+    let image = pins.pin(image);
+    pins.save()?;
+    vm_success!("Pinned {} in the Docker registry cache", image);
+    Ok(())
+}
+
+/// Release a Docker image for cache eviction
+fn handle_unpin(image: &str) -> VmResult<()> {
+    let mut pins = Pins::load()?;
+    if !pins.unpin(image) {
+        vm_println!("{} is not protected", image);
+        return Ok(());
+    }
+    pins.save()?;
+    vm_success!("{} may be evicted from the Docker registry cache", image);
+    Ok(())
+}
+
+/// List protected Docker images with the VMs or pins protecting them
+fn handle_pins() -> VmResult<()> {
+    let protected = Pins::load()?.protected();
+    if protected.is_empty() {
+        vm_println!("No Docker images are protected from cache eviction");
+        return Ok(());
+    }
+    vm_println!("Protected Docker images:");
+    for (image, reasons) in protected {
+        vm_println!("  {} ({})", image, reasons.join(", "));
+    }
+    Ok(())
+}
+
 /// Handle configuration commands
 async fn handle_config(
     action: &RegistryConfigAction,
@@ -251,7 +344,7 @@ async fn check_server_running(global_config: &GlobalConfig) -> bool {
 /// Check if the package registry server is running at a specific URL
 async fn check_server_running_with_url(base_url: &str) -> bool {
     let health_url = format!("{base_url}/health");
-    match reqwest::get(&health_url).await {
+    match vm_core::network::client().get(&health_url).send().await {
         Ok(response) => response.status().is_success(),
         Err(_) => false,
     }
@@ -260,7 +353,9 @@ async fn check_server_running_with_url(base_url: &str) -> bool {
 /// Get the version of the running server
 async fn get_server_version(base_url: &str) -> VmResult<String> {
     let status_url = format!("{base_url}/api/status");
-    let response = reqwest::get(&status_url)
+    let response = vm_core::network::client()
+        .get(&status_url)
+        .send()
         .await
         .map_err(|e| VmError::general(e, "Failed to get server status"))?;
 
@@ -286,7 +381,7 @@ async fn get_server_version(base_url: &str) -> VmResult<String> {
 /// Gracefully shutdown the server
 async fn shutdown_server(base_url: &str) -> VmResult<()> {
     let shutdown_url = format!("{base_url}/shutdown");
-    let client = reqwest::Client::new();
+    let client = vm_core::network::client();
     let _ = client.post(&shutdown_url).send().await;
     Ok(())
 }
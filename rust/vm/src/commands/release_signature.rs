@@ -0,0 +1,283 @@
+//! Minisign verification for release downloads
+//!
+//! Release archives are signed with [minisign](https://jedisct1.github.io/minisign/)
+//! and published next to the archive as `<archive>.minisig`. `vm update` checks
+//! that signature against the public keys embedded at build time before it
+//! touches the installed binary.
+//!
+//! Keys are embedded from the `VM_RELEASE_PUBLIC_KEYS` environment variable at
+//! compile time (one minisign public key per line or comma-separated), which
+//! lets keys be rotated by publishing a release that embeds both the old and
+//! new key.
+//!
+//! The release workflow signs with the trusted comment
+//! `file:<archive> version:<tag>`, which [`check_trusted_comment`] matches
+//! against the download so a validly signed archive for another platform or an
+//! older release can't be substituted.
+
+use crate::error::{VmError, VmResult};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use blake2::{Blake2b512, Digest};
+use ed25519_dalek::{Signature, VerifyingKey};
+
+/// Release public keys embedded at build time
+const EMBEDDED_PUBLIC_KEYS: Option<&str> = option_env!("VM_RELEASE_PUBLIC_KEYS");
+
+/// Signature algorithm over the raw file contents (legacy minisign)
+const ALG_ED25519: [u8; 2] = *b"Ed";
+/// Signature algorithm over the BLAKE2b-512 hash of the file (minisign default)
+const ALG_ED25519_PREHASHED: [u8; 2] = *b"ED";
+
+const TRUSTED_COMMENT_PREFIX: &str = "trusted comment: ";
+
+/// A minisign public key
+#[derive(Debug, Clone)]
+pub struct PublicKey {
+    key_id: [u8; 8],
+    key: VerifyingKey,
+}
+
+impl PublicKey {
+    /// Parse a public key from the contents of a `.pub` file or its base64 line.
+    pub fn parse(text: &str) -> VmResult<Self> {
+        let encoded = text
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty() && !line.starts_with("untrusted comment:"))
+            .ok_or_else(|| malformed("public key is empty"))?;
+        let bytes = STANDARD
+            .decode(encoded)
+            .map_err(|_| malformed("public key is not valid base64"))?;
+        if bytes.len() != 42 || bytes[..2] != ALG_ED25519 {
+            return Err(malformed("public key is not a minisign Ed25519 key"));
+        }
+
+        let mut key_id = [0u8; 8];
+        key_id.copy_from_slice(&bytes[2..10]);
+        let mut key = [0u8; 32];
+        key.copy_from_slice(&bytes[10..]);
+        let key = VerifyingKey::from_bytes(&key).map_err(|_| malformed("public key is invalid"))?;
+        Ok(Self { key_id, key })
+    }
+
+    /// Key ID in the upper-case hex form minisign prints
+    pub fn key_id(&self) -> String {
+        format_key_id(&self.key_id)
+    }
+}
+
+fn format_key_id(key_id: &[u8; 8]) -> String {
+    // minisign stores the ID little-endian and displays it as a number
+    key_id.iter().rev().map(|b| format!("{b:02X}")).collect()
+}
+
+fn malformed(reason: &str) -> VmError {
+    VmError::validation(
+        format!("Release signature check failed: {reason}"),
+        None::<String>,
+    )
+}
+
+/// Public keys this binary trusts for release downloads.
+pub fn embedded_keys() -> VmResult<Vec<PublicKey>> {
+    EMBEDDED_PUBLIC_KEYS
+        .unwrap_or_default()
+        .split([',', '\n'])
+        .map(str::trim)
+        .filter(|key| !key.is_empty())
+        .map(PublicKey::parse)
+        .collect()
+}
+
+/// Verify `data` against the contents of a `.minisig` file.
+///
+/// Returns the signature's trusted comment on success. Both the file signature
+/// and the global signature covering the trusted comment must be valid, and the
+/// signature must come from one of `keys`.
+pub fn verify(data: &[u8], minisig: &str, keys: &[PublicKey]) -> VmResult<String> {
+    let mut lines = minisig
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty());
+    if lines
+        .next()
+        .is_some_and(|line| !line.starts_with("untrusted comment:"))
+    {
+        return Err(malformed("signature file is not in minisign format"));
+    }
+    let signature_line = lines
+        .next()
+        .ok_or_else(|| malformed("signature file has no signature"))?;
+    let trusted_comment = lines
+        .next()
+        .and_then(|line| line.strip_prefix(TRUSTED_COMMENT_PREFIX))
+        .ok_or_else(|| malformed("signature file has no trusted comment"))?;
+    let global_line = lines
+        .next()
+        .ok_or_else(|| malformed("signature file has no global signature"))?;
+
+    let signature_bytes = STANDARD
+        .decode(signature_line)
+        .map_err(|_| malformed("signature is not valid base64"))?;
+    if signature_bytes.len() != 74 {
+        return Err(malformed("signature has the wrong length"));
+    }
+    let algorithm = &signature_bytes[..2];
+    let mut key_id = [0u8; 8];
+    key_id.copy_from_slice(&signature_bytes[2..10]);
+    let signature = Signature::from_slice(&signature_bytes[10..])
+        .map_err(|_| malformed("signature is invalid"))?;
+
+    let key = keys
+        .iter()
+        .find(|key| key.key_id == key_id)
+        .ok_or_else(|| {
+            VmError::validation(
+                format!(
+                    "Release is signed with key {}, which this version of vm does not trust",
+                    format_key_id(&key_id)
+                ),
+                None::<String>,
+            )
+        })?;
+
+    let verified = if algorithm == ALG_ED25519_PREHASHED {
+        key.key.verify_strict(&Blake2b512::digest(data), &signature)
+    } else if algorithm == ALG_ED25519 {
+        key.key.verify_strict(data, &signature)
+    } else {
+        return Err(malformed("signature uses an unsupported algorithm"));
+    };
+    verified.map_err(|_| {
+        VmError::validation(
+            "Release signature does not match the downloaded file; it may have been tampered with",
+            None::<String>,
+        )
+    })?;
+
+    let global_signature = STANDARD
+        .decode(global_line)
+        .ok()
+        .and_then(|bytes| Signature::from_slice(&bytes).ok())
+        .ok_or_else(|| malformed("global signature is invalid"))?;
+    let mut signed_comment = signature_bytes[10..].to_vec();
+    signed_comment.extend_from_slice(trusted_comment.as_bytes());
+    key.key
+        .verify_strict(&signed_comment, &global_signature)
+        .map_err(|_| malformed("trusted comment has been modified"))?;
+
+    Ok(trusted_comment.to_string())
+}
+
+/// Check that a verified trusted comment names `asset` from release `version`.
+///
+/// The comment holds whitespace-separated `key:value` fields; the `v` prefix of
+/// release tags is ignored when comparing versions.
+pub fn check_trusted_comment(comment: &str, asset: &str, version: &str) -> VmResult<()> {
+    let field = |key: &str| {
+        comment
+            .split_whitespace()
+            .find_map(|field| field.strip_prefix(key)?.strip_prefix(':'))
+    };
+    if field("file") != Some(asset) {
+        return Err(VmError::validation(
+            format!("Release signature is for a different file than {asset} ({comment})"),
+            None::<String>,
+        ));
+    }
+    let signed_version = field("version").map(|v| v.trim_start_matches('v'));
+    if signed_version != Some(version.trim_start_matches('v')) {
+        return Err(VmError::validation(
+            format!("Release signature is not for version {version} ({comment})"),
+            None::<String>,
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Signer, SigningKey};
+
+    const KEY_ID: [u8; 8] = [1, 2, 3, 4, 5, 6, 7, 8];
+
+    fn signing_key() -> SigningKey {
+        SigningKey::from_bytes(&[7u8; 32])
+    }
+
+    fn public_key_file() -> String {
+        let mut bytes = ALG_ED25519.to_vec();
+        bytes.extend_from_slice(&KEY_ID);
+        bytes.extend_from_slice(signing_key().verifying_key().as_bytes());
+        format!(
+            "untrusted comment: minisign public key\n{}\n",
+            STANDARD.encode(bytes)
+        )
+    }
+
+    /// Produce a `.minisig` file the way `minisign -S` does
+    fn sign(data: &[u8], comment: &str) -> String {
+        let key = signing_key();
+        let signature = key.sign(&Blake2b512::digest(data)).to_bytes();
+        let mut signature_bytes = ALG_ED25519_PREHASHED.to_vec();
+        signature_bytes.extend_from_slice(&KEY_ID);
+        signature_bytes.extend_from_slice(&signature);
+
+        let mut signed_comment = signature.to_vec();
+        signed_comment.extend_from_slice(comment.as_bytes());
+        let global = key.sign(&signed_comment).to_bytes();
+
+        format!(
+            "untrusted comment: signature from minisign secret key\n{}\ntrusted comment: {comment}\n{}\n",
+            STANDARD.encode(signature_bytes),
+            STANDARD.encode(global)
+        )
+    }
+
+    #[test]
+    fn test_valid_signature_is_accepted() {
+        let keys = vec![PublicKey::parse(&public_key_file()).unwrap()];
+        let minisig = sign(b"release archive", "file:vm.tar.gz");
+
+        let comment = verify(b"release archive", &minisig, &keys).unwrap();
+        assert_eq!(comment, "file:vm.tar.gz");
+        assert_eq!(keys[0].key_id(), "0807060504030201");
+    }
+
+    #[test]
+    fn test_tampering_is_rejected() {
+        let keys = vec![PublicKey::parse(&public_key_file()).unwrap()];
+        let minisig = sign(b"release archive", "file:vm.tar.gz");
+
+        assert!(verify(b"tampered archive", &minisig, &keys).is_err());
+
+        let edited = minisig.replace("file:vm.tar.gz", "file:other.tar.gz");
+        assert!(verify(b"release archive", &edited, &keys).is_err());
+    }
+
+    #[test]
+    fn test_trusted_comment_must_name_asset_and_version() {
+        let comment = "file:vm-x86_64-unknown-linux-gnu.tar.gz version:v1.2.0";
+        let asset = "vm-x86_64-unknown-linux-gnu.tar.gz";
+        assert!(check_trusted_comment(comment, asset, "v1.2.0").is_ok());
+        assert!(check_trusted_comment(comment, asset, "1.2.0").is_ok());
+
+        // Another platform's archive or an older release, even if validly signed
+        assert!(
+            check_trusted_comment(comment, "vm-aarch64-apple-darwin.tar.gz", "v1.2.0").is_err()
+        );
+        assert!(check_trusted_comment(comment, asset, "v1.3.0").is_err());
+        assert!(
+            check_trusted_comment("file:vm-x86_64-unknown-linux-gnu.tar.gz", asset, "v1.2.0")
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_unknown_key_is_rejected() {
+        let minisig = sign(b"release archive", "file:vm.tar.gz");
+        let err = verify(b"release archive", &minisig, &[]).unwrap_err();
+        assert!(err.to_string().contains("0807060504030201"));
+    }
+}
@@ -0,0 +1,127 @@
+//! Usage reports
+//!
+//! Summarizes the per-project resource history collected by [`crate::usage`]
+//! so it's easy to spot which environments are worth pruning.
+
+use std::path::PathBuf;
+
+use chrono::{Duration as ChronoDuration, Utc};
+use vm_config::GlobalConfig;
+use vm_core::vm_println;
+
+use crate::cli::ReportSubcommand;
+use crate::error::{VmError, VmResult};
+use crate::services::container_runtime;
+use crate::usage::{self, UsageHistory, UsageSummary};
+
+/// Handle `vm report` subcommands
+pub async fn handle_report(
+    command: ReportSubcommand,
+    config_path: Option<PathBuf>,
+    profile: Option<String>,
+) -> VmResult<()> {
+    match command {
+        ReportSubcommand::Usage { since } => handle_usage(&since).await,
+        ReportSubcommand::Bundle { output } => {
+            crate::commands::report_bundle::handle_bundle(output, config_path, profile)
+        }
+    }
+}
+
+async fn handle_usage(since: &str) -> VmResult<()> {
+    let window = parse_window(since).ok_or_else(|| {
+        VmError::validation(
+            format!("Invalid --since value '{since}' (use e.g. 24h, 7d or 4w)"),
+            Some("since".to_string()),
+        )
+    })?;
+
+    // Take a fresh sample so running environments are up to date
+    let global_config = GlobalConfig::load().unwrap_or_default();
+    usage::sample_quietly(container_runtime(&global_config)).await;
+
+    let history = UsageHistory::load().map_err(|e| {
+        VmError::general(
+            std::io::Error::new(std::io::ErrorKind::Other, e.to_string()),
+            "Failed to load usage history",
+        )
+    })?;
+    let start = (Utc::now() - window).date_naive();
+    let summaries = history.summarize(start);
+
+    if summaries.is_empty() {
+        vm_println!("📊 No usage recorded since {}", start);
+        vm_println!("   Usage is sampled whenever VMs start or stop");
+        return Ok(());
+    }
+
+    print_usage(&summaries, since);
+    Ok(())
+}
+
+fn print_usage(summaries: &[UsageSummary], since: &str) {
+    vm_println!("📊 Resource usage (last {})\n", since);
+    vm_println!(
+        "  {:<28} {:>10} {:>12} {:>10}  LAST SEEN",
+        "PROJECT",
+        "CPU",
+        "PEAK MEM",
+        "DISK"
+    );
+    for summary in summaries {
+        vm_println!(
+            "  {:<28} {:>10} {:>12} {:>10}  {}",
+            summary.project,
+            format_cpu(summary.cpu_seconds),
+            format!("{} MB", summary.peak_memory_mb),
+            format!("{} MB", summary.disk_mb),
+            summary.last_seen.get(..10).unwrap_or(&summary.last_seen)
+        );
+    }
+    vm_println!("\n💡 Remove environments you no longer need with: vm destroy <container>");
+}
+
+/// Format CPU time as `1h 05m`, `12m 30s` or `45s`
+fn format_cpu(seconds: f64) -> String {
+    let total = seconds.round() as u64;
+    match (total / 3600, (total % 3600) / 60, total % 60) {
+        (0, 0, s) => format!("{s}s"),
+        (0, m, s) => format!("{m}m {s:02}s"),
+        (h, m, _) => format!("{h}h {m:02}m"),
+    }
+}
+
+/// Parse a reporting window like `24h`, `7d` or `4w`
+fn parse_window(value: &str) -> Option<ChronoDuration> {
+    let value = value.trim();
+    let unit = value.chars().last()?;
+    let amount: i64 = value[..value.len() - unit.len_utf8()]
+        .parse()
+        .ok()
+        .filter(|n| *n > 0)?;
+    match unit {
+        'h' => Some(ChronoDuration::hours(amount)),
+        'd' => Some(ChronoDuration::days(amount)),
+        'w' => Some(ChronoDuration::weeks(amount)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_window_and_format_cpu() {
+        assert_eq!(parse_window("7d"), Some(ChronoDuration::days(7)));
+        assert_eq!(parse_window("24h"), Some(ChronoDuration::hours(24)));
+        assert_eq!(parse_window("2w"), Some(ChronoDuration::weeks(2)));
+        assert_eq!(parse_window("0d"), None);
+        assert_eq!(parse_window("week"), None);
+        assert_eq!(parse_window(""), None);
+
+        assert_eq!(format_cpu(45.2), "45s");
+        assert_eq!(format_cpu(750.0), "12m 30s");
+        assert_eq!(format_cpu(3900.0), "1h 05m");
+    }
+}
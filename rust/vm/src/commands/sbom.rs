@@ -0,0 +1,31 @@
+//! Software bill of materials generation - thin wrapper around vm-snapshot
+
+use crate::error::VmResult;
+use std::path::{Path, PathBuf};
+use vm_config::AppConfig;
+
+/// Handle `vm sbom`
+pub async fn handle_sbom(
+    format: &str,
+    scanner: &str,
+    image: Option<&str>,
+    snapshot: Option<&str>,
+    output: Option<&Path>,
+    config_path: Option<PathBuf>,
+    profile: Option<String>,
+) -> VmResult<()> {
+    let app_config = AppConfig::load(config_path, profile, None)?;
+    let executable = app_config.vm.provider.as_deref().unwrap_or("docker");
+
+    vm_snapshot::sbom::handle_sbom(
+        &app_config,
+        executable,
+        format,
+        scanner,
+        image,
+        snapshot,
+        output,
+    )
+    .await?;
+    Ok(())
+}
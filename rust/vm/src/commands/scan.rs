@@ -0,0 +1,520 @@
+//! Vulnerability scanning for box images
+//!
+//! Packages are inventoried with the SBOM scanner and checked against the OSV
+//! database, which aggregates advisories from distros, GitHub, PyPI, npm and
+//! NVD-derived sources. The command exits non-zero when a finding meets the
+//! configured severity threshold so it can gate CI pipelines.
+
+use crate::error::{VmError, VmResult};
+use futures::stream::{self, StreamExt};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::path::PathBuf;
+use vm_config::global_config::ScanSettings;
+use vm_config::AppConfig;
+use vm_core::vm_println;
+use vm_snapshot::sbom::{self, Package};
+
+/// OSV accepts at most this many queries per batch request
+const OSV_BATCH_SIZE: usize = 1000;
+
+/// Concurrent vulnerability detail lookups
+const DETAIL_CONCURRENCY: usize = 8;
+
+/// Vulnerability severity, ordered from least to most severe
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd)]
+pub enum Severity {
+    Unknown,
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+impl Severity {
+    fn from_label(label: &str) -> Option<Self> {
+        match label.to_ascii_lowercase().as_str() {
+            "negligible" | "low" => Some(Self::Low),
+            "medium" | "moderate" => Some(Self::Medium),
+            "high" | "important" => Some(Self::High),
+            "critical" => Some(Self::Critical),
+            _ => None,
+        }
+    }
+
+    fn from_score(score: f64) -> Self {
+        match score {
+            s if s >= 9.0 => Self::Critical,
+            s if s >= 7.0 => Self::High,
+            s if s >= 4.0 => Self::Medium,
+            s if s > 0.0 => Self::Low,
+            _ => Self::Unknown,
+        }
+    }
+
+    /// Parse a `fail_on` threshold; `none` disables failing
+    pub fn parse_threshold(value: &str) -> VmResult<Option<Self>> {
+        match value {
+            "none" => Ok(None),
+            other => Self::from_label(other).map(Some).ok_or_else(|| {
+                VmError::validation(
+                    format!("Invalid severity threshold '{other}'"),
+                    Some("fail_on"),
+                )
+            }),
+        }
+    }
+
+    fn icon(self) -> &'static str {
+        match self {
+            Self::Critical => "🔴",
+            Self::High => "🟠",
+            Self::Medium => "🟡",
+            Self::Low => "🔵",
+            Self::Unknown => "⚪",
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            Self::Critical => "CRITICAL",
+            Self::High => "HIGH",
+            Self::Medium => "MEDIUM",
+            Self::Low => "LOW",
+            Self::Unknown => "UNKNOWN",
+        };
+        f.write_str(label)
+    }
+}
+
+/// A vulnerability affecting one package in the image
+#[derive(Debug)]
+struct Finding {
+    id: String,
+    severity: Severity,
+    package: Package,
+    summary: Option<String>,
+    fixed: Option<String>,
+}
+
+/// Compute a CVSS v3.x base score from a vector string
+pub fn cvss3_base_score(vector: &str) -> Option<f64> {
+    let metrics: HashMap<&str, &str> = vector
+        .split('/')
+        .skip(1)
+        .filter_map(|part| part.split_once(':'))
+        .collect();
+    let scope_changed = *metrics.get("S")? == "C";
+
+    let av = match *metrics.get("AV")? {
+        "N" => 0.85,
+        "A" => 0.62,
+        "L" => 0.55,
+        "P" => 0.2,
+        _ => return None,
+    };
+    let ac = match *metrics.get("AC")? {
+        "L" => 0.77,
+        "H" => 0.44,
+        _ => return None,
+    };
+    let pr = match (*metrics.get("PR")?, scope_changed) {
+        ("N", _) => 0.85,
+        ("L", false) => 0.62,
+        ("L", true) => 0.68,
+        ("H", false) => 0.27,
+        ("H", true) => 0.5,
+        _ => return None,
+    };
+    let ui = match *metrics.get("UI")? {
+        "N" => 0.85,
+        "R" => 0.62,
+        _ => return None,
+    };
+    let cia = |key: &str| -> Option<f64> {
+        match *metrics.get(key)? {
+            "H" => Some(0.56),
+            "L" => Some(0.22),
+            "N" => Some(0.0),
+            _ => None,
+        }
+    };
+    let iss = 1.0 - (1.0 - cia("C")?) * (1.0 - cia("I")?) * (1.0 - cia("A")?);
+
+    let impact = if scope_changed {
+        7.52 * (iss - 0.029) - 3.25 * (iss - 0.02).powi(15)
+    } else {
+        6.42 * iss
+    };
+    if impact <= 0.0 {
+        return Some(0.0);
+    }
+    let exploitability = 8.22 * av * ac * pr * ui;
+    let raw = if scope_changed {
+        1.08 * (impact + exploitability)
+    } else {
+        impact + exploitability
+    };
+    Some(round_up(raw.min(10.0)))
+}
+
+/// CVSS "round up" to one decimal place
+fn round_up(value: f64) -> f64 {
+    let int_input = (value * 100_000.0).round() as i64;
+    if int_input % 10_000 == 0 {
+        int_input as f64 / 100_000.0
+    } else {
+        ((int_input / 10_000) + 1) as f64 / 10.0
+    }
+}
+
+/// Determine the severity of an OSV vulnerability record
+pub fn vuln_severity(vuln: &Value) -> Severity {
+    if let Some(label) = vuln
+        .pointer("/database_specific/severity")
+        .and_then(Value::as_str)
+        .and_then(Severity::from_label)
+    {
+        return label;
+    }
+
+    vuln.get("severity")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| {
+            let score = entry.get("score")?.as_str()?;
+            match entry.get("type")?.as_str()? {
+                "CVSS_V3" => cvss3_base_score(score).map(Severity::from_score),
+                _ => Severity::from_label(score),
+            }
+        })
+        .max()
+        .unwrap_or(Severity::Unknown)
+}
+
+/// First fixed version recorded for a package in an OSV record
+fn fixed_version(vuln: &Value, package: &str) -> Option<String> {
+    vuln.get("affected")?
+        .as_array()?
+        .iter()
+        .filter(|affected| {
+            affected
+                .pointer("/package/name")
+                .and_then(Value::as_str)
+                .is_some_and(|name| name.eq_ignore_ascii_case(package))
+        })
+        .flat_map(|affected| {
+            affected
+                .get("ranges")
+                .and_then(Value::as_array)
+                .cloned()
+                .unwrap_or_default()
+        })
+        .flat_map(|range| {
+            range
+                .get("events")
+                .and_then(Value::as_array)
+                .cloned()
+                .unwrap_or_default()
+        })
+        .find_map(|event| event.get("fixed")?.as_str().map(str::to_string))
+}
+
+fn is_ignored(vuln: &Value, id: &str, ignore: &[String]) -> bool {
+    let aliases = vuln
+        .get("aliases")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_str);
+    std::iter::once(id)
+        .chain(aliases)
+        .any(|candidate| ignore.iter().any(|i| i.eq_ignore_ascii_case(candidate)))
+}
+
+fn network_error(e: reqwest::Error, endpoint: &str, context: &str) -> VmError {
+    VmError::network(e, Some(endpoint), context)
+}
+
+/// Query OSV for the vulnerability IDs affecting each package
+async fn query_osv(
+    client: &reqwest::Client,
+    base_url: &str,
+    packages: &[Package],
+) -> VmResult<Vec<Vec<String>>> {
+    let endpoint = format!("{}/v1/querybatch", base_url.trim_end_matches('/'));
+    let mut ids = Vec::with_capacity(packages.len());
+
+    for chunk in packages.chunks(OSV_BATCH_SIZE) {
+        let queries: Vec<Value> = chunk
+            .iter()
+            .map(|p| json!({ "package": { "purl": p.purl() } }))
+            .collect();
+        let response: Value = client
+            .post(&endpoint)
+            .json(&json!({ "queries": queries }))
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .map_err(|e| network_error(e, &endpoint, "OSV batch query failed"))?
+            .json()
+            .await
+            .map_err(|e| network_error(e, &endpoint, "Invalid OSV response"))?;
+
+        let results = response
+            .get("results")
+            .and_then(Value::as_array)
+            .cloned()
+            .unwrap_or_default();
+        for index in 0..chunk.len() {
+            let vuln_ids = results
+                .get(index)
+                .and_then(|r| r.get("vulns"))
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .filter_map(|v| v.get("id")?.as_str().map(str::to_string))
+                .collect();
+            ids.push(vuln_ids);
+        }
+    }
+
+    Ok(ids)
+}
+
+/// Fetch full OSV records for a set of vulnerability IDs
+async fn fetch_vulns(
+    client: &reqwest::Client,
+    base_url: &str,
+    ids: Vec<String>,
+) -> VmResult<HashMap<String, Value>> {
+    let base_url = base_url.trim_end_matches('/');
+    let fetched: Vec<VmResult<(String, Value)>> = stream::iter(ids)
+        .map(|id| async move {
+            let endpoint = format!("{base_url}/v1/vulns/{id}");
+            let vuln = client
+                .get(&endpoint)
+                .send()
+                .await
+                .and_then(reqwest::Response::error_for_status)
+                .map_err(|e| network_error(e, &endpoint, "OSV lookup failed"))?
+                .json()
+                .await
+                .map_err(|e| network_error(e, &endpoint, "Invalid OSV response"))?;
+            Ok((id, vuln))
+        })
+        .buffer_unordered(DETAIL_CONCURRENCY)
+        .collect()
+        .await;
+
+    fetched.into_iter().collect()
+}
+
+/// Handle `vm scan`
+pub async fn handle_scan(
+    image: Option<&str>,
+    fail_on: Option<&str>,
+    config_path: Option<PathBuf>,
+    profile: Option<String>,
+) -> VmResult<()> {
+    let app_config = AppConfig::load(config_path, profile, None)?;
+    let executable = app_config.vm.provider.as_deref().unwrap_or("docker");
+    let settings: &ScanSettings = &app_config.global.scan;
+    let threshold = Severity::parse_threshold(fail_on.unwrap_or(&settings.fail_on))?;
+
+    let image = match image {
+        Some(image) => image.to_string(),
+        None => {
+            let project_name = app_config
+                .vm
+                .project
+                .as_ref()
+                .and_then(|p| p.name.clone())
+                .unwrap_or_else(|| "default".to_string());
+            sbom::project_image(executable, &project_name).await?
+        }
+    };
+
+    vm_println!("🔍 Scanning {}...", image);
+    let packages = sbom::collect_packages(executable, &image).await?;
+    vm_println!("  Checking {} packages against OSV...", packages.len());
+
+    let client = vm_core::network::client();
+    let ids = query_osv(&client, &settings.osv_url, &packages).await?;
+    let mut unique: Vec<String> = ids.iter().flatten().cloned().collect();
+    unique.sort();
+    unique.dedup();
+    let vulns = fetch_vulns(&client, &settings.osv_url, unique).await?;
+
+    let mut findings = Vec::new();
+    let mut ignored = 0;
+    for (package, vuln_ids) in packages.iter().zip(ids) {
+        for id in vuln_ids {
+            let Some(vuln) = vulns.get(&id) else {
+                continue;
+            };
+            if is_ignored(vuln, &id, &settings.ignore) {
+                ignored += 1;
+                continue;
+            }
+            findings.push(Finding {
+                severity: vuln_severity(vuln),
+                summary: vuln
+                    .get("summary")
+                    .and_then(Value::as_str)
+                    .map(str::to_string),
+                fixed: fixed_version(vuln, &package.name),
+                package: package.clone(),
+                id,
+            });
+        }
+    }
+    findings.sort_by(|a, b| {
+        b.severity
+            .cmp(&a.severity)
+            .then_with(|| a.package.name.cmp(&b.package.name))
+    });
+
+    print_findings(&findings, ignored);
+
+    if let Some(threshold) = threshold {
+        let failing = findings.iter().filter(|f| f.severity >= threshold).count();
+        if failing > 0 {
+            return Err(VmError::validation(
+                format!(
+                    "{failing} vulnerabilit{} at or above {threshold} severity",
+                    if failing == 1 { "y" } else { "ies" }
+                ),
+                Some("scan.fail_on"),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+fn print_findings(findings: &[Finding], ignored: usize) {
+    if findings.is_empty() {
+        vm_println!("✅ No known vulnerabilities found");
+    } else {
+        vm_println!("");
+        for finding in findings {
+            let fix = finding
+                .fixed
+                .as_deref()
+                .map(|v| format!(" → fixed in {v}"))
+                .unwrap_or_default();
+            vm_println!(
+                "  {} {:<8} {}  {} {}{}",
+                finding.severity.icon(),
+                finding.severity.to_string(),
+                finding.id,
+                finding.package.name,
+                finding.package.version,
+                fix
+            );
+            if let Some(summary) = &finding.summary {
+                vm_println!("       {}", summary);
+            }
+        }
+
+        let mut counts: BTreeMap<std::cmp::Reverse<Severity>, usize> = BTreeMap::new();
+        for finding in findings {
+            *counts
+                .entry(std::cmp::Reverse(finding.severity))
+                .or_default() += 1;
+        }
+        let summary: Vec<String> = counts
+            .iter()
+            .map(|(severity, count)| format!("{} {}", count, severity.0))
+            .collect();
+        vm_println!(
+            "\n📊 {} vulnerabilities: {}",
+            findings.len(),
+            summary.join(", ")
+        );
+    }
+
+    if ignored > 0 {
+        vm_println!("  ({} ignored via scan.ignore)", ignored);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cvss3_base_score() {
+        assert_eq!(
+            cvss3_base_score("CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:H/A:H"),
+            Some(9.8)
+        );
+        assert_eq!(
+            cvss3_base_score("CVSS:3.1/AV:N/AC:L/PR:N/UI:R/S:C/C:L/I:L/A:N"),
+            Some(6.1)
+        );
+        assert_eq!(
+            cvss3_base_score("CVSS:3.1/AV:L/AC:L/PR:L/UI:N/S:U/C:N/I:N/A:N"),
+            Some(0.0)
+        );
+        assert_eq!(cvss3_base_score("not a vector"), None);
+    }
+
+    #[test]
+    fn test_vuln_severity_sources() {
+        let ghsa = json!({ "database_specific": { "severity": "MODERATE" } });
+        assert_eq!(vuln_severity(&ghsa), Severity::Medium);
+
+        let cvss = json!({ "severity": [
+            { "type": "CVSS_V3", "score": "CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:H/A:H" }
+        ]});
+        assert_eq!(vuln_severity(&cvss), Severity::Critical);
+
+        let ubuntu = json!({ "severity": [{ "type": "Ubuntu", "score": "low" }] });
+        assert_eq!(vuln_severity(&ubuntu), Severity::Low);
+
+        assert_eq!(vuln_severity(&json!({})), Severity::Unknown);
+    }
+
+    #[test]
+    fn test_threshold_and_ignore() {
+        assert_eq!(Severity::parse_threshold("none").unwrap(), None);
+        assert_eq!(
+            Severity::parse_threshold("high").unwrap(),
+            Some(Severity::High)
+        );
+        assert!(Severity::parse_threshold("severe").is_err());
+
+        let vuln = json!({ "aliases": ["CVE-2024-0001"] });
+        assert!(is_ignored(
+            &vuln,
+            "GHSA-aaaa",
+            &["cve-2024-0001".to_string()]
+        ));
+        assert!(!is_ignored(
+            &vuln,
+            "GHSA-aaaa",
+            &["CVE-2024-9999".to_string()]
+        ));
+    }
+
+    #[test]
+    fn test_fixed_version() {
+        let vuln = json!({ "affected": [{
+            "package": { "name": "openssl", "ecosystem": "Ubuntu:22.04" },
+            "ranges": [{ "type": "ECOSYSTEM", "events": [
+                { "introduced": "0" }, { "fixed": "3.0.2-0ubuntu1.15" }
+            ]}]
+        }]});
+        assert_eq!(
+            fixed_version(&vuln, "openssl").as_deref(),
+            Some("3.0.2-0ubuntu1.15")
+        );
+        assert_eq!(fixed_version(&vuln, "curl"), None);
+    }
+}
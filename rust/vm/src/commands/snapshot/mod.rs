@@ -2,7 +2,9 @@
 
 use crate::cli::SnapshotSubcommand;
 use crate::error::VmResult;
+use crate::notifications;
 use std::path::PathBuf;
+use std::time::Instant;
 use vm_config::AppConfig;
 
 // Re-export modules and types for internal use
@@ -16,6 +18,7 @@ pub async fn handle_snapshot(
 ) -> VmResult<()> {
     let app_config = AppConfig::load(config_path, profile, None)?;
     let executable = app_config.vm.provider.as_deref().unwrap_or("docker");
+    let started = Instant::now();
 
     match command {
         SnapshotSubcommand::Create {
@@ -27,6 +30,7 @@ pub async fn handle_snapshot(
             build_context,
             build_arg,
             force,
+            allow_secrets,
         } => {
             vm_snapshot::create::handle_create(
                 &app_config,
@@ -39,8 +43,15 @@ pub async fn handle_snapshot(
                 build_context.as_deref(),
                 &build_arg,
                 force,
+                allow_secrets,
             )
             .await?;
+            notifications::notify_finished(
+                &app_config.global,
+                started,
+                "Snapshot created",
+                &format!("Snapshot '{name}' is ready"),
+            );
         }
         SnapshotSubcommand::List { project, r#type } => {
             vm_snapshot::manager::handle_list(project.as_deref(), r#type.as_deref(), true).await?;
@@ -58,6 +69,12 @@ pub async fn handle_snapshot(
                 force,
             )
             .await?;
+            notifications::notify_finished(
+                &app_config.global,
+                started,
+                "Snapshot restored",
+                &format!("Snapshot '{name}' has been restored"),
+            );
         }
         SnapshotSubcommand::Delete {
             name,
@@ -71,6 +88,7 @@ pub async fn handle_snapshot(
             output,
             compress,
             project,
+            allow_secrets,
         } => {
             vm_snapshot::export::handle_export(
                 executable,
@@ -78,8 +96,15 @@ pub async fn handle_snapshot(
                 output.as_deref(),
                 compress,
                 project.as_deref(),
+                allow_secrets,
             )
             .await?;
+            notifications::notify_finished(
+                &app_config.global,
+                started,
+                "Snapshot exported",
+                &format!("Snapshot '{name}' has been exported"),
+            );
         }
         SnapshotSubcommand::Import {
             file,
@@ -89,6 +114,12 @@ pub async fn handle_snapshot(
         } => {
             vm_snapshot::import::handle_import(executable, &file, name.as_deref(), verify, force)
                 .await?;
+            notifications::notify_finished(
+                &app_config.global,
+                started,
+                "Snapshot imported",
+                &format!("{} has been imported", file.display()),
+            );
         }
     }
 
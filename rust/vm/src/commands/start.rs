@@ -13,7 +13,10 @@ use vm_config::{
     resources::detect_resource_defaults,
     AppConfig,
 };
-use vm_core::{vm_println, vm_success};
+use vm_core::{vm_println, vm_success, vm_warning};
+use vm_provider::common::instance_env::{
+    apply_env_overrides, parse_env_assignment, save_instance_env,
+};
 use vm_provider::get_provider;
 
 /// Handle the `vm start` command
@@ -24,15 +27,24 @@ use vm_provider::get_provider;
 /// 2. Creating the VM if it doesn't exist
 /// 3. Starting the VM if it's stopped
 /// 4. Opening an SSH session (or executing a command)
+#[allow(clippy::too_many_arguments)]
 pub async fn handle_start(
     config_path: Option<std::path::PathBuf>,
     command: Option<String>,
     profile: Option<String>,
     provider_override: Option<String>,
     wait: bool,
+    instance: Option<String>,
+    env: &[String],
+    save_env: bool,
 ) -> VmResult<()> {
     debug!("Handling vm start command");
 
+    let env_overrides = env
+        .iter()
+        .map(|assignment| parse_env_assignment(assignment))
+        .collect::<Result<Vec<_>, _>>()?;
+
     // Stage 1: Check if vm.yaml exists, create if not
     let config_file = config_path
         .clone()
@@ -91,40 +103,67 @@ pub async fn handle_start(
         .and_then(|p| p.name.clone())
         .unwrap_or_else(|| "vm-project".to_string());
 
+    if save_env {
+        let instance_name = instance
+            .as_deref()
+            .unwrap_or(vm_core::labels::DEFAULT_INSTANCE);
+        save_instance_env(&project_name, instance_name, &env_overrides)?;
+        vm_println!(
+            "💾 Saved environment overrides for '{}' ({} instance)",
+            project_name,
+            instance_name
+        );
+    }
+
+    // Named instances run as `<project>-<instance>`
+    let container = instance
+        .as_ref()
+        .map(|instance| format!("{project_name}-{instance}"));
+    let container = container.as_deref();
+
     // Try to get status report - this tells us if VM exists and its state
-    let status_report = provider.get_status_report(None);
+    let status_report = provider.get_status_report(container);
     debug!("Current VM status report: {:?}", status_report);
 
     match status_report {
         Ok(report) if report.is_running => {
             vm_println!("✓ VM '{}' is already running", project_name);
+            if !env_overrides.is_empty() {
+                vm_warning!(
+                    "--env takes effect on the next start; run 'vm stop' first to apply it"
+                );
+            }
         }
         Ok(_report) => {
             // VM exists but is stopped
             vm_println!("🚀 Starting VM '{}'...", project_name);
             vm_ops::handle_start(
                 get_provider(config.clone()).map_err(VmError::from)?,
-                None,
+                container,
                 config.clone(),
                 global_config.clone(),
                 false,
+                env_overrides.clone(),
             )
             .await?;
         }
         Err(_) => {
             // VM doesn't exist, create it
             vm_println!("🚀 Creating VM '{}'...", project_name);
+            let mut create_config = config.clone();
+            apply_env_overrides(&mut create_config.environment, &env_overrides);
             vm_ops::handle_create(
-                get_provider(config.clone()).map_err(VmError::from)?,
-                config.clone(),
+                get_provider(create_config.clone()).map_err(VmError::from)?,
+                create_config,
                 global_config.clone(),
                 false, // force
-                None,  // instance
+                instance.clone(),
                 false, // verbose
                 None,  // save_as
                 None,  // from_dockerfile
                 true,  // preserve_services
                 false, // refresh_packages
+                false, // ephemeral
             )
             .await?;
         }
@@ -134,12 +173,13 @@ pub async fn handle_start(
         let provider = get_provider(config.clone()).map_err(VmError::from)?;
         vm_ops::handle_wait(
             provider,
-            None,
+            container,
             None,
             60,
             config.clone(),
             global_config.clone(),
-        )?;
+        )
+        .await?;
     }
 
     // Stage 4: SSH in or execute command
@@ -149,7 +189,7 @@ pub async fn handle_start(
         debug!("Executing command: {}", cmd);
         vm_ops::handle_ssh(
             provider,
-            None,
+            container,
             None,
             Some(vec!["/bin/bash".to_string(), "-c".to_string(), cmd]),
             config,
@@ -158,6 +198,6 @@ pub async fn handle_start(
         )
     } else {
         debug!("Opening interactive SSH session");
-        vm_ops::handle_ssh(provider, None, None, None, config, false, false)
+        vm_ops::handle_ssh(provider, container, None, None, config, false, false)
     }
 }
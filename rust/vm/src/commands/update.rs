@@ -1,3 +1,4 @@
+use crate::commands::release_signature;
 use crate::error::VmError;
 use std::process::Command;
 use vm_core::msg;
@@ -6,7 +7,22 @@ use vm_messages::messages::MESSAGES;
 
 const CARGO_PACKAGE_NAME: &str = "goobits-vm";
 
-pub fn handle_update(version: Option<&str>, _force: bool) -> Result<(), VmError> {
+/// A `curl` command that also trusts the configured extra CA bundle.
+fn curl(temp_dir: &std::path::Path) -> Result<Command, VmError> {
+    let mut command = Command::new("curl");
+    if let Some(bundle) = vm_core::network::write_combined_ca_bundle(temp_dir)? {
+        command.arg("--cacert").arg(bundle);
+    }
+    Ok(command)
+}
+
+pub fn handle_update(
+    version: Option<&str>,
+    _force: bool,
+    skip_verify: bool,
+) -> Result<(), VmError> {
+    vm_core::offline::ensure_online("check for or download updates")?;
+
     // Get current version
     let current_version = env!("CARGO_PKG_VERSION");
     let normalized_current_version = normalize_cargo_version(current_version);
@@ -89,7 +105,7 @@ pub fn handle_update(version: Option<&str>, _force: bool) -> Result<(), VmError>
 
         // Download release info
         vm_println!("{}", MESSAGES.vm.update_fetching_release);
-        let release_info = Command::new("curl")
+        let release_info = curl(&temp_dir)?
             .args([
                 "-sSL",
                 "-H",
@@ -156,7 +172,7 @@ pub fn handle_update(version: Option<&str>, _force: bool) -> Result<(), VmError>
                 "Archive path is not valid UTF-8",
             )
         })?;
-        let download_output = Command::new("curl")
+        let download_output = curl(&temp_dir)?
             .args(["-sSL", "-o", archive_path_str, &asset_url])
             .output()?;
 
@@ -168,6 +184,19 @@ pub fn handle_update(version: Option<&str>, _force: bool) -> Result<(), VmError>
             ));
         }
 
+        // Verify the release signature before anything is unpacked or replaced
+        if skip_verify {
+            vm_warning!("Skipping release signature verification (--skip-verify)");
+        } else {
+            verify_download(
+                &archive_path,
+                &asset_url,
+                &asset_pattern,
+                &release_tag,
+                &temp_dir,
+            )?;
+        }
+
         // Extract the archive
         vm_println!("{}", MESSAGES.vm.update_extracting);
         let temp_dir_str = temp_dir.to_str().ok_or_else(|| {
@@ -247,6 +276,63 @@ pub fn handle_update(version: Option<&str>, _force: bool) -> Result<(), VmError>
     Ok(())
 }
 
+/// Download `<asset_url>.minisig` and check the archive against the embedded release keys.
+///
+/// The signature's trusted comment must also name `asset` and `release_tag`.
+fn verify_download(
+    archive_path: &std::path::Path,
+    asset_url: &str,
+    asset: &str,
+    release_tag: &str,
+    temp_dir: &std::path::Path,
+) -> Result<(), VmError> {
+    let keys = release_signature::embedded_keys()?;
+    if keys.is_empty() {
+        return Err(VmError::validation(
+            "This build of vm has no release signing keys embedded, so downloaded updates cannot be verified. Reinstall from an official release, or pass --skip-verify to update anyway",
+            None::<String>,
+        ));
+    }
+    let key_ids: Vec<String> = keys.iter().map(|key| key.key_id()).collect();
+    vm_println!(
+        "🔏 Verifying release signature (trusted keys: {})...",
+        key_ids.join(", ")
+    );
+
+    let signature_url = format!("{asset_url}.minisig");
+    let signature_path = temp_dir.join("release.minisig");
+    let download = curl(temp_dir)?
+        .args(["-fsSL", "-o"])
+        .arg(&signature_path)
+        .arg(&signature_url)
+        .output()?;
+    if !download.status.success() {
+        let _ = std::fs::remove_dir_all(temp_dir);
+        return Err(VmError::validation(
+            format!("Release has no signature at {signature_url}; refusing to install an unsigned binary"),
+            None::<String>,
+        ));
+    }
+
+    let archive = std::fs::read(archive_path)?;
+    let minisig = std::fs::read_to_string(&signature_path)?;
+    let verified = release_signature::verify(&archive, &minisig, &keys).and_then(|comment| {
+        release_signature::check_trusted_comment(&comment, asset, release_tag)?;
+        Ok(comment)
+    });
+    match verified {
+        Ok(comment) => {
+            vm_success!("Release signature verified ({})", comment);
+            Ok(())
+        }
+        Err(e) => {
+            let _ = std::fs::remove_dir_all(temp_dir);
+            vm_error!("The downloaded release failed signature verification; your installed vm was not changed");
+            Err(e)
+        }
+    }
+}
+
 fn normalize_cargo_version(version: &str) -> String {
     version.strip_prefix('v').unwrap_or(version).to_string()
 }
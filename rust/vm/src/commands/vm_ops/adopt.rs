@@ -0,0 +1,30 @@
+//! Adoption of pre-existing containers
+//!
+//! `vm adopt` registers a container that vm didn't create so it shows up in
+//! `vm list` and can be targeted by the other commands like any managed one.
+
+use crate::error::VmResult;
+use vm_config::config::VmConfig;
+use vm_core::vm_println;
+use vm_provider::Provider;
+
+/// Handle `vm adopt <container>`
+pub fn handle_adopt(
+    provider: Box<dyn Provider>,
+    container: &str,
+    config: &VmConfig,
+) -> VmResult<()> {
+    let name = provider.adopt(container)?;
+    let project = config
+        .project
+        .as_ref()
+        .and_then(|p| p.name.as_deref())
+        .unwrap_or("vm-project");
+
+    vm_println!("✅ Adopted '{}' into project '{}'", name, project);
+    vm_println!(
+        "💡 It now appears in 'vm list'; manage it with e.g. 'vm ssh {}'",
+        name
+    );
+    Ok(())
+}
@@ -0,0 +1,263 @@
+//! File copy between the host and VMs
+//!
+//! `vm copy` takes one source and one destination, either of which may be a VM
+//! path written as `<container>:/path` (or `:/path` with `--all-vms`). Host
+//! sources may be glob patterns; every match is copied into the destination
+//! directory. Directories are copied recursively with their permission bits, and
+//! `--all-vms` repeats the whole set of copies for each managed instance.
+
+use std::path::Path;
+
+use tracing::debug;
+use walkdir::WalkDir;
+
+use crate::error::{VmError, VmResult};
+use crate::utils::format_size;
+use vm_config::config::VmConfig;
+use vm_core::vm_println;
+use vm_provider::Provider;
+
+/// One provider-level copy
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct CopyItem {
+    source: String,
+    destination: String,
+}
+
+fn has_glob(path: &str) -> bool {
+    path.contains(['*', '?', '['])
+}
+
+/// Expand a host source into the paths it names, in sorted order
+fn expand_host_source(source: &str) -> VmResult<Vec<String>> {
+    if !has_glob(source) {
+        return Ok(vec![source.to_string()]);
+    }
+    let paths = glob::glob(source).map_err(|e| {
+        VmError::validation(
+            format!("Invalid glob pattern '{source}': {e}"),
+            Some("source"),
+        )
+    })?;
+    let mut matches: Vec<String> = paths
+        .filter_map(Result::ok)
+        .map(|path| path.to_string_lossy().into_owned())
+        .collect();
+    if matches.is_empty() {
+        return Err(VmError::validation(
+            format!("No files match '{source}'"),
+            Some("source"),
+        ));
+    }
+    matches.sort();
+    Ok(matches)
+}
+
+/// Copies needed for `sources`; several sources go into `destination` as a directory
+fn plan_copies(sources: &[String], destination: &str) -> Vec<CopyItem> {
+    if let [source] = sources {
+        return vec![CopyItem {
+            source: source.clone(),
+            destination: destination.to_string(),
+        }];
+    }
+    let directory = destination.trim_end_matches('/');
+    sources
+        .iter()
+        .map(|source| {
+            let name = Path::new(source)
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_else(|| source.clone());
+            CopyItem {
+                source: source.clone(),
+                destination: format!("{directory}/{name}"),
+            }
+        })
+        .collect()
+}
+
+/// Point a VM-side path (`:/path`, `/path` or `<container>:/path`) at `instance`
+fn on_instance(path: &str, instance: &str) -> String {
+    let path = path.split_once(':').map_or(path, |(_, path)| path);
+    format!("{instance}:{path}")
+}
+
+/// Number of files and total bytes under the host paths
+fn host_totals(paths: &[String]) -> (usize, u64) {
+    paths
+        .iter()
+        .flat_map(WalkDir::new)
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_file())
+        .fold((0, 0), |(files, bytes), entry| {
+            let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+            (files + 1, bytes + size)
+        })
+}
+
+/// Handle file copy to/from VM
+pub fn handle_copy(
+    provider: Box<dyn Provider>,
+    source: &str,
+    destination: &str,
+    all_vms: bool,
+    config: VmConfig,
+) -> VmResult<()> {
+    debug!(
+        "Copying files: source='{}', destination='{}', all_vms={}, provider='{}'",
+        source,
+        destination,
+        all_vms,
+        provider.name()
+    );
+
+    let to_vm = !source.contains(':');
+    if !to_vm && has_glob(source) {
+        return Err(VmError::validation(
+            "Glob patterns only work for host paths; copy the enclosing directory instead",
+            Some("source"),
+        ));
+    }
+    let sources = if to_vm {
+        expand_host_source(source)?
+    } else {
+        vec![source.to_string()]
+    };
+    let items = plan_copies(&sources, destination);
+
+    let targets: Vec<Option<String>> = if all_vms {
+        let instances = provider.list_instances().map_err(VmError::from)?;
+        if instances.is_empty() {
+            vm_println!("No instances found");
+            return Ok(());
+        }
+        instances.into_iter().map(|i| Some(i.name)).collect()
+    } else {
+        vec![None]
+    };
+
+    let target_label = if all_vms {
+        format!("{} instances", targets.len())
+    } else {
+        let vm_name = config
+            .project
+            .as_ref()
+            .and_then(|p| p.name.as_deref())
+            .unwrap_or("vm-project");
+        format!("VM '{vm_name}'")
+    };
+    if to_vm {
+        let (files, bytes) = host_totals(&sources);
+        vm_println!(
+            "📦 Copying {} file{} ({}) to {}...",
+            files,
+            if files == 1 { "" } else { "s" },
+            format_size(bytes),
+            target_label
+        );
+    } else {
+        vm_println!("📦 Copying files from {}...", target_label);
+    }
+
+    let total = targets.len() * items.len();
+    let mut done = 0;
+    let mut failed = Vec::new();
+    for target in &targets {
+        for item in &items {
+            done += 1;
+            let (copy_source, copy_destination) = match target {
+                Some(instance) if to_vm => (
+                    item.source.clone(),
+                    on_instance(&item.destination, instance),
+                ),
+                Some(instance) => (
+                    on_instance(&item.source, instance),
+                    item.destination.clone(),
+                ),
+                None => (item.source.clone(), item.destination.clone()),
+            };
+            let label = match target {
+                Some(instance) => format!("{instance}: {}", item.source),
+                None => item.source.clone(),
+            };
+
+            match provider.copy(&copy_source, &copy_destination, target.as_deref()) {
+                Ok(()) if total > 1 => vm_println!("  ✓ [{}/{}] {}", done, total, label),
+                Ok(()) => {}
+                Err(e) if total == 1 => {
+                    vm_println!("❌ Copy failed: {}", e);
+                    return Err(VmError::from(e));
+                }
+                Err(e) => {
+                    vm_println!("  ❌ [{}/{}] {}: {}", done, total, label, e);
+                    failed.push(label);
+                }
+            }
+        }
+    }
+
+    if total > 1 {
+        vm_println!(
+            "\n📊 {} of {} copies successful",
+            total - failed.len(),
+            total
+        );
+    } else {
+        vm_println!("✓ File copied successfully");
+    }
+    if !failed.is_empty() {
+        return Err(vm_core::error::VmError::Provider(format!(
+            "Failed to copy: {}",
+            failed.join(", ")
+        ))
+        .into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn single_source_keeps_destination() {
+        let items = plan_copies(&["dist".to_string()], ":/srv/app");
+        assert_eq!(
+            items,
+            vec![CopyItem {
+                source: "dist".to_string(),
+                destination: ":/srv/app".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn glob_matches_go_into_destination_directory() {
+        let sources = ["logs/a.txt".to_string(), "logs/b.txt".to_string()];
+        let destinations: Vec<String> = plan_copies(&sources, "web:/tmp/logs/")
+            .into_iter()
+            .map(|item| item.destination)
+            .collect();
+        assert_eq!(destinations, ["web:/tmp/logs/a.txt", "web:/tmp/logs/b.txt"]);
+    }
+
+    #[test]
+    fn vm_paths_are_retargeted_per_instance() {
+        assert_eq!(on_instance(":/etc/hosts", "api-dev"), "api-dev:/etc/hosts");
+        assert_eq!(on_instance("/etc/hosts", "api-dev"), "api-dev:/etc/hosts");
+        assert_eq!(
+            on_instance("web:/etc/hosts", "api-dev"),
+            "api-dev:/etc/hosts"
+        );
+    }
+
+    #[test]
+    fn plain_paths_are_not_expanded() {
+        assert_eq!(
+            expand_host_source("README.md").unwrap(),
+            vec!["README.md".to_string()]
+        );
+        assert!(expand_host_source("/definitely/missing/*.nothing").is_err());
+    }
+}
@@ -4,9 +4,11 @@
 //! multi-instance providers, and service registration.
 
 use std::path::Path;
+use std::time::Instant;
 use tracing::{debug, info_span, warn};
 
 use crate::error::{VmError, VmResult};
+use crate::notifications;
 use vm_config::{config::MemoryLimit, config::VmConfig, validator::ConfigValidator, GlobalConfig};
 use vm_core::msg;
 use vm_core::{get_cpu_core_count, get_total_memory_gb, vm_error, vm_println};
@@ -100,10 +102,12 @@ pub async fn handle_create(
     from_dockerfile: Option<std::path::PathBuf>,
     preserve_services: bool,
     refresh_packages: bool,
+    ephemeral: bool,
 ) -> VmResult<()> {
     let span = info_span!("vm_operation", operation = "create");
     let _enter = span.enter();
     debug!("Starting VM creation");
+    let started = Instant::now();
 
     // Note: Config modifications for --from-dockerfile and --save-as are now handled
     // in commands/mod.rs before provider creation to avoid container name conflicts
@@ -116,6 +120,16 @@ pub async fn handle_create(
         force = true;
     }
 
+    if ephemeral && !matches!(provider.name(), "docker" | "podman") {
+        return Err(VmError::validation(
+            format!(
+                "--ephemeral is only supported by the docker and podman providers, not {}",
+                provider.name()
+            ),
+            None::<String>,
+        ));
+    }
+
     if force && !is_snapshot_build {
         // Regular force mode: use minimal resources and skip validation
         vm_println!("⚡ Force mode: using minimal resources and skipping validation");
@@ -269,12 +283,14 @@ pub async fn handle_create(
     }
 
     // Create provider context with verbose flag and global config
-    // Skip provisioning for snapshot builds (Dockerfile already has everything)
+    // Skip provisioning for snapshot builds (Dockerfile already has everything) and
+    // for ephemeral VMs, whose root filesystem is read-only
     let mut context = ProviderContext::with_verbose(verbose)
         .with_config(global_config.clone())
         .preserve_services(preserve_services)
-        .refresh_packages(refresh_packages);
-    if save_as.is_some() {
+        .refresh_packages(refresh_packages)
+        .ephemeral(ephemeral);
+    if save_as.is_some() || ephemeral {
         context = context.skip_provisioning();
     }
 
@@ -292,6 +308,15 @@ pub async fn handle_create(
     match create_result {
         Ok(()) => {
             vm_println!("{}", MESSAGES.vm.create_success);
+            if ephemeral {
+                vm_println!("🔒 Ephemeral VM: the root filesystem and /workspace are read-only, scratch space is in memory and nothing is kept on the host. Remove it with 'vm destroy'.");
+            }
+            notifications::notify_finished(
+                &global_config,
+                started,
+                "VM ready",
+                &format!("{vm_name} finished provisioning"),
+            );
 
             let container_name = if let Some(instance_name) = &instance {
                 format!("{vm_name}-{instance_name}")
@@ -505,6 +530,12 @@ pub async fn handle_create(
             Ok(())
         }
         Err(e) => {
+            notifications::notify_finished(
+                &global_config,
+                started,
+                "VM creation failed",
+                &format!("{vm_name}: {e}"),
+            );
             vm_println!(
                 "{}",
                 msg!(
@@ -517,20 +548,8 @@ pub async fn handle_create(
         }
     }?;
 
-    // Seed database if configured
-    if let Some(service_config) = config.services.get("postgresql") {
-        if let Some(seed_file) = &service_config.seed_file {
-            let default_db_name = format!("{}_dev", vm_name.replace('-', "_"));
-            let db_name = service_config
-                .database
-                .as_deref()
-                .unwrap_or(&default_db_name);
-            vm_println!("🌱 Seeding database '{}' from {:?}...", db_name, seed_file);
-            if let Err(e) = crate::commands::db::backup::import_db(db_name, seed_file).await {
-                vm_println!("Database seeding failed: {}", e);
-            }
-        }
-    }
+    // Seed databases if configured
+    crate::commands::db::seed::seed_after_create(&config).await;
 
     Ok(())
 }
@@ -17,22 +17,26 @@ use vm_core::{vm_error, vm_println};
 use vm_messages::messages::MESSAGES;
 use vm_provider::{get_provider, InstanceInfo, Provider, ProviderContext};
 
-use super::helpers::unregister_vm_services_helper;
+use super::helpers::{forget_project_images, unregister_vm_services_helper};
 use super::targets::{get_all_instances, get_instances_from_provider, match_pattern};
 
 /// Helper function to backup database services configured with backup_on_destroy
 async fn backup_databases(config: &VmConfig, vm_name: &str, global_config: &GlobalConfig) {
     use crate::commands::db::backup::backup_db;
+    use crate::commands::db::engine::DbEngine;
 
     for (service_name, service_config) in &config.services {
         if service_config.backup_on_destroy != Some(true) {
             continue;
         }
+        let Some(engine) = DbEngine::from_service_name(service_name) else {
+            continue;
+        };
 
         let db_name = format!("{}_{}", vm_name.replace('-', "_"), service_name);
         vm_println!("📦 Creating backup for database: {}", db_name);
 
-        if let Err(e) = backup_db(&db_name, None, global_config.backups.keep_count).await {
+        if let Err(e) = backup_db(engine, &db_name, None, global_config.backups.keep_count).await {
             vm_println!("⚠️  Warning: Failed to backup {}: {}", db_name, e);
         } else {
             vm_println!("✓ Backup created for {}", db_name);
@@ -95,7 +99,8 @@ pub async fn handle_destroy(
                 .output();
         }
 
-        unregister_vm_services_helper(&target_container, &global_config).await?;
+        unregister_vm_services_helper(&target_container, vm_name, &global_config).await?;
+        forget_project_images(&target_container);
 
         vm_println!("{}", MESSAGES.common.cleanup_complete);
         return Ok(());
@@ -196,6 +201,8 @@ pub async fn handle_destroy(
         // Build context with preserve_services flag
         let context = ProviderContext::default().preserve_services(preserve_services);
 
+        // Sample while the container is still running, or its last stretch is lost
+        crate::usage::sample_quietly(crate::services::container_runtime(&global_config)).await;
         match provider.destroy_with_context(container, &context) {
             Ok(()) => {
                 // Backup database services if configured (run in background)
@@ -215,7 +222,8 @@ pub async fn handle_destroy(
                 }
 
                 vm_println!("{}", MESSAGES.common.configuring_services);
-                unregister_vm_services_helper(&target_container, &global_config).await?;
+                unregister_vm_services_helper(&target_container, vm_name, &global_config).await?;
+                forget_project_images(&target_container);
 
                 vm_println!("{}", MESSAGES.vm.destroy_success);
                 Ok(())
@@ -252,6 +260,7 @@ fn provider_display_name(provider: &dyn Provider) -> &'static str {
         "docker" => "Docker",
         "podman" => "Podman",
         "tart" => "Tart",
+        "libvirt" => "libvirt",
         _ => "Provider",
     }
 }
@@ -259,7 +268,7 @@ fn provider_display_name(provider: &dyn Provider) -> &'static str {
 fn provider_resource_label(provider: &dyn Provider) -> &'static str {
     match provider.name() {
         "docker" | "podman" => "Container",
-        "tart" => "VM",
+        "tart" | "libvirt" => "VM",
         _ => "Resource",
     }
 }
@@ -268,6 +277,7 @@ fn provider_destroyed_items(provider: &dyn Provider) -> &'static str {
     match provider.name() {
         "docker" | "podman" => "  • Container and all data\n  • Docker image and build cache",
         "tart" => "  • Tart VM and all data",
+        "libvirt" => "  • libvirt domain, disk overlay and cloud-init seed",
         _ => "  • Provider resource and all data",
     }
 }
@@ -310,7 +320,7 @@ pub async fn handle_destroy_enhanced(
 }
 
 fn is_provider_name(value: &str) -> bool {
-    matches!(value, "docker" | "podman" | "tart")
+    matches!(value, "docker" | "podman" | "tart" | "libvirt")
 }
 
 fn provider_for_name(provider_name: &str, config: &VmConfig) -> VmResult<Box<dyn Provider>> {
@@ -7,11 +7,13 @@ use tracing::{debug, warn};
 
 use crate::error::VmResult;
 use crate::service_manager::get_service_manager;
+use crate::services::container_runtime;
 use vm_config::{config::VmConfig, GlobalConfig};
 use vm_core::msg;
 use vm_core::vm_println;
+use vm_docker_registry::Pins;
 use vm_messages::messages::MESSAGES;
-use vm_provider::Provider;
+use vm_provider::{BoxConfig, Provider};
 
 /// Handle get sync directory
 pub fn handle_get_sync_directory(provider: Box<dyn Provider>) {
@@ -27,6 +29,10 @@ pub(super) async fn register_vm_services_helper(
     vm_config: &VmConfig,
     global_config: &GlobalConfig,
 ) -> VmResult<()> {
+    if global_config.services.docker_registry.enabled {
+        track_project_images(vm_name, vm_config, global_config);
+    }
+
     let service_manager = match get_service_manager() {
         Ok(sm) => sm,
         Err(e) => {
@@ -54,9 +60,103 @@ pub(super) async fn register_vm_services_helper(
     Ok(())
 }
 
-/// Helper function to unregister VM services
+/// Protect the images a VM was built from in the Docker registry cache
+fn track_project_images(vm_name: &str, vm_config: &VmConfig, global_config: &GlobalConfig) {
+    let images = project_images(vm_config, global_config);
+    let result = Pins::load().and_then(|mut pins| {
+        pins.track_project(vm_name, &images);
+        pins.save()
+    });
+    if let Err(e) = result {
+        warn!("Failed to pin images for {}: {}", vm_name, e);
+    }
+}
+
+/// Stop protecting a destroyed VM's images in the Docker registry cache
+pub(super) fn forget_project_images(vm_name: &str) {
+    let result = Pins::load().and_then(|mut pins| {
+        if pins.forget_project(vm_name) {
+            pins.save()?;
+        }
+        Ok(())
+    });
+    if let Err(e) = result {
+        warn!("Failed to unpin images for {}: {}", vm_name, e);
+    }
+}
+
+/// Base and service images a VM pulls
+fn project_images(vm_config: &VmConfig, global_config: &GlobalConfig) -> Vec<String> {
+    let mut images = Vec::new();
+    let base_dir = std::env::current_dir().unwrap_or_default();
+    match vm_config
+        .vm
+        .as_ref()
+        .and_then(|vm| vm.get_box_spec())
+        .map(|spec| BoxConfig::parse_for_docker(&spec, &base_dir))
+    {
+        None => images.push("ubuntu:24.04".to_string()),
+        Some(Ok(BoxConfig::DockerImage(image))) => images.push(image),
+        Some(Ok(BoxConfig::Dockerfile { path, .. })) => {
+            if let Ok(content) = std::fs::read_to_string(&path) {
+                images.extend(dockerfile_base_images(&content));
+            }
+        }
+        Some(_) => {}
+    }
+
+    let enabled = |name: &str, global: bool| {
+        global || vm_config.services.get(name).is_some_and(|s| s.enabled)
+    };
+    let services = &global_config.services;
+    if enabled("postgresql", services.postgresql.enabled) {
+        images.push(format!("postgres:{}", services.postgresql.version));
+    }
+    if enabled("redis", services.redis.enabled) {
+        images.push(format!("redis:{}", services.redis.version));
+    }
+    if enabled("mongodb", services.mongodb.enabled) {
+        images.push(format!("mongo:{}", services.mongodb.version));
+    }
+    if enabled("mysql", services.mysql.enabled) {
+        images.push(format!("mysql:{}", services.mysql.version));
+    }
+    images
+}
+
+/// Images named in a Dockerfile's `FROM` lines, skipping earlier build stages
+fn dockerfile_base_images(content: &str) -> Vec<String> {
+    let mut stages = Vec::new();
+    let mut images = Vec::new();
+    for line in content.lines() {
+        let mut words = line.split_whitespace();
+        if !words
+            .next()
+            .is_some_and(|word| word.eq_ignore_ascii_case("FROM"))
+        {
+            continue;
+        }
+        let words: Vec<&str> = words.filter(|word| !word.starts_with("--")).collect();
+        let Some(image) = words.first() else {
+            continue;
+        };
+        if *image != "scratch" && !image.contains('$') && !stages.contains(image) {
+            images.push(image.to_string());
+        }
+        if let [_, alias, stage] = words.as_slice() {
+            if alias.eq_ignore_ascii_case("AS") {
+                stages.push(*stage);
+            }
+        }
+    }
+    images
+}
+
+/// Helper function to unregister VM services, archiving their logs under
+/// `project` first when `logs.archive` is enabled
 pub(super) async fn unregister_vm_services_helper(
     vm_name: &str,
+    project: &str,
     global_config: &GlobalConfig,
 ) -> VmResult<()> {
     let service_manager = match get_service_manager() {
@@ -67,6 +167,22 @@ pub(super) async fn unregister_vm_services_helper(
         }
     };
 
+    if global_config.logs.archive {
+        let services: Vec<String> = service_manager
+            .get_all_service_statuses()
+            .into_iter()
+            .filter(|(_, state)| state.registered_vms.iter().any(|vm| vm == vm_name))
+            .map(|(name, _)| name)
+            .collect();
+        crate::service_logs::archive_quietly(
+            container_runtime(global_config),
+            project,
+            &services,
+            &global_config.logs,
+        )
+        .await;
+    }
+
     if let Err(e) = service_manager
         .unregister_vm_services(vm_name, global_config)
         .await
@@ -144,3 +260,24 @@ pub(super) fn print_vm_runtime_details(config: &VmConfig, include_ports: bool) {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dockerfile_base_images() {
+        let dockerfile = "\
+FROM --platform=linux/amd64 node:20 AS build
+RUN npm ci
+from build as assets
+FROM scratch
+FROM nginx:1.27
+COPY --from=build /app /usr/share/nginx/html
+";
+        assert_eq!(
+            dockerfile_base_images(dockerfile),
+            ["node:20", "nginx:1.27"]
+        );
+    }
+}
@@ -18,7 +18,7 @@ use vm_config::{
 use vm_core::msg;
 use vm_core::vm_println;
 use vm_messages::messages::MESSAGES;
-use vm_provider::{Provider, ProviderContext};
+use vm_provider::{LogsRequest, Provider, ProviderContext};
 
 /// Compares detected worktrees with current container mounts.
 ///
@@ -58,6 +58,17 @@ fn describe_create_target(
                 details.push(("Base", base));
             }
         }
+        "libvirt" => {
+            details.push(("Guest OS", "Linux VM (KVM)".to_string()));
+            if let Some(base) = config
+                .vm
+                .as_ref()
+                .and_then(|vm| vm.r#box.as_ref())
+                .map(box_name)
+            {
+                details.push(("Base", base));
+            }
+        }
         "docker" | "podman" => {
             details.push(("Guest OS", "Ubuntu/Linux container".to_string()));
             if let Some(base) = config
@@ -637,139 +648,90 @@ pub fn handle_exec(
     result.map_err(VmError::from)
 }
 
-/// Handle VM logs viewing
-pub fn handle_logs(
+/// Run a local script (or one piped on stdin) inside the VM
+///
+/// The VM's exit code is passed through, so `vm exec --file` can gate CI steps.
+pub fn handle_exec_script(
     provider: Box<dyn Provider>,
     container: Option<&str>,
+    file: Option<&Path>,
+    args: Vec<String>,
     config: VmConfig,
-    follow: bool,
-    tail: usize,
-    service: Option<&str>,
 ) -> VmResult<()> {
+    let (script, source) = match file {
+        Some(path) => {
+            let script = std::fs::read(path)
+                .map_err(|e| VmError::filesystem(e, path.display().to_string(), "read script"))?;
+            (script, path.display().to_string())
+        }
+        None => {
+            if io::stdin().is_terminal() {
+                return Err(VmError::validation(
+                    "--stdin expects a script to be piped in, e.g. `vm exec --stdin < setup.sh`",
+                    Some("stdin"),
+                ));
+            }
+            let mut script = Vec::new();
+            io::Read::read_to_end(&mut io::stdin(), &mut script)?;
+            (script, "stdin".to_string())
+        }
+    };
     debug!(
-        "Viewing VM logs: provider='{}', follow={}, tail={}, service={:?}",
-        provider.name(),
-        follow,
-        tail,
-        service
+        "Running script in VM: source={}, args={:?}, provider='{}'",
+        source,
+        args,
+        provider.name()
     );
 
-    // Use extended logs method (falls back to basic logs for non-Docker providers)
-    let result = provider.logs_extended(container, follow, tail, service, &config);
+    let vm_name = config
+        .project
+        .as_ref()
+        .and_then(|p| p.name.as_ref())
+        .map(|s| s.as_str())
+        .unwrap_or("vm-project");
+    let cmd_display = std::iter::once(source)
+        .chain(args.iter().cloned())
+        .collect::<Vec<_>>()
+        .join(" ");
+    vm_println!(
+        "{}",
+        msg!(
+            MESSAGES.vm.exec_header,
+            name = vm_name,
+            command = &cmd_display
+        )
+    );
 
-    result.map_err(VmError::from)
+    let code = provider.exec_script(container, &script, &args)?;
+    vm_println!("{}", MESSAGES.vm.exec_separator);
+    if code == 0 {
+        vm_println!("{}", MESSAGES.vm.exec_success);
+        Ok(())
+    } else {
+        Err(VmError::Exit { code })
+    }
 }
 
-/// Handle file copy to/from VM
-pub fn handle_copy(
+/// Handle VM logs viewing
+pub fn handle_logs(
     provider: Box<dyn Provider>,
-    source: &str,
-    destination: &str,
-    all_vms: bool,
+    container: Option<&str>,
     config: VmConfig,
+    request: &LogsRequest,
 ) -> VmResult<()> {
-    if all_vms {
-        // Get all instances from the provider
-        let instances = provider.list_instances().map_err(VmError::from)?;
-
-        if instances.is_empty() {
-            vm_println!("No instances found");
-            return Ok(());
-        }
-
-        // Determine direction and prepare the path template
-        let (direction, is_to_vms) = if source.contains(':') {
-            ("from", false)
-        } else {
-            ("to", true)
-        };
-
-        vm_println!(
-            "📦 Copying files {} {} instances...",
-            direction,
-            instances.len()
-        );
-
-        let mut success_count = 0;
-        let mut failed = vec![];
-
-        for instance in &instances {
-            let (actual_source, actual_dest) = if is_to_vms {
-                // Copy TO all VMs: source is local, destination has container prefix
-                let dest_path = destination.strip_prefix(':').unwrap_or(destination);
-                (
-                    source.to_string(),
-                    format!("{}:{}", instance.name, dest_path),
-                )
-            } else {
-                // Copy FROM all VMs: source has container prefix, destination is local
-                let src_path = source.strip_prefix(':').unwrap_or(source);
-                (
-                    format!("{}:{}", instance.name, src_path),
-                    destination.to_string(),
-                )
-            };
-
-            match provider.copy(&actual_source, &actual_dest, Some(&instance.name)) {
-                Ok(()) => {
-                    vm_println!("  ✓ {} '{}'", direction, instance.name);
-                    success_count += 1;
-                }
-                Err(e) => {
-                    vm_println!("  ❌ '{}': {}", instance.name, e);
-                    failed.push(instance.name.clone());
-                }
-            }
-        }
-
-        vm_println!(
-            "\n📊 {} of {} copies successful",
-            success_count,
-            instances.len()
-        );
-
-        if !failed.is_empty() {
-            return Err(VmError::general(
-                std::io::Error::new(std::io::ErrorKind::Other, "Some copies failed"),
-                format!("Failed for instances: {}", failed.join(", ")),
-            ));
-        }
-
-        Ok(())
-    } else {
-        // Original single-VM copy logic
-        debug!(
-            "Copying files: source='{}', destination='{}', provider='{}'",
-            source,
-            destination,
-            provider.name()
-        );
-
-        let vm_name = config
-            .project
-            .as_ref()
-            .and_then(|p| p.name.as_ref())
-            .map(|s| s.as_str())
-            .unwrap_or("vm-project");
-
-        // Determine direction for user feedback
-        let direction = if source.contains(':') { "from" } else { "to" };
-
-        vm_println!("📦 Copying file {} VM '{}'...", direction, vm_name);
-
-        let result = provider.copy(source, destination, None);
+    debug!(
+        "Viewing VM logs: provider='{}', follow={}, tail={}, service={:?}, all_services={}",
+        provider.name(),
+        request.follow,
+        request.tail,
+        request.service,
+        request.all_services
+    );
 
-        match &result {
-            Ok(()) => {
-                vm_println!("✓ File copied successfully");
-            }
-            Err(e) => {
-                vm_println!("❌ Copy failed: {}", e);
-            }
-        }
+    // Use extended logs method (falls back to basic logs for non-Docker providers)
+    let result = provider.logs_extended(container, request, &config);
 
-        result.map_err(VmError::from)
-    }
+    result.map_err(VmError::from)
 }
 
 #[cfg(test)]
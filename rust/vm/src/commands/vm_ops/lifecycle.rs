@@ -6,6 +6,7 @@
 use tracing::{debug, info_span, warn};
 
 use crate::error::{VmError, VmResult};
+use crate::services::container_runtime;
 use vm_config::{config::VmConfig, GlobalConfig};
 use vm_core::msg;
 use vm_core::vm_println;
@@ -23,6 +24,7 @@ pub async fn handle_start(
     config: VmConfig,
     global_config: GlobalConfig,
     no_wait: bool,
+    env_overrides: Vec<(String, String)>,
 ) -> VmResult<()> {
     let span = info_span!("vm_operation", operation = "start");
     let _enter = span.enter();
@@ -55,7 +57,9 @@ pub async fn handle_start(
 
     vm_println!("{}", msg!(MESSAGES.vm.start_header, name = vm_name));
 
-    let context = ProviderContext::with_verbose(false).with_config(global_config.clone());
+    let context = ProviderContext::with_verbose(false)
+        .with_config(global_config.clone())
+        .with_env_overrides(env_overrides);
     match provider.start_with_context(container, &context) {
         Ok(()) => {
             if provider.name() == "tart" && !no_wait {
@@ -183,13 +187,16 @@ pub async fn handle_stop(
 
             vm_println!("{}", msg!(MESSAGES.vm.stop_header, name = vm_name));
 
+            // Sample while the container is still running, or its last stretch is lost
+            crate::usage::sample_quietly(container_runtime(&global_config)).await;
             match provider.stop(None) {
                 Ok(()) => {
                     // Unregister VM services after successful stop
                     let vm_instance_name = format!("{vm_name}-dev");
 
                     vm_println!("{}", MESSAGES.vm.stop_success);
-                    unregister_vm_services_helper(&vm_instance_name, &global_config).await?;
+                    unregister_vm_services_helper(&vm_instance_name, vm_name, &global_config)
+                        .await?;
 
                     vm_println!("{}", MESSAGES.vm.stop_restart_hint);
                     Ok(())
@@ -218,11 +225,17 @@ pub async fn handle_stop(
                 msg!(MESSAGES.vm.stop_force_header, name = container_name)
             );
 
+            crate::usage::sample_quietly(container_runtime(&global_config)).await;
             match provider.kill(Some(container_name)) {
                 Ok(()) => {
                     // For force kill, still unregister services for cleanup
                     vm_println!("{}", MESSAGES.vm.stop_force_success);
-                    unregister_vm_services_helper(container_name, &global_config).await?;
+                    let project = config
+                        .project
+                        .as_ref()
+                        .and_then(|p| p.name.as_deref())
+                        .unwrap_or(container_name);
+                    unregister_vm_services_helper(container_name, project, &global_config).await?;
                     Ok(())
                 }
                 Err(e) => {
@@ -6,6 +6,7 @@
 use tracing::{debug, info_span};
 
 use crate::commands::vm_ops::targets::{get_all_instances, get_instances_from_provider};
+use crate::crash_watch::CrashHistory;
 use crate::error::VmResult;
 use vm_core::msg;
 use vm_core::vm_println;
@@ -53,13 +54,23 @@ pub fn render_instance_table(instances: Vec<InstanceInfo>) {
     // Sort instances by provider then name for consistent output
     let mut sorted_instances = instances;
     sorted_instances.sort_by(|a, b| a.provider.cmp(&b.provider).then(a.name.cmp(&b.name)));
+    let crashes = CrashHistory::load().unwrap_or_default();
 
     for instance in sorted_instances {
+        let crash_looping = crashes
+            .containers
+            .get(&instance.name)
+            .is_some_and(|record| record.is_crash_loop());
+        let status = if crash_looping {
+            "💥 Crashing".to_string()
+        } else {
+            format_status(&instance.status)
+        };
         vm_println!(
             "{:<20} {:<10} {:<12} {:<20} {:<10} {:<15}",
             truncate_string(&instance.name, 20),
             instance.provider,
-            format_status(&instance.status),
+            status,
             truncate_string(&instance.id, 20),
             format_uptime(&instance.uptime),
             instance.project.as_deref().unwrap_or("--")
@@ -85,6 +96,8 @@ fn format_status(status: &str) -> String {
         || lower_status.contains("poweroff")
     {
         "🔴 Stopped".to_string()
+    } else if lower_status.contains("restarting") {
+        "🔁 Restarting".to_string()
     } else if lower_status.contains("paused") {
         "⏸️  Paused".to_string()
     } else {
@@ -4,9 +4,12 @@
 //! - Creation and destruction
 //! - Lifecycle management (start, stop)
 //! - Interaction (SSH, exec, logs)
+//! - File copy to and from VMs
 //! - Status and listing
 
 // Module declarations
+mod adopt;
+mod copy;
 mod create;
 mod destroy;
 mod fleet;
@@ -19,9 +22,11 @@ mod targets;
 mod wait;
 
 // Re-export all public handlers for external use
+pub use adopt::handle_adopt;
+pub use copy::handle_copy;
 pub use create::handle_create;
 pub use helpers::handle_get_sync_directory;
-pub use interaction::{handle_copy, handle_exec, handle_logs, handle_ssh};
+pub use interaction::{handle_exec, handle_exec_script, handle_logs, handle_ssh};
 pub use lifecycle::{handle_start, handle_stop};
 pub use status::handle_status;
 pub use wait::handle_wait;
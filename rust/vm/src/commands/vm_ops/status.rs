@@ -6,6 +6,7 @@
 use tracing::debug;
 
 use super::list::handle_list_enhanced;
+use crate::crash_watch::{CrashHistory, CrashRecord};
 use crate::error::VmResult;
 use vm_config::{config::VmConfig, GlobalConfig};
 use vm_core::vm_println;
@@ -40,6 +41,15 @@ pub fn handle_status(
     match provider.get_status_report(container) {
         Ok(report) => {
             display_status_dashboard(&report);
+            if report.is_running {
+                display_clock_skew(provider.as_ref(), container);
+            }
+            if let Some(record) = CrashHistory::load()
+                .ok()
+                .and_then(|history| history.containers.get(&report.name).cloned())
+            {
+                display_crash_record(&record);
+            }
             Ok(())
         }
         Err(e) => {
@@ -100,6 +110,44 @@ fn display_status_dashboard(report: &VmStatusReport) {
     }
 }
 
+/// Warn when the VM clock has drifted from the host's
+fn display_clock_skew(provider: &dyn Provider, container: Option<&str>) {
+    match provider.clock_skew(container) {
+        Ok(Some(skew)) if skew.is_significant() => {
+            vm_println!("\n⏰ Clock is {}", skew.describe());
+            vm_println!("   TLS and token validation inside the VM may fail");
+            vm_println!("💡 Resync: vm doctor --fix");
+        }
+        Ok(_) => {}
+        Err(e) => debug!("Could not measure clock skew: {}", e),
+    }
+}
+
+/// Display recent crashes recorded by the restart watchdog
+fn display_crash_record(record: &CrashRecord) {
+    vm_println!("");
+    if record.is_crash_loop() {
+        vm_println!(
+            "💥 Crash loop: restarted {} times, last exit code {}",
+            record.restarts,
+            record.last_exit_code
+        );
+    } else {
+        vm_println!(
+            "⚠️  Recovered from a crash (exit code {}), restarted {} time{}",
+            record.last_exit_code,
+            record.restarts,
+            if record.restarts == 1 { "" } else { "s" }
+        );
+    }
+    if !record.log_tail.is_empty() {
+        vm_println!("   Last log lines:");
+        for line in &record.log_tail {
+            vm_println!("   │ {}", line);
+        }
+    }
+}
+
 /// Display basic stopped status for providers without enhanced status support
 fn display_basic_stopped_status(vm_name: &str, provider_name: &str) {
     vm_println!("🖥️  {} ({})", vm_name, provider_name);
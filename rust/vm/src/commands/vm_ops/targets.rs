@@ -56,7 +56,7 @@ pub fn get_all_instances() -> VmResult<Vec<InstanceInfo>> {
 
     let mut all_instances = Vec::new();
     let mut provider_errors = Vec::new();
-    let providers = ["docker", "podman", "tart"];
+    let providers = ["docker", "podman", "tart", "libvirt"];
 
     for provider_name in providers {
         let config = VmConfig {
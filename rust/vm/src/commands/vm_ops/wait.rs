@@ -3,11 +3,12 @@
 //! This module provides functionality to wait for services to become ready
 //! before proceeding with other operations.
 
-use std::thread::sleep;
 use std::time::{Duration, Instant};
+use tokio::time::sleep;
 use tracing::debug;
 
 use crate::error::{VmError, VmResult};
+use crate::service_manager::get_service_manager;
 use vm_config::{config::VmConfig, GlobalConfig};
 use vm_core::vm_println;
 use vm_provider::Provider;
@@ -15,14 +16,15 @@ use vm_provider::Provider;
 /// Handle service wait command
 ///
 /// Polls service health status until all (or specified) services are ready,
-/// or until the timeout is reached.
-pub fn handle_wait(
+/// or until the timeout is reached. Readiness gates declared with
+/// `services.<name>.depends_on` in vm.yaml must also pass.
+pub async fn handle_wait(
     provider: Box<dyn Provider>,
     container: Option<&str>,
     service: Option<&str>,
     timeout: u64,
     config: VmConfig,
-    _global_config: GlobalConfig,
+    global_config: GlobalConfig,
 ) -> VmResult<()> {
     // Get VM name from config
     let vm_name = config
@@ -126,7 +128,10 @@ pub fn handle_wait(
                 // Check if all target services are ready
                 let all_ready = services_to_check.iter().all(|s| s.is_running);
 
-                if all_ready {
+                let pending_gates =
+                    pending_dependencies(&config, &global_config, service_filter.as_deref()).await;
+
+                if all_ready && pending_gates.is_empty() {
                     let elapsed = start.elapsed().as_secs();
                     vm_println!("✓ All services ready! ({}s)", elapsed);
                     for svc in services_to_check {
@@ -152,6 +157,9 @@ pub fn handle_wait(
                         vm_println!("  🔴 {} ({})", svc.name, status);
                     }
                 }
+                for gate in pending_gates {
+                    vm_println!("  🔴 {}", gate);
+                }
             }
             Err(e) => {
                 debug!("Failed to get status report: {}", e);
@@ -161,6 +169,42 @@ pub fn handle_wait(
         }
 
         // Wait before next poll
-        sleep(poll_interval);
+        sleep(poll_interval).await;
+    }
+}
+
+/// Describe the `depends_on` readiness gates that don't pass yet.
+///
+/// With a service filter, only gates of that service (or on it) are checked.
+async fn pending_dependencies(
+    config: &VmConfig,
+    global_config: &GlobalConfig,
+    service_filter: Option<&str>,
+) -> Vec<String> {
+    let Ok(service_manager) = get_service_manager() else {
+        return Vec::new();
+    };
+
+    let mut pending = Vec::new();
+    for (service_name, service) in &config.services {
+        if !service.enabled {
+            continue;
+        }
+        for (dependency, gate) in &service.depends_on {
+            let relevant = service_filter.map_or(true, |filter| {
+                filter == service_name || filter == dependency
+            });
+            if relevant
+                && !service_manager
+                    .check_readiness(dependency, gate, global_config)
+                    .await
+            {
+                pending.push(format!(
+                    "{} (needed by {}, waiting for {:?} check)",
+                    dependency, service_name, gate.condition
+                ));
+            }
+        }
     }
+    pending
 }
@@ -0,0 +1,378 @@
+//! Crash recovery for VMs with a restart policy
+//!
+//! `vm.restart_policy` is handed to the container engine, which restarts a
+//! crashed container while its daemon is up. Containers it gives up on (or
+//! that were down when the daemon restarted) are picked up here. The CLI has no
+//! long-running daemon, so like usage sampling this watchdog runs whenever the
+//! service manager registers a VM, and only looks at that VM's project.
+//! Restarts back off exponentially, and every crash is recorded in
+//! `~/.vm/crashes.json` with its exit code and log tail so `vm status` can
+//! report crash loops without touching any containers itself.
+
+use std::collections::BTreeMap;
+use std::process::Command;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+/// First retry waits this long; each further retry doubles it
+const BASE_BACKOFF_SECS: i64 = 10;
+/// Upper bound on the wait between retries
+const MAX_BACKOFF_SECS: i64 = 600;
+/// Restarts after which a container counts as crash looping
+const CRASH_LOOP_THRESHOLD: u32 = 3;
+/// A container running this long is considered recovered
+const STABLE_AFTER_SECS: i64 = 600;
+/// Log lines kept from the most recent crash
+const LOG_TAIL_LINES: usize = 20;
+
+/// Exit codes left by `docker stop` (SIGTERM, or SIGKILL after the timeout)
+const STOP_EXIT_CODES: [i64; 2] = [137, 143];
+
+/// Crash history of one container
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct CrashRecord {
+    /// Project the container belongs to (its `com.vm.project` label)
+    #[serde(default)]
+    pub project: String,
+    /// Restarts since the container last ran stably, by the engine or the watchdog
+    pub restarts: u32,
+    pub last_exit_code: i64,
+    /// RFC 3339 timestamp of the most recent crash
+    pub last_crash_at: String,
+    /// RFC 3339 timestamp of the watchdog's most recent restart attempt
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_restart_at: Option<String>,
+    #[serde(default)]
+    pub log_tail: Vec<String>,
+}
+
+impl CrashRecord {
+    pub fn is_crash_loop(&self) -> bool {
+        self.restarts >= CRASH_LOOP_THRESHOLD
+    }
+}
+
+/// Persisted crash records keyed by container name
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct CrashHistory {
+    #[serde(default)]
+    pub containers: BTreeMap<String, CrashRecord>,
+}
+
+impl CrashHistory {
+    /// Load the history, starting empty if it doesn't exist yet
+    pub fn load() -> Result<Self> {
+        let path = vm_core::user_paths::crash_history_path()?;
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(&path).context("Failed to read crash history")?;
+        serde_json::from_str(&content).context("Failed to parse crash history")
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = vm_core::user_paths::crash_history_path()?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).context("Failed to create state directory")?;
+        }
+        let json = serde_json::to_string_pretty(self).context("Failed to serialize crashes")?;
+        std::fs::write(&path, json).context("Failed to write crash history")
+    }
+
+    /// Fold a container's current state into its record and decide whether the
+    /// watchdog should restart it now
+    pub fn observe(&mut self, state: &ContainerState, now: DateTime<Utc>) -> bool {
+        match state.status.as_str() {
+            "running" => {
+                let stable = parse_time(&state.started_at).is_some_and(|started| {
+                    now - started >= ChronoDuration::seconds(STABLE_AFTER_SECS)
+                });
+                if stable {
+                    self.containers.remove(&state.name);
+                }
+                false
+            }
+            "restarting" => {
+                // The engine is already retrying; just keep the record current
+                let record = self.record(state);
+                record.restarts = record.restarts.max(state.restart_count);
+                record.last_exit_code = state.exit_code;
+                record.last_crash_at = now.to_rfc3339();
+                false
+            }
+            "exited" if state.crashed() => {
+                let record = self.record(state);
+                record.restarts = record.restarts.max(state.restart_count);
+                record.last_exit_code = state.exit_code;
+                if record.last_crash_at.is_empty() {
+                    record.last_crash_at = now.to_rfc3339();
+                }
+
+                let due = record
+                    .last_restart_at
+                    .as_deref()
+                    .and_then(parse_time)
+                    .map_or(true, |last| now - last >= backoff(record.restarts));
+                if due {
+                    record.restarts += 1;
+                    record.last_restart_at = Some(now.to_rfc3339());
+                }
+                due
+            }
+            _ => false,
+        }
+    }
+
+    fn record(&mut self, state: &ContainerState) -> &mut CrashRecord {
+        let record = self.containers.entry(state.name.clone()).or_default();
+        record.project.clone_from(&state.project);
+        record
+    }
+}
+
+/// Engine-reported state of a vm-managed container
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ContainerState {
+    pub name: String,
+    pub project: String,
+    pub status: String,
+    pub exit_code: i64,
+    pub oom_killed: bool,
+    pub restart_count: u32,
+    /// Restart policy the container was created with (`no`, `on-failure`, `always`, ...)
+    pub policy: String,
+    pub started_at: String,
+}
+
+impl ContainerState {
+    /// Whether the container exited on its own in a way its policy restarts
+    pub fn crashed(&self) -> bool {
+        let stopped_by_user = STOP_EXIT_CODES.contains(&self.exit_code) && !self.oom_killed;
+        match self.policy.as_str() {
+            "on-failure" => self.exit_code != 0 && !stopped_by_user,
+            "always" | "unless-stopped" => !stopped_by_user,
+            _ => false,
+        }
+    }
+
+    /// Parse a line printed with [`INSPECT_FORMAT`]
+    fn parse(line: &str) -> Option<Self> {
+        let fields: Vec<&str> = line.split('\t').collect();
+        let [name, project, status, exit_code, oom_killed, restart_count, policy, started_at] =
+            fields.as_slice()
+        else {
+            return None;
+        };
+        Some(Self {
+            name: name.trim_start_matches('/').to_string(),
+            project: project.to_string(),
+            status: status.to_string(),
+            exit_code: exit_code.parse().ok()?,
+            oom_killed: *oom_killed == "true",
+            restart_count: restart_count.parse().unwrap_or(0),
+            policy: policy.to_string(),
+            started_at: started_at.to_string(),
+        })
+    }
+}
+
+const INSPECT_FORMAT: &str = "{{.Name}}\t{{index .Config.Labels \"com.vm.project\"}}\t{{.State.Status}}\t{{.State.ExitCode}}\t{{.State.OOMKilled}}\t{{.RestartCount}}\t{{.HostConfig.RestartPolicy.Name}}\t{{.State.StartedAt}}";
+
+/// Delay before the next watchdog restart after `restarts` attempts
+fn backoff(restarts: u32) -> ChronoDuration {
+    let secs = BASE_BACKOFF_SECS.saturating_mul(1_i64 << restarts.saturating_sub(1).min(16));
+    ChronoDuration::seconds(secs.min(MAX_BACKOFF_SECS))
+}
+
+fn parse_time(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value)
+        .ok()
+        .map(|time| time.with_timezone(&Utc))
+}
+
+/// States of the vm-managed containers belonging to `project`
+fn container_states(executable: &str, project: &str) -> Result<Vec<ContainerState>> {
+    let output = Command::new(executable)
+        .args([
+            "ps",
+            "-a",
+            "--filter",
+            "label=com.vm.managed=true",
+            "--filter",
+        ])
+        .arg(format!("label={}={project}", vm_core::labels::PROJECT))
+        .args(["--format", "{{.Names}}"])
+        .output()
+        .context("Failed to list containers")?;
+    if !output.status.success() {
+        return Ok(Vec::new());
+    }
+    let names: Vec<String> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect();
+    if names.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let output = Command::new(executable)
+        .args(["inspect", "--format", INSPECT_FORMAT])
+        .args(&names)
+        .output()
+        .context("Failed to inspect containers")?;
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(ContainerState::parse)
+        .collect())
+}
+
+fn log_tail(executable: &str, container: &str) -> Vec<String> {
+    Command::new(executable)
+        .args(["logs", "--tail", &LOG_TAIL_LINES.to_string(), container])
+        .output()
+        .map(|output| {
+            // Container output may go to either stream
+            let mut lines: Vec<String> = String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .chain(String::from_utf8_lossy(&output.stderr).lines())
+                .map(str::to_string)
+                .collect();
+            let skip = lines.len().saturating_sub(LOG_TAIL_LINES);
+            lines.drain(..skip);
+            lines
+        })
+        .unwrap_or_default()
+}
+
+/// Check the containers of `project` and restart crashed ones that are due.
+///
+/// Records of other projects are left alone.
+pub fn watch(executable: &str, project: &str) -> Result<()> {
+    let states = container_states(executable, project)?;
+    let mut history = CrashHistory::load()?;
+    history.containers.retain(|name, record| {
+        record.project != project || states.iter().any(|state| &state.name == name)
+    });
+
+    let now = Utc::now();
+    for state in &states {
+        let restart = history.observe(state, now);
+        if matches!(state.status.as_str(), "exited" | "restarting") && state.crashed() {
+            if let Some(record) = history.containers.get_mut(&state.name) {
+                record.log_tail = log_tail(executable, &state.name);
+            }
+        }
+        if restart {
+            debug!(
+                "Restarting crashed container '{}' (exit code {})",
+                state.name, state.exit_code
+            );
+            let started = Command::new(executable)
+                .args(["start", &state.name])
+                .output()
+                .is_ok_and(|output| output.status.success());
+            if !started {
+                debug!("Failed to restart container '{}'", state.name);
+            }
+        }
+    }
+
+    history.save()
+}
+
+/// Best-effort watchdog pass; failures are only logged
+pub fn watch_quietly(executable: &str, project: &str) {
+    if let Err(e) = watch(executable, project) {
+        debug!("Crash watchdog failed: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn exited(exit_code: i64, policy: &str) -> ContainerState {
+        ContainerState {
+            name: "shop-dev".to_string(),
+            project: "shop".to_string(),
+            status: "exited".to_string(),
+            exit_code,
+            policy: policy.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_crashed_respects_policy_and_user_stops() {
+        assert!(exited(1, "on-failure").crashed());
+        assert!(!exited(0, "on-failure").crashed());
+        assert!(exited(0, "always").crashed());
+        assert!(!exited(1, "no").crashed());
+        // `vm stop` leaves 137/143 behind, which is not a crash...
+        assert!(!exited(143, "always").crashed());
+        // ...unless the kernel killed it for running out of memory
+        let oom = ContainerState {
+            oom_killed: true,
+            ..exited(137, "on-failure")
+        };
+        assert!(oom.crashed());
+    }
+
+    #[test]
+    fn test_restarts_back_off_and_flag_crash_loops() {
+        let start = Utc.with_ymd_and_hms(2026, 3, 10, 12, 0, 0).unwrap();
+        let mut history = CrashHistory::default();
+        let state = exited(2, "on-failure");
+
+        assert!(history.observe(&state, start));
+        // Within the first backoff window nothing happens
+        assert!(!history.observe(&state, start + ChronoDuration::seconds(5)));
+        assert!(history.observe(&state, start + ChronoDuration::seconds(30)));
+        // The second wait is twice as long
+        assert!(!history.observe(&state, start + ChronoDuration::seconds(45)));
+        assert!(history.observe(&state, start + ChronoDuration::seconds(80)));
+
+        let record = &history.containers["shop-dev"];
+        assert_eq!(record.project, "shop");
+        assert_eq!(record.restarts, 3);
+        assert_eq!(record.last_exit_code, 2);
+        assert!(record.is_crash_loop());
+    }
+
+    #[test]
+    fn test_stable_container_clears_record() {
+        let now = Utc.with_ymd_and_hms(2026, 3, 10, 12, 0, 0).unwrap();
+        let mut history = CrashHistory::default();
+        history.observe(&exited(1, "always"), now);
+
+        let mut running = exited(0, "always");
+        running.status = "running".to_string();
+        running.started_at = (now + ChronoDuration::seconds(60)).to_rfc3339();
+        history.observe(&running, now + ChronoDuration::seconds(120));
+        assert!(history.containers.contains_key("shop-dev"));
+
+        history.observe(
+            &running,
+            now + ChronoDuration::seconds(60 + STABLE_AFTER_SECS),
+        );
+        assert!(history.containers.is_empty());
+    }
+
+    #[test]
+    fn test_parse_inspect_line() {
+        let state = ContainerState::parse(
+            "/shop-dev\tshop\texited\t1\tfalse\t4\ton-failure\t2026-03-10T12:00:00.123456789Z",
+        )
+        .unwrap();
+        assert_eq!(state.name, "shop-dev");
+        assert_eq!(state.project, "shop");
+        assert_eq!(state.exit_code, 1);
+        assert_eq!(state.restart_count, 4);
+        assert_eq!(state.policy, "on-failure");
+    }
+}
@@ -97,6 +97,12 @@ pub enum VmError {
         field: Option<String>,
     },
 
+    /// A command run inside the VM finished with a non-zero exit code
+    Exit {
+        /// The exit code to pass on to the caller
+        code: i32,
+    },
+
     /// General application errors that don't fit other categories
     General {
         /// The underlying error
@@ -176,6 +182,7 @@ impl fmt::Display for VmError {
                 Some(field_name) => write!(f, "Validation error for '{field_name}': {message}"),
                 None => write!(f, "Validation error: {message}"),
             },
+            VmError::Exit { code } => write!(f, "Command exited with code {code}"),
             VmError::General { context, .. } => {
                 write!(f, "Error: {context}")
             }
@@ -195,7 +202,7 @@ impl std::error::Error for VmError {
             | VmError::FileSystem { source, .. }
             | VmError::Network { source, .. }
             | VmError::General { source, .. } => Some(source.as_ref()),
-            VmError::Validation { .. } => None,
+            VmError::Validation { .. } | VmError::Exit { .. } => None,
         }
     }
 }
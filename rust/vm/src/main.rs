@@ -20,15 +20,20 @@ use vm_logging::init_subscriber;
 // Local modules
 mod cli;
 mod commands;
+mod crash_watch;
 mod error;
+mod notifications;
+mod service_logs;
 mod service_manager;
 mod service_registry;
 mod services;
 mod state;
+mod usage;
 mod utils;
 
 use cli::Args;
 use commands::execute_command;
+use error::VmError;
 use service_manager::init_service_manager;
 
 /// Request ID for this execution - used for tracing logs across the entire request
@@ -40,9 +45,14 @@ fn get_request_id() -> &'static str {
 
 /// Executes the given command and handles top-level errors.
 async fn run_command(args: Args) {
-    if let Err(e) = execute_command(args).await {
-        vm_error!("{}", e);
-        std::process::exit(1);
+    match execute_command(args).await {
+        Ok(()) => {}
+        // The command's own output already explains the failure
+        Err(VmError::Exit { code }) => std::process::exit(code),
+        Err(e) => {
+            vm_error!("{}", e);
+            std::process::exit(1);
+        }
     }
 }
 
@@ -54,6 +64,24 @@ async fn main() {
         std::env::set_var("NO_COLOR", "1");
     }
 
+    // Offline mode and proxy settings from the global config are shared with
+    // every subsystem (including in-process services) through the environment
+    if let Some(config) = vm_core::user_paths::global_config_path()
+        .ok()
+        .filter(|path| path.exists())
+        .and_then(|path| vm_config::GlobalConfig::load_from_path(&path).ok())
+    {
+        if config.offline {
+            vm_core::offline::enable();
+        }
+        vm_core::network::apply(&vm_core::network::ProxySettings {
+            http: config.proxy.http,
+            https: config.proxy.https,
+            no_proxy: config.proxy.no_proxy,
+            ca_bundle: config.proxy.ca_bundle,
+        });
+    }
+
     let args = Args::parse();
     // The guard must be kept in scope for the lifetime of the application
     // to ensure that all buffered logs are flushed to the file.
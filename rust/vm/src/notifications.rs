@@ -0,0 +1,29 @@
+//! Desktop notifications for long-running operations
+//!
+//! Notifications are opt-in via `notifications.enabled` in the global config.
+//! Delivery is best-effort: a missing notifier never fails the command.
+
+use std::time::{Duration, Instant};
+use tracing::debug;
+use vm_config::GlobalConfig;
+
+/// Notify that an operation finished, if it ran longer than `min_duration_secs`.
+pub fn notify_finished(global_config: &GlobalConfig, started: Instant, title: &str, body: &str) {
+    let settings = &global_config.notifications;
+    if settings.enabled && started.elapsed() >= Duration::from_secs(settings.min_duration_secs) {
+        send(title, body);
+    }
+}
+
+/// Notify immediately, regardless of duration (e.g. a service went down).
+pub fn notify_now(global_config: &GlobalConfig, title: &str, body: &str) {
+    if global_config.notifications.enabled {
+        send(title, body);
+    }
+}
+
+fn send(title: &str, body: &str) {
+    if let Err(e) = vm_platform::platform::send_notification(title, body) {
+        debug!("Desktop notification failed: {e}");
+    }
+}
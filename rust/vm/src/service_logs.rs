@@ -0,0 +1,189 @@
+//! Archive of service container logs
+//!
+//! The postgres, redis, mongodb and mysql containers are shared by every VM and
+//! removed once the last VM stops using them, taking their logs along. With
+//! `logs.archive` enabled, the logs of the services a VM used are appended to
+//! `~/.vm/logs/<project>/<service>.log` whenever that VM stops or is destroyed,
+//! so diagnostic history survives `vm destroy`. Each archive continues from the
+//! last archived timestamp, and logs are rotated to `<service>.log.1`,
+//! `<service>.log.2`, ... once they reach `logs.max_size_mb`.
+
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, FixedOffset};
+use tracing::debug;
+use vm_config::global_config::LogArchiveSettings;
+
+use crate::services::service_container;
+
+/// Directory holding the archived logs of `project`
+pub fn project_logs_dir(settings: &LogArchiveSettings, project: &str) -> PathBuf {
+    PathBuf::from(shellexpand::tilde(&settings.path).as_ref()).join(project)
+}
+
+/// Append the logs of `services` to the project's archive.
+///
+/// Returns the number of lines archived.
+pub async fn archive(
+    executable: &str,
+    project: &str,
+    services: &[String],
+    settings: &LogArchiveSettings,
+) -> Result<usize> {
+    let dir = project_logs_dir(settings, project);
+    let mut archived = 0;
+    for service in services {
+        let Some(container) = service_container(service) else {
+            continue;
+        };
+        let path = dir.join(format!("{service}.log"));
+        archived += archive_container(executable, container, &path, settings).await?;
+    }
+    Ok(archived)
+}
+
+/// Best-effort archive used when a VM stops; failures are only logged
+pub async fn archive_quietly(
+    executable: &str,
+    project: &str,
+    services: &[String],
+    settings: &LogArchiveSettings,
+) {
+    match archive(executable, project, services, settings).await {
+        Ok(lines) => debug!("Archived {} service log lines for {}", lines, project),
+        Err(e) => debug!("Service log archiving failed: {}", e),
+    }
+}
+
+async fn archive_container(
+    executable: &str,
+    container: &str,
+    path: &Path,
+    settings: &LogArchiveSettings,
+) -> Result<usize> {
+    let since = last_timestamp(path);
+    let mut command = tokio::process::Command::new(executable);
+    command.args(["logs", "--timestamps"]);
+    if let Some(since) = &since {
+        command.args(["--since", &since.to_rfc3339()]);
+    }
+    let output = command
+        .arg(container)
+        .output()
+        .await
+        .with_context(|| format!("Failed to read logs of {container}"))?;
+    if !output.status.success() {
+        // The service isn't running
+        return Ok(0);
+    }
+
+    // Containers log to both streams; merge them back into one timeline
+    let mut logs = String::from_utf8_lossy(&output.stdout).into_owned();
+    logs.push_str(&String::from_utf8_lossy(&output.stderr));
+    let lines = lines_after(&logs, since);
+    if lines.is_empty() {
+        return Ok(0);
+    }
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).context("Failed to create log directory")?;
+    }
+    let current_size = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    if current_size > 0 && current_size >= settings.max_size_mb * 1024 * 1024 {
+        rotate(path, settings.keep_count)?;
+    }
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+    for line in &lines {
+        writeln!(file, "{line}")?;
+    }
+    Ok(lines.len())
+}
+
+fn parse_timestamp(line: &str) -> Option<DateTime<FixedOffset>> {
+    let token = line.split_whitespace().next()?;
+    DateTime::parse_from_rfc3339(token).ok()
+}
+
+/// Timestamp of the newest archived line
+fn last_timestamp(path: &Path) -> Option<DateTime<FixedOffset>> {
+    let content = std::fs::read_to_string(path).ok()?;
+    content.lines().rev().find_map(parse_timestamp)
+}
+
+/// Timestamped lines newer than `since`, oldest first
+fn lines_after(logs: &str, since: Option<DateTime<FixedOffset>>) -> Vec<&str> {
+    let mut lines: Vec<(DateTime<FixedOffset>, &str)> = logs
+        .lines()
+        .filter_map(|line| parse_timestamp(line).map(|timestamp| (timestamp, line)))
+        .filter(|(timestamp, _)| since.map_or(true, |since| *timestamp > since))
+        .collect();
+    lines.sort_by_key(|(timestamp, _)| *timestamp);
+    lines.into_iter().map(|(_, line)| line).collect()
+}
+
+/// Shift `log` to `log.1`, `log.1` to `log.2` and so on, dropping the oldest
+fn rotate(path: &Path, keep_count: u32) -> Result<()> {
+    let rotated = |n: u32| PathBuf::from(format!("{}.{n}", path.display()));
+    if keep_count == 0 {
+        return std::fs::remove_file(path).context("Failed to remove full log");
+    }
+    let _ = std::fs::remove_file(rotated(keep_count));
+    for n in (1..keep_count).rev() {
+        if rotated(n).exists() {
+            std::fs::rename(rotated(n), rotated(n + 1)).context("Failed to rotate log")?;
+        }
+    }
+    std::fs::rename(path, rotated(1)).context("Failed to rotate log")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_lines_after_merges_streams_and_skips_archived() {
+        let logs = "2026-01-01T10:00:02.5Z LOG: checkpoint complete\n\
+                    2026-01-01T10:00:01Z LOG: database system is ready\n\
+                    not a log line\n\
+                    2026-01-01T10:00:03Z ERROR: relation \"users\" does not exist\n";
+        assert_eq!(
+            lines_after(logs, None),
+            [
+                "2026-01-01T10:00:01Z LOG: database system is ready",
+                "2026-01-01T10:00:02.5Z LOG: checkpoint complete",
+                "2026-01-01T10:00:03Z ERROR: relation \"users\" does not exist",
+            ]
+        );
+        let since = DateTime::parse_from_rfc3339("2026-01-01T10:00:02.5Z").unwrap();
+        assert_eq!(lines_after(logs, Some(since)).len(), 1);
+    }
+
+    #[test]
+    fn test_rotate_keeps_newest_logs() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("redis.log");
+        for content in ["first", "second", "third"] {
+            std::fs::write(&path, content).unwrap();
+            rotate(&path, 2).unwrap();
+        }
+        assert!(!path.exists());
+        let read = |name: &str| std::fs::read_to_string(dir.path().join(name)).unwrap();
+        assert_eq!(read("redis.log.1"), "third");
+        assert_eq!(read("redis.log.2"), "second");
+        assert!(!dir.path().join("redis.log.3").exists());
+
+        std::fs::write(&path, "2026-01-01T10:00:00Z ready\n").unwrap();
+        assert_eq!(
+            last_timestamp(&path),
+            DateTime::parse_from_rfc3339("2026-01-01T10:00:00Z").ok()
+        );
+    }
+}
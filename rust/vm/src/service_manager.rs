@@ -12,13 +12,14 @@
 //!
 //! # State Persistence
 //!
-//! Service state is persisted to disk to survive CLI restarts and system reboots.
-//! This ensures reference counting remains accurate across sessions.
+//! Service state is persisted in the shared state database (`~/.vm/state.db`) to
+//! survive CLI restarts and system reboots. This ensures reference counting remains
+//! accurate across sessions.
 
 use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use anyhow::{Context, Result};
 use futures::future;
@@ -26,14 +27,20 @@ use serde::{Deserialize, Serialize};
 use tokio::time::sleep;
 use tracing::{debug, info, warn};
 
+use crate::commands::db::engine::DbEngine;
 use crate::error::VmError;
+use crate::notifications;
 use crate::services::{
-    auth_proxy::AuthProxyService, docker_registry::DockerRegistryService, mongodb::MongodbService,
-    mysql::MysqlService, package_registry::PackageRegistryService, postgresql::PostgresqlService,
-    redis::RedisService, ManagedService,
+    auth_proxy::AuthProxyService, container_runtime, docker_registry::DockerRegistryService,
+    mongodb::MongodbService, mysql::MysqlService, package_registry::PackageRegistryService,
+    postgresql::PostgresqlService, redis::RedisService, ManagedService,
+};
+use vm_config::{
+    config::{ReadinessCondition, ServiceDependency, VmConfig},
+    GlobalConfig,
 };
-use vm_config::{config::VmConfig, GlobalConfig};
 use vm_core::{vm_println, vm_success, vm_warning};
+use vm_state::StateStore;
 
 /// Represents the current state of a managed service
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -55,8 +62,8 @@ pub struct ServiceState {
 pub struct ServiceManager {
     /// Service state map protected by mutex for thread safety
     state: Arc<Mutex<HashMap<String, ServiceState>>>,
-    /// Path to persistent state file
-    state_file: PathBuf,
+    /// Path to the state database holding persisted service state
+    db_path: PathBuf,
     /// Shutdown handles for services that support graceful shutdown
     #[allow(dead_code)]
     shutdown_handles: Arc<Mutex<HashMap<String, tokio::sync::oneshot::Sender<()>>>>,
@@ -67,7 +74,7 @@ pub struct ServiceManager {
 impl ServiceManager {
     /// Create a new ServiceManager instance
     pub fn new() -> Result<Self> {
-        let state_file = vm_core::user_paths::services_state_path()?;
+        let db_path = vm_core::user_paths::state_db_path()?;
         let shutdown_handles = Arc::new(Mutex::new(HashMap::new()));
 
         // Initialize all service implementations
@@ -91,7 +98,7 @@ impl ServiceManager {
 
         let manager = Self {
             state: Arc::new(Mutex::new(HashMap::new())),
-            state_file,
+            db_path,
             shutdown_handles,
             services: Arc::new(Mutex::new(services)),
         };
@@ -205,9 +212,24 @@ impl ServiceManager {
             }
         }
 
-        // Start services that need starting
-        for service_name in services_needing_start {
-            if let Err(e) = self.start_service(&service_name, global_config).await {
+        // Start services in dependency order, gating each on its dependencies' readiness
+        let start_order = vm_config
+            .service_start_order(&services_to_start)
+            .unwrap_or_else(|e| {
+                vm_warning!("{}", e);
+                services_to_start.iter().map(|s| s.to_string()).collect()
+            });
+        for service_name in &start_order {
+            if let Err(e) = self
+                .wait_for_dependencies(service_name, vm_config, global_config)
+                .await
+            {
+                vm_warning!("{}", e);
+            }
+            if !services_needing_start.contains(service_name) {
+                continue;
+            }
+            if let Err(e) = self.start_service(service_name, global_config).await {
                 warn!("Failed to start service '{}': {}", service_name, e);
                 // Don't fail VM creation if service startup fails
                 vm_warning!("Service '{}' failed to start: {}", service_name, e);
@@ -215,6 +237,23 @@ impl ServiceManager {
         }
 
         self.save_state()?;
+
+        // No daemon runs between commands, so VM lifecycle events drive usage sampling
+        // and crash recovery
+        crate::usage::sample_quietly(container_runtime(global_config)).await;
+        if let Some(project) = vm_config.project.as_ref().and_then(|p| p.name.as_deref()) {
+            crate::crash_watch::watch_quietly(container_runtime(global_config), project);
+        }
+
+        // Services are up, so this is the point to run any overdue scheduled backups,
+        // then keep checking the schedule while this process runs
+        if let Err(e) =
+            crate::commands::db::schedule::run_due_backups(vm_config, global_config).await
+        {
+            warn!("Failed to run scheduled database backups: {}", e);
+        }
+        crate::commands::db::schedule::start_backup_timer(vm_name, vm_config, global_config);
+
         Ok(())
     }
 
@@ -225,6 +264,10 @@ impl ServiceManager {
         _global_config: &GlobalConfig,
     ) -> Result<()> {
         info!("Unregistering services for VM: {}", vm_name);
+        crate::commands::db::schedule::stop_backup_timer(vm_name);
+        // Usage was sampled before the VM was stopped; it is gone from `ps` by now.
+        // The crash watchdog doesn't run here either, as it would restart the
+        // containers that are being stopped.
 
         let mut services_to_stop = Vec::new();
 
@@ -352,6 +395,11 @@ impl ServiceManager {
             );
         }
 
+        notifications::notify_now(
+            global_config,
+            "Service failed",
+            &format!("Service '{service_name}' failed to start"),
+        );
         Err(anyhow::anyhow!(
             "Service '{service_name}' failed to start properly"
         ))
@@ -398,6 +446,72 @@ impl ServiceManager {
         Ok(())
     }
 
+    /// Wait until every dependency declared by `service_name` in vm.yaml is ready
+    pub async fn wait_for_dependencies(
+        &self,
+        service_name: &str,
+        vm_config: &VmConfig,
+        global_config: &GlobalConfig,
+    ) -> Result<()> {
+        let Some(service) = vm_config.services.get(service_name) else {
+            return Ok(());
+        };
+
+        for (dependency, gate) in &service.depends_on {
+            let started = Instant::now();
+            let mut announced = false;
+            while !self.check_readiness(dependency, gate, global_config).await {
+                if started.elapsed() >= gate.timeout() {
+                    return Err(anyhow::anyhow!(
+                        "Dependency '{dependency}' of '{service_name}' was not ready within {}s",
+                        gate.timeout().as_secs()
+                    ));
+                }
+                if !announced {
+                    vm_println!("⏳ Waiting for {} before {}...", dependency, service_name);
+                    announced = true;
+                }
+                sleep(Duration::from_secs(1)).await;
+            }
+            debug!("Dependency '{}' of '{}' is ready", dependency, service_name);
+        }
+        Ok(())
+    }
+
+    /// Evaluate a single readiness condition against a running service
+    pub async fn check_readiness(
+        &self,
+        service_name: &str,
+        gate: &ServiceDependency,
+        global_config: &GlobalConfig,
+    ) -> bool {
+        match gate.condition {
+            ReadinessCondition::Healthcheck => {
+                self.check_service_health(service_name, global_config).await
+            }
+            ReadinessCondition::Tcp => {
+                let port = gate
+                    .port
+                    .unwrap_or_else(|| self.get_service_port(service_name, global_config));
+                tokio::net::TcpStream::connect(("127.0.0.1", port))
+                    .await
+                    .is_ok()
+            }
+            ReadinessCondition::Sql => {
+                let Some(engine) = DbEngine::from_service_name(service_name) else {
+                    return false;
+                };
+                tokio::process::Command::new(container_runtime(global_config))
+                    .arg("exec")
+                    .arg(engine.container_name())
+                    .args(engine.query_command(gate.sql_query()))
+                    .output()
+                    .await
+                    .is_ok_and(|output| output.status.success())
+            }
+        }
+    }
+
     /// Get the port for a service from global configuration
     fn get_service_port(&self, service_name: &str, global_config: &GlobalConfig) -> u16 {
         self.services
@@ -425,7 +539,7 @@ impl ServiceManager {
         service_impl.check_health(global_config).await
     }
 
-    /// Save service state to disk
+    /// Save service state to the state database
     fn save_state(&self) -> Result<()> {
         let state_guard = self.state.lock().map_err(|e| {
             VmError::general(
@@ -433,31 +547,40 @@ impl ServiceManager {
                 "State mutex was poisoned",
             )
         })?;
-        let json = serde_json::to_string_pretty(&*state_guard)
-            .context("Failed to serialize service state")?;
 
-        if let Some(parent) = self.state_file.parent() {
-            std::fs::create_dir_all(parent).context("Failed to create service state directory")?;
-        }
-
-        std::fs::write(&self.state_file, json).context("Failed to write service state file")?;
+        let mut store = StateStore::open(&self.db_path)?;
+        store
+            .transaction(|tx| {
+                tx.clear(vm_state::SERVICES)?;
+                for (name, service_state) in state_guard.iter() {
+                    tx.put(vm_state::SERVICES, name, service_state)?;
+                }
+                Ok(())
+            })
+            .context("Failed to write service state")?;
 
-        debug!("Service state saved to {:?}", self.state_file);
+        debug!("Service state saved to {:?}", self.db_path);
         Ok(())
     }
 
-    /// Load service state from disk
+    /// Load service state from the state database, importing a legacy
+    /// `services.json` file the first time
     fn load_state(&self) -> Result<()> {
-        if !self.state_file.exists() {
-            debug!("No existing service state file found");
-            return Ok(());
-        }
-
-        let content = std::fs::read_to_string(&self.state_file)
-            .context("Failed to read service state file")?;
-
-        let loaded_state: HashMap<String, ServiceState> =
-            serde_json::from_str(&content).context("Failed to parse service state file")?;
+        let mut store = StateStore::open(&self.db_path)?;
+        store.import_legacy_file(
+            vm_state::SERVICES,
+            &vm_core::user_paths::services_state_path()?,
+            |content| {
+                let services: HashMap<String, serde_json::Value> = serde_json::from_str(content)?;
+                Ok(services.into_iter().collect())
+            },
+        )?;
+
+        let loaded_state: HashMap<String, ServiceState> = store
+            .list(vm_state::SERVICES)
+            .context("Failed to read service state")?
+            .into_iter()
+            .collect();
 
         {
             let mut state_guard = self.state.lock().map_err(|e| {
@@ -469,7 +592,7 @@ impl ServiceManager {
             *state_guard = loaded_state;
         }
 
-        info!("Service state loaded from {:?}", self.state_file);
+        info!("Service state loaded from {:?}", self.db_path);
         Ok(())
     }
 }
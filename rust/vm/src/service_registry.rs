@@ -85,7 +85,7 @@ impl ServiceRegistry {
                 name: "package_registry".to_string(),
                 display_name: "Package Registry".to_string(),
                 port: 3080,
-                health_endpoint: "/health".to_string(),
+                health_endpoint: "/readyz".to_string(),
                 description: "Private package registry for npm, pip, and cargo".to_string(),
                 supports_graceful_shutdown: true,
             },
@@ -104,7 +104,7 @@ impl ManagedService for AuthProxyService {
         let endpoint = format!("http://localhost:{port}/health");
 
         // Use reqwest to check health for HTTP-based services
-        match reqwest::get(&endpoint).await {
+        match vm_core::network::client().get(&endpoint).send().await {
             Ok(response) => response.status().is_success(),
             Err(_) => false,
         }
@@ -36,6 +36,8 @@ impl ManagedService for DockerRegistryService {
         // Create custom registry config with the specified port
         let config = RegistryConfig {
             registry_port: port,
+            offline: vm_core::offline::is_offline(),
+            max_download_kbps: global_config.services.docker_registry.max_download_kbps,
             ..Default::default()
         };
 
@@ -73,7 +75,7 @@ impl ManagedService for DockerRegistryService {
         let endpoint = format!("http://localhost:{port}/v2/");
 
         // Use reqwest to check health for HTTP-based services
-        match reqwest::get(&endpoint).await {
+        match vm_core::network::client().get(&endpoint).send().await {
             Ok(response) => response.status().is_success(),
             Err(_) => false,
         }
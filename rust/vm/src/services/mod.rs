@@ -41,6 +41,17 @@ pub async fn get_or_generate_password(service_name: &str) -> Result<String> {
         .map_err(|e| anyhow::anyhow!("Failed to get password: {}", e))
 }
 
+/// Shared container of each database service
+pub fn service_container(service: &str) -> Option<&'static str> {
+    match service {
+        "postgresql" => Some("vm-postgres-global"),
+        "redis" => Some("vm-redis-global"),
+        "mongodb" => Some("vm-mongodb-global"),
+        "mysql" => Some("vm-mysql-global"),
+        _ => None,
+    }
+}
+
 pub fn container_runtime(global_config: &GlobalConfig) -> &str {
     global_config
         .defaults
@@ -33,6 +33,12 @@ impl ManagedService for PackageRegistryService {
 
         let data_dir = vm_core::project::get_package_data_dir()?;
 
+        // The in-process server reads its global bandwidth cap from the environment
+        let max_download_kbps = global_config.services.package_registry.max_download_kbps;
+        if max_download_kbps > 0 {
+            std::env::set_var("PKG_SERVER_MAX_KBPS", max_download_kbps.to_string());
+        }
+
         // Create shutdown channel
         let (shutdown_tx, shutdown_rx) = tokio::sync::oneshot::channel();
 
@@ -98,10 +104,11 @@ impl ManagedService for PackageRegistryService {
 
     async fn check_health(&self, global_config: &GlobalConfig) -> bool {
         let port = self.get_port(global_config);
-        let endpoint = format!("http://localhost:{port}/health");
+        // Readiness (not just liveness): storage must be writable before VMs use the cache
+        let endpoint = format!("http://localhost:{port}/readyz");
 
         // Use reqwest to check health for HTTP-based services
-        match reqwest::get(&endpoint).await {
+        match vm_core::network::client().get(&endpoint).send().await {
             Ok(response) => response.status().is_success(),
             Err(_) => false,
         }
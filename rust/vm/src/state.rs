@@ -1,7 +1,10 @@
+use fs2::FileExt;
 use serde::{Deserialize, Serialize};
-use std::fs;
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Seek, Write};
 use std::path::PathBuf;
 use vm_core::error::{Result, VmError};
+use vm_core::vm_println;
 
 #[derive(Debug, Serialize, Deserialize, Default)]
 pub struct VmState {
@@ -29,9 +32,7 @@ impl VmState {
     }
 
     fn get_state_path(project_name: &str) -> Result<PathBuf> {
-        let home_dir = dirs::home_dir()
-            .ok_or_else(|| VmError::Internal("Could not get home directory".to_string()))?;
-        Ok(home_dir.join(format!(".vm/state/{project_name}.json")))
+        Ok(state_dir()?.join(format!("{project_name}.json")))
     }
 
     pub fn increment_ssh_sessions(&mut self) {
@@ -49,3 +50,95 @@ pub fn count_active_ssh_sessions(project_name: &str) -> Result<u32> {
     let state = VmState::load(project_name)?;
     Ok(state.active_ssh_sessions)
 }
+
+fn state_dir() -> Result<PathBuf> {
+    let home_dir = dirs::home_dir()
+        .ok_or_else(|| VmError::Internal("Could not get home directory".to_string()))?;
+    Ok(home_dir.join(".vm/state"))
+}
+
+/// Advisory lock serializing commands that change a project's VM.
+///
+/// Held for the lifetime of the value; the OS drops the lock if the process dies,
+/// so a crashed `vm` never leaves the project locked.
+#[derive(Debug)]
+pub struct ProjectLock {
+    _file: File,
+}
+
+impl ProjectLock {
+    /// Lock `project_name`, failing fast if another `vm` process holds it,
+    /// or waiting for it to finish when `wait` is set (`--wait-lock`).
+    pub fn acquire(project_name: &str, wait: bool) -> Result<Self> {
+        let dir = state_dir()?;
+        fs::create_dir_all(&dir)?;
+        Self::acquire_at(
+            &dir.join(format!("{project_name}.lock")),
+            project_name,
+            wait,
+        )
+    }
+
+    fn acquire_at(path: &std::path::Path, project_name: &str, wait: bool) -> Result<Self> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .read(true)
+            .write(true)
+            .open(path)?;
+
+        if file.try_lock_exclusive().is_err() {
+            let holder = Self::holder(&mut file);
+            if !wait {
+                return Err(VmError::Internal(format!(
+                    "Another vm command{holder} is already changing '{project_name}'. \
+                     Wait for it to finish or re-run with --wait-lock"
+                )));
+            }
+            vm_println!(
+                "⏳ Waiting for another vm command{} to finish with '{}'...",
+                holder,
+                project_name
+            );
+            file.lock_exclusive().map_err(|e| {
+                VmError::Internal(format!("Failed to lock {}: {e}", path.display()))
+            })?;
+        }
+
+        // Record our PID so a blocked invocation can say who it is waiting on
+        file.set_len(0)?;
+        file.rewind()?;
+        write!(file, "{}", std::process::id())?;
+        file.flush()?;
+        Ok(Self { _file: file })
+    }
+
+    /// " (pid N)" for the process recorded in the lock file, if any
+    fn holder(file: &mut File) -> String {
+        let mut pid = String::new();
+        match file.read_to_string(&mut pid) {
+            Ok(_) if !pid.trim().is_empty() => format!(" (pid {})", pid.trim()),
+            _ => String::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_project_lock_is_exclusive_until_dropped() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("demo.lock");
+
+        let lock = ProjectLock::acquire_at(&path, "demo", false).unwrap();
+        let err = ProjectLock::acquire_at(&path, "demo", false).unwrap_err();
+        let message = err.to_string();
+        assert!(message.contains("--wait-lock"));
+        assert!(message.contains(&format!("pid {}", std::process::id())));
+
+        drop(lock);
+        assert!(ProjectLock::acquire_at(&path, "demo", false).is_ok());
+    }
+}
@@ -0,0 +1,350 @@
+//! Per-project resource usage accounting
+//!
+//! The CLI has no long-running daemon, so usage is sampled whenever the service
+//! manager registers a VM, right before `vm stop` or `vm destroy` takes a VM
+//! down, and right before `vm report usage`.
+//! Each sample reads the cgroup CPU counter and memory peak of every running
+//! vm-managed container plus its writable layer size, and folds them into daily
+//! per-project buckets in `~/.vm/usage.json`.
+
+use std::collections::BTreeMap;
+
+use anyhow::{Context, Result};
+use chrono::{Duration as ChronoDuration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+/// Daily buckets older than this are dropped when the history is saved
+const RETENTION_DAYS: i64 = 90;
+
+/// Prints `cpu_usec <n>` and `mem_bytes <n>` from cgroup v2, falling back to v1
+const CGROUP_SCRIPT: &str = r#"
+if [ -r /sys/fs/cgroup/cpu.stat ]; then
+  awk '/^usage_usec/ {print "cpu_usec", $2}' /sys/fs/cgroup/cpu.stat
+elif [ -r /sys/fs/cgroup/cpuacct/cpuacct.usage ]; then
+  echo cpu_usec $(( $(cat /sys/fs/cgroup/cpuacct/cpuacct.usage) / 1000 ))
+fi
+for f in /sys/fs/cgroup/memory.peak /sys/fs/cgroup/memory/memory.max_usage_in_bytes \
+         /sys/fs/cgroup/memory.current /sys/fs/cgroup/memory/memory.usage_in_bytes; do
+  if [ -r "$f" ]; then echo mem_bytes $(cat "$f"); break; fi
+done
+"#;
+
+/// Usage accumulated by one project on one day
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct DailyUsage {
+    pub cpu_seconds: f64,
+    pub peak_memory_mb: u64,
+    pub disk_mb: u64,
+}
+
+/// Usage history for a single project
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProjectUsage {
+    /// RFC 3339 timestamp of the most recent sample
+    pub last_seen: String,
+    /// Daily buckets keyed by `YYYY-MM-DD`
+    #[serde(default)]
+    pub days: BTreeMap<String, DailyUsage>,
+}
+
+/// Last cgroup counter seen for a container, used to compute CPU deltas
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct CpuCounter {
+    started_at: String,
+    cpu_usec: u64,
+}
+
+/// Persisted usage history
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct UsageHistory {
+    #[serde(default)]
+    pub projects: BTreeMap<String, ProjectUsage>,
+    #[serde(default)]
+    counters: BTreeMap<String, CpuCounter>,
+}
+
+/// One reading from a running container
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContainerSample {
+    pub container: String,
+    pub project: String,
+    pub started_at: String,
+    pub cpu_usec: u64,
+    pub memory_bytes: u64,
+    pub disk_bytes: u64,
+}
+
+/// Totals for a project over a reporting window
+#[derive(Debug, Clone, PartialEq)]
+pub struct UsageSummary {
+    pub project: String,
+    pub cpu_seconds: f64,
+    pub peak_memory_mb: u64,
+    pub disk_mb: u64,
+    pub last_seen: String,
+}
+
+impl UsageHistory {
+    /// Load the history, starting empty if it doesn't exist yet
+    pub fn load() -> Result<Self> {
+        let path = vm_core::user_paths::usage_history_path()?;
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(&path).context("Failed to read usage history")?;
+        serde_json::from_str(&content).context("Failed to parse usage history")
+    }
+
+    /// Save the history, dropping buckets past the retention window
+    pub fn save(&mut self) -> Result<()> {
+        let cutoff = (Utc::now() - ChronoDuration::days(RETENTION_DAYS))
+            .format("%Y-%m-%d")
+            .to_string();
+        for usage in self.projects.values_mut() {
+            usage.days.retain(|day, _| *day >= cutoff);
+        }
+        self.projects.retain(|_, usage| !usage.days.is_empty());
+
+        let path = vm_core::user_paths::usage_history_path()?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).context("Failed to create state directory")?;
+        }
+        let json = serde_json::to_string_pretty(self).context("Failed to serialize usage")?;
+        std::fs::write(&path, json).context("Failed to write usage history")
+    }
+
+    /// Fold a round of samples into today's buckets
+    pub fn record(&mut self, samples: &[ContainerSample], now: chrono::DateTime<Utc>) {
+        let today = now.format("%Y-%m-%d").to_string();
+        let mut disk_by_project: BTreeMap<&str, u64> = BTreeMap::new();
+
+        for sample in samples {
+            // The cgroup counter restarts with the container, so only count the delta
+            // while it's the same run
+            let cpu_delta_usec = match self.counters.get(&sample.container) {
+                Some(previous)
+                    if previous.started_at == sample.started_at
+                        && sample.cpu_usec >= previous.cpu_usec =>
+                {
+                    sample.cpu_usec - previous.cpu_usec
+                }
+                _ => sample.cpu_usec,
+            };
+            self.counters.insert(
+                sample.container.clone(),
+                CpuCounter {
+                    started_at: sample.started_at.clone(),
+                    cpu_usec: sample.cpu_usec,
+                },
+            );
+
+            let usage = self.projects.entry(sample.project.clone()).or_default();
+            usage.last_seen = now.to_rfc3339();
+            let day = usage.days.entry(today.clone()).or_default();
+            day.cpu_seconds += cpu_delta_usec as f64 / 1_000_000.0;
+            day.peak_memory_mb = day.peak_memory_mb.max(sample.memory_bytes / (1024 * 1024));
+            *disk_by_project.entry(sample.project.as_str()).or_default() += sample.disk_bytes;
+        }
+
+        for (project, disk_bytes) in disk_by_project {
+            if let Some(day) = self
+                .projects
+                .get_mut(project)
+                .and_then(|usage| usage.days.get_mut(&today))
+            {
+                day.disk_mb = disk_bytes / (1024 * 1024);
+            }
+        }
+    }
+
+    /// Summarize usage per project since `since`, most CPU-hungry first
+    pub fn summarize(&self, since: NaiveDate) -> Vec<UsageSummary> {
+        let since = since.format("%Y-%m-%d").to_string();
+        let mut summaries: Vec<UsageSummary> = self
+            .projects
+            .iter()
+            .filter_map(|(project, usage)| {
+                let days: Vec<&DailyUsage> = usage
+                    .days
+                    .range(since.clone()..)
+                    .map(|(_, day)| day)
+                    .collect();
+                if days.is_empty() {
+                    return None;
+                }
+                Some(UsageSummary {
+                    project: project.clone(),
+                    cpu_seconds: days.iter().map(|d| d.cpu_seconds).sum(),
+                    peak_memory_mb: days.iter().map(|d| d.peak_memory_mb).max().unwrap_or(0),
+                    disk_mb: days.last().map(|d| d.disk_mb).unwrap_or(0),
+                    last_seen: usage.last_seen.clone(),
+                })
+            })
+            .collect();
+        summaries.sort_by(|a, b| b.cpu_seconds.total_cmp(&a.cpu_seconds));
+        summaries
+    }
+}
+
+/// Sample every running vm-managed container and record it in the history
+pub async fn sample_and_record(executable: &str) -> Result<()> {
+    let samples = sample_containers(executable).await?;
+    if samples.is_empty() {
+        return Ok(());
+    }
+    let mut history = UsageHistory::load()?;
+    history.record(&samples, Utc::now());
+    history.save()
+}
+
+/// Best-effort sampling used by the service manager; failures are only logged
+pub async fn sample_quietly(executable: &str) {
+    if let Err(e) = sample_and_record(executable).await {
+        debug!("Usage sampling failed: {}", e);
+    }
+}
+
+async fn sample_containers(executable: &str) -> Result<Vec<ContainerSample>> {
+    let output = tokio::process::Command::new(executable)
+        .args([
+            "ps",
+            "--size",
+            "--filter",
+            "label=com.vm.managed=true",
+            "--format",
+            "{{.Names}}\t{{.Label \"com.vm.project\"}}\t{{.Size}}",
+        ])
+        .output()
+        .await
+        .context("Failed to list running containers")?;
+    if !output.status.success() {
+        return Ok(Vec::new());
+    }
+
+    let mut samples = Vec::new();
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        let mut fields = line.split('\t');
+        let (Some(container), Some(project), Some(size)) =
+            (fields.next(), fields.next(), fields.next())
+        else {
+            continue;
+        };
+        let project = if project.is_empty() {
+            container.trim_end_matches("-dev")
+        } else {
+            project
+        };
+
+        let started_at = tokio::process::Command::new(executable)
+            .args(["inspect", "-f", "{{.State.StartedAt}}", container])
+            .output()
+            .await
+            .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+            .unwrap_or_default();
+        let cgroup = tokio::process::Command::new(executable)
+            .args(["exec", container, "sh", "-c", CGROUP_SCRIPT])
+            .output()
+            .await
+            .map(|o| String::from_utf8_lossy(&o.stdout).to_string())
+            .unwrap_or_default();
+        let (cpu_usec, memory_bytes) = parse_cgroup_output(&cgroup);
+
+        samples.push(ContainerSample {
+            container: container.to_string(),
+            project: project.to_string(),
+            started_at,
+            cpu_usec,
+            memory_bytes,
+            disk_bytes: parse_docker_size(size).unwrap_or(0),
+        });
+    }
+    Ok(samples)
+}
+
+/// Parse the `cpu_usec` / `mem_bytes` lines printed by [`CGROUP_SCRIPT`]
+fn parse_cgroup_output(output: &str) -> (u64, u64) {
+    let mut cpu_usec = 0;
+    let mut memory_bytes = 0;
+    for line in output.lines() {
+        match line.split_once(' ') {
+            Some(("cpu_usec", value)) => cpu_usec = value.trim().parse().unwrap_or(0),
+            Some(("mem_bytes", value)) => memory_bytes = value.trim().parse().unwrap_or(0),
+            _ => {}
+        }
+    }
+    (cpu_usec, memory_bytes)
+}
+
+/// Parse the writable layer size from `docker ps --size`, e.g. `12.3MB (virtual 1.2GB)`
+fn parse_docker_size(size: &str) -> Option<u64> {
+    let value = size.split_whitespace().next()?;
+    let split = value.find(|c: char| c.is_ascii_alphabetic())?;
+    let (number, unit) = value.split_at(split);
+    let number: f64 = number.parse().ok()?;
+    let multiplier = match unit.to_ascii_lowercase().as_str() {
+        "b" => 1.0,
+        "kb" | "kib" => 1e3,
+        "mb" | "mib" => 1e6,
+        "gb" | "gib" => 1e9,
+        "tb" | "tib" => 1e12,
+        _ => return None,
+    };
+    Some((number * multiplier) as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn sample(started_at: &str, cpu_usec: u64) -> ContainerSample {
+        ContainerSample {
+            container: "shop-dev".to_string(),
+            project: "shop".to_string(),
+            started_at: started_at.to_string(),
+            cpu_usec,
+            memory_bytes: 512 * 1024 * 1024,
+            disk_bytes: 40 * 1024 * 1024,
+        }
+    }
+
+    #[test]
+    fn test_record_accumulates_cpu_across_restarts() {
+        let now = Utc.with_ymd_and_hms(2026, 3, 10, 12, 0, 0).unwrap();
+        let mut history = UsageHistory::default();
+
+        history.record(&[sample("run-1", 2_000_000)], now);
+        history.record(&[sample("run-1", 5_000_000)], now);
+        // Container restarted: the counter starts over
+        history.record(&[sample("run-2", 1_000_000)], now);
+
+        let day = &history.projects["shop"].days["2026-03-10"];
+        assert_eq!(day.cpu_seconds, 6.0);
+        assert_eq!(day.peak_memory_mb, 512);
+        assert_eq!(day.disk_mb, 40);
+
+        let since = NaiveDate::from_ymd_opt(2026, 3, 4).unwrap();
+        let summary = history.summarize(since);
+        assert_eq!(summary.len(), 1);
+        assert_eq!(summary[0].cpu_seconds, 6.0);
+        assert!(history
+            .summarize(NaiveDate::from_ymd_opt(2026, 3, 11).unwrap())
+            .is_empty());
+    }
+
+    #[test]
+    fn test_parse_probe_outputs() {
+        assert_eq!(
+            parse_cgroup_output("cpu_usec 1234\nmem_bytes 2048\n"),
+            (1234, 2048)
+        );
+        assert_eq!(parse_cgroup_output(""), (0, 0));
+        assert_eq!(
+            parse_docker_size("12.5MB (virtual 1.2GB)"),
+            Some(12_500_000)
+        );
+        assert_eq!(parse_docker_size("0B (virtual 80MB)"), Some(0));
+        assert_eq!(parse_docker_size("n/a"), None);
+    }
+}